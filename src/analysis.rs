@@ -0,0 +1,28 @@
+//! Analyses computed over `llvm-ir`'s data structures.
+//!
+//! These analyses work purely on the Rust representation of the IR; they do
+//! not call into LLVM.
+
+mod alias_analysis;
+mod block_addresses;
+mod control_flow_graph;
+mod coroutine_info;
+mod dominator_tree;
+mod edge_assignments;
+mod loop_info;
+mod memory_dependences;
+mod reachability;
+mod slice;
+mod ssa_graph;
+
+pub use alias_analysis::{AliasResult, BasicAliasAnalysis};
+pub use block_addresses::{BlockAddressInconsistency, BlockAddresses};
+pub use control_flow_graph::ControlFlowGraph;
+pub use coroutine_info::{CoroCall, CoroutineInfo, SuspendPoint};
+pub use dominator_tree::DominatorTree;
+pub use edge_assignments::EdgeAssignments;
+pub use loop_info::{Loop, LoopInfo};
+pub use memory_dependences::{LoadDependences, MemoryDependences};
+pub use reachability::Reachability;
+pub use slice::backward_slice;
+pub use ssa_graph::{Definition, SsaGraph, Use};
@@ -0,0 +1,288 @@
+use super::SsaGraph;
+use crate::constant::Constant;
+use crate::function::Function;
+use crate::instruction::Instruction;
+use crate::module::Module;
+use crate::name::Name;
+use crate::operand::Operand;
+use crate::types::{NamedStructDef, Type, TypeRef, Types};
+use crate::ConstantRef;
+use std::collections::HashSet;
+use std::convert::TryFrom;
+
+/// The result of an alias query between two pointers
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum AliasResult {
+    /// The pointers never point into overlapping memory
+    NoAlias,
+    /// The pointers may or may not point into overlapping memory
+    MayAlias,
+    /// The pointers always point to the same address
+    MustAlias,
+}
+
+/// A simple, conservative alias analysis for the pointers used in a
+/// `Function`, in the spirit of LLVM's `BasicAA`.
+///
+/// Each pointer is traced back (through `bitcast`s and `getelementptr`s, both
+/// instructions and constant expressions) to the object it points into. Then:
+///
+///   - pointers into distinct allocas, distinct globals, or null don't alias;
+///   - pointers at constant `getelementptr` paths into the same object don't
+///     alias if the paths select disjoint parts of the object, and must alias
+///     if the paths are equal;
+///   - anything else may alias.
+///
+/// Like LLVM's typed-pointer `BasicAA`, this assumes that each pointer is used
+/// to access (at most) a value of its pointee type.
+#[derive(Clone)]
+pub struct BasicAliasAnalysis<'m> {
+    types: &'m Types,
+    ssa: SsaGraph<'m>,
+    /// Names of the `GlobalAlias`es in the module, which may alias other
+    /// globals
+    global_aliases: HashSet<&'m Name>,
+}
+
+/// What a pointer points into
+#[derive(PartialEq, Eq, Clone, Debug)]
+enum Base<'a> {
+    /// The local value with this name (which may be an `Alloca`)
+    Local(&'a Name),
+    /// The global with this name
+    Global(&'a Name),
+    Null,
+    /// Something we can't reason about
+    Unknown,
+}
+
+/// A pointer, expressed as its `Base` plus a constant `getelementptr` path
+/// from the base, if known
+#[derive(Clone, Debug)]
+struct Decomposed<'a> {
+    base: Base<'a>,
+    /// The source element type and the (sign-extended) indices. The base
+    /// pointer itself is `[0]`. `None` if the offset from the base is unknown.
+    path: Option<(TypeRef, Vec<i64>)>,
+}
+
+impl<'m> BasicAliasAnalysis<'m> {
+    /// Prepare to answer alias queries about the pointers used in the given
+    /// `Function`, which must be in the given `Module`.
+    pub fn new(module: &'m Module, function: &'m Function) -> Self {
+        Self {
+            types: &module.types,
+            ssa: SsaGraph::new(function),
+            global_aliases: module.global_aliases.iter().map(|ga| &ga.name).collect(),
+        }
+    }
+
+    /// Determine whether the two given pointers may point into overlapping
+    /// memory.
+    pub fn alias(&self, a: &Operand, b: &Operand) -> AliasResult {
+        if a == b {
+            return AliasResult::MustAlias;
+        }
+        let a = self.decompose_operand(a);
+        let b = self.decompose_operand(b);
+        if a.base == b.base && a.base != Base::Unknown {
+            match (a.path, b.path) {
+                (Some(a), Some(b)) => self.compare_paths(a, b),
+                _ => AliasResult::MayAlias,
+            }
+        } else if self.is_identified(&a.base) && self.is_identified(&b.base) {
+            AliasResult::NoAlias
+        } else {
+            AliasResult::MayAlias
+        }
+    }
+
+    /// Is the given `Base` a distinct object from any other identified
+    /// `Base`?
+    fn is_identified(&self, base: &Base) -> bool {
+        match base {
+            Base::Local(name) => matches!(
+                self.ssa.defining_instruction(name),
+                Some(Instruction::Alloca(_))
+            ),
+            Base::Global(name) => !self.global_aliases.contains(name),
+            Base::Null => true,
+            Base::Unknown => false,
+        }
+    }
+
+    fn decompose_operand<'a>(&'a self, op: &'a Operand) -> Decomposed<'a> {
+        match op {
+            Operand::LocalOperand { name, ty } => match self.ssa.defining_instruction(name) {
+                Some(Instruction::BitCast(bc)) => {
+                    Self::cast(self.decompose_operand(&bc.operand), &bc.to_type)
+                },
+                Some(Instruction::GetElementPtr(gep)) => self.gep(
+                    self.decompose_operand(&gep.address),
+                    gep.indices.iter().map(|index| index.as_constant()),
+                ),
+                _ => Decomposed {
+                    base: Base::Local(name),
+                    path: Self::base_path(ty),
+                },
+            },
+            Operand::ConstantOperand(c) => self.decompose_constant(c),
+            Operand::MetadataOperand(_) => Decomposed {
+                base: Base::Unknown,
+                path: None,
+            },
+        }
+    }
+
+    fn decompose_constant<'a>(&'a self, c: &'a ConstantRef) -> Decomposed<'a> {
+        match c.as_ref() {
+            Constant::GlobalReference { name, .. } => Decomposed {
+                base: Base::Global(name),
+                path: Self::base_path(&self.types.type_of(c)),
+            },
+            Constant::Null(ty) => Decomposed {
+                base: Base::Null,
+                path: Self::base_path(ty),
+            },
+            Constant::BitCast(bc) => Self::cast(self.decompose_constant(&bc.operand), &bc.to_type),
+            Constant::GetElementPtr(gep) => self.gep(
+                self.decompose_constant(&gep.address),
+                gep.indices.iter().map(|index| Some(index.as_ref())),
+            ),
+            _ => Decomposed {
+                base: Base::Unknown,
+                path: None,
+            },
+        }
+    }
+
+    /// The path of a pointer of type `ptr_ty` which points exactly at its base
+    fn base_path(ptr_ty: &TypeRef) -> Option<(TypeRef, Vec<i64>)> {
+        match ptr_ty.as_ref() {
+            Type::PointerType { pointee_type, .. } => Some((pointee_type.clone(), vec![0])),
+            _ => None,
+        }
+    }
+
+    /// Apply a pointer cast to `to_type`. We only keep track of the path if
+    /// the pointer is still exactly at the base.
+    fn cast<'a>(decomposed: Decomposed<'a>, to_type: &TypeRef) -> Decomposed<'a> {
+        let at_base = matches!(&decomposed.path, Some((_, path)) if path.iter().all(|&i| i == 0));
+        Decomposed {
+            base: decomposed.base,
+            path: if at_base {
+                Self::base_path(to_type)
+            } else {
+                None
+            },
+        }
+    }
+
+    /// Apply a `getelementptr` with the given indices to a pointer, appending
+    /// the indices to its path if they are all constant.
+    fn gep<'a, 'c>(
+        &self,
+        decomposed: Decomposed<'a>,
+        indices: impl Iterator<Item = Option<&'c Constant>>,
+    ) -> Decomposed<'a> {
+        let path = decomposed.path.and_then(|(ty, mut path)| {
+            let mut indices = indices.map(|index| match index {
+                Some(Constant::Int { bits, value }) => Some(sign_extend(*value, *bits)),
+                _ => None,
+            });
+            // the first index offsets the last index of the existing path
+            let first = indices.next()??;
+            if let Some(last) = path.last_mut() {
+                *last = last.wrapping_add(first);
+            }
+            for index in indices {
+                path.push(index?);
+            }
+            Some((ty, path))
+        });
+        Decomposed {
+            base: decomposed.base,
+            path,
+        }
+    }
+
+    /// Compare two paths from the same base
+    fn compare_paths(&self, a: (TypeRef, Vec<i64>), b: (TypeRef, Vec<i64>)) -> AliasResult {
+        let (a_ty, a) = a;
+        let (b_ty, b) = b;
+        if a.iter().chain(&b).all(|&i| i == 0) {
+            // both point exactly at the base
+            return AliasResult::MustAlias;
+        }
+        if a_ty != b_ty {
+            return AliasResult::MayAlias;
+        }
+        if a == b {
+            return AliasResult::MustAlias;
+        }
+        if !self.in_bounds(&a_ty, &a) || !self.in_bounds(&b_ty, &b) {
+            return AliasResult::MayAlias;
+        }
+        if a.iter().zip(&b).any(|(i, j)| i != j) {
+            // the paths diverge, selecting disjoint elements or fields
+            AliasResult::NoAlias
+        } else {
+            // one path is a prefix of the other, so one pointer points into
+            // the object pointed to by the other
+            AliasResult::MayAlias
+        }
+    }
+
+    /// Do all of the indices after the first stay within the bounds of the
+    /// arrays, vectors, or structs they index into?
+    fn in_bounds(&self, ty: &TypeRef, path: &[i64]) -> bool {
+        let mut ty = ty.clone();
+        for &index in &path[1 ..] {
+            let (element_ty, len) = match self.resolve_struct(&ty).as_ref() {
+                Type::ArrayType {
+                    element_type,
+                    num_elements,
+                }
+                | Type::VectorType {
+                    element_type,
+                    num_elements,
+                    ..
+                } => (element_type.clone(), *num_elements),
+                Type::StructType { element_types, .. } => match usize::try_from(index) {
+                    Ok(i) if i < element_types.len() => {
+                        (element_types[i].clone(), element_types.len())
+                    },
+                    _ => return false,
+                },
+                _ => return false,
+            };
+            if index < 0 || index as usize >= len {
+                return false;
+            }
+            ty = element_ty;
+        }
+        true
+    }
+
+    /// Get the definition of a named struct type, or the type itself if it
+    /// isn't one (or if it is opaque).
+    fn resolve_struct(&self, ty: &TypeRef) -> TypeRef {
+        match ty.as_ref() {
+            Type::NamedStructType { name } => match self.types.named_struct_def(name) {
+                Some(NamedStructDef::Defined(def)) => def.clone(),
+                _ => ty.clone(),
+            },
+            _ => ty.clone(),
+        }
+    }
+}
+
+/// Interpret the low `bits` bits of `value` as a signed integer
+fn sign_extend(value: u64, bits: u32) -> i64 {
+    if bits == 0 || bits >= 64 {
+        value as i64
+    } else {
+        let shift = 64 - bits;
+        ((value << shift) as i64) >> shift
+    }
+}
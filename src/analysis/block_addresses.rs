@@ -0,0 +1,188 @@
+use crate::constant::{Constant, ConstantRef};
+use crate::module::Module;
+use crate::name::Name;
+use crate::operand::Operand;
+use crate::terminator::Terminator;
+use std::collections::{HashMap, HashSet};
+
+/// A disagreement between the `blockaddress` constants of a `Module` and the
+/// destinations of its `IndirectBr`s, as found by
+/// [`BlockAddresses`](struct.BlockAddresses.html).
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum BlockAddressInconsistency<'m> {
+    /// A `blockaddress` of a block which the `Module` doesn't define, or of a
+    /// block of a function which it doesn't define (e.g., because the
+    /// function's body was skipped while parsing)
+    DanglingBlockAddress { function: &'m Name, block: &'m Name },
+    /// A destination of the `IndirectBr` ending `block` whose address is
+    /// never taken in the `Module`, so the `IndirectBr` can't actually jump
+    /// there
+    UntakenDestination {
+        function: &'m str,
+        block: &'m Name,
+        dest: &'m Name,
+    },
+    /// A block of `function` whose address is taken, but which the
+    /// `IndirectBr` ending `block` doesn't list as a destination. This is fine
+    /// if that address can't reach the `IndirectBr`; otherwise, jumping to it
+    /// is undefined behavior, and a CFG built from the `IndirectBr`'s
+    /// destinations misses the edge.
+    MissingDestination {
+        function: &'m str,
+        block: &'m Name,
+        dest: &'m Name,
+    },
+}
+
+/// The blocks of each function in a `Module` whose addresses are taken by
+/// `blockaddress` constants, anywhere in the `Module` (in instructions, global
+/// variable initializers, aliases, or personality functions), and the
+/// [`inconsistencies`](#method.inconsistencies) between those and the
+/// destinations of the `Module`'s `IndirectBr`s.
+///
+/// The address-taken blocks of a function are the only blocks an
+/// `IndirectBr` in it can jump to, so they are what a sound CFG has to
+/// assume for an `IndirectBr` whose destinations can't be trusted.
+#[derive(Clone, Debug)]
+pub struct BlockAddresses<'m> {
+    /// For each function with address-taken blocks, those blocks, in the
+    /// order they appear in the function
+    taken: HashMap<&'m str, Vec<&'m Name>>,
+    inconsistencies: Vec<BlockAddressInconsistency<'m>>,
+}
+
+impl<'m> BlockAddresses<'m> {
+    /// Find the address-taken blocks of all the functions in the `Module`,
+    /// and cross-check them against its `IndirectBr`s.
+    pub fn new(module: &'m Module) -> Self {
+        let mut addresses: HashSet<(&'m Name, &'m Name)> = HashSet::new();
+        let mut visit = |c: &'m ConstantRef| collect_block_addresses(c, &mut addresses);
+        for var in &module.global_vars {
+            var.initializer.iter().for_each(&mut visit);
+        }
+        for alias in &module.global_aliases {
+            visit(&alias.aliasee);
+        }
+        for func in &module.functions {
+            func.personality_function.iter().for_each(&mut visit);
+            for bb in &func.basic_blocks {
+                let operands = bb
+                    .instrs
+                    .iter()
+                    .flat_map(|inst| inst.operands())
+                    .chain(bb.term.operands());
+                for op in operands {
+                    if let Operand::ConstantOperand(c) = op {
+                        visit(c);
+                    }
+                }
+            }
+        }
+
+        let mut taken: HashMap<&'m str, Vec<&'m Name>> = HashMap::new();
+        let mut inconsistencies = vec![];
+        for func in &module.functions {
+            let blocks: Vec<&'m Name> = func
+                .basic_blocks
+                .iter()
+                .map(|bb| &bb.name)
+                .filter(|&block| {
+                    addresses
+                        .iter()
+                        .any(|(f, b)| *b == block && f.as_bytes() == Some(func.name.as_bytes()))
+                })
+                .collect();
+            for bb in &func.basic_blocks {
+                let ibr = match &bb.term {
+                    Terminator::IndirectBr(ibr) => ibr,
+                    _ => continue,
+                };
+                for dest in &ibr.possible_dests {
+                    if !blocks.contains(&dest) {
+                        inconsistencies.push(BlockAddressInconsistency::UntakenDestination {
+                            function: &func.name,
+                            block: &bb.name,
+                            dest,
+                        });
+                    }
+                }
+                for &dest in &blocks {
+                    if !ibr.possible_dests.contains(dest) {
+                        inconsistencies.push(BlockAddressInconsistency::MissingDestination {
+                            function: &func.name,
+                            block: &bb.name,
+                            dest,
+                        });
+                    }
+                }
+            }
+            if !blocks.is_empty() {
+                taken.insert(&func.name, blocks);
+            }
+        }
+
+        let mut dangling: Vec<(&'m Name, &'m Name)> = addresses
+            .into_iter()
+            .filter(|&(function, block)| {
+                !function
+                    .as_bytes()
+                    .and_then(|bytes| taken.iter().find(|(f, _)| f.as_bytes() == bytes))
+                    .is_some_and(|(_, blocks)| blocks.contains(&block))
+            })
+            .collect();
+        dangling.sort();
+        for (function, block) in dangling {
+            inconsistencies
+                .push(BlockAddressInconsistency::DanglingBlockAddress { function, block });
+        }
+
+        Self {
+            taken,
+            inconsistencies,
+        }
+    }
+
+    /// Get the blocks of the function with the given name whose addresses are
+    /// taken, in the order they appear in the function. This is empty if
+    /// there are none, or if there is no such function.
+    pub fn address_taken_blocks(&self, func_name: &str) -> &[&'m Name] {
+        match self.taken.get(func_name) {
+            Some(blocks) => blocks,
+            None => &[],
+        }
+    }
+
+    /// Is the address of the given block of the function with the given name
+    /// taken?
+    pub fn is_address_taken(&self, func_name: &str, block: &Name) -> bool {
+        self.address_taken_blocks(func_name).contains(&block)
+    }
+
+    /// Iterate over the names of the functions which have address-taken
+    /// blocks, in no particular order.
+    pub fn functions(&self) -> impl Iterator<Item = &'m str> + '_ {
+        self.taken.keys().copied()
+    }
+
+    /// Get the inconsistencies between the `blockaddress` constants and the
+    /// `IndirectBr`s of the `Module`: first those of each `IndirectBr`, in
+    /// `Module` order, then the dangling `blockaddress`es, sorted.
+    pub fn inconsistencies(&self) -> &[BlockAddressInconsistency<'m>] {
+        &self.inconsistencies
+    }
+}
+
+/// Add the function and block of each `blockaddress` in `c` (including in
+/// its sub-constants) to `addresses`
+fn collect_block_addresses<'m>(c: &'m Constant, addresses: &mut HashSet<(&'m Name, &'m Name)>) {
+    match c {
+        Constant::BlockAddress { function, block } => {
+            addresses.insert((function, block));
+        },
+        c => {
+            for sub in c.sub_constants() {
+                collect_block_addresses(sub, addresses);
+            }
+        },
+    }
+}
@@ -0,0 +1,163 @@
+use crate::function::Function;
+use crate::name::Name;
+use std::collections::HashMap;
+
+/// The control-flow graph of a `Function`: which `BasicBlock`s can branch to
+/// which other `BasicBlock`s.
+///
+/// Blocks are identified by their `Name`s.
+#[derive(Clone, Debug)]
+pub struct ControlFlowGraph<'f> {
+    /// Names of all blocks, in the order they appear in the `Function`
+    blocks: Vec<&'f Name>,
+    /// Map from block name to its index in `blocks`
+    indices: HashMap<&'f Name, usize>,
+    /// `succs[i]` are the indices of the successors of block `i`
+    succs: Vec<Vec<usize>>,
+    /// `preds[i]` are the indices of the predecessors of block `i`
+    preds: Vec<Vec<usize>>,
+    /// Indices of the blocks reachable from the entry block, in reverse
+    /// postorder
+    rpo: Vec<usize>,
+}
+
+impl<'f> ControlFlowGraph<'f> {
+    /// Compute the control-flow graph of the given `Function`.
+    ///
+    /// Panics if the `Function` has no `BasicBlock`s, or if a terminator
+    /// refers to a block which isn't in the `Function`.
+    pub fn new(function: &'f Function) -> Self {
+        assert!(
+            !function.basic_blocks.is_empty(),
+            "ControlFlowGraph::new: function {:?} has no basic blocks",
+            function.name
+        );
+        let blocks: Vec<&'f Name> = function.basic_blocks.iter().map(|bb| &bb.name).collect();
+        let indices: HashMap<&'f Name, usize> = blocks
+            .iter()
+            .enumerate()
+            .map(|(i, &name)| (name, i))
+            .collect();
+        let mut succs = vec![vec![]; blocks.len()];
+        let mut preds = vec![vec![]; blocks.len()];
+        for (i, bb) in function.basic_blocks.iter().enumerate() {
+            for dest in bb.term.successors() {
+                let j = *indices.get(dest).unwrap_or_else(|| {
+                    panic!(
+                        "ControlFlowGraph::new: block {} branches to {}, which isn't in function {:?}",
+                        bb.name, dest, function.name
+                    )
+                });
+                // a terminator may name the same successor more than once
+                // (e.g., several switch cases going to the same block), but
+                // that's still just one edge in the CFG
+                if !succs[i].contains(&j) {
+                    succs[i].push(j);
+                    preds[j].push(i);
+                }
+            }
+        }
+        let rpo = reverse_postorder(&succs);
+        Self {
+            blocks,
+            indices,
+            succs,
+            preds,
+            rpo,
+        }
+    }
+
+    /// Get the name of the entry block of the function.
+    pub fn entry(&self) -> &'f Name {
+        self.blocks[0]
+    }
+
+    /// Get the names of all the blocks in the function, in the order they
+    /// appear in the function.
+    pub fn blocks(&self) -> impl Iterator<Item = &'f Name> + '_ {
+        self.blocks.iter().copied()
+    }
+
+    /// Get the names of the blocks which the given block may branch to.
+    ///
+    /// Panics if `block` isn't in the function.
+    pub fn succs<'s>(&'s self, block: &Name) -> impl Iterator<Item = &'f Name> + 's {
+        self.succ_indices(self.index_of(block))
+            .iter()
+            .map(move |&i| self.blocks[i])
+    }
+
+    /// Get the names of the blocks which may branch to the given block.
+    ///
+    /// Panics if `block` isn't in the function.
+    pub fn preds<'s>(&'s self, block: &Name) -> impl Iterator<Item = &'f Name> + 's {
+        self.pred_indices(self.index_of(block))
+            .iter()
+            .map(move |&i| self.blocks[i])
+    }
+
+    /// Get the names of the blocks which are reachable from the entry block,
+    /// in reverse postorder.
+    pub fn reverse_postorder(&self) -> impl Iterator<Item = &'f Name> + '_ {
+        self.rpo.iter().map(move |&i| self.blocks[i])
+    }
+
+    /// Is the given block reachable from the entry block?
+    ///
+    /// Panics if `block` isn't in the function.
+    pub fn is_reachable(&self, block: &Name) -> bool {
+        self.rpo.contains(&self.index_of(block))
+    }
+
+    pub(crate) fn num_blocks(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub(crate) fn name_of(&self, index: usize) -> &'f Name {
+        self.blocks[index]
+    }
+
+    pub(crate) fn index_of(&self, block: &Name) -> usize {
+        *self
+            .indices
+            .get(block)
+            .unwrap_or_else(|| panic!("Block {} not found in the control-flow graph", block))
+    }
+
+    pub(crate) fn succ_indices(&self, index: usize) -> &[usize] {
+        &self.succs[index]
+    }
+
+    pub(crate) fn pred_indices(&self, index: usize) -> &[usize] {
+        &self.preds[index]
+    }
+
+    pub(crate) fn rpo_indices(&self) -> &[usize] {
+        &self.rpo
+    }
+}
+
+/// Compute the reverse postorder of the blocks reachable from block 0, given
+/// the successor lists.
+fn reverse_postorder(succs: &[Vec<usize>]) -> Vec<usize> {
+    let mut visited = vec![false; succs.len()];
+    let mut postorder = Vec::with_capacity(succs.len());
+    // explicit stack of (block, index of the next successor to visit), to
+    // avoid recursion depth problems on very large functions
+    let mut stack = vec![(0, 0)];
+    visited[0] = true;
+    while let Some((block, next_succ)) = stack.pop() {
+        match succs[block].get(next_succ) {
+            Some(&succ) => {
+                stack.push((block, next_succ + 1));
+                if !visited[succ] {
+                    visited[succ] = true;
+                    stack.push((succ, 0));
+                }
+            },
+            None => postorder.push(block),
+        }
+    }
+    postorder.reverse();
+    postorder
+}
@@ -0,0 +1,218 @@
+use super::{Definition, SsaGraph};
+use crate::constant::Constant;
+use crate::function::Function;
+use crate::instruction::{Alloca, Call, Instruction};
+use crate::intrinsic::Intrinsic;
+use crate::name::Name;
+use crate::operand::Operand;
+use std::collections::HashSet;
+
+/// A call to a coroutine intrinsic within a `Function`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub struct CoroCall<'f> {
+    /// The block containing the call
+    pub block: &'f Name,
+    /// The index of the call in the block's `instrs`
+    pub index: usize,
+    /// Which coroutine intrinsic is called
+    pub intrinsic: Intrinsic,
+    /// The call itself
+    pub call: &'f Call,
+}
+
+/// A suspend point of a coroutine: a call to `llvm.coro.suspend` (or one of
+/// its variants for other lowering strategies).
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub struct SuspendPoint<'f> {
+    /// The call to the suspend intrinsic
+    pub call: CoroCall<'f>,
+    /// The `llvm.coro.save` call marking where the coroutine is considered
+    /// suspended, if the suspend point has one
+    pub save: Option<CoroCall<'f>>,
+    /// Is this the final suspend point, after which the coroutine can only be
+    /// destroyed, not resumed? Only `llvm.coro.suspend` has final suspend
+    /// points.
+    pub is_final: bool,
+}
+
+/// The coroutine structure of a `Function` which has not been split yet: the
+/// calls to the `llvm.coro.*` intrinsics identifying it, its suspend points,
+/// and where its coroutine frame comes from.
+///
+/// See [LLVM 14 docs on Coroutines](https://releases.llvm.org/14.0.0/docs/Coroutines.html)
+#[derive(Clone, Debug)]
+pub struct CoroutineInfo<'f> {
+    id: CoroCall<'f>,
+    begin: Option<CoroCall<'f>>,
+    suspend_points: Vec<SuspendPoint<'f>>,
+    ends: Vec<CoroCall<'f>>,
+    frees: Vec<CoroCall<'f>>,
+    frame_alloca: Option<&'f Alloca>,
+    is_presplit: bool,
+}
+
+impl<'f> CoroutineInfo<'f> {
+    /// Compute the `CoroutineInfo` of the given `Function`, or `None` if it
+    /// isn't a coroutine, i.e., if it doesn't call `llvm.coro.id` (or one of
+    /// its variants). Coroutines which have already been split into their
+    /// ramp, resume, and destroy functions are no longer coroutines in this
+    /// sense.
+    pub fn new(function: &'f Function) -> Option<Self> {
+        let calls: Vec<CoroCall<'f>> = function
+            .basic_blocks
+            .iter()
+            .flat_map(|bb| {
+                bb.instrs
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(index, inst)| {
+                        let call = inst.as_call()?;
+                        let intrinsic = call.intrinsic().filter(|i| i.is_coroutine())?;
+                        Some(CoroCall {
+                            block: &bb.name,
+                            index,
+                            intrinsic,
+                            call,
+                        })
+                    })
+            })
+            .collect();
+        let id = *calls.iter().find(|c| c.intrinsic.is_coro_id())?;
+        let find = |intrinsic: Intrinsic| {
+            calls
+                .iter()
+                .copied()
+                .filter(move |c| c.intrinsic == intrinsic)
+        };
+        let begin = find(Intrinsic::CoroBegin).next();
+
+        // `llvm.coro.suspend` takes the token of its `llvm.coro.save` (or
+        // `none`), and a flag saying whether it is the final suspend point
+        let saves: Vec<CoroCall<'f>> = find(Intrinsic::CoroSave).collect();
+        let suspend_points = calls
+            .iter()
+            .filter(|c| c.intrinsic.is_coro_suspend())
+            .map(|&call| {
+                let args = &call.call.arguments;
+                let (save, is_final) = match call.intrinsic {
+                    Intrinsic::CoroSuspend => {
+                        let save = match args.first().map(|(arg, _)| arg.as_ref()) {
+                            Some(Operand::LocalOperand { name, .. }) => saves
+                                .iter()
+                                .copied()
+                                .find(|save| save.call.dest.as_ref() == Some(name)),
+                            _ => None,
+                        };
+                        let is_final = match args.get(1).and_then(|(arg, _)| arg.as_constant()) {
+                            Some(Constant::Int { value, .. }) => *value != 0,
+                            _ => false,
+                        };
+                        (save, is_final)
+                    },
+                    _ => (None, false),
+                };
+                SuspendPoint {
+                    call,
+                    save,
+                    is_final,
+                }
+            })
+            .collect();
+
+        let frame_alloca = begin.and_then(|begin| {
+            // `llvm.coro.begin`'s second argument is the memory for the frame
+            let mem = &begin.call.arguments.get(1)?.0;
+            find_alloca(&SsaGraph::new(function), mem, &mut HashSet::new())
+        });
+
+        Some(Self {
+            id,
+            begin,
+            suspend_points,
+            ends: calls
+                .iter()
+                .copied()
+                .filter(|c| matches!(c.intrinsic, Intrinsic::CoroEnd | Intrinsic::CoroEndAsync))
+                .collect(),
+            frees: find(Intrinsic::CoroFree).collect(),
+            frame_alloca,
+            is_presplit: function.is_presplit_coroutine(),
+        })
+    }
+
+    /// Get the call to `llvm.coro.id` (or one of its variants) identifying
+    /// the coroutine.
+    pub fn id(&self) -> CoroCall<'f> {
+        self.id
+    }
+
+    /// Get the call to `llvm.coro.begin`, whose result is the coroutine
+    /// handle, if there is one.
+    pub fn begin(&self) -> Option<CoroCall<'f>> {
+        self.begin
+    }
+
+    /// Get the coroutine handle: the result of `llvm.coro.begin`, if there is
+    /// one.
+    pub fn handle(&self) -> Option<&'f Name> {
+        self.begin.and_then(|begin| begin.call.dest.as_ref())
+    }
+
+    /// Get the suspend points of the coroutine, in the order they appear in
+    /// the function.
+    pub fn suspend_points(&self) -> &[SuspendPoint<'f>] {
+        &self.suspend_points
+    }
+
+    /// Get the calls to `llvm.coro.end` (and `llvm.coro.end.async`), in the
+    /// order they appear in the function.
+    pub fn ends(&self) -> &[CoroCall<'f>] {
+        &self.ends
+    }
+
+    /// Get the calls to `llvm.coro.free`, in the order they appear in the
+    /// function.
+    pub fn frees(&self) -> &[CoroCall<'f>] {
+        &self.frees
+    }
+
+    /// Get the `Alloca` providing the memory for the coroutine frame, if the
+    /// memory passed to `llvm.coro.begin` comes from one (possibly through
+    /// casts, or as one of the incoming values of a `Phi`). Frames which are
+    /// only allocated on the heap have no such `Alloca`.
+    pub fn frame_alloca(&self) -> Option<&'f Alloca> {
+        self.frame_alloca
+    }
+
+    /// Is the function marked as a coroutine which hasn't been split yet?
+    /// See [`Function::is_presplit_coroutine()`](../function/struct.Function.html#method.is_presplit_coroutine).
+    pub fn is_presplit(&self) -> bool {
+        self.is_presplit
+    }
+}
+
+/// Find the `Alloca` which the given pointer comes from, looking through
+/// casts and `Phi`s
+fn find_alloca<'f>(
+    ssa: &SsaGraph<'f>,
+    op: &Operand,
+    visited: &mut HashSet<&'f Name>,
+) -> Option<&'f Alloca> {
+    let inst = match ssa.resolve(op)? {
+        Definition::Instruction { inst, .. } => *inst,
+        _ => return None,
+    };
+    if !visited.insert(inst.try_get_result()?) {
+        return None;
+    }
+    match inst {
+        Instruction::Alloca(alloca) => Some(alloca),
+        Instruction::BitCast(bitcast) => find_alloca(ssa, &bitcast.operand, visited),
+        Instruction::AddrSpaceCast(cast) => find_alloca(ssa, &cast.operand, visited),
+        Instruction::Phi(phi) => phi
+            .incoming_values
+            .iter()
+            .find_map(|(value, _)| find_alloca(ssa, value, visited)),
+        _ => None,
+    }
+}
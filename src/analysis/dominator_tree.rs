@@ -0,0 +1,176 @@
+use super::ControlFlowGraph;
+use crate::name::Name;
+use std::collections::HashMap;
+
+/// The dominator tree of a `Function`.
+///
+/// Block `A` dominates block `B` if every path from the entry block to `B`
+/// passes through `A`. Every block dominates itself.
+///
+/// Blocks which are unreachable from the entry block are not part of the
+/// dominator tree: they have no immediate dominator, dominate nothing, and
+/// are dominated by nothing.
+#[derive(Clone, Debug)]
+pub struct DominatorTree<'f> {
+    /// `idoms[i]` is the index of the immediate dominator of block `i`.
+    /// The entry block is its own immediate dominator here, and unreachable
+    /// blocks have `None`.
+    idoms: Vec<Option<usize>>,
+    /// `children[i]` are the indices of the blocks immediately dominated by
+    /// block `i`
+    children: Vec<Vec<usize>>,
+    /// `depths[i]` is the depth of block `i` in the dominator tree, where the
+    /// entry block has depth 0
+    depths: Vec<usize>,
+    /// Block names, indexed the same way as in the `ControlFlowGraph`
+    blocks: Vec<&'f Name>,
+    /// Map from block name to its index in `blocks`
+    indices: HashMap<&'f Name, usize>,
+}
+
+impl<'f> DominatorTree<'f> {
+    /// Compute the dominator tree from the given `ControlFlowGraph`.
+    pub fn new(cfg: &ControlFlowGraph<'f>) -> Self {
+        // This is the algorithm from "A Simple, Fast Dominance Algorithm" by
+        // Cooper, Harvey, and Kennedy
+        let num_blocks = cfg.num_blocks();
+        let rpo = cfg.rpo_indices();
+        let mut rpo_number = vec![usize::MAX; num_blocks];
+        for (n, &block) in rpo.iter().enumerate() {
+            rpo_number[block] = n;
+        }
+
+        let mut idoms: Vec<Option<usize>> = vec![None; num_blocks];
+        idoms[rpo[0]] = Some(rpo[0]);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &block in &rpo[1 ..] {
+                let mut new_idom = None;
+                for &pred in cfg.pred_indices(block) {
+                    if idoms[pred].is_none() {
+                        // not processed yet, or unreachable
+                        continue;
+                    }
+                    new_idom = Some(match new_idom {
+                        None => pred,
+                        Some(cur) => intersect(&idoms, &rpo_number, pred, cur),
+                    });
+                }
+                if new_idom.is_some() && idoms[block] != new_idom {
+                    idoms[block] = new_idom;
+                    changed = true;
+                }
+            }
+        }
+
+        let mut children = vec![vec![]; num_blocks];
+        let mut depths = vec![0; num_blocks];
+        for &block in &rpo[1 ..] {
+            let idom = idoms[block].expect("reachable block should have an idom");
+            children[idom].push(block);
+            // in reverse postorder, the idom is always visited first
+            depths[block] = depths[idom] + 1;
+        }
+        for children in &mut children {
+            // report children in the order they appear in the function
+            children.sort_unstable();
+        }
+
+        let blocks: Vec<&'f Name> = (0 .. num_blocks).map(|i| cfg.name_of(i)).collect();
+        let indices = blocks
+            .iter()
+            .enumerate()
+            .map(|(i, &name)| (name, i))
+            .collect();
+        Self {
+            idoms,
+            children,
+            depths,
+            blocks,
+            indices,
+        }
+    }
+
+    /// Get the name of the immediate dominator of the given block.
+    ///
+    /// Returns `None` for the entry block, and for blocks which are
+    /// unreachable from the entry block.
+    ///
+    /// Panics if `block` isn't in the function.
+    pub fn idom(&self, block: &Name) -> Option<&'f Name> {
+        let index = self.index_of(block);
+        match self.idoms[index] {
+            Some(idom) if idom != index => Some(self.blocks[idom]),
+            _ => None,
+        }
+    }
+
+    /// Get the names of the blocks which the given block immediately
+    /// dominates; that is, its children in the dominator tree.
+    ///
+    /// Panics if `block` isn't in the function.
+    pub fn children<'s>(&'s self, block: &Name) -> impl Iterator<Item = &'f Name> + 's {
+        self.children[self.index_of(block)]
+            .iter()
+            .map(move |&i| self.blocks[i])
+    }
+
+    /// Does block `a` dominate block `b`?
+    ///
+    /// Panics if either block isn't in the function.
+    pub fn dominates(&self, a: &Name, b: &Name) -> bool {
+        self.dominates_index(self.index_of(a), self.index_of(b))
+    }
+
+    /// Does block `a` strictly dominate block `b`? That is, does `a` dominate
+    /// `b`, and `a != b`?
+    ///
+    /// Panics if either block isn't in the function.
+    pub fn strictly_dominates(&self, a: &Name, b: &Name) -> bool {
+        a != b && self.dominates(a, b)
+    }
+
+    /// Get the depth of the given block in the dominator tree. The entry block
+    /// has depth 0.
+    ///
+    /// Returns `None` for blocks which are unreachable from the entry block.
+    ///
+    /// Panics if `block` isn't in the function.
+    pub fn depth(&self, block: &Name) -> Option<usize> {
+        let index = self.index_of(block);
+        self.idoms[index].map(|_| self.depths[index])
+    }
+
+    pub(crate) fn dominates_index(&self, a: usize, mut b: usize) -> bool {
+        if self.idoms[a].is_none() || self.idoms[b].is_none() {
+            return false;
+        }
+        // walk up the tree from `b` until we reach `a`'s depth
+        while self.depths[b] > self.depths[a] {
+            b = self.idoms[b].unwrap();
+        }
+        a == b
+    }
+
+    fn index_of(&self, block: &Name) -> usize {
+        *self
+            .indices
+            .get(block)
+            .unwrap_or_else(|| panic!("Block {} not found in the dominator tree", block))
+    }
+}
+
+/// Find the nearest common dominator of `a` and `b` (given that the idoms
+/// computed so far are correct for both of them)
+fn intersect(idoms: &[Option<usize>], rpo_number: &[usize], mut a: usize, mut b: usize) -> usize {
+    while a != b {
+        while rpo_number[a] > rpo_number[b] {
+            a = idoms[a].unwrap();
+        }
+        while rpo_number[b] > rpo_number[a] {
+            b = idoms[b].unwrap();
+        }
+    }
+    a
+}
@@ -0,0 +1,78 @@
+use crate::function::Function;
+use crate::instruction::Instruction;
+use crate::name::Name;
+use crate::operand::Operand;
+use std::collections::HashMap;
+
+/// Copies, as (destination, value) pairs
+type Copies<'f> = Vec<(&'f Name, &'f Operand)>;
+
+/// A "phi-free" view of a `Function`: for each control-flow edge, the copies
+/// implied by the `Phi`s at the start of the edge's destination block.
+///
+/// Taking the edge from block `from` to block `to` assigns, to the result of
+/// each `Phi` in `to`, the `Phi`'s incoming value for `from`. These copies
+/// happen in parallel: all of the operands are read before any of the results
+/// are assigned. (This matters when one `Phi` in a loop header uses the
+/// result of another.)
+#[derive(Clone, Debug)]
+pub struct EdgeAssignments<'f> {
+    /// Map from predecessor block name, to successor block name, to the
+    /// copies for that edge, in the order of the `Phi`s in the successor
+    assignments: HashMap<&'f Name, HashMap<&'f Name, Copies<'f>>>,
+}
+
+impl<'f> EdgeAssignments<'f> {
+    /// Compute the `EdgeAssignments` of the given `Function`.
+    pub fn new(function: &'f Function) -> Self {
+        let mut assignments: HashMap<_, HashMap<_, Vec<_>>> = HashMap::new();
+        for bb in &function.basic_blocks {
+            let phis = bb.instrs.iter().map_while(|inst| match inst {
+                Instruction::Phi(phi) => Some(phi),
+                _ => None,
+            });
+            for phi in phis {
+                for (i, (value, pred)) in phi.incoming_values.iter().enumerate() {
+                    // a `Phi` may list the same predecessor more than once
+                    // (with the same value), if there are several edges from
+                    // it (e.g., several switch cases)
+                    if phi.incoming_values[.. i].iter().any(|(_, p)| p == pred) {
+                        continue;
+                    }
+                    assignments
+                        .entry(pred)
+                        .or_default()
+                        .entry(&bb.name)
+                        .or_default()
+                        .push((&phi.dest, value.as_ref()));
+                }
+            }
+        }
+        Self { assignments }
+    }
+
+    /// Get the copies for the edge from the block named `from` to the block
+    /// named `to`, as (destination, value) pairs in the order of the `Phi`s in
+    /// `to`. Returns an empty slice if `to` has no `Phi`s, or if there is no
+    /// such edge.
+    pub fn assignments(&self, from: &Name, to: &Name) -> &[(&'f Name, &'f Operand)] {
+        self.assignments
+            .get(from)
+            .and_then(|succs| succs.get(to))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Iterate over the edges which have copies, as (from, to) block names,
+    /// along with their copies. The edges are in no particular order.
+    #[allow(clippy::type_complexity)]
+    pub fn edges(
+        &self,
+    ) -> impl Iterator<Item = ((&'f Name, &'f Name), &[(&'f Name, &'f Operand)])> + '_ {
+        self.assignments.iter().flat_map(|(&from, succs)| {
+            succs
+                .iter()
+                .map(move |(&to, copies)| ((from, to), copies.as_slice()))
+        })
+    }
+}
@@ -0,0 +1,258 @@
+use super::{ControlFlowGraph, DominatorTree};
+use crate::name::Name;
+use std::collections::{HashMap, HashSet};
+
+/// The natural loops of a `Function`, and how they nest.
+///
+/// A natural loop is identified by a back edge: an edge from a block (the
+/// latch) to a block which dominates it (the header). All back edges with the
+/// same header are considered part of the same loop.
+///
+/// Cycles in irreducible control flow (cycles which can be entered at more than
+/// one block) have no back edges in this sense, and so are not reported as
+/// loops.
+#[derive(Clone, Debug)]
+pub struct LoopInfo<'f> {
+    /// All of the loops in the function, ordered so that each loop comes
+    /// after the loop containing it (if any)
+    loops: Vec<Loop<'f>>,
+    /// All of the back edges in the function, as (latch, header) pairs
+    back_edges: Vec<(&'f Name, &'f Name)>,
+    /// Map from block name to the index (in `loops`) of the innermost loop
+    /// containing it. Blocks not in any loop aren't in this map.
+    innermost: HashMap<&'f Name, usize>,
+}
+
+/// A single natural loop. See [`LoopInfo`](struct.LoopInfo.html).
+#[derive(Clone, Debug)]
+pub struct Loop<'f> {
+    header: &'f Name,
+    /// Blocks in the loop, in the order they appear in the function
+    blocks: Vec<&'f Name>,
+    block_set: HashSet<&'f Name>,
+    latches: Vec<&'f Name>,
+    exiting_blocks: Vec<&'f Name>,
+    exit_blocks: Vec<&'f Name>,
+    preheader: Option<&'f Name>,
+    depth: usize,
+    /// Index of the parent loop in `LoopInfo.loops`
+    parent: Option<usize>,
+    /// Indices of the immediate sub-loops in `LoopInfo.loops`
+    sub_loops: Vec<usize>,
+}
+
+impl<'f> LoopInfo<'f> {
+    /// Compute the loops of a function, given its `ControlFlowGraph` and
+    /// `DominatorTree`.
+    pub fn new(cfg: &ControlFlowGraph<'f>, domtree: &DominatorTree<'f>) -> Self {
+        // Find the back edges, grouped by header. Visiting the blocks in
+        // reverse postorder means that the headers of outer loops are found
+        // before the headers of loops nested inside them, since an outer
+        // loop's header dominates an inner loop's header.
+        let mut back_edges = vec![];
+        let mut headers: Vec<usize> = vec![];
+        let mut latches_by_header: HashMap<usize, Vec<usize>> = HashMap::new();
+        for &header in cfg.rpo_indices() {
+            for &latch in cfg.pred_indices(header) {
+                if domtree.dominates_index(header, latch) {
+                    back_edges.push((cfg.name_of(latch), cfg.name_of(header)));
+                    latches_by_header
+                        .entry(header)
+                        .or_insert_with(|| {
+                            headers.push(header);
+                            vec![]
+                        })
+                        .push(latch);
+                }
+            }
+        }
+
+        let mut reachable = vec![false; cfg.num_blocks()];
+        for &block in cfg.rpo_indices() {
+            reachable[block] = true;
+        }
+        let mut loops: Vec<Loop<'f>> = Vec::with_capacity(headers.len());
+        let mut innermost: HashMap<&'f Name, usize> = HashMap::new();
+        for header in headers {
+            let latches = &latches_by_header[&header];
+
+            // The body of the loop is the header, plus everything which can
+            // reach a latch without going through the header
+            let mut in_loop = vec![false; cfg.num_blocks()];
+            in_loop[header] = true;
+            let mut worklist = latches.clone();
+            while let Some(block) = worklist.pop() {
+                if !in_loop[block] {
+                    in_loop[block] = true;
+                    worklist.extend(
+                        cfg.pred_indices(block)
+                            .iter()
+                            .filter(|&&pred| reachable[pred]),
+                    );
+                }
+            }
+            let members: Vec<usize> = (0 .. cfg.num_blocks()).filter(|&b| in_loop[b]).collect();
+
+            let mut exiting_blocks = vec![];
+            let mut exit_blocks = vec![];
+            for &block in &members {
+                let mut exits = cfg
+                    .succ_indices(block)
+                    .iter()
+                    .filter(|&&succ| !in_loop[succ])
+                    .peekable();
+                if exits.peek().is_some() {
+                    exiting_blocks.push(cfg.name_of(block));
+                }
+                for &exit in exits {
+                    if !exit_blocks.contains(&exit) {
+                        exit_blocks.push(exit);
+                    }
+                }
+            }
+            exit_blocks.sort_unstable();
+
+            // A preheader is the unique (reachable) predecessor of the header
+            // from outside the loop, if that predecessor's only successor is
+            // the header
+            let mut outside_preds = cfg
+                .pred_indices(header)
+                .iter()
+                .filter(|&&pred| reachable[pred] && !in_loop[pred]);
+            let preheader = match (outside_preds.next(), outside_preds.next()) {
+                (Some(&pred), None) if cfg.succ_indices(pred) == [header] => {
+                    Some(cfg.name_of(pred))
+                },
+                _ => None,
+            };
+
+            // Since outer loops come first, the innermost loop already found
+            // which contains our header is our parent
+            let index = loops.len();
+            let parent = innermost.get(cfg.name_of(header)).copied();
+            let depth = match parent {
+                Some(parent) => {
+                    loops[parent].sub_loops.push(index);
+                    loops[parent].depth + 1
+                },
+                None => 1,
+            };
+
+            let blocks: Vec<&'f Name> = members.iter().map(|&b| cfg.name_of(b)).collect();
+            for &block in &blocks {
+                innermost.insert(block, index);
+            }
+            loops.push(Loop {
+                header: cfg.name_of(header),
+                block_set: blocks.iter().copied().collect(),
+                blocks,
+                latches: latches.iter().map(|&b| cfg.name_of(b)).collect(),
+                exiting_blocks,
+                exit_blocks: exit_blocks.into_iter().map(|b| cfg.name_of(b)).collect(),
+                preheader,
+                depth,
+                parent,
+                sub_loops: vec![],
+            });
+        }
+
+        Self {
+            loops,
+            back_edges,
+            innermost,
+        }
+    }
+
+    /// Get all of the loops in the function. Each loop appears after the loop
+    /// containing it (if any).
+    pub fn loops(&self) -> &[Loop<'f>] {
+        &self.loops
+    }
+
+    /// Get the loops which aren't contained in any other loop.
+    pub fn top_level_loops(&self) -> impl Iterator<Item = &Loop<'f>> {
+        self.loops.iter().filter(|l| l.parent.is_none())
+    }
+
+    /// Get all of the back edges in the function, as (latch, header) pairs.
+    pub fn back_edges(&self) -> &[(&'f Name, &'f Name)] {
+        &self.back_edges
+    }
+
+    /// Get the innermost loop containing the given block, or `None` if the
+    /// block isn't in any loop.
+    pub fn innermost_loop(&self, block: &Name) -> Option<&Loop<'f>> {
+        self.innermost.get(block).map(|&i| &self.loops[i])
+    }
+
+    /// Get the loop nesting depth of the given block: 0 if the block isn't in
+    /// any loop, 1 if it is in a loop but not a nested loop, and so on.
+    pub fn loop_depth(&self, block: &Name) -> usize {
+        self.innermost_loop(block).map_or(0, |l| l.depth)
+    }
+
+    /// Is the given block the header of some loop?
+    pub fn is_loop_header(&self, block: &Name) -> bool {
+        self.innermost_loop(block)
+            .is_some_and(|l| l.header == block)
+    }
+
+    /// Get the loop immediately containing the given loop, if any.
+    pub fn parent(&self, l: &Loop<'f>) -> Option<&Loop<'f>> {
+        l.parent.map(|i| &self.loops[i])
+    }
+
+    /// Get the loops immediately contained in the given loop.
+    pub fn sub_loops<'s>(&'s self, l: &'s Loop<'f>) -> impl Iterator<Item = &'s Loop<'f>> {
+        l.sub_loops.iter().map(move |&i| &self.loops[i])
+    }
+}
+
+impl<'f> Loop<'f> {
+    /// The loop header: the single entry point of the loop, which dominates
+    /// all of the blocks in the loop.
+    pub fn header(&self) -> &'f Name {
+        self.header
+    }
+
+    /// All of the blocks in the loop (including blocks in nested loops), in
+    /// the order they appear in the function.
+    pub fn blocks(&self) -> impl Iterator<Item = &'f Name> + '_ {
+        self.blocks.iter().copied()
+    }
+
+    /// Is the given block part of this loop (including nested loops)?
+    pub fn contains(&self, block: &Name) -> bool {
+        self.block_set.contains(block)
+    }
+
+    /// The latches of the loop: blocks in the loop which branch back to the
+    /// header.
+    pub fn latches(&self) -> &[&'f Name] {
+        &self.latches
+    }
+
+    /// Blocks in the loop which may branch to a block outside the loop.
+    pub fn exiting_blocks(&self) -> &[&'f Name] {
+        &self.exiting_blocks
+    }
+
+    /// Blocks outside the loop which may be branched to from inside the loop.
+    pub fn exit_blocks(&self) -> &[&'f Name] {
+        &self.exit_blocks
+    }
+
+    /// The loop preheader, if there is one: the only block outside the loop
+    /// which branches to the header, provided that its only successor is the
+    /// header. Blocks which are unreachable from the entry block are ignored
+    /// here.
+    pub fn preheader(&self) -> Option<&'f Name> {
+        self.preheader
+    }
+
+    /// The nesting depth of this loop: 1 for a loop which isn't contained in
+    /// any other loop, 2 for a loop directly inside one of those, and so on.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
@@ -0,0 +1,226 @@
+use super::{AliasResult, BasicAliasAnalysis, ControlFlowGraph};
+use crate::constant::Constant;
+use crate::function::{Function, FunctionAttribute, MemoryEffects, MemoryLocation};
+use crate::instruction::{InlineAssembly, Instruction};
+use crate::location::{InstrLoc, ModuleLoc};
+use crate::module::Module;
+use crate::name::Name;
+use crate::operand::{Operand, OperandRef};
+use crate::terminator::Terminator;
+use crate::types::TypeRef;
+use either::Either;
+use std::collections::{HashMap, HashSet};
+
+/// The instructions which may have written the memory read by a `Load`.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct LoadDependences {
+    /// The `Store`s, `CmpXchg`s, `AtomicRMW`s, and calls (including `Invoke`s)
+    /// which may have written the loaded memory, in order of location
+    pub writers: Vec<InstrLoc>,
+    /// Whether the load may instead read memory as it was when the function
+    /// was entered: that is, whether some path from the entry block to the
+    /// load doesn't definitely overwrite the loaded memory
+    pub live_on_entry: bool,
+}
+
+/// For each `Load` in a `Function`, the instructions in the function which
+/// may have written the memory it reads: a lightweight, intra-procedural
+/// stand-in for LLVM's `MemorySSA`.
+///
+/// A `Store` to an address which must alias the load's address, storing a
+/// value of the loaded type, is assumed to overwrite the loaded memory
+/// completely, hiding any earlier writers. Any other `Store`, `CmpXchg`, or
+/// `AtomicRMW` which may alias the load's address (according to
+/// [`BasicAliasAnalysis`](struct.BasicAliasAnalysis.html)) is a possible
+/// writer, as is any call which may write memory according to its
+/// [`MemoryEffects`](../function/enum.MemoryEffects.html).
+#[derive(Clone, Debug)]
+pub struct MemoryDependences {
+    /// Map from the location of each `Load` to its dependences
+    dependences: HashMap<InstrLoc, LoadDependences>,
+}
+
+impl MemoryDependences {
+    /// Compute the `MemoryDependences` of the given `Function`.
+    ///
+    /// Panics if the `Function` isn't one of the `functions` of the given
+    /// `Module`.
+    pub fn new(module: &Module, function: &Function) -> Self {
+        let func_index = module
+            .functions
+            .iter()
+            .position(|f| std::ptr::eq(f, function))
+            .expect("MemoryDependences::new: function is not in the module");
+        let walker = Walker {
+            module,
+            function,
+            cfg: ControlFlowGraph::new(function),
+            aa: BasicAliasAnalysis::new(module, function),
+        };
+        let mut dependences = HashMap::new();
+        for (block_index, bb) in function.basic_blocks.iter().enumerate() {
+            for (index, inst) in bb.instrs.iter().enumerate() {
+                if let Instruction::Load(load) = inst {
+                    let loc = ModuleLoc::new(func_index, block_index).instr(index);
+                    let deps = walker.find_writers(
+                        func_index,
+                        block_index,
+                        index,
+                        &load.address,
+                        &module.type_of(load),
+                    );
+                    dependences.insert(loc, deps);
+                }
+            }
+        }
+        Self { dependences }
+    }
+
+    /// Get the dependences of the `Load` at the given location, or `None` if
+    /// there is no `Load` there.
+    pub fn dependences(&self, load: InstrLoc) -> Option<&LoadDependences> {
+        self.dependences.get(&load)
+    }
+
+    /// Iterate over all of the `Load`s in the function and their dependences,
+    /// in no particular order.
+    pub fn loads(&self) -> impl Iterator<Item = (InstrLoc, &LoadDependences)> {
+        self.dependences.iter().map(|(&loc, deps)| (loc, deps))
+    }
+}
+
+struct Walker<'m> {
+    module: &'m Module,
+    function: &'m Function,
+    cfg: ControlFlowGraph<'m>,
+    aa: BasicAliasAnalysis<'m>,
+}
+
+impl<'m> Walker<'m> {
+    /// Walk backwards from the instruction at `index` in block `block_index`,
+    /// collecting the instructions which may write to `address`
+    fn find_writers(
+        &self,
+        func_index: usize,
+        block_index: usize,
+        index: usize,
+        address: &Operand,
+        ty: &TypeRef,
+    ) -> LoadDependences {
+        let mut deps = LoadDependences::default();
+        let block = ModuleLoc::new(func_index, block_index);
+        let mut worklist = vec![];
+        if !self.scan_block(block, Some(index), address, ty, &mut deps.writers) {
+            worklist.push(block_index);
+        }
+        // blocks which have been scanned in full (the load's own block has
+        // only been scanned from the load upwards so far)
+        let mut visited = HashSet::new();
+        while let Some(block_index) = worklist.pop() {
+            if block_index == 0 {
+                deps.live_on_entry = true;
+            }
+            for &pred in self.cfg.pred_indices(block_index) {
+                if visited.insert(pred) {
+                    let pred_block = ModuleLoc::new(func_index, pred);
+                    if !self.scan_block(pred_block, None, address, ty, &mut deps.writers) {
+                        worklist.push(pred);
+                    }
+                }
+            }
+        }
+        deps.writers.sort_unstable();
+        deps.writers.dedup();
+        deps
+    }
+
+    /// Scan the given block backwards, from just before the instruction at
+    /// index `before` (or from the end of the block, including the
+    /// terminator), pushing any writers of `address` found to `writers`.
+    /// Returns `true` if some instruction definitely overwrites `address`, so
+    /// that the scan stopped there.
+    fn scan_block(
+        &self,
+        block: ModuleLoc,
+        before: Option<usize>,
+        address: &Operand,
+        ty: &TypeRef,
+        writers: &mut Vec<InstrLoc>,
+    ) -> bool {
+        let bb = &self.function.basic_blocks[block.block];
+        let end = match before {
+            Some(index) => index,
+            None => {
+                if self.term_may_write(&bb.term) {
+                    writers.push(block.terminator());
+                }
+                bb.instrs.len()
+            },
+        };
+        for (index, inst) in bb.instrs[.. end].iter().enumerate().rev() {
+            let may_alias = |ptr: &Operand| self.aa.alias(ptr, address) != AliasResult::NoAlias;
+            match inst {
+                Instruction::Store(store) => {
+                    let alias = self.aa.alias(&store.address, address);
+                    if alias != AliasResult::NoAlias {
+                        writers.push(block.instr(index));
+                    }
+                    if alias == AliasResult::MustAlias && &self.module.type_of(&store.value) == ty {
+                        return true;
+                    }
+                },
+                Instruction::CmpXchg(cmpxchg) if may_alias(&cmpxchg.address) => {
+                    writers.push(block.instr(index));
+                },
+                Instruction::AtomicRMW(rmw) if may_alias(&rmw.address) => {
+                    writers.push(block.instr(index));
+                },
+                Instruction::Call(call)
+                    if self.call_may_write(&call.function, &call.function_attributes) =>
+                {
+                    writers.push(block.instr(index));
+                },
+                _ => {},
+            }
+        }
+        false
+    }
+
+    fn term_may_write(&self, term: &Terminator) -> bool {
+        match term {
+            Terminator::Invoke(invoke) => {
+                self.call_may_write(&invoke.function, &invoke.function_attributes)
+            },
+            #[cfg(feature = "llvm-9-or-greater")]
+            Terminator::CallBr(callbr) => {
+                self.call_may_write(&callbr.function, &callbr.function_attributes)
+            },
+            _ => false,
+        }
+    }
+
+    /// Might a call to the given function, with the given call-site
+    /// attributes, write to memory?
+    fn call_may_write(
+        &self,
+        function: &Either<InlineAssembly, OperandRef>,
+        attributes: &[FunctionAttribute],
+    ) -> bool {
+        let call_site = MemoryEffects::from_attributes(attributes);
+        let callee = match function {
+            Either::Right(op) => match op.as_constant() {
+                Some(Constant::GlobalReference {
+                    name: Name::Name(name),
+                    ..
+                }) => match self.module.get_func_by_name(name) {
+                    Some(f) => f.memory_effects(),
+                    None => MemoryEffects::of_intrinsic(name)
+                        .unwrap_or(MemoryEffects::ReadWrite(MemoryLocation::Any)),
+                },
+                _ => MemoryEffects::ReadWrite(MemoryLocation::Any),
+            },
+            Either::Left(_) => MemoryEffects::ReadWrite(MemoryLocation::Any),
+        };
+        call_site.intersect(callee).may_write()
+    }
+}
@@ -0,0 +1,180 @@
+use crate::function::Function;
+use crate::module::{
+    constant_global_refs,
+    function_global_refs,
+    GlobalAlias,
+    GlobalVariable,
+    Linkage,
+    Module,
+};
+use crate::name::Name;
+use std::collections::{HashMap, HashSet};
+
+/// Which functions, global variables, and aliases of a `Module` are reachable
+/// from a set of roots, by following the references in function bodies
+/// (including personality functions), global variable initializers, and
+/// aliasees. Everything the `Module` defines which isn't reachable is dead:
+/// nothing which can be called or accessed from outside the `Module` can get
+/// to it.
+///
+/// By default, the roots are the [`default_roots()`](#method.default_roots)
+/// of the `Module`; use [`with_roots()`](#method.with_roots) to choose
+/// others, e.g., only the entry points of a program.
+#[derive(Clone)]
+pub struct Reachability<'m> {
+    module: &'m Module,
+    reachable: HashSet<Name>,
+}
+
+impl<'m> Reachability<'m> {
+    /// Compute reachability from the
+    /// [`default_roots()`](#method.default_roots) of the `Module`.
+    pub fn new(module: &'m Module) -> Self {
+        Self::with_roots(module, Self::default_roots(module))
+    }
+
+    /// Compute reachability from the given roots. Roots which don't name a
+    /// function, global variable, or alias defined in the `Module` are still
+    /// reachable themselves, but have nothing to follow.
+    pub fn with_roots(module: &'m Module, roots: impl IntoIterator<Item = Name>) -> Self {
+        let funcs: HashMap<&[u8], &Function> = module
+            .functions
+            .iter()
+            .map(|func| (func.name.as_bytes(), func))
+            .collect();
+        let vars: HashMap<&Name, &GlobalVariable> = module
+            .global_vars
+            .iter()
+            .map(|var| (&var.name, var))
+            .collect();
+        let aliases: HashMap<&Name, &GlobalAlias> = module
+            .global_aliases
+            .iter()
+            .map(|alias| (&alias.name, alias))
+            .collect();
+
+        let mut reachable: HashSet<Name> = HashSet::new();
+        let mut worklist: Vec<Name> = roots.into_iter().collect();
+        while let Some(name) = worklist.pop() {
+            if reachable.contains(&name) {
+                continue;
+            }
+            let mut refs = vec![];
+            if let Some(func) = name.as_bytes().and_then(|bytes| funcs.get(bytes)) {
+                function_global_refs(func, &mut refs);
+            } else if let Some(var) = vars.get(&name) {
+                if let Some(init) = &var.initializer {
+                    constant_global_refs(init, &mut refs);
+                }
+            } else if let Some(alias) = aliases.get(&name) {
+                constant_global_refs(&alias.aliasee, &mut refs);
+            }
+            worklist.extend(
+                refs.into_iter()
+                    .filter(|&referenced| !reachable.contains(referenced))
+                    .cloned(),
+            );
+            reachable.insert(name);
+        }
+
+        Self { module, reachable }
+    }
+
+    /// Get the default roots of the `Module`, in this order:
+    ///   - its exported symbols: the functions, global variables, and aliases
+    ///     it defines without `private` or `internal` linkage, in `Module`
+    ///     order. These include the `llvm.used`, `llvm.compiler.used`,
+    ///     `llvm.global_ctors`, and `llvm.global_dtors` arrays, which have
+    ///     `appending` linkage;
+    ///   - the globals listed in [`llvm.used`](../struct.Module.html#method.used_globals)
+    ///     and [`llvm.compiler.used`](../struct.Module.html#method.compiler_used_globals);
+    ///   - the functions listed in [`llvm.global_ctors`](../struct.Module.html#method.global_ctors)
+    ///     and [`llvm.global_dtors`](../struct.Module.html#method.global_dtors),
+    ///     and the globals their data fields refer to.
+    ///
+    /// The last two are reachable from the arrays anyway, but are listed so
+    /// that they stay roots if the arrays are left out, e.g., when filtering
+    /// these roots before passing them to [`with_roots()`](#method.with_roots).
+    /// A root may be listed more than once.
+    pub fn default_roots(module: &Module) -> Vec<Name> {
+        let exported = |linkage: Linkage| !matches!(linkage, Linkage::Private | Linkage::Internal);
+        let mut roots: Vec<Name> = module
+            .functions
+            .iter()
+            .filter(|func| exported(func.linkage))
+            .map(|func| Name::from(func.name.as_str()))
+            .chain(
+                module
+                    .global_vars
+                    .iter()
+                    .filter(|var| exported(var.linkage))
+                    .map(|var| var.name.clone()),
+            )
+            .chain(
+                module
+                    .global_aliases
+                    .iter()
+                    .filter(|alias| exported(alias.linkage))
+                    .map(|alias| alias.name.clone()),
+            )
+            .collect();
+        roots.extend(module.used_globals());
+        roots.extend(module.compiler_used_globals());
+        for (_, func, data) in module
+            .global_ctors()
+            .into_iter()
+            .chain(module.global_dtors())
+        {
+            roots.push(func);
+            roots.extend(data);
+        }
+        roots
+    }
+
+    /// Is the global with the given name reachable? This includes the
+    /// functions which the `Module` only declares, if they are referenced
+    /// from anything reachable.
+    pub fn is_reachable(&self, name: &Name) -> bool {
+        self.reachable.contains(name)
+    }
+
+    /// Iterate over the reachable functions which the `Module` defines, in
+    /// `Module` order.
+    pub fn reachable_functions(&self) -> impl Iterator<Item = &'m Function> + '_ {
+        self.module
+            .functions
+            .iter()
+            .filter(move |func| self.is_function_reachable(func))
+    }
+
+    /// Iterate over the dead functions: the functions which the `Module`
+    /// defines but which aren't reachable, in `Module` order.
+    pub fn dead_functions(&self) -> impl Iterator<Item = &'m Function> + '_ {
+        self.module
+            .functions
+            .iter()
+            .filter(move |func| !self.is_function_reachable(func))
+    }
+
+    /// Iterate over the global variables of the `Module` which aren't
+    /// reachable, in `Module` order.
+    pub fn dead_global_vars(&self) -> impl Iterator<Item = &'m GlobalVariable> + '_ {
+        self.module
+            .global_vars
+            .iter()
+            .filter(move |var| !self.is_reachable(&var.name))
+    }
+
+    /// Iterate over the global aliases of the `Module` which aren't
+    /// reachable, in `Module` order.
+    pub fn dead_global_aliases(&self) -> impl Iterator<Item = &'m GlobalAlias> + '_ {
+        self.module
+            .global_aliases
+            .iter()
+            .filter(move |alias| !self.is_reachable(&alias.name))
+    }
+
+    fn is_function_reachable(&self, func: &Function) -> bool {
+        self.is_reachable(&Name::from(func.name.as_str()))
+    }
+}
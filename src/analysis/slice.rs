@@ -0,0 +1,103 @@
+use super::{Definition, MemoryDependences, SsaGraph};
+use crate::function::Function;
+use crate::instruction::Instruction;
+use crate::location::{InstrLoc, InstrPos, ModuleLoc};
+use crate::metadata::Metadata;
+use crate::name::Name;
+use crate::operand::Operand;
+use std::collections::{HashMap, HashSet};
+
+/// Compute the backward slice of an operand: the instructions and terminators
+/// in the `Function` which (transitively) contribute to its value.
+///
+/// The operand is the one at index `operand_index` in the `operands()` of the
+/// instruction or terminator at `loc`, which must be in the given `Function`.
+/// The slice follows data dependences only (including through `Phi`s), not
+/// control dependences, and stops at function parameters and constants.
+///
+/// If `memory` is given (and is the `MemoryDependences` of the given
+/// `Function`), the slice also follows each `Load` to the instructions which
+/// may have written the memory it reads, and then to their operands.
+///
+/// Returns the locations of the instructions and terminators in the slice, in
+/// order of location. The instruction at `loc` itself is only included if it
+/// contributes to the operand (e.g., through a loop).
+///
+/// Panics if there is no instruction or terminator at `loc`, or if it has
+/// fewer than `operand_index + 1` operands.
+pub fn backward_slice(
+    function: &Function,
+    loc: InstrLoc,
+    operand_index: usize,
+    memory: Option<&MemoryDependences>,
+) -> Vec<InstrLoc> {
+    let func_index = loc.block.func;
+    let ssa = SsaGraph::new(function);
+    let block_indices: HashMap<&Name, usize> = function
+        .basic_blocks
+        .iter()
+        .enumerate()
+        .map(|(i, bb)| (&bb.name, i))
+        .collect();
+    let operands_at = |loc: InstrLoc| -> Vec<&Operand> {
+        let bb = function
+            .basic_blocks
+            .get(loc.block.block)
+            .unwrap_or_else(|| panic!("backward_slice: no block at {:?}", loc.block));
+        match loc.pos {
+            InstrPos::Instr(index) => bb
+                .instrs
+                .get(index)
+                .unwrap_or_else(|| panic!("backward_slice: no instruction at {:?}", loc))
+                .operands()
+                .collect(),
+            InstrPos::Terminator => bb.term.operands().collect(),
+        }
+    };
+
+    let operand = *operands_at(loc).get(operand_index).unwrap_or_else(|| {
+        panic!(
+            "backward_slice: the instruction at {:?} has no operand {}",
+            loc, operand_index
+        )
+    });
+    let mut slice = HashSet::new();
+    let mut worklist = vec![operand];
+    while let Some(operand) = worklist.pop() {
+        let operand = match operand {
+            Operand::MetadataOperand(md) => match md.as_ref() {
+                Metadata::Value(op) => op,
+                _ => continue,
+            },
+            op => op,
+        };
+        let (def_loc, is_load) = match ssa.resolve(operand) {
+            Some(Definition::Instruction { block, index, inst }) => (
+                ModuleLoc::new(func_index, block_indices[block]).instr(*index),
+                matches!(inst, Instruction::Load(_)),
+            ),
+            Some(Definition::Terminator { block, .. }) => (
+                ModuleLoc::new(func_index, block_indices[block]).terminator(),
+                false,
+            ),
+            Some(Definition::Parameter { .. }) | None => continue,
+        };
+        if !slice.insert(def_loc) {
+            continue;
+        }
+        worklist.extend(operands_at(def_loc));
+        if let Some(deps) = memory
+            .filter(|_| is_load)
+            .and_then(|m| m.dependences(def_loc))
+        {
+            for &writer in &deps.writers {
+                if slice.insert(writer) {
+                    worklist.extend(operands_at(writer));
+                }
+            }
+        }
+    }
+    let mut slice: Vec<InstrLoc> = slice.into_iter().collect();
+    slice.sort_unstable();
+    slice
+}
@@ -0,0 +1,214 @@
+use crate::function::{Function, Parameter};
+use crate::instruction::Instruction;
+use crate::location::{InstrPos, InstrRef};
+use crate::name::Name;
+use crate::operand::Operand;
+use crate::terminator::Terminator;
+use std::collections::HashMap;
+
+/// Where an SSA value is defined within a `Function`.
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+pub enum Definition<'f> {
+    /// The value is the function parameter with the given index
+    Parameter { index: usize, param: &'f Parameter },
+    /// The value is the result of the `Instruction` at the given index in the
+    /// given block
+    Instruction {
+        block: &'f Name,
+        index: usize,
+        inst: &'f Instruction,
+    },
+    /// The value is the result of the given block's `Terminator` (for
+    /// instance, an `Invoke`)
+    Terminator {
+        block: &'f Name,
+        term: &'f Terminator,
+    },
+}
+
+impl<'f> Definition<'f> {
+    /// Get the name of the value being defined.
+    pub fn name(&self) -> &'f Name {
+        match self {
+            Definition::Parameter { param, .. } => &param.name,
+            Definition::Instruction { inst, .. } => inst
+                .try_get_result()
+                .expect("Definition::Instruction should have a result"),
+            Definition::Terminator { term, .. } => term
+                .try_get_result()
+                .expect("Definition::Terminator should have a result"),
+        }
+    }
+
+    /// Get the name of the block containing the definition, or `None` for
+    /// function parameters.
+    pub fn block(&self) -> Option<&'f Name> {
+        match self {
+            Definition::Parameter { .. } => None,
+            Definition::Instruction { block, .. } => Some(block),
+            Definition::Terminator { block, .. } => Some(block),
+        }
+    }
+}
+
+/// A use of an SSA value as an operand of an `Instruction` or `Terminator`.
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+pub struct Use<'f> {
+    /// The block containing the user
+    pub block: &'f Name,
+    /// The position of the user within the block
+    pub pos: InstrPos,
+    /// The `Instruction` or `Terminator` using the value
+    pub user: InstrRef<'f>,
+    /// The index of the use in the user's `operands()`
+    pub operand_index: usize,
+}
+
+/// Resolves each local value in a `Function` to the place where it is
+/// defined, and to the places where it is used, without scanning the function.
+///
+/// Each SSA value (parameter, or instruction or terminator result) is also
+/// given a value number: parameters are numbered first, in order, followed by
+/// instruction and terminator results in the order they appear in the
+/// function.
+#[derive(Clone, Debug)]
+pub struct SsaGraph<'f> {
+    /// All of the definitions in the function, indexed by value number
+    defs: Vec<Definition<'f>>,
+    /// The uses of each value, indexed by value number
+    uses: Vec<Vec<Use<'f>>>,
+    /// Map from value name to value number
+    numbers: HashMap<&'f Name, usize>,
+}
+
+impl<'f> SsaGraph<'f> {
+    /// Compute the `SsaGraph` of the given `Function`.
+    pub fn new(function: &'f Function) -> Self {
+        let mut defs = vec![];
+        for (index, param) in function.parameters.iter().enumerate() {
+            defs.push(Definition::Parameter { index, param });
+        }
+        for bb in &function.basic_blocks {
+            for (index, inst) in bb.instrs.iter().enumerate() {
+                if inst.try_get_result().is_some() {
+                    defs.push(Definition::Instruction {
+                        block: &bb.name,
+                        index,
+                        inst,
+                    });
+                }
+            }
+            if bb.term.try_get_result().is_some() {
+                defs.push(Definition::Terminator {
+                    block: &bb.name,
+                    term: &bb.term,
+                });
+            }
+        }
+        let numbers = defs
+            .iter()
+            .enumerate()
+            .map(|(i, def)| (def.name(), i))
+            .collect();
+        let mut graph = Self {
+            uses: vec![vec![]; defs.len()],
+            defs,
+            numbers,
+        };
+        for bb in &function.basic_blocks {
+            let users = bb
+                .instrs
+                .iter()
+                .enumerate()
+                .map(|(i, inst)| (InstrPos::Instr(i), InstrRef::Instr(inst)))
+                .chain(std::iter::once((
+                    InstrPos::Terminator,
+                    InstrRef::Terminator(&bb.term),
+                )));
+            for (pos, user) in users {
+                let operands: Vec<&Operand> = match user {
+                    InstrRef::Instr(inst) => inst.operands().collect(),
+                    InstrRef::Terminator(term) => term.operands().collect(),
+                };
+                for (operand_index, operand) in operands.into_iter().enumerate() {
+                    if let Operand::LocalOperand { name, .. } = operand {
+                        if let Some(&number) = graph.numbers.get(name) {
+                            graph.uses[number].push(Use {
+                                block: &bb.name,
+                                pos,
+                                user,
+                                operand_index,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        graph
+    }
+
+    /// Get the `Definition` of the local value with the given name, or `None`
+    /// if no such value is defined in the function.
+    pub fn definition(&self, name: &Name) -> Option<&Definition<'f>> {
+        self.numbers.get(name).map(|&i| &self.defs[i])
+    }
+
+    /// Get the `Definition` of the value referred to by the given `Operand`.
+    ///
+    /// Returns `None` for operands which aren't `LocalOperand`s, and for
+    /// `LocalOperand`s which aren't defined in the function.
+    pub fn resolve(&self, operand: &Operand) -> Option<&Definition<'f>> {
+        match operand {
+            Operand::LocalOperand { name, .. } => self.definition(name),
+            Operand::ConstantOperand(_) | Operand::MetadataOperand(_) => None,
+        }
+    }
+
+    /// Get the `Instruction` defining the local value with the given name, or
+    /// `None` if the value isn't defined by an `Instruction` (for instance,
+    /// because it is a function parameter).
+    pub fn defining_instruction(&self, name: &Name) -> Option<&'f Instruction> {
+        match self.definition(name) {
+            Some(Definition::Instruction { inst, .. }) => Some(inst),
+            _ => None,
+        }
+    }
+
+    /// Get the uses of the local value with the given name, in the order they
+    /// appear in the function: by block, and then by position within the
+    /// block, with uses by the same user in operand order. This order is
+    /// deterministic, unlike the order of LLVM's own use-lists, which depends
+    /// on the order in which the uses were created.
+    ///
+    /// Returns an empty slice if no such value is defined in the function.
+    pub fn uses(&self, name: &Name) -> &[Use<'f>] {
+        match self.numbers.get(name) {
+            Some(&number) => &self.uses[number],
+            None => &[],
+        }
+    }
+
+    /// Get the value number of the local value with the given name, or `None`
+    /// if no such value is defined in the function.
+    pub fn value_number(&self, name: &Name) -> Option<usize> {
+        self.numbers.get(name).copied()
+    }
+
+    /// Get the `Definition` of the value with the given value number.
+    ///
+    /// Panics if `number` is not less than `num_values()`.
+    pub fn value(&self, number: usize) -> &Definition<'f> {
+        &self.defs[number]
+    }
+
+    /// Get the number of SSA values defined in the function.
+    pub fn num_values(&self) -> usize {
+        self.defs.len()
+    }
+
+    /// Iterate over the `Definition`s of all the values in the function, in
+    /// value-number order.
+    pub fn values(&self) -> impl Iterator<Item = &Definition<'f>> {
+        self.defs.iter()
+    }
+}
@@ -0,0 +1,112 @@
+//! A reader for the Unix `ar` archive format of static libraries, in both its
+//! GNU and BSD variants, for reading archives of bitcode files such as those
+//! built with `-flto`. The LLVM C API doesn't give access to archive members.
+
+use crate::error::Error;
+
+/// The magic string at the start of every (non-thin) archive
+const MAGIC: &[u8] = b"!<arch>\n";
+/// The magic string at the start of a thin archive, whose members are stored
+/// in separate files
+const THIN_MAGIC: &[u8] = b"!<thin>\n";
+/// The size of each member's header, in bytes
+const HEADER_SIZE: usize = 60;
+
+/// A file stored in an archive
+pub(crate) struct Member<'a> {
+    pub name: String,
+    pub data: &'a [u8],
+}
+
+/// Does the given data start like an archive (thin or not)?
+pub(crate) fn is_archive(data: &[u8]) -> bool {
+    data.starts_with(MAGIC) || data.starts_with(THIN_MAGIC)
+}
+
+/// Get the members of the given archive, in order, without the symbol table
+/// and the GNU long-name table
+pub(crate) fn members(data: &[u8]) -> Result<Vec<Member<'_>>, Error> {
+    if data.starts_with(THIN_MAGIC) {
+        return Err(malformed("thin archives are not supported"));
+    }
+    if !data.starts_with(MAGIC) {
+        return Err(malformed("missing archive magic"));
+    }
+    let mut members = vec![];
+    // the GNU table of names which don't fit in a header
+    let mut long_names: &[u8] = &[];
+    let mut pos = MAGIC.len();
+    while pos < data.len() {
+        let header = data
+            .get(pos .. pos + HEADER_SIZE)
+            .ok_or_else(|| malformed("truncated member header"))?;
+        if &header[58 ..] != b"`\n" {
+            return Err(malformed("bad member header"));
+        }
+        let size: usize = field(&header[48 .. 58])
+            .parse()
+            .map_err(|_| malformed("bad member size"))?;
+        let start = pos + HEADER_SIZE;
+        let mut contents = data
+            .get(start .. start + size)
+            .ok_or_else(|| malformed("truncated member"))?;
+        // members are aligned to an even offset
+        pos = start + size + size % 2;
+
+        let raw_name = field(&header[.. 16]);
+        if raw_name == "//" {
+            long_names = contents;
+            continue;
+        }
+        let name = if raw_name == "/" || raw_name == "/SYM64/" {
+            // the GNU symbol table
+            continue;
+        } else if let Some(len) = raw_name.strip_prefix("#1/") {
+            // BSD: the name is stored at the start of the member's contents
+            let len: usize = len.parse().map_err(|_| malformed("bad name length"))?;
+            if len > contents.len() {
+                return Err(malformed("truncated member name"));
+            }
+            let (name, rest) = contents.split_at(len);
+            contents = rest;
+            String::from_utf8_lossy(name)
+                .trim_end_matches('\0')
+                .to_owned()
+        } else if let Some(offset) = raw_name.strip_prefix('/') {
+            // GNU: the name is stored in the long-name table, terminated by
+            // "/\n"
+            let offset: usize = offset.parse().map_err(|_| malformed("bad name offset"))?;
+            let name = long_names
+                .get(offset ..)
+                .ok_or_else(|| malformed("name offset out of range"))?;
+            let end = name
+                .windows(2)
+                .position(|w| w == b"/\n")
+                .unwrap_or(name.len());
+            String::from_utf8_lossy(&name[.. end]).into_owned()
+        } else {
+            // GNU names end with '/'; BSD names are padded with spaces
+            raw_name.strip_suffix('/').unwrap_or(raw_name).to_owned()
+        };
+        if name.starts_with("__.SYMDEF") {
+            // the BSD symbol table
+            continue;
+        }
+        members.push(Member {
+            name,
+            data: contents,
+        });
+    }
+    Ok(members)
+}
+
+/// A field of a member header, without its padding
+fn field(bytes: &[u8]) -> &str {
+    std::str::from_utf8(bytes)
+        .unwrap_or("")
+        .trim_end_matches(' ')
+}
+
+fn malformed(msg: &str) -> Error {
+    Error::ReadFailed(format!("malformed archive: {}", msg))
+}
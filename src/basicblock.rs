@@ -5,7 +5,8 @@ use crate::terminator::Terminator;
 /// A `BasicBlock` is a sequence of zero or more non-terminator instructions
 /// followed by a single terminator instruction which ends the block.
 /// Basic blocks are discussed in the [LLVM 14 docs on Functions](https://releases.llvm.org/14.0.0/docs/LangRef.html#functionstructure)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BasicBlock {
     pub name: Name,
     pub instrs: Vec<Instruction>,
@@ -31,6 +32,7 @@ impl BasicBlock {
 // from_llvm //
 // ********* //
 
+use crate::error::Error;
 use crate::from_llvm::*;
 use crate::function::FunctionContext;
 use crate::llvm_sys::*;
@@ -43,8 +45,8 @@ impl BasicBlock {
         bb: LLVMBasicBlockRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
-        let name = Name::name_or_num(unsafe { get_bb_name(bb) }, &mut func_ctx.ctr);
+    ) -> Result<Self, Error> {
+        let name = Name::name_or_num(unsafe { get_bb_name_bytes(bb) }, &mut func_ctx.ctr);
         debug_assert_eq!(
             &name,
             func_ctx
@@ -53,17 +55,18 @@ impl BasicBlock {
                 .expect("Expected to find bb in func_ctx.bb_names"),
         );
         debug!("Processing a basic block named {:?}", name);
-        Self {
-            name,
-            instrs: all_but_last(get_instructions(bb))
-                .map(|i| Instruction::from_llvm_ref(i, ctx, func_ctx))
-                .collect(),
-            term: Terminator::from_llvm_ref(
-                unsafe { LLVMGetBasicBlockTerminator(bb) },
-                ctx,
-                func_ctx,
-            ),
-        }
+        let skip_debug_intrinsics = ctx.options.skip_debug_info;
+        let instrs = all_but_last(get_instructions(bb))
+            .filter(|&i| !(skip_debug_intrinsics && is_debug_intrinsic_call(i)))
+            .map(|i| {
+                Instruction::from_llvm_ref(i, ctx, func_ctx)
+                    .map_err(|e| e.in_block(&name).in_instruction(i))
+            })
+            .collect::<Result<_, _>>()?;
+        let term = unsafe { LLVMGetBasicBlockTerminator(bb) };
+        let term = Terminator::from_llvm_ref(term, ctx, func_ctx)
+            .map_err(|e| e.in_block(&name).in_instruction(term))?;
+        Ok(Self { name, instrs, term })
     }
 
     // Returns the name of the basic block and a vec of (instruction/terminator, name) pairs
@@ -71,25 +74,30 @@ impl BasicBlock {
         bb: LLVMBasicBlockRef,
         ctr: &mut usize,
     ) -> (Name, Vec<(LLVMValueRef, Name)>) {
-        let bbname = Name::name_or_num(unsafe { get_bb_name(bb) }, ctr);
+        let bbname = Name::name_or_num(unsafe { get_bb_name_bytes(bb) }, ctr);
         let mut instnames = vec![];
         for inst in all_but_last(get_instructions(bb)).filter(|&i| needs_name(i)) {
             instnames.push((
                 inst,
-                Name::name_or_num(unsafe { get_value_name(inst) }, ctr),
+                Name::name_or_num(unsafe { get_value_name_bytes(inst) }, ctr),
             ));
         }
         let term = unsafe { LLVMGetBasicBlockTerminator(bb) };
         if term_needs_name(term) {
             instnames.push((
                 term,
-                Name::name_or_num(unsafe { get_value_name(term) }, ctr),
+                Name::name_or_num(unsafe { get_value_name_bytes(term) }, ctr),
             ));
         }
         (bbname, instnames)
     }
 }
 
+/// Is `inst` a call to one of the `llvm.dbg.*` intrinsics
+fn is_debug_intrinsic_call(inst: LLVMValueRef) -> bool {
+    unsafe { !LLVMIsADbgInfoIntrinsic(inst).is_null() }
+}
+
 // Given only the LLVMValueRef for an Instruction, determine whether it needs a name
 fn needs_name(inst: LLVMValueRef) -> bool {
     if unsafe { !get_value_name(inst).is_empty() } {
@@ -104,7 +112,9 @@ fn needs_name(inst: LLVMValueRef) -> bool {
                 unsafe { LLVMGetTypeKind(LLVMGetReturnType(LLVMGetCalledFunctionType(inst))) };
             kind != LLVMVoidTypeKind
         },
-        _ => true, // all other instructions have results (destinations) and thus will need names
+        // all other instructions we know about have results (destinations) and
+        // thus will need names; for ones we don't, it depends on their type
+        _ => unsafe { LLVMGetTypeKind(LLVMTypeOf(inst)) != LLVMVoidTypeKind },
     }
 }
 
@@ -118,6 +128,8 @@ fn term_needs_name(term: LLVMValueRef) -> bool {
         LLVMOpcode::LLVMCatchSwitch => true,
         #[cfg(feature = "llvm-9-or-greater")]
         LLVMOpcode::LLVMCallBr => true,
-        _ => false, // all other terminators have no result (destination) and thus don't need names
+        // all other terminators we know about have no result (destination) and
+        // thus don't need names; for ones we don't, it depends on their type
+        _ => unsafe { LLVMGetTypeKind(LLVMTypeOf(term)) != LLVMVoidTypeKind },
     }
 }
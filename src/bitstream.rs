@@ -0,0 +1,465 @@
+//! A reader for LLVM's bitstream container format, in which bitcode files are
+//! stored, for the parts of bitcode files which the LLVM C API doesn't give
+//! access to.
+//! See [LLVM 14 docs on the Bitstream Format](https://releases.llvm.org/14.0.0/docs/BitCodeFormat.html#bitstream-format)
+
+use crate::error::Error;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::ops::Range;
+use std::rc::Rc;
+
+/// The magic number at the start of every bitcode file
+const MAGIC: &[u8] = b"BC\xC0\xDE";
+
+/// Block ID of the `BLOCKINFO` block, which defines abbreviations for other
+/// blocks
+const BLOCKINFO_BLOCK_ID: u64 = 0;
+/// Record code, in the `BLOCKINFO` block, which sets the block ID which
+/// subsequent abbreviations are for
+const BLOCKINFO_CODE_SETBID: u64 = 1;
+
+/// Abbreviation IDs with fixed meanings
+const END_BLOCK: u64 = 0;
+const ENTER_SUBBLOCK: u64 = 1;
+const DEFINE_ABBREV: u64 = 2;
+const UNABBREV_RECORD: u64 = 3;
+
+/// Reads individual bits from a byte slice, least significant bit first
+#[derive(Clone)]
+struct BitReader<'a> {
+    data: &'a [u8],
+    /// Current position, in bits
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn read(&mut self, width: u32) -> Result<u64, Error> {
+        debug_assert!(width <= 64);
+        let mut value = 0;
+        let mut done = 0;
+        while done < width {
+            let byte = *self.data.get(self.pos / 8).ok_or(Error::InvalidBitcode)?;
+            let offset = (self.pos % 8) as u32;
+            let take = (8 - offset).min(width - done);
+            let bits = (u64::from(byte) >> offset) & ((1 << take) - 1);
+            value |= bits << done;
+            done += take;
+            self.pos += take as usize;
+        }
+        Ok(value)
+    }
+
+    fn read_vbr(&mut self, width: u32) -> Result<u64, Error> {
+        if width < 2 {
+            return Err(Error::InvalidBitcode);
+        }
+        let continuation = 1 << (width - 1);
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let chunk = self.read(width)?;
+            if shift >= 64 {
+                return Err(Error::InvalidBitcode);
+            }
+            value |= (chunk & (continuation - 1)) << shift;
+            if chunk & continuation == 0 {
+                return Ok(value);
+            }
+            shift += width - 1;
+        }
+    }
+
+    fn align32(&mut self) {
+        self.pos = self.pos.div_ceil(32) * 32;
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        debug_assert_eq!(self.pos % 8, 0);
+        let start = self.pos / 8;
+        let bytes = self
+            .data
+            .get(start .. start.checked_add(len).ok_or(Error::InvalidBitcode)?)
+            .ok_or(Error::InvalidBitcode)?;
+        self.pos += len * 8;
+        Ok(bytes)
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.data.len() * 8
+    }
+}
+
+/// One operand of an abbreviation
+#[derive(Clone, Debug)]
+enum AbbrevOp {
+    Literal(u64),
+    Fixed(u32),
+    Vbr(u32),
+    /// An array, whose elements are encoded with the following operand
+    Array,
+    Char6,
+    Blob,
+}
+
+type Abbrev = Rc<Vec<AbbrevOp>>;
+
+/// A record read from a bitstream
+#[derive(Clone, Debug)]
+pub(crate) struct Record<'a> {
+    pub code: u64,
+    pub ops: Vec<u64>,
+    /// The contents of the record's blob operand, if it has one
+    pub blob: Option<&'a [u8]>,
+}
+
+impl<'a> Record<'a> {
+    /// Interpret the operands starting at `start` as the characters of a
+    /// string
+    pub fn string_from(&self, start: usize) -> Vec<u8> {
+        self.ops.iter().skip(start).map(|&c| c as u8).collect()
+    }
+}
+
+/// An entry in a block of a bitstream
+#[derive(Clone, Debug)]
+pub(crate) enum Entry<'a> {
+    /// The start of a nested block, which must next be either entered with
+    /// `enter_block()` or skipped with `skip_block()`
+    SubBlock(SubBlock),
+    Record(Record<'a>),
+    /// The end of the current block
+    EndBlock,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct SubBlock {
+    pub id: u64,
+    abbrev_width: u32,
+    /// Position (in bits) of the end of the block
+    end: usize,
+}
+
+/// The state of a block being read
+struct Scope {
+    abbrev_width: u32,
+    abbrevs: Vec<Abbrev>,
+}
+
+/// Reads the blocks and records of a bitstream, in order
+pub(crate) struct BitstreamReader<'a> {
+    bits: BitReader<'a>,
+    /// Abbreviations defined in the `BLOCKINFO` block, by block ID
+    blockinfo: HashMap<u64, Vec<Abbrev>>,
+    /// The blocks currently being read, innermost last. The first `Scope` is
+    /// the top level of the stream.
+    scopes: Vec<Scope>,
+}
+
+impl<'a> BitstreamReader<'a> {
+    /// Start reading the given LLVM bitcode, which may be wrapped in a
+    /// bitcode wrapper header
+    pub fn new(bitcode: &'a [u8]) -> Result<Self, Error> {
+        let bitcode = strip_wrapper(bitcode)?;
+        if !bitcode.starts_with(MAGIC) {
+            return Err(Error::InvalidBitcode);
+        }
+        Ok(Self {
+            bits: BitReader {
+                data: bitcode,
+                pos: 32,
+            },
+            blockinfo: HashMap::new(),
+            scopes: vec![Scope {
+                abbrev_width: 2,
+                abbrevs: vec![],
+            }],
+        })
+    }
+
+    /// Read the next entry of the current block. `BLOCKINFO` blocks and
+    /// abbreviation definitions are handled internally. Returns `None` at the
+    /// end of the stream.
+    pub fn next(&mut self) -> Result<Option<Entry<'a>>, Error> {
+        loop {
+            if self.scopes.len() == 1 && self.bits.at_end() {
+                return Ok(None);
+            }
+            let scope = self.scopes.last().expect("should always have a scope");
+            match self.bits.read(scope.abbrev_width)? {
+                END_BLOCK => {
+                    if self.scopes.len() == 1 {
+                        return Err(Error::InvalidBitcode);
+                    }
+                    self.bits.align32();
+                    self.scopes.pop();
+                    return Ok(Some(Entry::EndBlock));
+                },
+                ENTER_SUBBLOCK => {
+                    let id = self.bits.read_vbr(8)?;
+                    let abbrev_width =
+                        u32::try_from(self.bits.read_vbr(4)?).map_err(|_| Error::InvalidBitcode)?;
+                    self.bits.align32();
+                    let len_words = self.bits.read(32)? as usize;
+                    let block = SubBlock {
+                        id,
+                        abbrev_width,
+                        end: self.bits.pos + len_words * 32,
+                    };
+                    if id == BLOCKINFO_BLOCK_ID {
+                        self.read_blockinfo(block)?;
+                    } else {
+                        return Ok(Some(Entry::SubBlock(block)));
+                    }
+                },
+                DEFINE_ABBREV => {
+                    let abbrev = self.read_abbrev()?;
+                    self.scopes
+                        .last_mut()
+                        .expect("should always have a scope")
+                        .abbrevs
+                        .push(abbrev);
+                },
+                UNABBREV_RECORD => {
+                    let code = self.bits.read_vbr(6)?;
+                    let num_ops = self.bits.read_vbr(6)?;
+                    let ops = (0 .. num_ops)
+                        .map(|_| self.bits.read_vbr(6))
+                        .collect::<Result<_, _>>()?;
+                    return Ok(Some(Entry::Record(Record {
+                        code,
+                        ops,
+                        blob: None,
+                    })));
+                },
+                id => {
+                    let abbrev = scope
+                        .abbrevs
+                        .get((id - 4) as usize)
+                        .ok_or(Error::InvalidBitcode)?
+                        .clone();
+                    return self
+                        .read_abbreviated_record(&abbrev)
+                        .map(|r| Some(Entry::Record(r)));
+                },
+            }
+        }
+    }
+
+    /// Enter the given block, which must have just been returned by `next()`
+    pub fn enter_block(&mut self, block: &SubBlock) {
+        self.scopes.push(Scope {
+            abbrev_width: block.abbrev_width,
+            abbrevs: self.blockinfo.get(&block.id).cloned().unwrap_or_default(),
+        });
+    }
+
+    /// Skip the given block, which must have just been returned by `next()`
+    pub fn skip_block(&mut self, block: &SubBlock) {
+        self.bits.pos = block.end;
+    }
+
+    fn read_blockinfo(&mut self, block: SubBlock) -> Result<(), Error> {
+        self.enter_block(&block);
+        let mut current_id = None;
+        loop {
+            let scope = self.scopes.last().expect("should always have a scope");
+            match self.bits.read(scope.abbrev_width)? {
+                END_BLOCK => {
+                    self.bits.align32();
+                    self.scopes.pop();
+                    return Ok(());
+                },
+                ENTER_SUBBLOCK => return Err(Error::InvalidBitcode),
+                DEFINE_ABBREV => {
+                    let abbrev = self.read_abbrev()?;
+                    let id = current_id.ok_or(Error::InvalidBitcode)?;
+                    self.blockinfo.entry(id).or_default().push(abbrev);
+                },
+                UNABBREV_RECORD => {
+                    let code = self.bits.read_vbr(6)?;
+                    let num_ops = self.bits.read_vbr(6)?;
+                    let ops: Vec<u64> = (0 .. num_ops)
+                        .map(|_| self.bits.read_vbr(6))
+                        .collect::<Result<_, _>>()?;
+                    if code == BLOCKINFO_CODE_SETBID {
+                        current_id = Some(*ops.first().ok_or(Error::InvalidBitcode)?);
+                    }
+                },
+                // records in the BLOCKINFO block (block and record names) aren't abbreviated
+                _ => return Err(Error::InvalidBitcode),
+            }
+        }
+    }
+
+    fn read_abbrev(&mut self) -> Result<Abbrev, Error> {
+        let num_ops = self.bits.read_vbr(5)?;
+        let mut ops = vec![];
+        for _ in 0 .. num_ops {
+            let is_literal = self.bits.read(1)? == 1;
+            let op = if is_literal {
+                AbbrevOp::Literal(self.bits.read_vbr(8)?)
+            } else {
+                match self.bits.read(3)? {
+                    1 => AbbrevOp::Fixed(self.read_width()?),
+                    2 => AbbrevOp::Vbr(self.read_width()?),
+                    3 => AbbrevOp::Array,
+                    4 => AbbrevOp::Char6,
+                    5 => AbbrevOp::Blob,
+                    _ => return Err(Error::InvalidBitcode),
+                }
+            };
+            ops.push(op);
+        }
+        Ok(Rc::new(ops))
+    }
+
+    fn read_width(&mut self) -> Result<u32, Error> {
+        match self.bits.read_vbr(5)? {
+            width if width <= 64 => Ok(width as u32),
+            _ => Err(Error::InvalidBitcode),
+        }
+    }
+
+    fn read_abbreviated_record(&mut self, abbrev: &[AbbrevOp]) -> Result<Record<'a>, Error> {
+        let mut values = vec![];
+        let mut blob = None;
+        let mut ops = abbrev.iter();
+        while let Some(op) = ops.next() {
+            match op {
+                AbbrevOp::Array => {
+                    let len = self.bits.read_vbr(6)?;
+                    let element = ops.next().ok_or(Error::InvalidBitcode)?;
+                    for _ in 0 .. len {
+                        values.push(self.read_scalar(element)?);
+                    }
+                },
+                AbbrevOp::Blob => {
+                    let len = self.bits.read_vbr(6)? as usize;
+                    self.bits.align32();
+                    blob = Some(self.bits.read_bytes(len)?);
+                    self.bits.align32();
+                },
+                op => values.push(self.read_scalar(op)?),
+            }
+        }
+        if values.is_empty() {
+            return Err(Error::InvalidBitcode);
+        }
+        let code = values.remove(0);
+        Ok(Record {
+            code,
+            ops: values,
+            blob,
+        })
+    }
+
+    fn read_scalar(&mut self, op: &AbbrevOp) -> Result<u64, Error> {
+        match op {
+            AbbrevOp::Literal(value) => Ok(*value),
+            AbbrevOp::Fixed(width) => self.bits.read(*width),
+            AbbrevOp::Vbr(width) => self.bits.read_vbr(*width),
+            AbbrevOp::Char6 => Ok(u64::from(decode_char6(self.bits.read(6)?))),
+            AbbrevOp::Array | AbbrevOp::Blob => Err(Error::InvalidBitcode),
+        }
+    }
+}
+
+fn decode_char6(value: u64) -> u8 {
+    b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789._"[value as usize & 63]
+}
+
+/// The magic number at the start of the bitcode wrapper header
+const WRAPPER_MAGIC: u32 = 0x0B17_C0DE;
+
+/// If the given bitcode starts with a bitcode wrapper header, get the bitcode
+/// it wraps; otherwise, return it unchanged.
+/// See [LLVM 14 docs on the Bitcode Wrapper Format](https://releases.llvm.org/14.0.0/docs/BitCodeFormat.html#bitcode-wrapper-format)
+pub(crate) fn strip_wrapper(bitcode: &[u8]) -> Result<&[u8], Error> {
+    match wrapped_range(bitcode)? {
+        Some(range) => Ok(&bitcode[range]),
+        None => Ok(bitcode),
+    }
+}
+
+/// If the given bitcode starts with a bitcode wrapper header, get the range of
+/// the bitcode it wraps
+fn wrapped_range(bitcode: &[u8]) -> Result<Option<Range<usize>>, Error> {
+    let field = |index: usize| -> Option<usize> {
+        let bytes = bitcode.get(index * 4 .. index * 4 + 4)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    };
+    if field(0) != Some(WRAPPER_MAGIC as usize) {
+        return Ok(None);
+    }
+    let offset = field(2).ok_or(Error::InvalidBitcode)?;
+    let size = field(3).ok_or(Error::InvalidBitcode)?;
+    let end = offset.checked_add(size).ok_or(Error::InvalidBitcode)?;
+    if end > bitcode.len() {
+        return Err(Error::InvalidBitcode);
+    }
+    Ok(Some(offset .. end))
+}
+
+/// Is the given data LLVM bitcode, possibly wrapped in a bitcode wrapper
+/// header?
+pub(crate) fn is_bitcode(data: &[u8]) -> bool {
+    matches!(strip_wrapper(data), Ok(bitcode) if bitcode.starts_with(MAGIC))
+}
+
+/// Split data containing several bitcode files one after another (as the
+/// embedded-bitcode section of an object file linked with `ld -r` does) into
+/// the individual files, each possibly wrapped in a bitcode wrapper header.
+/// Zero padding between the files is skipped.
+#[cfg(feature = "embedded-bitcode")]
+pub(crate) fn split_bitcode_files(data: &[u8]) -> Result<Vec<&[u8]>, Error> {
+    let mut files = vec![];
+    let mut start = 0;
+    loop {
+        while data.get(start .. start + 4) == Some(&[0; 4][..]) {
+            start += 4;
+        }
+        let rest = &data[start ..];
+        if rest.iter().all(|&b| b == 0) {
+            return Ok(files);
+        }
+        let len = bitcode_file_len(rest)?;
+        files.push(&rest[.. len]);
+        start += len;
+    }
+}
+
+/// The length, in bytes, of the bitcode file at the start of `data`, which
+/// may be followed by other data
+#[cfg(feature = "embedded-bitcode")]
+fn bitcode_file_len(data: &[u8]) -> Result<usize, Error> {
+    if let Some(range) = wrapped_range(data)? {
+        return Ok(range.end);
+    }
+    if !data.starts_with(MAGIC) {
+        return Err(Error::InvalidBitcode);
+    }
+    let mut bits = BitReader { data, pos: 32 };
+    loop {
+        // every entry at the top level is a block, and each block ends on a
+        // 32-bit boundary; the file ends where the next word isn't the start
+        // of a block
+        let pos = bits.pos / 8;
+        let word = match data.get(pos .. pos + 4) {
+            Some(word) => word,
+            None if pos == data.len() => return Ok(pos),
+            None => return Err(Error::InvalidBitcode),
+        };
+        if word == MAGIC || word == WRAPPER_MAGIC.to_le_bytes() || word == [0; 4] {
+            return Ok(pos);
+        }
+        if bits.read(2)? != ENTER_SUBBLOCK {
+            return Err(Error::InvalidBitcode);
+        }
+        let _id = bits.read_vbr(8)?;
+        let _abbrev_width = bits.read_vbr(4)?;
+        bits.align32();
+        let len_words = bits.read(32)? as usize;
+        bits.pos += len_words * 32;
+    }
+}
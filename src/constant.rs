@@ -1,14 +1,17 @@
+use crate::module::AddrSpace;
 use crate::name::Name;
 use crate::predicates::*;
 use crate::types::{FPType, NamedStructDef, Type, TypeRef, Typed, Types};
 use std::convert::TryFrom;
 use std::fmt::{self, Display};
+use std::hash::{Hash, Hasher};
 use std::ops::Deref;
 use std::sync::Arc;
 
 /// See [LLVM 14 docs on Constants](https://releases.llvm.org/14.0.0/docs/LangRef.html#constants).
 /// Constants can be either values, or expressions involving other constants (see [LLVM 14 docs on Constant Expressions](https://releases.llvm.org/14.0.0/docs/LangRef.html#constant-expressions)).
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Constant {
     Int {
         /// Number of bits in the constant integer
@@ -20,16 +23,17 @@ pub enum Constant {
         /// If `bits < 64`, the constant value is zero-extended to fit in this
         /// field.
         ///
-        /// If `bits > 64`, the constant value is truncated to fit in this field;
-        /// but if this truncation would change the value (i.e., if the value is
-        /// >= 2^64 when interpreted as unsigned) then `Module::from_bc_path()`
-        /// will fail. See [#5](https://github.com/cdisselkoen/llvm-ir/issues/5).
+        /// `bits` is never greater than 64: wider integer constants are
+        /// represented with [`Constant::BigInt`](enum.Constant.html#variant.BigInt)
+        /// instead. See [#5](https://github.com/cdisselkoen/llvm-ir/issues/5).
         //
         // Note that LLVM integers aren't signed or unsigned; each individual
         // instruction indicates whether it's treating the integer as signed or
         // unsigned if necessary (e.g., UDiv vs SDiv).
         value: u64,
     },
+    /// An integer constant with more than 64 bits
+    BigInt(BigIntValue),
     Float(Float),
     /// The `TypeRef` here must be to a `PointerType`. See [LLVM 14 docs on Simple Constants](https://releases.llvm.org/14.0.0/docs/LangRef.html#simple-constants)
     Null(TypeRef),
@@ -45,17 +49,45 @@ pub enum Constant {
         elements: Vec<ConstantRef>,
     },
     Vector(Vec<ConstantRef>),
+    /// A `ConstantDataArray`: an array of simple integer or floating-point
+    /// elements, kept in LLVM's compact raw form rather than as one
+    /// `ConstantRef` per element. See [`Constant::raw_data_element()`](enum.Constant.html#method.raw_data_element)
+    /// and [`Constant::raw_data_elements()`](enum.Constant.html#method.raw_data_elements)
+    /// for expanding the elements on demand.
+    RawDataArray {
+        element_type: TypeRef,
+        /// The elements, each occupying the number of bytes given by
+        /// [`Constant::raw_data_element_size()`](enum.Constant.html#method.raw_data_element_size),
+        /// in little-endian byte order.
+        data: Vec<u8>,
+    },
+    /// A `ConstantDataVector`: like `RawDataArray`, but of vector type.
+    RawDataVector {
+        element_type: TypeRef,
+        /// Same layout as for `RawDataArray`.
+        data: Vec<u8>,
+    },
     /// `Undef` can be used anywhere a constant is expected. See [LLVM 14 docs on Undefined Values](https://releases.llvm.org/14.0.0/docs/LangRef.html#undefined-values)
     Undef(TypeRef),
     /// See [LLVM 14 docs on Poison Values](https://releases.llvm.org/14.0.0/docs/LangRef.html#undefined-values)
     #[cfg(feature = "llvm-12-or-greater")]
     Poison(TypeRef),
-    /// The address of the given (non-entry) [`BasicBlock`](../struct.BasicBlock.html). See [LLVM 14 docs on Addresses of Basic Blocks](https://releases.llvm.org/14.0.0/docs/LangRef.html#addresses-of-basic-blocks).
-    /// `BlockAddress` needs more fields, but the necessary getter functions are apparently not exposed in the LLVM C API (only the C++ API)
-    BlockAddress, // --TODO ideally we want BlockAddress { function: Name, block: Name },
+    /// The address of the given (non-entry) [`BasicBlock`](../struct.BasicBlock.html) of the given function. See [LLVM 14 docs on Addresses of Basic Blocks](https://releases.llvm.org/14.0.0/docs/LangRef.html#addresses-of-basic-blocks).
+    /// See also [`BlockAddresses`](../analysis/struct.BlockAddresses.html).
+    BlockAddress {
+        function: Name,
+        block: Name,
+    },
+    /// A reference to a global value: a function, global variable, alias,
+    /// or ifunc. `ty` is the type of the global value itself (its "value
+    /// type"), not the pointer type of the reference.
     GlobalReference {
         name: Name,
         ty: TypeRef,
+        /// What kind of global value `name` refers to
+        kind: GlobalKind,
+        /// The address space the global value is in
+        addr_space: AddrSpace,
     },
     TokenNone,
 
@@ -84,6 +116,7 @@ pub enum Constant {
     FMul(FMul),
     FDiv(FDiv),
     FRem(FRem),
+    FNeg(FNeg),
 
     // Vector ops
     ExtractElement(ExtractElement),
@@ -116,13 +149,27 @@ pub enum Constant {
     ICmp(ICmp),
     FCmp(FCmp),
     Select(Select),
+
+    /// A constant expression with an opcode this crate doesn't otherwise
+    /// handle, e.g., one introduced by a newer version of LLVM. `opcode` is
+    /// the value of the LLVM C API's `LLVMOpcode` for it.
+    OtherExpr {
+        opcode: u32,
+        operands: Vec<ConstantRef>,
+        ty: TypeRef,
+    },
 }
 
 /// All of these `Float` variants should have data associated with them, but
 /// Rust only has `f32` and `f64` floating-point types, and furthermore,
 /// it's not clear how to get 16-, 80-, or 128-bit FP constant values through
 /// the LLVM C API (the getters seem to only be exposed in the C++ API?)
-#[derive(PartialEq, Clone, Debug)]
+///
+/// `Float`s are compared and hashed by their bit patterns, as LLVM does for
+/// constants: so `NaN` equals itself (if the payloads are the same), but
+/// `0.0` and `-0.0` are different constants.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(non_camel_case_types)]
 pub enum Float {
     Half, // TODO perhaps Half(u16)
@@ -135,6 +182,32 @@ pub enum Float {
     PPC_FP128, // TODO perhaps PPC_FP128((u64, u64)) with the most-significant bits on the left
 }
 
+impl Float {
+    /// The bit pattern of the value, for those variants that carry one
+    fn bits(&self) -> Option<u64> {
+        match self {
+            Float::Single(f) => Some(u64::from(f.to_bits())),
+            Float::Double(d) => Some(d.to_bits()),
+            _ => None,
+        }
+    }
+}
+
+impl PartialEq for Float {
+    fn eq(&self, other: &Self) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other) && self.bits() == other.bits()
+    }
+}
+
+impl Eq for Float {}
+
+impl Hash for Float {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        self.bits().hash(state);
+    }
+}
+
 impl Typed for Float {
     fn get_type(&self, types: &Types) -> TypeRef {
         types.fp(match self {
@@ -165,11 +238,141 @@ impl Display for Float {
     }
 }
 
+/// The kind of global value referred to by a
+/// [`Constant::GlobalReference`](enum.Constant.html#variant.GlobalReference)
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GlobalKind {
+    /// A [`Function`](../function/struct.Function.html), either defined or
+    /// declared in the module
+    Function,
+    /// A [`GlobalVariable`](../module/struct.GlobalVariable.html)
+    GlobalVariable,
+    /// A [`GlobalAlias`](../module/struct.GlobalAlias.html)
+    GlobalAlias,
+    /// An `ifunc`. See [LLVM 14 docs on IFuncs](https://releases.llvm.org/14.0.0/docs/LangRef.html#ifuncs)
+    GlobalIFunc,
+}
+
+/// An integer value of arbitrary bitwidth, stored as little-endian 64-bit
+/// words.
+///
+/// Like `Constant::Int`, this doesn't say whether the value is signed or
+/// unsigned; see the comment there.
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BigIntValue {
+    bits: u32,
+    /// Always exactly `bits.div_ceil(64)` words, with any bits in the last
+    /// word beyond `bits` cleared
+    words: Vec<u64>,
+}
+
+impl BigIntValue {
+    /// Construct a value with the given number of bits from little-endian
+    /// 64-bit words. Missing words are taken to be zero, and bits beyond
+    /// `bits` are discarded.
+    pub fn from_words(bits: u32, mut words: Vec<u64>) -> Self {
+        let num_words = (bits as usize).div_ceil(64);
+        words.resize(num_words, 0);
+        if !bits.is_multiple_of(64) {
+            words[num_words - 1] &= (1 << (bits % 64)) - 1;
+        }
+        Self { bits, words }
+    }
+
+    /// Construct a value with the given number of bits from a `u64`
+    pub fn from_u64(bits: u32, value: u64) -> Self {
+        Self::from_words(bits, vec![value])
+    }
+
+    /// Number of bits in the value
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    /// The value as little-endian 64-bit words
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Get the value as a `u64`, or `None` if it doesn't fit (interpreting the
+    /// value as unsigned)
+    pub fn to_u64(&self) -> Option<u64> {
+        match self.words.split_first() {
+            None => Some(0),
+            Some((low, high)) if high.iter().all(|&w| w == 0) => Some(*low),
+            Some(_) => None,
+        }
+    }
+
+    /// Whether the value is negative, interpreting it as signed
+    fn is_negative(&self) -> bool {
+        match self.words.last() {
+            None => false,
+            Some(high) => (high >> ((self.bits - 1) % 64)) & 1 == 1,
+        }
+    }
+
+    /// The value in decimal, interpreted as unsigned. (`Display` interprets
+    /// it as signed.)
+    pub fn to_unsigned_string(&self) -> String {
+        // repeatedly divide by 10^19 (the largest power of 10 fitting in a
+        // u64), collecting the remainders as decimal "digits"
+        const TEN_19: u64 = 10_000_000_000_000_000_000;
+        let mut words = self.words.clone();
+        let mut chunks = vec![];
+        loop {
+            let mut rem: u128 = 0;
+            for word in words.iter_mut().rev() {
+                let cur = (rem << 64) | u128::from(*word);
+                *word = (cur / u128::from(TEN_19)) as u64;
+                rem = cur % u128::from(TEN_19);
+            }
+            chunks.push(rem as u64);
+            if words.iter().all(|&w| w == 0) {
+                break;
+            }
+        }
+        let mut chunks = chunks.iter().rev();
+        let mut string = chunks.next().unwrap().to_string();
+        for chunk in chunks {
+            string.push_str(&format!("{:019}", chunk));
+        }
+        string
+    }
+}
+
+impl Display for BigIntValue {
+    /// Displays the value in decimal, interpreted as signed (as LLVM does).
+    /// See also [`to_unsigned_string()`](#method.to_unsigned_string).
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_negative() {
+            // two's complement: invert and add one
+            let mut carry = true;
+            let magnitude = self
+                .words
+                .iter()
+                .map(|&word| {
+                    let (word, overflowed) = (!word).overflowing_add(u64::from(carry));
+                    carry = overflowed;
+                    word
+                })
+                .collect();
+            let magnitude = Self::from_words(self.bits, magnitude);
+            write!(f, "-{}", magnitude.to_unsigned_string())
+        } else {
+            write!(f, "{}", self.to_unsigned_string())
+        }
+    }
+}
+
 impl Typed for Constant {
     #[rustfmt::skip] // to keep all the branches more consistent with each other
     fn get_type(&self, types: &Types) -> TypeRef {
         match self {
             Constant::Int { bits, .. } => types.int(*bits),
+            Constant::BigInt(value) => types.int(value.bits()),
             Constant::Float(f) => types.type_of(f),
             Constant::Null(t) => t.clone(),
             Constant::AggregateZero(t) => t.clone(),
@@ -192,11 +395,26 @@ impl Typed for Constant {
                 types.type_of(&v[0]),
                 v.len(),
             ),
+            Constant::RawDataArray { element_type, .. } => types.array_of(
+                element_type.clone(),
+                self.raw_data_num_elements().unwrap(),
+            ),
+            #[cfg(feature="llvm-11-or-greater")]
+            Constant::RawDataVector { element_type, .. } => types.vector_of(
+                element_type.clone(),
+                self.raw_data_num_elements().unwrap(),
+                false,
+            ),
+            #[cfg(feature="llvm-10-or-lower")]
+            Constant::RawDataVector { element_type, .. } => types.vector_of(
+                element_type.clone(),
+                self.raw_data_num_elements().unwrap(),
+            ),
             Constant::Undef(t) => t.clone(),
             #[cfg(feature="llvm-12-or-greater")]
             Constant::Poison(t) => t.clone(),
             Constant::BlockAddress { .. } => types.label_type(),
-            Constant::GlobalReference { ty, .. } => types.pointer_to(ty.clone()),
+            Constant::GlobalReference { ty, addr_space, .. } => types.pointer_for(ty.clone(), *addr_space),
             Constant::TokenNone => types.token_type(),
             Constant::Add(a) => types.type_of(a),
             Constant::Sub(s) => types.type_of(s),
@@ -216,6 +434,7 @@ impl Typed for Constant {
             Constant::FMul(f) => types.type_of(f),
             Constant::FDiv(f) => types.type_of(f),
             Constant::FRem(f) => types.type_of(f),
+            Constant::FNeg(f) => types.type_of(f),
             Constant::ExtractElement(e) => types.type_of(e),
             Constant::InsertElement(i) => types.type_of(i),
             Constant::ShuffleVector(s) => types.type_of(s),
@@ -238,51 +457,77 @@ impl Typed for Constant {
             Constant::ICmp(i) => types.type_of(i),
             Constant::FCmp(f) => types.type_of(f),
             Constant::Select(s) => types.type_of(s),
+            Constant::OtherExpr { ty, .. } => ty.clone(),
         }
     }
 }
 
-impl Display for Constant {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+impl Constant {
+    /// Write the constant without its type, as LLVM does where the type is
+    /// implied by the context (e.g., in the second operand of an `add`).
+    /// Constants which don't have a separate type and value (e.g.,
+    /// aggregates and constant expressions) are written as by `Display`.
+    pub(crate) fn fmt_untyped(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Constant::Int { bits, value } => {
-                if *bits == 1 {
-                    if *value == 0 {
-                        write!(f, "i1 false")
-                    } else {
-                        write!(f, "i1 true")
-                    }
+            Constant::Int { bits: 1, value } => {
+                if *value == 0 {
+                    write!(f, "false")
                 } else {
-                    // for readability, use heuristic to decide whether to show as negative number or not
-                    match *bits {
-                        16 => {
-                            let signed_val = (*value & 0xFFFF) as i16;
-                            if signed_val > -1000 {
-                                write!(f, "i{} {}", bits, signed_val)
-                            } else {
-                                write!(f, "i{} {}", bits, *value)
-                            }
-                        },
-                        32 => {
-                            let signed_val = (*value & 0xFFFF_FFFF) as i32;
-                            if signed_val > -1000 {
-                                write!(f, "i{} {}", bits, signed_val)
-                            } else {
-                                write!(f, "i{} {}", bits, *value)
-                            }
-                        },
-                        64 => {
-                            let signed_val = *value as i64;
-                            if signed_val > -1000 {
-                                write!(f, "i{} {}", bits, signed_val)
-                            } else {
-                                write!(f, "i{} {}", bits, *value)
-                            }
-                        },
-                        _ => write!(f, "i{} {}", bits, value),
-                    }
+                    write!(f, "true")
                 }
             },
+            Constant::Int { bits, value } => {
+                // for readability, use heuristic to decide whether to show as negative number or not
+                match *bits {
+                    16 => {
+                        let signed_val = (*value & 0xFFFF) as i16;
+                        if signed_val > -1000 {
+                            write!(f, "{}", signed_val)
+                        } else {
+                            write!(f, "{}", *value)
+                        }
+                    },
+                    32 => {
+                        let signed_val = (*value & 0xFFFF_FFFF) as i32;
+                        if signed_val > -1000 {
+                            write!(f, "{}", signed_val)
+                        } else {
+                            write!(f, "{}", *value)
+                        }
+                    },
+                    64 => {
+                        let signed_val = *value as i64;
+                        if signed_val > -1000 {
+                            write!(f, "{}", signed_val)
+                        } else {
+                            write!(f, "{}", *value)
+                        }
+                    },
+                    _ => write!(f, "{}", value),
+                }
+            },
+            Constant::BigInt(value) => write!(f, "{}", value),
+            Constant::Float(Float::Single(s)) => write!(f, "{}", s),
+            Constant::Float(Float::Double(d)) => write!(f, "{}", d),
+            Constant::Null(_) => write!(f, "null"),
+            Constant::AggregateZero(_) => write!(f, "zeroinitializer"),
+            Constant::Undef(_) => write!(f, "undef"),
+            #[cfg(feature = "llvm-12-or-greater")]
+            Constant::Poison(_) => write!(f, "poison"),
+            Constant::GlobalReference { name, .. } => name.fmt_with_sigil(f, '@'),
+            _ => write!(f, "{}", self),
+        }
+    }
+}
+
+impl Display for Constant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Constant::Int { bits, .. } => {
+                write!(f, "i{} ", bits)?;
+                self.fmt_untyped(f)
+            },
+            Constant::BigInt(value) => write!(f, "i{} {}", value.bits(), value),
             Constant::Float(float) => write!(f, "{}", float),
             Constant::Null(ty) => write!(f, "{} null", ty),
             Constant::AggregateZero(ty) => write!(f, "{} zeroinitializer", ty),
@@ -330,25 +575,73 @@ impl Display for Constant {
                 write!(f, " >")?;
                 Ok(())
             },
+            Constant::RawDataArray { element_type, data } => {
+                if let Type::IntegerType { bits: 8 } = element_type.as_ref() {
+                    // like LLVM, display arrays of bytes as a string
+                    write!(f, "c\"")?;
+                    for byte in data {
+                        if (byte.is_ascii_graphic() || *byte == b' ')
+                            && *byte != b'"'
+                            && *byte != b'\\'
+                        {
+                            write!(f, "{}", *byte as char)?;
+                        } else {
+                            write!(f, "\\{:02X}", byte)?;
+                        }
+                    }
+                    write!(f, "\"")
+                } else {
+                    write!(f, "[ ")?;
+                    let num_elements = self.raw_data_num_elements().unwrap();
+                    for (i, elt) in self.raw_data_elements().enumerate() {
+                        if i == num_elements - 1 {
+                            write!(f, "{}", elt)?;
+                        } else {
+                            write!(f, "{}, ", elt)?;
+                        }
+                    }
+                    write!(f, " ]")
+                }
+            },
+            Constant::RawDataVector { .. } => {
+                write!(f, "< ")?;
+                let num_elements = self.raw_data_num_elements().unwrap();
+                for (i, elt) in self.raw_data_elements().enumerate() {
+                    if i == num_elements - 1 {
+                        write!(f, "{}", elt)?;
+                    } else {
+                        write!(f, "{}, ", elt)?;
+                    }
+                }
+                write!(f, " >")
+            },
             Constant::Undef(ty) => write!(f, "{} undef", ty),
             #[cfg(feature = "llvm-12-or-greater")]
             Constant::Poison(ty) => write!(f, "{} poison", ty),
-            Constant::BlockAddress => write!(f, "blockaddr"),
-            Constant::GlobalReference { name, ty } => {
-                let name = match name {
-                    Name::Name(n) => n,
-                    _ => panic!("Expected global to be named, not numbered"),
-                };
+            Constant::BlockAddress { function, block } => {
+                write!(f, "blockaddress(")?;
+                function.fmt_with_sigil(f, '@')?;
+                write!(f, ", {})", block)
+            },
+            Constant::GlobalReference {
+                name,
+                ty,
+                addr_space,
+                ..
+            } => {
                 match ty.as_ref() {
                     Type::FuncType { .. } => {
                         // function types: just write the name, not the type
-                        write!(f, "@{}", name)
+                    },
+                    _ if *addr_space != 0 => {
+                        write!(f, "{} addrspace({})* ", ty, addr_space)?;
                     },
                     _ => {
                         // non-function types: typical style with the type and name
-                        write!(f, "{}* @{}", ty, name)
+                        write!(f, "{}* ", ty)?;
                     },
                 }
+                name.fmt_with_sigil(f, '@')
             },
             Constant::TokenNone => write!(f, "none"),
             Constant::Add(a) => write!(f, "{}", a),
@@ -369,6 +662,7 @@ impl Display for Constant {
             Constant::FMul(m) => write!(f, "{}", m),
             Constant::FDiv(d) => write!(f, "{}", d),
             Constant::FRem(r) => write!(f, "{}", r),
+            Constant::FNeg(n) => write!(f, "{}", n),
             Constant::ExtractElement(e) => write!(f, "{}", e),
             Constant::InsertElement(i) => write!(f, "{}", i),
             Constant::ShuffleVector(s) => write!(f, "{}", s),
@@ -391,8 +685,181 @@ impl Display for Constant {
             Constant::ICmp(i) => write!(f, "{}", i),
             Constant::FCmp(c) => write!(f, "{}", c),
             Constant::Select(s) => write!(f, "{}", s),
+            Constant::OtherExpr {
+                opcode, operands, ..
+            } => {
+                write!(f, "<constexpr with opcode {}> (", opcode)?;
+                for (i, op) in operands.iter().enumerate() {
+                    if i == operands.len() - 1 {
+                        write!(f, "{}", op)?;
+                    } else {
+                        write!(f, "{}, ", op)?;
+                    }
+                }
+                write!(f, ")")
+            },
+        }
+    }
+}
+
+impl Constant {
+    /// For a `Constant::Int` or `Constant::BigInt`, get its value as a
+    /// `BigIntValue`. Returns `None` for any other kind of `Constant`.
+    pub fn int_value(&self) -> Option<BigIntValue> {
+        match self {
+            Constant::Int { bits, value } => Some(BigIntValue::from_u64(*bits, *value)),
+            Constant::BigInt(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    /// Get the `ConstantRef`s directly contained in this `Constant`: the
+    /// elements of an aggregate, or the operands of a constant expression.
+    pub(crate) fn sub_constants(&self) -> Vec<&ConstantRef> {
+        match self {
+            Constant::Struct { values, .. } => values.iter().collect(),
+            Constant::Array { elements, .. } => elements.iter().collect(),
+            Constant::Vector(elements) => elements.iter().collect(),
+            Constant::Add(a) => vec![&a.operand0, &a.operand1],
+            Constant::Sub(s) => vec![&s.operand0, &s.operand1],
+            Constant::Mul(m) => vec![&m.operand0, &m.operand1],
+            Constant::UDiv(d) => vec![&d.operand0, &d.operand1],
+            Constant::SDiv(d) => vec![&d.operand0, &d.operand1],
+            Constant::URem(r) => vec![&r.operand0, &r.operand1],
+            Constant::SRem(r) => vec![&r.operand0, &r.operand1],
+            Constant::And(a) => vec![&a.operand0, &a.operand1],
+            Constant::Or(o) => vec![&o.operand0, &o.operand1],
+            Constant::Xor(x) => vec![&x.operand0, &x.operand1],
+            Constant::Shl(s) => vec![&s.operand0, &s.operand1],
+            Constant::LShr(l) => vec![&l.operand0, &l.operand1],
+            Constant::AShr(a) => vec![&a.operand0, &a.operand1],
+            Constant::FAdd(f) => vec![&f.operand0, &f.operand1],
+            Constant::FSub(f) => vec![&f.operand0, &f.operand1],
+            Constant::FMul(f) => vec![&f.operand0, &f.operand1],
+            Constant::FDiv(f) => vec![&f.operand0, &f.operand1],
+            Constant::FRem(f) => vec![&f.operand0, &f.operand1],
+            Constant::FNeg(f) => vec![&f.operand],
+            Constant::ExtractElement(e) => vec![&e.vector, &e.index],
+            Constant::InsertElement(i) => vec![&i.vector, &i.element, &i.index],
+            Constant::ShuffleVector(s) => vec![&s.operand0, &s.operand1, &s.mask],
+            Constant::ExtractValue(e) => vec![&e.aggregate],
+            Constant::InsertValue(i) => vec![&i.aggregate, &i.element],
+            Constant::GetElementPtr(g) => std::iter::once(&g.address).chain(&g.indices).collect(),
+            Constant::Trunc(t) => vec![&t.operand],
+            Constant::ZExt(z) => vec![&z.operand],
+            Constant::SExt(s) => vec![&s.operand],
+            Constant::FPTrunc(f) => vec![&f.operand],
+            Constant::FPExt(f) => vec![&f.operand],
+            Constant::FPToUI(f) => vec![&f.operand],
+            Constant::FPToSI(f) => vec![&f.operand],
+            Constant::UIToFP(u) => vec![&u.operand],
+            Constant::SIToFP(s) => vec![&s.operand],
+            Constant::PtrToInt(p) => vec![&p.operand],
+            Constant::IntToPtr(i) => vec![&i.operand],
+            Constant::BitCast(b) => vec![&b.operand],
+            Constant::AddrSpaceCast(a) => vec![&a.operand],
+            Constant::ICmp(i) => vec![&i.operand0, &i.operand1],
+            Constant::FCmp(f) => vec![&f.operand0, &f.operand1],
+            Constant::Select(s) => vec![&s.condition, &s.true_value, &s.false_value],
+            Constant::OtherExpr { operands, .. } => operands.iter().collect(),
+            _ => vec![],
+        }
+    }
+
+    /// The heap memory owned directly by this `Constant` (not counting its
+    /// `sub_constants()`), in bytes
+    pub(crate) fn own_heap_size(&self) -> usize {
+        use std::mem::size_of;
+        match self {
+            Constant::BigInt(value) => value.words.capacity() * size_of::<u64>(),
+            Constant::Struct { name, values, .. } => {
+                name.as_ref().map_or(0, String::capacity)
+                    + values.capacity() * size_of::<ConstantRef>()
+            },
+            Constant::Array { elements, .. } | Constant::Vector(elements) => {
+                elements.capacity() * size_of::<ConstantRef>()
+            },
+            Constant::RawDataArray { data, .. } | Constant::RawDataVector { data, .. } => {
+                data.capacity()
+            },
+            Constant::GlobalReference { name, .. } => name.heap_size(),
+            Constant::BlockAddress { function, block } => function.heap_size() + block.heap_size(),
+            Constant::ExtractValue(e) => e.indices.capacity() * size_of::<u32>(),
+            Constant::InsertValue(i) => i.indices.capacity() * size_of::<u32>(),
+            Constant::GetElementPtr(g) => g.indices.capacity() * size_of::<ConstantRef>(),
+            Constant::OtherExpr { operands, .. } => operands.capacity() * size_of::<ConstantRef>(),
+            _ => 0,
+        }
+    }
+
+    /// For a `RawDataArray` or `RawDataVector`, get the size in bytes of each
+    /// element in its `data`. Returns `None` for any other kind of `Constant`.
+    pub fn raw_data_element_size(&self) -> Option<usize> {
+        match self {
+            Constant::RawDataArray { element_type, .. }
+            | Constant::RawDataVector { element_type, .. } => match element_type.as_ref() {
+                Type::IntegerType { bits } => Some((*bits as usize).div_ceil(8)),
+                Type::FPType(FPType::Half) => Some(2),
+                #[cfg(feature = "llvm-11-or-greater")]
+                Type::FPType(FPType::BFloat) => Some(2),
+                Type::FPType(FPType::Single) => Some(4),
+                Type::FPType(FPType::Double) => Some(8),
+                ty => panic!("Unexpected element type for raw constant data: {:?}", ty),
+            },
+            _ => None,
         }
     }
+
+    /// For a `RawDataArray` or `RawDataVector`, get the number of elements.
+    /// Returns `None` for any other kind of `Constant`.
+    pub fn raw_data_num_elements(&self) -> Option<usize> {
+        match self {
+            Constant::RawDataArray { data, .. } | Constant::RawDataVector { data, .. } => {
+                Some(data.len() / self.raw_data_element_size().unwrap())
+            },
+            _ => None,
+        }
+    }
+
+    /// For a `RawDataArray` or `RawDataVector`, expand the element at the given
+    /// index into an ordinary `Constant::Int` or `Constant::Float`.
+    /// Returns `None` if the index is out of range, or for any other kind of
+    /// `Constant`.
+    pub fn raw_data_element(&self, index: usize) -> Option<Constant> {
+        let (element_type, data) = match self {
+            Constant::RawDataArray { element_type, data }
+            | Constant::RawDataVector { element_type, data } => (element_type, data),
+            _ => return None,
+        };
+        let size = self.raw_data_element_size().unwrap();
+        let bytes = data.get(index * size .. (index + 1) * size)?;
+        let mut buf = [0u8; 8];
+        buf[.. size].copy_from_slice(bytes);
+        let raw = u64::from_le_bytes(buf);
+        Some(match element_type.as_ref() {
+            Type::IntegerType { bits } => Constant::Int {
+                bits: *bits,
+                value: raw,
+            },
+            Type::FPType(FPType::Half) => Constant::Float(Float::Half),
+            #[cfg(feature = "llvm-11-or-greater")]
+            Type::FPType(FPType::BFloat) => Constant::Float(Float::BFloat),
+            Type::FPType(FPType::Single) => {
+                Constant::Float(Float::Single(f32::from_bits(raw as u32)))
+            },
+            Type::FPType(FPType::Double) => Constant::Float(Float::Double(f64::from_bits(raw))),
+            ty => panic!("Unexpected element type for raw constant data: {:?}", ty),
+        })
+    }
+
+    /// For a `RawDataArray` or `RawDataVector`, iterate over all of its
+    /// elements, expanding each one on demand as with
+    /// [`raw_data_element()`](enum.Constant.html#method.raw_data_element).
+    /// For any other kind of `Constant`, the iterator is empty.
+    pub fn raw_data_elements(&self) -> impl Iterator<Item = Constant> + '_ {
+        (0 .. self.raw_data_num_elements().unwrap_or(0))
+            .map(move |i| self.raw_data_element(i).unwrap())
+    }
 }
 
 /// A `ConstantRef` is a reference to a [`Constant`](enum.Constant.html).
@@ -403,7 +870,7 @@ impl Display for Constant {
 // `Arc` is used rather than `Rc` so that `Module` can remain `Sync`.
 // This is important because it allows multiple threads to simultaneously access
 // a single (immutable) `Module`.
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct ConstantRef(Arc<Constant>);
 
 impl AsRef<Constant> for ConstantRef {
@@ -441,6 +908,27 @@ impl ConstantRef {
     pub fn new(c: Constant) -> Self {
         Self(Arc::new(c))
     }
+
+    /// The address of the referenced `Constant`, which identifies it among
+    /// shared `Constant`s
+    pub(crate) fn as_ptr(&self) -> *const Constant {
+        Arc::as_ptr(&self.0)
+    }
+}
+
+// A `ConstantRef` is serialized as the `Constant` it refers to
+#[cfg(feature = "serde")]
+impl serde::Serialize for ConstantRef {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_ref().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ConstantRef {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <Constant as serde::Deserialize>::deserialize(deserializer).map(Self::new)
+    }
 }
 
 pub trait ConstUnaryOp {
@@ -567,7 +1055,8 @@ macro_rules! unop_explicitly_typed {
     };
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Add {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -578,7 +1067,8 @@ pub struct Add {
 impl_constexpr!(Add, Add);
 binop_same_type!(Add, "add");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Sub {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -589,7 +1079,8 @@ pub struct Sub {
 impl_constexpr!(Sub, Sub);
 binop_same_type!(Sub, "sub");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Mul {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -600,7 +1091,8 @@ pub struct Mul {
 impl_constexpr!(Mul, Mul);
 binop_same_type!(Mul, "mul");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UDiv {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -610,7 +1102,8 @@ pub struct UDiv {
 impl_constexpr!(UDiv, UDiv);
 binop_same_type!(UDiv, "udiv");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SDiv {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -620,7 +1113,8 @@ pub struct SDiv {
 impl_constexpr!(SDiv, SDiv);
 binop_same_type!(SDiv, "sdiv");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct URem {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -629,7 +1123,8 @@ pub struct URem {
 impl_constexpr!(URem, URem);
 binop_same_type!(URem, "urem");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SRem {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -638,7 +1133,8 @@ pub struct SRem {
 impl_constexpr!(SRem, SRem);
 binop_same_type!(SRem, "srem");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct And {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -647,7 +1143,8 @@ pub struct And {
 impl_constexpr!(And, And);
 binop_same_type!(And, "and");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Or {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -656,7 +1153,8 @@ pub struct Or {
 impl_constexpr!(Or, Or);
 binop_same_type!(Or, "or");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Xor {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -665,7 +1163,8 @@ pub struct Xor {
 impl_constexpr!(Xor, Xor);
 binop_same_type!(Xor, "xor");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Shl {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -676,7 +1175,8 @@ pub struct Shl {
 impl_constexpr!(Shl, Shl);
 binop_left_type!(Shl, "shl");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LShr {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -686,7 +1186,8 @@ pub struct LShr {
 impl_constexpr!(LShr, LShr);
 binop_left_type!(LShr, "lshr");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AShr {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -696,7 +1197,8 @@ pub struct AShr {
 impl_constexpr!(AShr, AShr);
 binop_left_type!(AShr, "ashr");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FAdd {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -705,7 +1207,8 @@ pub struct FAdd {
 impl_constexpr!(FAdd, FAdd);
 binop_same_type!(FAdd, "fadd");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FSub {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -714,7 +1217,8 @@ pub struct FSub {
 impl_constexpr!(FSub, FSub);
 binop_same_type!(FSub, "fsub");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FMul {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -723,7 +1227,8 @@ pub struct FMul {
 impl_constexpr!(FMul, FMul);
 binop_same_type!(FMul, "fmul");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FDiv {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -732,7 +1237,8 @@ pub struct FDiv {
 impl_constexpr!(FDiv, FDiv);
 binop_same_type!(FDiv, "fdiv");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FRem {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -741,7 +1247,29 @@ pub struct FRem {
 impl_constexpr!(FRem, FRem);
 binop_same_type!(FRem, "frem");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FNeg {
+    pub operand: ConstantRef,
+}
+
+impl_constexpr!(FNeg, FNeg);
+impl_unop!(FNeg);
+
+impl Typed for FNeg {
+    fn get_type(&self, types: &Types) -> TypeRef {
+        types.type_of(&self.operand)
+    }
+}
+
+impl Display for FNeg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "fneg ({})", &self.operand)
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ExtractElement {
     pub vector: ConstantRef,
     pub index: ConstantRef,
@@ -767,7 +1295,8 @@ impl Display for ExtractElement {
     }
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InsertElement {
     pub vector: ConstantRef,
     pub element: ConstantRef,
@@ -792,7 +1321,8 @@ impl Display for InsertElement {
     }
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ShuffleVector {
     pub operand0: ConstantRef,
     pub operand1: ConstantRef,
@@ -841,7 +1371,8 @@ impl Display for ShuffleVector {
     }
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ExtractValue {
     pub aggregate: ConstantRef,
     pub indices: Vec<u32>,
@@ -886,7 +1417,8 @@ impl Display for ExtractValue {
     }
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InsertValue {
     pub aggregate: ConstantRef,
     pub element: ConstantRef,
@@ -912,30 +1444,49 @@ impl Display for InsertValue {
     }
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetElementPtr {
     pub address: ConstantRef,
     pub indices: Vec<ConstantRef>,
     pub in_bounds: bool,
+    /// The type which the first index steps over, and which the remaining
+    /// indices index into. With typed pointers, this is the pointee type of
+    /// `address`; with opaque pointers, it can only be known from here.
+    pub source_element_type: TypeRef,
 }
 
 impl_constexpr!(GetElementPtr, GetElementPtr);
 
 impl Typed for GetElementPtr {
     fn get_type(&self, types: &Types) -> TypeRef {
-        gep_type(types.type_of(&self.address), self.indices.iter(), types)
+        let address_ty = types.type_of(&self.address);
+        let addr_space = address_ty.addr_space().unwrap_or(0);
+        match address_ty.as_ref() {
+            Type::OpaquePointerType { .. } => types.opaque_pointer_in_addr_space(addr_space),
+            _ => {
+                // the first index steps over the `source_element_type` without
+                // changing the type
+                let element_type = gep_type(
+                    self.source_element_type.clone(),
+                    self.indices.iter().skip(1),
+                    types,
+                );
+                types.pointer_in_addr_space(element_type, addr_space)
+            },
+        }
     }
 }
 
+/// The type reached by indexing into `cur_type` with the given `indices`
 fn gep_type<'c>(
     cur_type: TypeRef,
     mut indices: impl Iterator<Item = &'c ConstantRef>,
     types: &Types,
 ) -> TypeRef {
     match indices.next() {
-        None => types.pointer_to(cur_type), // iterator is done
+        None => cur_type, // iterator is done
         Some(index) => match cur_type.as_ref() {
-            Type::PointerType { pointee_type, .. } => gep_type(pointee_type.clone(), indices, types),
             Type::VectorType { element_type, .. } => gep_type(element_type.clone(), indices, types),
             Type::ArrayType { element_type, .. } => gep_type(element_type.clone(), indices, types),
             Type::StructType { element_types, .. } => {
@@ -963,7 +1514,7 @@ fn gep_type<'c>(
                     ty => panic!("Expected NamedStructDef inner type to be a StructType; got {:?}", ty),
                 },
             }
-            _ => panic!("Expected GEP base type to be a PointerType, VectorType, ArrayType, StructType, or NamedStructType; got {:?}", cur_type),
+            _ => panic!("Expected GEP to index into a VectorType, ArrayType, StructType, or NamedStructType; got {:?}", cur_type),
         },
     }
 }
@@ -984,7 +1535,8 @@ impl Display for GetElementPtr {
     }
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Trunc {
     pub operand: ConstantRef,
     pub to_type: TypeRef,
@@ -993,7 +1545,8 @@ pub struct Trunc {
 impl_constexpr!(Trunc, Trunc);
 unop_explicitly_typed!(Trunc, "trunc");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ZExt {
     pub operand: ConstantRef,
     pub to_type: TypeRef,
@@ -1002,7 +1555,8 @@ pub struct ZExt {
 impl_constexpr!(ZExt, ZExt);
 unop_explicitly_typed!(ZExt, "zext");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SExt {
     pub operand: ConstantRef,
     pub to_type: TypeRef,
@@ -1011,7 +1565,8 @@ pub struct SExt {
 impl_constexpr!(SExt, SExt);
 unop_explicitly_typed!(SExt, "sext");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FPTrunc {
     pub operand: ConstantRef,
     pub to_type: TypeRef,
@@ -1020,7 +1575,8 @@ pub struct FPTrunc {
 impl_constexpr!(FPTrunc, FPTrunc);
 unop_explicitly_typed!(FPTrunc, "fptrunc");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FPExt {
     pub operand: ConstantRef,
     pub to_type: TypeRef,
@@ -1029,7 +1585,8 @@ pub struct FPExt {
 impl_constexpr!(FPExt, FPExt);
 unop_explicitly_typed!(FPExt, "fpext");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FPToUI {
     pub operand: ConstantRef,
     pub to_type: TypeRef,
@@ -1038,7 +1595,8 @@ pub struct FPToUI {
 impl_constexpr!(FPToUI, FPToUI);
 unop_explicitly_typed!(FPToUI, "fptoui");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FPToSI {
     pub operand: ConstantRef,
     pub to_type: TypeRef,
@@ -1047,7 +1605,8 @@ pub struct FPToSI {
 impl_constexpr!(FPToSI, FPToSI);
 unop_explicitly_typed!(FPToSI, "fptosi");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UIToFP {
     pub operand: ConstantRef,
     pub to_type: TypeRef,
@@ -1056,7 +1615,8 @@ pub struct UIToFP {
 impl_constexpr!(UIToFP, UIToFP);
 unop_explicitly_typed!(UIToFP, "uitofp");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SIToFP {
     pub operand: ConstantRef,
     pub to_type: TypeRef,
@@ -1065,7 +1625,8 @@ pub struct SIToFP {
 impl_constexpr!(SIToFP, SIToFP);
 unop_explicitly_typed!(SIToFP, "sitofp");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PtrToInt {
     pub operand: ConstantRef,
     pub to_type: TypeRef,
@@ -1074,7 +1635,8 @@ pub struct PtrToInt {
 impl_constexpr!(PtrToInt, PtrToInt);
 unop_explicitly_typed!(PtrToInt, "ptrtoint");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IntToPtr {
     pub operand: ConstantRef,
     pub to_type: TypeRef,
@@ -1083,7 +1645,8 @@ pub struct IntToPtr {
 impl_constexpr!(IntToPtr, IntToPtr);
 unop_explicitly_typed!(IntToPtr, "inttoptr");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BitCast {
     pub operand: ConstantRef,
     pub to_type: TypeRef,
@@ -1092,7 +1655,8 @@ pub struct BitCast {
 impl_constexpr!(BitCast, BitCast);
 unop_explicitly_typed!(BitCast, "bitcast");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AddrSpaceCast {
     pub operand: ConstantRef,
     pub to_type: TypeRef,
@@ -1101,7 +1665,8 @@ pub struct AddrSpaceCast {
 impl_constexpr!(AddrSpaceCast, AddrSpaceCast);
 unop_explicitly_typed!(AddrSpaceCast, "addrspacecast");
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ICmp {
     pub predicate: IntPredicate,
     pub operand0: ConstantRef,
@@ -1139,7 +1704,8 @@ impl Display for ICmp {
     }
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FCmp {
     pub predicate: FPPredicate,
     pub operand0: ConstantRef,
@@ -1177,7 +1743,8 @@ impl Display for FCmp {
     }
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Select {
     pub condition: ConstantRef,
     pub true_value: ConstantRef,
@@ -1208,23 +1775,27 @@ impl Display for Select {
 // from_llvm //
 // ********* //
 
+use crate::error::Error;
 use crate::llvm_sys::*;
 use crate::module::ModuleContext;
 use std::collections::hash_map::Entry;
 
 impl Constant {
-    pub(crate) fn from_llvm_ref(constant: LLVMValueRef, ctx: &mut ModuleContext) -> ConstantRef {
+    pub(crate) fn from_llvm_ref(
+        constant: LLVMValueRef,
+        ctx: &mut ModuleContext,
+    ) -> Result<ConstantRef, Error> {
         if let Some(constantref) = ctx.constants.get(&constant) {
-            return constantref.clone();
+            return Ok(constantref.clone());
         }
-        let parsed = Self::parse_from_llvm_ref(constant, ctx);
+        let parsed = Self::parse_from_llvm_ref(constant, ctx)?;
         match ctx.constants.entry(constant) {
             Entry::Occupied(_) => panic!("This case should have been handled above"),
-            Entry::Vacant(ventry) => ventry.insert(ConstantRef::new(parsed)).clone(),
+            Entry::Vacant(ventry) => Ok(ventry.insert(ConstantRef::new(parsed)).clone()),
         }
     }
 
-    fn parse_from_llvm_ref(constant: LLVMValueRef, ctx: &mut ModuleContext) -> Self {
+    fn parse_from_llvm_ref(constant: LLVMValueRef, ctx: &mut ModuleContext) -> Result<Self, Error> {
         use llvm_sys::LLVMValueKind;
         if unsafe { LLVMIsAConstant(constant).is_null() } {
             panic!(
@@ -1232,9 +1803,10 @@ impl Constant {
                 unsafe { LLVMGetValueKind(constant) }
             )
         }
-        match unsafe { LLVMGetValueKind(constant) } {
+        Ok(match unsafe { LLVMGetValueKind(constant) } {
             LLVMValueKind::LLVMConstantIntValueKind => {
                 match ctx.types.type_from_llvm_ref( unsafe { LLVMTypeOf(constant) } ).as_ref() {
+                    Type::IntegerType { bits } if *bits > 64 => Constant::BigInt(big_int_from_llvm_ref(constant, *bits)),
                     Type::IntegerType { bits } => Constant::Int {
                         bits: *bits,
                         value: unsafe { LLVMConstIntGetZExtValue(constant) } as u64,
@@ -1269,7 +1841,7 @@ impl Constant {
                 let (num_elements, is_packed) = match ctx.types.type_from_llvm_ref( unsafe { LLVMTypeOf(constant) } ).as_ref() {
                     Type::StructType { element_types, is_packed } => (element_types.len(), *is_packed),
                     Type::NamedStructType { name } => match ctx.types.named_struct_def(name) {
-                        NamedStructDef::Opaque => panic!("Constant of opaque struct type (struct name {:?})", name),
+                        NamedStructDef::Opaque => return Err(Error::unsupported(format!("Constant of opaque struct type (struct name {:?})", name))),
                         NamedStructDef::Defined(ty) => match ty.as_ref() {
                             Type::StructType { element_types, is_packed } => {
                                 (element_types.len(), *is_packed)
@@ -1284,17 +1856,24 @@ impl Constant {
                     values: {
                         (0 .. num_elements).map(|i| {
                             Constant::from_llvm_ref( unsafe { LLVMGetOperand(constant, i as u32) }, ctx)
-                        }).collect()
+                        }).collect::<Result<_, _>>()?
                     },
                     is_packed,
                 }
             },
+            LLVMValueKind::LLVMConstantArrayValueKind | LLVMValueKind::LLVMConstantDataArrayValueKind
+                if ctx.options.max_constant_array_elements.is_some_and(|max| {
+                    (unsafe { LLVMGetArrayLength(LLVMTypeOf(constant)) }) as usize > max
+                }) =>
+            {
+                Constant::Undef(ctx.types.type_from_llvm_ref( unsafe { LLVMTypeOf(constant) } ))
+            },
             LLVMValueKind::LLVMConstantArrayValueKind => {
                 match ctx.types.type_from_llvm_ref( unsafe { LLVMTypeOf(constant) } ).as_ref() {
                     Type::ArrayType { element_type, num_elements } => Constant::Array {
                         element_type: element_type.clone(),
                         elements: {
-                            (0 .. *num_elements).map(|i| Constant::from_llvm_ref( unsafe { LLVMGetOperand(constant, i as u32) }, ctx)).collect()
+                            (0 .. *num_elements).map(|i| Constant::from_llvm_ref( unsafe { LLVMGetOperand(constant, i as u32) }, ctx)).collect::<Result<_, _>>()?
                         },
                     },
                     ty => panic!("Expected Constant::Array to have type Type::ArrayType; got {:?}", ty),
@@ -1303,25 +1882,24 @@ impl Constant {
             LLVMValueKind::LLVMConstantVectorValueKind => {
                 let num_elements = unsafe { LLVMGetNumOperands(constant) };
                 Constant::Vector(
-                    (0 .. num_elements).map(|i| Constant::from_llvm_ref( unsafe { LLVMGetOperand(constant, i as u32) }, ctx)).collect()
+                    (0 .. num_elements).map(|i| Constant::from_llvm_ref( unsafe { LLVMGetOperand(constant, i as u32) }, ctx)).collect::<Result<_, _>>()?
                 )
             },
             LLVMValueKind::LLVMConstantDataArrayValueKind => {
                 match ctx.types.type_from_llvm_ref( unsafe { LLVMTypeOf(constant) } ).as_ref() {
-                    Type::ArrayType { element_type, num_elements } => Constant::Array {
+                    Type::ArrayType { element_type, num_elements } => Constant::RawDataArray {
                         element_type: element_type.clone(),
-                        elements: {
-                            (0 .. *num_elements).map(|i| Constant::from_llvm_ref( unsafe { LLVMGetElementAsConstant(constant, i as u32) }, ctx)).collect()
-                        },
+                        data: raw_data_from_llvm_ref(constant, element_type, *num_elements),
                     },
                     ty => panic!("Expected ConstantDataArray to have type Type::ArrayType; got {:?}", ty),
                 }
             },
             LLVMValueKind::LLVMConstantDataVectorValueKind => {
                 match ctx.types.type_from_llvm_ref( unsafe { LLVMTypeOf(constant) } ).as_ref() {
-                    Type::VectorType { num_elements, .. } => Constant::Vector(
-                        (0 .. *num_elements).map(|i| Constant::from_llvm_ref( unsafe { LLVMGetElementAsConstant(constant, i as u32) }, ctx)).collect()
-                    ),
+                    Type::VectorType { element_type, num_elements, .. } => Constant::RawDataVector {
+                        element_type: element_type.clone(),
+                        data: raw_data_from_llvm_ref(constant, element_type, *num_elements),
+                    },
                     ty => panic!("Expected ConstantDataVector to have type Type::VectorType; got {:?}", ty),
                 }
             },
@@ -1342,76 +1920,157 @@ impl Constant {
                 Constant::TokenNone
             },
             LLVMValueKind::LLVMBlockAddressValueKind => {
-                Constant::BlockAddress
+                // the operands of a `blockaddress` are the function and the block
+                let function = unsafe { LLVMGetOperand(constant, 0) };
+                let block = unsafe { LLVMValueAsBasicBlock(LLVMGetOperand(constant, 1)) };
+                Constant::BlockAddress {
+                    function: ctx.global_name(function),
+                    block: crate::function::block_name(function, block),
+                }
             },
             LLVMValueKind::LLVMConstantExprValueKind => {
                 use llvm_sys::LLVMOpcode;
                 match unsafe { LLVMGetConstOpcode(constant) } {
-                    LLVMOpcode::LLVMAdd => Constant::Add(Add::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMSub => Constant::Sub(Sub::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMMul => Constant::Mul(Mul::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMUDiv => Constant::UDiv(UDiv::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMSDiv => Constant::SDiv(SDiv::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMURem => Constant::URem(URem::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMSRem => Constant::SRem(SRem::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMAnd => Constant::And(And::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMOr => Constant::Or(Or::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMXor => Constant::Xor(Xor::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMShl => Constant::Shl(Shl::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMLShr => Constant::LShr(LShr::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMAShr => Constant::AShr(AShr::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMFAdd => Constant::FAdd(FAdd::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMFSub => Constant::FSub(FSub::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMFMul => Constant::FMul(FMul::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMFDiv => Constant::FDiv(FDiv::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMFRem => Constant::FRem(FRem::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMExtractElement => Constant::ExtractElement(ExtractElement::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMInsertElement => Constant::InsertElement(InsertElement::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMShuffleVector => Constant::ShuffleVector(ShuffleVector::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMExtractValue => Constant::ExtractValue(ExtractValue::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMInsertValue => Constant::InsertValue(InsertValue::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMGetElementPtr => Constant::GetElementPtr(GetElementPtr::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMTrunc => Constant::Trunc(Trunc::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMZExt => Constant::ZExt(ZExt::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMSExt => Constant::SExt(SExt::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMFPTrunc => Constant::FPTrunc(FPTrunc::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMFPExt => Constant::FPExt(FPExt::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMFPToUI => Constant::FPToUI(FPToUI::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMFPToSI => Constant::FPToSI(FPToSI::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMUIToFP => Constant::UIToFP(UIToFP::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMSIToFP => Constant::SIToFP(SIToFP::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMPtrToInt => Constant::PtrToInt(PtrToInt::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMIntToPtr => Constant::IntToPtr(IntToPtr::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMBitCast => Constant::BitCast(BitCast::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMAddrSpaceCast => Constant::AddrSpaceCast(AddrSpaceCast::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMICmp => Constant::ICmp(ICmp::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMFCmp => Constant::FCmp(FCmp::from_llvm_ref(constant, ctx)),
-                    LLVMOpcode::LLVMSelect => Constant::Select(Select::from_llvm_ref(constant, ctx)),
-                    opcode => panic!("ConstantExpr has unexpected opcode {:?}", opcode),
+                    LLVMOpcode::LLVMAdd => Constant::Add(Add::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMSub => Constant::Sub(Sub::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMMul => Constant::Mul(Mul::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMUDiv => Constant::UDiv(UDiv::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMSDiv => Constant::SDiv(SDiv::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMURem => Constant::URem(URem::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMSRem => Constant::SRem(SRem::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMAnd => Constant::And(And::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMOr => Constant::Or(Or::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMXor => Constant::Xor(Xor::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMShl => Constant::Shl(Shl::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMLShr => Constant::LShr(LShr::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMAShr => Constant::AShr(AShr::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMFAdd => Constant::FAdd(FAdd::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMFSub => Constant::FSub(FSub::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMFMul => Constant::FMul(FMul::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMFDiv => Constant::FDiv(FDiv::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMFRem => Constant::FRem(FRem::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMFNeg => Constant::FNeg(FNeg::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMExtractElement => Constant::ExtractElement(ExtractElement::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMInsertElement => Constant::InsertElement(InsertElement::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMShuffleVector => Constant::ShuffleVector(ShuffleVector::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMExtractValue => Constant::ExtractValue(ExtractValue::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMInsertValue => Constant::InsertValue(InsertValue::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMGetElementPtr => Constant::GetElementPtr(GetElementPtr::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMTrunc => Constant::Trunc(Trunc::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMZExt => Constant::ZExt(ZExt::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMSExt => Constant::SExt(SExt::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMFPTrunc => Constant::FPTrunc(FPTrunc::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMFPExt => Constant::FPExt(FPExt::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMFPToUI => Constant::FPToUI(FPToUI::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMFPToSI => Constant::FPToSI(FPToSI::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMUIToFP => Constant::UIToFP(UIToFP::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMSIToFP => Constant::SIToFP(SIToFP::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMPtrToInt => Constant::PtrToInt(PtrToInt::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMIntToPtr => Constant::IntToPtr(IntToPtr::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMBitCast => Constant::BitCast(BitCast::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMAddrSpaceCast => Constant::AddrSpaceCast(AddrSpaceCast::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMICmp => Constant::ICmp(ICmp::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMFCmp => Constant::FCmp(FCmp::from_llvm_ref(constant, ctx)?),
+                    LLVMOpcode::LLVMSelect => Constant::Select(Select::from_llvm_ref(constant, ctx)?),
+                    opcode => {
+                        debug!("ConstantExpr has unexpected opcode {:?}", opcode);
+                        Constant::OtherExpr {
+                            opcode: opcode as u32,
+                            operands: (0 .. unsafe { LLVMGetNumOperands(constant) } as u32)
+                                .map(|i| Constant::from_llvm_ref(unsafe { LLVMGetOperand(constant, i) }, ctx))
+                                .collect::<Result<_, _>>()?,
+                            ty: ctx.types.type_from_llvm_ref( unsafe { LLVMTypeOf(constant) } ),
+                        }
+                    },
                 }
             },
             _ if unsafe { !LLVMIsAGlobalValue(constant).is_null() } => {
                 Constant::GlobalReference {
-                    name: ctx.global_names.get(&constant)
-                        .unwrap_or_else(|| { let names: Vec<_> = ctx.global_names.values().collect(); panic!("Global not found in ctx.global_names; have names {:?}", names) })
-                        .clone(),
+                    name: ctx.global_name(constant),
                     ty: ctx.types.type_from_llvm_ref( unsafe { LLVMGlobalGetValueType(constant) } ),
+                    kind: match unsafe { LLVMGetValueKind(constant) } {
+                        LLVMValueKind::LLVMFunctionValueKind => GlobalKind::Function,
+                        LLVMValueKind::LLVMGlobalVariableValueKind => GlobalKind::GlobalVariable,
+                        LLVMValueKind::LLVMGlobalAliasValueKind => GlobalKind::GlobalAlias,
+                        LLVMValueKind::LLVMGlobalIFuncValueKind => GlobalKind::GlobalIFunc,
+                        k => return Err(Error::unsupported(format!("Expected a function, global variable, alias, or ifunc; got ValueKind {:?}", k))),
+                    },
+                    addr_space: unsafe { LLVMGetPointerAddressSpace(LLVMTypeOf(constant)) },
                 }
             },
-            k => panic!("Constant::from_llvm_ref: don't know how to handle this Constant with ValueKind {:?}", k),
+            k => return Err(Error::unsupported(format!("Constant::from_llvm_ref: don't know how to handle this Constant with ValueKind {:?}", k))),
+        })
+    }
+}
+
+/// Get the value of a `ConstantInt` wider than 64 bits. The C API can only
+/// return the low 64 bits of a `ConstantInt`, so we have LLVM constant-fold
+/// shifts and truncations to extract each 64-bit word in turn.
+fn big_int_from_llvm_ref(constant: LLVMValueRef, bits: u32) -> BigIntValue {
+    let ty = unsafe { LLVMTypeOf(constant) };
+    let i64_ty = unsafe { LLVMInt64TypeInContext(LLVMGetTypeContext(ty)) };
+    let words = (0 .. bits.div_ceil(64))
+        .map(|i| unsafe {
+            let shifted = LLVMConstLShr(constant, LLVMConstInt(ty, u64::from(i) * 64, 0));
+            LLVMConstIntGetZExtValue(LLVMConstTrunc(shifted, i64_ty))
+        })
+        .collect();
+    BigIntValue::from_words(bits, words)
+}
+
+/// Get the contents of a `ConstantDataArray` or `ConstantDataVector` in the
+/// layout described on `Constant::RawDataArray`, without creating a
+/// `ConstantRef` for each element
+fn raw_data_from_llvm_ref(
+    constant: LLVMValueRef,
+    element_type: &TypeRef,
+    num_elements: usize,
+) -> Vec<u8> {
+    if unsafe { LLVMIsConstantString(constant) } != 0 {
+        // arrays of i8 can be copied out directly
+        let mut len = 0;
+        let ptr = unsafe { LLVMGetAsString(constant, &mut len) };
+        return unsafe { std::slice::from_raw_parts(ptr as *const u8, len) }.to_vec();
+    }
+    let bits = match element_type.as_ref() {
+        Type::IntegerType { bits } => *bits,
+        Type::FPType(FPType::Half) => 16,
+        #[cfg(feature = "llvm-11-or-greater")]
+        Type::FPType(FPType::BFloat) => 16,
+        Type::FPType(FPType::Single) => 32,
+        Type::FPType(FPType::Double) => 64,
+        ty => panic!(
+            "Unexpected element type for ConstantDataSequential: {:?}",
+            ty
+        ),
+    };
+    let size = (bits as usize).div_ceil(8);
+    let int_ty = unsafe { LLVMIntTypeInContext(LLVMGetTypeContext(LLVMTypeOf(constant)), bits) };
+    let mut data = Vec::with_capacity(num_elements * size);
+    for i in 0 .. num_elements {
+        let mut elt = unsafe { LLVMGetElementAsConstant(constant, i as u32) };
+        if let Type::FPType(_) = element_type.as_ref() {
+            // LLVM constant-folds this into a ConstantInt with the same bits
+            elt = unsafe { LLVMConstBitCast(elt, int_ty) };
         }
+        let value = unsafe { LLVMConstIntGetZExtValue(elt) } as u64;
+        data.extend_from_slice(&value.to_le_bytes()[.. size]);
     }
+    data
 }
 
 macro_rules! binop_from_llvm {
     ($expr:ident) => {
         impl $expr {
-            pub(crate) fn from_llvm_ref(expr: LLVMValueRef, ctx: &mut ModuleContext) -> Self {
+            pub(crate) fn from_llvm_ref(
+                expr: LLVMValueRef,
+                ctx: &mut ModuleContext,
+            ) -> Result<Self, Error> {
                 assert_eq!(unsafe { LLVMGetNumOperands(expr) }, 2);
-                Self {
-                    operand0: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 0) }, ctx),
-                    operand1: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 1) }, ctx),
-                }
+                Ok(Self {
+                    operand0: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 0) }, ctx)?,
+                    operand1: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 1) }, ctx)?,
+                })
             }
         }
     };
@@ -1436,88 +2095,138 @@ binop_from_llvm!(FMul);
 binop_from_llvm!(FDiv);
 binop_from_llvm!(FRem);
 
+impl FNeg {
+    pub(crate) fn from_llvm_ref(
+        expr: LLVMValueRef,
+        ctx: &mut ModuleContext,
+    ) -> Result<Self, Error> {
+        assert_eq!(unsafe { LLVMGetNumOperands(expr) }, 1);
+        Ok(Self {
+            operand: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 0) }, ctx)?,
+        })
+    }
+}
+
 impl ExtractElement {
-    pub(crate) fn from_llvm_ref(expr: LLVMValueRef, ctx: &mut ModuleContext) -> Self {
+    pub(crate) fn from_llvm_ref(
+        expr: LLVMValueRef,
+        ctx: &mut ModuleContext,
+    ) -> Result<Self, Error> {
         assert_eq!(unsafe { LLVMGetNumOperands(expr) }, 2);
-        Self {
-            vector: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 0) }, ctx),
-            index: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 1) }, ctx),
-        }
+        Ok(Self {
+            vector: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 0) }, ctx)?,
+            index: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 1) }, ctx)?,
+        })
     }
 }
 
 impl InsertElement {
-    pub(crate) fn from_llvm_ref(expr: LLVMValueRef, ctx: &mut ModuleContext) -> Self {
+    pub(crate) fn from_llvm_ref(
+        expr: LLVMValueRef,
+        ctx: &mut ModuleContext,
+    ) -> Result<Self, Error> {
         assert_eq!(unsafe { LLVMGetNumOperands(expr) }, 3);
-        Self {
-            vector: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 0) }, ctx),
-            element: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 1) }, ctx),
-            index: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 2) }, ctx),
-        }
+        Ok(Self {
+            vector: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 0) }, ctx)?,
+            element: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 1) }, ctx)?,
+            index: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 2) }, ctx)?,
+        })
     }
 }
 
 impl ShuffleVector {
     #[cfg(feature = "llvm-10-or-lower")]
-    pub(crate) fn from_llvm_ref(expr: LLVMValueRef, ctx: &mut ModuleContext) -> Self {
+    pub(crate) fn from_llvm_ref(
+        expr: LLVMValueRef,
+        ctx: &mut ModuleContext,
+    ) -> Result<Self, Error> {
         assert_eq!(unsafe { LLVMGetNumOperands(expr) }, 3);
-        Self {
-            operand0: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 0) }, ctx),
-            operand1: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 1) }, ctx),
-            mask: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 2) }, ctx),
-        }
+        Ok(Self {
+            operand0: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 0) }, ctx)?,
+            operand1: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 1) }, ctx)?,
+            mask: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 2) }, ctx)?,
+        })
     }
     #[cfg(feature = "llvm-11-or-greater")]
-    pub(crate) fn from_llvm_ref(expr: LLVMValueRef, _ctx: &mut ModuleContext) -> Self {
+    pub(crate) fn from_llvm_ref(
+        expr: LLVMValueRef,
+        _ctx: &mut ModuleContext,
+    ) -> Result<Self, Error> {
         assert_eq!(unsafe { LLVMGetNumOperands(expr) }, 2);
         // We currently (as of LLVM 11) have no way to get the mask of a
         // ShuffleVector constant expression; LLVMGetMaskValue() only works for
         // ShuffleVector instructions, not ShuffleVector constant expressions
-        panic!("Encountered a Constant::ShuffleVector, which is not supported for LLVM 11+")
+        Err(Error::unsupported(
+            "Encountered a Constant::ShuffleVector, which is not supported for LLVM 11+",
+        ))
     }
 }
 
 impl ExtractValue {
-    pub(crate) fn from_llvm_ref(expr: LLVMValueRef, ctx: &mut ModuleContext) -> Self {
+    pub(crate) fn from_llvm_ref(
+        expr: LLVMValueRef,
+        ctx: &mut ModuleContext,
+    ) -> Result<Self, Error> {
         assert_eq!(unsafe { LLVMGetNumOperands(expr) }, 2);
-        Self {
-            aggregate: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 0) }, ctx),
+        Ok(Self {
+            aggregate: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 0) }, ctx)?,
             indices: unsafe {
                 let num_indices = LLVMGetNumIndices(expr);
                 let ptr = LLVMGetIndices(expr);
                 std::slice::from_raw_parts(ptr, num_indices as usize).to_vec()
             },
-        }
+        })
     }
 }
 
 impl InsertValue {
-    pub(crate) fn from_llvm_ref(expr: LLVMValueRef, ctx: &mut ModuleContext) -> Self {
+    pub(crate) fn from_llvm_ref(
+        expr: LLVMValueRef,
+        ctx: &mut ModuleContext,
+    ) -> Result<Self, Error> {
         assert_eq!(unsafe { LLVMGetNumOperands(expr) }, 3);
-        Self {
-            aggregate: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 0) }, ctx),
-            element: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 1) }, ctx),
+        Ok(Self {
+            aggregate: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 0) }, ctx)?,
+            element: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 1) }, ctx)?,
             indices: unsafe {
                 let num_indices = LLVMGetNumIndices(expr);
                 let ptr = LLVMGetIndices(expr);
                 std::slice::from_raw_parts(ptr, num_indices as usize).to_vec()
             },
-        }
+        })
     }
 }
 
 impl GetElementPtr {
-    pub(crate) fn from_llvm_ref(expr: LLVMValueRef, ctx: &mut ModuleContext) -> Self {
-        Self {
-            address: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 0) }, ctx),
+    pub(crate) fn from_llvm_ref(
+        expr: LLVMValueRef,
+        ctx: &mut ModuleContext,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            address: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 0) }, ctx)?,
             indices: {
                 let num_indices = unsafe { LLVMGetNumOperands(expr) as u32 } - 1; // LLVMGetNumIndices(), which we use for instruction::GetElementPtr, appears empirically to not work for constant::GetElementPtr
                 (1 ..= num_indices)
                     .map(|i| Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, i) }, ctx))
-                    .collect()
+                    .collect::<Result<_, _>>()?
             },
             in_bounds: unsafe { LLVMIsInBounds(expr) } != 0,
-        }
+            #[cfg(feature = "llvm-14-or-greater")]
+            source_element_type: ctx
+                .types
+                .type_from_llvm_ref(unsafe { LLVMGetGEPSourceElementType(expr) }),
+            #[cfg(feature = "llvm-13-or-lower")]
+            source_element_type: {
+                let address_ty = ctx
+                    .types
+                    .type_from_llvm_ref(unsafe { LLVMTypeOf(LLVMGetOperand(expr, 0)) });
+                match address_ty.as_ref() {
+                    Type::VectorType { element_type, .. } => element_type.pointee_type(),
+                    _ => address_ty.pointee_type(),
+                }
+                .expect("GEP address should be a pointer or a vector of pointers")
+            },
+        })
     }
 }
 
@@ -1526,12 +2235,15 @@ impl GetElementPtr {
 macro_rules! typed_unop_from_llvm {
     ($expr:ident) => {
         impl $expr {
-            pub(crate) fn from_llvm_ref(expr: LLVMValueRef, ctx: &mut ModuleContext) -> Self {
+            pub(crate) fn from_llvm_ref(
+                expr: LLVMValueRef,
+                ctx: &mut ModuleContext,
+            ) -> Result<Self, Error> {
                 assert_eq!(unsafe { LLVMGetNumOperands(expr) }, 1);
-                Self {
-                    operand: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 0) }, ctx),
+                Ok(Self {
+                    operand: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 0) }, ctx)?,
                     to_type: ctx.types.type_from_llvm_ref(unsafe { LLVMTypeOf(expr) }),
-                }
+                })
             }
         }
     };
@@ -1552,34 +2264,43 @@ typed_unop_from_llvm!(BitCast);
 typed_unop_from_llvm!(AddrSpaceCast);
 
 impl ICmp {
-    pub(crate) fn from_llvm_ref(expr: LLVMValueRef, ctx: &mut ModuleContext) -> Self {
+    pub(crate) fn from_llvm_ref(
+        expr: LLVMValueRef,
+        ctx: &mut ModuleContext,
+    ) -> Result<Self, Error> {
         assert_eq!(unsafe { LLVMGetNumOperands(expr) }, 2);
-        Self {
+        Ok(Self {
             predicate: IntPredicate::from_llvm(unsafe { LLVMGetICmpPredicate(expr) }),
-            operand0: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 0) }, ctx),
-            operand1: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 1) }, ctx),
-        }
+            operand0: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 0) }, ctx)?,
+            operand1: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 1) }, ctx)?,
+        })
     }
 }
 
 impl FCmp {
-    pub(crate) fn from_llvm_ref(expr: LLVMValueRef, ctx: &mut ModuleContext) -> Self {
+    pub(crate) fn from_llvm_ref(
+        expr: LLVMValueRef,
+        ctx: &mut ModuleContext,
+    ) -> Result<Self, Error> {
         assert_eq!(unsafe { LLVMGetNumOperands(expr) }, 2);
-        Self {
+        Ok(Self {
             predicate: FPPredicate::from_llvm(unsafe { LLVMGetFCmpPredicate(expr) }),
-            operand0: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 0) }, ctx),
-            operand1: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 1) }, ctx),
-        }
+            operand0: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 0) }, ctx)?,
+            operand1: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 1) }, ctx)?,
+        })
     }
 }
 
 impl Select {
-    pub(crate) fn from_llvm_ref(expr: LLVMValueRef, ctx: &mut ModuleContext) -> Self {
+    pub(crate) fn from_llvm_ref(
+        expr: LLVMValueRef,
+        ctx: &mut ModuleContext,
+    ) -> Result<Self, Error> {
         assert_eq!(unsafe { LLVMGetNumOperands(expr) }, 3);
-        Self {
-            condition: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 0) }, ctx),
-            true_value: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 1) }, ctx),
-            false_value: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 2) }, ctx),
-        }
+        Ok(Self {
+            condition: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 0) }, ctx)?,
+            true_value: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 1) }, ctx)?,
+            false_value: Constant::from_llvm_ref(unsafe { LLVMGetOperand(expr, 2) }, ctx)?,
+        })
     }
 }
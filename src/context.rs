@@ -0,0 +1,50 @@
+//! Sharing types between `Module`s.
+
+use crate::types::TypesBuilder;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// A `Context` lets several `Module`s share their `Type`s: `Module`s parsed
+/// in the same `Context` (with, e.g.,
+/// [`Module::from_bc_path_in()`](../module/struct.Module.html#method.from_bc_path_in))
+/// get the same `TypeRef` for each `Type`, including for named structs with
+/// the same name. This is useful for whole-program analyses, which can then
+/// compare types from different `Module`s cheaply.
+///
+/// Each `Module` still has its own `Types`, and in particular its own
+/// definitions of named structs: if two `Module`s define a named struct
+/// with the same name differently, both `Module`s refer to it with the same
+/// `TypeRef`, but `Types::named_struct_def()` gives each `Module`'s own
+/// definition.
+///
+/// Only the `TypeRef`s created while parsing are shared. `TypeRef`s for
+/// `Type`s which first appear when looking them up in a `Module`'s `Types`
+/// afterwards (for instance, a function type computed with
+/// `Module::type_of()`) are still equal to the corresponding `TypeRef`s in
+/// other `Module`s, but aren't shared with them.
+///
+/// `Module`s can be parsed in the same `Context` from multiple threads, but
+/// only one will be parsed at a time.
+pub struct Context {
+    types: Mutex<TypesBuilder>,
+}
+
+impl Context {
+    pub fn new() -> Self {
+        Self {
+            types: Mutex::new(TypesBuilder::new()),
+        }
+    }
+
+    /// Lock the shared types, for parsing a `Module`
+    pub(crate) fn lock_types(&self) -> MutexGuard<'_, TypesBuilder> {
+        // we only update the shared types once a `Module` has been parsed
+        // successfully, so they can't be left inconsistent by a panic
+        self.types.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,8 +1,12 @@
+use crate::location::{InstrLoc, ModuleLoc};
+use crate::module::Module;
 use std::cmp::{Ordering, PartialOrd};
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 
 /// Describes a "debug location" (source location)
 #[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DebugLoc {
     /// The source line number
     pub line: u32,
@@ -19,13 +23,8 @@ pub struct DebugLoc {
 }
 
 impl PartialOrd for DebugLoc {
-    #[rustfmt::skip] // self on one line, other on the next
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        // compare in the order (directory, filename, line, col)
-        Some(
-            (&self.directory, &self.filename, &self.line, &self.col)
-                .cmp(&(&other.directory, &other.filename, &other.line, &other.col))
-        )
+        Some(self.cmp(other))
     }
 }
 
@@ -84,39 +83,289 @@ pub trait HasDebugLoc {
     fn get_debug_loc(&self) -> &Option<DebugLoc>;
 }
 
+/// Implemented by everything which implements `HasDebugLoc`, so that
+/// transformations within the crate (e.g.,
+/// [`Module::strip_debug_info()`](../module/struct.Module.html#method.strip_debug_info))
+/// can change the `DebugLoc` in place.
+pub(crate) trait HasDebugLocMut: HasDebugLoc {
+    /// Mutable access to the `DebugLoc`, e.g., to remove it
+    fn get_debug_loc_mut(&mut self) -> &mut Option<DebugLoc>;
+}
+
+/// Describes the source function a `Function` was compiled from, as given by
+/// the `DISubprogram` attached to it.
+/// See [`Function::subprogram()`](../function/struct.Function.html#method.subprogram).
+///
+/// The source file of the function is the `filename` of the `Function`'s
+/// `DebugLoc`, which may differ from the main source file of its
+/// `compile_unit` (e.g., for a function defined in a header).
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Subprogram {
+    /// The name of the function in the source, e.g., `add`
+    pub name: String,
+    /// The name of the function in the object file, e.g., the mangled name
+    /// `_Z3addii`, or `None` if it is the same as `name` (as in C)
+    pub linkage_name: Option<String>,
+    /// The source line the function is declared on
+    pub line: u32,
+    /// The compile unit the function was compiled as part of
+    pub compile_unit: Option<CompileUnit>,
+}
+
+/// Describes a compile unit, i.e., a source file and everything it includes,
+/// as given by a `DICompileUnit`. See
+/// [LLVM 14 docs on DICompileUnit](https://releases.llvm.org/14.0.0/docs/LangRef.html#dicompileunit)
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CompileUnit {
+    /// The main source file of the compile unit
+    pub filename: String,
+    /// The directory of the main source file, if available
+    pub directory: Option<String>,
+    /// The compiler which produced the compile unit, e.g.,
+    /// `clang version 14.0.6`
+    pub producer: String,
+}
+
+/// An index from source lines to the instructions and functions of a
+/// `Module` whose `DebugLoc`s fall on them, with reverse queries from
+/// instructions and functions to source lines.
+///
+/// Source files are identified by `filename`, exactly as it appears in the
+/// `DebugLoc`s; `directory` is ignored. Columns are also ignored, so all of
+/// the instructions on a line are grouped together.
+///
+/// Like the `InstrLoc`s it contains, a `SourceMap` is invalidated if the
+/// `Module` it was built from is modified.
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+    /// Map from filename to the entries for each line of that file
+    files: HashMap<String, BTreeMap<u32, LineEntry>>,
+    /// Map from each instruction with a `DebugLoc` to its `DebugLoc`
+    instr_locs: HashMap<InstrLoc, DebugLoc>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct LineEntry {
+    /// Instructions with a `DebugLoc` on this line, in order
+    instrs: Vec<InstrLoc>,
+    /// Indices of the functions which either contain an instruction on this
+    /// line, or whose own `DebugLoc` is on this line; sorted and deduplicated
+    functions: Vec<usize>,
+}
+
+impl SourceMap {
+    /// Build the `SourceMap` for the given `Module`.
+    pub fn new(module: &Module) -> Self {
+        let mut map = Self::default();
+        for (func, f) in module.functions.iter().enumerate() {
+            if let Some(debugloc) = f.get_debug_loc() {
+                map.entry(debugloc).functions.push(func);
+            }
+            for (block, bb) in f.basic_blocks.iter().enumerate() {
+                let bb_loc = ModuleLoc::new(func, block);
+                let instrs = bb
+                    .instrs
+                    .iter()
+                    .enumerate()
+                    .map(|(index, inst)| (bb_loc.instr(index), inst.get_debug_loc()))
+                    .chain(std::iter::once((
+                        bb_loc.terminator(),
+                        bb.term.get_debug_loc(),
+                    )));
+                for (loc, debugloc) in instrs {
+                    if let Some(debugloc) = debugloc {
+                        let entry = map.entry(debugloc);
+                        entry.instrs.push(loc);
+                        entry.functions.push(func);
+                        map.instr_locs.insert(loc, debugloc.clone());
+                    }
+                }
+            }
+        }
+        for entry in map.files.values_mut().flat_map(BTreeMap::values_mut) {
+            entry.functions.sort_unstable();
+            entry.functions.dedup();
+        }
+        map
+    }
+
+    fn entry(&mut self, debugloc: &DebugLoc) -> &mut LineEntry {
+        self.files
+            .entry(debugloc.filename.clone())
+            .or_default()
+            .entry(debugloc.line)
+            .or_default()
+    }
+
+    fn get(&self, filename: &str, line: u32) -> Option<&LineEntry> {
+        self.files.get(filename)?.get(&line)
+    }
+
+    /// Get the locations of the instructions and terminators whose `DebugLoc`
+    /// is on the given line of the given file, in order.
+    pub fn instrs_at(&self, filename: &str, line: u32) -> &[InstrLoc] {
+        self.get(filename, line).map_or(&[], |entry| &entry.instrs)
+    }
+
+    /// Get the indices (in the `Module`'s `functions`) of the functions which
+    /// contain an instruction on the given line of the given file, or which
+    /// are themselves defined on that line, in ascending order.
+    pub fn functions_at(&self, filename: &str, line: u32) -> &[usize] {
+        self.get(filename, line)
+            .map_or(&[], |entry| &entry.functions)
+    }
+
+    /// Iterate over the lines of the given file which have at least one
+    /// instruction or function, in ascending order.
+    pub fn lines(&self, filename: &str) -> impl Iterator<Item = u32> + '_ {
+        self.files
+            .get(filename)
+            .into_iter()
+            .flat_map(|lines| lines.keys().copied())
+    }
+
+    /// Iterate over the names of all the files in the `SourceMap`, in no
+    /// particular order.
+    pub fn filenames(&self) -> impl Iterator<Item = &str> {
+        self.files.keys().map(String::as_str)
+    }
+
+    /// Get the `DebugLoc` of the instruction or terminator at the given
+    /// location, or `None` if it doesn't have one.
+    pub fn debug_loc_of(&self, loc: InstrLoc) -> Option<&DebugLoc> {
+        self.instr_locs.get(&loc)
+    }
+
+    /// Get the source lines, as (filename, line) pairs, of all the
+    /// instructions in the function at the given index in the `Module`'s
+    /// `functions`. The result is sorted and has no duplicates.
+    pub fn lines_of_function(&self, func: usize) -> Vec<(&str, u32)> {
+        let mut lines: Vec<(&str, u32)> = self
+            .instr_locs
+            .iter()
+            .filter(|(loc, _)| loc.block.func == func)
+            .map(|(_, debugloc)| (debugloc.filename.as_str(), debugloc.line))
+            .collect();
+        lines.sort_unstable();
+        lines.dedup();
+        lines
+    }
+}
+
 // ********* //
 // from_llvm //
 // ********* //
 
 use crate::from_llvm::*;
 use crate::llvm_sys::*;
-
+use crate::module::ModuleContext;
+use llvm_sys::debuginfo::{LLVMDIScopeGetFile, LLVMDISubprogramGetLine, LLVMGetSubprogram};
 impl DebugLoc {
     /// `value`: must represent an Instruction, Terminator, GlobalVariable, or Function
     ///
-    /// Returns `None` if the object does not have a `DebugLoc`
-    pub(crate) fn from_llvm_no_col(value: LLVMValueRef) -> Option<Self> {
-        match unsafe { get_debugloc_filename(value) } {
-            None => None, // if no filename, assume no debugloc. To my knowledge, everything with a debugloc has a filename.
-            Some(filename) => Some(Self {
-                line: unsafe { LLVMGetDebugLocLine(value) },
-                col: None,
-                filename,
-                directory: unsafe { get_debugloc_directory(value) },
-            }),
+    /// Returns `None` if the object does not have a `DebugLoc`, or if the
+    /// `Module` is being parsed with `ParseOptions::skip_debug_info`
+    pub(crate) fn from_llvm_no_col(value: LLVMValueRef, ctx: &ModuleContext) -> Option<Self> {
+        if ctx.options.skip_debug_info {
+            return None;
         }
+        // if no filename, assume no debugloc. To my knowledge, everything with a debugloc has a filename.
+        unsafe { get_debugloc_filename(value) }.map(|filename| Self {
+            line: unsafe { LLVMGetDebugLocLine(value) },
+            col: None,
+            filename,
+            directory: unsafe { get_debugloc_directory(value) },
+        })
     }
 
     /// `value`: must represent an Instruction or Terminator
     ///
-    /// Returns `None` if the object does not have a `DebugLoc`
-    pub(crate) fn from_llvm_with_col(value: LLVMValueRef) -> Option<Self> {
-        match Self::from_llvm_no_col(value) {
-            Some(mut debugloc) => {
-                debugloc.col = Some(unsafe { LLVMGetDebugLocColumn(value) });
-                Some(debugloc)
+    /// Returns `None` if the object does not have a `DebugLoc`, or if the
+    /// `Module` is being parsed with `ParseOptions::skip_debug_info`
+    pub(crate) fn from_llvm_with_col(value: LLVMValueRef, ctx: &ModuleContext) -> Option<Self> {
+        Self::from_llvm_no_col(value, ctx).map(|mut debugloc| {
+            debugloc.col = Some(unsafe { LLVMGetDebugLocColumn(value) });
+            debugloc
+        })
+    }
+}
+
+impl Subprogram {
+    /// `func`: must represent a Function
+    ///
+    /// Returns `None` if the function has no `DISubprogram`, or if the
+    /// `Module` is being parsed with `ParseOptions::skip_debug_info`
+    pub(crate) fn from_llvm_func(func: LLVMValueRef, ctx: &ModuleContext) -> Option<Self> {
+        if ctx.options.skip_debug_info {
+            return None;
+        }
+        let subprogram = unsafe { LLVMGetSubprogram(func) };
+        if subprogram.is_null() {
+            return None;
+        }
+        let context = unsafe { LLVMGetTypeContext(LLVMTypeOf(func)) };
+        // the operands of a `DISubprogram` are its file, scope, name, linkage
+        // name, type, and unit, then others we don't need
+        let operands = unsafe { md_node_operands(LLVMMetadataAsValue(context, subprogram)) };
+        Some(Self {
+            name: md_string(operands.get(2)).unwrap_or_default(),
+            linkage_name: md_string(operands.get(3)).filter(|name| !name.is_empty()),
+            line: unsafe { LLVMDISubprogramGetLine(subprogram) },
+            compile_unit: operands
+                .get(5)
+                .filter(|unit| !unit.is_null())
+                .map(|&unit| CompileUnit::from_llvm_ref(unit)),
+        })
+    }
+}
+
+impl CompileUnit {
+    /// `unit`: must be a `DICompileUnit`, wrapped as a `MetadataAsValue`
+    fn from_llvm_ref(unit: LLVMValueRef) -> Self {
+        let file = unsafe { LLVMDIScopeGetFile(LLVMValueAsMetadata(unit)) };
+        // the operands of a `DICompileUnit` are its file and producer, then
+        // others we don't need
+        let operands = unsafe { md_node_operands(unit) };
+        Self {
+            filename: if file.is_null() {
+                String::new()
+            } else {
+                unsafe { get_difile_filename(file) }.unwrap_or_default()
             },
-            None => None,
+            directory: if file.is_null() {
+                None
+            } else {
+                unsafe { get_difile_directory(file) }
+            },
+            producer: md_string(operands.get(1)).unwrap_or_default(),
         }
     }
 }
+
+/// Get the operands of the given metadata node (wrapped as a
+/// `MetadataAsValue`). Null operands are null.
+unsafe fn md_node_operands(node: LLVMValueRef) -> Vec<LLVMValueRef> {
+    let num_operands = LLVMGetMDNodeNumOperands(node);
+    let mut operands: Vec<LLVMValueRef> = Vec::with_capacity(num_operands as usize);
+    LLVMGetMDNodeOperands(node, operands.as_mut_ptr());
+    operands.set_len(num_operands as usize);
+    operands
+}
+
+/// Get the string of the given `MDString` operand, or `None` if the operand
+/// is missing or null
+fn md_string(operand: Option<&LLVMValueRef>) -> Option<String> {
+    let operand = *operand?;
+    if operand.is_null() {
+        return None;
+    }
+    let mut len = 0;
+    let ptr = unsafe { LLVMGetMDString(operand, &mut len) };
+    if ptr.is_null() {
+        None
+    } else {
+        Some(unsafe { raw_to_string_with_len(ptr, len as usize) })
+    }
+}
@@ -0,0 +1,179 @@
+//! Structural differences between two `Module`s, e.g., to see what an
+//! optimization pass changed.
+
+use crate::basicblock::BasicBlock;
+use crate::function::Function;
+use crate::module::{GlobalAlias, GlobalVariable, Module};
+use crate::name::Name;
+use std::collections::HashMap;
+
+/// The differences between two `Module`s, an old one and a new one.
+/// See [`Module::diff()`](../module/struct.Module.html#method.diff).
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct ModuleDiff {
+    /// Functions defined in the new `Module` but not the old one, in the
+    /// order they appear in the new `Module`
+    pub added_functions: Vec<String>,
+    /// Functions defined in the old `Module` but not the new one, in the
+    /// order they appear in the old `Module`
+    pub removed_functions: Vec<String>,
+    /// Functions defined in both `Module`s which differ, in the order they
+    /// appear in the old `Module`
+    pub changed_functions: Vec<FunctionDiff>,
+    /// Global variables and aliases in the new `Module` but not the old one
+    pub added_globals: Vec<Name>,
+    /// Global variables and aliases in the old `Module` but not the new one
+    pub removed_globals: Vec<Name>,
+    /// Global variables and aliases in both `Module`s which differ
+    pub changed_globals: Vec<Name>,
+}
+
+/// The differences between two versions of a `Function`.
+///
+/// Basic blocks are matched up by name, after renumbering the unnamed locals
+/// of both versions as in
+/// [`Function::rename_locals()`](../function/struct.Function.html#method.rename_locals).
+/// So, the names here are those of the renumbered functions. For functions
+/// parsed from bitcode, renumbering doesn't change any names.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct FunctionDiff {
+    pub name: String,
+    /// Basic blocks in the new version but not the old one
+    pub added_blocks: Vec<Name>,
+    /// Basic blocks in the old version but not the new one
+    pub removed_blocks: Vec<Name>,
+    /// Basic blocks in both versions whose instructions or terminator differ.
+    ///
+    /// If all of `added_blocks`, `removed_blocks`, and `changed_blocks` are
+    /// empty, the function's basic blocks are unchanged, but something else
+    /// about it (e.g., its attributes or linkage) changed.
+    pub changed_blocks: Vec<Name>,
+}
+
+impl ModuleDiff {
+    /// Compute the differences between the `old` and `new` `Module`s.
+    /// See [`Module::diff()`](../module/struct.Module.html#method.diff).
+    pub fn new(old: &Module, new: &Module) -> Self {
+        let mut diff = Self::default();
+
+        let new_funcs: HashMap<&str, &Function> =
+            new.functions.iter().map(|f| (f.name.as_str(), f)).collect();
+        for old_func in &old.functions {
+            match new_funcs.get(old_func.name.as_str()) {
+                None => diff.removed_functions.push(old_func.name.clone()),
+                Some(new_func) => {
+                    if let Some(func_diff) = FunctionDiff::new(old_func, new_func) {
+                        diff.changed_functions.push(func_diff);
+                    }
+                },
+            }
+        }
+        diff.added_functions = new
+            .functions
+            .iter()
+            .filter(|f| old.get_func_by_name(&f.name).is_none())
+            .map(|f| f.name.clone())
+            .collect();
+
+        let old_globals = globals(old);
+        let new_globals = globals(new);
+        for (name, old_global) in &old_globals {
+            match new_globals.iter().find(|(n, _)| n == name) {
+                None => diff.removed_globals.push((*name).clone()),
+                Some((_, new_global)) if new_global != old_global => {
+                    diff.changed_globals.push((*name).clone())
+                },
+                Some(_) => {},
+            }
+        }
+        diff.added_globals = new_globals
+            .iter()
+            .filter(|(name, _)| !old_globals.iter().any(|(n, _)| n == name))
+            .map(|(name, _)| (*name).clone())
+            .collect();
+
+        diff
+    }
+
+    /// Are the two `Module`s the same (ignoring debug information and the
+    /// numbering of unnamed locals)?
+    pub fn is_empty(&self) -> bool {
+        self.added_functions.is_empty()
+            && self.removed_functions.is_empty()
+            && self.changed_functions.is_empty()
+            && self.added_globals.is_empty()
+            && self.removed_globals.is_empty()
+            && self.changed_globals.is_empty()
+    }
+}
+
+impl FunctionDiff {
+    /// Compare two versions of a `Function`, returning `None` if they are
+    /// the same (ignoring debug information and the numbering of unnamed
+    /// locals)
+    fn new(old: &Function, new: &Function) -> Option<Self> {
+        let old = normalize(old);
+        let new = normalize(new);
+        if old == new {
+            return None;
+        }
+        let new_blocks: HashMap<&Name, &BasicBlock> =
+            new.basic_blocks.iter().map(|bb| (&bb.name, bb)).collect();
+        let mut diff = Self {
+            name: old.name.clone(),
+            added_blocks: vec![],
+            removed_blocks: vec![],
+            changed_blocks: vec![],
+        };
+        for old_bb in &old.basic_blocks {
+            match new_blocks.get(&old_bb.name) {
+                None => diff.removed_blocks.push(old_bb.name.clone()),
+                Some(&new_bb) if new_bb != old_bb => diff.changed_blocks.push(old_bb.name.clone()),
+                Some(_) => {},
+            }
+        }
+        diff.added_blocks = new
+            .basic_blocks
+            .iter()
+            .filter(|bb| !old.basic_blocks.iter().any(|old_bb| old_bb.name == bb.name))
+            .map(|bb| bb.name.clone())
+            .collect();
+        Some(diff)
+    }
+}
+
+/// A copy of the `Function` without debug information, with its unnamed
+/// locals renumbered, and without its attribute group ID (which depends on
+/// the other functions in the module)
+fn normalize(func: &Function) -> Function {
+    let mut func = func.clone();
+    func.strip_debug_info();
+    func.rename_locals(false);
+    func.function_attribute_group = None;
+    func
+}
+
+#[derive(PartialEq, Clone, Debug)]
+enum Global {
+    Var(Box<GlobalVariable>),
+    Alias(GlobalAlias),
+}
+
+/// The global variables and aliases of the `Module`, in order, without debug
+/// information
+fn globals(module: &Module) -> Vec<(&Name, Global)> {
+    let vars = module.global_vars.iter().map(|var| {
+        #[allow(unused_mut)]
+        let mut stripped = var.clone();
+        #[cfg(feature = "llvm-9-or-greater")]
+        {
+            stripped.debugloc = None;
+        }
+        (&var.name, Global::Var(Box::new(stripped)))
+    });
+    let aliases = module
+        .global_aliases
+        .iter()
+        .map(|alias| (&alias.name, Global::Alias(alias.clone())));
+    vars.chain(aliases).collect()
+}
@@ -0,0 +1,196 @@
+use crate::name::Name;
+use std::fmt;
+
+/// Errors which can occur when creating a [`Module`](../module/struct.Module.html)
+/// from LLVM bitcode.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Error {
+    /// Failed to read the bitcode file. The `String` is LLVM's error message.
+    ReadFailed(String),
+    /// LLVM failed to parse the contents of the file as bitcode
+    InvalidBitcode,
+    /// LLVM's module verifier found the bitcode to be malformed. The `String`
+    /// is the verifier's error message.
+    /// See [`Module::from_bc_path_verified()`](../module/struct.Module.html#method.from_bc_path_verified).
+    VerificationFailed(String),
+    /// Running LLVM passes on the module failed, e.g., because a pass name
+    /// wasn't recognized. The `String` is LLVM's error message.
+    /// See [`Module::from_bc_path_with_passes()`](../module/struct.Module.html#method.from_bc_path_with_passes).
+    PassesFailed(String),
+    /// LLVM parsed the bitcode, but it contains something which `llvm-ir`
+    /// didn't expect or doesn't support. A panic inside `llvm-ir` while
+    /// converting the bitcode is also reported this way, without a location.
+    Unsupported {
+        /// Description of the problem
+        message: String,
+        /// Name of the function being processed when the problem occurred,
+        /// if any
+        function: Option<String>,
+        /// Name of the basic block being processed when the problem occurred,
+        /// if any
+        block: Option<Name>,
+        /// LLVM's textual representation of the instruction being processed
+        /// when the problem occurred, if any
+        instruction: Option<String>,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::ReadFailed(msg) => write!(f, "failed to read bitcode file: {}", msg),
+            Error::InvalidBitcode => write!(f, "failed to parse bitcode"),
+            Error::VerificationFailed(msg) => write!(f, "module failed verification: {}", msg),
+            Error::PassesFailed(msg) => write!(f, "failed to run LLVM passes: {}", msg),
+            Error::Unsupported {
+                message,
+                function,
+                block,
+                instruction,
+            } => {
+                write!(f, "{}", message)?;
+                if let Some(function) = function {
+                    write!(f, "\n  in function {:?}", function)?;
+                }
+                if let Some(block) = block {
+                    write!(f, "\n  in block {}", block)?;
+                }
+                if let Some(instruction) = instruction {
+                    write!(f, "\n  in instruction {}", instruction.trim())?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Errors which can occur when adding or removing the functions and globals
+/// of a [`Module`](../module/struct.Module.html), e.g., with
+/// [`Module::add_function()`](../module/struct.Module.html#method.add_function).
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum EditError {
+    /// The `Module` already has a function, global variable, or alias with
+    /// this name
+    NameTaken(Name),
+    /// The `Module` has no function, global variable, or alias (of the kind
+    /// being removed) with this name
+    NotFound(Name),
+    /// The function, global variable, or alias can't be removed, because other
+    /// functions, global variables, or aliases still refer to it
+    StillReferenced {
+        /// Name of the function, global variable, or alias being removed
+        name: Name,
+        /// Names of the functions, global variables, and aliases referring to
+        /// it
+        referrers: Vec<Name>,
+    },
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EditError::NameTaken(name) => {
+                write!(f, "the module already has a global value named ")?;
+                name.fmt_with_sigil(f, '@')
+            },
+            EditError::NotFound(name) => {
+                write!(f, "the module has no global value named ")?;
+                name.fmt_with_sigil(f, '@')
+            },
+            EditError::StillReferenced { name, referrers } => {
+                name.fmt_with_sigil(f, '@')?;
+                write!(f, " is still referred to by ")?;
+                for (i, referrer) in referrers.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    referrer.fmt_with_sigil(f, '@')?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+impl std::error::Error for EditError {}
+
+// ********* //
+// from_llvm //
+// ********* //
+
+use crate::llvm_sys::LLVMValueRef;
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+
+impl Error {
+    /// An `Error::Unsupported` with the given message. Its location is filled
+    /// in with `in_function()`, `in_block()`, and `in_instruction()` as it is
+    /// returned from the conversion.
+    pub(crate) fn unsupported(message: impl Into<String>) -> Self {
+        Error::Unsupported {
+            message: message.into(),
+            function: None,
+            block: None,
+            instruction: None,
+        }
+    }
+
+    /// Record the function being processed when this error occurred, unless
+    /// it is already recorded
+    pub(crate) fn in_function(mut self, name: String) -> Self {
+        if let Error::Unsupported { function, .. } = &mut self {
+            if function.is_none() {
+                *function = Some(name);
+            }
+        }
+        self
+    }
+
+    /// Record the basic block being processed when this error occurred, unless
+    /// it is already recorded
+    pub(crate) fn in_block(mut self, name: &Name) -> Self {
+        if let Error::Unsupported { block, .. } = &mut self {
+            if block.is_none() {
+                *block = Some(name.clone());
+            }
+        }
+        self
+    }
+
+    /// Record the instruction or terminator being processed when this error
+    /// occurred, unless it is already recorded
+    pub(crate) fn in_instruction(mut self, inst: LLVMValueRef) -> Self {
+        if let Error::Unsupported { instruction, .. } = &mut self {
+            if instruction.is_none() {
+                *instruction = Some(unsafe { crate::from_llvm::print_to_string(inst) });
+            }
+        }
+        self
+    }
+
+    /// An `Error::Unsupported` describing the panic with the given payload
+    pub(crate) fn from_panic(payload: Box<dyn Any + Send>) -> Self {
+        if let Some(s) = payload.downcast_ref::<&str>() {
+            Error::unsupported(*s)
+        } else if let Some(s) = payload.downcast_ref::<String>() {
+            Error::unsupported(s.clone())
+        } else {
+            Error::unsupported("unknown error")
+        }
+    }
+}
+
+/// Run the given conversion, turning a panic into an `Error::Unsupported`.
+///
+/// Conversions report IR which `llvm-ir` doesn't support by returning an
+/// `Error`; this is only a last-resort backstop for the internal assertions
+/// which remain, e.g., on the number of operands of an instruction. Whatever
+/// state `f` was building when it panicked must be discarded. Note that the
+/// panic is still reported by the panic hook, and if panics abort (with
+/// `panic = "abort"`), so does this.
+pub(crate) fn catch_panic<T>(f: impl FnOnce() -> Result<T, Error>) -> Result<T, Error> {
+    panic::catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|payload| Err(Error::from_panic(payload)))
+}
@@ -1,13 +1,27 @@
 pub use crate::iterators::*;
 use crate::llvm_sys::*;
+#[cfg(feature = "llvm-9-or-greater")]
+use llvm_sys::debuginfo::{LLVMDIFileGetDirectory, LLVMDIFileGetFilename};
 use std::ffi::CStr;
 use std::os::raw::c_char;
 
 // We convert all LLVM strings to owned Strings (which involves a copy)
-// partly because we intend to serialize/deserialize our ASTs eventually
+// partly because we intend to serialize/deserialize our ASTs eventually.
+// LLVM doesn't require strings to be valid UTF-8, so any invalid sequences are
+// replaced with U+FFFD.
 pub unsafe fn raw_to_string(raw: *const c_char) -> String {
     let cstr = CStr::from_ptr(raw);
-    cstr.to_str().expect("Failed to convert CStr").into()
+    cstr.to_string_lossy().into_owned()
+}
+
+// For strings which LLVM gives us along with their length, and which therefore
+// may contain nul bytes
+pub unsafe fn raw_to_string_with_len(raw: *const c_char, len: usize) -> String {
+    String::from_utf8_lossy(&raw_to_bytes_with_len(raw, len)).into_owned()
+}
+
+pub unsafe fn raw_to_bytes_with_len(raw: *const c_char, len: usize) -> Vec<u8> {
+    std::slice::from_raw_parts(raw as *const u8, len).to_vec()
 }
 
 macro_rules! wrap {
@@ -40,7 +54,7 @@ macro_rules! wrap_with_len {
             debug_assert!(!arg.is_null());
             let mut len = 0;
             let ptr = $llvmFunc(arg, &mut len);
-            raw_to_string(ptr)
+            raw_to_string_with_len(ptr, len as usize)
         }
     };
 }
@@ -55,7 +69,7 @@ macro_rules! wrap_with_len_maybe_null {
             if ptr.is_null() {
                 None
             } else {
-                Some(raw_to_string(ptr))
+                Some(raw_to_string_with_len(ptr, len as usize))
             }
         }
     };
@@ -74,8 +88,30 @@ wrap_with_len!(LLVMGetValueName2, LLVMValueRef, get_value_name);
 wrap_maybe_null!(LLVMGetStructName, LLVMTypeRef, get_struct_name);
 wrap_maybe_null!(LLVMGetSection, LLVMValueRef, get_section);
 wrap_maybe_null!(LLVMGetGC, LLVMValueRef, get_gc);
-wrap!(LLVMGetBasicBlockName, LLVMBasicBlockRef, get_bb_name);
-wrap!(LLVMPrintValueToString, LLVMValueRef, print_to_string);
+
+// Value names may be arbitrary byte strings, so for `Name`s we get the raw
+// bytes rather than a `String`
+pub unsafe fn get_value_name_bytes(v: LLVMValueRef) -> Vec<u8> {
+    debug_assert!(!v.is_null());
+    let mut len = 0;
+    let ptr = LLVMGetValueName2(v, &mut len);
+    raw_to_bytes_with_len(ptr, len)
+}
+
+pub unsafe fn get_bb_name_bytes(bb: LLVMBasicBlockRef) -> Vec<u8> {
+    debug_assert!(!bb.is_null());
+    get_value_name_bytes(LLVMBasicBlockAsValue(bb))
+}
+
+// Unlike the strings above, the string returned by `LLVMPrintValueToString` is
+// owned by the caller, so we must free it
+pub unsafe fn print_to_string(v: LLVMValueRef) -> String {
+    debug_assert!(!v.is_null());
+    let ptr = LLVMPrintValueToString(v);
+    let s = raw_to_string(ptr);
+    LLVMDisposeMessage(ptr);
+    s
+}
 // wrap!(LLVMPrintTypeToString, LLVMTypeRef, print_type_to_string);
 wrap_with_len!(
     LLVMGetStringAttributeKind,
@@ -95,6 +131,14 @@ wrap_with_len_maybe_null!(
     LLVMValueRef,
     get_debugloc_directory
 );
+#[cfg(feature = "llvm-9-or-greater")]
+wrap_with_len_maybe_null!(LLVMDIFileGetFilename, LLVMMetadataRef, get_difile_filename);
+#[cfg(feature = "llvm-9-or-greater")]
+wrap_with_len_maybe_null!(
+    LLVMDIFileGetDirectory,
+    LLVMMetadataRef,
+    get_difile_directory
+);
 
 // Panics if the LLVMValueRef is not a basic block
 pub unsafe fn op_to_bb(op: LLVMValueRef) -> LLVMBasicBlockRef {
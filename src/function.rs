@@ -1,18 +1,44 @@
+use crate::analysis::LoopInfo;
 #[cfg(feature = "llvm-9-or-greater")]
-use crate::debugloc::{DebugLoc, HasDebugLoc};
-use crate::module::{Comdat, DLLStorageClass, Linkage, Visibility};
+use crate::debugloc::{DebugLoc, HasDebugLoc, HasDebugLocMut, Subprogram};
+use crate::instruction::{Call, Instruction};
+use crate::intrinsic::{callee_name, has_base_name};
+use crate::module::{AddrSpace, Comdat, DLLStorageClass, Linkage, Module, Visibility};
+use crate::name::MapLocalNames;
+use crate::operand::{HasOperands, Operand, OperandRef};
+use crate::terminator::{Br, Terminator};
+use crate::text::{self, TextOptions};
+use crate::transform::replace_uses;
 use crate::types::{TypeRef, Typed, Types};
-use crate::{BasicBlock, ConstantRef, Name};
+use crate::{BasicBlock, Constant, ConstantRef, Name};
+use either::Either;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::num::NonZeroU32;
 
 /// See [LLVM 14 docs on Functions](https://releases.llvm.org/14.0.0/docs/LangRef.html#functions)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Function {
     pub name: String,
     pub parameters: Vec<Parameter>,
     pub is_var_arg: bool,
     pub return_type: TypeRef,
+    /// The blocks of the function, in layout order (the order they appear in
+    /// the function's text). The first block is always the entry block; see
+    /// [`entry_block()`](#method.entry_block). Functions which are only
+    /// declared (or whose bodies were skipped) have no blocks.
     pub basic_blocks: Vec<BasicBlock>,
-    pub function_attributes: Vec<FunctionAttribute>, // llvm-hs-pure has Vec<Either<GroupID, FunctionAttribute>>, but I'm not sure how the GroupID ones come about
+    pub function_attributes: Vec<FunctionAttribute>,
+    /// The attribute group (in `Module::function_attribute_groups`) containing
+    /// exactly the `function_attributes`, or `None` if there are no
+    /// `function_attributes`. This corresponds to the `#0`-style group
+    /// reference in a `.ll` file.
+    pub function_attribute_group: Option<GroupID>,
+    /// Attributes of the function's return value, e.g., `noalias`,
+    /// `nonnull`, `zeroext`, or `signext`. Call sites have their own; see
+    /// `Call::return_attributes`.
     pub return_attributes: Vec<ParameterAttribute>,
     pub linkage: Linkage,
     pub visibility: Visibility,
@@ -20,7 +46,13 @@ pub struct Function {
     pub calling_convention: CallingConvention,
     pub section: Option<String>,
     pub comdat: Option<Comdat>, // llvm-hs-pure has Option<String>, I'm not sure why
-    pub alignment: u32,
+    /// The address space the function is in. This is usually `0`, but
+    /// targets with a separate program memory (e.g., AVR) put functions in
+    /// their `DataLayout`'s `program_address_space`.
+    pub addr_space: AddrSpace,
+    /// The explicit alignment of the function's code, in bytes, or `None` if
+    /// it isn't specified
+    pub alignment: Option<NonZeroU32>,
     /// See [LLVM 14 docs on Garbage Collector Strategy Names](https://releases.llvm.org/14.0.0/docs/LangRef.html#gc)
     pub garbage_collector_name: Option<String>,
     // pub prefix: Option<ConstantRef>,  // appears to not be exposed in the LLVM C API, only the C++ API
@@ -28,6 +60,10 @@ pub struct Function {
     pub personality_function: Option<ConstantRef>,
     #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
+    /// The source function this function was compiled from, if it has debug
+    /// info. See [`subprogram()`](#method.subprogram).
+    #[cfg(feature = "llvm-9-or-greater")]
+    pub subprogram: Option<Subprogram>,
     // --TODO not yet implemented-- pub metadata: Vec<(String, MetadataRef<MetadataNode>)>,
 }
 
@@ -48,12 +84,90 @@ impl HasDebugLoc for Function {
     }
 }
 
+#[cfg(feature = "llvm-9-or-greater")]
+impl HasDebugLocMut for Function {
+    fn get_debug_loc_mut(&mut self) -> &mut Option<DebugLoc> {
+        &mut self.debugloc
+    }
+}
+
 impl Function {
     /// Get the `BasicBlock` having the given `Name` (if any).
     pub fn get_bb_by_name(&self, name: &Name) -> Option<&BasicBlock> {
         self.basic_blocks.iter().find(|bb| &bb.name == name)
     }
 
+    /// Get the entry block of the function, which is always the first of its
+    /// `basic_blocks`. Returns `None` if the function has no blocks (e.g.,
+    /// because it is only a declaration).
+    pub fn entry_block(&self) -> Option<&BasicBlock> {
+        self.basic_blocks.first()
+    }
+
+    /// Get the index in `basic_blocks` of the block with the given `Name` (if
+    /// any). The entry block is at index 0.
+    pub fn block_position(&self, name: &Name) -> Option<usize> {
+        self.basic_blocks.iter().position(|bb| &bb.name == name)
+    }
+
+    /// Iterate over the parameters of the function, as pairs of each one's
+    /// `Name` and type
+    pub fn params_and_types(&self) -> impl Iterator<Item = (&Name, &TypeRef)> {
+        self.parameters.iter().map(|param| (&param.name, &param.ty))
+    }
+
+    /// Get the value of the string function attribute with the given kind
+    /// (e.g., `"target-cpu"` or `"target-features"`), if the function has
+    /// that attribute.
+    pub fn get_string_attribute(&self, kind: &str) -> Option<&str> {
+        self.function_attributes.iter().find_map(|attr| match attr {
+            FunctionAttribute::StringAttribute { kind: k, value } if k == kind => {
+                Some(value.as_str())
+            },
+            _ => None,
+        })
+    }
+
+    /// Is this `Function` a coroutine which hasn't been split yet?
+    ///
+    /// LLVM 14 and earlier mark such functions with the `"coroutine.presplit"`
+    /// string attribute, which the coroutine passes remove once they have
+    /// split the coroutine into its ramp, resume, and destroy functions.
+    pub fn is_presplit_coroutine(&self) -> bool {
+        self.get_string_attribute("coroutine.presplit").is_some()
+    }
+
+    /// Get the demangled name of this `Function`, or `None` if its name isn't
+    /// a mangled symbol (e.g., because it is a C function).
+    ///
+    /// Rust symbols are demangled if the `rustc-demangle` feature is enabled,
+    /// and C++ symbols if the `cpp_demangle` feature is enabled. Demangled Rust
+    /// names don't include the trailing hash: e.g., `core::ptr::drop_in_place`
+    /// rather than `core::ptr::drop_in_place::h0123456789abcdef`.
+    #[cfg(any(feature = "rustc-demangle", feature = "cpp_demangle"))]
+    pub fn demangled_name(&self) -> Option<String> {
+        #[cfg(feature = "rustc-demangle")]
+        if let Ok(demangled) = rustc_demangle::try_demangle(&self.name) {
+            return Some(format!("{:#}", demangled));
+        }
+        #[cfg(feature = "cpp_demangle")]
+        if let Ok(symbol) = cpp_demangle::Symbol::new(self.name.as_bytes()) {
+            return symbol.demangle(&Default::default()).ok();
+        }
+        None
+    }
+
+    /// Get the source function this `Function` was compiled from: its name
+    /// and linkage name in the source, the line it is declared on, and its
+    /// compile unit. Returns `None` if the `Function` has no debug info.
+    ///
+    /// The source file the function is in is the `filename` of its
+    /// [`DebugLoc`](../debugloc/struct.DebugLoc.html).
+    #[cfg(feature = "llvm-9-or-greater")]
+    pub fn subprogram(&self) -> Option<&Subprogram> {
+        self.subprogram.as_ref()
+    }
+
     /// A Function instance as empty as possible, using defaults
     pub fn new(name: impl Into<String>) -> Self {
         Self {
@@ -63,6 +177,7 @@ impl Function {
             return_type: Types::blank_for_testing().void(),
             basic_blocks: vec![],
             function_attributes: vec![],
+            function_attribute_group: None,
             return_attributes: vec![],
             linkage: Linkage::Private,
             visibility: Visibility::Default,
@@ -70,16 +185,999 @@ impl Function {
             calling_convention: CallingConvention::C,
             section: None,
             comdat: None,
-            alignment: 4,
+            addr_space: 0,
+            alignment: NonZeroU32::new(4),
             garbage_collector_name: None,
             personality_function: None,
             #[cfg(feature = "llvm-9-or-greater")]
             debugloc: None,
+            #[cfg(feature = "llvm-9-or-greater")]
+            subprogram: None,
+        }
+    }
+
+    /// Renumber the local values and basic blocks of this `Function` in place,
+    /// so that functions which differ only in how their locals are numbered
+    /// compare equal.
+    ///
+    /// Parameters, and then each basic block followed by the results of its
+    /// instructions and terminator, are numbered sequentially in the order
+    /// they appear, as in a `.ll` file. If `drop_names` is `true`, every local
+    /// is numbered; otherwise, locals with string names keep them, and only
+    /// the unnamed ones are renumbered.
+    pub fn rename_locals(&mut self, drop_names: bool) {
+        let mut ctr = 0;
+        let mut new_names: HashMap<Name, Name> = HashMap::new();
+        let mut assign = |name: &Name| {
+            let new_name = match name {
+                Name::Name(_) | Name::Bytes(_) if !drop_names => name.clone(),
+                _ => {
+                    ctr += 1;
+                    Name::Number(ctr - 1)
+                },
+            };
+            new_names.insert(name.clone(), new_name);
+        };
+        for param in &self.parameters {
+            assign(&param.name);
+        }
+        for bb in &self.basic_blocks {
+            assign(&bb.name);
+            for inst in &bb.instrs {
+                if let Some(dest) = inst.try_get_result() {
+                    assign(dest);
+                }
+            }
+            if let Some(result) = bb.term.try_get_result() {
+                assign(result);
+            }
+        }
+        self.apply_renames(&new_names);
+    }
+
+    /// Give each unnamed local value and basic block of this `Function` a
+    /// string name: the name from `debug_names` if there is one, or else
+    /// `arg<N>` for a parameter, `bb<N>` for a basic block, and `v<N>` for
+    /// any other value, where `<N>` is the local's number. If a name is
+    /// already taken, a number is appended to make it unique, as in LLVM.
+    pub(crate) fn name_unnamed_locals(&mut self, debug_names: &HashMap<Name, String>) {
+        let mut taken: HashSet<Name> = HashSet::new();
+        taken.extend(self.parameters.iter().map(|param| param.name.clone()));
+        for bb in &self.basic_blocks {
+            taken.insert(bb.name.clone());
+            taken.extend(
+                bb.instrs
+                    .iter()
+                    .filter_map(|inst| inst.try_get_result().cloned()),
+            );
+            taken.extend(bb.term.try_get_result().cloned());
+        }
+        let mut new_names: HashMap<Name, Name> = HashMap::new();
+        let mut assign = |name: &Name, prefix: &str| {
+            if let Name::Number(n) = name {
+                let base = match debug_names.get(name) {
+                    Some(debug_name) => debug_name.clone(),
+                    None => format!("{}{}", prefix, n),
+                };
+                let mut new_name = Name::from(base.clone());
+                let mut suffix = 0;
+                while taken.contains(&new_name) {
+                    suffix += 1;
+                    new_name = Name::from(format!("{}{}", base, suffix));
+                }
+                taken.insert(new_name.clone());
+                new_names.insert(name.clone(), new_name);
+            }
+        };
+        for param in &self.parameters {
+            assign(&param.name, "arg");
+        }
+        for bb in &self.basic_blocks {
+            assign(&bb.name, "bb");
+            for inst in &bb.instrs {
+                if let Some(dest) = inst.try_get_result() {
+                    assign(dest, "v");
+                }
+            }
+            if let Some(result) = bb.term.try_get_result() {
+                assign(result, "v");
+            }
+        }
+        self.apply_renames(&new_names);
+    }
+
+    /// Rename each local (wherever it is defined or used) which is a key of
+    /// `new_names` to the corresponding value
+    fn apply_renames(&mut self, new_names: &HashMap<Name, Name>) {
+        let mut rename = |name: &mut Name| {
+            if let Some(new_name) = new_names.get(name) {
+                *name = new_name.clone();
+            }
+        };
+        for param in &mut self.parameters {
+            rename(&mut param.name);
+        }
+        for bb in &mut self.basic_blocks {
+            rename(&mut bb.name);
+            bb.instrs.map_local_names(&mut rename);
+            bb.term.map_local_names(&mut rename);
+        }
+    }
+
+    /// Remove all debug information from this `Function` in place: the
+    /// `DebugLoc`s of the function and of its instructions and terminators,
+    /// its `Subprogram`, and any calls to debug intrinsics such as
+    /// `llvm.dbg.value`.
+    pub fn strip_debug_info(&mut self) {
+        #[cfg(feature = "llvm-9-or-greater")]
+        {
+            self.debugloc = None;
+            self.subprogram = None;
+        }
+        for bb in &mut self.basic_blocks {
+            bb.instrs.retain(|inst| !is_debug_intrinsic_call(inst));
+            #[cfg(feature = "llvm-9-or-greater")]
+            {
+                for inst in &mut bb.instrs {
+                    *inst.get_debug_loc_mut() = None;
+                }
+                *bb.term.get_debug_loc_mut() = None;
+            }
+        }
+    }
+
+    /// Compute a hash of the structure of this `Function`, e.g., for finding
+    /// duplicate functions across many modules.
+    ///
+    /// The hash covers the function's signature (its return type, parameter
+    /// types, and their attributes, and whether it is variadic) and its body:
+    /// the opcodes, types, operands (including constants), and other fields
+    /// of its instructions and terminators, and how its blocks connect.
+    /// Locals (parameters, basic blocks, and instruction results) are hashed
+    /// by position rather than by name, as if renumbered with
+    /// [`rename_locals(true)`](#method.rename_locals), and debug information
+    /// is ignored as in [`strip_debug_info()`](#method.strip_debug_info).
+    /// So, functions which differ only in those, or in their own names,
+    /// linkage, or function attributes, hash the same. References to globals
+    /// (including to other functions) are hashed by name.
+    ///
+    /// Equal hashes make it very likely, but don't guarantee, that two
+    /// functions are structurally identical. The hash is the same across
+    /// runs of a program, but may change with the version of `llvm-ir` or of
+    /// Rust.
+    pub fn structural_hash(&self) -> u64 {
+        let mut positions: HashMap<&Name, usize> = HashMap::new();
+        for local in self.locals() {
+            let position = positions.len();
+            positions.entry(local).or_insert(position);
+        }
+        let mut renumber = |name: &mut Name| {
+            if let Some(&position) = positions.get(&*name) {
+                *name = Name::Number(position);
+            }
+        };
+
+        let mut hasher = DefaultHasher::new();
+        self.return_type.hash(&mut hasher);
+        self.return_attributes.hash(&mut hasher);
+        self.is_var_arg.hash(&mut hasher);
+        self.parameters.len().hash(&mut hasher);
+        for param in &self.parameters {
+            param.ty.hash(&mut hasher);
+            param.attributes.hash(&mut hasher);
+        }
+        self.basic_blocks.len().hash(&mut hasher);
+        for bb in &self.basic_blocks {
+            // only one instruction is copied at a time, to be renumbered
+            let instrs: Vec<&Instruction> = bb
+                .instrs
+                .iter()
+                .filter(|inst| !is_debug_intrinsic_call(inst))
+                .collect();
+            instrs.len().hash(&mut hasher);
+            for inst in instrs {
+                let mut inst = inst.clone();
+                inst.map_local_names(&mut renumber);
+                #[cfg(feature = "llvm-9-or-greater")]
+                {
+                    *inst.get_debug_loc_mut() = None;
+                }
+                inst.hash(&mut hasher);
+            }
+            let mut term = bb.term.clone();
+            term.map_local_names(&mut renumber);
+            #[cfg(feature = "llvm-9-or-greater")]
+            {
+                *term.get_debug_loc_mut() = None;
+            }
+            term.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// Check whether this `Function` and `other` are the same up to the names
+    /// of their locals, and if so, return the mapping from each local
+    /// (parameter, basic block, or instruction result) of this `Function` to
+    /// the corresponding local of `other`. E.g., this can check that a
+    /// transformation didn't change a function, or that two compilers
+    /// produced the same body.
+    ///
+    /// Locals correspond by position, in the order
+    /// [`rename_locals()`](#method.rename_locals) numbers them. Everything
+    /// which [`structural_hash()`](#method.structural_hash) covers is compared,
+    /// and the things it ignores are ignored here too. So, functions for
+    /// which this returns `Some` have the same `structural_hash()`.
+    pub fn is_isomorphic_to(&self, other: &Function) -> Option<HashMap<Name, Name>> {
+        if self.return_type != other.return_type
+            || self.return_attributes != other.return_attributes
+            || self.is_var_arg != other.is_var_arg
+            || self.parameters.len() != other.parameters.len()
+            || self.basic_blocks.len() != other.basic_blocks.len()
+            || self.locals().count() != other.locals().count()
+        {
+            return None;
+        }
+        for (param, other_param) in self.parameters.iter().zip(&other.parameters) {
+            if param.ty != other_param.ty || param.attributes != other_param.attributes {
+                return None;
+            }
+        }
+
+        let mapping: HashMap<Name, Name> = self
+            .locals()
+            .cloned()
+            .zip(other.locals().cloned())
+            .collect();
+        let mut rename = |name: &mut Name| {
+            if let Some(other_name) = mapping.get(&*name) {
+                *name = other_name.clone();
+            }
+        };
+        for (bb, other_bb) in self.basic_blocks.iter().zip(&other.basic_blocks) {
+            let instrs = bb
+                .instrs
+                .iter()
+                .filter(|inst| !is_debug_intrinsic_call(inst));
+            let other_instrs = other_bb
+                .instrs
+                .iter()
+                .filter(|inst| !is_debug_intrinsic_call(inst));
+            if instrs.clone().count() != other_instrs.clone().count() {
+                return None;
+            }
+            // rename a copy of each of our instructions, and give it the
+            // other's `DebugLoc`, so that the other's needn't be copied too
+            for (inst, other_inst) in instrs.zip(other_instrs) {
+                let mut inst = inst.clone();
+                inst.map_local_names(&mut rename);
+                #[cfg(feature = "llvm-9-or-greater")]
+                {
+                    *inst.get_debug_loc_mut() = other_inst.get_debug_loc().clone();
+                }
+                if &inst != other_inst {
+                    return None;
+                }
+            }
+            let mut term = bb.term.clone();
+            term.map_local_names(&mut rename);
+            #[cfg(feature = "llvm-9-or-greater")]
+            {
+                *term.get_debug_loc_mut() = other_bb.term.get_debug_loc().clone();
+            }
+            if term != other_bb.term {
+                return None;
+            }
+        }
+        Some(mapping)
+    }
+
+    /// Iterate over the locals defined in this `Function`: its parameters,
+    /// and then each basic block followed by the results of its instructions
+    /// and terminator, in the order they appear
+    fn locals(&self) -> impl Iterator<Item = &Name> {
+        self.parameters
+            .iter()
+            .map(|param| &param.name)
+            .chain(self.basic_blocks.iter().flat_map(|bb| {
+                std::iter::once(&bb.name)
+                    .chain(bb.instrs.iter().filter_map(|inst| inst.try_get_result()))
+                    .chain(bb.term.try_get_result())
+            }))
+    }
+
+    /// Split the basic block with the given name in two, before the
+    /// instruction at index `at_index`, and return the name of the new block.
+    ///
+    /// The new block is placed right after the original one, and gets the
+    /// instructions from `at_index` on, and the terminator. The original block
+    /// ends with an unconditional `Br` to the new block instead. `Phi`s in the
+    /// successors of the new block are updated to refer to it rather than to
+    /// the original block.
+    ///
+    /// As in LLVM, if the original block is named `%foo`, the new block is
+    /// named `%foo.split` (with a number appended if that name is taken).
+    /// If the original block is unnamed, so is the new one, and the unnamed
+    /// locals of the `Function` are then renumbered as in
+    /// [`rename_locals()`](#method.rename_locals).
+    ///
+    /// Panics if there is no block with the given name, or if `at_index` is
+    /// past the end of its instructions or among the `Phi`s (and
+    /// exception-handling pad) at its start.
+    pub fn split_block(&mut self, block_name: &Name, at_index: usize) -> Name {
+        let index = self
+            .block_position(block_name)
+            .unwrap_or_else(|| panic!("split_block: no block named {}", block_name));
+        let bb = &mut self.basic_blocks[index];
+        assert!(
+            at_index <= bb.instrs.len(),
+            "split_block: index {} is past the end of block {}",
+            at_index,
+            block_name
+        );
+        let num_phis = bb
+            .instrs
+            .iter()
+            .take_while(|inst| matches!(inst, Instruction::Phi(_)))
+            .count();
+        let prefix_len = match bb.instrs.get(num_phis) {
+            Some(Instruction::LandingPad(_))
+            | Some(Instruction::CatchPad(_))
+            | Some(Instruction::CleanupPad(_)) => num_phis + 1,
+            _ => num_phis,
+        };
+        assert!(
+            at_index >= prefix_len,
+            "split_block: can't split block {} among the phis or pad at its start",
+            block_name
+        );
+
+        let new_name = match block_name {
+            Name::Number(_) => Name::Number(usize::MAX),
+            _ => {
+                let mut base = block_name.as_bytes().expect("should have a name").to_vec();
+                base.extend_from_slice(b".split");
+                let taken = |name: &Name| self.basic_blocks.iter().any(|bb| &bb.name == name);
+                let mut new_name = Name::from(base.clone());
+                let mut suffix = 0;
+                while taken(&new_name) {
+                    suffix += 1;
+                    let mut bytes = base.clone();
+                    bytes.extend_from_slice(suffix.to_string().as_bytes());
+                    new_name = Name::from(bytes);
+                }
+                new_name
+            },
+        };
+        let bb = &mut self.basic_blocks[index];
+        let br = Terminator::Br(Br {
+            dest: new_name.clone(),
+            loop_metadata: None,
+            #[cfg(feature = "llvm-9-or-greater")]
+            debugloc: None,
+        });
+        let new_bb = BasicBlock {
+            name: new_name.clone(),
+            instrs: bb.instrs.split_off(at_index),
+            term: std::mem::replace(&mut bb.term, br),
+        };
+        let successors: HashSet<Name> = new_bb.term.successors().cloned().collect();
+        self.basic_blocks.insert(index + 1, new_bb);
+        for bb in &mut self.basic_blocks {
+            if successors.contains(&bb.name) {
+                retarget_phis(bb, block_name, &new_name);
+            }
+        }
+
+        if let Name::Number(_) = new_name {
+            self.rename_locals(false);
+            self.basic_blocks[index + 1].name.clone()
+        } else {
+            new_name
+        }
+    }
+
+    /// Merge each basic block into its predecessor, where the predecessor ends
+    /// with an unconditional `Br` to the block and is its only predecessor,
+    /// until there are no such blocks left. (The entry block is never merged
+    /// into anything.)
+    ///
+    /// The merged block's `Phi`s (which each have just one incoming value)
+    /// are removed, and their uses replaced with their incoming values. `Phi`s
+    /// in the merged block's successors are updated to refer to the
+    /// predecessor instead.
+    ///
+    /// If any of the merged blocks were unnamed, the unnamed locals of the
+    /// `Function` are then renumbered as in
+    /// [`rename_locals()`](#method.rename_locals).
+    ///
+    /// Returns the number of blocks merged away.
+    pub fn merge_single_successor_blocks(&mut self) -> usize {
+        let mut merged = 0;
+        let mut merged_unnamed = false;
+        loop {
+            let mut num_preds: HashMap<&Name, usize> = HashMap::new();
+            for bb in &self.basic_blocks {
+                for succ in bb.term.successors() {
+                    *num_preds.entry(succ).or_default() += 1;
+                }
+            }
+            let entry = self.entry_block().map(|bb| &bb.name);
+            let candidate =
+                self.basic_blocks
+                    .iter()
+                    .enumerate()
+                    .find_map(|(pred, bb)| match &bb.term {
+                        Terminator::Br(br)
+                            if br.dest != bb.name
+                                && Some(&br.dest) != entry
+                                && num_preds.get(&br.dest) == Some(&1) =>
+                        {
+                            let succ = self
+                                .basic_blocks
+                                .iter()
+                                .position(|succ| succ.name == br.dest)?;
+                            Some((pred, succ))
+                        },
+                        _ => None,
+                    });
+            let (pred, succ) = match candidate {
+                Some(candidate) => candidate,
+                None => {
+                    if merged_unnamed {
+                        self.rename_locals(false);
+                    }
+                    return merged;
+                },
+            };
+
+            let mut succ_bb = self.basic_blocks.remove(succ);
+            merged_unnamed |= matches!(succ_bb.name, Name::Number(_));
+            let pred = if succ < pred { pred - 1 } else { pred };
+            let pred_name = self.basic_blocks[pred].name.clone();
+            let mut replacements = HashMap::new();
+            succ_bb.instrs.retain(|inst| match inst {
+                Instruction::Phi(phi) => {
+                    let incoming = phi
+                        .incoming_values
+                        .iter()
+                        .find(|(_, block)| *block == pred_name)
+                        .or_else(|| phi.incoming_values.first());
+                    if let Some((value, _)) = incoming {
+                        replacements.insert(phi.dest.clone(), value.clone());
+                    }
+                    false
+                },
+                _ => true,
+            });
+            let successors: HashSet<Name> = succ_bb.term.successors().cloned().collect();
+            let pred_bb = &mut self.basic_blocks[pred];
+            pred_bb.instrs.append(&mut succ_bb.instrs);
+            pred_bb.term = succ_bb.term;
+            for bb in &mut self.basic_blocks {
+                if successors.contains(&bb.name) {
+                    retarget_phis(bb, &succ_bb.name, &pred_name);
+                }
+                for op in bb.instrs.iter_mut().flat_map(Instruction::operand_refs_mut) {
+                    replace_uses(op, &replacements);
+                }
+                for op in bb.term.operand_refs_mut() {
+                    replace_uses(op, &replacements);
+                }
+            }
+            merged += 1;
+        }
+    }
+
+    /// Rebuild the instructions of this `Function` in place, calling `f` on
+    /// each instruction to decide whether to keep it, replace it with any
+    /// number of instructions, or delete it. Terminators are left as they are
+    /// (except for uses of renamed values; see below).
+    ///
+    /// If an instruction with a result is replaced, and none of its
+    /// replacements reuses its name, then the value it defined is taken to be
+    /// the result of the last replacement, and all uses of the old name in
+    /// the `Function` are renamed to that. Uses of a deleted instruction's
+    /// result (or of a replaced one's, if the last replacement has no result)
+    /// are left as they are, so only instructions whose results are unused
+    /// should be deleted.
+    ///
+    /// Returns the renames made, from the old name of each replaced value to
+    /// its new name.
+    pub fn map_instructions(
+        &mut self,
+        mut f: impl FnMut(&Instruction) -> InstrMapResult,
+    ) -> HashMap<Name, Name> {
+        let mut renames: HashMap<Name, Name> = HashMap::new();
+        for bb in &mut self.basic_blocks {
+            for inst in std::mem::take(&mut bb.instrs) {
+                match f(&inst) {
+                    InstrMapResult::Keep => bb.instrs.push(inst),
+                    InstrMapResult::Delete => {},
+                    InstrMapResult::Replace(replacements) => {
+                        let old_name = inst.try_get_result();
+                        let new_name = replacements.last().and_then(Instruction::try_get_result);
+                        if let (Some(old_name), Some(new_name)) = (old_name, new_name) {
+                            let reused = replacements
+                                .iter()
+                                .any(|r| r.try_get_result() == Some(old_name));
+                            if !reused {
+                                renames.insert(old_name.clone(), new_name.clone());
+                            }
+                        }
+                        bb.instrs.extend(replacements);
+                    },
+                }
+            }
+        }
+        // a replacement's result may itself have been replaced. As in
+        // `transform::simplify()`, this terminates even for (unreachable)
+        // self-referential code.
+        let final_names: HashMap<Name, Name> = renames
+            .keys()
+            .map(|old_name| {
+                let mut new_name = &renames[old_name];
+                for _ in 1 .. renames.len() {
+                    match renames.get(new_name) {
+                        Some(next) => new_name = next,
+                        None => break,
+                    }
+                }
+                (old_name.clone(), new_name.clone())
+            })
+            .collect();
+        self.apply_renames(&final_names);
+        final_names
+    }
+
+    /// Render this `Function`'s control-flow graph in the Graphviz DOT format.
+    ///
+    /// Each `BasicBlock` becomes a node listing its instructions and
+    /// terminator. Each control-flow edge is labeled where the terminator
+    /// distinguishes between its successors: `true`/`false` for a `CondBr`,
+    /// the case value (or `default`) for a `Switch`, `normal`/`unwind` for an
+    /// `Invoke`, and so on.
+    pub fn to_dot(&self) -> String {
+        use std::fmt::Write;
+
+        let mut dot = String::new();
+        writeln!(dot, "digraph \"{}\" {{", dot_escape(&self.name)).unwrap();
+        writeln!(dot, "    node [shape=box, fontname=\"monospace\"];").unwrap();
+        for bb in &self.basic_blocks {
+            let mut label = format!("{}:\\l", dot_escape(&bb.name.to_string()));
+            for inst in &bb.instrs {
+                write!(label, "  {}\\l", dot_escape(&inst.to_string())).unwrap();
+            }
+            write!(label, "  {}\\l", dot_escape(&bb.term.to_string())).unwrap();
+            writeln!(
+                dot,
+                "    \"{}\" [label=\"{}\"];",
+                dot_escape(&bb.name.to_string()),
+                label
+            )
+            .unwrap();
+        }
+        for bb in &self.basic_blocks {
+            let src = dot_escape(&bb.name.to_string());
+            for (dest, edge_label) in dot_edges(&bb.term) {
+                let dest = dot_escape(&dest.to_string());
+                match edge_label {
+                    None => writeln!(dot, "    \"{}\" -> \"{}\";", src, dest).unwrap(),
+                    Some(edge_label) => writeln!(
+                        dot,
+                        "    \"{}\" -> \"{}\" [label=\"{}\"];",
+                        src,
+                        dest,
+                        dot_escape(&edge_label)
+                    )
+                    .unwrap(),
+                }
+            }
+        }
+        writeln!(dot, "}}").unwrap();
+        dot
+    }
+
+    /// Write this `Function` as text, in a syntax close to LLVM's `.ll`
+    /// format. See the [`text`](../text/index.html) module.
+    pub fn to_text(&self) -> String {
+        text::write_function(self, &TextOptions::default())
+    }
+
+    /// Write this `Function` as text, as configured by the given
+    /// `TextOptions`. Only the options which apply to a single function
+    /// (`renumber_locals` and `strip_debug_info`) are used.
+    pub fn to_text_with_options(&self, options: &TextOptions) -> String {
+        text::write_function(self, options)
+    }
+
+    /// Compute some static size metrics for this `Function`, e.g., for
+    /// deciding which functions to analyze first. The `Function` must be in
+    /// the given `Module`, and `loop_info` must be the `LoopInfo` of this
+    /// `Function`.
+    ///
+    /// Calls to debug intrinsics such as `llvm.dbg.value` aren't counted,
+    /// either as instructions or as call sites.
+    pub fn size_metrics(&self, module: &Module, loop_info: &LoopInfo) -> SizeMetrics {
+        let mut metrics = SizeMetrics::default();
+        for bb in &self.basic_blocks {
+            for inst in &bb.instrs {
+                if is_debug_intrinsic_call(inst) {
+                    continue;
+                }
+                metrics.instruction_count += 1;
+                match inst {
+                    Instruction::Call(_) => metrics.call_sites += 1,
+                    Instruction::Alloca(alloca) => {
+                        let size_and_align = module
+                            .types
+                            .alloc_size_and_align(&alloca.allocated_type, &module.data_layout);
+                        let num_elements = match alloca.num_elements.as_constant() {
+                            Some(Constant::Int { value, .. }) => Some(*value),
+                            _ => None,
+                        };
+                        match (size_and_align, num_elements) {
+                            (Some((size, align)), Some(num_elements)) => {
+                                let explicit = alloca.alignment.map_or(0, NonZeroU32::get);
+                                let align = align.max(u64::from(explicit));
+                                metrics.stack_bytes = metrics.stack_bytes.div_ceil(align) * align
+                                    + size * num_elements;
+                            },
+                            _ => metrics.has_dynamic_allocas = true,
+                        }
+                    },
+                    _ => {},
+                }
+            }
+            metrics.instruction_count += 1;
+            match &bb.term {
+                Terminator::Invoke(_) => metrics.call_sites += 1,
+                #[cfg(feature = "llvm-9-or-greater")]
+                Terminator::CallBr(_) => metrics.call_sites += 1,
+                _ => {},
+            }
+            metrics.max_loop_depth = metrics.max_loop_depth.max(loop_info.loop_depth(&bb.name));
+        }
+        metrics
+    }
+
+    /// Get the `MemoryEffects` of calling this `Function`, as implied by its
+    /// function attributes (`readnone`, `readonly`, `writeonly`,
+    /// `argmemonly`, `inaccessiblememonly`, and
+    /// `inaccessiblemem_or_argmemonly`) and, for LLVM intrinsics, by what is
+    /// known about the intrinsic.
+    pub fn memory_effects(&self) -> MemoryEffects {
+        let effects = MemoryEffects::from_attributes(&self.function_attributes);
+        match MemoryEffects::of_intrinsic(&self.name) {
+            Some(intrinsic) => effects.intersect(intrinsic),
+            None => effects,
+        }
+    }
+
+    /// Get the names of the LLVM intrinsics which this `Function` calls
+    /// directly (with `Call`, `Invoke`, or `CallBr`), sorted and without
+    /// duplicates.
+    ///
+    /// These are the intrinsics after any auto-upgrading LLVM did while
+    /// reading the bitcode; see
+    /// [`Module::upgraded_intrinsics`](../module/struct.Module.html#structfield.upgraded_intrinsics)
+    /// for the intrinsics which were upgraded.
+    pub fn declared_intrinsics(&self) -> Vec<&str> {
+        let mut intrinsics: Vec<&str> = self
+            .basic_blocks
+            .iter()
+            .flat_map(|bb| {
+                let instrs = bb.instrs.iter().filter_map(|inst| match inst {
+                    Instruction::Call(call) => Some(&call.function),
+                    _ => None,
+                });
+                let term = match &bb.term {
+                    Terminator::Invoke(invoke) => Some(&invoke.function),
+                    #[cfg(feature = "llvm-9-or-greater")]
+                    Terminator::CallBr(callbr) => Some(&callbr.function),
+                    _ => None,
+                };
+                instrs.chain(term)
+            })
+            .filter_map(callee_name)
+            .filter(|name| name.starts_with("llvm."))
+            .collect();
+        intrinsics.sort_unstable();
+        intrinsics.dedup();
+        intrinsics
+    }
+}
+
+/// What [`Function::map_instructions()`](struct.Function.html#method.map_instructions)
+/// does with an `Instruction`
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+pub enum InstrMapResult {
+    /// Keep the instruction as it is
+    Keep,
+    /// Replace the instruction with the given instructions, in order
+    Replace(Vec<Instruction>),
+    /// Remove the instruction
+    Delete,
+}
+
+/// Static size metrics for a `Function`, as computed by
+/// [`Function::size_metrics()`](struct.Function.html#method.size_metrics)
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct SizeMetrics {
+    /// Number of instructions, including terminators
+    pub instruction_count: usize,
+    /// Estimated stack usage, in bytes, of the function's `Alloca`s, laid out
+    /// one after another with their required alignment. Only `Alloca`s with
+    /// a constant number of elements of a sized type are included.
+    pub stack_bytes: u64,
+    /// Whether any `Alloca` wasn't included in `stack_bytes`, because its
+    /// number of elements isn't constant (or its type is unsized)
+    pub has_dynamic_allocas: bool,
+    /// Number of `Call`s, `Invoke`s, and `CallBr`s
+    pub call_sites: usize,
+    /// Depth of the most deeply nested loop, or `0` if there are no loops
+    pub max_loop_depth: usize,
+}
+
+/// What memory a function (or a call) may access, and how.
+/// See [`Function::memory_effects()`](struct.Function.html#method.memory_effects).
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum MemoryEffects {
+    /// Doesn't access memory at all
+    None,
+    /// May read, but not write, memory in the given location
+    ReadOnly(MemoryLocation),
+    /// May write, but not read, memory in the given location
+    WriteOnly(MemoryLocation),
+    /// May read and write memory in the given location
+    ReadWrite(MemoryLocation),
+}
+
+/// Which memory a function (or a call) may access
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum MemoryLocation {
+    /// Only memory pointed to by its pointer arguments (`argmemonly`)
+    ArgMem,
+    /// Only memory which isn't accessible to the module being compiled
+    /// (`inaccessiblememonly`)
+    InaccessibleMem,
+    /// Either of the above (`inaccessiblemem_or_argmemonly`)
+    InaccessibleOrArgMem,
+    /// Any memory
+    Any,
+}
+
+impl MemoryEffects {
+    /// Interpret the given function attributes (of a function or a call
+    /// site). With no relevant attributes, this is `ReadWrite(Any)`.
+    pub fn from_attributes(attributes: &[FunctionAttribute]) -> Self {
+        let has = |attr: &FunctionAttribute| attributes.contains(attr);
+        let location = if has(&FunctionAttribute::ArgMemOnly) {
+            MemoryLocation::ArgMem
+        } else if has(&FunctionAttribute::InaccessibleMemOnly) {
+            MemoryLocation::InaccessibleMem
+        } else if has(&FunctionAttribute::InaccessibleMemOrArgMemOnly) {
+            MemoryLocation::InaccessibleOrArgMem
+        } else {
+            MemoryLocation::Any
+        };
+        let reads = !has(&FunctionAttribute::ReadNone) && !has(&FunctionAttribute::WriteOnly);
+        let writes = !has(&FunctionAttribute::ReadNone) && !has(&FunctionAttribute::ReadOnly);
+        Self::from_access(reads, writes, location)
+    }
+
+    /// Get the `MemoryEffects` of the LLVM intrinsic with the given name
+    /// (e.g., `llvm.memcpy.p0i8.p0i8.i64`), or `None` if `name` isn't an
+    /// intrinsic this crate knows about.
+    pub fn of_intrinsic(name: &str) -> Option<Self> {
+        let name = name.strip_prefix("llvm.")?;
+        let is = |base: &str| has_base_name(name, base);
+        let any_of = |bases: &[&str]| bases.iter().any(|base| is(base));
+        if any_of(&["dbg", "donothing", "expect", "expect.with.probability"])
+            || any_of(&[
+                "abs",
+                "bitreverse",
+                "bswap",
+                "ceil",
+                "copysign",
+                "cos",
+                "ctlz",
+                "ctpop",
+                "cttz",
+                "exp",
+                "exp2",
+                "fabs",
+                "floor",
+                "fma",
+                "fmuladd",
+                "fshl",
+                "fshr",
+                "log",
+                "log10",
+                "log2",
+                "maxnum",
+                "minnum",
+                "nearbyint",
+                "pow",
+                "powi",
+                "rint",
+                "round",
+                "sin",
+                "smax",
+                "smin",
+                "sqrt",
+                "trunc",
+                "umax",
+                "umin",
+            ])
+            || any_of(&[
+                "sadd.with.overflow",
+                "uadd.with.overflow",
+                "ssub.with.overflow",
+                "usub.with.overflow",
+                "smul.with.overflow",
+                "umul.with.overflow",
+                "sadd.sat",
+                "uadd.sat",
+                "ssub.sat",
+                "usub.sat",
+                "sshl.sat",
+                "ushl.sat",
+            ])
+        {
+            Some(MemoryEffects::None)
+        } else if any_of(&[
+            "memcpy",
+            "memcpy.inline",
+            "memmove",
+            "memset",
+            "lifetime.start",
+            "lifetime.end",
+            "invariant.start",
+            "invariant.end",
+        ]) {
+            Some(MemoryEffects::ReadWrite(MemoryLocation::ArgMem))
+        } else if is("assume") {
+            Some(MemoryEffects::ReadWrite(MemoryLocation::InaccessibleMem))
+        } else if is("prefetch") {
+            Some(MemoryEffects::ReadWrite(
+                MemoryLocation::InaccessibleOrArgMem,
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Combine two descriptions of the same function's memory effects (e.g.,
+    /// from the call site and from the callee), keeping only the effects
+    /// allowed by both.
+    pub fn intersect(self, other: Self) -> Self {
+        let location = match (self.location(), other.location()) {
+            (Some(a), Some(b)) => a.intersect(b),
+            _ => None,
+        };
+        match location {
+            Some(location) => Self::from_access(
+                self.may_read() && other.may_read(),
+                self.may_write() && other.may_write(),
+                location,
+            ),
+            None => MemoryEffects::None,
+        }
+    }
+
+    /// May the function read memory?
+    pub fn may_read(self) -> bool {
+        matches!(
+            self,
+            MemoryEffects::ReadOnly(_) | MemoryEffects::ReadWrite(_)
+        )
+    }
+
+    /// May the function write memory?
+    pub fn may_write(self) -> bool {
+        matches!(
+            self,
+            MemoryEffects::WriteOnly(_) | MemoryEffects::ReadWrite(_)
+        )
+    }
+
+    /// Which memory the function may access, or `None` if it doesn't access
+    /// memory at all.
+    pub fn location(self) -> Option<MemoryLocation> {
+        match self {
+            MemoryEffects::None => None,
+            MemoryEffects::ReadOnly(location)
+            | MemoryEffects::WriteOnly(location)
+            | MemoryEffects::ReadWrite(location) => Some(location),
+        }
+    }
+
+    fn from_access(reads: bool, writes: bool, location: MemoryLocation) -> Self {
+        match (reads, writes) {
+            (false, false) => MemoryEffects::None,
+            (true, false) => MemoryEffects::ReadOnly(location),
+            (false, true) => MemoryEffects::WriteOnly(location),
+            (true, true) => MemoryEffects::ReadWrite(location),
+        }
+    }
+}
+
+impl MemoryLocation {
+    /// The memory in both locations, or `None` if they are disjoint.
+    fn intersect(self, other: Self) -> Option<Self> {
+        use MemoryLocation::*;
+        match (self, other) {
+            (Any, loc) | (loc, Any) => Some(loc),
+            (InaccessibleOrArgMem, loc) | (loc, InaccessibleOrArgMem) => Some(loc),
+            (ArgMem, ArgMem) => Some(ArgMem),
+            (InaccessibleMem, InaccessibleMem) => Some(InaccessibleMem),
+            (ArgMem, InaccessibleMem) | (InaccessibleMem, ArgMem) => None,
+        }
+    }
+}
+
+/// Is `inst` a call to one of the `llvm.dbg.*` intrinsics
+fn is_debug_intrinsic_call(inst: &Instruction) -> bool {
+    match inst {
+        Instruction::Call(Call {
+            function: Either::Right(op),
+            ..
+        }) => matches!(
+            op.as_constant(),
+            Some(Constant::GlobalReference { name: Name::Name(name), .. }) if name.starts_with("llvm.dbg.")
+        ),
+        _ => false,
+    }
+}
+
+/// Escape a string for use inside a double-quoted DOT string
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// The outgoing control-flow edges of the given `Terminator`, i.e., its
+/// `successors()`, each with an optional label
+fn dot_edges(term: &Terminator) -> Vec<(&Name, Option<String>)> {
+    // labels for the successors, in the same order as `successors()`
+    let labels: Vec<String> = match term {
+        Terminator::CondBr(_) => vec!["true".into(), "false".into()],
+        Terminator::Switch(switch) => switch
+            .dests
+            .iter()
+            .map(|(val, _)| val.to_string())
+            .chain(std::iter::once("default".into()))
+            .collect(),
+        Terminator::Invoke(_) => vec!["normal".into(), "unwind".into()],
+        Terminator::CleanupRet(_) => vec!["unwind".into()],
+        Terminator::CatchSwitch(catchswitch) => catchswitch
+            .catch_handlers
+            .iter()
+            .map(|_| "catch".into())
+            .chain(std::iter::once("unwind".into()))
+            .collect(),
+        _ => vec![],
+    };
+    term.successors()
+        .zip(labels.into_iter().map(Some).chain(std::iter::repeat(None)))
+        .collect()
+}
+
+/// Make the `Phi`s in the given block refer to the predecessor `to` instead of
+/// `from`
+fn retarget_phis(bb: &mut BasicBlock, from: &Name, to: &Name) {
+    for inst in &mut bb.instrs {
+        if let Instruction::Phi(phi) = inst {
+            for (_, pred) in &mut phi.incoming_values {
+                if pred == from {
+                    *pred = to.clone();
+                }
+            }
         }
     }
 }
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Parameter {
     pub name: Name,
     pub ty: TypeRef,
@@ -93,7 +1191,8 @@ impl Typed for Parameter {
 }
 
 /// See [LLVM 14 docs on Calling Conventions](https://releases.llvm.org/14.0.0/docs/LangRef.html#callingconv)
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(non_camel_case_types)]
 pub enum CallingConvention {
     C,
@@ -144,7 +1243,8 @@ pub enum CallingConvention {
 }
 
 /// See [LLVM 14 docs on Function Attributes](https://releases.llvm.org/14.0.0/docs/LangRef.html#fnattrs)
-#[derive(PartialEq, Eq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FunctionAttribute {
     AlignStack(u64),
     AllocSize {
@@ -155,11 +1255,15 @@ pub enum FunctionAttribute {
     Builtin,
     Cold,
     Convergent,
+    #[cfg(feature = "llvm-12-or-greater")]
+    Hot,
     InaccessibleMemOnly,
     InaccessibleMemOrArgMemOnly,
     InlineHint,
     JumpTable,
     MinimizeSize,
+    #[cfg(feature = "llvm-12-or-greater")]
+    MustProgress,
     Naked,
     NoBuiltin,
     NoCFCheck,
@@ -170,6 +1274,8 @@ pub enum FunctionAttribute {
     NoInline,
     #[cfg(feature = "llvm-11-or-greater")]
     NoMerge,
+    #[cfg(feature = "llvm-12-or-greater")]
+    NoProfile,
     NonLazyBind,
     NoRedZone,
     NoReturn,
@@ -204,16 +1310,29 @@ pub enum FunctionAttribute {
     StackProtectStrong,
     StrictFP,
     UWTable,
+    #[cfg(feature = "llvm-12-or-greater")]
+    VScaleRange {
+        min: u32,
+        max: Option<u32>, // `None` if there is no upper bound
+    },
     StringAttribute {
         kind: String,
         value: String, // for no value, use ""
     },
-    UnknownAttribute, // this is used if we get a value not in the above list
+    /// An enum attribute not in the above list, identified by LLVM's kind ID.
+    /// `value` is the attribute's integer value, or 0 if it has none.
+    /// Kind IDs are not stable across LLVM versions.
+    EnumAttribute {
+        kind_id: u32,
+        value: u64,
+    },
+    UnknownAttribute, // this is used if we get an attribute which is neither an enum nor a string attribute
 }
 
 /// `ParameterAttribute`s can apply to function parameters as well as function return types.
 /// See [LLVM 14 docs on Parameter Attributes](https://releases.llvm.org/14.0.0/docs/LangRef.html#paramattrs)
-#[derive(PartialEq, Eq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ParameterAttribute {
     ZeroExt,
     SignExt,
@@ -222,6 +1341,8 @@ pub enum ParameterAttribute {
     ByVal,
     #[cfg(feature = "llvm-12-or-greater")]
     ByVal(TypeRef),
+    #[cfg(feature = "llvm-12-or-greater")]
+    ByRef(TypeRef),
     #[cfg(feature = "llvm-11")]
     Preallocated,
     #[cfg(feature = "llvm-12-or-greater")]
@@ -235,6 +1356,8 @@ pub enum ParameterAttribute {
     #[cfg(feature = "llvm-12-or-greater")]
     SRet(TypeRef),
     Alignment(u64),
+    #[cfg(feature = "llvm-13-or-greater")]
+    ElementType(TypeRef),
     NoAlias,
     NoCapture,
     #[cfg(feature = "llvm-9-or-greater")]
@@ -253,18 +1376,67 @@ pub enum ParameterAttribute {
         kind: String,
         value: String, // for no value, use ""
     },
-    UnknownAttribute, // this is used if we get an EnumAttribute not in the above list; or, for LLVM 11 or lower, also for some TypeAttributes (due to C API limitations)
+    /// An enum attribute not in the above list, identified by LLVM's kind ID.
+    /// `value` is the attribute's integer value, or 0 if it has none.
+    /// Kind IDs are not stable across LLVM versions.
+    EnumAttribute {
+        kind_id: u32,
+        value: u64,
+    },
+    UnknownAttribute, // this is used if we get an attribute which is neither an enum, string, nor type attribute; or, for LLVM 11 or lower, also for some TypeAttributes (due to C API limitations)
     #[cfg(feature = "llvm-12-or-greater")]
     UnknownTypeAttribute(TypeRef), // this is used if we get a TypeAttribute not in the above list
 }
 
+impl ParameterAttribute {
+    /// Get the integer value of the attribute, for attributes which have one:
+    /// e.g., `Some(8)` for `align 8` or `dereferenceable(8)`.
+    pub fn int_value(&self) -> Option<u64> {
+        match self {
+            Self::Alignment(value) => Some(*value),
+            Self::Dereferenceable(value) => Some(*value),
+            Self::DereferenceableOrNull(value) => Some(*value),
+            Self::EnumAttribute { value, .. } => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Get the type associated with the attribute, for type attributes:
+    /// e.g., the `%struct.T` in `byval(%struct.T)`.
+    #[cfg(feature = "llvm-12-or-greater")]
+    pub fn type_value(&self) -> Option<&TypeRef> {
+        match self {
+            Self::ByVal(ty) => Some(ty),
+            Self::ByRef(ty) => Some(ty),
+            Self::Preallocated(ty) => Some(ty),
+            #[cfg(feature = "llvm-13-or-greater")]
+            Self::InAlloca(ty) => Some(ty),
+            Self::SRet(ty) => Some(ty),
+            #[cfg(feature = "llvm-13-or-greater")]
+            Self::ElementType(ty) => Some(ty),
+            Self::UnknownTypeAttribute(ty) => Some(ty),
+            _ => None,
+        }
+    }
+}
+
+/// Parameter attributes never refer to local values
+impl MapLocalNames for ParameterAttribute {
+    fn map_local_names(&mut self, _f: &mut dyn FnMut(&mut Name)) {}
+}
+
+impl HasOperands for ParameterAttribute {
+    fn push_operands<'a>(&'a self, _out: &mut Vec<&'a Operand>) {}
+    fn push_operands_mut<'a>(&'a mut self, _out: &mut Vec<&'a mut OperandRef>) {}
+}
+
 pub type GroupID = usize;
 
 // ********* //
 // from_llvm //
 // ********* //
 
-use crate::constant::Constant;
+use crate::error::Error;
 use crate::from_llvm::*;
 use crate::llvm_sys::*;
 use crate::module::ModuleContext;
@@ -272,7 +1444,6 @@ use crate::module::ModuleContext;
 use crate::types::TypesBuilder;
 use llvm_sys::comdat::*;
 use llvm_sys::{LLVMAttributeFunctionIndex, LLVMAttributeReturnIndex};
-use std::collections::HashMap;
 use std::ffi::CString;
 
 /// This struct contains data used when translating from llvm-sys into our data
@@ -290,18 +1461,44 @@ pub(crate) struct FunctionContext<'a> {
     pub ctr: usize,
 }
 
+/// Get the `Name` of the given basic block of the given function, as it is
+/// (or will be) when the function is parsed. For an unnamed block, this
+/// repeats the numbering of the function's first pass, so it costs a pass over
+/// the function.
+pub(crate) fn block_name(func: LLVMValueRef, bb: LLVMBasicBlockRef) -> Name {
+    let name = unsafe { get_bb_name_bytes(bb) };
+    if !name.is_empty() {
+        return Name::from(name);
+    }
+    let mut ctr = 0;
+    for param in get_parameters(func) {
+        Name::name_or_num(unsafe { get_value_name_bytes(param) }, &mut ctr);
+    }
+    get_basic_blocks(func)
+        .map(|block| (block, BasicBlock::first_pass_names(block, &mut ctr).0))
+        .find(|(block, _)| *block == bb)
+        .map(|(_, name)| name)
+        .expect("Failed to find basic block in its function")
+}
+
 impl Function {
-    pub(crate) fn from_llvm_ref(func: LLVMValueRef, ctx: &mut ModuleContext) -> Self {
+    /// `with_body`: if `false`, the `Function` is given no `basic_blocks`
+    pub(crate) fn from_llvm_ref(
+        func: LLVMValueRef,
+        ctx: &mut ModuleContext,
+        with_body: bool,
+    ) -> Result<Self, Error> {
         let func = unsafe { LLVMIsAFunction(func) };
         assert!(!func.is_null());
         debug!("Processing func {:?}", unsafe { get_value_name(func) });
+        let in_function = |e: Error| e.in_function(unsafe { get_value_name(func) });
         let mut local_ctr = 0; // this counter is used to number parameters, variables, and basic blocks that aren't named
 
         let parameters: Vec<Parameter> = {
             get_parameters(func)
                 .enumerate()
                 .map(|(i, p)| Parameter {
-                    name: Name::name_or_num(unsafe { get_value_name(p) }, &mut local_ctr),
+                    name: Name::name_or_num(unsafe { get_value_name_bytes(p) }, &mut local_ctr),
                     ty: ctx.types.type_from_llvm_ref(unsafe { LLVMTypeOf(p) }),
                     attributes: {
                         let param_num = i + 1; // https://docs.rs/llvm-sys/100.0.1/llvm_sys/type.LLVMAttributeIndex.html indicates that parameter numbers are 1-indexed here; see issue #4
@@ -363,40 +1560,24 @@ impl Function {
             ctr: ctr_val_after_parameters, // restart the local_ctr; the second pass should number everything exactly the same though
         };
 
-        let functy = unsafe { LLVMGetElementType(LLVMTypeOf(func)) }; // for some reason the TypeOf a function is <pointer to function> and not just <function> so we have to deref it like this
-        Self {
+        let functy = unsafe { LLVMGlobalGetValueType(func) }; // the TypeOf a function is <pointer to function>, and with opaque pointers we can't deref that
+        Ok(Self {
             name: unsafe { get_value_name(func) },
             parameters,
             is_var_arg: unsafe { LLVMIsFunctionVarArg(functy) } != 0,
             return_type: ctx
                 .types
                 .type_from_llvm_ref(unsafe { LLVMGetReturnType(functy) }),
-            basic_blocks: {
+            basic_blocks: if with_body {
                 get_basic_blocks(func)
                     .map(|bb| BasicBlock::from_llvm_ref(bb, ctx, &mut func_ctx))
-                    .collect()
-            },
-            function_attributes: {
-                let num_attrs =
-                    unsafe { LLVMGetAttributeCountAtIndex(func, LLVMAttributeFunctionIndex) };
-                if num_attrs > 0 {
-                    let mut attrs: Vec<LLVMAttributeRef> = Vec::with_capacity(num_attrs as usize);
-                    unsafe {
-                        LLVMGetAttributesAtIndex(
-                            func,
-                            LLVMAttributeFunctionIndex,
-                            attrs.as_mut_ptr(),
-                        );
-                        attrs.set_len(num_attrs as usize);
-                    };
-                    attrs
-                        .into_iter()
-                        .map(|attr| FunctionAttribute::from_llvm_ref(attr, &ctx.attrsdata))
-                        .collect()
-                } else {
-                    vec![]
-                }
+                    .collect::<Result<_, _>>()
+                    .map_err(in_function)?
+            } else {
+                vec![]
             },
+            function_attributes: FunctionAttribute::all_from_function(func, &ctx.attrsdata),
+            function_attribute_group: None, // filled in by `Module::from_llvm_ref()`
             return_attributes: {
                 let num_attrs =
                     unsafe { LLVMGetAttributeCountAtIndex(func, LLVMAttributeReturnIndex) };
@@ -440,22 +1621,25 @@ impl Function {
                     Some(Comdat::from_llvm_ref(comdat))
                 }
             },
-            alignment: unsafe { LLVMGetAlignment(func) },
+            addr_space: unsafe { LLVMGetPointerAddressSpace(LLVMTypeOf(func)) },
+            alignment: NonZeroU32::new(unsafe { LLVMGetAlignment(func) }),
             garbage_collector_name: unsafe { get_gc(func) },
             personality_function: {
                 if unsafe { LLVMHasPersonalityFn(func) } != 0 {
-                    Some(Constant::from_llvm_ref(
-                        unsafe { LLVMGetPersonalityFn(func) },
-                        ctx,
-                    ))
+                    Some(
+                        Constant::from_llvm_ref(unsafe { LLVMGetPersonalityFn(func) }, ctx)
+                            .map_err(in_function)?,
+                    )
                 } else {
                     None
                 }
             },
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_no_col(func),
+            debugloc: DebugLoc::from_llvm_no_col(func, ctx),
+            #[cfg(feature = "llvm-9-or-greater")]
+            subprogram: Subprogram::from_llvm_func(func, ctx),
             // metadata: unimplemented!("Function.metadata"),
-        }
+        })
     }
 }
 
@@ -526,11 +1710,15 @@ impl AttributesData {
             "builtin",
             "cold",
             "convergent",
+            #[cfg(feature = "llvm-12-or-greater")]
+            "hot",
             "inaccessiblememonly",
             "inaccessiblemem_or_argmemonly",
             "inlinehint",
             "jumptable",
             "minsize",
+            #[cfg(feature = "llvm-12-or-greater")]
+            "mustprogress",
             "naked",
             "nobuiltin",
             "nocf_check",
@@ -541,6 +1729,8 @@ impl AttributesData {
             "noinline",
             #[cfg(feature = "llvm-11-or-greater")]
             "nomerge",
+            #[cfg(feature = "llvm-12-or-greater")]
+            "noprofile",
             "nonlazybind",
             "noredzone",
             "noreturn",
@@ -575,6 +1765,8 @@ impl AttributesData {
             "sspstrong",
             "strictfp",
             "uwtable",
+            #[cfg(feature = "llvm-12-or-greater")]
+            "vscale_range",
         ]
         .iter()
         .map(|&attrname| {
@@ -589,11 +1781,15 @@ impl AttributesData {
             "signext",
             "inreg",
             "byval",
+            #[cfg(feature = "llvm-12-or-greater")]
+            "byref",
             #[cfg(feature = "llvm-11-or-greater")]
             "preallocated",
             "inalloca",
             "sret",
             "align",
+            #[cfg(feature = "llvm-13-or-greater")]
+            "elementtype",
             "noalias",
             "nocapture",
             #[cfg(feature = "llvm-9-or-greater")]
@@ -638,6 +1834,25 @@ impl AttributesData {
 }
 
 impl FunctionAttribute {
+    /// Get all of the function attributes of the given function (not of a
+    /// call site)
+    pub(crate) fn all_from_function(func: LLVMValueRef, attrsdata: &AttributesData) -> Vec<Self> {
+        let num_attrs = unsafe { LLVMGetAttributeCountAtIndex(func, LLVMAttributeFunctionIndex) };
+        if num_attrs > 0 {
+            let mut attrs: Vec<LLVMAttributeRef> = Vec::with_capacity(num_attrs as usize);
+            unsafe {
+                LLVMGetAttributesAtIndex(func, LLVMAttributeFunctionIndex, attrs.as_mut_ptr());
+                attrs.set_len(num_attrs as usize);
+            };
+            attrs
+                .into_iter()
+                .map(|attr| Self::from_llvm_ref(attr, attrsdata))
+                .collect()
+        } else {
+            vec![]
+        }
+    }
+
     pub(crate) fn from_llvm_ref(a: LLVMAttributeRef, attrsdata: &AttributesData) -> Self {
         if unsafe { LLVMIsEnumAttribute(a) } != 0 {
             let kind = unsafe { LLVMGetEnumAttributeKind(a) };
@@ -660,11 +1875,15 @@ impl FunctionAttribute {
                 Some("builtin") => Self::Builtin,
                 Some("cold") => Self::Cold,
                 Some("convergent") => Self::Convergent,
+                #[cfg(feature = "llvm-12-or-greater")]
+                Some("hot") => Self::Hot,
                 Some("inaccessiblememonly") => Self::InaccessibleMemOnly,
                 Some("inaccessiblemem_or_argmemonly") => Self::InaccessibleMemOrArgMemOnly,
                 Some("inlinehint") => Self::InlineHint,
                 Some("jumptable") => Self::JumpTable,
                 Some("minsize") => Self::MinimizeSize,
+                #[cfg(feature = "llvm-12-or-greater")]
+                Some("mustprogress") => Self::MustProgress,
                 Some("naked") => Self::Naked,
                 Some("nobuiltin") => Self::NoBuiltin,
                 Some("nocf_check") => Self::NoCFCheck,
@@ -675,6 +1894,8 @@ impl FunctionAttribute {
                 Some("noinline") => Self::NoInline,
                 #[cfg(feature = "llvm-11-or-greater")]
                 Some("nomerge") => Self::NoMerge,
+                #[cfg(feature = "llvm-12-or-greater")]
+                Some("noprofile") => Self::NoProfile,
                 Some("nonlazybind") => Self::NonLazyBind,
                 Some("noredzone") => Self::NoRedZone,
                 Some("noreturn") => Self::NoReturn,
@@ -709,10 +1930,25 @@ impl FunctionAttribute {
                 Some("sspstrong") => Self::StackProtectStrong,
                 Some("strictfp") => Self::StrictFP,
                 Some("uwtable") => Self::UWTable,
+                #[cfg(feature = "llvm-12-or-greater")]
+                Some("vscale_range") => {
+                    // the min is the upper 32 bits, and the max is the lower
+                    // 32 bits, with 0 meaning no upper bound
+                    let value = unsafe { LLVMGetEnumAttributeValue(a) };
+                    let min = (value >> 32) as u32;
+                    let max = match (value & 0xFFFF_FFFF) as u32 {
+                        0 => None,
+                        val => Some(val),
+                    };
+                    Self::VScaleRange { min, max }
+                },
                 Some(s) => panic!("Unhandled value from lookup_function_attr: {:?}", s),
                 None => {
                     debug!("unknown enum function attr {}", kind);
-                    Self::UnknownAttribute
+                    Self::EnumAttribute {
+                        kind_id: kind,
+                        value: unsafe { LLVMGetEnumAttributeValue(a) },
+                    }
                 },
             }
         } else if unsafe { LLVMIsStringAttribute(a) } != 0 {
@@ -769,7 +2005,10 @@ impl ParameterAttribute {
                 Some(s) => panic!("Unhandled value from lookup_param_attr: {:?}", s),
                 None => {
                     debug!("unknown enum param attr {}", kind);
-                    Self::UnknownAttribute
+                    Self::EnumAttribute {
+                        kind_id: kind,
+                        value: unsafe { LLVMGetEnumAttributeValue(a) },
+                    }
                 },
             }
         } else if unsafe { LLVMIsStringAttribute(a) } != 0 {
@@ -789,6 +2028,9 @@ impl ParameterAttribute {
                 let ty = types.type_from_llvm_ref(unsafe { LLVMGetTypeAttributeValue(a) });
                 match attrsdata.lookup_param_attr(kind) {
                     Some("byval") => Self::ByVal(ty),
+                    Some("byref") => Self::ByRef(ty),
+                    #[cfg(feature = "llvm-13-or-greater")]
+                    Some("elementtype") => Self::ElementType(ty),
                     Some("preallocated") => Self::Preallocated(ty),
                     #[cfg(feature = "llvm-13-or-greater")]
                     Some("inalloca") => Self::InAlloca(ty),
@@ -1,17 +1,21 @@
-use crate::constant::ConstantRef;
+use crate::constant::{Constant, ConstantRef};
 #[cfg(feature = "llvm-9-or-greater")]
-use crate::debugloc::{DebugLoc, HasDebugLoc};
-use crate::function::{CallingConvention, FunctionAttribute, ParameterAttribute};
-use crate::name::Name;
-use crate::operand::Operand;
+use crate::debugloc::{DebugLoc, HasDebugLoc, HasDebugLocMut};
+use crate::function::{CallingConvention, FunctionAttribute, MemoryEffects, ParameterAttribute};
+use crate::intrinsic::callee_name;
+use crate::module::Module;
+use crate::name::{MapLocalNames, Name};
+use crate::operand::{HasOperands, Operand, OperandRef};
 use crate::predicates::*;
 use crate::types::{NamedStructDef, Type, TypeRef, Typed, Types};
 use either::Either;
 use std::convert::TryFrom;
 use std::fmt::{self, Debug, Display};
+use std::num::NonZeroU32;
 
 /// Non-terminator instructions.
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Instruction {
     // Integer binary ops
     Add(Add),
@@ -83,6 +87,9 @@ pub enum Instruction {
     LandingPad(LandingPad),
     CatchPad(CatchPad),
     CleanupPad(CleanupPad),
+
+    /// An instruction with an opcode `llvm-ir` doesn't otherwise handle
+    Other(Other),
 }
 
 /// The [`Type`](../enum.Type.html) of an `Instruction` (or any subtype of `Instruction`) is its result type.
@@ -144,6 +151,7 @@ impl Typed for Instruction {
             Instruction::LandingPad(i) => types.type_of(i),
             Instruction::CatchPad(i) => types.type_of(i),
             Instruction::CleanupPad(i) => types.type_of(i),
+            Instruction::Other(i) => types.type_of(i),
         }
     }
 }
@@ -207,6 +215,258 @@ impl HasDebugLoc for Instruction {
             Instruction::LandingPad(i) => i.get_debug_loc(),
             Instruction::CatchPad(i) => i.get_debug_loc(),
             Instruction::CleanupPad(i) => i.get_debug_loc(),
+            Instruction::Other(i) => i.get_debug_loc(),
+        }
+    }
+}
+
+#[cfg(feature = "llvm-9-or-greater")]
+impl HasDebugLocMut for Instruction {
+    fn get_debug_loc_mut(&mut self) -> &mut Option<DebugLoc> {
+        match self {
+            Instruction::Add(i) => i.get_debug_loc_mut(),
+            Instruction::Sub(i) => i.get_debug_loc_mut(),
+            Instruction::Mul(i) => i.get_debug_loc_mut(),
+            Instruction::UDiv(i) => i.get_debug_loc_mut(),
+            Instruction::SDiv(i) => i.get_debug_loc_mut(),
+            Instruction::URem(i) => i.get_debug_loc_mut(),
+            Instruction::SRem(i) => i.get_debug_loc_mut(),
+            Instruction::And(i) => i.get_debug_loc_mut(),
+            Instruction::Or(i) => i.get_debug_loc_mut(),
+            Instruction::Xor(i) => i.get_debug_loc_mut(),
+            Instruction::Shl(i) => i.get_debug_loc_mut(),
+            Instruction::LShr(i) => i.get_debug_loc_mut(),
+            Instruction::AShr(i) => i.get_debug_loc_mut(),
+            Instruction::FAdd(i) => i.get_debug_loc_mut(),
+            Instruction::FSub(i) => i.get_debug_loc_mut(),
+            Instruction::FMul(i) => i.get_debug_loc_mut(),
+            Instruction::FDiv(i) => i.get_debug_loc_mut(),
+            Instruction::FRem(i) => i.get_debug_loc_mut(),
+            Instruction::FNeg(i) => i.get_debug_loc_mut(),
+            Instruction::ExtractElement(i) => i.get_debug_loc_mut(),
+            Instruction::InsertElement(i) => i.get_debug_loc_mut(),
+            Instruction::ShuffleVector(i) => i.get_debug_loc_mut(),
+            Instruction::ExtractValue(i) => i.get_debug_loc_mut(),
+            Instruction::InsertValue(i) => i.get_debug_loc_mut(),
+            Instruction::Alloca(i) => i.get_debug_loc_mut(),
+            Instruction::Load(i) => i.get_debug_loc_mut(),
+            Instruction::Store(i) => i.get_debug_loc_mut(),
+            Instruction::Fence(i) => i.get_debug_loc_mut(),
+            Instruction::CmpXchg(i) => i.get_debug_loc_mut(),
+            Instruction::AtomicRMW(i) => i.get_debug_loc_mut(),
+            Instruction::GetElementPtr(i) => i.get_debug_loc_mut(),
+            Instruction::Trunc(i) => i.get_debug_loc_mut(),
+            Instruction::ZExt(i) => i.get_debug_loc_mut(),
+            Instruction::SExt(i) => i.get_debug_loc_mut(),
+            Instruction::FPTrunc(i) => i.get_debug_loc_mut(),
+            Instruction::FPExt(i) => i.get_debug_loc_mut(),
+            Instruction::FPToUI(i) => i.get_debug_loc_mut(),
+            Instruction::FPToSI(i) => i.get_debug_loc_mut(),
+            Instruction::UIToFP(i) => i.get_debug_loc_mut(),
+            Instruction::SIToFP(i) => i.get_debug_loc_mut(),
+            Instruction::PtrToInt(i) => i.get_debug_loc_mut(),
+            Instruction::IntToPtr(i) => i.get_debug_loc_mut(),
+            Instruction::BitCast(i) => i.get_debug_loc_mut(),
+            Instruction::AddrSpaceCast(i) => i.get_debug_loc_mut(),
+            Instruction::ICmp(i) => i.get_debug_loc_mut(),
+            Instruction::FCmp(i) => i.get_debug_loc_mut(),
+            Instruction::Phi(i) => i.get_debug_loc_mut(),
+            Instruction::Select(i) => i.get_debug_loc_mut(),
+            #[cfg(feature = "llvm-10-or-greater")]
+            Instruction::Freeze(i) => i.get_debug_loc_mut(),
+            Instruction::Call(i) => i.get_debug_loc_mut(),
+            Instruction::VAArg(i) => i.get_debug_loc_mut(),
+            Instruction::LandingPad(i) => i.get_debug_loc_mut(),
+            Instruction::CatchPad(i) => i.get_debug_loc_mut(),
+            Instruction::CleanupPad(i) => i.get_debug_loc_mut(),
+            Instruction::Other(i) => i.get_debug_loc_mut(),
+        }
+    }
+}
+
+impl MapLocalNames for Instruction {
+    fn map_local_names(&mut self, f: &mut dyn FnMut(&mut Name)) {
+        match self {
+            Instruction::Add(i) => i.map_local_names(f),
+            Instruction::Sub(i) => i.map_local_names(f),
+            Instruction::Mul(i) => i.map_local_names(f),
+            Instruction::UDiv(i) => i.map_local_names(f),
+            Instruction::SDiv(i) => i.map_local_names(f),
+            Instruction::URem(i) => i.map_local_names(f),
+            Instruction::SRem(i) => i.map_local_names(f),
+            Instruction::And(i) => i.map_local_names(f),
+            Instruction::Or(i) => i.map_local_names(f),
+            Instruction::Xor(i) => i.map_local_names(f),
+            Instruction::Shl(i) => i.map_local_names(f),
+            Instruction::LShr(i) => i.map_local_names(f),
+            Instruction::AShr(i) => i.map_local_names(f),
+            Instruction::FAdd(i) => i.map_local_names(f),
+            Instruction::FSub(i) => i.map_local_names(f),
+            Instruction::FMul(i) => i.map_local_names(f),
+            Instruction::FDiv(i) => i.map_local_names(f),
+            Instruction::FRem(i) => i.map_local_names(f),
+            Instruction::FNeg(i) => i.map_local_names(f),
+            Instruction::ExtractElement(i) => i.map_local_names(f),
+            Instruction::InsertElement(i) => i.map_local_names(f),
+            Instruction::ShuffleVector(i) => i.map_local_names(f),
+            Instruction::ExtractValue(i) => i.map_local_names(f),
+            Instruction::InsertValue(i) => i.map_local_names(f),
+            Instruction::Alloca(i) => i.map_local_names(f),
+            Instruction::Load(i) => i.map_local_names(f),
+            Instruction::Store(i) => i.map_local_names(f),
+            Instruction::Fence(i) => i.map_local_names(f),
+            Instruction::CmpXchg(i) => i.map_local_names(f),
+            Instruction::AtomicRMW(i) => i.map_local_names(f),
+            Instruction::GetElementPtr(i) => i.map_local_names(f),
+            Instruction::Trunc(i) => i.map_local_names(f),
+            Instruction::ZExt(i) => i.map_local_names(f),
+            Instruction::SExt(i) => i.map_local_names(f),
+            Instruction::FPTrunc(i) => i.map_local_names(f),
+            Instruction::FPExt(i) => i.map_local_names(f),
+            Instruction::FPToUI(i) => i.map_local_names(f),
+            Instruction::FPToSI(i) => i.map_local_names(f),
+            Instruction::UIToFP(i) => i.map_local_names(f),
+            Instruction::SIToFP(i) => i.map_local_names(f),
+            Instruction::PtrToInt(i) => i.map_local_names(f),
+            Instruction::IntToPtr(i) => i.map_local_names(f),
+            Instruction::BitCast(i) => i.map_local_names(f),
+            Instruction::AddrSpaceCast(i) => i.map_local_names(f),
+            Instruction::ICmp(i) => i.map_local_names(f),
+            Instruction::FCmp(i) => i.map_local_names(f),
+            Instruction::Phi(i) => i.map_local_names(f),
+            Instruction::Select(i) => i.map_local_names(f),
+            #[cfg(feature = "llvm-10-or-greater")]
+            Instruction::Freeze(i) => i.map_local_names(f),
+            Instruction::Call(i) => i.map_local_names(f),
+            Instruction::VAArg(i) => i.map_local_names(f),
+            Instruction::LandingPad(i) => i.map_local_names(f),
+            Instruction::CatchPad(i) => i.map_local_names(f),
+            Instruction::CleanupPad(i) => i.map_local_names(f),
+            Instruction::Other(i) => i.map_local_names(f),
+        }
+    }
+}
+
+impl HasOperands for Instruction {
+    fn push_operands<'a>(&'a self, out: &mut Vec<&'a Operand>) {
+        match self {
+            Instruction::Add(i) => i.push_operands(out),
+            Instruction::Sub(i) => i.push_operands(out),
+            Instruction::Mul(i) => i.push_operands(out),
+            Instruction::UDiv(i) => i.push_operands(out),
+            Instruction::SDiv(i) => i.push_operands(out),
+            Instruction::URem(i) => i.push_operands(out),
+            Instruction::SRem(i) => i.push_operands(out),
+            Instruction::And(i) => i.push_operands(out),
+            Instruction::Or(i) => i.push_operands(out),
+            Instruction::Xor(i) => i.push_operands(out),
+            Instruction::Shl(i) => i.push_operands(out),
+            Instruction::LShr(i) => i.push_operands(out),
+            Instruction::AShr(i) => i.push_operands(out),
+            Instruction::FAdd(i) => i.push_operands(out),
+            Instruction::FSub(i) => i.push_operands(out),
+            Instruction::FMul(i) => i.push_operands(out),
+            Instruction::FDiv(i) => i.push_operands(out),
+            Instruction::FRem(i) => i.push_operands(out),
+            Instruction::FNeg(i) => i.push_operands(out),
+            Instruction::ExtractElement(i) => i.push_operands(out),
+            Instruction::InsertElement(i) => i.push_operands(out),
+            Instruction::ShuffleVector(i) => i.push_operands(out),
+            Instruction::ExtractValue(i) => i.push_operands(out),
+            Instruction::InsertValue(i) => i.push_operands(out),
+            Instruction::Alloca(i) => i.push_operands(out),
+            Instruction::Load(i) => i.push_operands(out),
+            Instruction::Store(i) => i.push_operands(out),
+            Instruction::Fence(i) => i.push_operands(out),
+            Instruction::CmpXchg(i) => i.push_operands(out),
+            Instruction::AtomicRMW(i) => i.push_operands(out),
+            Instruction::GetElementPtr(i) => i.push_operands(out),
+            Instruction::Trunc(i) => i.push_operands(out),
+            Instruction::ZExt(i) => i.push_operands(out),
+            Instruction::SExt(i) => i.push_operands(out),
+            Instruction::FPTrunc(i) => i.push_operands(out),
+            Instruction::FPExt(i) => i.push_operands(out),
+            Instruction::FPToUI(i) => i.push_operands(out),
+            Instruction::FPToSI(i) => i.push_operands(out),
+            Instruction::UIToFP(i) => i.push_operands(out),
+            Instruction::SIToFP(i) => i.push_operands(out),
+            Instruction::PtrToInt(i) => i.push_operands(out),
+            Instruction::IntToPtr(i) => i.push_operands(out),
+            Instruction::BitCast(i) => i.push_operands(out),
+            Instruction::AddrSpaceCast(i) => i.push_operands(out),
+            Instruction::ICmp(i) => i.push_operands(out),
+            Instruction::FCmp(i) => i.push_operands(out),
+            Instruction::Phi(i) => i.push_operands(out),
+            Instruction::Select(i) => i.push_operands(out),
+            #[cfg(feature = "llvm-10-or-greater")]
+            Instruction::Freeze(i) => i.push_operands(out),
+            Instruction::Call(i) => i.push_operands(out),
+            Instruction::VAArg(i) => i.push_operands(out),
+            Instruction::LandingPad(i) => i.push_operands(out),
+            Instruction::CatchPad(i) => i.push_operands(out),
+            Instruction::CleanupPad(i) => i.push_operands(out),
+            Instruction::Other(i) => i.push_operands(out),
+        }
+    }
+
+    fn push_operands_mut<'a>(&'a mut self, out: &mut Vec<&'a mut OperandRef>) {
+        match self {
+            Instruction::Add(i) => i.push_operands_mut(out),
+            Instruction::Sub(i) => i.push_operands_mut(out),
+            Instruction::Mul(i) => i.push_operands_mut(out),
+            Instruction::UDiv(i) => i.push_operands_mut(out),
+            Instruction::SDiv(i) => i.push_operands_mut(out),
+            Instruction::URem(i) => i.push_operands_mut(out),
+            Instruction::SRem(i) => i.push_operands_mut(out),
+            Instruction::And(i) => i.push_operands_mut(out),
+            Instruction::Or(i) => i.push_operands_mut(out),
+            Instruction::Xor(i) => i.push_operands_mut(out),
+            Instruction::Shl(i) => i.push_operands_mut(out),
+            Instruction::LShr(i) => i.push_operands_mut(out),
+            Instruction::AShr(i) => i.push_operands_mut(out),
+            Instruction::FAdd(i) => i.push_operands_mut(out),
+            Instruction::FSub(i) => i.push_operands_mut(out),
+            Instruction::FMul(i) => i.push_operands_mut(out),
+            Instruction::FDiv(i) => i.push_operands_mut(out),
+            Instruction::FRem(i) => i.push_operands_mut(out),
+            Instruction::FNeg(i) => i.push_operands_mut(out),
+            Instruction::ExtractElement(i) => i.push_operands_mut(out),
+            Instruction::InsertElement(i) => i.push_operands_mut(out),
+            Instruction::ShuffleVector(i) => i.push_operands_mut(out),
+            Instruction::ExtractValue(i) => i.push_operands_mut(out),
+            Instruction::InsertValue(i) => i.push_operands_mut(out),
+            Instruction::Alloca(i) => i.push_operands_mut(out),
+            Instruction::Load(i) => i.push_operands_mut(out),
+            Instruction::Store(i) => i.push_operands_mut(out),
+            Instruction::Fence(i) => i.push_operands_mut(out),
+            Instruction::CmpXchg(i) => i.push_operands_mut(out),
+            Instruction::AtomicRMW(i) => i.push_operands_mut(out),
+            Instruction::GetElementPtr(i) => i.push_operands_mut(out),
+            Instruction::Trunc(i) => i.push_operands_mut(out),
+            Instruction::ZExt(i) => i.push_operands_mut(out),
+            Instruction::SExt(i) => i.push_operands_mut(out),
+            Instruction::FPTrunc(i) => i.push_operands_mut(out),
+            Instruction::FPExt(i) => i.push_operands_mut(out),
+            Instruction::FPToUI(i) => i.push_operands_mut(out),
+            Instruction::FPToSI(i) => i.push_operands_mut(out),
+            Instruction::UIToFP(i) => i.push_operands_mut(out),
+            Instruction::SIToFP(i) => i.push_operands_mut(out),
+            Instruction::PtrToInt(i) => i.push_operands_mut(out),
+            Instruction::IntToPtr(i) => i.push_operands_mut(out),
+            Instruction::BitCast(i) => i.push_operands_mut(out),
+            Instruction::AddrSpaceCast(i) => i.push_operands_mut(out),
+            Instruction::ICmp(i) => i.push_operands_mut(out),
+            Instruction::FCmp(i) => i.push_operands_mut(out),
+            Instruction::Phi(i) => i.push_operands_mut(out),
+            Instruction::Select(i) => i.push_operands_mut(out),
+            #[cfg(feature = "llvm-10-or-greater")]
+            Instruction::Freeze(i) => i.push_operands_mut(out),
+            Instruction::Call(i) => i.push_operands_mut(out),
+            Instruction::VAArg(i) => i.push_operands_mut(out),
+            Instruction::LandingPad(i) => i.push_operands_mut(out),
+            Instruction::CatchPad(i) => i.push_operands_mut(out),
+            Instruction::CleanupPad(i) => i.push_operands_mut(out),
+            Instruction::Other(i) => i.push_operands_mut(out),
         }
     }
 }
@@ -271,9 +531,54 @@ impl Instruction {
             Instruction::LandingPad(i) => Some(&i.dest),
             Instruction::CatchPad(i) => Some(&i.dest),
             Instruction::CleanupPad(i) => Some(&i.dest),
+            Instruction::Other(i) => i.dest.as_ref(),
         }
     }
 
+    /// Get the name defined by the `Instruction`, if any. This is the same as
+    /// `try_get_result()`.
+    pub fn defined_name(&self) -> Option<&Name> {
+        self.try_get_result()
+    }
+
+    /// Get the result (destination) of the `Instruction`, or `None` if the
+    /// `Instruction` doesn't have a result. This is the same as
+    /// `try_get_result()`.
+    pub fn try_dest(&self) -> Option<&Name> {
+        self.try_get_result()
+    }
+
+    /// Get the result (destination) of the `Instruction` and its type, or
+    /// `None` if the `Instruction` doesn't have a result.
+    pub fn try_result(&self, types: &Types) -> Option<(&Name, TypeRef)> {
+        self.try_get_result()
+            .map(|dest| (dest, self.get_type(types)))
+    }
+
+    /// Iterate over the `Operand`s used by the `Instruction`, in the order they
+    /// appear in the `Instruction`.
+    pub fn operands(&self) -> impl Iterator<Item = &Operand> {
+        let mut operands = vec![];
+        self.push_operands(&mut operands);
+        operands.into_iter()
+    }
+
+    /// Like `operands()`, but allows modifying the `Operand`s in place.
+    ///
+    /// An `Operand` which is shared with other `OperandRef`s is copied before
+    /// it is handed out, so modifying it only affects this `Instruction`.
+    pub fn operands_mut(&mut self) -> impl Iterator<Item = &mut Operand> {
+        self.operand_refs_mut().map(OperandRef::make_mut)
+    }
+
+    /// Like `operands_mut()`, but gives the `OperandRef`s themselves, so that
+    /// they can be replaced without copying the `Operand`s they refer to.
+    pub(crate) fn operand_refs_mut(&mut self) -> impl Iterator<Item = &mut OperandRef> {
+        let mut operands = vec![];
+        self.push_operands_mut(&mut operands);
+        operands.into_iter()
+    }
+
     /// Whether the `Instruction` is atomic
     pub fn is_atomic(&self) -> bool {
         match self {
@@ -332,10 +637,107 @@ impl Instruction {
             Instruction::LandingPad(_) => false,
             Instruction::CatchPad(_) => false,
             Instruction::CleanupPad(_) => false,
+            Instruction::Other(_) => false,
+        }
+    }
+
+    /// Might the `Instruction` have effects other than computing its result,
+    /// so that it can't be removed even if its result is unused?
+    ///
+    /// This is `true` for instructions which may write memory, trap, or not
+    /// return: stores, fences, `cmpxchg`s and `atomicrmw`s, volatile loads and
+    /// atomic loads stronger than `unordered`, `va_arg`s, and calls which
+    /// aren't known to be `nounwind` and `willreturn` and to not write memory.
+    /// It is also `true` for exception-handling pads, for calls to debug
+    /// intrinsics, which are needed even when their result isn't, and for
+    /// [`Other`](struct.Other.html) instructions, whose effects are unknown.
+    pub fn may_have_side_effects(&self) -> bool {
+        match self {
+            Instruction::Load(load) => {
+                load.volatile
+                    || matches!(&load.atomicity, Some(a) if a.mem_ordering != MemoryOrdering::Unordered)
+            },
+            Instruction::Store(_)
+            | Instruction::Fence(_)
+            | Instruction::CmpXchg(_)
+            | Instruction::AtomicRMW(_)
+            | Instruction::VAArg(_)
+            | Instruction::LandingPad(_)
+            | Instruction::CatchPad(_)
+            | Instruction::CleanupPad(_)
+            | Instruction::Other(_) => true,
+            Instruction::Call(call) => {
+                let call_site = MemoryEffects::from_attributes(&call.function_attributes);
+                match callee_name(&call.function) {
+                    Some(name) if name.starts_with("llvm.dbg.") => true,
+                    Some(name) => match MemoryEffects::of_intrinsic(name) {
+                        // the intrinsics we know about all return normally
+                        Some(effects) => call_site.intersect(effects).may_write(),
+                        None => call_may_have_side_effects(call_site, &call.function_attributes),
+                    },
+                    None => call_may_have_side_effects(call_site, &call.function_attributes),
+                }
+            },
+            _ => false,
+        }
+    }
+
+    /// Is it safe to execute the `Instruction` where it otherwise wouldn't
+    /// be, e.g., to hoist it out of a conditional? That is, it has no side
+    /// effects, can't trap, and its result doesn't depend on where it is.
+    ///
+    /// This is conservative. In particular, it is `false` for:
+    ///
+    ///   - integer division and remainder, unless the divisor is a constant
+    ///     other than zero (and, for `sdiv` and `srem`, other than -1, as
+    ///     dividing the minimum value by -1 overflows);
+    ///   - all loads, as this crate can't tell whether their address is
+    ///     dereferenceable;
+    ///   - `alloca`s and `phi`s, whose results depend on where they are;
+    ///   - calls, except to intrinsics known not to access memory (such as
+    ///     `llvm.ctpop`), since whether other callees are `speculatable` is
+    ///     only recorded on their declarations;
+    ///   - any `Instruction` for which
+    ///     [`may_have_side_effects()`](#method.may_have_side_effects) is `true`.
+    pub fn is_safe_to_speculate(&self) -> bool {
+        match self {
+            Instruction::UDiv(UDiv { operand1, .. }) | Instruction::URem(URem { operand1, .. }) => {
+                matches!(operand1.as_constant(), Some(Constant::Int { value, .. }) if *value != 0)
+            },
+            Instruction::SDiv(SDiv { operand1, .. }) | Instruction::SRem(SRem { operand1, .. }) => {
+                match operand1.as_constant() {
+                    Some(Constant::Int { bits, value }) => {
+                        let minus_one = if *bits >= 64 {
+                            u64::MAX
+                        } else {
+                            (1_u64 << *bits) - 1
+                        };
+                        *value != 0 && *value != minus_one
+                    },
+                    _ => false,
+                }
+            },
+            Instruction::Load(_) | Instruction::Alloca(_) | Instruction::Phi(_) => false,
+            Instruction::Call(call) => match callee_name(&call.function) {
+                Some(name) if !name.starts_with("llvm.dbg.") => {
+                    MemoryEffects::of_intrinsic(name) == Some(MemoryEffects::None)
+                },
+                _ => false,
+            },
+            _ => !self.may_have_side_effects(),
         }
     }
 }
 
+fn call_may_have_side_effects(effects: MemoryEffects, attributes: &[FunctionAttribute]) -> bool {
+    // LLVM 8 has no `willreturn` attribute, so any call might not return
+    #[cfg(feature = "llvm-8")]
+    let will_return = false;
+    #[cfg(feature = "llvm-9-or-greater")]
+    let will_return = attributes.contains(&FunctionAttribute::WillReturn);
+    effects.may_write() || !attributes.contains(&FunctionAttribute::NoUnwind) || !will_return
+}
+
 /* --TODO not yet implemented: metadata
 pub trait HasMetadata {
     fn get_metadata(&self) -> &InstructionMetadata;
@@ -404,8 +806,24 @@ impl HasMetadata for Instruction {
 }
 */
 
+/// Implemented by the instructions (and terminators) which always produce a
+/// result, i.e., all of them except for those with void type and `Call`
+/// (whose result is optional). For an `Instruction` of any kind, see
+/// [`Instruction::try_dest()`](enum.Instruction.html#method.try_dest).
 pub trait HasResult: Debug + Typed {
+    /// Get the name of the result (destination)
     fn get_result(&self) -> &Name;
+
+    /// Get the name of the result (destination). This is the same as
+    /// `get_result()`.
+    fn dest(&self) -> &Name {
+        self.get_result()
+    }
+
+    /// Get the type of the result
+    fn result_type(&self, types: &Types) -> TypeRef {
+        self.get_type(types)
+    }
 }
 
 pub trait UnaryOp: HasResult {
@@ -424,27 +842,27 @@ impl Instruction {
     /// [`groups::BinaryOp`](groups/enum.BinaryOp.html), without actually using
     /// `try_into()` (which would consume it)
     pub fn is_binary_op(&self) -> bool {
-        match self {
-            Instruction::Add(_) => true,
-            Instruction::Sub(_) => true,
-            Instruction::Mul(_) => true,
-            Instruction::UDiv(_) => true,
-            Instruction::SDiv(_) => true,
-            Instruction::URem(_) => true,
-            Instruction::SRem(_) => true,
-            Instruction::And(_) => true,
-            Instruction::Or(_) => true,
-            Instruction::Xor(_) => true,
-            Instruction::Shl(_) => true,
-            Instruction::LShr(_) => true,
-            Instruction::AShr(_) => true,
-            Instruction::FAdd(_) => true,
-            Instruction::FSub(_) => true,
-            Instruction::FMul(_) => true,
-            Instruction::FDiv(_) => true,
-            Instruction::FRem(_) => true,
-            _ => false,
-        }
+        matches!(
+            self,
+            Instruction::Add(_)
+                | Instruction::Sub(_)
+                | Instruction::Mul(_)
+                | Instruction::UDiv(_)
+                | Instruction::SDiv(_)
+                | Instruction::URem(_)
+                | Instruction::SRem(_)
+                | Instruction::And(_)
+                | Instruction::Or(_)
+                | Instruction::Xor(_)
+                | Instruction::Shl(_)
+                | Instruction::LShr(_)
+                | Instruction::AShr(_)
+                | Instruction::FAdd(_)
+                | Instruction::FSub(_)
+                | Instruction::FMul(_)
+                | Instruction::FDiv(_)
+                | Instruction::FRem(_)
+        )
     }
 
     /// Determine if the `Instruction` is one of the ones in
@@ -531,12 +949,13 @@ impl Display for Instruction {
             Instruction::LandingPad(i) => write!(f, "{}", i),
             Instruction::CatchPad(i) => write!(f, "{}", i),
             Instruction::CleanupPad(i) => write!(f, "{}", i),
+            Instruction::Other(i) => write!(f, "{}", i),
         }
     }
 }
 
 macro_rules! impl_inst {
-    ($inst:ty, $id:ident) => {
+    ($inst:ty, $id:ident, $as:ident) => {
         impl From<$inst> for Instruction {
             fn from(inst: $inst) -> Instruction {
                 Instruction::$id(inst)
@@ -553,6 +972,26 @@ macro_rules! impl_inst {
             }
         }
 
+        impl<'a> TryFrom<&'a Instruction> for &'a $inst {
+            type Error = &'static str;
+            fn try_from(inst: &'a Instruction) -> Result<Self, Self::Error> {
+                match inst {
+                    Instruction::$id(inst) => Ok(inst),
+                    _ => Err("Instruction is not of requested type"),
+                }
+            }
+        }
+
+        impl Instruction {
+            #[doc = concat!("If this is a `", stringify!($id), "`, get a reference to it")]
+            pub fn $as(&self) -> Option<&$inst> {
+                match self {
+                    Instruction::$id(inst) => Some(inst),
+                    _ => None,
+                }
+            }
+        }
+
         #[cfg(feature = "llvm-9-or-greater")]
         impl HasDebugLoc for $inst {
             fn get_debug_loc(&self) -> &Option<DebugLoc> {
@@ -560,6 +999,13 @@ macro_rules! impl_inst {
             }
         }
 
+        #[cfg(feature = "llvm-9-or-greater")]
+        impl HasDebugLocMut for $inst {
+            fn get_debug_loc_mut(&mut self) -> &mut Option<DebugLoc> {
+                &mut self.debugloc
+            }
+        }
+
         /* --TODO not yet implemented: metadata
         impl HasMetadata for $inst {
             fn get_metadata(&self) -> &InstructionMetadata {
@@ -570,6 +1016,29 @@ macro_rules! impl_inst {
     };
 }
 
+macro_rules! impl_field_visitors {
+    ($inst:ty $(, $field:ident)*) => {
+        impl MapLocalNames for $inst {
+            #[allow(unused_variables)]
+            fn map_local_names(&mut self, f: &mut dyn FnMut(&mut Name)) {
+                $(self.$field.map_local_names(f);)*
+            }
+        }
+
+        impl HasOperands for $inst {
+            #[allow(unused_variables)]
+            fn push_operands<'a>(&'a self, out: &mut Vec<&'a Operand>) {
+                $(self.$field.push_operands(out);)*
+            }
+
+            #[allow(unused_variables)]
+            fn push_operands_mut<'a>(&'a mut self, out: &mut Vec<&'a mut OperandRef>) {
+                $(self.$field.push_operands_mut(out);)*
+            }
+        }
+    };
+}
+
 macro_rules! impl_hasresult {
     ($inst:ty) => {
         impl HasResult for $inst {
@@ -632,7 +1101,10 @@ macro_rules! impl_binop {
                 write!(
                     f,
                     "{} = {} {}, {}",
-                    &self.dest, $dispname, &self.operand0, &self.operand1,
+                    &self.dest,
+                    $dispname,
+                    &self.operand0,
+                    self.operand1.untyped(),
                 )?;
                 #[cfg(feature = "llvm-9-or-greater")]
                 if self.debugloc.is_some() {
@@ -742,10 +1214,11 @@ macro_rules! void_typed {
 
 /// Integer add.
 /// See [LLVM 14 docs on the 'add' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#add-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Add {
-    pub operand0: Operand,
-    pub operand1: Operand,
+    pub operand0: OperandRef,
+    pub operand1: OperandRef,
     pub dest: Name,
     // pub nsw: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
     // pub nuw: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
@@ -754,15 +1227,17 @@ pub struct Add {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(Add, Add);
+impl_inst!(Add, Add, as_add);
+impl_field_visitors!(Add, operand0, operand1, dest);
 binop_same_type!(Add, Add, "add");
 
 /// Integer subtract.
 /// See [LLVM 14 docs on the 'sub' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#sub-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Sub {
-    pub operand0: Operand,
-    pub operand1: Operand,
+    pub operand0: OperandRef,
+    pub operand1: OperandRef,
     pub dest: Name,
     // pub nsw: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
     // pub nuw: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
@@ -771,15 +1246,17 @@ pub struct Sub {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(Sub, Sub);
+impl_inst!(Sub, Sub, as_sub);
+impl_field_visitors!(Sub, operand0, operand1, dest);
 binop_same_type!(Sub, Sub, "sub");
 
 /// Integer multiply.
 /// See [LLVM 14 docs on the 'mul' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#mul-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Mul {
-    pub operand0: Operand,
-    pub operand1: Operand,
+    pub operand0: OperandRef,
+    pub operand1: OperandRef,
     pub dest: Name,
     // pub nsw: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
     // pub nuw: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
@@ -788,15 +1265,17 @@ pub struct Mul {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(Mul, Mul);
+impl_inst!(Mul, Mul, as_mul);
+impl_field_visitors!(Mul, operand0, operand1, dest);
 binop_same_type!(Mul, Mul, "mul");
 
 /// Unsigned integer divide.
 /// See [LLVM 14 docs on the 'udiv' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#udiv-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UDiv {
-    pub operand0: Operand,
-    pub operand1: Operand,
+    pub operand0: OperandRef,
+    pub operand1: OperandRef,
     pub dest: Name,
     // pub exact: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
     #[cfg(feature = "llvm-9-or-greater")]
@@ -804,15 +1283,17 @@ pub struct UDiv {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(UDiv, UDiv);
+impl_inst!(UDiv, UDiv, as_udiv);
+impl_field_visitors!(UDiv, operand0, operand1, dest);
 binop_same_type!(UDiv, UDiv, "udiv");
 
 /// Signed integer divide.
 /// See [LLVM 14 docs on the 'sdiv' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#sdiv-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SDiv {
-    pub operand0: Operand,
-    pub operand1: Operand,
+    pub operand0: OperandRef,
+    pub operand1: OperandRef,
     pub dest: Name,
     // pub exact: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
     #[cfg(feature = "llvm-9-or-greater")]
@@ -820,90 +1301,102 @@ pub struct SDiv {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(SDiv, SDiv);
+impl_inst!(SDiv, SDiv, as_sdiv);
+impl_field_visitors!(SDiv, operand0, operand1, dest);
 binop_same_type!(SDiv, SDiv, "sdiv");
 
 /// Unsigned integer remainder.
 /// See [LLVM 14 docs on the 'urem' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#urem-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct URem {
-    pub operand0: Operand,
-    pub operand1: Operand,
+    pub operand0: OperandRef,
+    pub operand1: OperandRef,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(URem, URem);
+impl_inst!(URem, URem, as_urem);
+impl_field_visitors!(URem, operand0, operand1, dest);
 binop_same_type!(URem, URem, "urem");
 
 /// Signed integer remainder.
 /// See [LLVM 14 docs on the 'srem' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#srem-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SRem {
-    pub operand0: Operand,
-    pub operand1: Operand,
+    pub operand0: OperandRef,
+    pub operand1: OperandRef,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(SRem, SRem);
+impl_inst!(SRem, SRem, as_srem);
+impl_field_visitors!(SRem, operand0, operand1, dest);
 binop_same_type!(SRem, SRem, "srem");
 
 /// Bitwise logical and.
 /// See [LLVM 14 docs on the 'and' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#and-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct And {
-    pub operand0: Operand,
-    pub operand1: Operand,
+    pub operand0: OperandRef,
+    pub operand1: OperandRef,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(And, And);
+impl_inst!(And, And, as_and);
+impl_field_visitors!(And, operand0, operand1, dest);
 binop_same_type!(And, And, "and");
 
 /// Bitwise logical inclusive or.
 /// See [LLVM 14 docs on the 'or' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#or-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Or {
-    pub operand0: Operand,
-    pub operand1: Operand,
+    pub operand0: OperandRef,
+    pub operand1: OperandRef,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(Or, Or);
+impl_inst!(Or, Or, as_or);
+impl_field_visitors!(Or, operand0, operand1, dest);
 binop_same_type!(Or, Or, "or");
 
 /// Bitwise logical exclusive or.
 /// See [LLVM 14 docs on the 'xor' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#xor-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Xor {
-    pub operand0: Operand,
-    pub operand1: Operand,
+    pub operand0: OperandRef,
+    pub operand1: OperandRef,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(Xor, Xor);
+impl_inst!(Xor, Xor, as_xor);
+impl_field_visitors!(Xor, operand0, operand1, dest);
 binop_same_type!(Xor, Xor, "xor");
 
 /// Shift left.
 /// See [LLVM 14 docs on the 'shl' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#shl-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Shl {
-    pub operand0: Operand,
-    pub operand1: Operand,
+    pub operand0: OperandRef,
+    pub operand1: OperandRef,
     pub dest: Name,
     // pub nsw: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
     // pub nuw: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
@@ -912,15 +1405,17 @@ pub struct Shl {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(Shl, Shl);
+impl_inst!(Shl, Shl, as_shl);
+impl_field_visitors!(Shl, operand0, operand1, dest);
 binop_left_type!(Shl, Shl, "shl");
 
 /// Logical shift right.
 /// See [LLVM 14 docs on the 'lshr' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#lshr-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LShr {
-    pub operand0: Operand,
-    pub operand1: Operand,
+    pub operand0: OperandRef,
+    pub operand1: OperandRef,
     pub dest: Name,
     // pub exact: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
     #[cfg(feature = "llvm-9-or-greater")]
@@ -928,15 +1423,17 @@ pub struct LShr {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(LShr, LShr);
+impl_inst!(LShr, LShr, as_lshr);
+impl_field_visitors!(LShr, operand0, operand1, dest);
 binop_left_type!(LShr, LShr, "lshr");
 
 /// Arithmetic shift right.
 /// See [LLVM 14 docs on the 'ashr' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#ashr-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AShr {
-    pub operand0: Operand,
-    pub operand1: Operand,
+    pub operand0: OperandRef,
+    pub operand1: OperandRef,
     pub dest: Name,
     // pub exact: bool,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
     #[cfg(feature = "llvm-9-or-greater")]
@@ -944,15 +1441,17 @@ pub struct AShr {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(AShr, AShr);
+impl_inst!(AShr, AShr, as_ashr);
+impl_field_visitors!(AShr, operand0, operand1, dest);
 binop_left_type!(AShr, AShr, "ashr");
 
 /// Floating-point add.
 /// See [LLVM 14 docs on the 'fadd' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#fadd-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FAdd {
-    pub operand0: Operand,
-    pub operand1: Operand,
+    pub operand0: OperandRef,
+    pub operand1: OperandRef,
     pub dest: Name,
     // pub fast_math_flags: FastMathFlags,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
     #[cfg(feature = "llvm-9-or-greater")]
@@ -960,15 +1459,17 @@ pub struct FAdd {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(FAdd, FAdd);
+impl_inst!(FAdd, FAdd, as_fadd);
+impl_field_visitors!(FAdd, operand0, operand1, dest);
 binop_same_type!(FAdd, FAdd, "fadd");
 
 /// Floating-point subtract.
 /// See [LLVM 14 docs on the 'fsub' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#fsub-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FSub {
-    pub operand0: Operand,
-    pub operand1: Operand,
+    pub operand0: OperandRef,
+    pub operand1: OperandRef,
     pub dest: Name,
     // pub fast_math_flags: FastMathFlags,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
     #[cfg(feature = "llvm-9-or-greater")]
@@ -976,15 +1477,17 @@ pub struct FSub {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(FSub, FSub);
+impl_inst!(FSub, FSub, as_fsub);
+impl_field_visitors!(FSub, operand0, operand1, dest);
 binop_same_type!(FSub, FSub, "fsub");
 
 /// Floating-point multiply.
 /// See [LLVM 14 docs on the 'fmul' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#fmul-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FMul {
-    pub operand0: Operand,
-    pub operand1: Operand,
+    pub operand0: OperandRef,
+    pub operand1: OperandRef,
     pub dest: Name,
     // pub fast_math_flags: FastMathFlags,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
     #[cfg(feature = "llvm-9-or-greater")]
@@ -992,15 +1495,17 @@ pub struct FMul {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(FMul, FMul);
+impl_inst!(FMul, FMul, as_fmul);
+impl_field_visitors!(FMul, operand0, operand1, dest);
 binop_same_type!(FMul, FMul, "fmul");
 
 /// Floating-point divide.
 /// See [LLVM 14 docs on the 'fdiv' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#fdiv-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FDiv {
-    pub operand0: Operand,
-    pub operand1: Operand,
+    pub operand0: OperandRef,
+    pub operand1: OperandRef,
     pub dest: Name,
     // pub fast_math_flags: FastMathFlags,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
     #[cfg(feature = "llvm-9-or-greater")]
@@ -1008,15 +1513,17 @@ pub struct FDiv {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(FDiv, FDiv);
+impl_inst!(FDiv, FDiv, as_fdiv);
+impl_field_visitors!(FDiv, operand0, operand1, dest);
 binop_same_type!(FDiv, FDiv, "fdiv");
 
 /// Floating-point remainder.
 /// See [LLVM 14 docs on the 'frem' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#frem-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FRem {
-    pub operand0: Operand,
-    pub operand1: Operand,
+    pub operand0: OperandRef,
+    pub operand1: OperandRef,
     pub dest: Name,
     // pub fast_math_flags: FastMathFlags,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
     #[cfg(feature = "llvm-9-or-greater")]
@@ -1024,14 +1531,16 @@ pub struct FRem {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(FRem, FRem);
+impl_inst!(FRem, FRem, as_frem);
+impl_field_visitors!(FRem, operand0, operand1, dest);
 binop_same_type!(FRem, FRem, "frem");
 
 /// Floating-point unary negation.
 /// See [LLVM 14 docs on the 'fneg' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#fneg-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FNeg {
-    pub operand: Operand,
+    pub operand: OperandRef,
     pub dest: Name,
     // pub fast_math_flags: FastMathFlags,  // getters for these seem to not be exposed in the LLVM C API, only in the C++ one
     #[cfg(feature = "llvm-9-or-greater")]
@@ -1039,22 +1548,25 @@ pub struct FNeg {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(FNeg, FNeg);
+impl_inst!(FNeg, FNeg, as_fneg);
+impl_field_visitors!(FNeg, operand, dest);
 unop_same_type!(FNeg, "fneg");
 
 /// Get an element from a vector at a specified index.
 /// See [LLVM 14 docs on the 'extractelement' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#extractelement-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ExtractElement {
-    pub vector: Operand,
-    pub index: Operand,
+    pub vector: OperandRef,
+    pub index: OperandRef,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(ExtractElement, ExtractElement);
+impl_inst!(ExtractElement, ExtractElement, as_extractelement);
+impl_field_visitors!(ExtractElement, vector, index, dest);
 impl_hasresult!(ExtractElement);
 
 impl Typed for ExtractElement {
@@ -1086,18 +1598,20 @@ impl Display for ExtractElement {
 
 /// Insert an element into a vector at a specified index.
 /// See [LLVM 14 docs on the 'insertelement' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#insertelement-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InsertElement {
-    pub vector: Operand,
-    pub element: Operand,
-    pub index: Operand,
+    pub vector: OperandRef,
+    pub element: OperandRef,
+    pub index: OperandRef,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(InsertElement, InsertElement);
+impl_inst!(InsertElement, InsertElement, as_insertelement);
+impl_field_visitors!(InsertElement, vector, element, index, dest);
 impl_hasresult!(InsertElement);
 
 impl Typed for InsertElement {
@@ -1123,10 +1637,11 @@ impl Display for InsertElement {
 
 /// Permute elements from two input vectors into a single output vector.
 /// See [LLVM 14 docs on the 'shufflevector' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#shufflevector-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ShuffleVector {
-    pub operand0: Operand,
-    pub operand1: Operand,
+    pub operand0: OperandRef,
+    pub operand1: OperandRef,
     pub dest: Name,
     pub mask: ConstantRef,
     #[cfg(feature = "llvm-9-or-greater")]
@@ -1134,7 +1649,8 @@ pub struct ShuffleVector {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(ShuffleVector, ShuffleVector);
+impl_inst!(ShuffleVector, ShuffleVector, as_shufflevector);
+impl_field_visitors!(ShuffleVector, operand0, operand1, dest);
 impl_hasresult!(ShuffleVector);
 
 impl Typed for ShuffleVector {
@@ -1183,9 +1699,10 @@ impl Display for ShuffleVector {
 
 /// Extract the value of a member field from an aggregate (struct or array) type.
 /// See [LLVM 14 docs on the 'extractvalue' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#extractvalue-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ExtractValue {
-    pub aggregate: Operand,
+    pub aggregate: OperandRef,
     pub indices: Vec<u32>,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
@@ -1193,7 +1710,8 @@ pub struct ExtractValue {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(ExtractValue, ExtractValue);
+impl_inst!(ExtractValue, ExtractValue, as_extractvalue);
+impl_field_visitors!(ExtractValue, aggregate, dest);
 impl_hasresult!(ExtractValue);
 
 impl Typed for ExtractValue {
@@ -1244,10 +1762,11 @@ impl Display for ExtractValue {
 
 /// Insert a value into a member field of an aggregate (struct or array) type.
 /// See [LLVM 14 docs on the 'insertvalue' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#insertvalue-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InsertValue {
-    pub aggregate: Operand,
-    pub element: Operand,
+    pub aggregate: OperandRef,
+    pub element: OperandRef,
     pub indices: Vec<u32>,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
@@ -1255,7 +1774,8 @@ pub struct InsertValue {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(InsertValue, InsertValue);
+impl_inst!(InsertValue, InsertValue, as_insertvalue);
+impl_field_visitors!(InsertValue, aggregate, element, dest);
 impl_hasresult!(InsertValue);
 
 impl Typed for InsertValue {
@@ -1287,23 +1807,28 @@ impl Display for InsertValue {
 
 /// Allocate memory on the stack.
 /// See [LLVM 14 docs on the 'alloca' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#alloca-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Alloca {
     pub allocated_type: TypeRef,
-    pub num_elements: Operand, // llvm-hs-pure has Option<Operand>
+    pub num_elements: OperandRef, // llvm-hs-pure has Option<Operand>
     pub dest: Name,
-    pub alignment: u32,
+    /// The explicit alignment, in bytes, or `None` if it isn't specified, in
+    /// which case the ABI alignment of the allocated type applies.
+    /// See `effective_alignment()`.
+    pub alignment: Option<NonZeroU32>,
     #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(Alloca, Alloca);
+impl_inst!(Alloca, Alloca, as_alloca);
+impl_field_visitors!(Alloca, num_elements, dest);
 impl_hasresult!(Alloca);
 
 impl Typed for Alloca {
     fn get_type(&self, types: &Types) -> TypeRef {
-        types.pointer_to(self.allocated_type.clone())
+        types.pointer_for(self.allocated_type.clone(), 0)
     }
 }
 
@@ -1315,7 +1840,9 @@ impl Display for Alloca {
         } else {
             write!(f, ", {}", &self.num_elements)?;
         }
-        write!(f, ", align {}", &self.alignment)?;
+        if let Some(alignment) = self.alignment {
+            write!(f, ", align {}", alignment)?;
+        }
         #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
@@ -1324,29 +1851,48 @@ impl Display for Alloca {
     }
 }
 
+impl Alloca {
+    /// The alignment of the allocated memory, in bytes: the explicit
+    /// `alignment` if there is one, and otherwise the ABI alignment of the
+    /// allocated type under the `Module`'s `DataLayout`. (LLVM may choose a
+    /// larger alignment, but never a smaller one.)
+    ///
+    /// Returns `None` if there is no explicit alignment and the allocated type
+    /// is unsized.
+    pub fn effective_alignment(&self, module: &Module) -> Option<NonZeroU32> {
+        self.alignment
+            .or_else(|| abi_alignment(module, &self.allocated_type))
+    }
+}
+
 /// Load a value from memory.
 /// See [LLVM 14 docs on the 'load' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#load-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Load {
-    pub address: Operand,
+    pub address: OperandRef,
+    /// The type of the value being loaded. With opaque pointers, this can't
+    /// be inferred from the type of `address`.
+    pub loaded_type: TypeRef,
     pub dest: Name,
     pub volatile: bool,
     pub atomicity: Option<Atomicity>,
-    pub alignment: u32,
+    /// The explicit alignment, in bytes, or `None` if it isn't specified, in
+    /// which case the ABI alignment of the loaded type applies.
+    /// See `effective_alignment()`.
+    pub alignment: Option<NonZeroU32>,
     #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(Load, Load);
+impl_inst!(Load, Load, as_load);
+impl_field_visitors!(Load, address, dest);
 impl_hasresult!(Load);
 
 impl Typed for Load {
-    fn get_type(&self, types: &Types) -> TypeRef {
-        match types.type_of(&self.address).as_ref() {
-            Type::PointerType { pointee_type, .. } => pointee_type.clone(),
-            ty => panic!("Expected a load address to be PointerType, got {:?}", ty),
-        }
+    fn get_type(&self, _types: &Types) -> TypeRef {
+        self.loaded_type.clone()
     }
 }
 
@@ -1366,7 +1912,9 @@ impl Display for Load {
         if let Some(a) = &self.atomicity {
             write!(f, " {}", a)?;
         }
-        write!(f, ", align {}", &self.alignment)?;
+        if let Some(alignment) = self.alignment {
+            write!(f, ", align {}", alignment)?;
+        }
         #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
@@ -1375,21 +1923,49 @@ impl Display for Load {
     }
 }
 
+impl Load {
+    /// The alignment of the loaded address, in bytes: the explicit `alignment`
+    /// if there is one, and otherwise the ABI alignment of the loaded type
+    /// under the `Module`'s `DataLayout`.
+    ///
+    /// Returns `None` if there is no explicit alignment and the loaded type
+    /// is unsized.
+    pub fn effective_alignment(&self, module: &Module) -> Option<NonZeroU32> {
+        self.alignment
+            .or_else(|| abi_alignment(module, &module.type_of(self)))
+    }
+
+    /// Does this `Load` follow LLVM's rules for the alignment of atomic
+    /// loads? An atomic load must have an explicit alignment, which must be at
+    /// least the size of the loaded type; otherwise its behavior is undefined.
+    ///
+    /// Always `true` for non-atomic loads.
+    pub fn has_valid_atomic_alignment(&self, module: &Module) -> bool {
+        self.atomicity.is_none()
+            || is_valid_atomic_alignment(module, self.alignment, &module.type_of(self))
+    }
+}
+
 /// Store a value to memory.
 /// See [LLVM 14 docs on the 'store' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#store-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Store {
-    pub address: Operand,
-    pub value: Operand,
+    pub address: OperandRef,
+    pub value: OperandRef,
     pub volatile: bool,
     pub atomicity: Option<Atomicity>,
-    pub alignment: u32,
+    /// The explicit alignment, in bytes, or `None` if it isn't specified, in
+    /// which case the ABI alignment of the stored type applies.
+    /// See `effective_alignment()`.
+    pub alignment: Option<NonZeroU32>,
     #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(Store, Store);
+impl_inst!(Store, Store, as_store);
+impl_field_visitors!(Store, address, value);
 void_typed!(Store);
 
 impl Display for Store {
@@ -1405,7 +1981,9 @@ impl Display for Store {
         if let Some(a) = &self.atomicity {
             write!(f, " {}", a)?;
         }
-        write!(f, ", align {}", &self.alignment)?;
+        if let Some(alignment) = self.alignment {
+            write!(f, ", align {}", alignment)?;
+        }
         #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
@@ -1414,9 +1992,53 @@ impl Display for Store {
     }
 }
 
+impl Store {
+    /// The alignment of the stored-to address, in bytes: the explicit
+    /// `alignment` if there is one, and otherwise the ABI alignment of the
+    /// stored type under the `Module`'s `DataLayout`.
+    ///
+    /// Returns `None` if there is no explicit alignment and the stored type
+    /// is unsized.
+    pub fn effective_alignment(&self, module: &Module) -> Option<NonZeroU32> {
+        self.alignment
+            .or_else(|| abi_alignment(module, &module.type_of(&self.value)))
+    }
+
+    /// Does this `Store` follow LLVM's rules for the alignment of atomic
+    /// stores? An atomic store must have an explicit alignment, which must be
+    /// at least the size of the stored type; otherwise its behavior is
+    /// undefined.
+    ///
+    /// Always `true` for non-atomic stores.
+    pub fn has_valid_atomic_alignment(&self, module: &Module) -> bool {
+        self.atomicity.is_none()
+            || is_valid_atomic_alignment(module, self.alignment, &module.type_of(&self.value))
+    }
+}
+
+/// The ABI alignment of the given type under the `Module`'s `DataLayout`, in
+/// bytes, or `None` if the type is unsized
+fn abi_alignment(module: &Module, ty: &Type) -> Option<NonZeroU32> {
+    let (_, align) = module.types.alloc_size_and_align(ty, &module.data_layout)?;
+    NonZeroU32::new(u32::try_from(align).ok()?)
+}
+
+/// Is `alignment` valid for an atomic access of the given type: explicit,
+/// and at least the type's size?
+fn is_valid_atomic_alignment(module: &Module, alignment: Option<NonZeroU32>, ty: &Type) -> bool {
+    match (
+        alignment,
+        module.types.alloc_size_and_align(ty, &module.data_layout),
+    ) {
+        (Some(alignment), Some((size, _))) => u64::from(alignment.get()) >= size,
+        _ => false,
+    }
+}
+
 /// Memory-ordering fence.
 /// See [LLVM 14 docs on the 'fence' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#fence-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Fence {
     pub atomicity: Atomicity,
     #[cfg(feature = "llvm-9-or-greater")]
@@ -1424,7 +2046,8 @@ pub struct Fence {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(Fence, Fence);
+impl_inst!(Fence, Fence, as_fence);
+impl_field_visitors!(Fence);
 void_typed!(Fence);
 
 impl Display for Fence {
@@ -1440,11 +2063,12 @@ impl Display for Fence {
 
 /// Atomic compare and exchange.
 /// See [LLVM 14 docs on the 'cmpxchg' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#cmpxchg-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CmpXchg {
-    pub address: Operand,
-    pub expected: Operand,
-    pub replacement: Operand,
+    pub address: OperandRef,
+    pub expected: OperandRef,
+    pub replacement: OperandRef,
     pub dest: Name,
     pub volatile: bool,
     /// This includes the "success" `MemoryOrdering`
@@ -1458,7 +2082,8 @@ pub struct CmpXchg {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(CmpXchg, CmpXchg);
+impl_inst!(CmpXchg, CmpXchg, as_cmpxchg);
+impl_field_visitors!(CmpXchg, address, expected, replacement, dest);
 impl_hasresult!(CmpXchg);
 
 impl Typed for CmpXchg {
@@ -1498,13 +2123,14 @@ impl Display for CmpXchg {
 
 /// Atomic read-modify-write.
 /// See [LLVM 14 docs on the 'atomicrmw' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#atomicrmw-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AtomicRMW {
     // the binop-getter was added to the LLVM C API in LLVM 10
     #[cfg(feature = "llvm-10-or-greater")]
     pub operation: RMWBinOp,
-    pub address: Operand,
-    pub value: Operand,
+    pub address: OperandRef,
+    pub value: OperandRef,
     pub dest: Name,
     pub volatile: bool,
     pub atomicity: Atomicity,
@@ -1513,18 +2139,15 @@ pub struct AtomicRMW {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(AtomicRMW, AtomicRMW);
+impl_inst!(AtomicRMW, AtomicRMW, as_atomicrmw);
+impl_field_visitors!(AtomicRMW, address, value, dest);
 impl_hasresult!(AtomicRMW);
 
 impl Typed for AtomicRMW {
     fn get_type(&self, types: &Types) -> TypeRef {
-        match types.type_of(&self.address).as_ref() {
-            Type::PointerType { pointee_type, .. } => pointee_type.clone(),
-            ty => panic!(
-                "Expected an AtomicRMW address to be PointerType, got {:?}",
-                ty
-            ),
-        }
+        // the result is the old value at the address, which has the same
+        // type as the operand
+        types.type_of(&self.value)
     }
 }
 
@@ -1548,26 +2171,44 @@ impl Display for AtomicRMW {
 /// Get the address of a subelement of an aggregate data structure.
 /// Only performs address calculation, does not actually access memory.
 /// See [LLVM 14 docs on the 'getelementptr' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#getelementptr-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GetElementPtr {
-    pub address: Operand,
-    pub indices: Vec<Operand>,
+    pub address: OperandRef,
+    pub indices: Vec<OperandRef>,
     pub dest: Name,
     pub in_bounds: bool,
+    /// The type which the first index steps over, and which the remaining
+    /// indices index into. With typed pointers, this is the pointee type of
+    /// `address`; with opaque pointers, it can only be known from here.
+    pub source_element_type: TypeRef,
     #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(GetElementPtr, GetElementPtr);
+impl_inst!(GetElementPtr, GetElementPtr, as_getelementptr);
+impl_field_visitors!(GetElementPtr, address, indices, dest);
 impl_hasresult!(GetElementPtr);
 
 impl Typed for GetElementPtr {
     fn get_type(&self, types: &Types) -> TypeRef {
-        gep_type(types.type_of(&self.address), self.indices.iter(), types)
+        let address_ty = types.type_of(&self.address);
+        let addr_space = address_ty.addr_space().unwrap_or(0);
+        match address_ty.as_ref() {
+            Type::OpaquePointerType { .. } => types.opaque_pointer_in_addr_space(addr_space),
+            _ => {
+                // the first index steps over the `source_element_type` without
+                // changing the type
+                let indices = self.indices.iter().skip(1).map(AsRef::as_ref);
+                let element_type = gep_type(self.source_element_type.clone(), indices, types);
+                types.pointer_in_addr_space(element_type, addr_space)
+            },
+        }
     }
 }
 
+/// The type reached by indexing into `cur_type` with the given `indices`
 fn gep_type<'o>(
     cur_type: TypeRef,
     mut indices: impl Iterator<Item = &'o Operand>,
@@ -1585,9 +2226,8 @@ fn gep_type<'o>(
         }
     }
     match indices.next() {
-        None => types.pointer_to(cur_type),  // iterator is done
+        None => cur_type,  // iterator is done
         Some(index) => match cur_type.as_ref() {
-            Type::PointerType { pointee_type, .. } => gep_type(pointee_type.clone(), indices, types),
             Type::VectorType { element_type, .. } => gep_type(element_type.clone(), indices, types),
             Type::ArrayType { element_type, .. } => gep_type(element_type.clone(), indices, types),
             Type::StructType { element_types, .. } => {
@@ -1602,7 +2242,7 @@ fn gep_type<'o>(
                 }
             },
             Type::NamedStructType { .. } => panic!("This case should have been handled above"),
-            _ => panic!("Expected GEP base type to be a PointerType, VectorType, ArrayType, StructType, or NamedStructType; got {:?}", cur_type),
+            _ => panic!("Expected GEP to index into a VectorType, ArrayType, StructType, or NamedStructType; got {:?}", cur_type),
         }
     }
 }
@@ -1631,9 +2271,10 @@ impl Display for GetElementPtr {
 
 /// Truncate.
 /// See [LLVM 14 docs on the 'trunc' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#trunc-to-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Trunc {
-    pub operand: Operand,
+    pub operand: OperandRef,
     pub to_type: TypeRef,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
@@ -1641,14 +2282,16 @@ pub struct Trunc {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(Trunc, Trunc);
+impl_inst!(Trunc, Trunc, as_trunc);
+impl_field_visitors!(Trunc, operand, dest);
 unop_explicitly_typed!(Trunc, "trunc");
 
 /// Zero-extend.
 /// See [LLVM 14 docs on the 'zext' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#zext-to-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ZExt {
-    pub operand: Operand,
+    pub operand: OperandRef,
     pub to_type: TypeRef,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
@@ -1656,14 +2299,16 @@ pub struct ZExt {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(ZExt, ZExt);
+impl_inst!(ZExt, ZExt, as_zext);
+impl_field_visitors!(ZExt, operand, dest);
 unop_explicitly_typed!(ZExt, "zext");
 
 /// Sign-extend.
 /// See [LLVM 14 docs on the 'sext' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#sext-to-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SExt {
-    pub operand: Operand,
+    pub operand: OperandRef,
     pub to_type: TypeRef,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
@@ -1671,14 +2316,16 @@ pub struct SExt {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(SExt, SExt);
+impl_inst!(SExt, SExt, as_sext);
+impl_field_visitors!(SExt, operand, dest);
 unop_explicitly_typed!(SExt, "sext");
 
 /// Truncate a floating-point value.
 /// See [LLVM 14 docs on the 'fptrunc' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#fptrunc-to-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FPTrunc {
-    pub operand: Operand,
+    pub operand: OperandRef,
     pub to_type: TypeRef,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
@@ -1686,14 +2333,16 @@ pub struct FPTrunc {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(FPTrunc, FPTrunc);
+impl_inst!(FPTrunc, FPTrunc, as_fptrunc);
+impl_field_visitors!(FPTrunc, operand, dest);
 unop_explicitly_typed!(FPTrunc, "fptrunc");
 
 /// Extend a floating-point value.
 /// See [LLVM 14 docs on the 'fpext' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#fpext-to-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FPExt {
-    pub operand: Operand,
+    pub operand: OperandRef,
     pub to_type: TypeRef,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
@@ -1701,14 +2350,16 @@ pub struct FPExt {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(FPExt, FPExt);
+impl_inst!(FPExt, FPExt, as_fpext);
+impl_field_visitors!(FPExt, operand, dest);
 unop_explicitly_typed!(FPExt, "fpext");
 
 /// Convert floating-point to unsigned integer.
 /// See [LLVM 14 docs on the 'fptoui' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#fptoui-to-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FPToUI {
-    pub operand: Operand,
+    pub operand: OperandRef,
     pub to_type: TypeRef,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
@@ -1716,14 +2367,16 @@ pub struct FPToUI {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(FPToUI, FPToUI);
+impl_inst!(FPToUI, FPToUI, as_fptoui);
+impl_field_visitors!(FPToUI, operand, dest);
 unop_explicitly_typed!(FPToUI, "fptoui");
 
 /// Convert floating-point to signed integer.
 /// See [LLVM 14 docs on the 'fptosi' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#fptosi-to-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FPToSI {
-    pub operand: Operand,
+    pub operand: OperandRef,
     pub to_type: TypeRef,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
@@ -1731,14 +2384,16 @@ pub struct FPToSI {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(FPToSI, FPToSI);
+impl_inst!(FPToSI, FPToSI, as_fptosi);
+impl_field_visitors!(FPToSI, operand, dest);
 unop_explicitly_typed!(FPToSI, "fptosi");
 
 /// Convert unsigned integer to floating-point.
 /// See [LLVM 14 docs on the 'uitofp' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#uitofp-to-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UIToFP {
-    pub operand: Operand,
+    pub operand: OperandRef,
     pub to_type: TypeRef,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
@@ -1746,14 +2401,16 @@ pub struct UIToFP {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(UIToFP, UIToFP);
+impl_inst!(UIToFP, UIToFP, as_uitofp);
+impl_field_visitors!(UIToFP, operand, dest);
 unop_explicitly_typed!(UIToFP, "uitofp");
 
 /// Convert signed integer to floating-point.
 /// See [LLVM 14 docs on the 'sitofp' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#sitofp-to-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SIToFP {
-    pub operand: Operand,
+    pub operand: OperandRef,
     pub to_type: TypeRef,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
@@ -1761,14 +2418,16 @@ pub struct SIToFP {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(SIToFP, SIToFP);
+impl_inst!(SIToFP, SIToFP, as_sitofp);
+impl_field_visitors!(SIToFP, operand, dest);
 unop_explicitly_typed!(SIToFP, "sitofp");
 
 /// Convert pointer to integer.
 /// See [LLVM 14 docs on the 'ptrtoint' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#ptrtoint-to-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PtrToInt {
-    pub operand: Operand,
+    pub operand: OperandRef,
     pub to_type: TypeRef,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
@@ -1776,14 +2435,16 @@ pub struct PtrToInt {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(PtrToInt, PtrToInt);
+impl_inst!(PtrToInt, PtrToInt, as_ptrtoint);
+impl_field_visitors!(PtrToInt, operand, dest);
 unop_explicitly_typed!(PtrToInt, "ptrtoint");
 
 /// Convert integer to pointer.
 /// See [LLVM 14 docs on the 'inttoptr' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#inttoptr-to-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IntToPtr {
-    pub operand: Operand,
+    pub operand: OperandRef,
     pub to_type: TypeRef,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
@@ -1791,14 +2452,16 @@ pub struct IntToPtr {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(IntToPtr, IntToPtr);
+impl_inst!(IntToPtr, IntToPtr, as_inttoptr);
+impl_field_visitors!(IntToPtr, operand, dest);
 unop_explicitly_typed!(IntToPtr, "inttoptr");
 
 /// Convert between types without changing any bits.
 /// See [LLVM 14 docs on the 'bitcast' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#bitcast-to-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BitCast {
-    pub operand: Operand,
+    pub operand: OperandRef,
     pub to_type: TypeRef,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
@@ -1806,14 +2469,16 @@ pub struct BitCast {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(BitCast, BitCast);
+impl_inst!(BitCast, BitCast, as_bitcast);
+impl_field_visitors!(BitCast, operand, dest);
 unop_explicitly_typed!(BitCast, "bitcast");
 
 /// Convert a pointer to a different address space.
 /// See [LLVM 14 docs on the 'addrspacecast' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#addrspacecast-to-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AddrSpaceCast {
-    pub operand: Operand,
+    pub operand: OperandRef,
     pub to_type: TypeRef,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
@@ -1821,23 +2486,26 @@ pub struct AddrSpaceCast {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(AddrSpaceCast, AddrSpaceCast);
+impl_inst!(AddrSpaceCast, AddrSpaceCast, as_addrspacecast);
+impl_field_visitors!(AddrSpaceCast, operand, dest);
 unop_explicitly_typed!(AddrSpaceCast, "addrspacecast");
 
 /// Compare integers, pointers, or vectors of integers or pointers.
 /// See [LLVM 14 docs on the 'icmp' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#icmp-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ICmp {
     pub predicate: IntPredicate,
-    pub operand0: Operand,
-    pub operand1: Operand,
+    pub operand0: OperandRef,
+    pub operand1: OperandRef,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(ICmp, ICmp);
+impl_inst!(ICmp, ICmp, as_icmp);
+impl_field_visitors!(ICmp, operand0, operand1, dest);
 impl_hasresult!(ICmp);
 
 impl Typed for ICmp {
@@ -1863,7 +2531,10 @@ impl Display for ICmp {
         write!(
             f,
             "{} = icmp {} {}, {}",
-            &self.dest, &self.predicate, &self.operand0, &self.operand1,
+            &self.dest,
+            &self.predicate,
+            &self.operand0,
+            self.operand1.untyped(),
         )?;
         #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
@@ -1875,18 +2546,20 @@ impl Display for ICmp {
 
 /// Compare floating-point values or vectors of floating-point values.
 /// See [LLVM 14 docs on the 'fcmp' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#fcmp-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FCmp {
     pub predicate: FPPredicate,
-    pub operand0: Operand,
-    pub operand1: Operand,
+    pub operand0: OperandRef,
+    pub operand1: OperandRef,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(FCmp, FCmp);
+impl_inst!(FCmp, FCmp, as_fcmp);
+impl_field_visitors!(FCmp, operand0, operand1, dest);
 impl_hasresult!(FCmp);
 
 impl Typed for FCmp {
@@ -1912,7 +2585,10 @@ impl Display for FCmp {
         write!(
             f,
             "{} = fcmp {} {}, {}",
-            &self.dest, &self.predicate, &self.operand0, &self.operand1,
+            &self.dest,
+            &self.predicate,
+            &self.operand0,
+            self.operand1.untyped(),
         )?;
         #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
@@ -1923,9 +2599,10 @@ impl Display for FCmp {
 }
 
 /// See [LLVM 14 docs on the 'phi' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#phi-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Phi {
-    pub incoming_values: Vec<(Operand, Name)>,
+    pub incoming_values: Vec<(OperandRef, Name)>,
     pub dest: Name,
     pub to_type: TypeRef,
     #[cfg(feature = "llvm-9-or-greater")]
@@ -1933,7 +2610,8 @@ pub struct Phi {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(Phi, Phi);
+impl_inst!(Phi, Phi, as_phi);
+impl_field_visitors!(Phi, incoming_values, dest);
 impl_hasresult!(Phi);
 explicitly_typed!(Phi);
 
@@ -1941,15 +2619,18 @@ impl Display for Phi {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let (first_val, first_label) = &self
             .incoming_values
-            .get(0)
+            .first()
             .expect("Phi with no incoming values");
         write!(
             f,
             "{} = phi {} [ {}, {} ]",
-            &self.dest, &self.to_type, first_val, first_label,
+            &self.dest,
+            &self.to_type,
+            first_val.untyped(),
+            first_label,
         )?;
         for (val, label) in &self.incoming_values[1 ..] {
-            write!(f, ", [ {}, {} ]", val, label)?;
+            write!(f, ", [ {}, {} ]", val.untyped(), label)?;
         }
         #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
@@ -1961,18 +2642,20 @@ impl Display for Phi {
 
 /// Choose between two values depending on a condition.
 /// See [LLVM 14 docs on the 'select' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#select-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Select {
-    pub condition: Operand,
-    pub true_value: Operand,
-    pub false_value: Operand,
+    pub condition: OperandRef,
+    pub true_value: OperandRef,
+    pub false_value: OperandRef,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(Select, Select);
+impl_inst!(Select, Select, as_select);
+impl_field_visitors!(Select, condition, true_value, false_value, dest);
 impl_hasresult!(Select);
 
 impl Typed for Select {
@@ -2001,9 +2684,10 @@ impl Display for Select {
 /// Stop the propagation of `undef` or `poison` values.
 /// See [LLVM 14 docs on the 'freeze' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#freeze-instruction)
 #[cfg(feature = "llvm-10-or-greater")]
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Freeze {
-    pub operand: Operand,
+    pub operand: OperandRef,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
@@ -2011,36 +2695,41 @@ pub struct Freeze {
 }
 
 #[cfg(feature = "llvm-10-or-greater")]
-impl_inst!(Freeze, Freeze);
+impl_inst!(Freeze, Freeze, as_freeze);
+#[cfg(feature = "llvm-10-or-greater")]
+impl_field_visitors!(Freeze, operand, dest);
 #[cfg(feature = "llvm-10-or-greater")]
 unop_same_type!(Freeze, "freeze");
 
 /// Function call.
 /// See [LLVM 14 docs on the 'call' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#call-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Call {
-    pub function: Either<InlineAssembly, Operand>,
-    pub arguments: Vec<(Operand, Vec<ParameterAttribute>)>,
+    pub function: Either<InlineAssembly, OperandRef>,
+    /// The type of the called function: a `FuncType`. With opaque pointers,
+    /// this can't be inferred from the type of `function`.
+    pub function_ty: TypeRef,
+    pub arguments: Vec<(OperandRef, Vec<ParameterAttribute>)>,
     pub return_attributes: Vec<ParameterAttribute>,
     pub dest: Option<Name>, // will be None if the `function` returns void
     pub function_attributes: Vec<FunctionAttribute>, // llvm-hs has the equivalent of Vec<Either<GroupID, FunctionAttribute>>, but I'm not sure how the GroupID option comes up
     pub is_tail_call: bool, // llvm-hs has the more sophisticated structure Option<TailCallKind>, but the LLVM C API just gives us true/false
     pub calling_convention: CallingConvention,
+    pub operand_bundles: Vec<OperandBundle>,
     #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(Call, Call);
+impl_inst!(Call, Call, as_call);
+impl_field_visitors!(Call, function, arguments, dest, operand_bundles);
 
 impl Typed for Call {
-    fn get_type(&self, types: &Types) -> TypeRef {
-        match types.type_of(&self.function).as_ref() {
-            Type::PointerType { pointee_type, .. } => match pointee_type.as_ref() {
-                Type::FuncType { result_type, .. } => result_type.clone(),
-                ty => panic!("Expected Call's function argument to be of type pointer-to-function, got pointer-to-{:?}", ty),
-            },
-            ty => panic!("Expected Call's function argument to be of type pointer-to-function, got {:?}", ty),
+    fn get_type(&self, _types: &Types) -> TypeRef {
+        match self.function_ty.as_ref() {
+            Type::FuncType { result_type, .. } => result_type.clone(),
+            ty => panic!("Expected Call's function_ty to be a FuncType, got {:?}", ty),
         }
     }
 }
@@ -2079,11 +2768,103 @@ impl Display for Call {
     }
 }
 
+impl Call {
+    /// Get the attributes of the argument with the given index at this call
+    /// site (e.g., `zeroext` or `byval(%struct.T)`), or an empty slice if
+    /// there is no such argument.
+    ///
+    /// These are the call site's own attributes, which may differ from those
+    /// of the called function's parameters.
+    pub fn argument_attributes(&self, index: usize) -> &[ParameterAttribute] {
+        self.arguments
+            .get(index)
+            .map_or(&[], |(_, attrs)| attrs.as_slice())
+    }
+
+    /// Iterate over the arguments of this call site, each with its
+    /// attributes
+    pub fn args_and_attrs(&self) -> impl Iterator<Item = (&Operand, &[ParameterAttribute])> {
+        self.arguments
+            .iter()
+            .map(|(arg, attrs)| (arg.as_ref(), attrs.as_slice()))
+    }
+
+    /// Iterate over the arguments of this call site, each with the type of
+    /// the corresponding parameter in `function_ty`, or `None` for arguments
+    /// passed to the variadic part of a varargs function
+    pub fn args_and_param_types(&self) -> impl Iterator<Item = (&Operand, Option<&TypeRef>)> {
+        let param_types = self.param_types();
+        self.arguments
+            .iter()
+            .enumerate()
+            .map(move |(i, (arg, _))| (arg.as_ref(), param_types.get(i)))
+    }
+
+    /// Get the arguments passed to the variadic part of a varargs function,
+    /// i.e., those after the fixed parameters of `function_ty`. This is empty
+    /// for calls to functions which aren't varargs.
+    pub fn variadic_arguments(&self) -> &[(OperandRef, Vec<ParameterAttribute>)] {
+        let num_fixed = self.param_types().len();
+        self.arguments.get(num_fixed ..).unwrap_or(&[])
+    }
+
+    fn param_types(&self) -> &[TypeRef] {
+        match self.function_ty.as_ref() {
+            Type::FuncType { param_types, .. } => param_types,
+            ty => panic!("Expected Call's function_ty to be a FuncType, got {:?}", ty),
+        }
+    }
+
+    /// Get the type of the argument with the given index, if it is passed
+    /// `byval` at this call site, in which case the callee gets its own copy
+    /// of the pointed-to value
+    #[cfg(feature = "llvm-12-or-greater")]
+    pub fn byval_type(&self, index: usize) -> Option<&TypeRef> {
+        self.argument_attributes(index)
+            .iter()
+            .find_map(|attr| match attr {
+                ParameterAttribute::ByVal(ty) => Some(ty),
+                _ => None,
+            })
+    }
+
+    /// Get the index and the pointed-to type of the `sret` argument at this
+    /// call site, if any: the argument through which the callee returns a
+    /// struct
+    #[cfg(feature = "llvm-12-or-greater")]
+    pub fn sret_argument(&self) -> Option<(usize, &TypeRef)> {
+        self.arguments
+            .iter()
+            .enumerate()
+            .find_map(|(i, (_, attrs))| {
+                attrs.iter().find_map(|attr| match attr {
+                    ParameterAttribute::SRet(ty) => Some((i, ty)),
+                    _ => None,
+                })
+            })
+    }
+}
+
+/// An operand bundle attached to a `Call` or `Invoke`, such as
+/// `"deopt"(i32 %x)`.
+///
+/// See [LLVM 14 docs on Operand Bundles](https://releases.llvm.org/14.0.0/docs/LangRef.html#operand-bundles)
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OperandBundle {
+    /// The bundle's tag, e.g., `"deopt"`, `"funclet"`, or `"gc-transition"`
+    pub tag: String,
+    pub args: Vec<OperandRef>,
+}
+
+impl_field_visitors!(OperandBundle, args);
+
 /// Used to access variadic arguments passed to a function.
 /// See [LLVM 14 docs on the 'va_arg' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#va-arg-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VAArg {
-    pub arg_list: Operand,
+    pub arg_list: OperandRef,
     pub cur_type: TypeRef,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
@@ -2091,7 +2872,8 @@ pub struct VAArg {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(VAArg, VAArg);
+impl_inst!(VAArg, VAArg, as_va_arg);
+impl_field_visitors!(VAArg, arg_list, dest);
 impl_hasresult!(VAArg);
 
 impl Typed for VAArg {
@@ -2117,7 +2899,8 @@ impl Display for VAArg {
 
 /// Used for exception handling.
 /// See [LLVM 14 docs on the 'landingpad' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#landingpad-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LandingPad {
     pub result_type: TypeRef,
     pub clauses: Vec<LandingPadClause>,
@@ -2128,7 +2911,8 @@ pub struct LandingPad {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(LandingPad, LandingPad);
+impl_inst!(LandingPad, LandingPad, as_landingpad);
+impl_field_visitors!(LandingPad, dest);
 impl_hasresult!(LandingPad);
 
 impl Typed for LandingPad {
@@ -2153,17 +2937,19 @@ impl Display for LandingPad {
 
 /// Used for exception handling.
 /// See [LLVM 14 docs on the 'catchpad' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#catchpad-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CatchPad {
-    pub catch_switch: Operand,
-    pub args: Vec<Operand>,
+    pub catch_switch: OperandRef,
+    pub args: Vec<OperandRef>,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(CatchPad, CatchPad);
+impl_inst!(CatchPad, CatchPad, as_catchpad);
+impl_field_visitors!(CatchPad, catch_switch, args, dest);
 impl_hasresult!(CatchPad);
 
 impl Typed for CatchPad {
@@ -2197,17 +2983,19 @@ impl Display for CatchPad {
 
 /// Used for exception handling.
 /// See [LLVM 14 docs on the 'cleanuppad' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#cleanuppad-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CleanupPad {
-    pub parent_pad: Operand,
-    pub args: Vec<Operand>,
+    pub parent_pad: OperandRef,
+    pub args: Vec<OperandRef>,
     pub dest: Name,
     #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_inst!(CleanupPad, CleanupPad);
+impl_inst!(CleanupPad, CleanupPad, as_cleanuppad);
+impl_field_visitors!(CleanupPad, parent_pad, args, dest);
 impl_hasresult!(CleanupPad);
 
 impl Typed for CleanupPad {
@@ -2239,8 +3027,56 @@ impl Display for CleanupPad {
     }
 }
 
+/// An instruction with an opcode `llvm-ir` doesn't otherwise handle, e.g.,
+/// one added by a newer version of LLVM than `llvm-ir` knows about. Only its
+/// operands and result are recorded.
+///
+/// Parsing produces these unless
+/// [`ParseOptions::strict_opcodes`](../module/struct.ParseOptions.html#structfield.strict_opcodes)
+/// is set, in which case such instructions are an error instead.
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Other {
+    /// The instruction's opcode, as the numeric value of LLVM's `LLVMOpcode`
+    pub opcode: u32,
+    pub operands: Vec<OperandRef>,
+    /// `None` if the instruction has void type
+    pub dest: Option<Name>,
+    /// The instruction's result type
+    pub ty: TypeRef,
+    #[cfg(feature = "llvm-9-or-greater")]
+    pub debugloc: Option<DebugLoc>,
+    // --TODO not yet implemented-- pub metadata: InstructionMetadata,
+}
+
+impl_inst!(Other, Other, as_other);
+impl_field_visitors!(Other, operands, dest);
+
+impl Typed for Other {
+    fn get_type(&self, _types: &Types) -> TypeRef {
+        self.ty.clone()
+    }
+}
+
+impl Display for Other {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(dest) = &self.dest {
+            write!(f, "{} = ", dest)?;
+        }
+        write!(f, "<opcode {}>", self.opcode)?;
+        for (i, op) in self.operands.iter().enumerate() {
+            write!(f, "{}{}", if i == 0 { " " } else { ", " }, op)?;
+        }
+        #[cfg(feature = "llvm-9-or-greater")]
+        if self.debugloc.is_some() {
+            write!(f, " (with debugloc)")?;
+        }
+        Ok(())
+    }
+}
+
 /*
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
 pub enum TailCallKind {
     Tail,
     MustTail,
@@ -2249,7 +3085,8 @@ pub enum TailCallKind {
 */
 
 /// See [LLVM 14 docs on Fast-Math Flags](https://releases.llvm.org/14.0.0/docs/LangRef.html#fastmath)
-#[derive(PartialEq, Eq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(non_snake_case)]
 pub struct FastMathFlags {
     pub allow_reassoc: bool,
@@ -2262,7 +3099,8 @@ pub struct FastMathFlags {
 }
 
 /// See [LLVM 14 docs on Atomic Memory Ordering Constraints](https://releases.llvm.org/14.0.0/docs/LangRef.html#ordering)
-#[derive(PartialEq, Eq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Atomicity {
     pub synch_scope: SynchronizationScope,
     pub mem_ordering: MemoryOrdering,
@@ -2280,14 +3118,16 @@ impl Display for Atomicity {
 }
 
 /// See [LLVM 14 docs on Atomic Memory Ordering Constraints](https://releases.llvm.org/14.0.0/docs/LangRef.html#ordering)
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SynchronizationScope {
     SingleThread,
     System,
 }
 
 /// See [LLVM 14 docs on Atomic Memory Ordering Constraints](https://releases.llvm.org/14.0.0/docs/LangRef.html#ordering)
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MemoryOrdering {
     Unordered,
     Monotonic,
@@ -2315,7 +3155,7 @@ impl Display for MemoryOrdering {
 // --TODO this seems to be the data structure we want. But see notes on
 // InlineAssembly::from_llvm_ref()
 /*
-#[derive(PartialEq, Eq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct InlineAssembly {
     pub assembly: String,
     pub ty: TypeRef,
@@ -2325,7 +3165,7 @@ pub struct InlineAssembly {
     pub dialect: AssemblyDialect,
 }
 
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
 pub enum AssemblyDialect {
     ATT,
     Intel,
@@ -2336,7 +3176,8 @@ pub enum AssemblyDialect {
 ///
 /// `InlineAssembly` needs more fields, but the necessary getter functions are
 /// apparently not exposed in the LLVM C API (only the C++ API)
-#[derive(PartialEq, Eq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InlineAssembly {
     pub ty: TypeRef,
 }
@@ -2347,8 +3188,18 @@ impl Typed for InlineAssembly {
     }
 }
 
+impl MapLocalNames for InlineAssembly {
+    fn map_local_names(&mut self, _f: &mut dyn FnMut(&mut Name)) {}
+}
+
+impl HasOperands for InlineAssembly {
+    fn push_operands<'a>(&'a self, _out: &mut Vec<&'a Operand>) {}
+    fn push_operands_mut<'a>(&'a mut self, _out: &mut Vec<&'a mut OperandRef>) {}
+}
+
 /// See [LLVM 14 docs on the 'atomicrmw' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#i-atomicrmw)
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RMWBinOp {
     Xchg,
     Add,
@@ -2392,7 +3243,7 @@ impl Display for RMWBinOp {
 // --TODO this seems to be the data structure we want. But see notes on
 // LandingPadClause::from_llvm_ref()
 /*
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub enum LandingPadClause {
     Catch(Constant),
     Filter(Constant),
@@ -2403,14 +3254,15 @@ pub enum LandingPadClause {
 ///
 /// `LandingPadClause` needs more fields, but the necessary getter functions are
 /// apparently not exposed in the LLVM C API (only the C++ API)
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LandingPadClause {}
 
 // ********* //
 // from_llvm //
 // ********* //
 
-use crate::constant::Constant;
+use crate::error::Error;
 use crate::from_llvm::*;
 use crate::function::FunctionContext;
 use crate::llvm_sys::*;
@@ -2429,117 +3281,123 @@ impl Instruction {
         inst: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
+    ) -> Result<Self, Error> {
         debug!("Processing instruction {:?}", unsafe {
             print_to_string(inst)
         });
-        match unsafe { LLVMGetInstructionOpcode(inst) } {
-            LLVMOpcode::LLVMAdd => Instruction::Add(Add::from_llvm_ref(inst, ctx, func_ctx)),
-            LLVMOpcode::LLVMSub => Instruction::Sub(Sub::from_llvm_ref(inst, ctx, func_ctx)),
-            LLVMOpcode::LLVMMul => Instruction::Mul(Mul::from_llvm_ref(inst, ctx, func_ctx)),
-            LLVMOpcode::LLVMUDiv => Instruction::UDiv(UDiv::from_llvm_ref(inst, ctx, func_ctx)),
-            LLVMOpcode::LLVMSDiv => Instruction::SDiv(SDiv::from_llvm_ref(inst, ctx, func_ctx)),
-            LLVMOpcode::LLVMURem => Instruction::URem(URem::from_llvm_ref(inst, ctx, func_ctx)),
-            LLVMOpcode::LLVMSRem => Instruction::SRem(SRem::from_llvm_ref(inst, ctx, func_ctx)),
-            LLVMOpcode::LLVMAnd => Instruction::And(And::from_llvm_ref(inst, ctx, func_ctx)),
-            LLVMOpcode::LLVMOr => Instruction::Or(Or::from_llvm_ref(inst, ctx, func_ctx)),
-            LLVMOpcode::LLVMXor => Instruction::Xor(Xor::from_llvm_ref(inst, ctx, func_ctx)),
-            LLVMOpcode::LLVMShl => Instruction::Shl(Shl::from_llvm_ref(inst, ctx, func_ctx)),
-            LLVMOpcode::LLVMLShr => Instruction::LShr(LShr::from_llvm_ref(inst, ctx, func_ctx)),
-            LLVMOpcode::LLVMAShr => Instruction::AShr(AShr::from_llvm_ref(inst, ctx, func_ctx)),
-            LLVMOpcode::LLVMFAdd => Instruction::FAdd(FAdd::from_llvm_ref(inst, ctx, func_ctx)),
-            LLVMOpcode::LLVMFSub => Instruction::FSub(FSub::from_llvm_ref(inst, ctx, func_ctx)),
-            LLVMOpcode::LLVMFMul => Instruction::FMul(FMul::from_llvm_ref(inst, ctx, func_ctx)),
-            LLVMOpcode::LLVMFDiv => Instruction::FDiv(FDiv::from_llvm_ref(inst, ctx, func_ctx)),
-            LLVMOpcode::LLVMFRem => Instruction::FRem(FRem::from_llvm_ref(inst, ctx, func_ctx)),
-            LLVMOpcode::LLVMFNeg => Instruction::FNeg(FNeg::from_llvm_ref(inst, ctx, func_ctx)),
+        Ok(match unsafe { LLVMGetInstructionOpcode(inst) } {
+            LLVMOpcode::LLVMAdd => Instruction::Add(Add::from_llvm_ref(inst, ctx, func_ctx)?),
+            LLVMOpcode::LLVMSub => Instruction::Sub(Sub::from_llvm_ref(inst, ctx, func_ctx)?),
+            LLVMOpcode::LLVMMul => Instruction::Mul(Mul::from_llvm_ref(inst, ctx, func_ctx)?),
+            LLVMOpcode::LLVMUDiv => Instruction::UDiv(UDiv::from_llvm_ref(inst, ctx, func_ctx)?),
+            LLVMOpcode::LLVMSDiv => Instruction::SDiv(SDiv::from_llvm_ref(inst, ctx, func_ctx)?),
+            LLVMOpcode::LLVMURem => Instruction::URem(URem::from_llvm_ref(inst, ctx, func_ctx)?),
+            LLVMOpcode::LLVMSRem => Instruction::SRem(SRem::from_llvm_ref(inst, ctx, func_ctx)?),
+            LLVMOpcode::LLVMAnd => Instruction::And(And::from_llvm_ref(inst, ctx, func_ctx)?),
+            LLVMOpcode::LLVMOr => Instruction::Or(Or::from_llvm_ref(inst, ctx, func_ctx)?),
+            LLVMOpcode::LLVMXor => Instruction::Xor(Xor::from_llvm_ref(inst, ctx, func_ctx)?),
+            LLVMOpcode::LLVMShl => Instruction::Shl(Shl::from_llvm_ref(inst, ctx, func_ctx)?),
+            LLVMOpcode::LLVMLShr => Instruction::LShr(LShr::from_llvm_ref(inst, ctx, func_ctx)?),
+            LLVMOpcode::LLVMAShr => Instruction::AShr(AShr::from_llvm_ref(inst, ctx, func_ctx)?),
+            LLVMOpcode::LLVMFAdd => Instruction::FAdd(FAdd::from_llvm_ref(inst, ctx, func_ctx)?),
+            LLVMOpcode::LLVMFSub => Instruction::FSub(FSub::from_llvm_ref(inst, ctx, func_ctx)?),
+            LLVMOpcode::LLVMFMul => Instruction::FMul(FMul::from_llvm_ref(inst, ctx, func_ctx)?),
+            LLVMOpcode::LLVMFDiv => Instruction::FDiv(FDiv::from_llvm_ref(inst, ctx, func_ctx)?),
+            LLVMOpcode::LLVMFRem => Instruction::FRem(FRem::from_llvm_ref(inst, ctx, func_ctx)?),
+            LLVMOpcode::LLVMFNeg => Instruction::FNeg(FNeg::from_llvm_ref(inst, ctx, func_ctx)?),
             LLVMOpcode::LLVMExtractElement => {
-                Instruction::ExtractElement(ExtractElement::from_llvm_ref(inst, ctx, func_ctx))
+                Instruction::ExtractElement(ExtractElement::from_llvm_ref(inst, ctx, func_ctx)?)
             },
             LLVMOpcode::LLVMInsertElement => {
-                Instruction::InsertElement(InsertElement::from_llvm_ref(inst, ctx, func_ctx))
+                Instruction::InsertElement(InsertElement::from_llvm_ref(inst, ctx, func_ctx)?)
             },
             LLVMOpcode::LLVMShuffleVector => {
-                Instruction::ShuffleVector(ShuffleVector::from_llvm_ref(inst, ctx, func_ctx))
+                Instruction::ShuffleVector(ShuffleVector::from_llvm_ref(inst, ctx, func_ctx)?)
             },
             LLVMOpcode::LLVMExtractValue => {
-                Instruction::ExtractValue(ExtractValue::from_llvm_ref(inst, ctx, func_ctx))
+                Instruction::ExtractValue(ExtractValue::from_llvm_ref(inst, ctx, func_ctx)?)
             },
             LLVMOpcode::LLVMInsertValue => {
-                Instruction::InsertValue(InsertValue::from_llvm_ref(inst, ctx, func_ctx))
+                Instruction::InsertValue(InsertValue::from_llvm_ref(inst, ctx, func_ctx)?)
             },
             LLVMOpcode::LLVMAlloca => {
-                Instruction::Alloca(Alloca::from_llvm_ref(inst, ctx, func_ctx))
+                Instruction::Alloca(Alloca::from_llvm_ref(inst, ctx, func_ctx)?)
             },
-            LLVMOpcode::LLVMLoad => Instruction::Load(Load::from_llvm_ref(inst, ctx, func_ctx)),
-            LLVMOpcode::LLVMStore => Instruction::Store(Store::from_llvm_ref(inst, ctx, func_ctx)),
-            LLVMOpcode::LLVMFence => Instruction::Fence(Fence::from_llvm_ref(inst)),
+            LLVMOpcode::LLVMLoad => Instruction::Load(Load::from_llvm_ref(inst, ctx, func_ctx)?),
+            LLVMOpcode::LLVMStore => Instruction::Store(Store::from_llvm_ref(inst, ctx, func_ctx)?),
+            LLVMOpcode::LLVMFence => Instruction::Fence(Fence::from_llvm_ref(inst, ctx)),
             LLVMOpcode::LLVMAtomicCmpXchg => {
-                Instruction::CmpXchg(CmpXchg::from_llvm_ref(inst, ctx, func_ctx))
+                Instruction::CmpXchg(CmpXchg::from_llvm_ref(inst, ctx, func_ctx)?)
             },
             LLVMOpcode::LLVMAtomicRMW => {
-                Instruction::AtomicRMW(AtomicRMW::from_llvm_ref(inst, ctx, func_ctx))
+                Instruction::AtomicRMW(AtomicRMW::from_llvm_ref(inst, ctx, func_ctx)?)
             },
             LLVMOpcode::LLVMGetElementPtr => {
-                Instruction::GetElementPtr(GetElementPtr::from_llvm_ref(inst, ctx, func_ctx))
+                Instruction::GetElementPtr(GetElementPtr::from_llvm_ref(inst, ctx, func_ctx)?)
             },
-            LLVMOpcode::LLVMTrunc => Instruction::Trunc(Trunc::from_llvm_ref(inst, ctx, func_ctx)),
-            LLVMOpcode::LLVMZExt => Instruction::ZExt(ZExt::from_llvm_ref(inst, ctx, func_ctx)),
-            LLVMOpcode::LLVMSExt => Instruction::SExt(SExt::from_llvm_ref(inst, ctx, func_ctx)),
+            LLVMOpcode::LLVMTrunc => Instruction::Trunc(Trunc::from_llvm_ref(inst, ctx, func_ctx)?),
+            LLVMOpcode::LLVMZExt => Instruction::ZExt(ZExt::from_llvm_ref(inst, ctx, func_ctx)?),
+            LLVMOpcode::LLVMSExt => Instruction::SExt(SExt::from_llvm_ref(inst, ctx, func_ctx)?),
             LLVMOpcode::LLVMFPTrunc => {
-                Instruction::FPTrunc(FPTrunc::from_llvm_ref(inst, ctx, func_ctx))
+                Instruction::FPTrunc(FPTrunc::from_llvm_ref(inst, ctx, func_ctx)?)
             },
-            LLVMOpcode::LLVMFPExt => Instruction::FPExt(FPExt::from_llvm_ref(inst, ctx, func_ctx)),
+            LLVMOpcode::LLVMFPExt => Instruction::FPExt(FPExt::from_llvm_ref(inst, ctx, func_ctx)?),
             LLVMOpcode::LLVMFPToUI => {
-                Instruction::FPToUI(FPToUI::from_llvm_ref(inst, ctx, func_ctx))
+                Instruction::FPToUI(FPToUI::from_llvm_ref(inst, ctx, func_ctx)?)
             },
             LLVMOpcode::LLVMFPToSI => {
-                Instruction::FPToSI(FPToSI::from_llvm_ref(inst, ctx, func_ctx))
+                Instruction::FPToSI(FPToSI::from_llvm_ref(inst, ctx, func_ctx)?)
             },
             LLVMOpcode::LLVMUIToFP => {
-                Instruction::UIToFP(UIToFP::from_llvm_ref(inst, ctx, func_ctx))
+                Instruction::UIToFP(UIToFP::from_llvm_ref(inst, ctx, func_ctx)?)
             },
             LLVMOpcode::LLVMSIToFP => {
-                Instruction::SIToFP(SIToFP::from_llvm_ref(inst, ctx, func_ctx))
+                Instruction::SIToFP(SIToFP::from_llvm_ref(inst, ctx, func_ctx)?)
             },
             LLVMOpcode::LLVMPtrToInt => {
-                Instruction::PtrToInt(PtrToInt::from_llvm_ref(inst, ctx, func_ctx))
+                Instruction::PtrToInt(PtrToInt::from_llvm_ref(inst, ctx, func_ctx)?)
             },
             LLVMOpcode::LLVMIntToPtr => {
-                Instruction::IntToPtr(IntToPtr::from_llvm_ref(inst, ctx, func_ctx))
+                Instruction::IntToPtr(IntToPtr::from_llvm_ref(inst, ctx, func_ctx)?)
             },
             LLVMOpcode::LLVMBitCast => {
-                Instruction::BitCast(BitCast::from_llvm_ref(inst, ctx, func_ctx))
+                Instruction::BitCast(BitCast::from_llvm_ref(inst, ctx, func_ctx)?)
             },
             LLVMOpcode::LLVMAddrSpaceCast => {
-                Instruction::AddrSpaceCast(AddrSpaceCast::from_llvm_ref(inst, ctx, func_ctx))
+                Instruction::AddrSpaceCast(AddrSpaceCast::from_llvm_ref(inst, ctx, func_ctx)?)
             },
-            LLVMOpcode::LLVMICmp => Instruction::ICmp(ICmp::from_llvm_ref(inst, ctx, func_ctx)),
-            LLVMOpcode::LLVMFCmp => Instruction::FCmp(FCmp::from_llvm_ref(inst, ctx, func_ctx)),
-            LLVMOpcode::LLVMPHI => Instruction::Phi(Phi::from_llvm_ref(inst, ctx, func_ctx)),
+            LLVMOpcode::LLVMICmp => Instruction::ICmp(ICmp::from_llvm_ref(inst, ctx, func_ctx)?),
+            LLVMOpcode::LLVMFCmp => Instruction::FCmp(FCmp::from_llvm_ref(inst, ctx, func_ctx)?),
+            LLVMOpcode::LLVMPHI => Instruction::Phi(Phi::from_llvm_ref(inst, ctx, func_ctx)?),
             LLVMOpcode::LLVMSelect => {
-                Instruction::Select(Select::from_llvm_ref(inst, ctx, func_ctx))
+                Instruction::Select(Select::from_llvm_ref(inst, ctx, func_ctx)?)
             },
             #[cfg(feature = "llvm-10-or-greater")]
             LLVMOpcode::LLVMFreeze => {
-                Instruction::Freeze(Freeze::from_llvm_ref(inst, ctx, func_ctx))
+                Instruction::Freeze(Freeze::from_llvm_ref(inst, ctx, func_ctx)?)
             },
-            LLVMOpcode::LLVMCall => Instruction::Call(Call::from_llvm_ref(inst, ctx, func_ctx)),
-            LLVMOpcode::LLVMVAArg => Instruction::VAArg(VAArg::from_llvm_ref(inst, ctx, func_ctx)),
+            LLVMOpcode::LLVMCall => Instruction::Call(Call::from_llvm_ref(inst, ctx, func_ctx)?),
+            LLVMOpcode::LLVMVAArg => Instruction::VAArg(VAArg::from_llvm_ref(inst, ctx, func_ctx)?),
             LLVMOpcode::LLVMLandingPad => {
-                Instruction::LandingPad(LandingPad::from_llvm_ref(inst, ctx, func_ctx))
+                Instruction::LandingPad(LandingPad::from_llvm_ref(inst, ctx, func_ctx)?)
             },
             LLVMOpcode::LLVMCatchPad => {
-                Instruction::CatchPad(CatchPad::from_llvm_ref(inst, ctx, func_ctx))
+                Instruction::CatchPad(CatchPad::from_llvm_ref(inst, ctx, func_ctx)?)
             },
             LLVMOpcode::LLVMCleanupPad => {
-                Instruction::CleanupPad(CleanupPad::from_llvm_ref(inst, ctx, func_ctx))
+                Instruction::CleanupPad(CleanupPad::from_llvm_ref(inst, ctx, func_ctx)?)
             },
-            opcode => panic!(
-                "Instruction::from_llvm_ref called with a terminator instruction (opcode {:?})",
-                opcode
-            ),
-        }
+            opcode if ctx.options.strict_opcodes => {
+                return Err(Error::unsupported(format!(
+                    "Instruction has unexpected opcode {:?}",
+                    opcode
+                )));
+            },
+            opcode => {
+                debug!("Instruction has unexpected opcode {:?}", opcode);
+                Instruction::Other(Other::from_llvm_ref(inst, ctx, func_ctx)?)
+            },
+        })
     }
 }
 
@@ -2550,19 +3408,22 @@ macro_rules! unop_from_llvm {
                 inst: LLVMValueRef,
                 ctx: &mut ModuleContext,
                 func_ctx: &mut FunctionContext,
-            ) -> Self {
+            ) -> Result<Self, Error> {
                 assert_eq!(unsafe { LLVMGetNumOperands(inst) }, 1);
-                Self {
-                    operand: Operand::from_llvm_ref(
+                Ok(Self {
+                    operand: OperandRef::from_llvm_ref(
                         unsafe { LLVMGetOperand(inst, 0) },
                         ctx,
                         func_ctx,
+                    )?,
+                    dest: Name::name_or_num(
+                        unsafe { get_value_name_bytes(inst) },
+                        &mut func_ctx.ctr,
                     ),
-                    dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
                     #[cfg(feature = "llvm-9-or-greater")]
-                    debugloc: DebugLoc::from_llvm_with_col(inst),
+                    debugloc: DebugLoc::from_llvm_with_col(inst, ctx),
                     // metadata: InstructionMetadata::from_llvm_inst(inst),
-                }
+                })
             }
         }
     };
@@ -2575,24 +3436,27 @@ macro_rules! binop_from_llvm {
                 inst: LLVMValueRef,
                 ctx: &mut ModuleContext,
                 func_ctx: &mut FunctionContext,
-            ) -> Self {
+            ) -> Result<Self, Error> {
                 assert_eq!(unsafe { LLVMGetNumOperands(inst) }, 2);
-                Self {
-                    operand0: Operand::from_llvm_ref(
+                Ok(Self {
+                    operand0: OperandRef::from_llvm_ref(
                         unsafe { LLVMGetOperand(inst, 0) },
                         ctx,
                         func_ctx,
-                    ),
-                    operand1: Operand::from_llvm_ref(
+                    )?,
+                    operand1: OperandRef::from_llvm_ref(
                         unsafe { LLVMGetOperand(inst, 1) },
                         ctx,
                         func_ctx,
+                    )?,
+                    dest: Name::name_or_num(
+                        unsafe { get_value_name_bytes(inst) },
+                        &mut func_ctx.ctr,
                     ),
-                    dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
                     #[cfg(feature = "llvm-9-or-greater")]
-                    debugloc: DebugLoc::from_llvm_with_col(inst),
+                    debugloc: DebugLoc::from_llvm_with_col(inst, ctx),
                     // metadata: InstructionMetadata::from_llvm_inst(inst),
-                }
+                })
             }
         }
     };
@@ -2625,16 +3489,16 @@ impl ExtractElement {
         inst: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
+    ) -> Result<Self, Error> {
         assert_eq!(unsafe { LLVMGetNumOperands(inst) }, 2);
-        Self {
-            vector: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx),
-            index: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 1) }, ctx, func_ctx),
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
+        Ok(Self {
+            vector: OperandRef::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx)?,
+            index: OperandRef::from_llvm_ref(unsafe { LLVMGetOperand(inst, 1) }, ctx, func_ctx)?,
+            dest: Name::name_or_num(unsafe { get_value_name_bytes(inst) }, &mut func_ctx.ctr),
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            debugloc: DebugLoc::from_llvm_with_col(inst, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
-        }
+        })
     }
 }
 
@@ -2643,17 +3507,17 @@ impl InsertElement {
         inst: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
+    ) -> Result<Self, Error> {
         assert_eq!(unsafe { LLVMGetNumOperands(inst) }, 3);
-        Self {
-            vector: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx),
-            element: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 1) }, ctx, func_ctx),
-            index: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 2) }, ctx, func_ctx),
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
+        Ok(Self {
+            vector: OperandRef::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx)?,
+            element: OperandRef::from_llvm_ref(unsafe { LLVMGetOperand(inst, 1) }, ctx, func_ctx)?,
+            index: OperandRef::from_llvm_ref(unsafe { LLVMGetOperand(inst, 2) }, ctx, func_ctx)?,
+            dest: Name::name_or_num(unsafe { get_value_name_bytes(inst) }, &mut func_ctx.ctr),
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            debugloc: DebugLoc::from_llvm_with_col(inst, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
-        }
+        })
     }
 }
 
@@ -2662,16 +3526,16 @@ impl ShuffleVector {
         inst: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
+    ) -> Result<Self, Error> {
         #[cfg(feature = "llvm-10-or-lower")]
         assert_eq!(unsafe { LLVMGetNumOperands(inst) }, 3);
         #[cfg(feature = "llvm-11-or-greater")]
         assert_eq!(unsafe { LLVMGetNumOperands(inst) }, 2);
-        Self {
-            operand0: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx),
-            operand1: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 1) }, ctx, func_ctx),
+        Ok(Self {
+            operand0: OperandRef::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx)?,
+            operand1: OperandRef::from_llvm_ref(unsafe { LLVMGetOperand(inst, 1) }, ctx, func_ctx)?,
             #[cfg(feature = "llvm-10-or-lower")]
-            mask: Constant::from_llvm_ref(unsafe { LLVMGetOperand(inst, 2) }, ctx),
+            mask: Constant::from_llvm_ref(unsafe { LLVMGetOperand(inst, 2) }, ctx)?,
             #[cfg(feature = "llvm-11-or-greater")]
             mask: {
                 let ret_ty = ctx.types.type_from_llvm_ref(unsafe { LLVMTypeOf(inst) });
@@ -2700,11 +3564,11 @@ impl ShuffleVector {
                     ty => panic!("ShuffleVector: expected instruction result type to be a vector type; got {:?}", ty),
                 }
             },
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
+            dest: Name::name_or_num(unsafe { get_value_name_bytes(inst) }, &mut func_ctx.ctr),
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            debugloc: DebugLoc::from_llvm_with_col(inst, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
-        }
+        })
     }
 }
 
@@ -2713,20 +3577,24 @@ impl ExtractValue {
         inst: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
+    ) -> Result<Self, Error> {
         assert_eq!(unsafe { LLVMGetNumOperands(inst) }, 1);
-        Self {
-            aggregate: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx),
+        Ok(Self {
+            aggregate: OperandRef::from_llvm_ref(
+                unsafe { LLVMGetOperand(inst, 0) },
+                ctx,
+                func_ctx,
+            )?,
             indices: unsafe {
                 let num_indices = LLVMGetNumIndices(inst);
                 let ptr = LLVMGetIndices(inst);
                 std::slice::from_raw_parts(ptr, num_indices as usize).to_vec()
             },
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
+            dest: Name::name_or_num(unsafe { get_value_name_bytes(inst) }, &mut func_ctx.ctr),
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            debugloc: DebugLoc::from_llvm_with_col(inst, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
-        }
+        })
     }
 }
 
@@ -2735,21 +3603,25 @@ impl InsertValue {
         inst: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
+    ) -> Result<Self, Error> {
         assert_eq!(unsafe { LLVMGetNumOperands(inst) }, 2);
-        Self {
-            aggregate: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx),
-            element: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 1) }, ctx, func_ctx),
+        Ok(Self {
+            aggregate: OperandRef::from_llvm_ref(
+                unsafe { LLVMGetOperand(inst, 0) },
+                ctx,
+                func_ctx,
+            )?,
+            element: OperandRef::from_llvm_ref(unsafe { LLVMGetOperand(inst, 1) }, ctx, func_ctx)?,
             indices: unsafe {
                 let num_indices = LLVMGetNumIndices(inst);
                 let ptr = LLVMGetIndices(inst);
                 std::slice::from_raw_parts(ptr, num_indices as usize).to_vec()
             },
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
+            dest: Name::name_or_num(unsafe { get_value_name_bytes(inst) }, &mut func_ctx.ctr),
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            debugloc: DebugLoc::from_llvm_with_col(inst, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
-        }
+        })
     }
 }
 
@@ -2758,23 +3630,23 @@ impl Alloca {
         inst: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
+    ) -> Result<Self, Error> {
         assert_eq!(unsafe { LLVMGetNumOperands(inst) }, 1);
-        Self {
+        Ok(Self {
             allocated_type: ctx
                 .types
                 .type_from_llvm_ref(unsafe { LLVMGetAllocatedType(inst) }),
-            num_elements: Operand::from_llvm_ref(
+            num_elements: OperandRef::from_llvm_ref(
                 unsafe { LLVMGetOperand(inst, 0) }, // This is a guess. or maybe num_elements is included in allocated_type?
                 ctx,
                 func_ctx,
-            ),
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
-            alignment: unsafe { LLVMGetAlignment(inst) },
+            )?,
+            dest: Name::name_or_num(unsafe { get_value_name_bytes(inst) }, &mut func_ctx.ctr),
+            alignment: NonZeroU32::new(unsafe { LLVMGetAlignment(inst) }),
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            debugloc: DebugLoc::from_llvm_with_col(inst, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
-        }
+        })
     }
 }
 
@@ -2783,11 +3655,12 @@ impl Load {
         inst: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
+    ) -> Result<Self, Error> {
         assert_eq!(unsafe { LLVMGetNumOperands(inst) }, 1);
-        Self {
-            address: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx),
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
+        Ok(Self {
+            address: OperandRef::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx)?,
+            loaded_type: ctx.types.type_from_llvm_ref(unsafe { LLVMTypeOf(inst) }),
+            dest: Name::name_or_num(unsafe { get_value_name_bytes(inst) }, &mut func_ctx.ctr),
             volatile: unsafe { LLVMGetVolatile(inst) } != 0,
             atomicity: {
                 let ordering = unsafe { LLVMGetOrdering(inst) };
@@ -2800,11 +3673,11 @@ impl Load {
                     })
                 }
             },
-            alignment: unsafe { LLVMGetAlignment(inst) },
+            alignment: NonZeroU32::new(unsafe { LLVMGetAlignment(inst) }),
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            debugloc: DebugLoc::from_llvm_with_col(inst, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
-        }
+        })
     }
 }
 
@@ -2813,11 +3686,11 @@ impl Store {
         inst: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
+    ) -> Result<Self, Error> {
         assert_eq!(unsafe { LLVMGetNumOperands(inst) }, 2);
-        Self {
-            address: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 1) }, ctx, func_ctx),
-            value: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx),
+        Ok(Self {
+            address: OperandRef::from_llvm_ref(unsafe { LLVMGetOperand(inst, 1) }, ctx, func_ctx)?,
+            value: OperandRef::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx)?,
             volatile: unsafe { LLVMGetVolatile(inst) } != 0,
             atomicity: {
                 let ordering = unsafe { LLVMGetOrdering(inst) };
@@ -2830,16 +3703,18 @@ impl Store {
                     })
                 }
             },
-            alignment: unsafe { LLVMGetAlignment(inst) },
+            alignment: NonZeroU32::new(unsafe { LLVMGetAlignment(inst) }),
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            debugloc: DebugLoc::from_llvm_with_col(inst, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
-        }
+        })
     }
 }
 
 impl Fence {
-    pub(crate) fn from_llvm_ref(inst: LLVMValueRef) -> Self {
+    // `ctx` is only needed for the `debugloc`
+    #[cfg_attr(not(feature = "llvm-9-or-greater"), allow(unused_variables))]
+    pub(crate) fn from_llvm_ref(inst: LLVMValueRef, ctx: &ModuleContext) -> Self {
         assert_eq!(unsafe { LLVMGetNumOperands(inst) }, 0);
         Self {
             atomicity: Atomicity {
@@ -2847,7 +3722,7 @@ impl Fence {
                 mem_ordering: MemoryOrdering::from_llvm(unsafe { LLVMGetOrdering(inst) }),
             },
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            debugloc: DebugLoc::from_llvm_with_col(inst, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
         }
     }
@@ -2858,13 +3733,17 @@ impl CmpXchg {
         inst: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
+    ) -> Result<Self, Error> {
         assert_eq!(unsafe { LLVMGetNumOperands(inst) }, 3);
-        Self {
-            address: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx),
-            expected: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 1) }, ctx, func_ctx),
-            replacement: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 2) }, ctx, func_ctx),
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
+        Ok(Self {
+            address: OperandRef::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx)?,
+            expected: OperandRef::from_llvm_ref(unsafe { LLVMGetOperand(inst, 1) }, ctx, func_ctx)?,
+            replacement: OperandRef::from_llvm_ref(
+                unsafe { LLVMGetOperand(inst, 2) },
+                ctx,
+                func_ctx,
+            )?,
+            dest: Name::name_or_num(unsafe { get_value_name_bytes(inst) }, &mut func_ctx.ctr),
             volatile: unsafe { LLVMGetVolatile(inst) } != 0,
             atomicity: Atomicity {
                 synch_scope: SynchronizationScope::from_llvm_ref(inst),
@@ -2878,9 +3757,9 @@ impl CmpXchg {
             #[cfg(feature = "llvm-10-or-greater")]
             weak: unsafe { LLVMGetWeak(inst) } != 0,
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            debugloc: DebugLoc::from_llvm_with_col(inst, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
-        }
+        })
     }
 }
 
@@ -2889,24 +3768,24 @@ impl AtomicRMW {
         inst: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
+    ) -> Result<Self, Error> {
         assert_eq!(unsafe { LLVMGetNumOperands(inst) }, 2);
-        Self {
+        Ok(Self {
             // the binop-getter was added to the LLVM C API in LLVM 10
             #[cfg(feature = "llvm-10-or-greater")]
             operation: RMWBinOp::from_llvm(unsafe { LLVMGetAtomicRMWBinOp(inst) }),
-            address: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx),
-            value: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 1) }, ctx, func_ctx),
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
+            address: OperandRef::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx)?,
+            value: OperandRef::from_llvm_ref(unsafe { LLVMGetOperand(inst, 1) }, ctx, func_ctx)?,
+            dest: Name::name_or_num(unsafe { get_value_name_bytes(inst) }, &mut func_ctx.ctr),
             volatile: unsafe { LLVMGetVolatile(inst) } != 0,
             atomicity: Atomicity {
                 synch_scope: SynchronizationScope::from_llvm_ref(inst),
                 mem_ordering: MemoryOrdering::from_llvm(unsafe { LLVMGetOrdering(inst) }),
             },
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            debugloc: DebugLoc::from_llvm_with_col(inst, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
-        }
+        })
     }
 }
 
@@ -2915,23 +3794,38 @@ impl GetElementPtr {
         inst: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
-        Self {
-            address: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx),
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            address: OperandRef::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx)?,
             indices: {
                 let num_indices = unsafe { LLVMGetNumIndices(inst) };
                 (1 ..= num_indices)
                     .map(|i| {
-                        Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, i) }, ctx, func_ctx)
+                        OperandRef::from_llvm_ref(unsafe { LLVMGetOperand(inst, i) }, ctx, func_ctx)
                     })
-                    .collect()
+                    .collect::<Result<_, _>>()?
             },
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
+            dest: Name::name_or_num(unsafe { get_value_name_bytes(inst) }, &mut func_ctx.ctr),
             in_bounds: unsafe { LLVMIsInBounds(inst) } != 0,
+            #[cfg(feature = "llvm-14-or-greater")]
+            source_element_type: ctx
+                .types
+                .type_from_llvm_ref(unsafe { LLVMGetGEPSourceElementType(inst) }),
+            #[cfg(feature = "llvm-13-or-lower")]
+            source_element_type: {
+                let address_ty = ctx
+                    .types
+                    .type_from_llvm_ref(unsafe { LLVMTypeOf(LLVMGetOperand(inst, 0)) });
+                match address_ty.as_ref() {
+                    Type::VectorType { element_type, .. } => element_type.pointee_type(),
+                    _ => address_ty.pointee_type(),
+                }
+                .expect("GEP address should be a pointer or a vector of pointers")
+            },
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            debugloc: DebugLoc::from_llvm_with_col(inst, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
-        }
+        })
     }
 }
 
@@ -2944,20 +3838,23 @@ macro_rules! typed_unop_from_llvm {
                 inst: LLVMValueRef,
                 ctx: &mut ModuleContext,
                 func_ctx: &mut FunctionContext,
-            ) -> Self {
+            ) -> Result<Self, Error> {
                 assert_eq!(unsafe { LLVMGetNumOperands(inst) }, 1);
-                Self {
-                    operand: Operand::from_llvm_ref(
+                Ok(Self {
+                    operand: OperandRef::from_llvm_ref(
                         unsafe { LLVMGetOperand(inst, 0) },
                         ctx,
                         func_ctx,
-                    ),
+                    )?,
                     to_type: ctx.types.type_from_llvm_ref(unsafe { LLVMTypeOf(inst) }),
-                    dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
+                    dest: Name::name_or_num(
+                        unsafe { get_value_name_bytes(inst) },
+                        &mut func_ctx.ctr,
+                    ),
                     #[cfg(feature = "llvm-9-or-greater")]
-                    debugloc: DebugLoc::from_llvm_with_col(inst),
+                    debugloc: DebugLoc::from_llvm_with_col(inst, ctx),
                     // metadata: InstructionMetadata::from_llvm_inst(inst),
-                }
+                })
             }
         }
     };
@@ -2982,17 +3879,17 @@ impl ICmp {
         inst: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
+    ) -> Result<Self, Error> {
         assert_eq!(unsafe { LLVMGetNumOperands(inst) }, 2);
-        Self {
+        Ok(Self {
             predicate: IntPredicate::from_llvm(unsafe { LLVMGetICmpPredicate(inst) }),
-            operand0: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx),
-            operand1: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 1) }, ctx, func_ctx),
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
+            operand0: OperandRef::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx)?,
+            operand1: OperandRef::from_llvm_ref(unsafe { LLVMGetOperand(inst, 1) }, ctx, func_ctx)?,
+            dest: Name::name_or_num(unsafe { get_value_name_bytes(inst) }, &mut func_ctx.ctr),
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            debugloc: DebugLoc::from_llvm_with_col(inst, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
-        }
+        })
     }
 }
 
@@ -3001,17 +3898,17 @@ impl FCmp {
         inst: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
+    ) -> Result<Self, Error> {
         assert_eq!(unsafe { LLVMGetNumOperands(inst) }, 2);
-        Self {
+        Ok(Self {
             predicate: FPPredicate::from_llvm(unsafe { LLVMGetFCmpPredicate(inst) }),
-            operand0: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx),
-            operand1: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 1) }, ctx, func_ctx),
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
+            operand0: OperandRef::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx)?,
+            operand1: OperandRef::from_llvm_ref(unsafe { LLVMGetOperand(inst, 1) }, ctx, func_ctx)?,
+            dest: Name::name_or_num(unsafe { get_value_name_bytes(inst) }, &mut func_ctx.ctr),
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            debugloc: DebugLoc::from_llvm_with_col(inst, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
-        }
+        })
     }
 }
 
@@ -3020,32 +3917,32 @@ impl Phi {
         inst: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
-        Self {
+    ) -> Result<Self, Error> {
+        Ok(Self {
             incoming_values: {
                 let num_incoming = unsafe { LLVMCountIncoming(inst) };
                 (0 .. num_incoming)
                     .map(|i| {
-                        let operand = Operand::from_llvm_ref(
+                        let operand = OperandRef::from_llvm_ref(
                             unsafe { LLVMGetIncomingValue(inst, i) },
                             ctx,
                             func_ctx,
-                        );
+                        )?;
                         let name = func_ctx
                             .bb_names
                             .get(unsafe { &LLVMGetIncomingBlock(inst, i) })
                             .expect("Failed to find incoming block in the map")
                             .clone();
-                        (operand, name)
+                        Ok((operand, name))
                     })
-                    .collect()
+                    .collect::<Result<_, _>>()?
             },
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
+            dest: Name::name_or_num(unsafe { get_value_name_bytes(inst) }, &mut func_ctx.ctr),
             to_type: ctx.types.type_from_llvm_ref(unsafe { LLVMTypeOf(inst) }),
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            debugloc: DebugLoc::from_llvm_with_col(inst, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
-        }
+        })
     }
 }
 
@@ -3054,24 +3951,37 @@ impl Select {
         inst: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
+    ) -> Result<Self, Error> {
         assert_eq!(unsafe { LLVMGetNumOperands(inst) }, 3);
-        Self {
-            condition: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx),
-            true_value: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 1) }, ctx, func_ctx),
-            false_value: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 2) }, ctx, func_ctx),
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
+        Ok(Self {
+            condition: OperandRef::from_llvm_ref(
+                unsafe { LLVMGetOperand(inst, 0) },
+                ctx,
+                func_ctx,
+            )?,
+            true_value: OperandRef::from_llvm_ref(
+                unsafe { LLVMGetOperand(inst, 1) },
+                ctx,
+                func_ctx,
+            )?,
+            false_value: OperandRef::from_llvm_ref(
+                unsafe { LLVMGetOperand(inst, 2) },
+                ctx,
+                func_ctx,
+            )?,
+            dest: Name::name_or_num(unsafe { get_value_name_bytes(inst) }, &mut func_ctx.ctr),
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            debugloc: DebugLoc::from_llvm_with_col(inst, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
-        }
+        })
     }
 }
 
 // just the logic shared by Call and Invoke. Not a public struct, just an implementation convenience.
 pub(crate) struct CallInfo {
-    pub function: Either<InlineAssembly, Operand>,
-    pub arguments: Vec<(Operand, Vec<ParameterAttribute>)>,
+    pub function: Either<InlineAssembly, OperandRef>,
+    pub function_ty: TypeRef,
+    pub arguments: Vec<(OperandRef, Vec<ParameterAttribute>)>,
     pub return_attributes: Vec<ParameterAttribute>,
     pub function_attributes: Vec<FunctionAttribute>,
     pub calling_convention: CallingConvention,
@@ -3083,34 +3993,36 @@ impl CallInfo {
         inst: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
+    ) -> Result<Self, Error> {
         use llvm_sys::{LLVMAttributeFunctionIndex, LLVMAttributeReturnIndex};
-        Self {
+        Ok(Self {
             function: {
                 let called_val = unsafe { LLVMGetCalledValue(inst) };
                 let asm = unsafe { LLVMIsAInlineAsm(called_val) };
                 if !asm.is_null() {
                     Either::Left(InlineAssembly::from_llvm_ref(asm, &mut ctx.types))
                 } else {
-                    Either::Right(Operand::from_llvm_ref(called_val, ctx, func_ctx))
+                    Either::Right(OperandRef::from_llvm_ref(called_val, ctx, func_ctx)?)
                 }
             },
+            function_ty: ctx
+                .types
+                .type_from_llvm_ref(unsafe { LLVMGetCalledFunctionType(inst) }),
             arguments: {
                 let num_args: u32 = unsafe { LLVMGetNumArgOperands(inst) } as u32;
                 (0 .. num_args) // arguments are (0 .. num_args); other operands (such as the called function) are after that
                     .map(|i| {
-                        let operand = Operand::from_llvm_ref(
+                        let operand = OperandRef::from_llvm_ref(
                             unsafe { LLVMGetOperand(inst, i) },
                             ctx,
                             func_ctx,
-                        );
+                        )?;
                         let attrs = {
-                            let num_attrs =
-                                unsafe { LLVMGetCallSiteAttributeCount(inst, (i + 1) as u32) }; // see LLVM C API (Core.h) comments on `LLVMAttributeReturnIndex` and `LLVMAttributeFunctionIndex`
+                            let num_attrs = unsafe { LLVMGetCallSiteAttributeCount(inst, i + 1) }; // see LLVM C API (Core.h) comments on `LLVMAttributeReturnIndex` and `LLVMAttributeFunctionIndex`
                             let mut attrs: Vec<LLVMAttributeRef> =
                                 Vec::with_capacity(num_attrs as usize);
                             unsafe {
-                                LLVMGetCallSiteAttributes(inst, (i + 1) as u32, attrs.as_mut_ptr());
+                                LLVMGetCallSiteAttributes(inst, i + 1, attrs.as_mut_ptr());
                                 attrs.set_len(num_attrs as usize);
                             };
                             attrs
@@ -3125,9 +4037,9 @@ impl CallInfo {
                                 })
                                 .collect()
                         };
-                        (operand, attrs)
+                        Ok((operand, attrs))
                     })
-                    .collect()
+                    .collect::<Result<_, _>>()?
             },
             return_attributes: {
                 let num_attrs =
@@ -3165,7 +4077,7 @@ impl CallInfo {
             calling_convention: CallingConvention::from_u32(unsafe {
                 LLVMGetInstructionCallConv(inst)
             }),
-        }
+        })
     }
 }
 
@@ -3174,10 +4086,11 @@ impl Call {
         inst: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
-        let callinfo = CallInfo::from_llvm_ref(inst, ctx, func_ctx);
-        Self {
+    ) -> Result<Self, Error> {
+        let callinfo = CallInfo::from_llvm_ref(inst, ctx, func_ctx)?;
+        Ok(Self {
             function: callinfo.function,
+            function_ty: callinfo.function_ty,
             arguments: callinfo.arguments,
             return_attributes: callinfo.return_attributes,
             dest: if unsafe {
@@ -3187,35 +4100,169 @@ impl Call {
                 None
             } else {
                 Some(Name::name_or_num(
-                    unsafe { get_value_name(inst) },
+                    unsafe { get_value_name_bytes(inst) },
                     &mut func_ctx.ctr,
                 ))
             },
             function_attributes: callinfo.function_attributes,
             is_tail_call: unsafe { LLVMIsTailCall(inst) } != 0,
             calling_convention: callinfo.calling_convention,
+            operand_bundles: OperandBundle::all_from_llvm_ref(inst, 1, ctx, func_ctx)?, // the called function is the last operand
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            debugloc: DebugLoc::from_llvm_with_col(inst, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
+        })
+    }
+}
+impl OperandBundle {
+    /// Get the operand bundles of the given call or invoke.
+    ///
+    /// `num_trailing_operands` is the number of operands which the instruction
+    /// has after its bundle operands: e.g., the called function.
+    pub(crate) fn all_from_llvm_ref(
+        inst: LLVMValueRef,
+        num_trailing_operands: u32,
+        ctx: &mut ModuleContext,
+        func_ctx: &mut FunctionContext,
+    ) -> Result<Vec<Self>, Error> {
+        let first_operand = unsafe { LLVMGetNumArgOperands(inst) } as u32;
+        let num_operands =
+            unsafe { LLVMGetNumOperands(inst) } as u32 - first_operand - num_trailing_operands;
+        // The operands of each bundle come after the call's arguments, but
+        // the LLVM C API doesn't tell us the bundle tags, or how many
+        // operands each bundle has, or even whether there are any bundles
+        // (which may have no operands). So we get those from LLVM's textual
+        // representation of the instruction.
+        let shapes = Self::parse_shapes(&unsafe { print_to_string(inst) })?;
+        if shapes.iter().map(|(_, n)| n).sum::<u32>() != num_operands {
+            return Err(Error::unsupported(format!(
+                "Mismatch between operand bundles and bundle operands; bundles are {:?}",
+                shapes,
+            )));
+        }
+        let mut next_operand = first_operand;
+        shapes
+            .into_iter()
+            .map(|(tag, num_args)| {
+                let args = (next_operand .. next_operand + num_args)
+                    .map(|i| {
+                        OperandRef::from_llvm_ref(unsafe { LLVMGetOperand(inst, i) }, ctx, func_ctx)
+                    })
+                    .collect::<Result<_, _>>()?;
+                next_operand += num_args;
+                Ok(Self { tag, args })
+            })
+            .collect()
+    }
+
+    /// Given the text of a call or invoke, get the tag and number of operands
+    /// of each of its operand bundles, which appear as e.g.
+    /// `[ "deopt"(i32 %x, i32 7), "gc-transition"() ]`
+    fn parse_shapes(text: &str) -> Result<Vec<(String, u32)>, Error> {
+        let malformed =
+            || Error::unsupported(format!("Failed to parse operand bundles in {:?}", text));
+        let bytes = text.as_bytes();
+        // find the start of the bundle list: a '[' followed by a tag, outside
+        // of any string or brackets (so that it isn't part of an argument)
+        let mut depth = 0;
+        let mut in_string = false;
+        let mut start = None;
+        for (i, &b) in bytes.iter().enumerate() {
+            match b {
+                b'"' => in_string = !in_string, // LLVM escapes '"' within strings
+                _ if in_string => {},
+                b'[' if depth == 0 && bytes[i + 1 ..].starts_with(b" \"") => {
+                    start = Some(i + 2);
+                    break;
+                },
+                b'(' | b'[' | b'{' | b'<' => depth += 1,
+                b')' | b']' | b'}' | b'>' => depth -= 1,
+                _ => {},
+            }
+        }
+        let mut pos = match start {
+            Some(start) => start,
+            None => return Ok(vec![]),
+        };
+
+        let mut shapes = vec![];
+        loop {
+            // `pos` is at the opening quote of a tag
+            if bytes.get(pos) != Some(&b'"') {
+                return Err(malformed());
+            }
+            let tag_end = pos + 1 + text[pos + 1 ..].find('"').ok_or_else(malformed)?;
+            let tag = unescape(&text[pos + 1 .. tag_end]).ok_or_else(malformed)?;
+            pos = tag_end + 1;
+            if bytes.get(pos) != Some(&b'(') {
+                return Err(malformed());
+            }
+            pos += 1;
+            // count the top-level commas in the argument list
+            let args_start = pos;
+            let mut depth = 0;
+            let mut in_string = false;
+            let mut num_commas = 0;
+            loop {
+                match *bytes.get(pos).ok_or_else(malformed)? {
+                    b'"' => in_string = !in_string,
+                    _ if in_string => {},
+                    b')' if depth == 0 => break,
+                    b'(' | b'[' | b'{' | b'<' => depth += 1,
+                    b')' | b']' | b'}' | b'>' => depth -= 1,
+                    b',' if depth == 0 => num_commas += 1,
+                    _ => {},
+                }
+                pos += 1;
+            }
+            let num_args = if text[args_start .. pos].trim().is_empty() {
+                0
+            } else {
+                num_commas + 1
+            };
+            shapes.push((tag, num_args));
+            // skip past the ')' and any ", " separating this bundle from the next
+            match text[pos + 1 ..].trim_start().strip_prefix(',') {
+                Some(rest) => pos = text.len() - rest.trim_start().len(),
+                None => return Ok(shapes),
+            }
         }
     }
 }
 
+/// Undo LLVM's escaping of a quoted string, in which non-printable characters,
+/// '"', and '\' are written as '\' followed by two hex digits. Returns `None`
+/// if an escape sequence is invalid.
+fn unescape(s: &str) -> Option<String> {
+    let mut bytes = vec![];
+    let mut iter = s.bytes();
+    while let Some(b) = iter.next() {
+        if b == b'\\' {
+            let hex: Vec<u8> = iter.by_ref().take(2).collect();
+            let hex = std::str::from_utf8(&hex).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
 impl VAArg {
     pub(crate) fn from_llvm_ref(
         inst: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
+    ) -> Result<Self, Error> {
         assert_eq!(unsafe { LLVMGetNumOperands(inst) }, 1);
-        Self {
-            arg_list: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx),
+        Ok(Self {
+            arg_list: OperandRef::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx)?,
             cur_type: ctx.types.type_from_llvm_ref(unsafe { LLVMTypeOf(inst) }),
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
+            dest: Name::name_or_num(unsafe { get_value_name_bytes(inst) }, &mut func_ctx.ctr),
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            debugloc: DebugLoc::from_llvm_with_col(inst, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
-        }
+        })
     }
 }
 
@@ -3224,8 +4271,8 @@ impl LandingPad {
         inst: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
-        Self {
+    ) -> Result<Self, Error> {
+        Ok(Self {
             result_type: ctx.types.type_from_llvm_ref(unsafe { LLVMTypeOf(inst) }),
             clauses: {
                 let num_clauses = unsafe { LLVMGetNumClauses(inst) };
@@ -3233,12 +4280,12 @@ impl LandingPad {
                     .map(|i| LandingPadClause::from_llvm_ref(unsafe { LLVMGetClause(inst, i) }))
                     .collect()
             },
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
+            dest: Name::name_or_num(unsafe { get_value_name_bytes(inst) }, &mut func_ctx.ctr),
             cleanup: unsafe { LLVMIsCleanup(inst) } != 0,
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            debugloc: DebugLoc::from_llvm_with_col(inst, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
-        }
+        })
     }
 }
 
@@ -3247,26 +4294,30 @@ impl CatchPad {
         inst: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
-        Self {
-            catch_switch: Operand::from_llvm_ref(
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            catch_switch: OperandRef::from_llvm_ref(
                 unsafe { LLVMGetParentCatchSwitch(inst) },
                 ctx,
                 func_ctx,
-            ),
+            )?,
             args: {
                 let num_args = unsafe { LLVMGetNumArgOperands(inst) };
                 (0 .. num_args)
                     .map(|i| {
-                        Operand::from_llvm_ref(unsafe { LLVMGetArgOperand(inst, i) }, ctx, func_ctx)
+                        OperandRef::from_llvm_ref(
+                            unsafe { LLVMGetArgOperand(inst, i) },
+                            ctx,
+                            func_ctx,
+                        )
                     })
-                    .collect()
+                    .collect::<Result<_, _>>()?
             },
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
+            dest: Name::name_or_num(unsafe { get_value_name_bytes(inst) }, &mut func_ctx.ctr),
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            debugloc: DebugLoc::from_llvm_with_col(inst, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
-        }
+        })
     }
 }
 
@@ -3275,22 +4326,62 @@ impl CleanupPad {
         inst: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
-        Self {
-            parent_pad: Operand::from_llvm_ref(unsafe { LLVMGetOperand(inst, 0) }, ctx, func_ctx),
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            parent_pad: OperandRef::from_llvm_ref(
+                unsafe { LLVMGetOperand(inst, 0) },
+                ctx,
+                func_ctx,
+            )?,
             args: {
                 let num_args = unsafe { LLVMGetNumArgOperands(inst) };
                 (0 .. num_args)
                     .map(|i| {
-                        Operand::from_llvm_ref(unsafe { LLVMGetArgOperand(inst, i) }, ctx, func_ctx)
+                        OperandRef::from_llvm_ref(
+                            unsafe { LLVMGetArgOperand(inst, i) },
+                            ctx,
+                            func_ctx,
+                        )
                     })
-                    .collect()
+                    .collect::<Result<_, _>>()?
             },
-            dest: Name::name_or_num(unsafe { get_value_name(inst) }, &mut func_ctx.ctr),
+            dest: Name::name_or_num(unsafe { get_value_name_bytes(inst) }, &mut func_ctx.ctr),
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(inst),
+            debugloc: DebugLoc::from_llvm_with_col(inst, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(inst),
-        }
+        })
+    }
+}
+
+impl Other {
+    pub(crate) fn from_llvm_ref(
+        inst: LLVMValueRef,
+        ctx: &mut ModuleContext,
+        func_ctx: &mut FunctionContext,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            opcode: unsafe { LLVMGetInstructionOpcode(inst) } as u32,
+            operands: {
+                let num_operands = unsafe { LLVMGetNumOperands(inst) } as u32;
+                (0 .. num_operands)
+                    .map(|i| unsafe { LLVMGetOperand(inst, i) })
+                    .filter(|&op| unsafe { LLVMValueIsBasicBlock(op) } == 0)
+                    .map(|op| OperandRef::from_llvm_ref(op, ctx, func_ctx))
+                    .collect::<Result<_, _>>()?
+            },
+            dest: if unsafe { LLVMGetTypeKind(LLVMTypeOf(inst)) == LLVMVoidTypeKind } {
+                None
+            } else {
+                Some(Name::name_or_num(
+                    unsafe { get_value_name_bytes(inst) },
+                    &mut func_ctx.ctr,
+                ))
+            },
+            ty: ctx.types.type_from_llvm_ref(unsafe { LLVMTypeOf(inst) }),
+            #[cfg(feature = "llvm-9-or-greater")]
+            debugloc: DebugLoc::from_llvm_with_col(inst, ctx),
+            // metadata: InstructionMetadata::from_llvm_inst(inst),
+        })
     }
 }
 
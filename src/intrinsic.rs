@@ -0,0 +1,1131 @@
+//! Recognizing calls to LLVM intrinsic functions.
+//!
+//! Intrinsics are identified by the name of the called function. Many
+//! intrinsics are overloaded, with the overload encoded in suffixes on the
+//! name (e.g., `llvm.memcpy.p0i8.p0i8.i64`); these suffixes are ignored when
+//! classifying a call.
+
+use crate::constant::Constant;
+use crate::function::{Function, ParameterAttribute};
+use crate::instruction::{Call, InlineAssembly, Instruction, OperandBundle};
+use crate::name::Name;
+use crate::opcode::Opcode;
+use crate::operand::{Operand, OperandRef};
+use crate::terminator::{Invoke, Terminator};
+use crate::types::{Type, TypeRef, Typed, Types};
+use either::Either;
+
+/// An LLVM intrinsic which this crate recognizes.
+/// See [LLVM 14 docs on Intrinsic Functions](https://releases.llvm.org/14.0.0/docs/LangRef.html#intrinsic-functions)
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum Intrinsic {
+    /// `llvm.va_start`: initializes a `va_list` for use by `va_arg`
+    VaStart,
+    /// `llvm.va_end`: destroys a `va_list` initialized by `llvm.va_start` or
+    /// `llvm.va_copy`
+    VaEnd,
+    /// `llvm.va_copy`: copies the position of one `va_list` to another
+    VaCopy,
+    /// `llvm.experimental.stackmap`: records the locations of live values at
+    /// a point in the code. See [`StackMap`](struct.StackMap.html).
+    StackMap,
+    /// `llvm.experimental.patchpoint`: a call site which can be patched at
+    /// runtime, which also records the locations of live values. See
+    /// [`PatchPoint`](struct.PatchPoint.html).
+    PatchPoint,
+    /// `llvm.experimental.gc.statepoint`: a call at which the garbage
+    /// collector may run. See [`Statepoint`](struct.Statepoint.html).
+    GcStatepoint,
+    /// `llvm.experimental.gc.relocate`: the new value of a pointer which was
+    /// live across a statepoint. See [`GcRelocate`](struct.GcRelocate.html).
+    GcRelocate,
+    /// `llvm.experimental.gc.result`: the return value of the call made by a
+    /// statepoint. See [`GcResult`](struct.GcResult.html).
+    GcResult,
+    /// `llvm.coro.id`: identifies a switched-resume coroutine
+    CoroId,
+    /// `llvm.coro.id.retcon`: identifies a returned-continuation coroutine
+    CoroIdRetcon,
+    /// `llvm.coro.id.retcon.once`: identifies a returned-continuation
+    /// coroutine which can only be resumed once
+    CoroIdRetconOnce,
+    /// `llvm.coro.id.async`: identifies an async coroutine
+    CoroIdAsync,
+    /// `llvm.coro.alloc`: whether the coroutine frame needs to be allocated
+    /// dynamically
+    CoroAlloc,
+    /// `llvm.coro.begin`: gives the coroutine handle, given the memory for the
+    /// coroutine frame
+    CoroBegin,
+    /// `llvm.coro.size`: the size of the coroutine frame
+    CoroSize,
+    /// `llvm.coro.free`: the memory to free when destroying the coroutine
+    /// frame
+    CoroFree,
+    /// `llvm.coro.frame`: the address of the coroutine frame
+    CoroFrame,
+    /// `llvm.coro.save`: marks the point where the coroutine is considered
+    /// suspended
+    CoroSave,
+    /// `llvm.coro.suspend`: a suspend point of a switched-resume coroutine
+    CoroSuspend,
+    /// `llvm.coro.suspend.retcon`: a suspend point of a returned-continuation
+    /// coroutine
+    CoroSuspendRetcon,
+    /// `llvm.coro.suspend.async`: a suspend point of an async coroutine
+    CoroSuspendAsync,
+    /// `llvm.coro.end`: marks a point where the coroutine returns to its
+    /// caller
+    CoroEnd,
+    /// `llvm.coro.end.async`: like `llvm.coro.end`, for async coroutines
+    CoroEndAsync,
+    /// `llvm.coro.resume`: resumes a suspended coroutine
+    CoroResume,
+    /// `llvm.coro.destroy`: destroys a suspended coroutine
+    CoroDestroy,
+    /// `llvm.coro.done`: whether a coroutine is suspended at its final suspend
+    /// point
+    CoroDone,
+    /// `llvm.coro.promise`: converts between a coroutine handle and the
+    /// address of its promise
+    CoroPromise,
+    /// `llvm.coro.noop`: the handle of a coroutine which does nothing
+    CoroNoop,
+    /// `llvm.coro.subfn.addr`: the address of a coroutine's resume or destroy
+    /// function
+    CoroSubfnAddr,
+    /// `llvm.sadd.with.overflow`: signed addition, and whether it overflowed.
+    /// See [`CheckedArithmetic`](struct.CheckedArithmetic.html).
+    SAddWithOverflow,
+    /// `llvm.uadd.with.overflow`: unsigned addition, and whether it
+    /// overflowed
+    UAddWithOverflow,
+    /// `llvm.ssub.with.overflow`: signed subtraction, and whether it
+    /// overflowed
+    SSubWithOverflow,
+    /// `llvm.usub.with.overflow`: unsigned subtraction, and whether it
+    /// overflowed
+    USubWithOverflow,
+    /// `llvm.smul.with.overflow`: signed multiplication, and whether it
+    /// overflowed
+    SMulWithOverflow,
+    /// `llvm.umul.with.overflow`: unsigned multiplication, and whether it
+    /// overflowed
+    UMulWithOverflow,
+    /// `llvm.sadd.sat`: signed addition, saturating at the minimum and
+    /// maximum values. See
+    /// [`SaturatingArithmetic`](struct.SaturatingArithmetic.html).
+    SAddSat,
+    /// `llvm.uadd.sat`: unsigned saturating addition
+    UAddSat,
+    /// `llvm.ssub.sat`: signed saturating subtraction
+    SSubSat,
+    /// `llvm.usub.sat`: unsigned saturating subtraction
+    USubSat,
+    /// `llvm.sshl.sat`: signed saturating left shift
+    SShlSat,
+    /// `llvm.ushl.sat`: unsigned saturating left shift
+    UShlSat,
+    /// `llvm.smul.fix`: signed fixed-point multiplication. See
+    /// [`FixedPointArithmetic`](struct.FixedPointArithmetic.html).
+    SMulFix,
+    /// `llvm.umul.fix`: unsigned fixed-point multiplication
+    UMulFix,
+    /// `llvm.smul.fix.sat`: signed saturating fixed-point multiplication
+    SMulFixSat,
+    /// `llvm.umul.fix.sat`: unsigned saturating fixed-point
+    /// multiplication
+    UMulFixSat,
+    /// `llvm.sdiv.fix`: signed fixed-point division
+    SDivFix,
+    /// `llvm.udiv.fix`: unsigned fixed-point division
+    UDivFix,
+    /// `llvm.sdiv.fix.sat`: signed saturating fixed-point division
+    SDivFixSat,
+    /// `llvm.udiv.fix.sat`: unsigned saturating fixed-point division
+    UDivFixSat,
+    /// `llvm.vector.reduce.add`: the sum of the elements of a vector. See
+    /// [`VectorReduction`](struct.VectorReduction.html), which also
+    /// describes the other `llvm.vector.reduce.*` intrinsics.
+    VectorReduceAdd,
+    /// `llvm.vector.reduce.mul`
+    VectorReduceMul,
+    /// `llvm.vector.reduce.and`
+    VectorReduceAnd,
+    /// `llvm.vector.reduce.or`
+    VectorReduceOr,
+    /// `llvm.vector.reduce.xor`
+    VectorReduceXor,
+    /// `llvm.vector.reduce.smax`
+    VectorReduceSMax,
+    /// `llvm.vector.reduce.smin`
+    VectorReduceSMin,
+    /// `llvm.vector.reduce.umax`
+    VectorReduceUMax,
+    /// `llvm.vector.reduce.umin`
+    VectorReduceUMin,
+    /// `llvm.vector.reduce.fadd`
+    VectorReduceFAdd,
+    /// `llvm.vector.reduce.fmul`
+    VectorReduceFMul,
+    /// `llvm.vector.reduce.fmax`
+    VectorReduceFMax,
+    /// `llvm.vector.reduce.fmin`
+    VectorReduceFMin,
+}
+
+/// All of the intrinsics which this crate recognizes
+const ALL_INTRINSICS: &[Intrinsic] = &[
+    Intrinsic::VaStart,
+    Intrinsic::VaEnd,
+    Intrinsic::VaCopy,
+    Intrinsic::StackMap,
+    Intrinsic::PatchPoint,
+    Intrinsic::GcStatepoint,
+    Intrinsic::GcRelocate,
+    Intrinsic::GcResult,
+    Intrinsic::CoroId,
+    Intrinsic::CoroIdRetcon,
+    Intrinsic::CoroIdRetconOnce,
+    Intrinsic::CoroIdAsync,
+    Intrinsic::CoroAlloc,
+    Intrinsic::CoroBegin,
+    Intrinsic::CoroSize,
+    Intrinsic::CoroFree,
+    Intrinsic::CoroFrame,
+    Intrinsic::CoroSave,
+    Intrinsic::CoroSuspend,
+    Intrinsic::CoroSuspendRetcon,
+    Intrinsic::CoroSuspendAsync,
+    Intrinsic::CoroEnd,
+    Intrinsic::CoroEndAsync,
+    Intrinsic::CoroResume,
+    Intrinsic::CoroDestroy,
+    Intrinsic::CoroDone,
+    Intrinsic::CoroPromise,
+    Intrinsic::CoroNoop,
+    Intrinsic::CoroSubfnAddr,
+    Intrinsic::SAddWithOverflow,
+    Intrinsic::UAddWithOverflow,
+    Intrinsic::SSubWithOverflow,
+    Intrinsic::USubWithOverflow,
+    Intrinsic::SMulWithOverflow,
+    Intrinsic::UMulWithOverflow,
+    Intrinsic::SAddSat,
+    Intrinsic::UAddSat,
+    Intrinsic::SSubSat,
+    Intrinsic::USubSat,
+    Intrinsic::SShlSat,
+    Intrinsic::UShlSat,
+    Intrinsic::SMulFix,
+    Intrinsic::UMulFix,
+    Intrinsic::SMulFixSat,
+    Intrinsic::UMulFixSat,
+    Intrinsic::SDivFix,
+    Intrinsic::UDivFix,
+    Intrinsic::SDivFixSat,
+    Intrinsic::UDivFixSat,
+    Intrinsic::VectorReduceAdd,
+    Intrinsic::VectorReduceMul,
+    Intrinsic::VectorReduceAnd,
+    Intrinsic::VectorReduceOr,
+    Intrinsic::VectorReduceXor,
+    Intrinsic::VectorReduceSMax,
+    Intrinsic::VectorReduceSMin,
+    Intrinsic::VectorReduceUMax,
+    Intrinsic::VectorReduceUMin,
+    Intrinsic::VectorReduceFAdd,
+    Intrinsic::VectorReduceFMul,
+    Intrinsic::VectorReduceFMax,
+    Intrinsic::VectorReduceFMin,
+];
+
+impl Intrinsic {
+    /// Recognize the intrinsic with the given name, ignoring any overload
+    /// suffixes. Returns `None` if the name isn't that of an intrinsic this
+    /// crate recognizes.
+    ///
+    /// The names which the vector reduction intrinsics had before LLVM 12
+    /// (`llvm.experimental.vector.reduce.*`) are recognized as well.
+    pub fn from_name(name: &str) -> Option<Self> {
+        // before LLVM 12, the vector reduction intrinsics were experimental,
+        // and (in LLVM 9 through 11) `fadd` and `fmul` had a `v2` version
+        let renamed;
+        let name = match name.strip_prefix("llvm.experimental.vector.reduce.") {
+            Some(rest) => {
+                renamed = format!(
+                    "llvm.vector.reduce.{}",
+                    rest.strip_prefix("v2.").unwrap_or(rest)
+                );
+                &renamed
+            },
+            None => name,
+        };
+        // some base names are prefixes of others (e.g., `llvm.coro.id` and
+        // `llvm.coro.id.async`), so take the longest one which matches
+        ALL_INTRINSICS
+            .iter()
+            .copied()
+            .filter(|intrinsic| has_base_name(name, intrinsic.base_name()))
+            .max_by_key(|intrinsic| intrinsic.base_name().len())
+    }
+
+    /// The name of the intrinsic, without any overload suffixes
+    pub fn base_name(self) -> &'static str {
+        match self {
+            Intrinsic::VaStart => "llvm.va_start",
+            Intrinsic::VaEnd => "llvm.va_end",
+            Intrinsic::VaCopy => "llvm.va_copy",
+            Intrinsic::StackMap => "llvm.experimental.stackmap",
+            Intrinsic::PatchPoint => "llvm.experimental.patchpoint",
+            Intrinsic::GcStatepoint => "llvm.experimental.gc.statepoint",
+            Intrinsic::GcRelocate => "llvm.experimental.gc.relocate",
+            Intrinsic::GcResult => "llvm.experimental.gc.result",
+            Intrinsic::CoroId => "llvm.coro.id",
+            Intrinsic::CoroIdRetcon => "llvm.coro.id.retcon",
+            Intrinsic::CoroIdRetconOnce => "llvm.coro.id.retcon.once",
+            Intrinsic::CoroIdAsync => "llvm.coro.id.async",
+            Intrinsic::CoroAlloc => "llvm.coro.alloc",
+            Intrinsic::CoroBegin => "llvm.coro.begin",
+            Intrinsic::CoroSize => "llvm.coro.size",
+            Intrinsic::CoroFree => "llvm.coro.free",
+            Intrinsic::CoroFrame => "llvm.coro.frame",
+            Intrinsic::CoroSave => "llvm.coro.save",
+            Intrinsic::CoroSuspend => "llvm.coro.suspend",
+            Intrinsic::CoroSuspendRetcon => "llvm.coro.suspend.retcon",
+            Intrinsic::CoroSuspendAsync => "llvm.coro.suspend.async",
+            Intrinsic::CoroEnd => "llvm.coro.end",
+            Intrinsic::CoroEndAsync => "llvm.coro.end.async",
+            Intrinsic::CoroResume => "llvm.coro.resume",
+            Intrinsic::CoroDestroy => "llvm.coro.destroy",
+            Intrinsic::CoroDone => "llvm.coro.done",
+            Intrinsic::CoroPromise => "llvm.coro.promise",
+            Intrinsic::CoroNoop => "llvm.coro.noop",
+            Intrinsic::CoroSubfnAddr => "llvm.coro.subfn.addr",
+            Intrinsic::SAddWithOverflow => "llvm.sadd.with.overflow",
+            Intrinsic::UAddWithOverflow => "llvm.uadd.with.overflow",
+            Intrinsic::SSubWithOverflow => "llvm.ssub.with.overflow",
+            Intrinsic::USubWithOverflow => "llvm.usub.with.overflow",
+            Intrinsic::SMulWithOverflow => "llvm.smul.with.overflow",
+            Intrinsic::UMulWithOverflow => "llvm.umul.with.overflow",
+            Intrinsic::SAddSat => "llvm.sadd.sat",
+            Intrinsic::UAddSat => "llvm.uadd.sat",
+            Intrinsic::SSubSat => "llvm.ssub.sat",
+            Intrinsic::USubSat => "llvm.usub.sat",
+            Intrinsic::SShlSat => "llvm.sshl.sat",
+            Intrinsic::UShlSat => "llvm.ushl.sat",
+            Intrinsic::SMulFix => "llvm.smul.fix",
+            Intrinsic::UMulFix => "llvm.umul.fix",
+            Intrinsic::SMulFixSat => "llvm.smul.fix.sat",
+            Intrinsic::UMulFixSat => "llvm.umul.fix.sat",
+            Intrinsic::SDivFix => "llvm.sdiv.fix",
+            Intrinsic::UDivFix => "llvm.udiv.fix",
+            Intrinsic::SDivFixSat => "llvm.sdiv.fix.sat",
+            Intrinsic::UDivFixSat => "llvm.udiv.fix.sat",
+            Intrinsic::VectorReduceAdd => "llvm.vector.reduce.add",
+            Intrinsic::VectorReduceMul => "llvm.vector.reduce.mul",
+            Intrinsic::VectorReduceAnd => "llvm.vector.reduce.and",
+            Intrinsic::VectorReduceOr => "llvm.vector.reduce.or",
+            Intrinsic::VectorReduceXor => "llvm.vector.reduce.xor",
+            Intrinsic::VectorReduceSMax => "llvm.vector.reduce.smax",
+            Intrinsic::VectorReduceSMin => "llvm.vector.reduce.smin",
+            Intrinsic::VectorReduceUMax => "llvm.vector.reduce.umax",
+            Intrinsic::VectorReduceUMin => "llvm.vector.reduce.umin",
+            Intrinsic::VectorReduceFAdd => "llvm.vector.reduce.fadd",
+            Intrinsic::VectorReduceFMul => "llvm.vector.reduce.fmul",
+            Intrinsic::VectorReduceFMax => "llvm.vector.reduce.fmax",
+            Intrinsic::VectorReduceFMin => "llvm.vector.reduce.fmin",
+        }
+    }
+
+    /// Is this one of the intrinsics for handling variadic arguments
+    /// (`llvm.va_start`, `llvm.va_end`, and `llvm.va_copy`)?
+    pub fn is_vararg(self) -> bool {
+        matches!(
+            self,
+            Intrinsic::VaStart | Intrinsic::VaEnd | Intrinsic::VaCopy
+        )
+    }
+
+    /// Is this one of the coroutine intrinsics (`llvm.coro.*`)?
+    pub fn is_coroutine(self) -> bool {
+        self.base_name().starts_with("llvm.coro.")
+    }
+
+    /// Is this one of the intrinsics identifying a coroutine
+    /// (`llvm.coro.id` and its variants for other lowering strategies)?
+    pub fn is_coro_id(self) -> bool {
+        matches!(
+            self,
+            Intrinsic::CoroId
+                | Intrinsic::CoroIdRetcon
+                | Intrinsic::CoroIdRetconOnce
+                | Intrinsic::CoroIdAsync
+        )
+    }
+
+    /// Is this one of the intrinsics marking a coroutine's suspend points
+    /// (`llvm.coro.suspend` and its variants for other lowering strategies)?
+    pub fn is_coro_suspend(self) -> bool {
+        matches!(
+            self,
+            Intrinsic::CoroSuspend | Intrinsic::CoroSuspendRetcon | Intrinsic::CoroSuspendAsync
+        )
+    }
+
+    /// Is this one of the arithmetic-with-overflow intrinsics (e.g.,
+    /// `llvm.sadd.with.overflow`)?
+    pub fn is_arithmetic_with_overflow(self) -> bool {
+        self.checked_arithmetic_op().is_some()
+    }
+
+    /// Is this one of the saturating arithmetic intrinsics (e.g.,
+    /// `llvm.sadd.sat`)? This doesn't include the saturating fixed-point
+    /// intrinsics.
+    pub fn is_saturating_arithmetic(self) -> bool {
+        self.saturating_op().is_some()
+    }
+
+    /// Is this one of the fixed-point arithmetic intrinsics (e.g.,
+    /// `llvm.smul.fix` or `llvm.sdiv.fix.sat`)?
+    pub fn is_fixed_point_arithmetic(self) -> bool {
+        self.fixed_point_op().is_some()
+    }
+
+    /// Is this one of the vector reduction intrinsics (`llvm.vector.reduce.*`)?
+    pub fn is_vector_reduction(self) -> bool {
+        self.reduction_kind().is_some()
+    }
+
+    /// For an arithmetic-with-overflow intrinsic, the operation it performs
+    fn checked_arithmetic_op(self) -> Option<(Opcode, Signedness)> {
+        match self {
+            Intrinsic::SAddWithOverflow => Some((Opcode::Add, Signedness::Signed)),
+            Intrinsic::UAddWithOverflow => Some((Opcode::Add, Signedness::Unsigned)),
+            Intrinsic::SSubWithOverflow => Some((Opcode::Sub, Signedness::Signed)),
+            Intrinsic::USubWithOverflow => Some((Opcode::Sub, Signedness::Unsigned)),
+            Intrinsic::SMulWithOverflow => Some((Opcode::Mul, Signedness::Signed)),
+            Intrinsic::UMulWithOverflow => Some((Opcode::Mul, Signedness::Unsigned)),
+            _ => None,
+        }
+    }
+
+    /// For a saturating arithmetic intrinsic, the operation it performs
+    fn saturating_op(self) -> Option<(Opcode, Signedness)> {
+        match self {
+            Intrinsic::SAddSat => Some((Opcode::Add, Signedness::Signed)),
+            Intrinsic::UAddSat => Some((Opcode::Add, Signedness::Unsigned)),
+            Intrinsic::SSubSat => Some((Opcode::Sub, Signedness::Signed)),
+            Intrinsic::USubSat => Some((Opcode::Sub, Signedness::Unsigned)),
+            Intrinsic::SShlSat => Some((Opcode::Shl, Signedness::Signed)),
+            Intrinsic::UShlSat => Some((Opcode::Shl, Signedness::Unsigned)),
+            _ => None,
+        }
+    }
+
+    /// For a fixed-point arithmetic intrinsic, the operation it performs, and
+    /// whether it saturates
+    fn fixed_point_op(self) -> Option<(Opcode, Signedness, bool)> {
+        match self {
+            Intrinsic::SMulFix => Some((Opcode::Mul, Signedness::Signed, false)),
+            Intrinsic::UMulFix => Some((Opcode::Mul, Signedness::Unsigned, false)),
+            Intrinsic::SMulFixSat => Some((Opcode::Mul, Signedness::Signed, true)),
+            Intrinsic::UMulFixSat => Some((Opcode::Mul, Signedness::Unsigned, true)),
+            Intrinsic::SDivFix => Some((Opcode::SDiv, Signedness::Signed, false)),
+            Intrinsic::UDivFix => Some((Opcode::UDiv, Signedness::Unsigned, false)),
+            Intrinsic::SDivFixSat => Some((Opcode::SDiv, Signedness::Signed, true)),
+            Intrinsic::UDivFixSat => Some((Opcode::UDiv, Signedness::Unsigned, true)),
+            _ => None,
+        }
+    }
+
+    /// For a vector reduction intrinsic, how it combines the elements
+    fn reduction_kind(self) -> Option<ReductionKind> {
+        match self {
+            Intrinsic::VectorReduceAdd => Some(ReductionKind::Add),
+            Intrinsic::VectorReduceMul => Some(ReductionKind::Mul),
+            Intrinsic::VectorReduceAnd => Some(ReductionKind::And),
+            Intrinsic::VectorReduceOr => Some(ReductionKind::Or),
+            Intrinsic::VectorReduceXor => Some(ReductionKind::Xor),
+            Intrinsic::VectorReduceSMax => Some(ReductionKind::SMax),
+            Intrinsic::VectorReduceSMin => Some(ReductionKind::SMin),
+            Intrinsic::VectorReduceUMax => Some(ReductionKind::UMax),
+            Intrinsic::VectorReduceUMin => Some(ReductionKind::UMin),
+            Intrinsic::VectorReduceFAdd => Some(ReductionKind::FAdd),
+            Intrinsic::VectorReduceFMul => Some(ReductionKind::FMul),
+            Intrinsic::VectorReduceFMax => Some(ReductionKind::FMax),
+            Intrinsic::VectorReduceFMin => Some(ReductionKind::FMin),
+            _ => None,
+        }
+    }
+}
+
+/// The arguments of a call to `llvm.experimental.stackmap`:
+///
+/// `call void @llvm.experimental.stackmap(i64 <id>, i32 <numShadowBytes>, ...)`
+///
+/// See [LLVM 14 docs on Stack Maps](https://releases.llvm.org/14.0.0/docs/StackMaps.html#stackmap-intrinsic)
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct StackMap<'a> {
+    /// The ID of the stack map, which identifies its record in the
+    /// `__llvm_stackmaps` section
+    pub id: u64,
+    /// The number of bytes of nops the code generator reserves after the
+    /// stack map's location, so that the code there can be patched
+    pub num_shadow_bytes: u32,
+    /// The values whose locations are recorded in the stack map
+    pub live_operands: Vec<&'a Operand>,
+}
+
+/// The arguments of a call to `llvm.experimental.patchpoint`:
+///
+/// `call <ty> @llvm.experimental.patchpoint.<ty>(i64 <id>, i32 <numBytes>,
+/// i8* <target>, i32 <numArgs>, ...)`
+///
+/// where the first `numArgs` of the variadic arguments are the arguments of
+/// the call to `target`, and the rest are live values.
+///
+/// See [LLVM 14 docs on Stack Maps](https://releases.llvm.org/14.0.0/docs/StackMaps.html#patchpoint-intrinsic)
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct PatchPoint<'a> {
+    /// The ID of the patch point, which identifies its record in the
+    /// `__llvm_stackmaps` section
+    pub id: u64,
+    /// The number of bytes reserved for the patchable code, including the
+    /// call to `target`
+    pub num_bytes: u32,
+    /// The function called by the patch point; a null pointer if no call is
+    /// emitted
+    pub target: &'a Operand,
+    /// The arguments passed to `target`
+    pub call_args: Vec<&'a Operand>,
+    /// The values whose locations are recorded for the patch point
+    pub live_operands: Vec<&'a Operand>,
+}
+
+impl<'a> StackMap<'a> {
+    fn from_call(
+        function: &Either<InlineAssembly, OperandRef>,
+        arguments: &'a [(OperandRef, Vec<ParameterAttribute>)],
+    ) -> Option<Self> {
+        if Intrinsic::from_name(callee_name(function)?) != Some(Intrinsic::StackMap) {
+            return None;
+        }
+        match arguments {
+            [(id, _), (num_shadow_bytes, _), live_operands @ ..] => Some(Self {
+                id: constant_int(id)?,
+                num_shadow_bytes: constant_int(num_shadow_bytes)? as u32,
+                live_operands: live_operands.iter().map(|(op, _)| op.as_ref()).collect(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> PatchPoint<'a> {
+    fn from_call(
+        function: &Either<InlineAssembly, OperandRef>,
+        arguments: &'a [(OperandRef, Vec<ParameterAttribute>)],
+    ) -> Option<Self> {
+        if Intrinsic::from_name(callee_name(function)?) != Some(Intrinsic::PatchPoint) {
+            return None;
+        }
+        match arguments {
+            [(id, _), (num_bytes, _), (target, _), (num_args, _), rest @ ..] => {
+                let num_args = constant_int(num_args)? as usize;
+                if num_args > rest.len() {
+                    return None;
+                }
+                let (call_args, live_operands) = rest.split_at(num_args);
+                Some(Self {
+                    id: constant_int(id)?,
+                    num_bytes: constant_int(num_bytes)? as u32,
+                    target: target.as_ref(),
+                    call_args: call_args.iter().map(|(op, _)| op.as_ref()).collect(),
+                    live_operands: live_operands.iter().map(|(op, _)| op.as_ref()).collect(),
+                })
+            },
+            _ => None,
+        }
+    }
+}
+
+/// The arguments of a call to `llvm.experimental.gc.statepoint`:
+///
+/// `call token @llvm.experimental.gc.statepoint(i64 <id>, i32 <numPatchBytes>,
+/// <target>, i64 <numCallArgs>, i32 <flags>, ..., i32 0, i32 0)
+/// ["gc-live"(...), "deopt"(...), "gc-transition"(...)]`
+///
+/// where the variadic arguments start with the `numCallArgs` arguments of the
+/// call to `target`. The live GC pointers, deoptimization state, and
+/// transition arguments are passed in operand bundles.
+///
+/// See [LLVM 14 docs on Statepoints](https://releases.llvm.org/14.0.0/docs/Statepoints.html#gc-statepoint)
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Statepoint<'a> {
+    /// The ID of the statepoint, which identifies its record in the
+    /// `__llvm_stackmaps` section
+    pub id: u64,
+    /// The number of bytes of nops to emit instead of the call to `target`,
+    /// or 0 to emit the call
+    pub num_patch_bytes: u32,
+    /// The function called by the statepoint
+    pub target: &'a Operand,
+    /// The arguments passed to `target`
+    pub call_args: Vec<&'a Operand>,
+    /// The statepoint's flags, e.g., 1 for a GC transition
+    pub flags: u64,
+    /// The GC pointers which are live across the statepoint, from the
+    /// `"gc-live"` operand bundle. `gc.relocate`s refer to these by index.
+    pub gc_live: Vec<&'a Operand>,
+    /// The deoptimization state, from the `"deopt"` operand bundle
+    pub deopt_args: Vec<&'a Operand>,
+    /// The arguments to the GC transition code, from the `"gc-transition"`
+    /// operand bundle
+    pub gc_transition_args: Vec<&'a Operand>,
+}
+
+/// The arguments of a call to `llvm.experimental.gc.relocate`:
+///
+/// `call <ty> @llvm.experimental.gc.relocate(token %statepoint, i32 <baseIndex>,
+/// i32 <derivedIndex>)`
+///
+/// Use [`find_statepoint()`](#method.find_statepoint) to get the statepoint
+/// which the relocation refers to, and [`base()`](#method.base) and
+/// [`derived()`](#method.derived) to get the relocated pointers.
+///
+/// See [LLVM 14 docs on Statepoints](https://releases.llvm.org/14.0.0/docs/Statepoints.html#gc-relocate)
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct GcRelocate<'a> {
+    /// The token identifying the statepoint: either the statepoint's result,
+    /// or, for relocations on the exceptional path of an invoked statepoint,
+    /// the result of the landing pad
+    pub statepoint_token: &'a Operand,
+    /// The index in the statepoint's `gc_live` of the base pointer of the
+    /// object which the relocated pointer points into
+    pub base_index: u32,
+    /// The index in the statepoint's `gc_live` of the relocated pointer
+    pub derived_index: u32,
+}
+
+/// The argument of a call to `llvm.experimental.gc.result`:
+///
+/// `call <ty> @llvm.experimental.gc.result(token %statepoint)`
+///
+/// See [LLVM 14 docs on Statepoints](https://releases.llvm.org/14.0.0/docs/Statepoints.html#gc-result)
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct GcResult<'a> {
+    /// The token identifying the statepoint, which is the statepoint's result
+    pub statepoint_token: &'a Operand,
+}
+
+impl<'a> Statepoint<'a> {
+    fn from_call(
+        function: &Either<InlineAssembly, OperandRef>,
+        arguments: &'a [(OperandRef, Vec<ParameterAttribute>)],
+        operand_bundles: &'a [OperandBundle],
+    ) -> Option<Self> {
+        if Intrinsic::from_name(callee_name(function)?) != Some(Intrinsic::GcStatepoint) {
+            return None;
+        }
+        if arguments.len() < 5 {
+            return None;
+        }
+        let (fixed, rest) = arguments.split_at(5);
+        let num_call_args = constant_int(&fixed[3].0)? as usize;
+        let bundle = |tag: &str| -> Vec<&'a Operand> {
+            operand_bundles
+                .iter()
+                .filter(|bundle| bundle.tag == tag)
+                .flat_map(|bundle| bundle.args.iter().map(AsRef::as_ref))
+                .collect()
+        };
+        Some(Self {
+            id: constant_int(&fixed[0].0)?,
+            num_patch_bytes: constant_int(&fixed[1].0)? as u32,
+            target: fixed[2].0.as_ref(),
+            call_args: rest
+                .get(.. num_call_args)?
+                .iter()
+                .map(|(op, _)| op.as_ref())
+                .collect(),
+            flags: constant_int(&fixed[4].0)?,
+            gc_live: bundle("gc-live"),
+            deopt_args: bundle("deopt"),
+            gc_transition_args: bundle("gc-transition"),
+        })
+    }
+}
+
+impl<'a> GcRelocate<'a> {
+    fn from_call(
+        function: &Either<InlineAssembly, OperandRef>,
+        arguments: &'a [(OperandRef, Vec<ParameterAttribute>)],
+    ) -> Option<Self> {
+        if Intrinsic::from_name(callee_name(function)?) != Some(Intrinsic::GcRelocate) {
+            return None;
+        }
+        match arguments {
+            [(token, _), (base_index, _), (derived_index, _)] => Some(Self {
+                statepoint_token: token.as_ref(),
+                base_index: constant_int(base_index)? as u32,
+                derived_index: constant_int(derived_index)? as u32,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Find the statepoint which this relocation refers to, in the given
+    /// `Function` (which should be the one containing the `gc.relocate`).
+    pub fn find_statepoint<'f>(&self, function: &'f Function) -> Option<Statepoint<'f>> {
+        find_statepoint(function, self.statepoint_token)
+    }
+
+    /// Get the base pointer of the object which the relocated pointer points
+    /// into, given the statepoint which this relocation refers to
+    pub fn base<'s>(&self, statepoint: &Statepoint<'s>) -> Option<&'s Operand> {
+        statepoint.gc_live.get(self.base_index as usize).copied()
+    }
+
+    /// Get the pointer which is relocated, given the statepoint which this
+    /// relocation refers to
+    pub fn derived<'s>(&self, statepoint: &Statepoint<'s>) -> Option<&'s Operand> {
+        statepoint.gc_live.get(self.derived_index as usize).copied()
+    }
+}
+
+impl<'a> GcResult<'a> {
+    fn from_call(
+        function: &Either<InlineAssembly, OperandRef>,
+        arguments: &'a [(OperandRef, Vec<ParameterAttribute>)],
+    ) -> Option<Self> {
+        if Intrinsic::from_name(callee_name(function)?) != Some(Intrinsic::GcResult) {
+            return None;
+        }
+        match arguments {
+            [(token, _)] => Some(Self {
+                statepoint_token: token.as_ref(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Find the statepoint whose result this is, in the given `Function`
+    /// (which should be the one containing the `gc.result`).
+    pub fn find_statepoint<'f>(&self, function: &'f Function) -> Option<Statepoint<'f>> {
+        find_statepoint(function, self.statepoint_token)
+    }
+}
+
+/// Whether an operation treats its integer operands as signed or unsigned
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum Signedness {
+    Signed,
+    Unsigned,
+}
+
+/// A call to one of the arithmetic-with-overflow intrinsics:
+///
+/// `%dest = call {<ty>, i1} @llvm.<op>.with.overflow.<ty>(<ty> %a, <ty> %b)`
+///
+/// where `<op>` is `sadd`, `uadd`, `ssub`, `usub`, `smul`, or `umul`. The
+/// result is a struct of the result of the operation, wrapped around as by
+/// the corresponding binary instruction, and whether the operation
+/// overflowed. For vector operands, the overflow bit is a vector of `i1`.
+/// The [`Typed`](../types/trait.Typed.html) implementation gives the type of
+/// that struct.
+///
+/// See [LLVM 14 docs on Arithmetic with Overflow Intrinsics](https://releases.llvm.org/14.0.0/docs/LangRef.html#arithmetic-with-overflow-intrinsics)
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct CheckedArithmetic<'a> {
+    /// The operation performed: `Opcode::Add`, `Opcode::Sub`, or
+    /// `Opcode::Mul`
+    pub op: Opcode,
+    /// Whether overflow is checked for signed or unsigned operands
+    pub signedness: Signedness,
+    pub operands: (&'a Operand, &'a Operand),
+    /// The name of the call's result, if it has one
+    pub dest: Option<&'a Name>,
+}
+
+impl<'a> CheckedArithmetic<'a> {
+    fn from_call(
+        function: &Either<InlineAssembly, OperandRef>,
+        arguments: &'a [(OperandRef, Vec<ParameterAttribute>)],
+        dest: Option<&'a Name>,
+    ) -> Option<Self> {
+        let (op, signedness) =
+            Intrinsic::from_name(callee_name(function)?)?.checked_arithmetic_op()?;
+        match arguments {
+            [(operand0, _), (operand1, _)] => Some(Self {
+                op,
+                signedness,
+                operands: (operand0.as_ref(), operand1.as_ref()),
+                dest,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> Typed for CheckedArithmetic<'a> {
+    fn get_type(&self, types: &Types) -> TypeRef {
+        let ty = types.type_of(self.operands.0);
+        let overflow_ty = match ty.as_ref() {
+            #[cfg(feature = "llvm-11-or-greater")]
+            Type::VectorType {
+                num_elements,
+                scalable,
+                ..
+            } => types.vector_of(types.bool(), *num_elements, *scalable),
+            #[cfg(feature = "llvm-10-or-lower")]
+            Type::VectorType { num_elements, .. } => types.vector_of(types.bool(), *num_elements),
+            _ => types.bool(),
+        };
+        types.struct_of(vec![ty, overflow_ty], false)
+    }
+}
+
+/// A call to one of the saturating arithmetic intrinsics:
+///
+/// `%dest = call <ty> @llvm.<op>.sat.<ty>(<ty> %a, <ty> %b)`
+///
+/// where `<op>` is `sadd`, `uadd`, `ssub`, `usub`, `sshl`, or `ushl`. The
+/// result is that of the corresponding binary instruction, except that on
+/// overflow it is clamped to the minimum or maximum value of the type.
+///
+/// See [LLVM 14 docs on Saturation Arithmetic Intrinsics](https://releases.llvm.org/14.0.0/docs/LangRef.html#saturation-arithmetic-intrinsics)
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct SaturatingArithmetic<'a> {
+    /// The operation performed: `Opcode::Add`, `Opcode::Sub`, or
+    /// `Opcode::Shl`
+    pub op: Opcode,
+    /// Whether the operands (and result) are signed or unsigned
+    pub signedness: Signedness,
+    pub operands: (&'a Operand, &'a Operand),
+}
+
+/// A call to one of the fixed-point arithmetic intrinsics:
+///
+/// `%dest = call <ty> @llvm.<op>.fix[.sat].<ty>(<ty> %a, <ty> %b, i32 <scale>)`
+///
+/// where `<op>` is `smul`, `umul`, `sdiv`, or `udiv`. The operands and result
+/// are fixed-point numbers with `scale` fractional bits. With the `.sat`
+/// suffix, the result saturates at the minimum and maximum values of the type
+/// instead of wrapping around.
+///
+/// See [LLVM 14 docs on Fixed Point Arithmetic Intrinsics](https://releases.llvm.org/14.0.0/docs/LangRef.html#fixed-point-arithmetic-intrinsics)
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct FixedPointArithmetic<'a> {
+    /// The operation performed: `Opcode::Mul`, or `Opcode::SDiv` or
+    /// `Opcode::UDiv` according to the `signedness`
+    pub op: Opcode,
+    /// Whether the operands (and result) are signed or unsigned
+    pub signedness: Signedness,
+    /// Whether the result saturates rather than wrapping around
+    pub saturating: bool,
+    pub operands: (&'a Operand, &'a Operand),
+    /// The number of fractional bits
+    pub scale: u32,
+}
+
+impl<'a> SaturatingArithmetic<'a> {
+    fn from_call(
+        function: &Either<InlineAssembly, OperandRef>,
+        arguments: &'a [(OperandRef, Vec<ParameterAttribute>)],
+    ) -> Option<Self> {
+        let (op, signedness) = Intrinsic::from_name(callee_name(function)?)?.saturating_op()?;
+        match arguments {
+            [(operand0, _), (operand1, _)] => Some(Self {
+                op,
+                signedness,
+                operands: (operand0.as_ref(), operand1.as_ref()),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> FixedPointArithmetic<'a> {
+    fn from_call(
+        function: &Either<InlineAssembly, OperandRef>,
+        arguments: &'a [(OperandRef, Vec<ParameterAttribute>)],
+    ) -> Option<Self> {
+        let (op, signedness, saturating) =
+            Intrinsic::from_name(callee_name(function)?)?.fixed_point_op()?;
+        match arguments {
+            [(operand0, _), (operand1, _), (scale, _)] => Some(Self {
+                op,
+                signedness,
+                saturating,
+                operands: (operand0.as_ref(), operand1.as_ref()),
+                scale: constant_int(scale)? as u32,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// How a vector reduction combines the elements of its vector
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum ReductionKind {
+    /// Integer addition
+    Add,
+    /// Integer multiplication
+    Mul,
+    /// Bitwise and
+    And,
+    /// Bitwise or
+    Or,
+    /// Bitwise xor
+    Xor,
+    /// Signed integer maximum
+    SMax,
+    /// Signed integer minimum
+    SMin,
+    /// Unsigned integer maximum
+    UMax,
+    /// Unsigned integer minimum
+    UMin,
+    /// Floating-point addition
+    FAdd,
+    /// Floating-point multiplication
+    FMul,
+    /// Floating-point maximum
+    FMax,
+    /// Floating-point minimum
+    FMin,
+}
+
+impl ReductionKind {
+    /// The binary instruction which combines two elements, if there is one
+    /// (there isn't for the minimum and maximum reductions)
+    pub fn opcode(self) -> Option<Opcode> {
+        match self {
+            ReductionKind::Add => Some(Opcode::Add),
+            ReductionKind::Mul => Some(Opcode::Mul),
+            ReductionKind::And => Some(Opcode::And),
+            ReductionKind::Or => Some(Opcode::Or),
+            ReductionKind::Xor => Some(Opcode::Xor),
+            ReductionKind::FAdd => Some(Opcode::FAdd),
+            ReductionKind::FMul => Some(Opcode::FMul),
+            _ => None,
+        }
+    }
+
+    /// Does this reduce a vector of floating-point values?
+    pub fn is_floating_point(self) -> bool {
+        matches!(
+            self,
+            ReductionKind::FAdd | ReductionKind::FMul | ReductionKind::FMax | ReductionKind::FMin
+        )
+    }
+}
+
+/// A call to one of the vector reduction intrinsics:
+///
+/// `%dest = call <elty> @llvm.vector.reduce.<kind>.<vecty>(<vecty> %vector)`
+///
+/// or, for `fadd` and `fmul`, which also take a start value:
+///
+/// `%dest = call <elty> @llvm.vector.reduce.<kind>.<vecty>(<elty> %acc, <vecty> %vector)`
+///
+/// The result is the elements of the vector (and the start value, if any)
+/// combined with the given operation. Without the `reassoc` fast-math flag,
+/// `fadd` and `fmul` reductions combine the elements in order; this crate
+/// doesn't record the fast-math flags of calls.
+///
+/// See [LLVM 14 docs on Vector Reduction Intrinsics](https://releases.llvm.org/14.0.0/docs/LangRef.html#vector-reduction-intrinsics)
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct VectorReduction<'a> {
+    /// How the elements are combined
+    pub kind: ReductionKind,
+    /// The vector whose elements are combined
+    pub vector: &'a Operand,
+    /// For `ReductionKind::FAdd` and `ReductionKind::FMul`, the start value
+    /// which the elements are combined with
+    pub accumulator: Option<&'a Operand>,
+}
+
+impl<'a> VectorReduction<'a> {
+    fn from_call(
+        function: &Either<InlineAssembly, OperandRef>,
+        arguments: &'a [(OperandRef, Vec<ParameterAttribute>)],
+    ) -> Option<Self> {
+        let kind = Intrinsic::from_name(callee_name(function)?)?.reduction_kind()?;
+        let (accumulator, vector) = match (kind, arguments) {
+            (ReductionKind::FAdd | ReductionKind::FMul, [(acc, _), (vector, _)]) => {
+                (Some(acc.as_ref()), vector)
+            },
+            (ReductionKind::FAdd | ReductionKind::FMul, _) => return None,
+            (_, [(vector, _)]) => (None, vector),
+            _ => return None,
+        };
+        Some(Self {
+            kind,
+            vector: vector.as_ref(),
+            accumulator,
+        })
+    }
+}
+
+/// Find the statepoint identified by the given token in the given `Function`:
+/// the `Call` or `Invoke` producing the token, or, if the token is produced by
+/// a `LandingPad`, the `Invoke` unwinding to that landing pad
+fn find_statepoint<'f>(function: &'f Function, token: &Operand) -> Option<Statepoint<'f>> {
+    let name = match token {
+        Operand::LocalOperand { name, .. } => name,
+        _ => return None,
+    };
+    for bb in &function.basic_blocks {
+        for inst in &bb.instrs {
+            match inst {
+                Instruction::Call(call) if call.dest.as_ref() == Some(name) => {
+                    return call.as_statepoint();
+                },
+                Instruction::LandingPad(lp) if &lp.dest == name => {
+                    return function
+                        .basic_blocks
+                        .iter()
+                        .find_map(|pred| match &pred.term {
+                            Terminator::Invoke(invoke) if invoke.exception_label == bb.name => {
+                                invoke.as_statepoint()
+                            },
+                            _ => None,
+                        });
+                },
+                _ => {},
+            }
+        }
+        if let Terminator::Invoke(invoke) = &bb.term {
+            if &invoke.result == name {
+                return invoke.as_statepoint();
+            }
+        }
+    }
+    None
+}
+
+impl Call {
+    /// If this is a call to an intrinsic which this crate recognizes, get
+    /// which intrinsic.
+    pub fn intrinsic(&self) -> Option<Intrinsic> {
+        Intrinsic::from_name(callee_name(&self.function)?)
+    }
+
+    /// If this is a call to `llvm.experimental.stackmap`, get its arguments.
+    /// Returns `None` for other calls, or if the ID or number of shadow bytes
+    /// isn't a constant integer.
+    pub fn as_stackmap(&self) -> Option<StackMap<'_>> {
+        StackMap::from_call(&self.function, &self.arguments)
+    }
+
+    /// If this is a call to `llvm.experimental.patchpoint`, get its
+    /// arguments. Returns `None` for other calls, or if the ID, number of
+    /// bytes, or number of call arguments isn't a constant integer.
+    pub fn as_patchpoint(&self) -> Option<PatchPoint<'_>> {
+        PatchPoint::from_call(&self.function, &self.arguments)
+    }
+
+    /// If this is a call to `llvm.experimental.gc.statepoint`, get its
+    /// arguments. Returns `None` for other calls, or if the ID, number of
+    /// patch bytes, number of call arguments, or flags isn't a constant
+    /// integer.
+    pub fn as_statepoint(&self) -> Option<Statepoint<'_>> {
+        Statepoint::from_call(&self.function, &self.arguments, &self.operand_bundles)
+    }
+
+    /// If this is a call to `llvm.experimental.gc.relocate`, get its
+    /// arguments. Returns `None` for other calls, or if the indices aren't
+    /// constant integers.
+    pub fn as_gc_relocate(&self) -> Option<GcRelocate<'_>> {
+        GcRelocate::from_call(&self.function, &self.arguments)
+    }
+
+    /// If this is a call to `llvm.experimental.gc.result`, get its argument.
+    /// Returns `None` for other calls.
+    pub fn as_gc_result(&self) -> Option<GcResult<'_>> {
+        GcResult::from_call(&self.function, &self.arguments)
+    }
+
+    /// If this is a call to one of the arithmetic-with-overflow intrinsics
+    /// (e.g., `llvm.sadd.with.overflow`), get the operation it performs.
+    /// Returns `None` for other calls.
+    pub fn as_checked_arithmetic(&self) -> Option<CheckedArithmetic<'_>> {
+        CheckedArithmetic::from_call(&self.function, &self.arguments, self.dest.as_ref())
+    }
+
+    /// If this is a call to one of the saturating arithmetic intrinsics
+    /// (e.g., `llvm.sadd.sat`), get the operation it performs. Returns `None`
+    /// for other calls.
+    pub fn as_saturating_arithmetic(&self) -> Option<SaturatingArithmetic<'_>> {
+        SaturatingArithmetic::from_call(&self.function, &self.arguments)
+    }
+
+    /// If this is a call to one of the fixed-point arithmetic intrinsics
+    /// (e.g., `llvm.smul.fix`), get the operation it performs. Returns `None`
+    /// for other calls, or if the scale isn't a constant integer.
+    pub fn as_fixed_point_arithmetic(&self) -> Option<FixedPointArithmetic<'_>> {
+        FixedPointArithmetic::from_call(&self.function, &self.arguments)
+    }
+
+    /// If this is a call to one of the vector reduction intrinsics (e.g.,
+    /// `llvm.vector.reduce.add`), get its kind and operands. Returns `None`
+    /// for other calls.
+    pub fn as_vector_reduction(&self) -> Option<VectorReduction<'_>> {
+        VectorReduction::from_call(&self.function, &self.arguments)
+    }
+}
+
+impl Invoke {
+    /// If this invokes an intrinsic which this crate recognizes, get which
+    /// intrinsic.
+    pub fn intrinsic(&self) -> Option<Intrinsic> {
+        Intrinsic::from_name(callee_name(&self.function)?)
+    }
+
+    /// Like [`Call::as_stackmap()`](../instruction/struct.Call.html#method.as_stackmap)
+    pub fn as_stackmap(&self) -> Option<StackMap<'_>> {
+        StackMap::from_call(&self.function, &self.arguments)
+    }
+
+    /// Like [`Call::as_patchpoint()`](../instruction/struct.Call.html#method.as_patchpoint)
+    pub fn as_patchpoint(&self) -> Option<PatchPoint<'_>> {
+        PatchPoint::from_call(&self.function, &self.arguments)
+    }
+
+    /// Like [`Call::as_statepoint()`](../instruction/struct.Call.html#method.as_statepoint)
+    pub fn as_statepoint(&self) -> Option<Statepoint<'_>> {
+        Statepoint::from_call(&self.function, &self.arguments, &self.operand_bundles)
+    }
+}
+
+/// The value of the given operand, if it is a constant integer
+fn constant_int(op: &Operand) -> Option<u64> {
+    match op.as_constant()? {
+        Constant::Int { value, .. } => Some(*value),
+        _ => None,
+    }
+}
+
+/// Is `name` the given `base` name, possibly followed by overload suffixes?
+pub(crate) fn has_base_name(name: &str, base: &str) -> bool {
+    match name.strip_prefix(base) {
+        Some(rest) => rest.is_empty() || rest.starts_with('.'),
+        None => false,
+    }
+}
+
+/// The name of the function being called, if it is called directly
+pub(crate) fn callee_name(function: &Either<InlineAssembly, OperandRef>) -> Option<&str> {
+    match function {
+        Either::Right(op) => match op.as_constant() {
+            Some(Constant::GlobalReference {
+                name: Name::Name(name),
+                ..
+            }) => Some(name),
+            _ => None,
+        },
+        Either::Left(_) => None,
+    }
+}
@@ -3,6 +3,10 @@
 use crate::llvm_sys::*;
 use std::iter::Peekable;
 
+pub fn get_functions(module: LLVMModuleRef) -> impl Iterator<Item = LLVMValueRef> {
+    FunctionIterator::new(module)
+}
+
 pub fn get_defined_functions(module: LLVMModuleRef) -> impl Iterator<Item = LLVMValueRef> {
     FunctionIterator::new(module).filter(|&f| is_defined(f))
 }
@@ -19,6 +23,11 @@ pub fn get_global_aliases(module: LLVMModuleRef) -> impl Iterator<Item = LLVMVal
     GlobalAliasIterator::new(module)
 }
 
+#[cfg(feature = "llvm-9-or-greater")]
+pub fn get_global_ifuncs(module: LLVMModuleRef) -> impl Iterator<Item = LLVMValueRef> {
+    GlobalIFuncIterator::new(module)
+}
+
 pub fn get_parameters(func: LLVMValueRef) -> impl Iterator<Item = LLVMValueRef> {
     ParamIterator::new(func)
 }
@@ -82,6 +91,14 @@ iterator!(
     LLVMGetFirstGlobalAlias,
     LLVMGetNextGlobalAlias
 );
+#[cfg(feature = "llvm-9-or-greater")]
+iterator!(
+    GlobalIFuncIterator,
+    LLVMModuleRef,
+    LLVMValueRef,
+    LLVMGetFirstGlobalIFunc,
+    LLVMGetNextGlobalIFunc
+);
 iterator!(
     ParamIterator,
     LLVMValueRef,
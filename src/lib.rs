@@ -5,38 +5,60 @@
 // sure if this is necessary or helpful anymore
 #![doc(html_root_url = "https://docs.rs/llvm-ir/0.8.2")]
 
+mod archive;
+mod bitstream;
 #[macro_use]
 mod from_llvm;
 mod iterators;
 #[rustfmt::skip]
 mod llvm_sys;
+#[cfg(feature = "native-reader")]
+mod native_reader;
 
+pub mod analysis;
 pub mod basicblock;
 pub use basicblock::BasicBlock;
 pub mod constant;
 pub use constant::{Constant, ConstantRef};
+pub mod context;
+pub use context::Context;
 #[cfg(feature = "llvm-9-or-greater")]
 pub mod debugloc;
 #[cfg(feature = "llvm-9-or-greater")]
-pub use debugloc::{DebugLoc, HasDebugLoc};
+pub use debugloc::{DebugLoc, HasDebugLoc, SourceMap};
+pub mod diff;
+pub mod error;
+pub use error::Error;
 pub mod function;
 pub use function::Function;
 pub mod instruction;
 pub use instruction::Instruction;
-// pub mod metadata;
-// pub use metadata::Metadata;
+pub mod intrinsic;
+pub mod location;
+pub use location::{InstrLoc, ModuleLoc};
+pub mod matchers;
+pub mod metadata;
+pub use metadata::Metadata;
 pub mod module;
 pub use module::Module;
 pub mod name;
 pub use name::Name;
+pub mod opcode;
+pub use opcode::Opcode;
 pub mod operand;
-pub use operand::Operand;
+pub use operand::{Operand, OperandRef};
 pub mod predicates;
 pub use predicates::{FPPredicate, IntPredicate};
+pub mod summary;
 pub mod terminator;
 pub use terminator::Terminator;
+pub mod text;
+pub mod transform;
 pub mod types;
 pub use types::{Type, TypeRef};
+pub mod value;
+pub use value::ValueKind;
+mod verifier;
 
 macro_rules! case {
     ($feature:expr) => {
@@ -0,0 +1,98 @@
+//! Lightweight, index-based handles identifying basic blocks and instructions
+//! within a `Module`.
+//!
+//! These are small `Copy` types which can be used as `HashMap` keys, so that
+//! analyses can refer to (e.g.) "instruction 7 of block 2 of function 0"
+//! without holding references into the `Module` or cloning instructions.
+//! Use [`Module::get_block()`](../module/struct.Module.html#method.get_block)
+//! and [`Module::get_instr()`](../module/struct.Module.html#method.get_instr)
+//! to resolve them.
+//!
+//! Handles are only meaningful for the `Module` they were created for, and
+//! are invalidated if that `Module`'s functions, blocks, or instructions are
+//! added, removed, or reordered.
+
+use crate::instruction::Instruction;
+use crate::terminator::Terminator;
+
+/// Identifies a basic block within a `Module`: the block at index `block` in
+/// the `basic_blocks` of the function at index `func` in the `Module`'s
+/// `functions`.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModuleLoc {
+    pub func: usize,
+    pub block: usize,
+}
+
+/// Identifies an instruction or terminator within a `Module`.
+///
+/// `InstrLoc`s are ordered by function, then by block, and then by position
+/// within the block, with each block's terminator ordered after its
+/// instructions.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InstrLoc {
+    /// The block containing the instruction
+    pub block: ModuleLoc,
+    /// The position of the instruction within the block
+    pub pos: InstrPos,
+}
+
+/// The position of an instruction or terminator within a basic block
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InstrPos {
+    /// The `Instruction` at the given index in the block's `instrs`
+    Instr(usize),
+    /// The block's `Terminator`
+    Terminator,
+}
+
+/// The result of resolving an `InstrLoc`: either an `Instruction` or a
+/// `Terminator`
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum InstrRef<'m> {
+    Instr(&'m Instruction),
+    Terminator(&'m Terminator),
+}
+
+impl ModuleLoc {
+    pub fn new(func: usize, block: usize) -> Self {
+        Self { func, block }
+    }
+
+    /// The `InstrLoc` of the `Instruction` at the given index in this block
+    pub fn instr(self, index: usize) -> InstrLoc {
+        InstrLoc {
+            block: self,
+            pos: InstrPos::Instr(index),
+        }
+    }
+
+    /// The `InstrLoc` of this block's `Terminator`
+    pub fn terminator(self) -> InstrLoc {
+        InstrLoc {
+            block: self,
+            pos: InstrPos::Terminator,
+        }
+    }
+}
+
+impl<'m> InstrRef<'m> {
+    /// Get the `Instruction`, or `None` if this is a `Terminator`
+    pub fn as_instr(self) -> Option<&'m Instruction> {
+        match self {
+            InstrRef::Instr(inst) => Some(inst),
+            InstrRef::Terminator(_) => None,
+        }
+    }
+
+    /// Get the `Terminator`, or `None` if this is an `Instruction`
+    pub fn as_terminator(self) -> Option<&'m Terminator> {
+        match self {
+            InstrRef::Instr(_) => None,
+            InstrRef::Terminator(term) => Some(term),
+        }
+    }
+}
@@ -0,0 +1,446 @@
+//! Composable matchers for recognizing patterns of instructions and constant
+//! expressions, in the style of LLVM's `PatternMatch.h`.
+//!
+//! Each `m_*` function builds a `Matcher`, and matchers nest to describe an
+//! expression tree. The same matcher recognizes both an `Instruction` and the
+//! equivalent constant expression; for instance,
+//!
+//! ```ignore
+//! let pat = m_add(m_value(), m_constant_int(1));
+//! ```
+//!
+//! matches both `%y = add i32 %x, 1` and the constant expression
+//! `add (i64 ptrtoint (i32* @g to i64), i64 1)`.
+//!
+//! By default, a `LocalOperand` only matches leaf matchers such as
+//! `m_value()`. To also look through local operands to the instructions which
+//! define them, use the `_in` variants of the `Matcher` methods, which take the
+//! `SsaGraph` of the enclosing function.
+
+use crate::analysis::SsaGraph;
+use crate::constant::Constant;
+use crate::instruction::Instruction;
+use crate::name::Name;
+use crate::operand::Operand;
+use crate::predicates::IntPredicate;
+
+/// A value which can be examined by a `Matcher`
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum Node<'a> {
+    Operand(&'a Operand),
+    Constant(&'a Constant),
+    Instruction(&'a Instruction),
+}
+
+impl<'a> Node<'a> {
+    /// Look through a `ConstantOperand` to its `Constant`, and (if `defs` is
+    /// provided) through a `LocalOperand` to the `Instruction` defining it.
+    fn resolve(self, defs: Option<&SsaGraph<'a>>) -> Self {
+        match self {
+            Node::Operand(Operand::ConstantOperand(c)) => Node::Constant(c),
+            Node::Operand(Operand::LocalOperand { name, .. }) => {
+                match defs.and_then(|defs| defs.defining_instruction(name)) {
+                    Some(inst) => Node::Instruction(inst),
+                    None => self,
+                }
+            },
+            _ => self,
+        }
+    }
+}
+
+/// A pattern over `Operand`s, `Constant`s, and `Instruction`s.
+///
+/// Matchers are usually built with the `m_*` functions in this module rather
+/// than implemented directly.
+pub trait Matcher {
+    /// Does the given `Node` match this pattern?
+    ///
+    /// If `defs` is provided, `LocalOperand`s defined by instructions are
+    /// matched by looking at their defining instructions.
+    fn match_node<'a>(&self, node: Node<'a>, defs: Option<&SsaGraph<'a>>) -> bool;
+
+    /// Does the given `Instruction` match this pattern?
+    fn matches_instr(&self, inst: &Instruction) -> bool {
+        self.match_node(Node::Instruction(inst), None)
+    }
+
+    /// Does the given `Constant` match this pattern?
+    fn matches_constant(&self, constant: &Constant) -> bool {
+        self.match_node(Node::Constant(constant), None)
+    }
+
+    /// Does the given `Operand` match this pattern?
+    fn matches_operand(&self, operand: &Operand) -> bool {
+        self.match_node(Node::Operand(operand), None)
+    }
+
+    /// Like `matches_instr()`, but looks through `LocalOperand`s to their
+    /// definitions in the given `SsaGraph`.
+    fn matches_instr_in<'a>(&self, inst: &'a Instruction, defs: &SsaGraph<'a>) -> bool {
+        self.match_node(Node::Instruction(inst), Some(defs))
+    }
+
+    /// Like `matches_operand()`, but looks through `LocalOperand`s to their
+    /// definitions in the given `SsaGraph`.
+    fn matches_operand_in<'a>(&self, operand: &'a Operand, defs: &SsaGraph<'a>) -> bool {
+        self.match_node(Node::Operand(operand), Some(defs))
+    }
+}
+
+impl<M: Matcher + ?Sized> Matcher for Box<M> {
+    fn match_node<'a>(&self, node: Node<'a>, defs: Option<&SsaGraph<'a>>) -> bool {
+        (**self).match_node(node, defs)
+    }
+}
+
+impl<M: Matcher + ?Sized> Matcher for &M {
+    fn match_node<'a>(&self, node: Node<'a>, defs: Option<&SsaGraph<'a>>) -> bool {
+        (**self).match_node(node, defs)
+    }
+}
+
+/// Matcher returned by `m_value()`
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct AnyValue;
+
+/// Matches any value at all
+pub fn m_value() -> AnyValue {
+    AnyValue
+}
+
+impl Matcher for AnyValue {
+    fn match_node<'a>(&self, _node: Node<'a>, _defs: Option<&SsaGraph<'a>>) -> bool {
+        true
+    }
+}
+
+/// Matcher returned by `m_constant()`
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct AnyConstant;
+
+/// Matches any `Constant` (including constant expressions)
+pub fn m_constant() -> AnyConstant {
+    AnyConstant
+}
+
+impl Matcher for AnyConstant {
+    fn match_node<'a>(&self, node: Node<'a>, _defs: Option<&SsaGraph<'a>>) -> bool {
+        matches!(node.resolve(None), Node::Constant(_))
+    }
+}
+
+/// Matcher returned by `m_constant_int()`
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct ConstantInt {
+    value: u64,
+}
+
+/// Matches a `Constant::Int` with the given value (of any bitwidth)
+pub fn m_constant_int(value: u64) -> ConstantInt {
+    ConstantInt { value }
+}
+
+impl Matcher for ConstantInt {
+    fn match_node<'a>(&self, node: Node<'a>, _defs: Option<&SsaGraph<'a>>) -> bool {
+        match node.resolve(None) {
+            Node::Constant(Constant::Int { value, .. }) => *value == self.value,
+            _ => false,
+        }
+    }
+}
+
+/// Matcher returned by `m_local()`
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Local {
+    name: Name,
+}
+
+/// Matches the local value with the given name: either a `LocalOperand`
+/// referring to it, or the `Instruction` which defines it
+pub fn m_local(name: impl Into<Name>) -> Local {
+    Local { name: name.into() }
+}
+
+impl Matcher for Local {
+    fn match_node<'a>(&self, node: Node<'a>, _defs: Option<&SsaGraph<'a>>) -> bool {
+        match node {
+            Node::Operand(Operand::LocalOperand { name, .. }) => *name == self.name,
+            Node::Instruction(inst) => inst.try_get_result() == Some(&self.name),
+            _ => false,
+        }
+    }
+}
+
+/// Matcher returned by `m_combine_or()`
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct CombineOr<A, B> {
+    a: A,
+    b: B,
+}
+
+/// Matches anything matched by either `a` or `b`
+pub fn m_combine_or<A: Matcher, B: Matcher>(a: A, b: B) -> CombineOr<A, B> {
+    CombineOr { a, b }
+}
+
+impl<A: Matcher, B: Matcher> Matcher for CombineOr<A, B> {
+    fn match_node<'a>(&self, node: Node<'a>, defs: Option<&SsaGraph<'a>>) -> bool {
+        self.a.match_node(node, defs) || self.b.match_node(node, defs)
+    }
+}
+
+/// Matcher returned by `m_combine_and()`
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct CombineAnd<A, B> {
+    a: A,
+    b: B,
+}
+
+/// Matches anything matched by both `a` and `b`
+pub fn m_combine_and<A: Matcher, B: Matcher>(a: A, b: B) -> CombineAnd<A, B> {
+    CombineAnd { a, b }
+}
+
+impl<A: Matcher, B: Matcher> Matcher for CombineAnd<A, B> {
+    fn match_node<'a>(&self, node: Node<'a>, defs: Option<&SsaGraph<'a>>) -> bool {
+        self.a.match_node(node, defs) && self.b.match_node(node, defs)
+    }
+}
+
+/// Matcher returned by `m_add()`, `m_sub()`, and the other binary-operation
+/// matchers
+#[derive(Clone, Debug)]
+pub struct BinaryOp<L, R> {
+    split: for<'a> fn(Node<'a>) -> Option<(Node<'a>, Node<'a>)>,
+    lhs: L,
+    rhs: R,
+}
+
+impl<L: Matcher, R: Matcher> Matcher for BinaryOp<L, R> {
+    fn match_node<'a>(&self, node: Node<'a>, defs: Option<&SsaGraph<'a>>) -> bool {
+        match (self.split)(node.resolve(defs)) {
+            Some((lhs, rhs)) => self.lhs.match_node(lhs, defs) && self.rhs.match_node(rhs, defs),
+            None => false,
+        }
+    }
+}
+
+macro_rules! binop_matcher {
+    ($fn:ident, $variant:ident, $opname:expr) => {
+        #[doc = concat!("Matches an `", $opname, "` instruction or constant expression whose operands match `lhs` and `rhs`")]
+        pub fn $fn<L: Matcher, R: Matcher>(lhs: L, rhs: R) -> BinaryOp<L, R> {
+            BinaryOp {
+                split: |node| match node {
+                    Node::Instruction(Instruction::$variant(i)) => {
+                        Some((Node::Operand(&i.operand0), Node::Operand(&i.operand1)))
+                    },
+                    Node::Constant(Constant::$variant(c)) => {
+                        Some((Node::Constant(&c.operand0), Node::Constant(&c.operand1)))
+                    },
+                    _ => None,
+                },
+                lhs,
+                rhs,
+            }
+        }
+    };
+}
+
+binop_matcher!(m_add, Add, "add");
+binop_matcher!(m_sub, Sub, "sub");
+binop_matcher!(m_mul, Mul, "mul");
+binop_matcher!(m_udiv, UDiv, "udiv");
+binop_matcher!(m_sdiv, SDiv, "sdiv");
+binop_matcher!(m_urem, URem, "urem");
+binop_matcher!(m_srem, SRem, "srem");
+binop_matcher!(m_and, And, "and");
+binop_matcher!(m_or, Or, "or");
+binop_matcher!(m_xor, Xor, "xor");
+binop_matcher!(m_shl, Shl, "shl");
+binop_matcher!(m_lshr, LShr, "lshr");
+binop_matcher!(m_ashr, AShr, "ashr");
+binop_matcher!(m_fadd, FAdd, "fadd");
+binop_matcher!(m_fsub, FSub, "fsub");
+binop_matcher!(m_fmul, FMul, "fmul");
+binop_matcher!(m_fdiv, FDiv, "fdiv");
+binop_matcher!(m_frem, FRem, "frem");
+
+/// Matcher returned by `m_trunc()`, `m_zext()`, and the other cast matchers
+#[derive(Clone, Debug)]
+pub struct Cast<M> {
+    split: for<'a> fn(Node<'a>) -> Option<Node<'a>>,
+    operand: M,
+}
+
+impl<M: Matcher> Matcher for Cast<M> {
+    fn match_node<'a>(&self, node: Node<'a>, defs: Option<&SsaGraph<'a>>) -> bool {
+        match (self.split)(node.resolve(defs)) {
+            Some(operand) => self.operand.match_node(operand, defs),
+            None => false,
+        }
+    }
+}
+
+macro_rules! cast_matcher {
+    ($fn:ident, $variant:ident, $opname:expr) => {
+        #[doc = concat!("Matches a `", $opname, "` instruction or constant expression whose operand matches `operand`")]
+        pub fn $fn<M: Matcher>(operand: M) -> Cast<M> {
+            Cast {
+                split: |node| match node {
+                    Node::Instruction(Instruction::$variant(i)) => Some(Node::Operand(&i.operand)),
+                    Node::Constant(Constant::$variant(c)) => Some(Node::Constant(&c.operand)),
+                    _ => None,
+                },
+                operand,
+            }
+        }
+    };
+}
+
+cast_matcher!(m_trunc, Trunc, "trunc");
+cast_matcher!(m_zext, ZExt, "zext");
+cast_matcher!(m_sext, SExt, "sext");
+cast_matcher!(m_ptrtoint, PtrToInt, "ptrtoint");
+cast_matcher!(m_inttoptr, IntToPtr, "inttoptr");
+cast_matcher!(m_bitcast, BitCast, "bitcast");
+cast_matcher!(m_addrspacecast, AddrSpaceCast, "addrspacecast");
+
+/// Matcher returned by `m_icmp()`
+#[derive(Clone, Debug)]
+pub struct ICmp<L, R> {
+    predicate: IntPredicate,
+    lhs: L,
+    rhs: R,
+}
+
+/// Matches an `icmp` instruction or constant expression with the given
+/// predicate, whose operands match `lhs` and `rhs`
+pub fn m_icmp<L: Matcher, R: Matcher>(predicate: IntPredicate, lhs: L, rhs: R) -> ICmp<L, R> {
+    ICmp {
+        predicate,
+        lhs,
+        rhs,
+    }
+}
+
+impl<L: Matcher, R: Matcher> Matcher for ICmp<L, R> {
+    fn match_node<'a>(&self, node: Node<'a>, defs: Option<&SsaGraph<'a>>) -> bool {
+        let (predicate, lhs, rhs) = match node.resolve(defs) {
+            Node::Instruction(Instruction::ICmp(i)) => (
+                i.predicate,
+                Node::Operand(&i.operand0),
+                Node::Operand(&i.operand1),
+            ),
+            Node::Constant(Constant::ICmp(c)) => (
+                c.predicate,
+                Node::Constant(&c.operand0),
+                Node::Constant(&c.operand1),
+            ),
+            _ => return false,
+        };
+        predicate == self.predicate
+            && self.lhs.match_node(lhs, defs)
+            && self.rhs.match_node(rhs, defs)
+    }
+}
+
+/// Matcher returned by `m_select()`
+#[derive(Clone, Debug)]
+pub struct Select<C, T, F> {
+    condition: C,
+    true_value: T,
+    false_value: F,
+}
+
+/// Matches a `select` instruction or constant expression whose operands match
+/// the given matchers
+pub fn m_select<C: Matcher, T: Matcher, F: Matcher>(
+    condition: C,
+    true_value: T,
+    false_value: F,
+) -> Select<C, T, F> {
+    Select {
+        condition,
+        true_value,
+        false_value,
+    }
+}
+
+impl<C: Matcher, T: Matcher, F: Matcher> Matcher for Select<C, T, F> {
+    fn match_node<'a>(&self, node: Node<'a>, defs: Option<&SsaGraph<'a>>) -> bool {
+        let (condition, true_value, false_value) = match node.resolve(defs) {
+            Node::Instruction(Instruction::Select(i)) => (
+                Node::Operand(&i.condition),
+                Node::Operand(&i.true_value),
+                Node::Operand(&i.false_value),
+            ),
+            Node::Constant(Constant::Select(c)) => (
+                Node::Constant(&c.condition),
+                Node::Constant(&c.true_value),
+                Node::Constant(&c.false_value),
+            ),
+            _ => return false,
+        };
+        self.condition.match_node(condition, defs)
+            && self.true_value.match_node(true_value, defs)
+            && self.false_value.match_node(false_value, defs)
+    }
+}
+
+/// Matcher returned by `m_gep()`
+pub struct GetElementPtr<A> {
+    address: A,
+    indices: Option<Vec<Box<dyn Matcher>>>,
+}
+
+/// Matches a `getelementptr` instruction or constant expression whose address
+/// matches `address`, with any indices. Use
+/// [`with_indices()`](struct.GetElementPtr.html#method.with_indices) to also
+/// constrain the indices.
+pub fn m_gep<A: Matcher>(address: A) -> GetElementPtr<A> {
+    GetElementPtr {
+        address,
+        indices: None,
+    }
+}
+
+impl<A: Matcher> GetElementPtr<A> {
+    /// Only match GEPs with exactly as many indices as given here, each
+    /// matching the corresponding matcher
+    pub fn with_indices(self, indices: Vec<Box<dyn Matcher>>) -> Self {
+        Self {
+            indices: Some(indices),
+            ..self
+        }
+    }
+}
+
+impl<A: Matcher> Matcher for GetElementPtr<A> {
+    fn match_node<'a>(&self, node: Node<'a>, defs: Option<&SsaGraph<'a>>) -> bool {
+        let (address, indices): (Node<'a>, Vec<Node<'a>>) = match node.resolve(defs) {
+            Node::Instruction(Instruction::GetElementPtr(i)) => (
+                Node::Operand(&i.address),
+                i.indices.iter().map(|op| Node::Operand(op)).collect(),
+            ),
+            Node::Constant(Constant::GetElementPtr(c)) => (
+                Node::Constant(&c.address),
+                c.indices.iter().map(|c| Node::Constant(c)).collect(),
+            ),
+            _ => return false,
+        };
+        if !self.address.match_node(address, defs) {
+            return false;
+        }
+        match &self.indices {
+            None => true,
+            Some(matchers) => {
+                matchers.len() == indices.len()
+                    && matchers
+                        .iter()
+                        .zip(indices)
+                        .all(|(m, index)| m.match_node(index, defs))
+            },
+        }
+    }
+}
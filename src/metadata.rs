@@ -1,44 +1,226 @@
 //! See [LLVM 14 docs on Metadata](https://releases.llvm.org/14.0.0/docs/LangRef.html#metadata)
 
-use either::Either;
-use std::fmt::Debug;
-
 use crate::operand::Operand;
-use crate::types::{Type, Typed};
-
-#[derive(PartialEq, Clone, Debug, Hash)]
-pub enum MetadataRef<T> where T: PartialEq + Clone + Debug {
-    Ref(MetadataNodeID),
-    Inline(Box<T>),
-}
-
+use crate::types::{TypeRef, Typed, Types};
+use std::fmt::{self, Display};
+
+/// Identifies a metadata node within a `Module`.
+/// See [`Module::metadata_nodes`](../module/struct.Module.html#structfield.metadata_nodes).
+///
+/// These IDs are assigned by `llvm-ir` in the order the nodes are encountered,
+/// so they don't necessarily match the `!12`-style numbers in a `.ll` file.
 pub type MetadataNodeID = usize;
 
 /// See [LLVM 14 docs on Metadata Nodes and Metadata Strings](https://releases.llvm.org/14.0.0/docs/LangRef.html#metadata-nodes-and-metadata-strings)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Metadata {
+    /// e.g., `!"foo"`
     String(String),
-    Node(MetadataRef<MetadataNode>),
+    /// A reference to a metadata node, e.g., `!12`
+    Node(MetadataNodeID),
+    /// A value used as metadata, e.g., the `i32 %x` in `metadata i32 %x`
     Value(Operand),
+    /// This is used if we get metadata which `llvm-ir` can't represent, e.g.,
+    /// a `DIArgList`, or a debug-info node which was skipped; see
+    /// [`ParseOptions::skip_debug_info`](../module/struct.ParseOptions.html#structfield.skip_debug_info)
+    Unknown,
 }
 
 impl Typed for Metadata {
-    fn get_type(&self) -> Type {
-        Type::MetadataType
+    fn get_type(&self, types: &Types) -> TypeRef {
+        types.metadata_type()
+    }
+}
+
+impl Display for Metadata {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Metadata::String(s) => write!(f, "!{:?}", s),
+            Metadata::Node(id) => write!(f, "!{}", id),
+            Metadata::Value(op) => write!(f, "{}", op),
+            Metadata::Unknown => write!(f, "<unknown metadata>"),
+        }
     }
 }
 
 /// See [LLVM 14 docs on Metadata Nodes and Metadata Strings](https://releases.llvm.org/14.0.0/docs/LangRef.html#metadata-nodes-and-metadata-strings)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MetadataNode {
-    Tuple(Vec<Option<Metadata>>),  // None represents null
-    Expression(DIExpression),
-    GlobalVariableExpression(DIGlobalVariableExpression),
-    Location(DILocation),
-    MacroNode(DIMacroNode),
-    Node(DINode),
+    /// A generic node, e.g., `!{i32 1, !"foo"}`
+    Tuple(Vec<Option<Metadata>>), // None represents null
+    /// A specialized node, e.g., `!DILocalVariable(...)`.
+    /// Only the node's metadata operands are available through the LLVM C
+    /// API, not its other fields (such as line numbers).
+    Specialized {
+        /// The kind of node, e.g., `"DILocalVariable"`
+        kind: String,
+        operands: Vec<Option<Metadata>>, // None represents null
+    },
+}
+
+impl MetadataNode {
+    /// Get the node's operands
+    pub fn operands(&self) -> &[Option<Metadata>] {
+        match self {
+            MetadataNode::Tuple(operands) => operands,
+            MetadataNode::Specialized { operands, .. } => operands,
+        }
+    }
+}
+
+/// The decoded `!llvm.loop` metadata attached to a loop's back-edge branch.
+/// See [LLVM 14 docs on `llvm.loop`](https://releases.llvm.org/14.0.0/docs/LangRef.html#llvm-loop)
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LoopMetadata {
+    /// The loop ID, i.e., the (distinct) node the branch's `!llvm.loop`
+    /// refers to. Branches of the same loop share the same loop ID.
+    pub id: MetadataNodeID,
+    /// The hints in the loop ID's operands, in order
+    pub hints: Vec<LoopHint>,
+}
+
+/// A hint in [`LoopMetadata`](struct.LoopMetadata.html), e.g.,
+/// `!{!"llvm.loop.unroll.count", i32 4}`.
+/// See [LLVM 14 docs on `llvm.loop`](https://releases.llvm.org/14.0.0/docs/LangRef.html#llvm-loop)
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LoopHint {
+    /// `llvm.loop.mustprogress`
+    MustProgress,
+    /// `llvm.loop.unroll.enable`
+    UnrollEnable,
+    /// `llvm.loop.unroll.disable`
+    UnrollDisable,
+    /// `llvm.loop.unroll.runtime.disable`
+    UnrollRuntimeDisable,
+    /// `llvm.loop.unroll.full`
+    UnrollFull,
+    /// `llvm.loop.unroll.count`
+    UnrollCount(u64),
+    /// `llvm.loop.unroll_and_jam.enable`
+    UnrollAndJamEnable,
+    /// `llvm.loop.unroll_and_jam.disable`
+    UnrollAndJamDisable,
+    /// `llvm.loop.unroll_and_jam.count`
+    UnrollAndJamCount(u64),
+    /// `llvm.loop.vectorize.enable`
+    VectorizeEnable(bool),
+    /// `llvm.loop.vectorize.predicate.enable`
+    VectorizePredicateEnable(bool),
+    /// `llvm.loop.vectorize.scalable.enable`
+    VectorizeScalableEnable(bool),
+    /// `llvm.loop.vectorize.width`
+    VectorizeWidth(u64),
+    /// `llvm.loop.interleave.count`
+    InterleaveCount(u64),
+    /// `llvm.loop.isvectorized`, which the vectorizer adds to loops it has
+    /// already processed
+    IsVectorized(bool),
+    /// `llvm.loop.distribute.enable`
+    DistributeEnable(bool),
+    /// `llvm.licm.disable`
+    LicmDisable,
+    /// `llvm.loop.licm_versioning.disable`
+    LicmVersioningDisable,
+    /// `llvm.loop.disable_nonforced`
+    DisableNonforced,
+    /// `llvm.loop.parallel_accesses`, with the access groups it lists
+    ParallelAccesses(Vec<MetadataNodeID>),
+    /// Any other operand of the loop ID: an unrecognized hint, a recognized
+    /// hint with unexpected operands, or something else entirely (such as the
+    /// `DILocation`s of the loop's start and end)
+    Other(Metadata),
+}
+
+impl LoopMetadata {
+    /// Decode the loop ID with the given `MetadataNodeID`.
+    /// Returns `None` if `nodes` doesn't contain a `Tuple` with that ID.
+    pub fn from_node(id: MetadataNodeID, nodes: &[(MetadataNodeID, MetadataNode)]) -> Option<Self> {
+        let operands = match nodes.get(id) {
+            Some((_, MetadataNode::Tuple(operands))) => operands,
+            _ => return None,
+        };
+        let hints = operands
+            .iter()
+            .flatten()
+            .filter(|op| **op != Metadata::Node(id)) // the loop ID refers to itself
+            .map(|op| LoopHint::from_metadata(op, nodes))
+            .collect();
+        Some(Self { id, hints })
+    }
 }
 
+impl LoopHint {
+    fn from_metadata(md: &Metadata, nodes: &[(MetadataNodeID, MetadataNode)]) -> Self {
+        let other = || LoopHint::Other(md.clone());
+        let operands = match md {
+            Metadata::Node(id) => match nodes.get(*id) {
+                Some((_, MetadataNode::Tuple(operands))) => operands,
+                _ => return other(),
+            },
+            _ => return other(),
+        };
+        let (name, args) = match operands.split_first() {
+            Some((Some(Metadata::String(name)), args)) => (name.as_str(), args),
+            _ => return other(),
+        };
+        let int_arg = || match args {
+            [Some(Metadata::Value(Operand::ConstantOperand(cref)))] => match cref.as_ref() {
+                Constant::Int { value, .. } => Some(*value),
+                _ => None,
+            },
+            _ => None,
+        };
+        let hint = match name {
+            "llvm.loop.mustprogress" if args.is_empty() => Some(LoopHint::MustProgress),
+            "llvm.loop.unroll.enable" if args.is_empty() => Some(LoopHint::UnrollEnable),
+            "llvm.loop.unroll.disable" if args.is_empty() => Some(LoopHint::UnrollDisable),
+            "llvm.loop.unroll.runtime.disable" if args.is_empty() => {
+                Some(LoopHint::UnrollRuntimeDisable)
+            },
+            "llvm.loop.unroll.full" if args.is_empty() => Some(LoopHint::UnrollFull),
+            "llvm.loop.unroll.count" => int_arg().map(LoopHint::UnrollCount),
+            "llvm.loop.unroll_and_jam.enable" if args.is_empty() => {
+                Some(LoopHint::UnrollAndJamEnable)
+            },
+            "llvm.loop.unroll_and_jam.disable" if args.is_empty() => {
+                Some(LoopHint::UnrollAndJamDisable)
+            },
+            "llvm.loop.unroll_and_jam.count" => int_arg().map(LoopHint::UnrollAndJamCount),
+            "llvm.loop.vectorize.enable" => int_arg().map(|v| LoopHint::VectorizeEnable(v != 0)),
+            "llvm.loop.vectorize.predicate.enable" => {
+                int_arg().map(|v| LoopHint::VectorizePredicateEnable(v != 0))
+            },
+            "llvm.loop.vectorize.scalable.enable" => {
+                int_arg().map(|v| LoopHint::VectorizeScalableEnable(v != 0))
+            },
+            "llvm.loop.vectorize.width" => int_arg().map(LoopHint::VectorizeWidth),
+            "llvm.loop.interleave.count" => int_arg().map(LoopHint::InterleaveCount),
+            "llvm.loop.isvectorized" => int_arg().map(|v| LoopHint::IsVectorized(v != 0)),
+            "llvm.loop.distribute.enable" => int_arg().map(|v| LoopHint::DistributeEnable(v != 0)),
+            "llvm.licm.disable" if args.is_empty() => Some(LoopHint::LicmDisable),
+            "llvm.loop.licm_versioning.disable" if args.is_empty() => {
+                Some(LoopHint::LicmVersioningDisable)
+            },
+            "llvm.loop.disable_nonforced" if args.is_empty() => Some(LoopHint::DisableNonforced),
+            "llvm.loop.parallel_accesses" => args
+                .iter()
+                .map(|arg| match arg {
+                    Some(Metadata::Node(group)) => Some(*group),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(LoopHint::ParallelAccesses),
+            _ => None,
+        };
+        hint.unwrap_or_else(other)
+    }
+}
+
+/* --TODO not yet implemented: specialized debug-info metadata nodes
 // DI* types are in alphabetical order in this file
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
@@ -545,15 +727,255 @@ pub enum Virtuality {
     Virtual,
     PureVirtual,
 }
+*/
 
 // ********* //
 // from_llvm //
 // ********* //
 
-use crate::from_llvm::*;
+use crate::constant::Constant;
+use crate::error::Error;
+use crate::function::FunctionContext;
+use crate::llvm_sys::*;
+use crate::module::ModuleContext;
+use llvm_sys::debuginfo::{LLVMGetMetadataKind, LLVMMetadataKind};
+use llvm_sys::LLVMValueKind;
 
 impl Metadata {
-    pub(crate) fn from_llvm_ref(md: LLVMValueRef) -> Self {
-        unimplemented!("Metadata::from_llvm_ref");
+    /// `md` should be a `MetadataAsValue`, e.g., an argument to `llvm.dbg.value`
+    pub(crate) fn from_llvm_ref(
+        md: LLVMValueRef,
+        ctx: &mut ModuleContext,
+        func_ctx: &FunctionContext,
+    ) -> Result<Self, Error> {
+        match unsafe { LLVMGetMetadataKind(LLVMValueAsMetadata(md)) } {
+            LLVMMetadataKind::LLVMLocalAsMetadataMetadataKind
+            | LLVMMetadataKind::LLVMConstantAsMetadataMetadataKind => {
+                // for these, LLVMGetMDNodeOperands() gets the wrapped value
+                let mut value = std::ptr::null_mut();
+                unsafe { LLVMGetMDNodeOperands(md, &mut value) };
+                Ok(Metadata::Value(Operand::from_llvm_ref(
+                    value, ctx, func_ctx,
+                )?))
+            },
+            _ => Self::from_llvm_ref_global(md, ctx),
+        }
+    }
+
+    /// Like `from_llvm_ref()`, but for metadata which can't refer to
+    /// function-local values, such as the operands of a metadata node
+    fn from_llvm_ref_global(md: LLVMValueRef, ctx: &mut ModuleContext) -> Result<Self, Error> {
+        Ok(
+            match unsafe { LLVMGetMetadataKind(LLVMValueAsMetadata(md)) } {
+                LLVMMetadataKind::LLVMMDStringMetadataKind => {
+                    let mut len = 0;
+                    let ptr = unsafe { LLVMGetMDString(md, &mut len) };
+                    let bytes =
+                        unsafe { std::slice::from_raw_parts(ptr as *const u8, len as usize) };
+                    Metadata::String(String::from_utf8_lossy(bytes).into_owned())
+                },
+                _ if MetadataNode::is_skipped_debug_info(md, ctx) => Metadata::Unknown,
+                _ if MetadataNode::is_node(md) => {
+                    Metadata::Node(MetadataNode::get_or_create_id(md, ctx)?)
+                },
+                _ => Metadata::Unknown,
+            },
+        )
+    }
+}
+
+impl MetadataNode {
+    /// Is the given metadata (wrapped as a `MetadataAsValue`) a node, as
+    /// opposed to a string, a wrapped value, or something else
+    fn is_node(md: LLVMValueRef) -> bool {
+        match unsafe { LLVMGetMetadataKind(LLVMValueAsMetadata(md)) } {
+            LLVMMetadataKind::LLVMMDStringMetadataKind
+            | LLVMMetadataKind::LLVMLocalAsMetadataMetadataKind
+            | LLVMMetadataKind::LLVMConstantAsMetadataMetadataKind
+            | LLVMMetadataKind::LLVMDistinctMDOperandPlaceholderMetadataKind => false,
+            #[cfg(feature = "llvm-13-or-greater")]
+            LLVMMetadataKind::LLVMDIArgListMetadataKind => false,
+            _ => true,
+        }
+    }
+
+    /// Is the given metadata (wrapped as a `MetadataAsValue`) a debug-info
+    /// node, such as a `DILocation`, while debug info is being skipped (see
+    /// `ParseOptions::skip_debug_info`)? Every specialized node is a
+    /// debug-info node.
+    fn is_skipped_debug_info(md: LLVMValueRef, ctx: &ModuleContext) -> bool {
+        ctx.options.skip_debug_info
+            && unsafe { LLVMGetValueKind(md) } == LLVMValueKind::LLVMMetadataAsValueValueKind
+            && Self::is_node(md)
+            && !matches!(
+                unsafe { LLVMGetMetadataKind(LLVMValueAsMetadata(md)) },
+                LLVMMetadataKind::LLVMMDTupleMetadataKind
+            )
+    }
+
+    /// Get the `MetadataNodeID` for the given metadata node (wrapped as a
+    /// `MetadataAsValue`), creating the node (and any nodes it refers to) if
+    /// it hasn't been seen before
+    fn get_or_create_id(
+        md: LLVMValueRef,
+        ctx: &mut ModuleContext,
+    ) -> Result<MetadataNodeID, Error> {
+        let num_nodes = ctx.metadata_nodes.len();
+        let result = Self::create_nodes(md, ctx);
+        if result.is_err() {
+            // Forget the nodes we were creating, which may be only partly
+            // filled in, so that a later lookup doesn't find them
+            ctx.metadata_nodes.truncate(num_nodes);
+            ctx.metadata_node_ids.retain(|_, &mut id| id < num_nodes);
+        }
+        result
+    }
+
+    /// Create the given node and any nodes it refers to which we haven't seen
+    /// before; see `get_or_create_id()`
+    fn create_nodes(md: LLVMValueRef, ctx: &mut ModuleContext) -> Result<MetadataNodeID, Error> {
+        // Nodes may refer to each other cyclically, so rather than recursing,
+        // we assign each node an ID as soon as we see it, and fill in its
+        // operands later
+        let mut worklist = vec![];
+        let id = Self::lookup_or_enqueue(md, ctx, &mut worklist);
+        while let Some((id, md)) = worklist.pop() {
+            let num_operands = unsafe { LLVMGetMDNodeNumOperands(md) };
+            let mut operands: Vec<LLVMValueRef> = Vec::with_capacity(num_operands as usize);
+            unsafe {
+                LLVMGetMDNodeOperands(md, operands.as_mut_ptr());
+                operands.set_len(num_operands as usize);
+            }
+            let operands = operands
+                .into_iter()
+                .map(|op| {
+                    Ok(if op.is_null() || Self::is_skipped_debug_info(op, ctx) {
+                        None
+                    } else if unsafe { LLVMGetValueKind(op) }
+                        == LLVMValueKind::LLVMMetadataAsValueValueKind
+                    {
+                        if Self::is_node(op) {
+                            Some(Metadata::Node(Self::lookup_or_enqueue(
+                                op,
+                                ctx,
+                                &mut worklist,
+                            )))
+                        } else {
+                            Some(Metadata::from_llvm_ref_global(op, ctx)?)
+                        }
+                    } else {
+                        // LLVMGetMDNodeOperands() unwraps constant operands
+                        Some(Metadata::Value(Operand::ConstantOperand(
+                            Constant::from_llvm_ref(op, ctx)?,
+                        )))
+                    })
+                })
+                .collect::<Result<_, Error>>()?;
+            ctx.metadata_nodes[id].1 = match unsafe { LLVMGetMetadataKind(LLVMValueAsMetadata(md)) }
+            {
+                LLVMMetadataKind::LLVMMDTupleMetadataKind => MetadataNode::Tuple(operands),
+                kind => MetadataNode::Specialized {
+                    kind: Self::specialized_kind_name(kind).to_owned(),
+                    operands,
+                },
+            };
+        }
+        Ok(id)
+    }
+
+    /// The name of the given kind of specialized node, as it appears in
+    /// LLVM's textual representation, e.g., `"DILocalVariable"` for
+    /// `!DILocalVariable(...)`
+    fn specialized_kind_name(kind: LLVMMetadataKind) -> &'static str {
+        match kind {
+            LLVMMetadataKind::LLVMDILocationMetadataKind => "DILocation",
+            LLVMMetadataKind::LLVMDIExpressionMetadataKind => "DIExpression",
+            LLVMMetadataKind::LLVMDIGlobalVariableExpressionMetadataKind => {
+                "DIGlobalVariableExpression"
+            },
+            LLVMMetadataKind::LLVMGenericDINodeMetadataKind => "GenericDINode",
+            LLVMMetadataKind::LLVMDISubrangeMetadataKind => "DISubrange",
+            LLVMMetadataKind::LLVMDIEnumeratorMetadataKind => "DIEnumerator",
+            LLVMMetadataKind::LLVMDIBasicTypeMetadataKind => "DIBasicType",
+            LLVMMetadataKind::LLVMDIDerivedTypeMetadataKind => "DIDerivedType",
+            LLVMMetadataKind::LLVMDICompositeTypeMetadataKind => "DICompositeType",
+            LLVMMetadataKind::LLVMDISubroutineTypeMetadataKind => "DISubroutineType",
+            LLVMMetadataKind::LLVMDIFileMetadataKind => "DIFile",
+            LLVMMetadataKind::LLVMDICompileUnitMetadataKind => "DICompileUnit",
+            LLVMMetadataKind::LLVMDISubprogramMetadataKind => "DISubprogram",
+            LLVMMetadataKind::LLVMDILexicalBlockMetadataKind => "DILexicalBlock",
+            LLVMMetadataKind::LLVMDILexicalBlockFileMetadataKind => "DILexicalBlockFile",
+            LLVMMetadataKind::LLVMDINamespaceMetadataKind => "DINamespace",
+            LLVMMetadataKind::LLVMDIModuleMetadataKind => "DIModule",
+            LLVMMetadataKind::LLVMDITemplateTypeParameterMetadataKind => "DITemplateTypeParameter",
+            LLVMMetadataKind::LLVMDITemplateValueParameterMetadataKind => {
+                "DITemplateValueParameter"
+            },
+            LLVMMetadataKind::LLVMDIGlobalVariableMetadataKind => "DIGlobalVariable",
+            LLVMMetadataKind::LLVMDILocalVariableMetadataKind => "DILocalVariable",
+            LLVMMetadataKind::LLVMDILabelMetadataKind => "DILabel",
+            LLVMMetadataKind::LLVMDIObjCPropertyMetadataKind => "DIObjCProperty",
+            LLVMMetadataKind::LLVMDIImportedEntityMetadataKind => "DIImportedEntity",
+            LLVMMetadataKind::LLVMDIMacroMetadataKind => "DIMacro",
+            LLVMMetadataKind::LLVMDIMacroFileMetadataKind => "DIMacroFile",
+            #[cfg(feature = "llvm-9-or-greater")]
+            LLVMMetadataKind::LLVMDICommonBlockMetadataKind => "DICommonBlock",
+            #[cfg(feature = "llvm-11-or-greater")]
+            LLVMMetadataKind::LLVMDIStringTypeMetadataKind => "DIStringType",
+            #[cfg(feature = "llvm-11-or-greater")]
+            LLVMMetadataKind::LLVMDIGenericSubrangeMetadataKind => "DIGenericSubrange",
+            LLVMMetadataKind::LLVMMDStringMetadataKind
+            | LLVMMetadataKind::LLVMConstantAsMetadataMetadataKind
+            | LLVMMetadataKind::LLVMLocalAsMetadataMetadataKind
+            | LLVMMetadataKind::LLVMDistinctMDOperandPlaceholderMetadataKind
+            | LLVMMetadataKind::LLVMMDTupleMetadataKind => {
+                panic!("Expected a specialized metadata node; got {:?}", kind)
+            },
+            #[cfg(feature = "llvm-13-or-greater")]
+            LLVMMetadataKind::LLVMDIArgListMetadataKind => {
+                panic!("Expected a specialized metadata node; got {:?}", kind)
+            },
+        }
+    }
+
+    /// Get the ID of the given node if we've seen it before; otherwise, assign
+    /// it an ID and add it to the `worklist` of nodes to fill in
+    fn lookup_or_enqueue(
+        md: LLVMValueRef,
+        ctx: &mut ModuleContext,
+        worklist: &mut Vec<(MetadataNodeID, LLVMValueRef)>,
+    ) -> MetadataNodeID {
+        let key = unsafe { LLVMValueAsMetadata(md) };
+        if let Some(&id) = ctx.metadata_node_ids.get(&key) {
+            return id;
+        }
+        let id = ctx.metadata_nodes.len();
+        ctx.metadata_nodes.push((id, MetadataNode::Tuple(vec![])));
+        ctx.metadata_node_ids.insert(key, id);
+        worklist.push((id, md));
+        id
+    }
+}
+
+impl LoopMetadata {
+    /// Decode the `!llvm.loop` metadata attached to the given instruction
+    /// (normally a loop's back-edge branch), if any
+    pub(crate) fn from_llvm_inst(
+        inst: LLVMValueRef,
+        ctx: &mut ModuleContext,
+    ) -> Result<Option<Self>, Error> {
+        let md = unsafe {
+            let context = LLVMGetTypeContext(LLVMTypeOf(inst));
+            let kind = "llvm.loop";
+            LLVMGetMetadata(
+                inst,
+                LLVMGetMDKindIDInContext(context, kind.as_ptr() as *const _, kind.len() as u32),
+            )
+        };
+        if md.is_null() || !MetadataNode::is_node(md) {
+            return Ok(None);
+        }
+        let id = MetadataNode::get_or_create_id(md, ctx)?;
+        Ok(Self::from_node(id, &ctx.metadata_nodes))
     }
 }
@@ -1,15 +1,48 @@
-use crate::constant::ConstantRef;
+use crate::basicblock::BasicBlock;
+use crate::constant::{Constant, ConstantRef};
+use crate::context::Context;
 #[cfg(feature = "llvm-9-or-greater")]
 use crate::debugloc::*;
-use crate::function::{Function, FunctionAttribute, GroupID};
+use crate::diff::ModuleDiff;
+use crate::error::{EditError, Error};
+use crate::function::{Function, FunctionAttribute, GroupID, ParameterAttribute};
+use crate::instruction::Instruction;
+use crate::intrinsic::callee_name;
 use crate::llvm_sys::*;
+use crate::location::{InstrLoc, InstrPos, InstrRef, ModuleLoc};
+use crate::metadata::{Metadata, MetadataNode, MetadataNodeID};
 use crate::name::Name;
-use crate::types::{FPType, Type, TypeRef, Typed, Types, TypesBuilder};
-use std::collections::{BTreeMap, HashMap, HashSet};
+use crate::operand::{Operand, OperandRef};
+use crate::terminator::Terminator;
+use crate::text::{self, TextOptions};
+use crate::types::{FPType, NamedStructDef, Type, TypeRef, Typed, Types, TypesBuilder};
+use either::Either;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fmt;
+use std::mem::size_of;
+use std::num::NonZeroU32;
 use std::path::Path;
 
 /// See [LLVM 14 docs on Module Structure](https://releases.llvm.org/14.0.0/docs/LangRef.html#module-structure)
+///
+/// Everything in a `Module` is in a deterministic order, which doesn't depend
+/// on hashing or on the addresses of LLVM's objects: `functions`,
+/// `global_vars`, and `global_aliases` are in the order they appear in the
+/// LLVM module (as in a `.ll` file), and so are each function's parameters,
+/// basic blocks, and instructions; `metadata_nodes` are in the order they are
+/// first reached while converting the module; and named struct types are in
+/// name order (see
+/// [`Types::all_named_structs()`](../types/struct.Types.html#method.all_named_structs)).
+/// So converting the same bitcode twice, or printing the result with
+/// `Debug` or `serde`, always gives the same output.
+///
+/// A `Module` is `Send` and `Sync`, and owns no LLVM objects, so it can be
+/// shared between threads, e.g., in an `Arc`. Reading it takes no locks; the
+/// only locking is in [`Types`](../types/struct.Types.html), when a thread
+/// looks up a type which isn't in the `Module` yet.
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Module {
     /// The name of the module
     pub name: String,
@@ -26,10 +59,40 @@ pub struct Module {
     pub global_vars: Vec<GlobalVariable>,
     /// See [LLVM 14 docs on Global Aliases](https://releases.llvm.org/14.0.0/docs/LangRef.html#aliases)
     pub global_aliases: Vec<GlobalAlias>,
-    // --TODO not yet implemented-- pub function_attribute_groups: Vec<FunctionAttributeGroup>,
+    /// The distinct sets of function attributes used by functions and call
+    /// sites in the `Module`, numbered as in a `.ll` file. A group's
+    /// `group_id` is also its index in this `Vec`.
+    /// See [LLVM 14 docs on Attribute Groups](https://releases.llvm.org/14.0.0/docs/LangRef.html#attribute-groups)
+    pub function_attribute_groups: Vec<FunctionAttributeGroup>,
+    /// The module-level inline assembly blocks, in order. Each entry is one
+    /// `module asm "..."` line of a `.ll` file, without a trailing newline.
     /// See [LLVM 14 docs on Module-Level Inline Assembly](https://releases.llvm.org/14.0.0/docs/LangRef.html#moduleasm)
-    pub inline_assembly: String,
-    // --TODO not yet implemented-- pub metadata_nodes: Vec<(MetadataNodeID, MetadataNode)>,
+    pub inline_assembly: Vec<String>,
+    /// The metadata nodes referred to by instructions' metadata operands, such
+    /// as the arguments to `llvm.dbg.value`, and by each other. A node's
+    /// `MetadataNodeID` is also its index in this `Vec`.
+    /// See [LLVM 14 docs on Metadata](https://releases.llvm.org/14.0.0/docs/LangRef.html#metadata)
+    pub metadata_nodes: Vec<(MetadataNodeID, MetadataNode)>,
+    /// The intrinsics which were declared in the bitcode, but which LLVM
+    /// auto-upgraded while reading it, because they are from an older LLVM
+    /// version: LLVM replaced each of them, and all calls to them, with newer
+    /// intrinsics (or other code). In the order they were declared in.
+    ///
+    /// This is only filled in if the `Module` was parsed with
+    /// [`ParseOptions::find_upgraded_intrinsics`](struct.ParseOptions.html#structfield.find_upgraded_intrinsics),
+    /// and is empty otherwise.
+    /// See also [`Function::declared_intrinsics()`](../function/struct.Function.html#method.declared_intrinsics).
+    pub upgraded_intrinsics: Vec<UpgradedIntrinsic>,
+    /// The compilers which produced the `Module`, from its `!llvm.ident`
+    /// metadata, in order. There is usually one; a `Module` linked from the
+    /// output of several compilers may have several, and one written by a
+    /// tool which doesn't set `!llvm.ident` has none.
+    pub producers: Vec<Producer>,
+    /// The version of LLVM which read the bitcode, i.e., the one `llvm-ir`
+    /// was configured for (e.g. `"14"`), or `None` if the `Module` was read
+    /// by the native reader.
+    /// See [`llvm_version_parsed_with()`](#method.llvm_version_parsed_with).
+    pub llvm_version: Option<String>,
     // --TODO not yet implemented-- pub named_metadatas: Vec<NamedMetadata>,
     // --TODO not yet implemented-- pub comdats: Vec<Comdat>,
     /// Holds a reference to all of the `Type`s used in the `Module`, and
@@ -37,35 +100,1055 @@ pub struct Module {
     pub types: Types,
 }
 
+// `Module` is documented to be `Send` and `Sync`; this fails to compile if a
+// change to it (or to anything it contains) breaks that
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Module>();
+};
+
 impl Module {
     /// Get the type of anything that is `Typed`.
     pub fn type_of<T: Typed + ?Sized>(&self, t: &T) -> TypeRef {
         self.types.type_of(t)
     }
 
+    /// Get the version of LLVM which read the bitcode for this `Module`, e.g.
+    /// `"14"`, or `None` if it was read by the native reader (see
+    /// [`from_bc_path_native()`](#method.from_bc_path_native)).
+    ///
+    /// For the version of the compiler which produced the bitcode, see
+    /// [`producers`](#structfield.producers).
+    pub fn llvm_version_parsed_with(&self) -> Option<&str> {
+        self.llvm_version.as_deref()
+    }
+
     /// Get the `Function` having the given `Name` (if any).
     /// Note that `Function`s are named with `String`s and not `Name`s.
     pub fn get_func_by_name(&self, name: &str) -> Option<&Function> {
         self.functions.iter().find(|func| func.name == name)
     }
 
+    /// Get the `Function` whose demangled name is the given `name` (if any).
+    /// See [`Function::demangled_name()`](../function/struct.Function.html#method.demangled_name).
+    #[cfg(any(feature = "rustc-demangle", feature = "cpp_demangle"))]
+    pub fn get_func_by_demangled_name(&self, name: &str) -> Option<&Function> {
+        self.functions
+            .iter()
+            .find(|func| func.demangled_name().as_deref() == Some(name))
+    }
+
     /// Get the `GlobalVariable` having the given `Name` (if any).
     pub fn get_global_var_by_name(&self, name: &Name) -> Option<&GlobalVariable> {
         self.global_vars.iter().find(|global| global.name == *name)
     }
 
+    /// Resolve the global with the given `Name` to the `Function` or
+    /// `GlobalVariable` it ultimately refers to: if it is a `GlobalAlias`,
+    /// follow its aliasee through any `bitcast`s and `addrspacecast`s, and
+    /// through any further aliases. A `Function` or `GlobalVariable` resolves
+    /// to itself.
+    ///
+    /// Returns `None` if there is no such global, if the chain ends at a
+    /// function which this `Module` only declares, if an aliasee is some
+    /// other constant expression (e.g., a `getelementptr` into a global), or
+    /// if the aliases form a cycle.
+    pub fn resolve_alias(&self, name: &Name) -> Option<Either<&Function, &GlobalVariable>> {
+        let mut name = name;
+        let mut seen: HashSet<&Name> = HashSet::new();
+        loop {
+            if let Some(func) = self
+                .functions
+                .iter()
+                .find(|func| name.as_bytes() == Some(func.name.as_bytes()))
+            {
+                return Some(Either::Left(func));
+            }
+            if let Some(var) = self.get_global_var_by_name(name) {
+                return Some(Either::Right(var));
+            }
+            let alias = self
+                .global_aliases
+                .iter()
+                .find(|alias| alias.name == *name)?;
+            if !seen.insert(&alias.name) {
+                return None;
+            }
+            let mut aliasee: &Constant = &alias.aliasee;
+            loop {
+                match aliasee {
+                    Constant::BitCast(bitcast) => aliasee = &bitcast.operand,
+                    Constant::AddrSpaceCast(cast) => aliasee = &cast.operand,
+                    Constant::GlobalReference { name: target, .. } => {
+                        name = target;
+                        break;
+                    },
+                    _ => return None,
+                }
+            }
+        }
+    }
+
+    /// Add the given `Function` to this `Module`. Its `TypeRef`s should be
+    /// from this `Module`'s `types`.
+    ///
+    /// Fails if the `Module` already has a function, global variable, or alias
+    /// with the same name. The function's type is interned in `types`, so that
+    /// `GlobalReference`s to the function can be made, and its
+    /// `function_attribute_group` is set to the group in
+    /// `function_attribute_groups` with exactly its `function_attributes`
+    /// (which is added if there is no such group).
+    pub fn add_function(&mut self, mut func: Function) -> Result<(), EditError> {
+        self.check_name_available(&Name::from(func.name.as_str()))?;
+        self.types.type_of(&func);
+        func.function_attribute_group = FunctionAttributeGroup::find_or_insert(
+            &mut self.function_attribute_groups,
+            &func.function_attributes,
+        );
+        self.functions.push(func);
+        Ok(())
+    }
+
+    /// Remove the `Function` with the given name from this `Module`, and
+    /// return it.
+    ///
+    /// Unless `force` is `true`, fails if any other function, global variable,
+    /// or alias refers to the function; see
+    /// [`global_referrers()`](#method.global_referrers). If `force` is `true`,
+    /// those references are left as they are, so they no longer refer to
+    /// anything defined in the `Module`.
+    pub fn remove_function(&mut self, name: &str, force: bool) -> Result<Function, EditError> {
+        let global_name = Name::from(name);
+        let index = self
+            .functions
+            .iter()
+            .position(|func| func.name == name)
+            .ok_or_else(|| EditError::NotFound(global_name.clone()))?;
+        self.check_removable(&global_name, force)?;
+        Ok(self.functions.remove(index))
+    }
+
+    /// Add a copy of the `Function` named `old_name` to this `Module`, named
+    /// `new_name`, and return the copy, e.g., to specialize it.
+    ///
+    /// Calls within the copy to the original function (i.e., recursive calls)
+    /// call the copy instead. Calls elsewhere in the `Module` are left as they
+    /// are; use [`redirect_calls()`](#method.redirect_calls) to change them.
+    /// Otherwise, including its linkage and attributes, the copy is the same
+    /// as the original.
+    ///
+    /// Fails if the `Module` has no function named `old_name`, or already has
+    /// a function, global variable, or alias named `new_name`.
+    pub fn clone_function(
+        &mut self,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<&mut Function, EditError> {
+        let (old_global_name, new_global_name) = (Name::from(old_name), Name::from(new_name));
+        let mut func = self
+            .get_func_by_name(old_name)
+            .ok_or_else(|| EditError::NotFound(old_global_name.clone()))?
+            .clone();
+        self.check_name_available(&new_global_name)?;
+        func.name = new_name.to_owned();
+        redirect_calls_in(&mut func, &old_global_name, &new_global_name);
+        self.functions.push(func);
+        Ok(self.functions.last_mut().expect("just pushed a function"))
+    }
+
+    /// Make every call (or invoke) in this `Module` which directly calls the
+    /// function named `from` call the function named `to` instead, and return
+    /// the number of calls changed. The two functions should have the same
+    /// type.
+    ///
+    /// Other references to `from`, such as taking its address, are left as
+    /// they are.
+    pub fn redirect_calls(&mut self, from: &str, to: &str) -> usize {
+        let (from, to) = (Name::from(from), Name::from(to));
+        self.functions
+            .iter_mut()
+            .map(|func| redirect_calls_in(func, &from, &to))
+            .sum()
+    }
+
+    /// Add the given `GlobalVariable` to this `Module`. Its `TypeRef`s should
+    /// be from this `Module`'s `types`.
+    ///
+    /// Fails if the `Module` already has a function, global variable, or alias
+    /// with the same name.
+    pub fn add_global_var(&mut self, var: GlobalVariable) -> Result<(), EditError> {
+        self.check_name_available(&var.name)?;
+        self.global_vars.push(var);
+        Ok(())
+    }
+
+    /// Remove the `GlobalVariable` with the given name from this `Module`, and
+    /// return it.
+    ///
+    /// As for [`remove_function()`](#method.remove_function), unless `force`
+    /// is `true`, fails if anything else in the `Module` refers to it.
+    pub fn remove_global_var(
+        &mut self,
+        name: &Name,
+        force: bool,
+    ) -> Result<GlobalVariable, EditError> {
+        let index = self
+            .global_vars
+            .iter()
+            .position(|var| var.name == *name)
+            .ok_or_else(|| EditError::NotFound(name.clone()))?;
+        self.check_removable(name, force)?;
+        Ok(self.global_vars.remove(index))
+    }
+
+    /// Add the given `GlobalAlias` to this `Module`. Its `TypeRef`s should be
+    /// from this `Module`'s `types`.
+    ///
+    /// Fails if the `Module` already has a function, global variable, or alias
+    /// with the same name.
+    pub fn add_global_alias(&mut self, alias: GlobalAlias) -> Result<(), EditError> {
+        self.check_name_available(&alias.name)?;
+        self.global_aliases.push(alias);
+        Ok(())
+    }
+
+    /// Remove the `GlobalAlias` with the given name from this `Module`, and
+    /// return it.
+    ///
+    /// As for [`remove_function()`](#method.remove_function), unless `force`
+    /// is `true`, fails if anything else in the `Module` refers to it.
+    pub fn remove_global_alias(
+        &mut self,
+        name: &Name,
+        force: bool,
+    ) -> Result<GlobalAlias, EditError> {
+        let index = self
+            .global_aliases
+            .iter()
+            .position(|alias| alias.name == *name)
+            .ok_or_else(|| EditError::NotFound(name.clone()))?;
+        self.check_removable(name, force)?;
+        Ok(self.global_aliases.remove(index))
+    }
+
+    /// Get the names of the functions, global variables, and aliases in this
+    /// `Module` which refer to the function, global variable, or alias with
+    /// the given name: through their instructions (or personality function),
+    /// initializer, or aliasee, respectively. Functions are listed first, then
+    /// global variables, then aliases, each in `Module` order.
+    ///
+    /// This includes the given global value itself if it refers to itself,
+    /// e.g., a recursive function.
+    pub fn global_referrers(&self, name: &Name) -> Vec<Name> {
+        let funcs = self
+            .functions
+            .iter()
+            .filter(|func| function_refers_to(func, name))
+            .map(|func| Name::from(func.name.as_str()));
+        let vars = self
+            .global_vars
+            .iter()
+            .filter(|var| {
+                var.initializer
+                    .as_ref()
+                    .is_some_and(|init| constant_refers_to(init, name))
+            })
+            .map(|var| var.name.clone());
+        let aliases = self
+            .global_aliases
+            .iter()
+            .filter(|alias| constant_refers_to(&alias.aliasee, name))
+            .map(|alias| alias.name.clone());
+        funcs.chain(vars).chain(aliases).collect()
+    }
+
+    /// Extract the functions with the given names, and everything they
+    /// depend on, into a new `Module`, e.g., to reduce a test case or to
+    /// analyze those functions in isolation.
+    ///
+    /// The new `Module` contains:
+    ///
+    ///   - the `roots`, with their bodies;
+    ///   - the global variables (with their initializers) and aliases which
+    ///     they refer to, directly or through other global variables and
+    ///     aliases;
+    ///   - the functions which those aliases alias (with their bodies, since
+    ///     an alias can't alias a declaration), and likewise their
+    ///     dependencies;
+    ///   - the definitions of the named struct types which all of the above
+    ///     use.
+    ///
+    /// Other functions which they refer to, e.g., functions the `roots` call,
+    /// are only declared in the new `Module`: as with the functions which
+    /// this `Module` only declares, they aren't in its `functions`, but
+    /// references to them are kept. Functions, global variables, and aliases
+    /// are in the same order as in this `Module`. Everything else (e.g., the
+    /// data layout, inline assembly, attribute groups, and metadata nodes) is
+    /// copied from this `Module` as it is.
+    ///
+    /// Panics if any of the `roots` isn't the name of a function defined in
+    /// this `Module`.
+    pub fn extract_slice(&self, roots: &[&str]) -> Module {
+        let funcs: HashMap<&[u8], &Function> = self
+            .functions
+            .iter()
+            .map(|func| (func.name.as_bytes(), func))
+            .collect();
+        let vars: HashMap<&Name, &GlobalVariable> = self
+            .global_vars
+            .iter()
+            .map(|var| (&var.name, var))
+            .collect();
+        let aliases: HashMap<&Name, &GlobalAlias> = self
+            .global_aliases
+            .iter()
+            .map(|alias| (&alias.name, alias))
+            .collect();
+
+        let root_names: Vec<Name> = roots
+            .iter()
+            .map(|&root| {
+                assert!(
+                    self.get_func_by_name(root).is_some(),
+                    "extract_slice: no function named {:?} is defined in the module",
+                    root
+                );
+                Name::from(root)
+            })
+            .collect();
+        // each entry is a global which the slice refers to, and whether it
+        // needs its body if it is a function
+        let mut worklist: Vec<(&Name, bool)> = root_names.iter().map(|root| (root, true)).collect();
+        let mut kept: HashSet<&Name> = HashSet::new();
+        while let Some((name, needs_body)) = worklist.pop() {
+            let mut refs = vec![];
+            let func = name.as_bytes().and_then(|bytes| funcs.get(bytes));
+            let refs_need_bodies = if let Some(func) = func {
+                if !needs_body || !kept.insert(name) {
+                    continue;
+                }
+                function_global_refs(func, &mut refs);
+                false
+            } else if let Some(var) = vars.get(name) {
+                if !kept.insert(name) {
+                    continue;
+                }
+                if let Some(init) = &var.initializer {
+                    constant_global_refs(init, &mut refs);
+                }
+                false
+            } else if let Some(alias) = aliases.get(name) {
+                if !kept.insert(name) {
+                    continue;
+                }
+                constant_global_refs(&alias.aliasee, &mut refs);
+                true
+            } else {
+                // only declared in this `Module`
+                continue;
+            };
+            worklist.extend(refs.into_iter().map(|name| (name, refs_need_bodies)));
+        }
+
+        let functions: Vec<Function> = self
+            .functions
+            .iter()
+            .filter(|func| kept.contains(&Name::from(func.name.as_str())))
+            .cloned()
+            .collect();
+        let global_vars: Vec<GlobalVariable> = self
+            .global_vars
+            .iter()
+            .filter(|var| kept.contains(&var.name))
+            .cloned()
+            .collect();
+        let global_aliases: Vec<GlobalAlias> = self
+            .global_aliases
+            .iter()
+            .filter(|alias| kept.contains(&alias.name))
+            .cloned()
+            .collect();
+
+        let mut struct_names = NamedStructs::new(&self.types);
+        for func in &functions {
+            struct_names.add_function(func);
+        }
+        for var in &global_vars {
+            struct_names.add_type(&var.ty);
+            if let Some(init) = &var.initializer {
+                struct_names.add_constant(init);
+            }
+        }
+        for alias in &global_aliases {
+            struct_names.add_type(&alias.ty);
+            struct_names.add_constant(&alias.aliasee);
+        }
+        let mut types = self.types.clone();
+        let unused: Vec<String> = self
+            .types
+            .all_struct_names()
+            .filter(|name| !struct_names.names.contains(*name))
+            .cloned()
+            .collect();
+        for name in unused {
+            types.remove_named_struct_def(&name);
+        }
+
+        Module {
+            name: self.name.clone(),
+            source_file_name: self.source_file_name.clone(),
+            data_layout: self.data_layout.clone(),
+            target_triple: self.target_triple.clone(),
+            functions,
+            global_vars,
+            global_aliases,
+            function_attribute_groups: self.function_attribute_groups.clone(),
+            inline_assembly: self.inline_assembly.clone(),
+            metadata_nodes: self.metadata_nodes.clone(),
+            upgraded_intrinsics: self.upgraded_intrinsics.clone(),
+            producers: self.producers.clone(),
+            llvm_version: self.llvm_version.clone(),
+            types,
+        }
+    }
+
+    fn check_name_available(&self, name: &Name) -> Result<(), EditError> {
+        let bytes = name.as_bytes();
+        let taken = self
+            .functions
+            .iter()
+            .any(|func| bytes == Some(func.name.as_bytes()))
+            || self.global_vars.iter().any(|var| var.name == *name)
+            || self.global_aliases.iter().any(|alias| alias.name == *name);
+        if taken {
+            Err(EditError::NameTaken(name.clone()))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn check_removable(&self, name: &Name, force: bool) -> Result<(), EditError> {
+        if force {
+            return Ok(());
+        }
+        let referrers: Vec<Name> = self
+            .global_referrers(name)
+            .into_iter()
+            .filter(|referrer| referrer != name)
+            .collect();
+        if referrers.is_empty() {
+            Ok(())
+        } else {
+            Err(EditError::StillReferenced {
+                name: name.clone(),
+                referrers,
+            })
+        }
+    }
+
+    /// Get the `BasicBlock` identified by the given `ModuleLoc`, or `None` if
+    /// there is no such block in this `Module`.
+    pub fn get_block(&self, loc: ModuleLoc) -> Option<&BasicBlock> {
+        self.functions.get(loc.func)?.basic_blocks.get(loc.block)
+    }
+
+    /// Get the `Instruction` or `Terminator` identified by the given
+    /// `InstrLoc`, or `None` if there is no such instruction in this `Module`.
+    pub fn get_instr(&self, loc: InstrLoc) -> Option<InstrRef<'_>> {
+        let bb = self.get_block(loc.block)?;
+        match loc.pos {
+            InstrPos::Instr(index) => bb.instrs.get(index).map(InstrRef::Instr),
+            InstrPos::Terminator => Some(InstrRef::Terminator(&bb.term)),
+        }
+    }
+
+    /// Get the `ModuleLoc` of the block with the given `Name` in the function
+    /// with the given name (if any).
+    pub fn locate_block(&self, func_name: &str, block_name: &Name) -> Option<ModuleLoc> {
+        let func = self.functions.iter().position(|f| f.name == func_name)?;
+        let block = self.functions[func].block_position(block_name)?;
+        Some(ModuleLoc::new(func, block))
+    }
+
+    /// Iterate over the `InstrLoc`s of all the instructions and terminators in
+    /// this `Module`, in order.
+    pub fn instr_locs(&self) -> impl Iterator<Item = InstrLoc> + '_ {
+        self.functions.iter().enumerate().flat_map(|(func, f)| {
+            f.basic_blocks
+                .iter()
+                .enumerate()
+                .flat_map(move |(block, bb)| {
+                    let loc = ModuleLoc::new(func, block);
+                    (0 .. bb.instrs.len())
+                        .map(move |index| loc.instr(index))
+                        .chain(std::iter::once(loc.terminator()))
+                })
+        })
+    }
+
+    /// Iterate over all the `Instruction`s in this `Module`, in order, each
+    /// with the `Function` and `BasicBlock` containing it.
+    pub fn all_instructions(
+        &self,
+    ) -> impl Iterator<Item = (&Function, &BasicBlock, &Instruction)> + '_ {
+        self.functions.iter().flat_map(|f| {
+            f.basic_blocks
+                .iter()
+                .flat_map(move |bb| bb.instrs.iter().map(move |inst| (f, bb, inst)))
+        })
+    }
+
+    /// Iterate over all the `Terminator`s in this `Module`, in order, each
+    /// with the `Function` and `BasicBlock` containing it.
+    pub fn all_terminators(
+        &self,
+    ) -> impl Iterator<Item = (&Function, &BasicBlock, &Terminator)> + '_ {
+        self.functions
+            .iter()
+            .flat_map(|f| f.basic_blocks.iter().map(move |bb| (f, bb, &bb.term)))
+    }
+
+    /// Build a [`SourceMap`](../debugloc/struct.SourceMap.html) indexing the
+    /// instructions and functions in this `Module` by the source lines of
+    /// their `DebugLoc`s.
+    ///
+    /// The `Module` must have been compiled with debuginfo for this to be
+    /// useful; otherwise the `SourceMap` will be empty.
+    #[cfg(feature = "llvm-9-or-greater")]
+    pub fn source_map(&self) -> SourceMap {
+        SourceMap::new(self)
+    }
+
+    /// Group the functions in this `Module` by the source file they are
+    /// defined in, i.e., the `filename` of their `DebugLoc`s. Each file's
+    /// functions are in the order they appear in the `Module`. Functions
+    /// without a `DebugLoc` are left out.
+    ///
+    /// A function's source file may differ from the main source file of its
+    /// [`Subprogram`](../debugloc/struct.Subprogram.html)'s compile unit,
+    /// e.g., for a function defined in a header.
+    #[cfg(feature = "llvm-9-or-greater")]
+    pub fn functions_by_source_file(&self) -> BTreeMap<&str, Vec<&Function>> {
+        let mut files: BTreeMap<&str, Vec<&Function>> = BTreeMap::new();
+        for func in &self.functions {
+            if let Some(debugloc) = &func.debugloc {
+                files.entry(&debugloc.filename).or_default().push(func);
+            }
+        }
+        files
+    }
+
+    /// Get the entries of the `llvm.global_ctors` array: the functions to be
+    /// called when the module is loaded. Each entry is its priority, the name
+    /// of the function, and the name of the global its data field refers to
+    /// (if any; see [LLVM 14 docs on `llvm.global_ctors`](https://releases.llvm.org/14.0.0/docs/LangRef.html#the-llvm-global-ctors-global-variable)).
+    ///
+    /// Entries are returned in the order they appear in the array. Entries
+    /// which don't have the expected shape are skipped.
+    pub fn global_ctors(&self) -> Vec<(u32, Name, Option<Name>)> {
+        self.structors("llvm.global_ctors")
+    }
+
+    /// Get the entries of the `llvm.global_dtors` array: the functions to be
+    /// called when the module is unloaded. Entries have the same form as in
+    /// [`global_ctors()`](#method.global_ctors).
+    pub fn global_dtors(&self) -> Vec<(u32, Name, Option<Name>)> {
+        self.structors("llvm.global_dtors")
+    }
+
+    /// Get the globals listed in the `llvm.used` array, which the compiler,
+    /// assembler, and linker must all treat as used, even if there are no
+    /// other references to them (see [LLVM 14 docs on `llvm.used`](https://releases.llvm.org/14.0.0/docs/LangRef.html#the-llvm-used-global-variable)).
+    ///
+    /// Names are returned in the order they appear in the array. Entries
+    /// which don't refer to a global are skipped.
+    pub fn used_globals(&self) -> Vec<Name> {
+        self.used_list("llvm.used")
+    }
+
+    /// Get the globals listed in the `llvm.compiler.used` array, which the
+    /// compiler must treat as used, but which the linker may still remove
+    /// (see [LLVM 14 docs on `llvm.compiler.used`](https://releases.llvm.org/14.0.0/docs/LangRef.html#the-llvm-compiler-used-global-variable)).
+    /// Names are returned as in [`used_globals()`](#method.used_globals).
+    pub fn compiler_used_globals(&self) -> Vec<Name> {
+        self.used_list("llvm.compiler.used")
+    }
+
+    /// Get the string literals in this `Module`: the constant global
+    /// variables whose initializer is an array of `i8`, whether represented
+    /// as a `Constant::RawDataArray`, a `Constant::Array`, or a
+    /// `Constant::AggregateZero`.
+    ///
+    /// Each item is the name of the global, its contents decoded as UTF-8
+    /// (replacing invalid sequences with U+FFFD), and whether the array ends
+    /// with a null byte. The final null byte, if any, is not included in the
+    /// decoded string; any other null bytes are.
+    pub fn string_literals(&self) -> impl Iterator<Item = (&Name, String, bool)> + '_ {
+        self.global_vars
+            .iter()
+            .filter(|var| var.is_constant)
+            .filter_map(|var| {
+                let mut bytes = byte_array_contents(var.initializer.as_ref()?)?;
+                let null_terminated = bytes.last() == Some(&0);
+                if null_terminated {
+                    bytes.pop();
+                }
+                Some((
+                    &var.name,
+                    String::from_utf8_lossy(&bytes).into_owned(),
+                    null_terminated,
+                ))
+            })
+    }
+
+    /// Interpret the initializer of `llvm.used` or `llvm.compiler.used`
+    fn used_list(&self, array_name: &str) -> Vec<Name> {
+        match self
+            .get_global_var_by_name(&Name::from(array_name))
+            .and_then(|global| global.initializer.as_ref())
+            .map(|initializer| initializer.as_ref())
+        {
+            Some(Constant::Array { elements, .. }) => elements
+                .iter()
+                .filter_map(referenced_global)
+                .cloned()
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Interpret the initializer of `llvm.global_ctors` or `llvm.global_dtors`
+    fn structors(&self, array_name: &str) -> Vec<(u32, Name, Option<Name>)> {
+        let initializer = match self
+            .get_global_var_by_name(&Name::from(array_name))
+            .and_then(|global| global.initializer.as_ref())
+        {
+            Some(initializer) => initializer,
+            None => return vec![],
+        };
+        let elements = match initializer.as_ref() {
+            Constant::Array { elements, .. } => elements,
+            _ => return vec![],
+        };
+        elements
+            .iter()
+            .filter_map(|element| match element.as_ref() {
+                Constant::Struct { values, .. } if values.len() >= 2 => {
+                    let priority = match values[0].as_ref() {
+                        Constant::Int { value, .. } => u32::try_from(*value).ok()?,
+                        _ => return None,
+                    };
+                    let func = referenced_global(&values[1])?.clone();
+                    let data = values.get(2).and_then(referenced_global).cloned();
+                    Some((priority, func, data))
+                },
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Append module-level inline assembly to this `Module`. As in LLVM,
+    /// `asm` containing newlines is split into one block per line.
+    pub fn append_inline_asm(&mut self, asm: &str) {
+        self.inline_assembly.extend(split_inline_asm(asm));
+    }
+
+    /// Remove all of the module-level inline assembly blocks which are exactly
+    /// `asm`. Returns `true` if any were removed.
+    pub fn remove_inline_asm(&mut self, asm: &str) -> bool {
+        let len = self.inline_assembly.len();
+        self.inline_assembly.retain(|block| block != asm);
+        self.inline_assembly.len() != len
+    }
+
+    /// Get all of the module-level inline assembly as a single string, as
+    /// LLVM stores it: each block followed by a newline.
+    pub fn inline_asm_string(&self) -> String {
+        self.inline_assembly
+            .iter()
+            .map(|block| format!("{}\n", block))
+            .collect()
+    }
+
+    /// Renumber the local values and basic blocks of each `Function` in this
+    /// `Module`, in place.
+    /// See [`Function::rename_locals()`](../function/struct.Function.html#method.rename_locals).
+    pub fn rename_locals(&mut self, drop_names: bool) {
+        for func in &mut self.functions {
+            func.rename_locals(drop_names);
+        }
+    }
+
+    /// Remove all debug information from this `Module` in place: the
+    /// `DebugLoc`s of its functions, global variables, instructions, and
+    /// terminators, and any calls to debug intrinsics such as
+    /// `llvm.dbg.value`.
+    /// See [`Function::strip_debug_info()`](../function/struct.Function.html#method.strip_debug_info).
+    pub fn strip_debug_info(&mut self) {
+        for func in &mut self.functions {
+            func.strip_debug_info();
+        }
+        #[cfg(feature = "llvm-9-or-greater")]
+        for global in &mut self.global_vars {
+            global.debugloc = None;
+        }
+    }
+
+    /// Give every unnamed local value and basic block in this `Module`'s
+    /// functions a deterministic, human-readable string name, e.g., so that
+    /// names in diffs and error messages are easier to follow.
+    ///
+    /// A local which is the value or address of a source variable in a call
+    /// to `llvm.dbg.declare`, `llvm.dbg.value`, or `llvm.dbg.addr` is named
+    /// after the variable. Otherwise, unnamed parameters are named `%arg<N>`,
+    /// basic blocks `%bb<N>`, and other values `%v<N>`, where `<N>` is the
+    /// local's number (so `%3` becomes `%v3` or `%bb3`). If a name is already
+    /// taken in the function, a number is appended to make it unique, as in
+    /// LLVM. Locals which already have string names keep them.
+    ///
+    /// See also [`ParseOptions::name_unnamed_locals`](struct.ParseOptions.html#structfield.name_unnamed_locals).
+    pub fn name_unnamed_locals(&mut self) {
+        for func in &mut self.functions {
+            let debug_names = debug_variable_names(func, &self.metadata_nodes);
+            func.name_unnamed_locals(&debug_names);
+        }
+    }
+
+    /// Compare this `Module` with `other`, e.g., a version of it after some
+    /// optimization pass: which functions and globals were added, removed, or
+    /// changed, and which basic blocks of each changed function.
+    ///
+    /// The comparison is structural, and ignores debug information and how
+    /// unnamed locals are numbered.
+    pub fn diff(&self, other: &Module) -> ModuleDiff {
+        ModuleDiff::new(self, other)
+    }
+
+    /// Write this `Module` as text, in a syntax close to LLVM's `.ll` format,
+    /// in the `Module`'s own order.
+    /// See the [`text`](../text/index.html) module.
+    pub fn to_text(&self) -> String {
+        self.to_text_with_options(&TextOptions {
+            module_id: true,
+            ..TextOptions::default()
+        })
+    }
+
+    /// Write this `Module` as text in a stable, deterministic form, e.g., for
+    /// golden tests of analysis or transform output.
+    /// See [`TextOptions::canonical()`](../text/struct.TextOptions.html#method.canonical).
+    pub fn to_canonical_text(&self) -> String {
+        self.to_text_with_options(&TextOptions::canonical())
+    }
+
+    /// Write this `Module` as text, as configured by the given `TextOptions`.
+    pub fn to_text_with_options(&self, options: &TextOptions) -> String {
+        text::write_module(self, options)
+    }
+
+    /// Estimate how much memory this `Module` uses, broken down by category.
+    ///
+    /// This is an estimate, not an exact count: it is based on the sizes of
+    /// the Rust data structures and the capacities of their heap allocations,
+    /// and doesn't account for allocator overhead. `Constant`s shared between
+    /// several uses are only counted once.
+    pub fn memory_footprint(&self) -> MemoryFootprint {
+        let mut footprint = MemoryFootprint {
+            types: self.types.heap_size(),
+            metadata: self.metadata_nodes.capacity() * size_of::<(MetadataNodeID, MetadataNode)>()
+                + self
+                    .metadata_nodes
+                    .iter()
+                    .map(|(_, node)| metadata_node_heap_size(node))
+                    .sum::<usize>(),
+            ..MemoryFootprint::default()
+        };
+        let mut constants = ConstantsFootprint::default();
+        let mut seen_operands = HashSet::new();
+        for func in &self.functions {
+            footprint.names += func.name.capacity();
+            footprint.names += func
+                .parameters
+                .iter()
+                .map(|p| p.name.heap_size())
+                .sum::<usize>();
+            if let Some(personality) = &func.personality_function {
+                constants.add(personality);
+            }
+            #[cfg(feature = "llvm-9-or-greater")]
+            {
+                footprint.debug_locs += debugloc_heap_size(&func.debugloc);
+                footprint.debug_locs += subprogram_heap_size(&func.subprogram);
+            }
+            footprint.instructions += func.basic_blocks.capacity() * size_of::<BasicBlock>();
+            for bb in &func.basic_blocks {
+                footprint.names += bb.name.heap_size();
+                footprint.instructions += bb.instrs.capacity() * size_of::<Instruction>();
+                for inst in &bb.instrs {
+                    footprint.instructions += instruction_heap_size(inst);
+                    footprint.names += inst.try_get_result().map_or(0, Name::heap_size);
+                    if let Instruction::Phi(phi) = inst {
+                        footprint.names += phi
+                            .incoming_values
+                            .iter()
+                            .map(|(_, block)| block.heap_size())
+                            .sum::<usize>();
+                    }
+                    #[cfg(feature = "llvm-9-or-greater")]
+                    {
+                        footprint.debug_locs += debugloc_heap_size(inst.get_debug_loc());
+                    }
+                    footprint.add_operands(inst.operands(), &mut seen_operands, &mut constants);
+                }
+                footprint.instructions += terminator_heap_size(&bb.term);
+                footprint.names += bb.term.try_get_result().map_or(0, Name::heap_size);
+                footprint.names += bb.term.successors().map(Name::heap_size).sum::<usize>();
+                if let Terminator::Switch(switch) = &bb.term {
+                    for (value, _) in &switch.dests {
+                        constants.add(value);
+                    }
+                }
+                #[cfg(feature = "llvm-9-or-greater")]
+                {
+                    footprint.debug_locs += debugloc_heap_size(bb.term.get_debug_loc());
+                }
+                footprint.add_operands(bb.term.operands(), &mut seen_operands, &mut constants);
+            }
+        }
+        for var in &self.global_vars {
+            footprint.names += var.name.heap_size();
+            if let Some(init) = &var.initializer {
+                constants.add(init);
+            }
+            #[cfg(feature = "llvm-9-or-greater")]
+            {
+                footprint.debug_locs += debugloc_heap_size(&var.debugloc);
+            }
+        }
+        for alias in &self.global_aliases {
+            footprint.names += alias.name.heap_size();
+            constants.add(&alias.aliasee);
+        }
+        footprint.constants = constants.bytes;
+        footprint
+    }
+
+    /// Write the control-flow graph of each `Function` in this `Module` to
+    /// `<dir>/<function name>.dot`, in the Graphviz DOT format.
+    /// See [`Function::to_dot()`](../function/struct.Function.html#method.to_dot).
+    ///
+    /// `dir` is created if it does not already exist. Characters in function
+    /// names which aren't safe in filenames are replaced with `_`. If that
+    /// makes the filenames of several functions the same (ignoring case, for
+    /// case-insensitive filesystems), the later functions get the filename
+    /// `<dir>/<function name>.<n>.dot` instead, for the smallest `n` (starting
+    /// from 1) which doesn't clash with another filename.
+    pub fn cfgs_to_dot_dir(&self, dir: impl AsRef<Path>) -> std::io::Result<()> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        let mut used_filenames = HashSet::new();
+        for func in &self.functions {
+            let sanitized: String = func
+                .name
+                .chars()
+                .map(|c| match c {
+                    'a' ..= 'z' | 'A' ..= 'Z' | '0' ..= '9' | '_' | '-' | '.' | '$' => c,
+                    _ => '_',
+                })
+                .collect();
+            let mut filename = sanitized.clone();
+            let mut index = 0;
+            while !used_filenames.insert(filename.to_lowercase()) {
+                index += 1;
+                filename = format!("{}.{}", sanitized, index);
+            }
+            std::fs::write(dir.join(filename + ".dot"), func.to_dot())?;
+        }
+        Ok(())
+    }
+
     /// Parse the LLVM bitcode (.bc) file at the given path to create a `Module`
-    pub fn from_bc_path(path: impl AsRef<Path>) -> Result<Self, String> {
+    pub fn from_bc_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::parse_bc_path(path, false, None, &[], &ParseOptions::default())
+    }
+
+    /// Parse the LLVM bitcode (.bc) file at the given path to create a
+    /// `Module`, as configured by the given `ParseOptions`.
+    /// See [`ParseOptions`](struct.ParseOptions.html).
+    pub fn from_bc_path_with_options(
+        path: impl AsRef<Path>,
+        options: &ParseOptions,
+    ) -> Result<Self, Error> {
+        Self::parse_bc_path(path, false, None, &[], options)
+    }
+
+    /// Parse the LLVM bitcode (.bc) file at the given path to create a
+    /// `Module`, sharing `TypeRef`s with the other `Module`s parsed in the
+    /// given `Context`.
+    /// See [`Context`](../context/struct.Context.html).
+    pub fn from_bc_path_in(context: &Context, path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::parse_bc_path(path, false, Some(context), &[], &ParseOptions::default())
+    }
+
+    /// Parse the LLVM bitcode (.bc) file at the given path to create a
+    /// `Module`, first checking it with LLVM's module verifier.
+    ///
+    /// If the verifier finds the bitcode to be malformed, this returns
+    /// `Error::VerificationFailed` with the verifier's error message, rather
+    /// than (potentially) failing partway through the conversion with a less
+    /// helpful error as `from_bc_path()` might.
+    pub fn from_bc_path_verified(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::parse_bc_path(path, true, None, &[], &ParseOptions::default())
+    }
+
+    /// Parse the LLVM bitcode (.bc) file at the given path, run the given LLVM
+    /// passes on it, and then create a `Module` from the result; e.g., to get
+    /// IR in SSA form with `&["mem2reg"]`, or with all locals named with
+    /// `&["instnamer"]`, without running `opt` first.
+    ///
+    /// On LLVM 13+, the passes are run with LLVM's new pass manager, and each
+    /// entry of `passes` can be anything `opt -passes=...` accepts, including
+    /// nested pipelines such as `"function(mem2reg,instcombine)"`. On older
+    /// LLVM versions, they are run with the legacy pass manager, and only the
+    /// following passes (which the LLVM C API provides) are available:
+    /// `adce`, `always-inline`, `dse`, `early-cse`, `globaldce`, `globalopt`,
+    /// `gvn`, `inline`, `instcombine`, `licm`, `loop-rotate`, `lowerswitch`,
+    /// `mem2reg`, `reassociate`, `simplifycfg`, `sroa`,
+    /// `strip-dead-prototypes`, and `tailcallelim`.
+    ///
+    /// Fails with `Error::PassesFailed` if a pass isn't recognized.
+    pub fn from_bc_path_with_passes(
+        path: impl AsRef<Path>,
+        passes: &[&str],
+    ) -> Result<Self, Error> {
+        Self::parse_bc_path(path, false, None, passes, &ParseOptions::default())
+    }
+
+    /// Parse the LLVM bitcode (.bc) file at the given path to create a
+    /// `Module`, using the bitcode reader written in Rust rather than LLVM's.
+    ///
+    /// The native reader supports only a subset of bitcode: modules written by
+    /// LLVM 5 or later, with global variables, aliases, and functions using
+    /// the common instructions and terminators (no exception handling,
+    /// atomics, vector shuffles, or inline assembly calls). It doesn't read
+    /// attributes, metadata, or debug locations, so the resulting `Module`
+    /// has none. Bitcode outside the subset gives `Error::Unsupported`.
+    ///
+    /// Requires the `native-reader` feature.
+    #[cfg(feature = "native-reader")]
+    pub fn from_bc_path_native(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let bitcode = std::fs::read(path).map_err(|e| Error::ReadFailed(e.to_string()))?;
+        let name = path.to_string_lossy().into_owned();
+        crate::error::catch_panic(|| crate::native_reader::read_module(&bitcode, name))
+    }
+
+    /// Like [`from_bc_path_native()`](#method.from_bc_path_native), but for
+    /// bitcode which is already in memory. The `Module`'s `name` is empty.
+    ///
+    /// Requires the `native-reader` feature.
+    #[cfg(feature = "native-reader")]
+    pub fn from_bc_bytes_native(bitcode: &[u8]) -> Result<Self, Error> {
+        crate::error::catch_panic(|| crate::native_reader::read_module(bitcode, String::new()))
+    }
+
+    /// Parse each of the LLVM bitcode files in the static archive (`.a`) at
+    /// the given path, such as a library built with `-flto`, to create a
+    /// `Module` for each, in the order they appear in the archive.
+    ///
+    /// Members which aren't bitcode (for instance, native object files in an
+    /// archive which mixes the two) are skipped. Members in a bitcode wrapper
+    /// header, as produced for Apple platforms, are unwrapped. Each `Module`'s
+    /// `name` is `<path>(<member name>)`, as in LLVM's tools.
+    pub fn from_archive_path(path: impl AsRef<Path>) -> Result<Vec<Self>, Error> {
+        let path = path.as_ref();
+        let data = std::fs::read(path).map_err(|e| Error::ReadFailed(e.to_string()))?;
+        crate::archive::members(&data)?
+            .into_iter()
+            .filter(|member| crate::bitstream::is_bitcode(member.data))
+            .map(|member| {
+                let name = format!("{}({})", path.display(), member.name);
+                Self::parse_bc_bytes(member.data, &name)
+            })
+            .collect()
+    }
+
+    /// Parse the LLVM bitcode embedded in the object file at the given path,
+    /// as by `-fembed-bitcode`, to create a `Module` for each embedded module.
+    ///
+    /// The bitcode is read from the `__LLVM,__bitcode` section of Mach-O
+    /// files, or the `.llvmbc` section of ELF and COFF files. If several
+    /// objects with embedded bitcode were linked together (e.g., with
+    /// `ld -r`), the section contains several modules, which are returned in
+    /// order. Returns an empty `Vec` if the object file has no embedded
+    /// bitcode, or only the placeholder of `-fembed-bitcode=marker`. Each
+    /// `Module`'s `name` is the path of the object file.
+    ///
+    /// Requires the `embedded-bitcode` feature.
+    #[cfg(feature = "embedded-bitcode")]
+    pub fn from_object_with_embedded_bc(path: impl AsRef<Path>) -> Result<Vec<Self>, Error> {
+        use object::{Object, ObjectSection};
+
+        let path = path.as_ref();
+        let data = std::fs::read(path).map_err(|e| Error::ReadFailed(e.to_string()))?;
+        let file = object::File::parse(&*data)
+            .map_err(|e| Error::ReadFailed(format!("failed to parse object file: {}", e)))?;
+        let name = path.to_string_lossy();
+        let mut modules = vec![];
+        for section in file.sections() {
+            let is_bitcode_section = match section.name() {
+                Ok(".llvmbc") => true,
+                Ok("__bitcode") => matches!(section.segment_name(), Ok(Some("__LLVM"))),
+                _ => false,
+            };
+            if !is_bitcode_section {
+                continue;
+            }
+            let contents = section
+                .data()
+                .map_err(|e| Error::ReadFailed(format!("failed to read section: {}", e)))?;
+            for bitcode in crate::bitstream::split_bitcode_files(contents)? {
+                modules.push(Self::parse_bc_bytes(bitcode, &name)?);
+            }
+        }
+        Ok(modules)
+    }
+
+    /// Parse the given LLVM bitcode, which may be wrapped in a bitcode wrapper
+    /// header, to create a `Module` with the given `name`
+    fn parse_bc_bytes(bitcode: &[u8], name: &str) -> Result<Self, Error> {
+        use std::ffi::CString;
+
+        debug!("Creating a Module from bitcode {:?}", name);
+        let name = CString::new(name)
+            .map_err(|_| Error::ReadFailed("Module name contains a nul byte".into()))?;
+        let bitcode = crate::bitstream::strip_wrapper(bitcode)?;
+        let memory_buffer = unsafe {
+            LLVMCreateMemoryBufferWithMemoryRangeCopy(
+                bitcode.as_ptr() as *const _,
+                bitcode.len(),
+                name.as_ptr(),
+            )
+        };
+        Self::parse_memory_buffer(memory_buffer, false, None, &[], &ParseOptions::default())
+    }
+
+    fn parse_bc_path(
+        path: impl AsRef<Path>,
+        verify: bool,
+        shared: Option<&Context>,
+        passes: &[&str],
+        options: &ParseOptions,
+    ) -> Result<Self, Error> {
         // implementation here inspired by the `inkwell` crate's `Module::parse_bitcode_from_path`
         use std::ffi::{CStr, CString};
-        use std::mem;
 
-        let path = CString::new(
-            path.as_ref()
-                .to_str()
-                .expect("Did not find a valid Unicode path string"),
-        )
-        .expect("Failed to convert to CString");
+        let path = path
+            .as_ref()
+            .to_str()
+            .ok_or_else(|| Error::ReadFailed("Did not find a valid Unicode path string".into()))?;
+        let path =
+            CString::new(path).map_err(|_| Error::ReadFailed("Path contains a nul byte".into()))?;
         debug!("Creating a Module from path {:?}", path);
 
         let memory_buffer = unsafe {
@@ -77,15 +1160,52 @@ impl Module {
                 &mut err_string,
             );
             if return_code != 0 {
-                return Err(CStr::from_ptr(err_string)
-                    .to_str()
-                    .expect("Failed to convert CStr")
-                    .to_owned());
+                let err = CStr::from_ptr(err_string).to_string_lossy().into_owned();
+                LLVMDisposeMessage(err_string);
+                return Err(Error::ReadFailed(err));
             }
             memory_buffer
         };
         debug!("Created a MemoryBuffer");
 
+        let contents = unsafe {
+            std::slice::from_raw_parts(
+                LLVMGetBufferStart(memory_buffer) as *const u8,
+                LLVMGetBufferSize(memory_buffer),
+            )
+        };
+        if crate::archive::is_archive(contents) {
+            unsafe { LLVMDisposeMemoryBuffer(memory_buffer) };
+            return Err(Error::ReadFailed(
+                "File is an archive; use Module::from_archive_path() instead".into(),
+            ));
+        }
+
+        Self::parse_memory_buffer(memory_buffer, verify, shared, passes, options)
+    }
+
+    /// Parse the bitcode in `memory_buffer`, which this takes ownership of
+    fn parse_memory_buffer(
+        memory_buffer: LLVMMemoryBufferRef,
+        verify: bool,
+        shared: Option<&Context>,
+        passes: &[&str],
+        options: &ParseOptions,
+    ) -> Result<Self, Error> {
+        use std::ffi::CStr;
+        use std::mem;
+
+        #[cfg(feature = "llvm-14-or-greater")]
+        if options.opaque_pointers {
+            enable_opaque_pointers();
+        }
+
+        let upgraded_intrinsics = if options.find_upgraded_intrinsics {
+            unsafe { find_upgraded_intrinsics(memory_buffer) }
+        } else {
+            vec![]
+        };
+
         let context = crate::from_llvm::Context::new();
 
         use llvm_sys::bit_reader::LLVMParseBitcodeInContext2;
@@ -95,23 +1215,599 @@ impl Module {
                 LLVMParseBitcodeInContext2(context.ctx, memory_buffer, module.as_mut_ptr());
             LLVMDisposeMemoryBuffer(memory_buffer);
             if return_code != 0 {
-                return Err("Failed to parse bitcode".to_string());
+                return Err(Error::InvalidBitcode);
             }
             module.assume_init()
         };
         debug!("Parsed bitcode to llvm_sys module");
-        Ok(Self::from_llvm_ref(module))
+        if verify {
+            use llvm_sys::analysis::{LLVMVerifierFailureAction, LLVMVerifyModule};
+            let err = unsafe {
+                let mut err_string = std::ptr::null_mut();
+                let return_code = LLVMVerifyModule(
+                    module,
+                    LLVMVerifierFailureAction::LLVMReturnStatusAction,
+                    &mut err_string,
+                );
+                let err = if return_code != 0 {
+                    Some(CStr::from_ptr(err_string).to_string_lossy().into_owned())
+                } else {
+                    None
+                };
+                LLVMDisposeMessage(err_string);
+                err
+            };
+            if let Some(err) = err {
+                return Err(Error::VerificationFailed(err));
+            }
+            debug!("Verified llvm_sys module");
+        }
+        if !passes.is_empty() {
+            unsafe { run_passes(module, passes) }?;
+            debug!("Ran passes {:?} on llvm_sys module", passes);
+        }
+        let mut shared_types = shared.map(Context::lock_types);
+        let mut parsed = crate::error::catch_panic(|| {
+            Self::from_llvm_ref(module, shared_types.as_deref_mut(), options)
+        })?;
+        parsed.upgraded_intrinsics = upgraded_intrinsics;
+        Ok(parsed)
+    }
+}
+
+/// Run the given passes on `module` with the new pass manager.
+/// See [`Module::from_bc_path_with_passes()`](struct.Module.html#method.from_bc_path_with_passes).
+#[cfg(feature = "llvm-13-or-greater")]
+unsafe fn run_passes(module: LLVMModuleRef, passes: &[&str]) -> Result<(), Error> {
+    use llvm_sys::error::{LLVMDisposeErrorMessage, LLVMGetErrorMessage};
+    use llvm_sys::transforms::pass_builder::*;
+    use std::ffi::{CStr, CString};
+
+    let pipeline = CString::new(passes.join(","))
+        .map_err(|_| Error::PassesFailed("pass names contain a nul byte".into()))?;
+    let options = LLVMCreatePassBuilderOptions();
+    let err = LLVMRunPasses(module, pipeline.as_ptr(), std::ptr::null_mut(), options);
+    LLVMDisposePassBuilderOptions(options);
+    if err.is_null() {
+        Ok(())
+    } else {
+        let message = LLVMGetErrorMessage(err);
+        let result = CStr::from_ptr(message).to_string_lossy().into_owned();
+        LLVMDisposeErrorMessage(message);
+        Err(Error::PassesFailed(result))
+    }
+}
+
+/// Run the given passes on `module` with the legacy pass manager, which only
+/// supports the passes available through the LLVM C API.
+/// See [`Module::from_bc_path_with_passes()`](struct.Module.html#method.from_bc_path_with_passes).
+#[cfg(feature = "llvm-12-or-lower")]
+unsafe fn run_passes(module: LLVMModuleRef, passes: &[&str]) -> Result<(), Error> {
+    use llvm_sys::transforms::instcombine::*;
+    use llvm_sys::transforms::ipo::*;
+    use llvm_sys::transforms::scalar::*;
+    use llvm_sys::transforms::util::*;
+
+    let pass_manager = LLVMCreatePassManager();
+    for &pass in passes {
+        let add: unsafe extern "C" fn(LLVMPassManagerRef) = match pass {
+            "adce" => LLVMAddAggressiveDCEPass,
+            "always-inline" => LLVMAddAlwaysInlinerPass,
+            "dse" => LLVMAddDeadStoreEliminationPass,
+            "early-cse" => LLVMAddEarlyCSEPass,
+            "globaldce" => LLVMAddGlobalDCEPass,
+            "globalopt" => LLVMAddGlobalOptimizerPass,
+            "gvn" => LLVMAddGVNPass,
+            "inline" => LLVMAddFunctionInliningPass,
+            "instcombine" => LLVMAddInstructionCombiningPass,
+            "licm" => LLVMAddLICMPass,
+            "loop-rotate" => LLVMAddLoopRotatePass,
+            "lowerswitch" => LLVMAddLowerSwitchPass,
+            "mem2reg" => LLVMAddPromoteMemoryToRegisterPass,
+            "reassociate" => LLVMAddReassociatePass,
+            "simplifycfg" => LLVMAddCFGSimplificationPass,
+            "sroa" => LLVMAddScalarReplAggregatesPass,
+            "strip-dead-prototypes" => LLVMAddStripDeadPrototypesPass,
+            "tailcallelim" => LLVMAddTailCallEliminationPass,
+            _ => {
+                LLVMDisposePassManager(pass_manager);
+                return Err(Error::PassesFailed(format!(
+                    "unknown pass name '{}' (only a few passes are available before LLVM 13)",
+                    pass
+                )));
+            },
+        };
+        add(pass_manager);
+    }
+    LLVMRunPassManager(pass_manager, module);
+    LLVMDisposePassManager(pass_manager);
+    Ok(())
+}
+
+/// Put LLVM into opaque-pointer mode, for every LLVM context created from now
+/// on. See `ParseOptions::opaque_pointers`.
+#[cfg(feature = "llvm-14-or-greater")]
+fn enable_opaque_pointers() {
+    use llvm_sys::support::LLVMParseCommandLineOptions;
+    use std::os::raw::c_char;
+    use std::sync::Once;
+    // LLVM rejects an option which occurs more than once, even across calls
+    static ENABLE: Once = Once::new();
+    ENABLE.call_once(|| {
+        let args: [*const c_char; 2] = [
+            b"llvm-ir\0".as_ptr() as *const c_char,
+            b"-opaque-pointers\0".as_ptr() as *const c_char,
+        ];
+        unsafe {
+            LLVMParseCommandLineOptions(
+                args.len() as i32,
+                args.as_ptr(),
+                b"\0".as_ptr() as *const c_char,
+            )
+        };
+    });
+}
+
+/// Find the intrinsics declared in the bitcode in `buffer` which LLVM
+/// auto-upgrades, and what it upgrades each of them to.
+///
+/// LLVM upgrades old intrinsics while reading bitcode, in a way that can't be
+/// observed afterwards. So we get the names of the declared functions from the
+/// bitcode itself, and their types from a lazily read module (in which the
+/// old intrinsics are still declared, under new names, because the function
+/// bodies calling them are never materialized). Then we write a module which
+/// calls each intrinsic, and see what LLVM makes of the calls when reading
+/// it back.
+///
+/// Returns no intrinsics if the bitcode is invalid; the caller will find out
+/// for itself.
+unsafe fn find_upgraded_intrinsics(buffer: LLVMMemoryBufferRef) -> Vec<UpgradedIntrinsic> {
+    use llvm_sys::bit_reader::{LLVMGetBitcodeModuleInContext2, LLVMParseBitcodeInContext2};
+    use llvm_sys::bit_writer::LLVMWriteBitcodeToMemoryBuffer;
+    use llvm_sys::LLVMTypeKind;
+    use std::ffi::CString;
+
+    let start = LLVMGetBufferStart(buffer);
+    let size = LLVMGetBufferSize(buffer);
+    let names = match crate::summary::function_names(std::slice::from_raw_parts(
+        start as *const u8,
+        size,
+    )) {
+        Ok(names) => names,
+        Err(_) => return vec![],
+    };
+    let context = crate::from_llvm::Context::new();
+    // a view of `buffer`, which the lazy module takes ownership of
+    let view = LLVMCreateMemoryBufferWithMemoryRange(start, size, b"\0".as_ptr() as *const _, 0);
+    let mut lazy = std::ptr::null_mut();
+    if LLVMGetBitcodeModuleInContext2(context.ctx, view, &mut lazy) != 0 {
+        LLVMDisposeMemoryBuffer(view);
+        return vec![];
+    }
+    // LLVM creates the functions in the order of their records, and adds the
+    // declarations of the new intrinsics after them
+    let intrinsics: Vec<(String, LLVMTypeRef)> = names
+        .into_iter()
+        .zip(get_functions(lazy))
+        .filter(|(name, _)| name.starts_with("llvm."))
+        .map(|(name, func)| (name, LLVMGlobalGetValueType(func)))
+        .collect();
+
+    // a module with a function for each intrinsic, which calls it
+    let probe = LLVMModuleCreateWithNameInContext(b"probe\0".as_ptr() as *const _, context.ctx);
+    let builder = LLVMCreateBuilderInContext(context.ctx);
+    let void_func_ty = LLVMFunctionType(
+        LLVMVoidTypeInContext(context.ctx),
+        std::ptr::null_mut(),
+        0,
+        0,
+    );
+    for (name, func_ty) in &intrinsics {
+        let name = CString::new(name.as_str()).unwrap();
+        let intrinsic = LLVMAddFunction(probe, name.as_ptr(), *func_ty);
+        let func = LLVMAddFunction(probe, b"probe\0".as_ptr() as *const _, void_func_ty);
+        let entry = LLVMAppendBasicBlockInContext(context.ctx, func, b"\0".as_ptr() as *const _);
+        LLVMPositionBuilderAtEnd(builder, entry);
+        let mut param_types = vec![std::ptr::null_mut(); LLVMCountParamTypes(*func_ty) as usize];
+        LLVMGetParamTypes(*func_ty, param_types.as_mut_ptr());
+        // LLVM expects some arguments of some intrinsics to be constants when
+        // upgrading them, so pass zeroes
+        let mut args: Vec<LLVMValueRef> = param_types
+            .into_iter()
+            .map(|ty| match LLVMGetTypeKind(ty) {
+                LLVMTypeKind::LLVMMetadataTypeKind => {
+                    let node = LLVMMDNodeInContext2(context.ctx, std::ptr::null_mut(), 0);
+                    LLVMMetadataAsValue(context.ctx, node)
+                },
+                LLVMTypeKind::LLVMX86_MMXTypeKind => LLVMGetUndef(ty),
+                #[cfg(feature = "llvm-12-or-greater")]
+                LLVMTypeKind::LLVMX86_AMXTypeKind => LLVMGetUndef(ty),
+                _ => LLVMConstNull(ty),
+            })
+            .collect();
+        LLVMBuildCall2(
+            builder,
+            *func_ty,
+            intrinsic,
+            args.as_mut_ptr(),
+            args.len() as u32,
+            b"\0".as_ptr() as *const _,
+        );
+        LLVMBuildRetVoid(builder);
+    }
+    LLVMDisposeBuilder(builder);
+    let probe_bitcode = LLVMWriteBitcodeToMemoryBuffer(probe);
+    LLVMDisposeModule(probe);
+    LLVMDisposeModule(lazy);
+    let mut upgraded_probe = std::ptr::null_mut();
+    let failed = LLVMParseBitcodeInContext2(context.ctx, probe_bitcode, &mut upgraded_probe) != 0;
+    LLVMDisposeMemoryBuffer(probe_bitcode);
+    if failed {
+        return vec![];
+    }
+
+    // the calling functions are still in the same order as the intrinsics
+    let upgraded = intrinsics
+        .into_iter()
+        .zip(get_defined_functions(upgraded_probe))
+        .filter_map(|((old_name, old_ty), func)| {
+            let callee = get_basic_blocks(func)
+                .flat_map(get_instructions)
+                .find(|&inst| !LLVMIsACallInst(inst).is_null())
+                .map(|call| LLVMIsAFunction(LLVMGetCalledValue(call)))
+                .filter(|callee| !callee.is_null());
+            let new_name = match callee {
+                Some(callee)
+                    if LLVMGlobalGetValueType(callee) == old_ty
+                        && get_value_name(callee) == old_name =>
+                {
+                    return None; // LLVM left the call alone
+                },
+                Some(callee) => Some(get_value_name(callee)),
+                None => None,
+            };
+            Some(UpgradedIntrinsic { old_name, new_name })
+        })
+        .collect();
+    LLVMDisposeModule(upgraded_probe);
+    upgraded
+}
+
+/// Options controlling how a `Module` is parsed.
+/// See [`Module::from_bc_path_with_options()`](struct.Module.html#method.from_bc_path_with_options).
+///
+/// The `Default` options parse everything, and fail with an error on anything
+/// `llvm-ir` doesn't support (except instructions with unknown opcodes; see
+/// `strict_opcodes`), as [`Module::from_bc_path()`](struct.Module.html#method.from_bc_path)
+/// does. The other options trade completeness for speed and memory (or for
+/// robustness), which can matter when processing large corpora:
+///
+/// ```no_run
+/// # use llvm_ir::module::{Module, ParseOptions};
+/// let options = ParseOptions {
+///     // only the bodies of functions in the `foo` namespace are needed
+///     skip_function_bodies: Some(Box::new(|name| !name.starts_with("_ZN3foo"))),
+///     skip_debug_info: true,
+///     ..ParseOptions::default()
+/// };
+/// let module = Module::from_bc_path_with_options("input.bc", &options);
+/// ```
+#[derive(Default)]
+pub struct ParseOptions {
+    /// If set, the bodies of the defined functions whose names this returns
+    /// `true` for are not parsed. Those functions are still in the `Module`'s
+    /// `functions`, with their parameters, attributes, etc., but with no
+    /// `basic_blocks`.
+    #[allow(clippy::type_complexity)]
+    pub skip_function_bodies: Option<Box<dyn Fn(&str) -> bool>>,
+    /// If `true`, debug info is dropped as the `Module` is parsed, rather
+    /// than parsed and then thrown away:
+    ///   - the `Module` has no `DebugLoc`s: all of the `debugloc`s of its
+    ///     functions, global variables, instructions, and terminators are
+    ///     `None`, and so are the `subprogram`s of its functions;
+    ///   - calls to debug intrinsics such as `llvm.dbg.value` are left out of
+    ///     the functions' basic blocks;
+    ///   - debug-info metadata nodes (`DILocation`, `DILocalVariable`, etc.)
+    ///     are not in the `Module`'s `metadata_nodes`. References to them
+    ///     from other metadata nodes, e.g., the `DILocation`s in a loop ID,
+    ///     are null, and metadata operands referring to them are
+    ///     `Metadata::Unknown`.
+    ///
+    /// So, the functions are the same as if the `Module` were parsed normally
+    /// and then passed to [`Module::strip_debug_info()`](struct.Module.html#method.strip_debug_info).
+    /// In bitcode compiled with `-g`, the debug intrinsics and the metadata
+    /// they refer to can make up a large part of what is parsed. For
+    /// example, for `tests/basic_bc/rust/rust.bc-g` (a small Rust crate
+    /// compiled with LLVM 14 and `-g`; about 1300 instructions, 10% of
+    /// them debug intrinsics, and about 600 debug-info metadata nodes),
+    /// this option cuts the
+    /// [`memory_footprint()`](struct.Module.html#method.memory_footprint)
+    /// of the `Module` by 40%, from about 1.0 MB to 0.6 MB, and parsing time
+    /// by about 15%, from 5.9 ms to 5.0 ms in a release build. Parsing time
+    /// saves less because LLVM still reads all of the debug info from the
+    /// bitcode. Compare the `memory_footprint()`s of a `Module` parsed with
+    /// and without this option to see how much it saves for a given input.
+    pub skip_debug_info: bool,
+    /// If set, constant arrays with more than this many elements (such as
+    /// large lookup tables or embedded data) are parsed as
+    /// `Constant::Undef` of the array type, instead of as a
+    /// `Constant::Array` or `Constant::RawDataArray`.
+    pub max_constant_array_elements: Option<usize>,
+    /// If `false` (the default), parsing fails with `Error::Unsupported` if
+    /// the bitcode contains something `llvm-ir` doesn't support. If `true`,
+    /// parsing continues as best it can instead: a function whose body
+    /// contains something unsupported is parsed with no `basic_blocks` (as if
+    /// by `skip_function_bodies`), and the problem is logged as a warning.
+    /// Unsupported constructs outside of function bodies are still errors,
+    /// as is a panic inside `llvm-ir` itself, which fails the whole parse.
+    pub lossy: bool,
+    /// If `true`, every unnamed local value and basic block is given a
+    /// string name, as by
+    /// [`Module::name_unnamed_locals()`](struct.Module.html#method.name_unnamed_locals).
+    pub name_unnamed_locals: bool,
+    /// If `true`, LLVM reads the bitcode in opaque-pointer mode, as with
+    /// LLVM's `-opaque-pointers` command-line option. LLVM 14 can only read
+    /// bitcode containing opaque pointers (`ptr`) in this mode.
+    ///
+    /// Note that this mode is global to the process and can't be turned off
+    /// again: once a `Module` has been parsed with this option, all bitcode
+    /// parsed afterwards (with or without the option) is read with opaque
+    /// pointers.
+    #[cfg(feature = "llvm-14-or-greater")]
+    pub opaque_pointers: bool,
+    /// If `true`, parsing fails with `Error::Unsupported` on instructions and
+    /// terminators with opcodes `llvm-ir` doesn't model. If `false` (the
+    /// default), they are parsed as
+    /// [`Instruction::Other`](../instruction/enum.Instruction.html#variant.Other)
+    /// and [`Terminator::Other`](../terminator/enum.Terminator.html#variant.Other),
+    /// which record only their operands, successors, and result.
+    pub strict_opcodes: bool,
+    /// If `true`, find out which intrinsics LLVM auto-upgraded while reading
+    /// the bitcode, and what it upgraded them to, and record them in the
+    /// `Module`'s
+    /// [`upgraded_intrinsics`](struct.Module.html#structfield.upgraded_intrinsics).
+    /// LLVM's upgrades can't be observed in the module it reads, so this
+    /// reads the bitcode a second time (lazily, without function bodies),
+    /// and has LLVM upgrade a call to each intrinsic in a small module of
+    /// its own.
+    pub find_upgraded_intrinsics: bool,
+}
+
+impl fmt::Debug for ParseOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut debug = f.debug_struct("ParseOptions");
+        debug
+            .field(
+                "skip_function_bodies",
+                &self.skip_function_bodies.as_ref().map(|_| "<predicate>"),
+            )
+            .field("skip_debug_info", &self.skip_debug_info)
+            .field(
+                "max_constant_array_elements",
+                &self.max_constant_array_elements,
+            )
+            .field("lossy", &self.lossy)
+            .field("name_unnamed_locals", &self.name_unnamed_locals);
+        #[cfg(feature = "llvm-14-or-greater")]
+        debug.field("opaque_pointers", &self.opaque_pointers);
+        debug
+            .field("strict_opcodes", &self.strict_opcodes)
+            .field("find_upgraded_intrinsics", &self.find_upgraded_intrinsics);
+        debug.finish()
     }
 }
 
+/// An estimate of how much memory a `Module` uses, in bytes, broken down by
+/// category.
+/// See [`Module::memory_footprint()`](struct.Module.html#method.memory_footprint).
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct MemoryFootprint {
+    /// The basic blocks, instructions, and terminators of the `Module`'s
+    /// functions, including their operands (counting each shared `Operand`
+    /// once) and attributes, but not the names, constants, or debug
+    /// locations counted below
+    pub instructions: usize,
+    /// The `Module`'s `Constant`s, counting each shared `Constant` once
+    pub constants: usize,
+    /// The `Module`'s `Types`
+    pub types: usize,
+    /// The names of functions, parameters, global variables and aliases,
+    /// basic blocks, and locals, wherever they appear
+    pub names: usize,
+    /// The filenames and directories of `DebugLoc`s, and the `Subprogram`s
+    /// of functions. These are stored once per `DebugLoc` (or `Subprogram`),
+    /// so they can be a large part of a `Module` with debug information.
+    pub debug_locs: usize,
+    /// The `Module`'s metadata nodes
+    pub metadata: usize,
+}
+
+impl MemoryFootprint {
+    /// The total of all the categories
+    pub fn total(&self) -> usize {
+        self.instructions
+            + self.constants
+            + self.types
+            + self.names
+            + self.debug_locs
+            + self.metadata
+    }
+
+    /// Add the `Operand`s, counting each shared `Operand` (and its name,
+    /// constant, or metadata) once
+    fn add_operands<'a>(
+        &mut self,
+        operands: impl Iterator<Item = &'a Operand>,
+        seen_operands: &mut HashSet<*const Operand>,
+        constants: &mut ConstantsFootprint,
+    ) {
+        for op in operands {
+            // `operands()` gives references into the `OperandRef`s, so their
+            // addresses identify shared `Operand`s
+            if !seen_operands.insert(op) {
+                continue;
+            }
+            // the `Arc`'s reference counts, plus the `Operand` itself
+            self.instructions += 2 * size_of::<usize>() + size_of::<Operand>();
+            match op {
+                Operand::LocalOperand { name, .. } => self.names += name.heap_size(),
+                Operand::ConstantOperand(c) => constants.add(c),
+                Operand::MetadataOperand(md) => {
+                    self.instructions += size_of::<Metadata>() + metadata_heap_size(md)
+                },
+            }
+        }
+    }
+}
+
+/// The memory used by `Constant`s, counting each shared `Constant` once
+#[derive(Default)]
+struct ConstantsFootprint {
+    seen: HashSet<*const Constant>,
+    bytes: usize,
+}
+
+impl ConstantsFootprint {
+    fn add(&mut self, constant: &ConstantRef) {
+        let mut worklist = vec![constant];
+        while let Some(constant) = worklist.pop() {
+            if !self.seen.insert(constant.as_ptr()) {
+                continue;
+            }
+            // the `Arc`'s reference counts, plus the `Constant` itself
+            self.bytes += 2 * size_of::<usize>() + size_of::<Constant>() + constant.own_heap_size();
+            worklist.extend(constant.sub_constants());
+        }
+    }
+}
+
+fn vec_heap_size<T>(v: &Vec<T>) -> usize {
+    v.capacity() * size_of::<T>()
+}
+
+fn arguments_heap_size(arguments: &Vec<(OperandRef, Vec<ParameterAttribute>)>) -> usize {
+    vec_heap_size(arguments)
+        + arguments
+            .iter()
+            .map(|(_, attrs)| vec_heap_size(attrs))
+            .sum::<usize>()
+}
+
+/// The heap memory owned by the `Instruction` itself, not counting its names,
+/// constants, or `DebugLoc`
+fn instruction_heap_size(inst: &Instruction) -> usize {
+    match inst {
+        Instruction::ExtractValue(ev) => vec_heap_size(&ev.indices),
+        Instruction::InsertValue(iv) => vec_heap_size(&iv.indices),
+        Instruction::GetElementPtr(gep) => vec_heap_size(&gep.indices),
+        Instruction::Phi(phi) => vec_heap_size(&phi.incoming_values),
+        Instruction::Call(call) => {
+            arguments_heap_size(&call.arguments)
+                + vec_heap_size(&call.return_attributes)
+                + vec_heap_size(&call.function_attributes)
+                + vec_heap_size(&call.operand_bundles)
+                + call
+                    .operand_bundles
+                    .iter()
+                    .map(|b| b.tag.capacity() + vec_heap_size(&b.args))
+                    .sum::<usize>()
+        },
+        Instruction::LandingPad(lp) => vec_heap_size(&lp.clauses),
+        Instruction::CatchPad(cp) => vec_heap_size(&cp.args),
+        Instruction::CleanupPad(cp) => vec_heap_size(&cp.args),
+        Instruction::Other(other) => vec_heap_size(&other.operands),
+        _ => 0,
+    }
+}
+
+/// The heap memory owned by the `Terminator` itself, not counting its names,
+/// constants, or `DebugLoc`
+fn terminator_heap_size(term: &Terminator) -> usize {
+    let weights = |w: &Option<Vec<u64>>| w.as_ref().map_or(0, vec_heap_size);
+    match term {
+        Terminator::CondBr(condbr) => weights(&condbr.branch_weights),
+        Terminator::Switch(switch) => {
+            vec_heap_size(&switch.dests) + weights(&switch.branch_weights)
+        },
+        Terminator::IndirectBr(ibr) => vec_heap_size(&ibr.possible_dests),
+        Terminator::Invoke(invoke) => {
+            arguments_heap_size(&invoke.arguments)
+                + vec_heap_size(&invoke.return_attributes)
+                + vec_heap_size(&invoke.function_attributes)
+                + vec_heap_size(&invoke.operand_bundles)
+        },
+        Terminator::CatchSwitch(cs) => vec_heap_size(&cs.catch_handlers),
+        #[cfg(feature = "llvm-9-or-greater")]
+        Terminator::CallBr(callbr) => {
+            arguments_heap_size(&callbr.arguments)
+                + vec_heap_size(&callbr.return_attributes)
+                + vec_heap_size(&callbr.function_attributes)
+        },
+        Terminator::Other(other) => {
+            vec_heap_size(&other.operands) + vec_heap_size(&other.successors)
+        },
+        _ => 0,
+    }
+}
+
+#[cfg(feature = "llvm-9-or-greater")]
+fn debugloc_heap_size(debugloc: &Option<DebugLoc>) -> usize {
+    debugloc.as_ref().map_or(0, |loc| {
+        loc.filename.capacity() + loc.directory.as_ref().map_or(0, String::capacity)
+    })
+}
+
+#[cfg(feature = "llvm-9-or-greater")]
+fn subprogram_heap_size(subprogram: &Option<Subprogram>) -> usize {
+    subprogram.as_ref().map_or(0, |sp| {
+        sp.name.capacity()
+            + sp.linkage_name.as_ref().map_or(0, String::capacity)
+            + sp.compile_unit.as_ref().map_or(0, |unit| {
+                unit.filename.capacity()
+                    + unit.directory.as_ref().map_or(0, String::capacity)
+                    + unit.producer.capacity()
+            })
+    })
+}
+
+fn metadata_heap_size(md: &Metadata) -> usize {
+    match md {
+        Metadata::String(s) => s.capacity(),
+        _ => 0,
+    }
+}
+
+fn metadata_node_heap_size(node: &MetadataNode) -> usize {
+    let (kind, operands) = match node {
+        MetadataNode::Tuple(operands) => (0, operands),
+        MetadataNode::Specialized { kind, operands } => (kind.capacity(), operands),
+    };
+    kind + vec_heap_size(operands)
+        + operands
+            .iter()
+            .flatten()
+            .map(metadata_heap_size)
+            .sum::<usize>()
+}
+
+/// Split module-level inline assembly into blocks, one per line
+pub(crate) fn split_inline_asm(asm: &str) -> Vec<String> {
+    asm.lines().map(String::from).collect()
+}
+
 /// See [LLVM 14 docs on Global Variables](https://releases.llvm.org/14.0.0/docs/LangRef.html#global-variables)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GlobalVariable {
     pub name: Name,
     pub linkage: Linkage,
     pub visibility: Visibility,
     pub is_constant: bool,
+    /// The type of the global variable itself, which is always a pointer type
+    /// (to the type of the `initializer`, if any). Like all types in a
+    /// `Module`, this is interned in the `Module`'s `types`.
     pub ty: TypeRef,
+    /// The address space of `ty`
     pub addr_space: AddrSpace,
     pub dll_storage_class: DLLStorageClass,
     pub thread_local_mode: ThreadLocalMode,
@@ -119,7 +1815,10 @@ pub struct GlobalVariable {
     pub initializer: Option<ConstantRef>,
     pub section: Option<String>,
     pub comdat: Option<Comdat>, // llvm-hs-pure has Option<String> for some reason
-    pub alignment: u32,
+    /// The explicit alignment, in bytes, or `None` if it isn't specified, in
+    /// which case LLVM chooses an alignment (at least the ABI alignment of
+    /// `ty`'s pointee type)
+    pub alignment: Option<NonZeroU32>,
     #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: Vec<(String, MetadataRef<MetadataNode>)>,
@@ -138,14 +1837,25 @@ impl HasDebugLoc for GlobalVariable {
     }
 }
 
+#[cfg(feature = "llvm-9-or-greater")]
+impl HasDebugLocMut for GlobalVariable {
+    fn get_debug_loc_mut(&mut self) -> &mut Option<DebugLoc> {
+        &mut self.debugloc
+    }
+}
+
 /// See [LLVM 14 docs on Global Aliases](https://releases.llvm.org/14.0.0/docs/LangRef.html#aliases)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GlobalAlias {
     pub name: Name,
     pub aliasee: ConstantRef,
     pub linkage: Linkage,
     pub visibility: Visibility,
+    /// The type of the alias itself, which is always a pointer type. Like all
+    /// types in a `Module`, this is interned in the `Module`'s `types`.
     pub ty: TypeRef,
+    /// The address space of `ty`
     pub addr_space: AddrSpace,
     pub dll_storage_class: DLLStorageClass,
     pub thread_local_mode: ThreadLocalMode,
@@ -158,14 +1868,16 @@ impl Typed for GlobalAlias {
     }
 }
 
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum UnnamedAddr {
     Local,
     Global,
 }
 
 /// See [LLVM 14 docs on Linkage Types](https://releases.llvm.org/14.0.0/docs/LangRef.html#linkage)
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Linkage {
     Private,
     Internal,
@@ -187,7 +1899,8 @@ pub enum Linkage {
 }
 
 /// See [LLVM 14 docs on Visibility Styles](https://releases.llvm.org/14.0.0/docs/LangRef.html#visibility-styles)
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Visibility {
     Default,
     Hidden,
@@ -195,7 +1908,8 @@ pub enum Visibility {
 }
 
 /// See [LLVM 14 docs on DLL Storage Classes](https://releases.llvm.org/14.0.0/docs/LangRef.html#dllstorageclass)
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DLLStorageClass {
     Default,
     Import,
@@ -203,7 +1917,8 @@ pub enum DLLStorageClass {
 }
 
 /// See [LLVM 14 docs on Thread Local Storage Models](https://releases.llvm.org/14.0.0/docs/LangRef.html#thread-local-storage-models)
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ThreadLocalMode {
     NotThreadLocal,
     GeneralDynamic,
@@ -215,8 +1930,86 @@ pub enum ThreadLocalMode {
 /// For discussion of address spaces, see [LLVM 14 docs on Pointer Type](https://releases.llvm.org/14.0.0/docs/LangRef.html#pointer-type)
 pub type AddrSpace = u32;
 
+/// An intrinsic which LLVM auto-upgraded while reading bitcode.
+/// See [`Module::upgraded_intrinsics`](struct.Module.html#structfield.upgraded_intrinsics).
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UpgradedIntrinsic {
+    /// The name of the old intrinsic, as declared in the bitcode, e.g.
+    /// `llvm.objectsize.i32`. It doesn't appear in the `Module`, unless the
+    /// new intrinsic has the same name (but a different type).
+    pub old_name: String,
+    /// The name of the function which LLVM calls instead, e.g.
+    /// `llvm.objectsize.i32.p0i8`, or `None` if LLVM replaced calls to the old
+    /// intrinsic with other instructions (or dropped them)
+    pub new_name: Option<String>,
+}
+
+/// A compiler which produced (part of) a `Module`, as recorded in an entry of
+/// its `!llvm.ident` metadata, such as
+/// `clang version 14.0.6 (https://github.com/llvm/llvm-project.git f28c006a5895fc0e329fe15fead81e37457cb1d1)`
+/// or `rustc version 1.70.0 (90c541806 2023-05-31)`.
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Producer {
+    /// The whole `!llvm.ident` string
+    pub ident: String,
+    /// The name of the compiler, e.g. `clang`, `Apple clang`, or `rustc`:
+    /// the part of `ident` before ` version `, or all of `ident` if it doesn't
+    /// contain ` version `
+    pub name: String,
+    /// The compiler's version as (major, minor, patch), e.g. `(14, 0, 6)`,
+    /// with any missing components 0. `None` if `ident` has no version
+    /// number.
+    pub version: Option<(u32, u32, u32)>,
+}
+
+impl Producer {
+    /// Parse an `!llvm.ident` string
+    pub fn parse(ident: &str) -> Self {
+        const SEPARATOR: &str = " version ";
+        let (name, version) = match ident.find(SEPARATOR) {
+            Some(pos) => {
+                let version = ident[pos + SEPARATOR.len() ..].split_whitespace().next();
+                (&ident[.. pos], version.and_then(parse_version))
+            },
+            None => (ident, None),
+        };
+        Self {
+            ident: ident.to_owned(),
+            name: name.to_owned(),
+            version,
+        }
+    }
+
+    /// Is this a version of Clang (including vendor builds such as
+    /// `Apple clang` or `Ubuntu clang`)?
+    pub fn is_clang(&self) -> bool {
+        self.name == "clang" || self.name.ends_with(" clang")
+    }
+
+    /// Is this a version of `rustc`?
+    pub fn is_rustc(&self) -> bool {
+        self.name == "rustc"
+    }
+}
+
+/// Parse a version number such as `14.0.6` or `14.0.0-1ubuntu1` into
+/// (major, minor, patch), ignoring any suffix
+fn parse_version(version: &str) -> Option<(u32, u32, u32)> {
+    let end = version
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(version.len());
+    let mut components = version[.. end].split('.').map(|c| c.parse().ok());
+    let major = components.next().flatten()?;
+    let minor = components.next().flatten().unwrap_or(0);
+    let patch = components.next().flatten().unwrap_or(0);
+    Some((major, minor, patch))
+}
+
 /// See [LLVM 14 docs on Attribute Groups](https://releases.llvm.org/14.0.0/docs/LangRef.html#attribute-groups)
-#[derive(PartialEq, Eq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FunctionAttributeGroup {
     pub group_id: GroupID,
     pub attrs: Vec<FunctionAttribute>,
@@ -224,7 +2017,7 @@ pub struct FunctionAttributeGroup {
 
 /* --TODO not yet implemented: metadata
 /// See [LLVM 14 docs on Named Metadata](https://releases.llvm.org/14.0.0/docs/LangRef.html#named-metadata)
-#[derive(PartialEq, Eq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
 pub struct NamedMetadata {
     pub name: String,
     pub node_ids: Vec<MetadataNodeID>,
@@ -232,13 +2025,15 @@ pub struct NamedMetadata {
 */
 
 /// See [LLVM 14 docs on Comdats](https://releases.llvm.org/14.0.0/docs/LangRef.html#langref-comdats)
-#[derive(PartialEq, Eq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Comdat {
     pub name: String,
     pub selection_kind: SelectionKind,
 }
 
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SelectionKind {
     Any,
     ExactMatch,
@@ -249,6 +2044,7 @@ pub enum SelectionKind {
 
 /// See [LLVM 14 docs on Data Layout](https://releases.llvm.org/14.0.0/docs/LangRef.html#data-layout)
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DataLayout {
     /// The data layout in string form, as described in the Data Layout docs linked above
     pub layout_str: String,
@@ -265,9 +2061,9 @@ pub struct DataLayout {
     /// What mangling will be applied when the LLVM module is compiled to machine code
     pub mangling: Option<Mangling>,
     /// Native integer width(s) for the target CPU
-    pub native_int_widths: Option<HashSet<u32>>,
+    pub native_int_widths: Option<BTreeSet<u32>>,
     /// Address spaces with non-integral pointer types
-    pub non_integral_ptr_types: HashSet<AddrSpace>,
+    pub non_integral_ptr_types: BTreeSet<AddrSpace>,
 }
 
 impl PartialEq for DataLayout {
@@ -279,7 +2075,8 @@ impl PartialEq for DataLayout {
 
 impl Eq for DataLayout {}
 
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Endianness {
     /// Least-significant bits are stored in the lowest address location
     LittleEndian,
@@ -290,6 +2087,7 @@ pub enum Endianness {
 /// Alignment details for a type.
 /// See [LLVM 14 docs on Data Layout](https://releases.llvm.org/14.0.0/docs/LangRef.html#data-layout)
 #[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Alignment {
     /// Minimum alignment (in bits) per the ABI
     pub abi: u32,
@@ -301,6 +2099,7 @@ pub struct Alignment {
 /// See [LLVM 14 docs on Data Layout](https://releases.llvm.org/14.0.0/docs/LangRef.html#data-layout)
 #[cfg(feature = "llvm-9-or-greater")]
 #[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FunctionPtrAlignment {
     /// If `true`, function pointer alignment is independent of function alignment.
     /// If `false`, function pointer alignment is a multiple of function alignment.
@@ -312,6 +2111,7 @@ pub struct FunctionPtrAlignment {
 /// Layout details for pointers (other than function pointers).
 /// See [LLVM 14 docs on Data Layout](https://releases.llvm.org/14.0.0/docs/LangRef.html#data-layout)
 #[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PointerLayout {
     /// Size of a pointer in bits
     pub size: u32,
@@ -324,6 +2124,7 @@ pub struct PointerLayout {
 /// Alignment for various types in memory.
 /// See [LLVM 14 docs on Data Layout](https://releases.llvm.org/14.0.0/docs/LangRef.html#data-layout)
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Alignments {
     /// Explicit alignments for various sizes of integers (in bits). Sizes not
     /// specified here are determined according to the rules described in the
@@ -334,7 +2135,7 @@ pub struct Alignments {
     /// Data Layout docs.
     vec_alignments: BTreeMap<u32, Alignment>,
     /// Alignment for floating-point types, by size (in bits)
-    fp_alignments: HashMap<u32, Alignment>,
+    fp_alignments: BTreeMap<u32, Alignment>,
     /// Alignment for aggregate types (structs, arrays)
     agg_alignment: Alignment,
     /// Alignment for function pointers
@@ -344,7 +2145,7 @@ pub struct Alignments {
     #[cfg(feature = "llvm-9-or-greater")]
     fptr_alignment_as_alignment: Alignment,
     /// Layout details for (non-function-pointer) pointers, by address space
-    pointer_layouts: HashMap<AddrSpace, PointerLayout>,
+    pointer_layouts: BTreeMap<AddrSpace, PointerLayout>,
 }
 
 impl Alignments {
@@ -376,6 +2177,7 @@ impl Alignments {
                 Type::FuncType { .. } => &self.fptr_alignment_as_alignment,
                 _ => &self.ptr_alignment(*addr_space).alignment,
             },
+            Type::OpaquePointerType { addr_space } => &self.ptr_alignment(*addr_space).alignment,
             _ => panic!("Don't know how to get the alignment of {:?}", ty),
         }
     }
@@ -394,8 +2196,7 @@ impl Alignments {
                 // `size` is larger than any explicit entry: use the largest explicit entry
                 self.int_alignments
                     .values()
-                    .rev()
-                    .next()
+                    .next_back()
                     .expect("Should have at least one explicit entry")
             },
         }
@@ -423,6 +2224,12 @@ impl Alignments {
         }
     }
 
+    /// for internal use: the alignment of vectors of exactly the given total
+    /// size (in bits), if the data layout specifies one
+    pub(crate) fn explicit_vec_alignment(&self, size: u32) -> Option<&Alignment> {
+        self.vec_alignments.get(&size)
+    }
+
     /// Alignment of the given floating-point type
     pub fn fp_alignment(&self, fpt: FPType) -> &Alignment {
         self.fp_alignments
@@ -473,7 +2280,8 @@ impl Alignments {
 }
 
 /// See [LLVM 14 docs on Data Layout](https://releases.llvm.org/14.0.0/docs/LangRef.html#data-layout)
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Mangling {
     ELF,
     MIPS,
@@ -484,11 +2292,350 @@ pub enum Mangling {
     XCOFF,
 }
 
+/// The names of the source variables of the unnamed locals in the given
+/// function, from the `DILocalVariable`s in its calls to debug intrinsics
+fn debug_variable_names(
+    func: &Function,
+    metadata_nodes: &[(MetadataNodeID, MetadataNode)],
+) -> HashMap<Name, String> {
+    let mut names = HashMap::new();
+    for inst in func.basic_blocks.iter().flat_map(|bb| &bb.instrs) {
+        let call = match inst {
+            Instruction::Call(call) => call,
+            _ => continue,
+        };
+        match callee_name(&call.function) {
+            Some("llvm.dbg.declare") | Some("llvm.dbg.value") | Some("llvm.dbg.addr") => {},
+            _ => continue,
+        }
+        let local = match call.arguments.first().map(|(arg, _)| arg.as_ref()) {
+            Some(Operand::MetadataOperand(md)) => match md.as_ref() {
+                Metadata::Value(Operand::LocalOperand { name, .. }) => name,
+                _ => continue,
+            },
+            _ => continue,
+        };
+        let node = match call.arguments.get(1).map(|(arg, _)| arg.as_ref()) {
+            Some(Operand::MetadataOperand(md)) => match md.as_ref() {
+                Metadata::Node(id) => metadata_nodes.get(*id).map(|(_, node)| node),
+                _ => None,
+            },
+            _ => None,
+        };
+        if let Some(MetadataNode::Specialized { kind, operands }) = node {
+            if let (Name::Number(_), "DILocalVariable", Some(Some(Metadata::String(var)))) =
+                (local, kind.as_str(), operands.get(1))
+            {
+                names.entry(local.clone()).or_insert_with(|| var.clone());
+            }
+        }
+    }
+    names
+}
+
+/// Whether the given constant refers to the global value with the given name,
+/// directly or in any of its sub-constants
+fn constant_refers_to(c: &Constant, name: &Name) -> bool {
+    match c {
+        Constant::GlobalReference { name: n, .. } => n == name,
+        Constant::BlockAddress { function, .. } => function == name,
+        c => c
+            .sub_constants()
+            .into_iter()
+            .any(|sub| constant_refers_to(sub, name)),
+    }
+}
+
+/// Whether the given function refers to the global value with the given name,
+/// in any instruction or terminator operand, or as its personality function
+fn function_refers_to(func: &Function, name: &Name) -> bool {
+    fn operand_refers_to(op: &Operand, name: &Name) -> bool {
+        match op {
+            Operand::LocalOperand { .. } => false,
+            Operand::ConstantOperand(c) => constant_refers_to(c, name),
+            Operand::MetadataOperand(md) => match md.as_ref() {
+                Metadata::Value(op) => operand_refers_to(op, name),
+                _ => false,
+            },
+        }
+    }
+
+    func.personality_function
+        .as_ref()
+        .is_some_and(|c| constant_refers_to(c, name))
+        || func.basic_blocks.iter().any(|bb| {
+            bb.instrs
+                .iter()
+                .flat_map(Instruction::operands)
+                .chain(bb.term.operands())
+                .any(|op| operand_refers_to(op, name))
+        })
+}
+
+/// Add the names of the global values which `func` refers to, in any
+/// instruction or terminator operand or as its personality function, to
+/// `refs`
+pub(crate) fn function_global_refs<'a>(func: &'a Function, refs: &mut Vec<&'a Name>) {
+    fn operand_global_refs<'a>(op: &'a Operand, refs: &mut Vec<&'a Name>) {
+        match op {
+            Operand::LocalOperand { .. } => {},
+            Operand::ConstantOperand(c) => constant_global_refs(c, refs),
+            Operand::MetadataOperand(md) => {
+                if let Metadata::Value(op) = md.as_ref() {
+                    operand_global_refs(op, refs);
+                }
+            },
+        }
+    }
+
+    if let Some(personality) = &func.personality_function {
+        constant_global_refs(personality, refs);
+    }
+    for bb in &func.basic_blocks {
+        for op in bb.instrs.iter().flat_map(Instruction::operands) {
+            operand_global_refs(op, refs);
+        }
+        for op in bb.term.operands() {
+            operand_global_refs(op, refs);
+        }
+    }
+}
+
+/// Add the names of the global values which `c` refers to, directly or in
+/// any of its sub-constants, to `refs`
+pub(crate) fn constant_global_refs<'a>(c: &'a Constant, refs: &mut Vec<&'a Name>) {
+    match c {
+        Constant::GlobalReference { name, .. } => refs.push(name),
+        Constant::BlockAddress { function, .. } => refs.push(function),
+        c => {
+            for sub in c.sub_constants() {
+                constant_global_refs(sub, refs);
+            }
+        },
+    }
+}
+
+/// The names of the named struct types used by some functions, global
+/// variables, and aliases, including the named structs used in the
+/// definitions of those
+struct NamedStructs<'a> {
+    types: &'a Types,
+    names: HashSet<String>,
+}
+
+impl<'a> NamedStructs<'a> {
+    fn new(types: &'a Types) -> Self {
+        Self {
+            types,
+            names: HashSet::new(),
+        }
+    }
+
+    fn add_type(&mut self, ty: &Type) {
+        match ty {
+            Type::PointerType { pointee_type, .. } => self.add_type(pointee_type),
+            Type::FuncType {
+                result_type,
+                param_types,
+                ..
+            } => {
+                self.add_type(result_type);
+                for param_type in param_types {
+                    self.add_type(param_type);
+                }
+            },
+            Type::VectorType { element_type, .. } | Type::ArrayType { element_type, .. } => {
+                self.add_type(element_type)
+            },
+            Type::StructType { element_types, .. } => {
+                for element_type in element_types {
+                    self.add_type(element_type);
+                }
+            },
+            Type::NamedStructType { name } => {
+                if !self.names.insert(name.clone()) {
+                    return;
+                }
+                let types = self.types;
+                if let Some(NamedStructDef::Defined(def)) = types.named_struct_def(name) {
+                    self.add_type(def);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn add_constant(&mut self, c: &Constant) {
+        self.add_type(&self.types.type_of(c));
+        if let Constant::GetElementPtr(gep) = c {
+            self.add_type(&gep.source_element_type);
+        }
+        for sub in c.sub_constants() {
+            self.add_constant(sub);
+        }
+    }
+
+    fn add_operand(&mut self, op: &Operand) {
+        match op {
+            Operand::LocalOperand { ty, .. } => self.add_type(ty),
+            Operand::ConstantOperand(c) => self.add_constant(c),
+            Operand::MetadataOperand(_) => {},
+        }
+    }
+
+    fn add_attributes(&mut self, attributes: &[ParameterAttribute]) {
+        for ty in attributes.iter().filter_map(ParameterAttribute::type_value) {
+            self.add_type(ty);
+        }
+    }
+
+    fn add_function(&mut self, func: &Function) {
+        self.add_type(&self.types.type_of(func));
+        for param in &func.parameters {
+            self.add_attributes(&param.attributes);
+        }
+        if let Some(personality) = &func.personality_function {
+            self.add_constant(personality);
+        }
+        for bb in &func.basic_blocks {
+            for inst in &bb.instrs {
+                self.add_type(&self.types.type_of(inst));
+                for op in inst.operands() {
+                    self.add_operand(op);
+                }
+                match inst {
+                    Instruction::Alloca(alloca) => self.add_type(&alloca.allocated_type),
+                    Instruction::Load(load) => self.add_type(&load.loaded_type),
+                    Instruction::GetElementPtr(gep) => self.add_type(&gep.source_element_type),
+                    Instruction::Call(call) => {
+                        self.add_type(&call.function_ty);
+                        for (_, attributes) in &call.arguments {
+                            self.add_attributes(attributes);
+                        }
+                    },
+                    _ => {},
+                }
+            }
+            self.add_type(&self.types.type_of(&bb.term));
+            for op in bb.term.operands() {
+                self.add_operand(op);
+            }
+            match &bb.term {
+                Terminator::Invoke(invoke) => {
+                    self.add_type(&invoke.function_ty);
+                    for (_, attributes) in &invoke.arguments {
+                        self.add_attributes(attributes);
+                    }
+                },
+                #[cfg(feature = "llvm-9-or-greater")]
+                Terminator::CallBr(callbr) => {
+                    self.add_type(&callbr.function_ty);
+                    for (_, attributes) in &callbr.arguments {
+                        self.add_attributes(attributes);
+                    }
+                },
+                _ => {},
+            }
+        }
+    }
+}
+
+/// Make the calls (and invokes) in `func` which directly call the function
+/// named `from` call `to` instead. Returns the number of calls changed.
+fn redirect_calls_in(func: &mut Function, from: &Name, to: &Name) -> usize {
+    let mut redirected = 0;
+    for bb in &mut func.basic_blocks {
+        let term_callee = match &mut bb.term {
+            Terminator::Invoke(invoke) => Some(&mut invoke.function),
+            #[cfg(feature = "llvm-9-or-greater")]
+            Terminator::CallBr(callbr) => Some(&mut callbr.function),
+            _ => None,
+        };
+        let callees = bb
+            .instrs
+            .iter_mut()
+            .filter_map(|inst| match inst {
+                Instruction::Call(call) => Some(&mut call.function),
+                _ => None,
+            })
+            .chain(term_callee);
+        for callee in callees {
+            let op = match callee {
+                Either::Right(op) => op,
+                Either::Left(_) => continue,
+            };
+            let redirected_callee = match op.as_constant() {
+                Some(Constant::GlobalReference {
+                    name,
+                    ty,
+                    kind,
+                    addr_space,
+                }) if name == from => Constant::GlobalReference {
+                    name: to.clone(),
+                    ty: ty.clone(),
+                    kind: *kind,
+                    addr_space: *addr_space,
+                },
+                _ => continue,
+            };
+            *op = Operand::ConstantOperand(ConstantRef::new(redirected_callee)).into();
+            redirected += 1;
+        }
+    }
+    redirected
+}
+
+/// The name of the global which the given constant refers to, looking
+/// through pointer casts and all-zero `GetElementPtr`s; or `None` if it
+/// doesn't refer to a global (for instance, because it is `null`)
+/// Get the bytes of a constant array of `i8`, or `None` if `c` is anything
+/// else
+fn byte_array_contents(c: &Constant) -> Option<Vec<u8>> {
+    let is_i8 = |ty: &TypeRef| matches!(ty.as_ref(), Type::IntegerType { bits: 8 });
+    match c {
+        Constant::RawDataArray { element_type, data } if is_i8(element_type) => Some(data.clone()),
+        Constant::Array {
+            element_type,
+            elements,
+        } if is_i8(element_type) => elements
+            .iter()
+            .map(|element| match element.as_ref() {
+                Constant::Int { bits: 8, value } => Some(*value as u8),
+                _ => None,
+            })
+            .collect(),
+        Constant::AggregateZero(ty) => match ty.as_ref() {
+            Type::ArrayType {
+                element_type,
+                num_elements,
+            } if is_i8(element_type) => Some(vec![0; *num_elements]),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn referenced_global(c: &ConstantRef) -> Option<&Name> {
+    match c.as_ref() {
+        Constant::GlobalReference { name, .. } => Some(name),
+        Constant::BitCast(bc) => referenced_global(&bc.operand),
+        Constant::AddrSpaceCast(ac) => referenced_global(&ac.operand),
+        Constant::GetElementPtr(gep)
+            if gep
+                .indices
+                .iter()
+                .all(|index| matches!(index.as_ref(), Constant::Int { value: 0, .. })) =>
+        {
+            referenced_global(&gep.address)
+        },
+        _ => None,
+    }
+}
+
 // ********* //
 // from_llvm //
 // ********* //
 
-use crate::constant::Constant;
 use crate::from_llvm::*;
 use crate::function::AttributesData;
 use llvm_sys::comdat::*;
@@ -509,74 +2656,234 @@ pub(crate) struct ModuleContext<'a> {
     // We use LLVMValueRef as a *const, even though it's technically a *mut
     #[allow(clippy::mutable_key_type)]
     pub constants: HashMap<LLVMValueRef, ConstantRef>,
-    /// Map from an llvm-sys global to its `Name`
+    /// Map from an llvm-sys value to the corresponding llvm-ir `OperandRef`,
+    /// for values used as (non-metadata) operands
+    // We use LLVMValueRef as a *const, even though it's technically a *mut
+    #[allow(clippy::mutable_key_type)]
+    pub operands: HashMap<LLVMValueRef, OperandRef>,
+    /// Map from an llvm-sys global to its `Name`. Use `global_name()`, which
+    /// also handles globals that weren't seen in the first pass.
     // We use LLVMValueRef as a *const, even though it's technically a *mut
     #[allow(clippy::mutable_key_type)]
-    pub global_names: &'a HashMap<LLVMValueRef, Name>,
+    global_names: HashMap<LLVMValueRef, Name>,
+    /// The number to give the next unnamed global that wasn't seen in the
+    /// first pass
+    next_global_number: usize,
+    /// Metadata nodes created so far; see `Module::metadata_nodes`
+    pub metadata_nodes: Vec<(MetadataNodeID, MetadataNode)>,
+    /// Map from an llvm-sys metadata node to its `MetadataNodeID`
+    pub metadata_node_ids: HashMap<LLVMMetadataRef, MetadataNodeID>,
+    /// The options the `Module` is being parsed with
+    pub options: &'a ParseOptions,
 }
 
 impl<'a> ModuleContext<'a> {
     // We use LLVMValueRef as a *const, even though it's technically a *mut
     #[allow(clippy::mutable_key_type)]
-    fn new(global_names: &'a HashMap<LLVMValueRef, Name>) -> Self {
+    fn new(
+        global_names: HashMap<LLVMValueRef, Name>,
+        types: TypesBuilder,
+        options: &'a ParseOptions,
+    ) -> Self {
+        let next_global_number = global_names
+            .values()
+            .filter(|name| matches!(name, Name::Number(_)))
+            .count();
         Self {
-            types: TypesBuilder::new(),
+            types,
             attrsdata: AttributesData::create(),
             constants: HashMap::new(),
+            operands: HashMap::new(),
             global_names,
+            next_global_number,
+            metadata_nodes: Vec::new(),
+            metadata_node_ids: HashMap::new(),
+            options,
         }
     }
+
+    /// Get the `Name` of the given llvm-sys global.
+    ///
+    /// Globals which weren't seen in the first pass over the module (for
+    /// instance, ones defined in another module, or added by LLVM while the
+    /// module was being read) get a placeholder: their own name if they have
+    /// one, or else a number after those of all the module's unnamed globals.
+    /// Either way, later references to the same global get the same `Name`.
+    pub fn global_name(&mut self, global: LLVMValueRef) -> Name {
+        if let Some(name) = self.global_names.get(&global) {
+            return name.clone();
+        }
+        let name = Name::name_or_num(
+            unsafe { get_value_name_bytes(global) },
+            &mut self.next_global_number,
+        );
+        debug!("Global {:?} wasn't seen in the first pass", name);
+        self.global_names.insert(global, name.clone());
+        name
+    }
 }
 
 impl Module {
-    pub(crate) fn from_llvm_ref(module: LLVMModuleRef) -> Self {
+    /// `shared_types`: if the `Module` is being parsed in a `Context`, the
+    /// `Context`'s types, which are updated with any new types in the
+    /// `Module`
+    pub(crate) fn from_llvm_ref(
+        module: LLVMModuleRef,
+        shared_types: Option<&mut TypesBuilder>,
+        options: &ParseOptions,
+    ) -> Result<Self, Error> {
         debug!("Creating a Module from an LLVMModuleRef");
         let mut global_ctr = 0; // this ctr is used to number global objects that aren't named
 
         // Modules require two passes over their contents.
         // First we make a pass just to map global objects -- in particular, Functions,
-        //   GlobalVariables, and GlobalAliases -- to Names; then we do the actual
-        //   detailed pass.
+        //   GlobalVariables, GlobalAliases, and (on LLVM 9+) ifuncs -- to Names; then we
+        //   do the actual detailed pass.
         // This is necessary because these structures may reference each other in a
         //   circular fashion, and we need to be able to fill in the Name of the
         //   referenced object from having only its `LLVMValueRef`.
-        // We use LLVMValueRef as a *const, even though it's technically a *mut
-        #[allow(clippy::mutable_key_type)]
-        let global_names: HashMap<LLVMValueRef, Name> = get_defined_functions(module)
+        // References to globals which this pass doesn't find get a placeholder
+        //   `Name`; see `ModuleContext::global_name()`.
+        let global_values = get_defined_functions(module)
             .chain(get_declared_functions(module))
             .chain(get_globals(module))
-            .chain(get_global_aliases(module))
+            .chain(get_global_aliases(module));
+        #[cfg(feature = "llvm-9-or-greater")]
+        let global_values = global_values.chain(get_global_ifuncs(module));
+        // We use LLVMValueRef as a *const, even though it's technically a *mut
+        #[allow(clippy::mutable_key_type)]
+        let global_names: HashMap<LLVMValueRef, Name> = global_values
             .map(|g| {
                 (
                     g,
-                    Name::name_or_num(unsafe { get_value_name(g) }, &mut global_ctr),
+                    Name::name_or_num(unsafe { get_value_name_bytes(g) }, &mut global_ctr),
                 )
             })
             .collect();
         global_ctr = 0; // reset the global_ctr; the second pass should number everything exactly the same though
 
-        let mut ctx = ModuleContext::new(&global_names);
+        let types = match &shared_types {
+            Some(shared) => shared.clone_without_defs(),
+            None => TypesBuilder::new(),
+        };
+        let mut ctx = ModuleContext::new(global_names, types, options);
 
-        Self {
+        // Attribute groups are numbered in order of first appearance: first
+        // the function attributes of each function (defined or declared), in
+        // module order, and then those of each call site
+        let mut function_attribute_groups = vec![];
+        let defined_function_groups: Vec<Option<GroupID>> = get_functions(module)
+            .filter_map(|f| {
+                let attrs = FunctionAttribute::all_from_function(f, &ctx.attrsdata);
+                let group =
+                    FunctionAttributeGroup::find_or_insert(&mut function_attribute_groups, &attrs);
+                if unsafe { LLVMIsDeclaration(f) } == 0 {
+                    Some(group)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        let mut functions: Vec<Function> = get_defined_functions(module)
+            .map(|f| {
+                let with_body = match &options.skip_function_bodies {
+                    Some(skip) => !skip(&unsafe { get_value_name(f) }),
+                    None => true,
+                };
+                match Function::from_llvm_ref(f, &mut ctx, with_body) {
+                    Err(err) if options.lossy && with_body => {
+                        log::warn!("Skipping the body of a function: {}", err);
+                        Function::from_llvm_ref(f, &mut ctx, false)
+                    },
+                    result => result,
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        for (func, group) in functions.iter_mut().zip(defined_function_groups) {
+            func.function_attribute_group = group;
+        }
+        for bb in functions.iter().flat_map(|f| &f.basic_blocks) {
+            for inst in &bb.instrs {
+                if let Instruction::Call(call) = inst {
+                    FunctionAttributeGroup::find_or_insert(
+                        &mut function_attribute_groups,
+                        &call.function_attributes,
+                    );
+                }
+            }
+            match &bb.term {
+                Terminator::Invoke(invoke) => {
+                    FunctionAttributeGroup::find_or_insert(
+                        &mut function_attribute_groups,
+                        &invoke.function_attributes,
+                    );
+                },
+                #[cfg(feature = "llvm-9-or-greater")]
+                Terminator::CallBr(callbr) => {
+                    FunctionAttributeGroup::find_or_insert(
+                        &mut function_attribute_groups,
+                        &callbr.function_attributes,
+                    );
+                },
+                _ => {},
+            }
+        }
+
+        let global_vars = get_globals(module)
+            .map(|g| GlobalVariable::from_llvm_ref(g, &mut global_ctr, &mut ctx))
+            .collect::<Result<_, _>>()?;
+        let global_aliases = get_global_aliases(module)
+            .map(|g| GlobalAlias::from_llvm_ref(g, &mut global_ctr, &mut ctx))
+            .collect::<Result<_, _>>()?;
+        if let Some(shared) = shared_types {
+            *shared = ctx.types.clone_without_defs();
+        }
+
+        let mut parsed = Self {
             name: unsafe { get_module_identifier(module) },
             source_file_name: unsafe { get_source_file_name(module) },
             data_layout: DataLayout::from_module_ref(module),
             target_triple: unsafe { get_target(module) },
-            functions: get_defined_functions(module)
-                .map(|f| Function::from_llvm_ref(f, &mut ctx))
-                .collect(),
-            global_vars: get_globals(module)
-                .map(|g| GlobalVariable::from_llvm_ref(g, &mut global_ctr, &mut ctx))
-                .collect(),
-            global_aliases: get_global_aliases(module)
-                .map(|g| GlobalAlias::from_llvm_ref(g, &mut global_ctr, &mut ctx))
-                .collect(),
-            // function_attribute_groups: unimplemented!("function_attribute_groups"),  // llvm-hs collects these in the decoder monad or something
-            inline_assembly: unsafe { get_module_inline_asm(module) },
-            // metadata_nodes: unimplemented!("metadata_nodes"),
+            functions,
+            global_vars,
+            global_aliases,
+            function_attribute_groups,
+            inline_assembly: split_inline_asm(&unsafe { get_module_inline_asm(module) }),
+            metadata_nodes: std::mem::take(&mut ctx.metadata_nodes),
+            upgraded_intrinsics: vec![], // filled in by `parse_bc_path()`, if at all
+            producers: Producer::all_from_module_ref(module),
+            llvm_version: Some(crate::llvm_version().to_owned()),
             // named_metadatas: unimplemented!("named_metadatas"),
             // comdats: unimplemented!("comdats"),  // I think llvm-hs also collects these along the way
             types: ctx.types.build(),
+        };
+        if options.name_unnamed_locals {
+            parsed.name_unnamed_locals();
+        }
+        Ok(parsed)
+    }
+}
+
+impl FunctionAttributeGroup {
+    /// Get the ID of the group in `groups` with exactly the given attributes,
+    /// adding a new group if there is none. Returns `None` if `attrs` is empty.
+    fn find_or_insert(
+        groups: &mut Vec<FunctionAttributeGroup>,
+        attrs: &[FunctionAttribute],
+    ) -> Option<GroupID> {
+        if attrs.is_empty() {
+            return None;
+        }
+        match groups.iter().find(|group| group.attrs == attrs) {
+            Some(group) => Some(group.group_id),
+            None => {
+                let group_id = groups.len();
+                groups.push(FunctionAttributeGroup {
+                    group_id,
+                    attrs: attrs.to_vec(),
+                });
+                Some(group_id)
+            },
         }
     }
 }
@@ -586,15 +2893,14 @@ impl GlobalVariable {
         global: LLVMValueRef,
         ctr: &mut usize,
         ctx: &mut ModuleContext,
-    ) -> Self {
+    ) -> Result<Self, Error> {
         let ty = ctx.types.type_from_llvm_ref(unsafe { LLVMTypeOf(global) });
-        let addr_space = match ty.as_ref() {
-            Type::PointerType { addr_space, .. } => *addr_space,
-            _ => panic!("GlobalVariable has a non-pointer type, {:?}", ty),
-        };
+        let addr_space = ty
+            .addr_space()
+            .unwrap_or_else(|| panic!("GlobalVariable has a non-pointer type, {:?}", ty));
         debug!("Processing a GlobalVariable with type {:?}", ty);
-        Self {
-            name: Name::name_or_num(unsafe { get_value_name(global) }, ctr),
+        Ok(Self {
+            name: Name::name_or_num(unsafe { get_value_name_bytes(global) }, ctr),
             linkage: Linkage::from_llvm(unsafe { LLVMGetLinkage(global) }),
             visibility: Visibility::from_llvm(unsafe { LLVMGetVisibility(global) }),
             is_constant: unsafe { LLVMIsGlobalConstant(global) } != 0,
@@ -612,7 +2918,7 @@ impl GlobalVariable {
                 if it.is_null() {
                     None
                 } else {
-                    Some(Constant::from_llvm_ref(it, ctx))
+                    Some(Constant::from_llvm_ref(it, ctx)?)
                 }
             },
             section: unsafe { get_section(global) },
@@ -624,11 +2930,11 @@ impl GlobalVariable {
                     Some(Comdat::from_llvm_ref(unsafe { LLVMGetComdat(global) }))
                 }
             },
-            alignment: unsafe { LLVMGetAlignment(global) },
+            alignment: NonZeroU32::new(unsafe { LLVMGetAlignment(global) }),
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_no_col(global),
+            debugloc: DebugLoc::from_llvm_no_col(global, ctx),
             // metadata: unimplemented!("metadata"),
-        }
+        })
     }
 }
 
@@ -637,15 +2943,14 @@ impl GlobalAlias {
         alias: LLVMValueRef,
         ctr: &mut usize,
         ctx: &mut ModuleContext,
-    ) -> Self {
+    ) -> Result<Self, Error> {
         let ty = ctx.types.type_from_llvm_ref(unsafe { LLVMTypeOf(alias) });
-        let addr_space = match ty.as_ref() {
-            Type::PointerType { addr_space, .. } => *addr_space,
-            _ => panic!("GlobalAlias has a non-pointer type, {:?}", ty),
-        };
-        Self {
-            name: Name::name_or_num(unsafe { get_value_name(alias) }, ctr),
-            aliasee: Constant::from_llvm_ref(unsafe { LLVMAliasGetAliasee(alias) }, ctx),
+        let addr_space = ty
+            .addr_space()
+            .unwrap_or_else(|| panic!("GlobalAlias has a non-pointer type, {:?}", ty));
+        Ok(Self {
+            name: Name::name_or_num(unsafe { get_value_name_bytes(alias) }, ctr),
+            aliasee: Constant::from_llvm_ref(unsafe { LLVMAliasGetAliasee(alias) }, ctx)?,
             linkage: Linkage::from_llvm(unsafe { LLVMGetLinkage(alias) }),
             visibility: Visibility::from_llvm(unsafe { LLVMGetVisibility(alias) }),
             ty,
@@ -653,7 +2958,7 @@ impl GlobalAlias {
             dll_storage_class: DLLStorageClass::from_llvm(unsafe { LLVMGetDLLStorageClass(alias) }),
             thread_local_mode: ThreadLocalMode::from_llvm(unsafe { LLVMGetThreadLocalMode(alias) }),
             unnamed_addr: UnnamedAddr::from_llvm(unsafe { LLVMGetUnnamedAddress(alias) }),
-        }
+        })
     }
 }
 
@@ -769,15 +3074,49 @@ impl Default for DataLayout {
             alignments: Alignments::default(),
             mangling: None,
             native_int_widths: None,
-            non_integral_ptr_types: HashSet::new(),
+            non_integral_ptr_types: BTreeSet::new(),
         }
     }
 }
 
+impl Producer {
+    /// Get the `Producer`s from the module's `!llvm.ident` metadata
+    fn all_from_module_ref(module: LLVMModuleRef) -> Vec<Self> {
+        let name = "llvm.ident\0".as_ptr() as *const _;
+        let num_idents = unsafe { LLVMGetNamedMetadataNumOperands(module, name) } as usize;
+        let mut idents = vec![std::ptr::null_mut(); num_idents];
+        unsafe { LLVMGetNamedMetadataOperands(module, name, idents.as_mut_ptr()) };
+        idents
+            .into_iter()
+            .filter_map(|ident| {
+                // each entry is a node with a single string operand
+                let num_operands = unsafe { LLVMGetMDNodeNumOperands(ident) } as usize;
+                let mut operands = vec![std::ptr::null_mut(); num_operands];
+                unsafe { LLVMGetMDNodeOperands(ident, operands.as_mut_ptr()) };
+                let mut len = 0;
+                let ptr = unsafe { LLVMGetMDString(*operands.first()?, &mut len) };
+                if ptr.is_null() {
+                    None
+                } else {
+                    let ident = unsafe { raw_to_string_with_len(ptr, len as usize) };
+                    Some(Self::parse(&ident))
+                }
+            })
+            .collect()
+    }
+}
+
 impl DataLayout {
     pub(crate) fn from_module_ref(module: LLVMModuleRef) -> Self {
-        let layout_str = unsafe { get_data_layout_str(module) };
-        let mut data_layout = DataLayout { layout_str, ..Default::default() };
+        Self::from_layout_str(unsafe { get_data_layout_str(module) })
+    }
+
+    /// Parse a data layout string, as it appears in a `.ll` or `.bc` file
+    pub(crate) fn from_layout_str(layout_str: String) -> Self {
+        let mut data_layout = DataLayout {
+            layout_str,
+            ..Default::default()
+        };
         for spec in data_layout.layout_str.split('-') {
             if spec == "E" {
                 data_layout.endianness = Endianness::BigEndian;
@@ -995,7 +3334,7 @@ impl DataLayout {
             } else if spec.starts_with('n') {
                 let native_int_widths = data_layout
                     .native_int_widths
-                    .get_or_insert_with(HashSet::new);
+                    .get_or_insert_with(BTreeSet::new);
                 let mut chunks = spec.split(':');
                 let first_chunk = chunks.next().unwrap();
                 let size = first_chunk[1 ..]
@@ -1019,9 +3358,9 @@ impl DataLayout {
 impl Default for Alignments {
     fn default() -> Self {
         Self {
-            /// Explicit alignments for various sizes of integers (in bits). Sizes not
-            /// specified here are determined according to the rules described in the
-            /// Data Layout docs.
+            // Explicit alignments for various sizes of integers (in bits). Sizes not
+            // specified here are determined according to the rules described in the
+            // Data Layout docs.
             int_alignments: vec![
                 (1, Alignment { abi: 8, pref: 8 }),
                 (8, Alignment { abi: 8, pref: 8 }),
@@ -1031,9 +3370,9 @@ impl Default for Alignments {
             ]
             .into_iter()
             .collect(),
-            /// Explicit alignments for various sizes of vectors (in bits). Sizes not
-            /// specified here are determined according to the rules described in the
-            /// Data Layout docs.
+            // Explicit alignments for various sizes of vectors (in bits). Sizes not
+            // specified here are determined according to the rules described in the
+            // Data Layout docs.
             vec_alignments: vec![
                 (64, Alignment { abi: 64, pref: 64 }),
                 (
@@ -1046,7 +3385,7 @@ impl Default for Alignments {
             ]
             .into_iter()
             .collect(),
-            /// Alignment for floating-point types, by size (in bits)
+            // Alignment for floating-point types, by size (in bits)
             fp_alignments: vec![
                 (16, Alignment { abi: 16, pref: 16 }),
                 (32, Alignment { abi: 32, pref: 32 }),
@@ -1061,18 +3400,18 @@ impl Default for Alignments {
             ]
             .into_iter()
             .collect(),
-            /// Alignment for aggregate types (structs, arrays)
+            // Alignment for aggregate types (structs, arrays)
             agg_alignment: Alignment { abi: 0, pref: 64 },
-            /// Alignment for function pointers
+            // Alignment for function pointers
             #[cfg(feature = "llvm-9-or-greater")]
             fptr_alignment: FunctionPtrAlignment {
                 independent: true,
                 abi: 64,
             },
-            /// Alignment for function pointers, as an `Alignment`
+            // Alignment for function pointers, as an `Alignment`
             #[cfg(feature = "llvm-9-or-greater")]
             fptr_alignment_as_alignment: Alignment { abi: 64, pref: 64 },
-            /// Layout details for (non-function-pointer) pointers, by address space
+            // Layout details for (non-function-pointer) pointers, by address space
             pointer_layouts: vec![(
                 0,
                 PointerLayout {
@@ -1086,3 +3425,57 @@ impl Default for Alignments {
         }
     }
 }
+
+#[cfg(all(test, feature = "llvm-14"))]
+mod tests {
+    use super::*;
+    use crate::constant::Constant;
+
+    /// No valid module refers to a global which isn't in it, so build one
+    /// which does: globals initialized with (pointers to) globals in another
+    /// module
+    #[test]
+    fn placeholder_global_names() {
+        let context = crate::from_llvm::Context::new();
+        unsafe {
+            let i8_ty = LLVMInt8TypeInContext(context.ctx);
+            let ptr_ty = LLVMPointerType(i8_ty, 0);
+            let other =
+                LLVMModuleCreateWithNameInContext(b"other\0".as_ptr() as *const _, context.ctx);
+            let foreign_unnamed = LLVMAddGlobal(other, i8_ty, b"\0".as_ptr() as *const _);
+            let foreign_named = LLVMAddGlobal(other, i8_ty, b"foreign\0".as_ptr() as *const _);
+
+            let module =
+                LLVMModuleCreateWithNameInContext(b"module\0".as_ptr() as *const _, context.ctx);
+            LLVMAddGlobal(module, i8_ty, b"\0".as_ptr() as *const _);
+            for (name, target) in [
+                (&b"a\0"[..], foreign_unnamed),
+                (&b"b\0"[..], foreign_named),
+                (&b"c\0"[..], foreign_unnamed),
+            ] {
+                let global = LLVMAddGlobal(module, ptr_ty, name.as_ptr() as *const _);
+                LLVMSetInitializer(global, target);
+            }
+
+            let parsed = Module::from_llvm_ref(module, None, &ParseOptions::default())
+                .expect("Failed to parse module");
+            LLVMDisposeModule(module);
+            LLVMDisposeModule(other);
+
+            let referenced: Vec<&Name> = parsed
+                .global_vars
+                .iter()
+                .filter_map(|var| match var.initializer.as_deref() {
+                    Some(Constant::GlobalReference { name, .. }) => Some(name),
+                    _ => None,
+                })
+                .collect();
+            // the unnamed global gets the number after the module's own
+            // unnamed global, and keeps it
+            assert_eq!(
+                referenced,
+                vec![&Name::from(1), &Name::from("foreign"), &Name::from(1)]
+            );
+        }
+    }
+}
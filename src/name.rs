@@ -1,8 +1,16 @@
+use crate::constant::ConstantRef;
+use either::Either;
 use std::fmt;
 
 /// Many LLVM objects have a `Name`, which is either a string name, or just a
 /// sequential numbering (e.g. `%3`).
+///
+/// LLVM allows names to be arbitrary byte strings. Names which are valid UTF-8
+/// are always represented as `Name::Name`, and only names which aren't are
+/// represented as `Name::Bytes`; so two `Name`s are equal exactly when the
+/// names are. `Name::from()` a `Vec<u8>` picks the right variant.
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Name {
     /// has a string name
     // with `Box`, the enum `Name` has size 16 bytes, vs with a `String`
@@ -14,18 +22,70 @@ pub enum Name {
     Name(Box<String>),
     /// doesn't have a string name and was given this sequential number
     Number(usize),
+    /// has a name which isn't valid UTF-8
+    Bytes(Box<Vec<u8>>),
 }
 
 impl Name {
-    pub(crate) fn name_or_num(s: String, ctr: &mut usize) -> Self {
-        if s.is_empty() {
+    pub(crate) fn name_or_num(bytes: Vec<u8>, ctr: &mut usize) -> Self {
+        if bytes.is_empty() {
             let rval = Name::Number(*ctr);
             *ctr += 1;
             rval
         } else {
-            Name::Name(Box::new(s))
+            Name::from(bytes)
         }
     }
+
+    /// Get the bytes of the name, or `None` if this is a `Name::Number`
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Name::Name(s) => Some(s.as_bytes()),
+            Name::Number(_) => None,
+            Name::Bytes(bytes) => Some(bytes),
+        }
+    }
+
+    /// The heap memory owned by this `Name`, in bytes
+    pub(crate) fn heap_size(&self) -> usize {
+        match self {
+            Name::Name(s) => std::mem::size_of::<String>() + s.capacity(),
+            Name::Number(_) => 0,
+            Name::Bytes(bytes) => std::mem::size_of::<Vec<u8>>() + bytes.capacity(),
+        }
+    }
+
+    /// Write the name as it appears in LLVM assembly, after the given sigil
+    /// (`%` for local values, `@` for globals). Names which can't be parsed
+    /// back as bare identifiers (those which contain anything other than
+    /// letters, digits, `$`, `-`, `.`, and `_`, or which start with a digit)
+    /// are quoted, with special bytes escaped as `\XX`, as in LLVM.
+    pub(crate) fn fmt_with_sigil(&self, f: &mut fmt::Formatter, sigil: char) -> fmt::Result {
+        let bytes = match self {
+            Name::Number(n) => return write!(f, "{}{}", sigil, n),
+            Name::Name(s) => s.as_bytes(),
+            Name::Bytes(bytes) => bytes,
+        };
+        let is_plain = |b: &u8| b.is_ascii_alphanumeric() || matches!(b, b'$' | b'-' | b'.' | b'_');
+        let needs_quotes = match bytes.first() {
+            None => true,
+            Some(first) => first.is_ascii_digit() || !bytes.iter().all(is_plain),
+        };
+        if !needs_quotes {
+            // all ASCII, so this is always valid UTF-8
+            return write!(f, "{}{}", sigil, String::from_utf8_lossy(bytes));
+        }
+        write!(f, "{}\"", sigil)?;
+        for &b in bytes {
+            let printable = b.is_ascii_graphic() || b == b' ';
+            if printable && b != b'\\' && b != b'"' {
+                write!(f, "{}", b as char)?;
+            } else {
+                write!(f, "\\{:02X}", b)?;
+            }
+        }
+        write!(f, "\"")
+    }
 }
 
 impl From<String> for Name {
@@ -40,6 +100,15 @@ impl From<&str> for Name {
     }
 }
 
+impl From<Vec<u8>> for Name {
+    fn from(bytes: Vec<u8>) -> Self {
+        match String::from_utf8(bytes) {
+            Ok(s) => Name::Name(Box::new(s)),
+            Err(e) => Name::Bytes(Box::new(e.into_bytes())),
+        }
+    }
+}
+
 impl From<usize> for Name {
     fn from(u: usize) -> Self {
         Name::Number(u)
@@ -48,9 +117,57 @@ impl From<usize> for Name {
 
 impl fmt::Display for Name {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_with_sigil(f, '%')
+    }
+}
+
+/// Implemented by things which may contain the `Name`s of local values or
+/// basic blocks, so that those `Name`s can be rewritten in place (e.g., by
+/// [`Function::rename_locals()`](../function/struct.Function.html#method.rename_locals)).
+pub(crate) trait MapLocalNames {
+    /// Apply `f` to each local `Name` (whether defined or used), in place
+    fn map_local_names(&mut self, f: &mut dyn FnMut(&mut Name));
+}
+
+impl MapLocalNames for Name {
+    fn map_local_names(&mut self, f: &mut dyn FnMut(&mut Name)) {
+        f(self)
+    }
+}
+
+/// Constants can only refer to global values, never to local ones
+impl MapLocalNames for ConstantRef {
+    fn map_local_names(&mut self, _f: &mut dyn FnMut(&mut Name)) {}
+}
+
+impl<T: MapLocalNames> MapLocalNames for Vec<T> {
+    fn map_local_names(&mut self, f: &mut dyn FnMut(&mut Name)) {
+        for t in self {
+            t.map_local_names(f);
+        }
+    }
+}
+
+impl<T: MapLocalNames> MapLocalNames for Option<T> {
+    fn map_local_names(&mut self, f: &mut dyn FnMut(&mut Name)) {
+        if let Some(t) = self {
+            t.map_local_names(f);
+        }
+    }
+}
+
+impl<A: MapLocalNames, B: MapLocalNames> MapLocalNames for (A, B) {
+    fn map_local_names(&mut self, f: &mut dyn FnMut(&mut Name)) {
+        self.0.map_local_names(f);
+        self.1.map_local_names(f);
+    }
+}
+
+impl<A: MapLocalNames, B: MapLocalNames> MapLocalNames for Either<A, B> {
+    fn map_local_names(&mut self, f: &mut dyn FnMut(&mut Name)) {
         match self {
-            Name::Name(s) => write!(f, "%{}", s),
-            Name::Number(n) => write!(f, "%{}", n),
+            Either::Left(a) => a.map_local_names(f),
+            Either::Right(b) => b.map_local_names(f),
         }
     }
 }
@@ -0,0 +1,1971 @@
+//! A bitcode reader written in Rust, which creates a `Module` directly from
+//! the bitcode rather than going through LLVM. See
+//! [`Module::from_bc_path_native()`](../module/struct.Module.html#method.from_bc_path_native).
+//!
+//! Only a subset of bitcode is supported: modules written by LLVM 5 or later
+//! (which keep names in a string table), containing global variables,
+//! functions, and aliases, and functions built from the common instructions
+//! (arithmetic, comparisons, casts, memory accesses, `getelementptr`, `phi`,
+//! `select`, calls, and the simple terminators). Attributes, metadata, and
+//! debug locations are not read. Anything else gives `Error::Unsupported`.
+
+use crate::bitstream::{BitstreamReader, Entry, Record};
+use crate::constant::{self, BigIntValue, Constant, ConstantRef, Float, GlobalKind};
+use crate::error::Error;
+use crate::function::{CallingConvention, Function, Parameter};
+use crate::instruction::{self, Instruction};
+use crate::module::{
+    split_inline_asm,
+    AddrSpace,
+    Comdat,
+    DLLStorageClass,
+    DataLayout,
+    GlobalAlias,
+    GlobalVariable,
+    Linkage,
+    Module,
+    SelectionKind,
+    ThreadLocalMode,
+    UnnamedAddr,
+    Visibility,
+};
+use crate::name::{MapLocalNames, Name};
+use crate::operand::{Operand, OperandRef};
+use crate::predicates::{FPPredicate, IntPredicate};
+use crate::terminator::{self, Terminator};
+use crate::types::{FPType, NamedStructDef, Type, TypeRef, TypesBuilder};
+use crate::BasicBlock;
+use either::Either;
+use std::collections::HashMap;
+use std::num::NonZeroU32;
+
+// Block IDs
+const MODULE_BLOCK_ID: u64 = 8;
+const CONSTANTS_BLOCK_ID: u64 = 11;
+const FUNCTION_BLOCK_ID: u64 = 12;
+const VALUE_SYMTAB_BLOCK_ID: u64 = 14;
+const TYPE_BLOCK_ID: u64 = 17;
+const STRTAB_BLOCK_ID: u64 = 23;
+
+// Record codes in the module block
+const MODULE_CODE_VERSION: u64 = 1;
+const MODULE_CODE_TRIPLE: u64 = 2;
+const MODULE_CODE_DATALAYOUT: u64 = 3;
+const MODULE_CODE_ASM: u64 = 4;
+const MODULE_CODE_SECTIONNAME: u64 = 5;
+const MODULE_CODE_GLOBALVAR: u64 = 7;
+const MODULE_CODE_FUNCTION: u64 = 8;
+const MODULE_CODE_ALIAS_OLD: u64 = 9;
+const MODULE_CODE_GCNAME: u64 = 11;
+const MODULE_CODE_COMDAT: u64 = 12;
+const MODULE_CODE_ALIAS: u64 = 14;
+const MODULE_CODE_SOURCE_FILENAME: u64 = 16;
+const MODULE_CODE_IFUNC: u64 = 18;
+
+// Record codes in the type block
+const TYPE_CODE_NUMENTRY: u64 = 1;
+const TYPE_CODE_VOID: u64 = 2;
+const TYPE_CODE_FLOAT: u64 = 3;
+const TYPE_CODE_DOUBLE: u64 = 4;
+const TYPE_CODE_LABEL: u64 = 5;
+const TYPE_CODE_OPAQUE: u64 = 6;
+const TYPE_CODE_INTEGER: u64 = 7;
+const TYPE_CODE_POINTER: u64 = 8;
+const TYPE_CODE_HALF: u64 = 10;
+const TYPE_CODE_ARRAY: u64 = 11;
+const TYPE_CODE_VECTOR: u64 = 12;
+const TYPE_CODE_X86_FP80: u64 = 13;
+const TYPE_CODE_FP128: u64 = 14;
+const TYPE_CODE_PPC_FP128: u64 = 15;
+const TYPE_CODE_METADATA: u64 = 16;
+const TYPE_CODE_X86_MMX: u64 = 17;
+const TYPE_CODE_STRUCT_ANON: u64 = 18;
+const TYPE_CODE_STRUCT_NAME: u64 = 19;
+const TYPE_CODE_STRUCT_NAMED: u64 = 20;
+const TYPE_CODE_FUNCTION: u64 = 21;
+const TYPE_CODE_TOKEN: u64 = 22;
+#[cfg(feature = "llvm-11-or-greater")]
+const TYPE_CODE_BFLOAT: u64 = 23;
+#[cfg(feature = "llvm-12-or-greater")]
+const TYPE_CODE_X86_AMX: u64 = 24;
+const TYPE_CODE_OPAQUE_POINTER: u64 = 25;
+
+// Record codes in the constants block
+const CST_CODE_SETTYPE: u64 = 1;
+const CST_CODE_NULL: u64 = 2;
+const CST_CODE_UNDEF: u64 = 3;
+const CST_CODE_INTEGER: u64 = 4;
+const CST_CODE_WIDE_INTEGER: u64 = 5;
+const CST_CODE_FLOAT: u64 = 6;
+const CST_CODE_AGGREGATE: u64 = 7;
+const CST_CODE_STRING: u64 = 8;
+const CST_CODE_CSTRING: u64 = 9;
+const CST_CODE_CE_BINOP: u64 = 10;
+const CST_CODE_CE_CAST: u64 = 11;
+const CST_CODE_CE_GEP: u64 = 12;
+const CST_CODE_CE_SELECT: u64 = 13;
+const CST_CODE_CE_CMP: u64 = 17;
+const CST_CODE_CE_INBOUNDS_GEP: u64 = 20;
+const CST_CODE_BLOCKADDRESS: u64 = 21;
+const CST_CODE_DATA: u64 = 22;
+const CST_CODE_CE_GEP_WITH_INRANGE_INDEX: u64 = 24;
+const CST_CODE_CE_UNOP: u64 = 25;
+#[cfg(feature = "llvm-12-or-greater")]
+const CST_CODE_POISON: u64 = 26;
+
+// Record codes in the function block
+const FUNC_CODE_DECLAREBLOCKS: u64 = 1;
+const FUNC_CODE_INST_BINOP: u64 = 2;
+const FUNC_CODE_INST_CAST: u64 = 3;
+const FUNC_CODE_INST_RET: u64 = 10;
+const FUNC_CODE_INST_BR: u64 = 11;
+const FUNC_CODE_INST_SWITCH: u64 = 12;
+const FUNC_CODE_INST_UNREACHABLE: u64 = 15;
+const FUNC_CODE_INST_PHI: u64 = 16;
+const FUNC_CODE_INST_ALLOCA: u64 = 19;
+const FUNC_CODE_INST_LOAD: u64 = 20;
+const FUNC_CODE_INST_EXTRACTVAL: u64 = 26;
+const FUNC_CODE_INST_INSERTVAL: u64 = 27;
+const FUNC_CODE_INST_CMP2: u64 = 28;
+const FUNC_CODE_INST_VSELECT: u64 = 29;
+const FUNC_CODE_DEBUG_LOC_AGAIN: u64 = 33;
+const FUNC_CODE_INST_CALL: u64 = 34;
+const FUNC_CODE_DEBUG_LOC: u64 = 35;
+const FUNC_CODE_INST_GEP: u64 = 43;
+const FUNC_CODE_INST_STORE: u64 = 44;
+const FUNC_CODE_INST_UNOP: u64 = 56;
+#[cfg(feature = "llvm-10-or-greater")]
+const FUNC_CODE_INST_FREEZE: u64 = 58;
+
+// Record codes in the value symbol table and string table blocks
+const VST_CODE_ENTRY: u64 = 1;
+const VST_CODE_BBENTRY: u64 = 2;
+const STRTAB_BLOB: u64 = 1;
+
+/// Create a `Module` from the given bitcode, using `name` as the module name
+pub(crate) fn read_module(bitcode: &[u8], name: String) -> Result<Module, Error> {
+    let strtab = read_strtab(bitcode)?;
+    let mut reader = BitstreamReader::new(bitcode)?;
+    while let Some(entry) = reader.next()? {
+        match entry {
+            Entry::SubBlock(block) if block.id == MODULE_BLOCK_ID => {
+                reader.enter_block(&block);
+                let mut module = ModuleReader::new(strtab);
+                module.read_module_block(&mut reader)?;
+                return module.finish(name);
+            },
+            Entry::SubBlock(block) => reader.skip_block(&block),
+            Entry::Record(_) | Entry::EndBlock => {},
+        }
+    }
+    Err(Error::InvalidBitcode)
+}
+
+/// Find the string table, which follows the module block
+fn read_strtab(bitcode: &[u8]) -> Result<&[u8], Error> {
+    let mut reader = BitstreamReader::new(bitcode)?;
+    while let Some(entry) = reader.next()? {
+        match entry {
+            Entry::SubBlock(block) if block.id == STRTAB_BLOCK_ID => {
+                reader.enter_block(&block);
+                loop {
+                    match reader.next()?.ok_or(Error::InvalidBitcode)? {
+                        Entry::Record(record) if record.code == STRTAB_BLOB => {
+                            return record.blob.ok_or(Error::InvalidBitcode);
+                        },
+                        Entry::Record(_) => {},
+                        Entry::SubBlock(block) => reader.skip_block(&block),
+                        Entry::EndBlock => break,
+                    }
+                }
+            },
+            Entry::SubBlock(block) => reader.skip_block(&block),
+            Entry::Record(_) | Entry::EndBlock => {},
+        }
+    }
+    Ok(&[])
+}
+
+fn unsupported(message: impl Into<String>) -> Error {
+    Error::unsupported(message)
+}
+
+/// Get operand `i` of a record
+fn op(ops: &[u64], i: usize) -> Result<u64, Error> {
+    ops.get(i).copied().ok_or(Error::InvalidBitcode)
+}
+
+/// Signed values are stored with the sign in the lowest bit
+fn decode_sign_rotated(value: u64) -> u64 {
+    if value & 1 == 0 {
+        value >> 1
+    } else if value != 1 {
+        (value >> 1).wrapping_neg()
+    } else {
+        // a "negative zero" stands for the most negative value
+        1 << 63
+    }
+}
+
+/// Alignments are stored as their log2, plus one, with 0 meaning none
+fn decode_alignment(encoded: u64) -> Result<Option<NonZeroU32>, Error> {
+    match encoded {
+        0 => Ok(None),
+        1 ..= 32 => Ok(NonZeroU32::new(1 << (encoded - 1))),
+        _ => Err(Error::InvalidBitcode),
+    }
+}
+
+fn decode_linkage(code: u64) -> Result<Linkage, Error> {
+    Ok(match code {
+        0 | 5 | 6 | 15 => Linkage::External,
+        1 | 16 => Linkage::WeakAny,
+        2 => Linkage::Appending,
+        3 => Linkage::Internal,
+        4 | 18 => Linkage::LinkOnceAny,
+        7 => Linkage::ExternalWeak,
+        8 => Linkage::Common,
+        9 | 13 | 14 => Linkage::Private,
+        10 | 17 => Linkage::WeakODR,
+        11 | 19 => Linkage::LinkOnceODR,
+        12 => Linkage::AvailableExternally,
+        _ => return Err(Error::InvalidBitcode),
+    })
+}
+
+fn decode_visibility(code: u64) -> Result<Visibility, Error> {
+    match code {
+        0 => Ok(Visibility::Default),
+        1 => Ok(Visibility::Hidden),
+        2 => Ok(Visibility::Protected),
+        _ => Err(Error::InvalidBitcode),
+    }
+}
+
+fn decode_dll_storage_class(code: u64) -> Result<DLLStorageClass, Error> {
+    match code {
+        0 => Ok(DLLStorageClass::Default),
+        1 => Ok(DLLStorageClass::Import),
+        2 => Ok(DLLStorageClass::Export),
+        _ => Err(Error::InvalidBitcode),
+    }
+}
+
+fn decode_thread_local_mode(code: u64) -> Result<ThreadLocalMode, Error> {
+    match code {
+        0 => Ok(ThreadLocalMode::NotThreadLocal),
+        1 => Ok(ThreadLocalMode::GeneralDynamic),
+        2 => Ok(ThreadLocalMode::LocalDynamic),
+        3 => Ok(ThreadLocalMode::InitialExec),
+        4 => Ok(ThreadLocalMode::LocalExec),
+        _ => Err(Error::InvalidBitcode),
+    }
+}
+
+fn decode_unnamed_addr(code: u64) -> Result<Option<UnnamedAddr>, Error> {
+    match code {
+        0 => Ok(None),
+        1 => Ok(Some(UnnamedAddr::Global)),
+        2 => Ok(Some(UnnamedAddr::Local)),
+        _ => Err(Error::InvalidBitcode),
+    }
+}
+
+fn decode_selection_kind(code: u64) -> Result<SelectionKind, Error> {
+    match code {
+        1 => Ok(SelectionKind::Any),
+        2 => Ok(SelectionKind::ExactMatch),
+        3 => Ok(SelectionKind::Largest),
+        4 => Ok(SelectionKind::NoDuplicates),
+        5 => Ok(SelectionKind::SameSize),
+        _ => Err(Error::InvalidBitcode),
+    }
+}
+
+fn decode_int_predicate(code: u64) -> Result<IntPredicate, Error> {
+    Ok(match code {
+        32 => IntPredicate::EQ,
+        33 => IntPredicate::NE,
+        34 => IntPredicate::UGT,
+        35 => IntPredicate::UGE,
+        36 => IntPredicate::ULT,
+        37 => IntPredicate::ULE,
+        38 => IntPredicate::SGT,
+        39 => IntPredicate::SGE,
+        40 => IntPredicate::SLT,
+        41 => IntPredicate::SLE,
+        _ => return Err(Error::InvalidBitcode),
+    })
+}
+
+fn decode_fp_predicate(code: u64) -> Result<FPPredicate, Error> {
+    Ok(match code {
+        0 => FPPredicate::False,
+        1 => FPPredicate::OEQ,
+        2 => FPPredicate::OGT,
+        3 => FPPredicate::OGE,
+        4 => FPPredicate::OLT,
+        5 => FPPredicate::OLE,
+        6 => FPPredicate::ONE,
+        7 => FPPredicate::ORD,
+        8 => FPPredicate::UNO,
+        9 => FPPredicate::UEQ,
+        10 => FPPredicate::UGT,
+        11 => FPPredicate::UGE,
+        12 => FPPredicate::ULT,
+        13 => FPPredicate::ULE,
+        14 => FPPredicate::UNE,
+        15 => FPPredicate::True,
+        _ => return Err(Error::InvalidBitcode),
+    })
+}
+
+/// Is `ty` a floating-point type, or a vector of them
+fn is_fp(ty: &TypeRef) -> bool {
+    match ty.as_ref() {
+        Type::FPType(_) => true,
+        Type::VectorType { element_type, .. } => matches!(element_type.as_ref(), Type::FPType(_)),
+        _ => false,
+    }
+}
+
+fn int_constant(bits: u32, words: Vec<u64>) -> Constant {
+    if bits > 64 {
+        Constant::BigInt(BigIntValue::from_words(bits, words))
+    } else {
+        let value = words.first().copied().unwrap_or(0);
+        Constant::Int {
+            bits,
+            value: if bits == 64 {
+                value
+            } else {
+                value & ((1 << bits) - 1)
+            },
+        }
+    }
+}
+
+fn float_constant(fpt: FPType, bits: u64) -> Float {
+    match fpt {
+        FPType::Half => Float::Half,
+        #[cfg(feature = "llvm-11-or-greater")]
+        FPType::BFloat => Float::BFloat,
+        FPType::Single => Float::Single(f32::from_bits(bits as u32)),
+        FPType::Double => Float::Double(f64::from_bits(bits)),
+        FPType::FP128 => Float::Quadruple,
+        FPType::X86_FP80 => Float::X86_FP80,
+        FPType::PPC_FP128 => Float::PPC_FP128,
+    }
+}
+
+/// Build the constant expression for binary operator `opcode`
+fn constant_binop(
+    opcode: u64,
+    fp: bool,
+    operand0: ConstantRef,
+    operand1: ConstantRef,
+) -> Result<Constant, Error> {
+    macro_rules! binop {
+        ($variant:ident) => {
+            Constant::$variant(constant::$variant { operand0, operand1 })
+        };
+    }
+    Ok(match (opcode, fp) {
+        (0, false) => binop!(Add),
+        (0, true) => binop!(FAdd),
+        (1, false) => binop!(Sub),
+        (1, true) => binop!(FSub),
+        (2, false) => binop!(Mul),
+        (2, true) => binop!(FMul),
+        (3, false) => binop!(UDiv),
+        (4, false) => binop!(SDiv),
+        (4, true) => binop!(FDiv),
+        (5, false) => binop!(URem),
+        (6, false) => binop!(SRem),
+        (6, true) => binop!(FRem),
+        (7, false) => binop!(Shl),
+        (8, false) => binop!(LShr),
+        (9, false) => binop!(AShr),
+        (10, false) => binop!(And),
+        (11, false) => binop!(Or),
+        (12, false) => binop!(Xor),
+        _ => return Err(Error::InvalidBitcode),
+    })
+}
+
+/// Build the constant expression for cast `opcode`
+fn constant_cast(opcode: u64, operand: ConstantRef, to_type: TypeRef) -> Result<Constant, Error> {
+    macro_rules! cast {
+        ($variant:ident) => {
+            Constant::$variant(constant::$variant { operand, to_type })
+        };
+    }
+    Ok(match opcode {
+        0 => cast!(Trunc),
+        1 => cast!(ZExt),
+        2 => cast!(SExt),
+        3 => cast!(FPToUI),
+        4 => cast!(FPToSI),
+        5 => cast!(UIToFP),
+        6 => cast!(SIToFP),
+        7 => cast!(FPTrunc),
+        8 => cast!(FPExt),
+        9 => cast!(PtrToInt),
+        10 => cast!(IntToPtr),
+        11 => cast!(BitCast),
+        12 => cast!(AddrSpaceCast),
+        _ => return Err(Error::InvalidBitcode),
+    })
+}
+
+/// Build the instruction for binary operator `opcode`
+fn instruction_binop(
+    opcode: u64,
+    fp: bool,
+    operand0: OperandRef,
+    operand1: OperandRef,
+    dest: Name,
+) -> Result<Instruction, Error> {
+    macro_rules! binop {
+        ($variant:ident) => {
+            Instruction::$variant(instruction::$variant {
+                operand0,
+                operand1,
+                dest,
+                #[cfg(feature = "llvm-9-or-greater")]
+                debugloc: None,
+            })
+        };
+    }
+    Ok(match (opcode, fp) {
+        (0, false) => binop!(Add),
+        (0, true) => binop!(FAdd),
+        (1, false) => binop!(Sub),
+        (1, true) => binop!(FSub),
+        (2, false) => binop!(Mul),
+        (2, true) => binop!(FMul),
+        (3, false) => binop!(UDiv),
+        (4, false) => binop!(SDiv),
+        (4, true) => binop!(FDiv),
+        (5, false) => binop!(URem),
+        (6, false) => binop!(SRem),
+        (6, true) => binop!(FRem),
+        (7, false) => binop!(Shl),
+        (8, false) => binop!(LShr),
+        (9, false) => binop!(AShr),
+        (10, false) => binop!(And),
+        (11, false) => binop!(Or),
+        (12, false) => binop!(Xor),
+        _ => return Err(Error::InvalidBitcode),
+    })
+}
+
+/// Build the instruction for cast `opcode`
+fn instruction_cast(
+    opcode: u64,
+    operand: OperandRef,
+    to_type: TypeRef,
+    dest: Name,
+) -> Result<Instruction, Error> {
+    macro_rules! cast {
+        ($variant:ident) => {
+            Instruction::$variant(instruction::$variant {
+                operand,
+                to_type,
+                dest,
+                #[cfg(feature = "llvm-9-or-greater")]
+                debugloc: None,
+            })
+        };
+    }
+    Ok(match opcode {
+        0 => cast!(Trunc),
+        1 => cast!(ZExt),
+        2 => cast!(SExt),
+        3 => cast!(FPToUI),
+        4 => cast!(FPToSI),
+        5 => cast!(UIToFP),
+        6 => cast!(SIToFP),
+        7 => cast!(FPTrunc),
+        8 => cast!(FPExt),
+        9 => cast!(PtrToInt),
+        10 => cast!(IntToPtr),
+        11 => cast!(BitCast),
+        12 => cast!(AddrSpaceCast),
+        _ => return Err(Error::InvalidBitcode),
+    })
+}
+
+fn vector_of(
+    types: &mut TypesBuilder,
+    element_type: TypeRef,
+    num_elements: usize,
+    scalable: bool,
+) -> TypeRef {
+    #[cfg(feature = "llvm-11-or-greater")]
+    {
+        types.vector_of(element_type, num_elements, scalable)
+    }
+    #[cfg(feature = "llvm-10-or-lower")]
+    {
+        let _ = scalable;
+        types.vector_of(element_type, num_elements)
+    }
+}
+
+/// A record from the type table, along with the name given to it by a
+/// preceding `TYPE_CODE_STRUCT_NAME` record, if any
+struct TypeRecord {
+    code: u64,
+    ops: Vec<u64>,
+    name: Option<String>,
+}
+
+/// The type table of a module
+struct TypeTable {
+    builder: TypesBuilder,
+    /// The types in the bitcode's type table, by type ID
+    types: Vec<TypeRef>,
+}
+
+impl TypeTable {
+    fn read(reader: &mut BitstreamReader) -> Result<Self, Error> {
+        let mut records = vec![];
+        let mut struct_name = None;
+        loop {
+            match reader.next()?.ok_or(Error::InvalidBitcode)? {
+                Entry::EndBlock => break,
+                Entry::SubBlock(block) => reader.skip_block(&block),
+                Entry::Record(record) => match record.code {
+                    TYPE_CODE_NUMENTRY => {},
+                    TYPE_CODE_STRUCT_NAME => {
+                        struct_name =
+                            Some(String::from_utf8_lossy(&record.string_from(0)).into_owned());
+                    },
+                    code => {
+                        let name = match code {
+                            TYPE_CODE_OPAQUE | TYPE_CODE_STRUCT_NAMED => struct_name.take(),
+                            _ => None,
+                        };
+                        records.push(TypeRecord {
+                            code,
+                            ops: record.ops,
+                            name,
+                        });
+                    },
+                },
+            }
+        }
+        let mut table = Self {
+            builder: TypesBuilder::new(),
+            types: vec![],
+        };
+        let mut resolved = vec![None; records.len()];
+        for id in 0 .. records.len() {
+            let ty = table.resolve(&records, &mut resolved, id as u64)?;
+            table.types.push(ty);
+        }
+        Ok(table)
+    }
+
+    /// Get the `TypeRef` for the type record with the given ID, resolving the
+    /// records it refers to as necessary
+    fn resolve(
+        &mut self,
+        records: &[TypeRecord],
+        resolved: &mut Vec<Option<TypeRef>>,
+        id: u64,
+    ) -> Result<TypeRef, Error> {
+        let record = records.get(id as usize).ok_or(Error::InvalidBitcode)?;
+        if let Some(ty) = &resolved[id as usize] {
+            return Ok(ty.clone());
+        }
+        let ops = &record.ops;
+        let ty = match record.code {
+            TYPE_CODE_VOID => self.builder.void(),
+            TYPE_CODE_FLOAT => self.builder.fp(FPType::Single),
+            TYPE_CODE_DOUBLE => self.builder.fp(FPType::Double),
+            TYPE_CODE_HALF => self.builder.fp(FPType::Half),
+            #[cfg(feature = "llvm-11-or-greater")]
+            TYPE_CODE_BFLOAT => self.builder.fp(FPType::BFloat),
+            TYPE_CODE_X86_FP80 => self.builder.fp(FPType::X86_FP80),
+            TYPE_CODE_FP128 => self.builder.fp(FPType::FP128),
+            TYPE_CODE_PPC_FP128 => self.builder.fp(FPType::PPC_FP128),
+            TYPE_CODE_LABEL => self.builder.label_type(),
+            TYPE_CODE_METADATA => self.builder.metadata_type(),
+            TYPE_CODE_X86_MMX => self.builder.x86_mmx(),
+            #[cfg(feature = "llvm-12-or-greater")]
+            TYPE_CODE_X86_AMX => self.builder.x86_amx(),
+            TYPE_CODE_TOKEN => self.builder.token_type(),
+            TYPE_CODE_INTEGER => self.builder.int(op(ops, 0)? as u32),
+            TYPE_CODE_POINTER => {
+                let pointee = self.resolve(records, resolved, op(ops, 0)?)?;
+                let addr_space = ops.get(1).copied().unwrap_or(0) as AddrSpace;
+                self.builder.pointer_in_addr_space(pointee, addr_space)
+            },
+            TYPE_CODE_OPAQUE_POINTER => {
+                let addr_space = ops.first().copied().unwrap_or(0) as AddrSpace;
+                self.builder.opaque_pointer_in_addr_space(addr_space)
+            },
+            TYPE_CODE_ARRAY => {
+                let element_type = self.resolve(records, resolved, op(ops, 1)?)?;
+                self.builder.array_of(element_type, op(ops, 0)? as usize)
+            },
+            TYPE_CODE_VECTOR => {
+                let element_type = self.resolve(records, resolved, op(ops, 1)?)?;
+                let scalable = ops.get(2).copied().unwrap_or(0) != 0;
+                vector_of(
+                    &mut self.builder,
+                    element_type,
+                    op(ops, 0)? as usize,
+                    scalable,
+                )
+            },
+            TYPE_CODE_FUNCTION => {
+                let result_type = self.resolve(records, resolved, op(ops, 1)?)?;
+                let param_types = ops
+                    .iter()
+                    .skip(2)
+                    .map(|&param| self.resolve(records, resolved, param))
+                    .collect::<Result<_, _>>()?;
+                self.builder
+                    .func_type(result_type, param_types, op(ops, 0)? != 0)
+            },
+            TYPE_CODE_STRUCT_ANON => {
+                let element_types =
+                    self.resolve_all(records, resolved, ops.get(1 ..).unwrap_or_default())?;
+                self.builder.struct_of(element_types, op(ops, 0)? != 0)
+            },
+            TYPE_CODE_STRUCT_NAMED | TYPE_CODE_OPAQUE => match &record.name {
+                Some(name) if !name.is_empty() => {
+                    // record the named struct before resolving its elements,
+                    // which may refer back to it
+                    let ty = self.builder.named_struct(name.clone());
+                    resolved[id as usize] = Some(ty.clone());
+                    let def = if record.code == TYPE_CODE_OPAQUE {
+                        NamedStructDef::Opaque
+                    } else {
+                        let element_types =
+                            self.resolve_all(records, resolved, ops.get(1 ..).unwrap_or_default())?;
+                        NamedStructDef::Defined(
+                            self.builder.struct_of(element_types, op(ops, 0)? != 0),
+                        )
+                    };
+                    self.builder.add_named_struct_def(name.clone(), def);
+                    ty
+                },
+                // an unnamed identified struct is treated as a literal struct
+                _ => {
+                    let element_types =
+                        self.resolve_all(records, resolved, ops.get(1 ..).unwrap_or_default())?;
+                    let is_packed = ops.first().copied().unwrap_or(0) != 0;
+                    self.builder.struct_of(element_types, is_packed)
+                },
+            },
+            code => return Err(unsupported(format!("type record code {}", code))),
+        };
+        resolved[id as usize] = Some(ty.clone());
+        Ok(ty)
+    }
+
+    fn resolve_all(
+        &mut self,
+        records: &[TypeRecord],
+        resolved: &mut Vec<Option<TypeRef>>,
+        ids: &[u64],
+    ) -> Result<Vec<TypeRef>, Error> {
+        ids.iter()
+            .map(|&id| self.resolve(records, resolved, id))
+            .collect()
+    }
+
+    fn get(&self, id: u64) -> Result<TypeRef, Error> {
+        self.types
+            .get(id as usize)
+            .cloned()
+            .ok_or(Error::InvalidBitcode)
+    }
+
+    /// Get the element types of the given struct type, and whether it is
+    /// packed, looking through named structs
+    fn struct_fields(&self, ty: &TypeRef) -> Result<(Vec<TypeRef>, bool), Error> {
+        match ty.as_ref() {
+            Type::StructType {
+                element_types,
+                is_packed,
+            } => Ok((element_types.clone(), *is_packed)),
+            Type::NamedStructType { name } => match self.builder.named_struct_def(name) {
+                NamedStructDef::Defined(ty) => self.struct_fields(ty),
+                NamedStructDef::Opaque => Err(Error::InvalidBitcode),
+            },
+            _ => Err(Error::InvalidBitcode),
+        }
+    }
+
+    /// Get the type at the given index of the given aggregate type
+    fn element_type(&self, ty: &TypeRef, index: u64) -> Result<TypeRef, Error> {
+        match ty.as_ref() {
+            Type::ArrayType { element_type, .. } | Type::VectorType { element_type, .. } => {
+                Ok(element_type.clone())
+            },
+            _ => self
+                .struct_fields(ty)?
+                .0
+                .get(index as usize)
+                .cloned()
+                .ok_or(Error::InvalidBitcode),
+        }
+    }
+}
+
+/// A value in the value table
+enum Value {
+    Constant(ConstantRef),
+    /// A constant whose record hasn't been interpreted yet, as the record's
+    /// code and operands
+    PendingConstant(u64, Vec<u64>),
+    /// A constant whose record we are in the middle of interpreting
+    Resolving,
+    /// A function parameter or the result of an instruction
+    Local,
+}
+
+/// The values which have been defined so far, by value ID, along with their
+/// types
+#[derive(Default)]
+struct ValueTable {
+    values: Vec<(TypeRef, Value)>,
+}
+
+impl ValueTable {
+    fn len(&self) -> u64 {
+        self.values.len() as u64
+    }
+
+    fn push(&mut self, ty: TypeRef, value: Value) {
+        self.values.push((ty, value));
+    }
+
+    /// Get the constant with the given value ID, interpreting its record (and
+    /// those of the constants it refers to) if that hasn't been done yet
+    fn constant(&mut self, id: u64, types: &TypeTable) -> Result<ConstantRef, Error> {
+        let (ty, value) = self
+            .values
+            .get_mut(id as usize)
+            .ok_or(Error::InvalidBitcode)?;
+        match value {
+            Value::Constant(constant) => return Ok(constant.clone()),
+            Value::PendingConstant(..) => {},
+            Value::Resolving | Value::Local => return Err(Error::InvalidBitcode),
+        }
+        let ty = ty.clone();
+        let (code, ops) = match std::mem::replace(value, Value::Resolving) {
+            Value::PendingConstant(code, ops) => (code, ops),
+            _ => unreachable!(),
+        };
+        let constant = ConstantRef::new(self.constant_from_record(code, &ops, ty, types)?);
+        self.values[id as usize].1 = Value::Constant(constant.clone());
+        Ok(constant)
+    }
+
+    fn constants(&mut self, ids: &[u64], types: &TypeTable) -> Result<Vec<ConstantRef>, Error> {
+        ids.iter().map(|&id| self.constant(id, types)).collect()
+    }
+
+    fn constant_from_record(
+        &mut self,
+        code: u64,
+        ops: &[u64],
+        ty: TypeRef,
+        types: &TypeTable,
+    ) -> Result<Constant, Error> {
+        Ok(match code {
+            CST_CODE_NULL => match ty.as_ref() {
+                Type::IntegerType { bits } => int_constant(*bits, vec![0]),
+                Type::FPType(fpt) => Constant::Float(float_constant(*fpt, 0)),
+                Type::PointerType { .. } | Type::OpaquePointerType { .. } => Constant::Null(ty),
+                Type::TokenType => Constant::TokenNone,
+                _ => Constant::AggregateZero(ty),
+            },
+            CST_CODE_UNDEF => Constant::Undef(ty),
+            #[cfg(feature = "llvm-12-or-greater")]
+            CST_CODE_POISON => Constant::Poison(ty),
+            CST_CODE_INTEGER | CST_CODE_WIDE_INTEGER => match ty.as_ref() {
+                Type::IntegerType { bits } => {
+                    int_constant(*bits, ops.iter().map(|&w| decode_sign_rotated(w)).collect())
+                },
+                _ => return Err(Error::InvalidBitcode),
+            },
+            CST_CODE_FLOAT => match ty.as_ref() {
+                Type::FPType(fpt) => Constant::Float(float_constant(*fpt, op(ops, 0)?)),
+                _ => return Err(Error::InvalidBitcode),
+            },
+            CST_CODE_AGGREGATE => {
+                let elements = self.constants(ops, types)?;
+                match ty.as_ref() {
+                    Type::ArrayType { element_type, .. } => Constant::Array {
+                        element_type: element_type.clone(),
+                        elements,
+                    },
+                    Type::VectorType { .. } => Constant::Vector(elements),
+                    _ => Constant::Struct {
+                        name: None,
+                        values: elements,
+                        is_packed: types.struct_fields(&ty)?.1,
+                    },
+                }
+            },
+            CST_CODE_STRING | CST_CODE_CSTRING | CST_CODE_DATA => {
+                let (element_type, is_vector) = match ty.as_ref() {
+                    Type::ArrayType { element_type, .. } => (element_type.clone(), false),
+                    Type::VectorType { element_type, .. } => (element_type.clone(), true),
+                    _ => return Err(Error::InvalidBitcode),
+                };
+                let element_size = match element_type.as_ref() {
+                    Type::IntegerType { bits } => (*bits as usize).div_ceil(8),
+                    Type::FPType(FPType::Half) => 2,
+                    #[cfg(feature = "llvm-11-or-greater")]
+                    Type::FPType(FPType::BFloat) => 2,
+                    Type::FPType(FPType::Single) => 4,
+                    Type::FPType(FPType::Double) => 8,
+                    _ => return Err(Error::InvalidBitcode),
+                };
+                let mut data: Vec<u8> = ops
+                    .iter()
+                    .flat_map(|element| element.to_le_bytes()[.. element_size].to_vec())
+                    .collect();
+                if code == CST_CODE_CSTRING {
+                    data.push(0);
+                }
+                if is_vector {
+                    Constant::RawDataVector { element_type, data }
+                } else {
+                    Constant::RawDataArray { element_type, data }
+                }
+            },
+            CST_CODE_CE_BINOP => {
+                let operand0 = self.constant(op(ops, 1)?, types)?;
+                let operand1 = self.constant(op(ops, 2)?, types)?;
+                constant_binop(op(ops, 0)?, is_fp(&ty), operand0, operand1)?
+            },
+            CST_CODE_CE_UNOP => match op(ops, 0)? {
+                0 => Constant::FNeg(constant::FNeg {
+                    operand: self.constant(op(ops, 1)?, types)?,
+                }),
+                _ => return Err(Error::InvalidBitcode),
+            },
+            CST_CODE_CE_CAST => {
+                let operand = self.constant(op(ops, 2)?, types)?;
+                constant_cast(op(ops, 0)?, operand, ty)?
+            },
+            CST_CODE_CE_GEP | CST_CODE_CE_INBOUNDS_GEP | CST_CODE_CE_GEP_WITH_INRANGE_INDEX => {
+                // the source element type comes first if the record has an
+                // odd number of operands (and always, for the inrange form)
+                let mut start = 0;
+                let explicit_type =
+                    code == CST_CODE_CE_GEP_WITH_INRANGE_INDEX || ops.len() % 2 == 1;
+                if explicit_type {
+                    start += 1;
+                }
+                let in_bounds = match code {
+                    CST_CODE_CE_INBOUNDS_GEP => true,
+                    CST_CODE_CE_GEP_WITH_INRANGE_INDEX => {
+                        start += 1;
+                        op(ops, 1)? & 1 != 0
+                    },
+                    _ => false,
+                };
+                let ids: Vec<u64> = ops[start ..].iter().skip(1).step_by(2).copied().collect();
+                let (address, indices) = ids.split_first().ok_or(Error::InvalidBitcode)?;
+                let source_element_type = if explicit_type {
+                    types.get(op(ops, 0)?)?
+                } else {
+                    // otherwise, it's the pointee type of the address
+                    let address_type = types.get(op(ops, start)?)?;
+                    match address_type.as_ref() {
+                        Type::VectorType { element_type, .. } => element_type.pointee_type(),
+                        _ => address_type.pointee_type(),
+                    }
+                    .ok_or(Error::InvalidBitcode)?
+                };
+                Constant::GetElementPtr(constant::GetElementPtr {
+                    address: self.constant(*address, types)?,
+                    indices: self.constants(indices, types)?,
+                    in_bounds,
+                    source_element_type,
+                })
+            },
+            CST_CODE_CE_SELECT => Constant::Select(constant::Select {
+                condition: self.constant(op(ops, 0)?, types)?,
+                true_value: self.constant(op(ops, 1)?, types)?,
+                false_value: self.constant(op(ops, 2)?, types)?,
+            }),
+            CST_CODE_CE_CMP => {
+                let operand_type = types.get(op(ops, 0)?)?;
+                let operand0 = self.constant(op(ops, 1)?, types)?;
+                let operand1 = self.constant(op(ops, 2)?, types)?;
+                if is_fp(&operand_type) {
+                    Constant::FCmp(constant::FCmp {
+                        predicate: decode_fp_predicate(op(ops, 3)?)?,
+                        operand0,
+                        operand1,
+                    })
+                } else {
+                    Constant::ICmp(constant::ICmp {
+                        predicate: decode_int_predicate(op(ops, 3)?)?,
+                        operand0,
+                        operand1,
+                    })
+                }
+            },
+            // the block is identified by its index in a function body we
+            // haven't read yet, so we can't give its `Name`
+            CST_CODE_BLOCKADDRESS => return Err(unsupported("blockaddress constants")),
+            code => return Err(unsupported(format!("constant record code {}", code))),
+        })
+    }
+}
+
+/// A global value, as read from its record in the module block
+struct GlobalRecord {
+    name: Vec<u8>,
+    kind: GlobalKind,
+    /// The type of the value itself (for a function, its function type)
+    ty: TypeRef,
+    addr_space: AddrSpace,
+    /// The operands of the record, after the name
+    ops: Vec<u64>,
+}
+
+impl GlobalRecord {
+    fn is_defined_function(&self) -> bool {
+        self.kind == GlobalKind::Function && self.ops.get(2) == Some(&0)
+    }
+}
+
+/// The parameters and basic blocks of a function, read from its block
+struct FunctionBody {
+    parameters: Vec<Parameter>,
+    basic_blocks: Vec<BasicBlock>,
+}
+
+/// The state while reading a module block
+struct ModuleReader<'a> {
+    strtab: &'a [u8],
+    version: u64,
+    types: TypeTable,
+    values: ValueTable,
+    triple: Option<String>,
+    data_layout: DataLayout,
+    inline_asm: String,
+    source_file_name: Option<String>,
+    section_names: Vec<String>,
+    gc_names: Vec<String>,
+    comdats: Vec<Comdat>,
+    /// The global values, in the order of their value IDs
+    globals: Vec<GlobalRecord>,
+    /// Whether the `globals` have been added to the value table yet
+    globals_defined: bool,
+    /// The bodies of the defined functions, in the order they appear
+    bodies: Vec<FunctionBody>,
+    /// The `OperandRef` for each distinct `Operand` in the function bodies
+    /// read so far, so that they can be shared
+    operands: HashMap<Operand, OperandRef>,
+}
+
+impl<'a> ModuleReader<'a> {
+    fn new(strtab: &'a [u8]) -> Self {
+        Self {
+            strtab,
+            version: 0,
+            types: TypeTable {
+                builder: TypesBuilder::new(),
+                types: vec![],
+            },
+            values: ValueTable::default(),
+            triple: None,
+            data_layout: DataLayout::from_layout_str(String::new()),
+            inline_asm: String::new(),
+            source_file_name: None,
+            section_names: vec![],
+            gc_names: vec![],
+            comdats: vec![],
+            globals: vec![],
+            globals_defined: false,
+            bodies: vec![],
+            operands: HashMap::new(),
+        }
+    }
+
+    fn string_from_strtab(&self, offset: u64, size: u64) -> Result<Vec<u8>, Error> {
+        self.strtab
+            .get(offset as usize .. (offset + size) as usize)
+            .map(<[u8]>::to_vec)
+            .ok_or(Error::InvalidBitcode)
+    }
+
+    fn read_module_block(&mut self, reader: &mut BitstreamReader) -> Result<(), Error> {
+        loop {
+            match reader.next()?.ok_or(Error::InvalidBitcode)? {
+                Entry::EndBlock => return Ok(()),
+                Entry::SubBlock(block) => match block.id {
+                    TYPE_BLOCK_ID => {
+                        reader.enter_block(&block);
+                        self.types = TypeTable::read(reader)?;
+                    },
+                    CONSTANTS_BLOCK_ID => {
+                        self.define_globals();
+                        reader.enter_block(&block);
+                        self.read_constants_block(reader)?;
+                    },
+                    FUNCTION_BLOCK_ID => {
+                        self.define_globals();
+                        reader.enter_block(&block);
+                        let func = self
+                            .globals
+                            .iter()
+                            .filter(|global| global.is_defined_function())
+                            .nth(self.bodies.len())
+                            .ok_or(Error::InvalidBitcode)?;
+                        let name = String::from_utf8_lossy(&func.name).into_owned();
+                        let func_type = func.ty.clone();
+                        let body = self
+                            .read_function_block(reader, &func_type)
+                            .map_err(|err| err.in_function(name))?;
+                        self.bodies.push(body);
+                    },
+                    _ => reader.skip_block(&block),
+                },
+                Entry::Record(record) => self.read_module_record(record)?,
+            }
+        }
+    }
+
+    fn read_module_record(&mut self, record: Record) -> Result<(), Error> {
+        let string = || String::from_utf8_lossy(&record.string_from(0)).into_owned();
+        match record.code {
+            MODULE_CODE_VERSION => {
+                self.version = op(&record.ops, 0)?;
+                if self.version < 2 {
+                    return Err(unsupported(
+                        "bitcode from before LLVM 5, which has no string table",
+                    ));
+                }
+            },
+            MODULE_CODE_TRIPLE => self.triple = Some(string()),
+            MODULE_CODE_DATALAYOUT => self.data_layout = DataLayout::from_layout_str(string()),
+            MODULE_CODE_ASM => self.inline_asm = string(),
+            MODULE_CODE_SECTIONNAME => self.section_names.push(string()),
+            MODULE_CODE_GCNAME => self.gc_names.push(string()),
+            MODULE_CODE_SOURCE_FILENAME => self.source_file_name = Some(string()),
+            MODULE_CODE_COMDAT => {
+                let name = self.string_from_strtab(op(&record.ops, 0)?, op(&record.ops, 1)?)?;
+                self.comdats.push(Comdat {
+                    name: String::from_utf8_lossy(&name).into_owned(),
+                    selection_kind: decode_selection_kind(op(&record.ops, 2)?)?,
+                });
+            },
+            MODULE_CODE_GLOBALVAR
+            | MODULE_CODE_FUNCTION
+            | MODULE_CODE_ALIAS
+            | MODULE_CODE_IFUNC => {
+                let name = self.string_from_strtab(op(&record.ops, 0)?, op(&record.ops, 1)?)?;
+                let ops = record.ops[2 ..].to_vec();
+                let (kind, ty, addr_space) = match record.code {
+                    MODULE_CODE_GLOBALVAR => {
+                        let flags = op(&ops, 1)?;
+                        let mut ty = self.types.get(op(&ops, 0)?)?;
+                        if flags & 2 == 0 {
+                            // without an explicit type, the record gives the
+                            // type of the global's address
+                            ty = match ty.as_ref() {
+                                Type::PointerType { pointee_type, .. } => pointee_type.clone(),
+                                _ => return Err(Error::InvalidBitcode),
+                            };
+                        }
+                        (GlobalKind::GlobalVariable, ty, (flags >> 2) as AddrSpace)
+                    },
+                    MODULE_CODE_FUNCTION => {
+                        let ty = match self.types.get(op(&ops, 0)?)?.as_ref() {
+                            Type::PointerType { pointee_type, .. } => pointee_type.clone(),
+                            _ => self.types.get(op(&ops, 0)?)?,
+                        };
+                        let addr_space = ops.get(16).copied().unwrap_or(0) as AddrSpace;
+                        (GlobalKind::Function, ty, addr_space)
+                    },
+                    MODULE_CODE_ALIAS => (
+                        GlobalKind::GlobalAlias,
+                        self.types.get(op(&ops, 0)?)?,
+                        op(&ops, 1)? as AddrSpace,
+                    ),
+                    _ => (
+                        GlobalKind::GlobalIFunc,
+                        self.types.get(op(&ops, 0)?)?,
+                        op(&ops, 1)? as AddrSpace,
+                    ),
+                };
+                self.globals.push(GlobalRecord {
+                    name,
+                    kind,
+                    ty,
+                    addr_space,
+                    ops,
+                });
+            },
+            MODULE_CODE_ALIAS_OLD => return Err(unsupported("old-style alias record")),
+            _ => {},
+        }
+        Ok(())
+    }
+
+    /// The `Name`s of the global values, numbering the unnamed ones in the
+    /// same order as `Module::from_bc_path()` does
+    fn global_names(&self) -> Vec<Name> {
+        let mut names = vec![Name::Number(0); self.globals.len()];
+        let mut ctr = 0;
+        let defined = |g: &GlobalRecord| g.is_defined_function();
+        let declared =
+            |g: &GlobalRecord| g.kind == GlobalKind::Function && !g.is_defined_function();
+        let filters: [&dyn Fn(&GlobalRecord) -> bool; 5] = [
+            &defined,
+            &declared,
+            &|g| g.kind == GlobalKind::GlobalVariable,
+            &|g| g.kind == GlobalKind::GlobalAlias,
+            &|g| g.kind == GlobalKind::GlobalIFunc,
+        ];
+        for filter in filters.iter() {
+            for (i, global) in self.globals.iter().enumerate() {
+                if filter(global) {
+                    names[i] = Name::name_or_num(global.name.clone(), &mut ctr);
+                }
+            }
+        }
+        names
+    }
+
+    /// Add the global values to the value table, if that hasn't been done
+    /// yet. They come first in the value table, before any constants.
+    fn define_globals(&mut self) {
+        if self.globals_defined {
+            return;
+        }
+        self.globals_defined = true;
+        for (global, name) in self.globals.iter().zip(self.global_names()) {
+            let ptr_type = self
+                .types
+                .builder
+                .pointer_for(global.ty.clone(), global.addr_space);
+            let reference = ConstantRef::new(Constant::GlobalReference {
+                name,
+                ty: global.ty.clone(),
+                kind: global.kind,
+                addr_space: global.addr_space,
+            });
+            self.values.push(ptr_type, Value::Constant(reference));
+        }
+    }
+
+    fn read_constants_block(&mut self, reader: &mut BitstreamReader) -> Result<(), Error> {
+        let mut ty = None;
+        loop {
+            match reader.next()?.ok_or(Error::InvalidBitcode)? {
+                Entry::EndBlock => return Ok(()),
+                Entry::SubBlock(block) => reader.skip_block(&block),
+                Entry::Record(record) if record.code == CST_CODE_SETTYPE => {
+                    ty = Some(self.types.get(op(&record.ops, 0)?)?);
+                },
+                Entry::Record(record) => {
+                    let ty = ty.clone().ok_or(Error::InvalidBitcode)?;
+                    self.values
+                        .push(ty, Value::PendingConstant(record.code, record.ops));
+                },
+            }
+        }
+    }
+
+    fn constant(&mut self, id: u64) -> Result<ConstantRef, Error> {
+        self.values.constant(id, &self.types)
+    }
+
+    /// Get the operand with the given value ID. `ty` is the type to use if the
+    /// ID refers to a value which hasn't been defined yet.
+    fn operand(&mut self, id: u64, ty: Option<TypeRef>) -> Result<(OperandRef, TypeRef), Error> {
+        let (operand, ty) = match self.values.values.get(id as usize) {
+            Some((ty, Value::Local)) => (
+                Operand::LocalOperand {
+                    name: Name::Number(id as usize),
+                    ty: ty.clone(),
+                },
+                ty.clone(),
+            ),
+            Some((ty, _)) => {
+                let ty = ty.clone();
+                (Operand::ConstantOperand(self.constant(id)?), ty)
+            },
+            None => {
+                let ty = ty.ok_or(Error::InvalidBitcode)?;
+                (
+                    Operand::LocalOperand {
+                        name: Name::Number(id as usize),
+                        ty: ty.clone(),
+                    },
+                    ty,
+                )
+            },
+        };
+        // these are shared later, once the locals have their final names
+        Ok((OperandRef::new(operand), ty))
+    }
+
+    /// Read a (relative) value ID, followed by its type if it is a forward
+    /// reference
+    fn value_and_type(&mut self, ops: &mut Ops) -> Result<(OperandRef, TypeRef), Error> {
+        let id = ops.value_id()?;
+        let ty = if id >= self.values.len() {
+            Some(self.types.get(ops.next()?)?)
+        } else {
+            None
+        };
+        self.operand(id, ty)
+    }
+
+    /// Read a (relative) value ID of a value with the given type
+    fn value(&mut self, ops: &mut Ops, ty: &TypeRef) -> Result<OperandRef, Error> {
+        let id = ops.value_id()?;
+        Ok(self.operand(id, Some(ty.clone()))?.0)
+    }
+
+    /// Add a value-producing instruction's result to the value table, and get
+    /// its (placeholder) `Name`
+    fn define_local(&mut self, ty: TypeRef) -> Name {
+        let name = Name::Number(self.values.len() as usize);
+        self.values.push(ty, Value::Local);
+        name
+    }
+
+    fn read_function_block(
+        &mut self,
+        reader: &mut BitstreamReader,
+        func_type: &TypeRef,
+    ) -> Result<FunctionBody, Error> {
+        let param_types = match func_type.as_ref() {
+            Type::FuncType { param_types, .. } => param_types.clone(),
+            _ => return Err(Error::InvalidBitcode),
+        };
+        let first_local = self.values.len();
+        for ty in &param_types {
+            self.values.push(ty.clone(), Value::Local);
+        }
+        let mut body = FunctionBlock::default();
+        loop {
+            match reader.next()?.ok_or(Error::InvalidBitcode)? {
+                Entry::EndBlock => break,
+                Entry::SubBlock(block) if block.id == CONSTANTS_BLOCK_ID => {
+                    reader.enter_block(&block);
+                    self.read_constants_block(reader)?;
+                },
+                Entry::SubBlock(block) if block.id == VALUE_SYMTAB_BLOCK_ID => {
+                    reader.enter_block(&block);
+                    loop {
+                        match reader.next()?.ok_or(Error::InvalidBitcode)? {
+                            Entry::EndBlock => break,
+                            Entry::SubBlock(block) => reader.skip_block(&block),
+                            Entry::Record(record) => match record.code {
+                                VST_CODE_ENTRY => {
+                                    body.value_names
+                                        .insert(op(&record.ops, 0)?, record.string_from(1));
+                                },
+                                VST_CODE_BBENTRY => {
+                                    body.block_names
+                                        .insert(op(&record.ops, 0)?, record.string_from(1));
+                                },
+                                _ => {},
+                            },
+                        }
+                    }
+                },
+                Entry::SubBlock(block) => reader.skip_block(&block),
+                Entry::Record(record) => self.read_instruction(&mut body, &record)?,
+            }
+        }
+        self.values.values.truncate(first_local as usize);
+        if body.blocks.iter().any(|(_, term)| term.is_none()) {
+            return Err(Error::InvalidBitcode);
+        }
+
+        // Now that we know the names in the function's symbol table, number
+        // the unnamed locals in order, as LLVM's slot tracker does
+        let mut ctr = 0;
+        let mut renames: HashMap<usize, Name> = HashMap::new();
+        let parameters = param_types
+            .into_iter()
+            .enumerate()
+            .map(|(i, ty)| {
+                let id = first_local + i as u64;
+                let name =
+                    Name::name_or_num(body.value_names.remove(&id).unwrap_or_default(), &mut ctr);
+                renames.insert(id as usize, name.clone());
+                Parameter {
+                    name,
+                    ty,
+                    attributes: vec![],
+                }
+            })
+            .collect();
+        for (i, (instrs, _)) in body.blocks.iter().enumerate() {
+            let name = body.block_names.remove(&(i as u64)).unwrap_or_default();
+            renames.insert(block_placeholder(i), Name::name_or_num(name, &mut ctr));
+            for inst in instrs {
+                if let Some(Name::Number(id)) = inst.try_get_result() {
+                    let name = body.value_names.remove(&(*id as u64)).unwrap_or_default();
+                    renames.insert(*id, Name::name_or_num(name, &mut ctr));
+                }
+            }
+        }
+        let mut rename = |name: &mut Name| {
+            if let Name::Number(n) = name {
+                if let Some(new_name) = renames.get(n) {
+                    *name = new_name.clone();
+                }
+            }
+        };
+        let basic_blocks = body
+            .blocks
+            .into_iter()
+            .enumerate()
+            .map(|(i, (mut instrs, term))| {
+                let mut term = term.expect("checked above");
+                instrs.map_local_names(&mut rename);
+                term.map_local_names(&mut rename);
+                let operands = instrs
+                    .iter_mut()
+                    .flat_map(Instruction::operand_refs_mut)
+                    .chain(term.operand_refs_mut());
+                for op in operands {
+                    match self.operands.get(op.as_ref()) {
+                        Some(shared) => *op = shared.clone(),
+                        None => {
+                            self.operands.insert(op.as_ref().clone(), op.clone());
+                        },
+                    }
+                }
+                BasicBlock {
+                    name: renames[&block_placeholder(i)].clone(),
+                    instrs,
+                    term,
+                }
+            })
+            .collect();
+        Ok(FunctionBody {
+            parameters,
+            basic_blocks,
+        })
+    }
+
+    /// Read one record of a function block
+    fn read_instruction(&mut self, body: &mut FunctionBlock, record: &Record) -> Result<(), Error> {
+        macro_rules! inst {
+            ($variant:ident { $($field:ident $(: $value:expr)?),* $(,)? }) => {
+                Either::Left(Instruction::$variant(instruction::$variant {
+                    $($field $(: $value)?,)*
+                    #[cfg(feature = "llvm-9-or-greater")]
+                    debugloc: None,
+                }))
+            };
+        }
+        macro_rules! term {
+            ($variant:ident { $($field:ident $(: $value:expr)?),* $(,)? }) => {
+                Either::Right(Terminator::$variant(terminator::$variant {
+                    $($field $(: $value)?,)*
+                    #[cfg(feature = "llvm-9-or-greater")]
+                    debugloc: None,
+                }))
+            };
+        }
+
+        let mut ops = Ops {
+            ops: &record.ops,
+            pos: 0,
+            next_value: self.values.len(),
+        };
+        let inst_or_term = match record.code {
+            FUNC_CODE_DECLAREBLOCKS => {
+                let num_blocks = ops.next()?;
+                body.blocks = (0 .. num_blocks).map(|_| (vec![], None)).collect();
+                return Ok(());
+            },
+            FUNC_CODE_DEBUG_LOC | FUNC_CODE_DEBUG_LOC_AGAIN => return Ok(()),
+            FUNC_CODE_INST_BINOP => {
+                let (operand0, ty) = self.value_and_type(&mut ops)?;
+                let operand1 = self.value(&mut ops, &ty)?;
+                let opcode = ops.next()?;
+                let fp = is_fp(&ty);
+                Either::Left(instruction_binop(
+                    opcode,
+                    fp,
+                    operand0,
+                    operand1,
+                    self.define_local(ty),
+                )?)
+            },
+            FUNC_CODE_INST_UNOP => {
+                let (operand, ty) = self.value_and_type(&mut ops)?;
+                match ops.next()? {
+                    0 => inst!(FNeg {
+                        operand,
+                        dest: self.define_local(ty),
+                    }),
+                    _ => return Err(Error::InvalidBitcode),
+                }
+            },
+            FUNC_CODE_INST_CAST => {
+                let (operand, _) = self.value_and_type(&mut ops)?;
+                let to_type = self.types.get(ops.next()?)?;
+                let opcode = ops.next()?;
+                let dest = self.define_local(to_type.clone());
+                Either::Left(instruction_cast(opcode, operand, to_type, dest)?)
+            },
+            FUNC_CODE_INST_GEP => {
+                let in_bounds = ops.next()? != 0;
+                let source_type = self.types.get(ops.next()?)?;
+                let mut operands = vec![];
+                while ops.remaining() > 0 {
+                    operands.push(self.value_and_type(&mut ops)?);
+                }
+                let ty = self.gep_result_type(source_type.clone(), &operands)?;
+                let mut operands = operands.into_iter().map(|(operand, _)| operand);
+                inst!(GetElementPtr {
+                    address: operands.next().ok_or(Error::InvalidBitcode)?,
+                    indices: operands.collect(),
+                    dest: self.define_local(ty),
+                    in_bounds,
+                    source_element_type: source_type,
+                })
+            },
+            FUNC_CODE_INST_EXTRACTVAL => {
+                let (aggregate, mut ty) = self.value_and_type(&mut ops)?;
+                let mut indices = vec![];
+                while ops.remaining() > 0 {
+                    let index = ops.next()?;
+                    ty = self.types.element_type(&ty, index)?;
+                    indices.push(index as u32);
+                }
+                inst!(ExtractValue {
+                    aggregate,
+                    indices,
+                    dest: self.define_local(ty),
+                })
+            },
+            FUNC_CODE_INST_INSERTVAL => {
+                let (aggregate, ty) = self.value_and_type(&mut ops)?;
+                let (element, _) = self.value_and_type(&mut ops)?;
+                let mut indices = vec![];
+                while ops.remaining() > 0 {
+                    indices.push(ops.next()? as u32);
+                }
+                inst!(InsertValue {
+                    aggregate,
+                    element,
+                    indices,
+                    dest: self.define_local(ty),
+                })
+            },
+            FUNC_CODE_INST_VSELECT => {
+                let (true_value, ty) = self.value_and_type(&mut ops)?;
+                let false_value = self.value(&mut ops, &ty)?;
+                let (condition, _) = self.value_and_type(&mut ops)?;
+                inst!(Select {
+                    condition,
+                    true_value,
+                    false_value,
+                    dest: self.define_local(ty),
+                })
+            },
+            FUNC_CODE_INST_CMP2 => {
+                let (operand0, ty) = self.value_and_type(&mut ops)?;
+                let operand1 = self.value(&mut ops, &ty)?;
+                let predicate = ops.next()?;
+                let bool_type = self.types.builder.bool();
+                let result_type = match vector_shape(&ty) {
+                    Some((num_elements, scalable)) => {
+                        vector_of(&mut self.types.builder, bool_type, num_elements, scalable)
+                    },
+                    None => bool_type,
+                };
+                if is_fp(&ty) {
+                    inst!(FCmp {
+                        predicate: decode_fp_predicate(predicate)?,
+                        operand0,
+                        operand1,
+                        dest: self.define_local(result_type),
+                    })
+                } else {
+                    inst!(ICmp {
+                        predicate: decode_int_predicate(predicate)?,
+                        operand0,
+                        operand1,
+                        dest: self.define_local(result_type),
+                    })
+                }
+            },
+            FUNC_CODE_INST_PHI => {
+                let to_type = self.types.get(ops.next()?)?;
+                let mut incoming_values = vec![];
+                // an odd number of remaining operands means there are
+                // fast-math flags at the end
+                while ops.remaining() >= 2 {
+                    let id = ops.signed_value_id()?;
+                    let (value, _) = self.operand(id, Some(to_type.clone()))?;
+                    incoming_values.push((value, body.block_name(ops.next()?)?));
+                }
+                inst!(Phi {
+                    incoming_values,
+                    dest: self.define_local(to_type.clone()),
+                    to_type,
+                })
+            },
+            FUNC_CODE_INST_ALLOCA => {
+                let mut allocated_type = self.types.get(ops.next()?)?;
+                let size_type = self.types.get(ops.next()?)?;
+                let (num_elements, _) = self.operand(ops.next()?, Some(size_type))?;
+                let align_record = ops.next()?;
+                if align_record & (1 << 6) == 0 {
+                    // without an explicit type, the record gives the type of
+                    // the result
+                    allocated_type = match allocated_type.as_ref() {
+                        Type::PointerType { pointee_type, .. } => pointee_type.clone(),
+                        _ => return Err(Error::InvalidBitcode),
+                    };
+                }
+                let ty = self.types.builder.pointer_for(
+                    allocated_type.clone(),
+                    self.data_layout.alloca_address_space,
+                );
+                inst!(Alloca {
+                    allocated_type,
+                    num_elements,
+                    dest: self.define_local(ty),
+                    alignment: decode_alignment(align_record & 0x1f)?,
+                })
+            },
+            FUNC_CODE_INST_LOAD => {
+                let (address, address_type) = self.value_and_type(&mut ops)?;
+                let ty = if ops.remaining() == 3 {
+                    self.types.get(ops.next()?)?
+                } else {
+                    match address_type.as_ref() {
+                        Type::PointerType { pointee_type, .. } => pointee_type.clone(),
+                        _ => return Err(Error::InvalidBitcode),
+                    }
+                };
+                let alignment = decode_alignment(ops.next()?)?;
+                inst!(Load {
+                    address,
+                    loaded_type: ty.clone(),
+                    dest: self.define_local(ty),
+                    volatile: ops.next()? != 0,
+                    atomicity: None,
+                    alignment,
+                })
+            },
+            FUNC_CODE_INST_STORE => {
+                let (address, _) = self.value_and_type(&mut ops)?;
+                let (value, _) = self.value_and_type(&mut ops)?;
+                let alignment = decode_alignment(ops.next()?)?;
+                inst!(Store {
+                    address,
+                    value,
+                    volatile: ops.next()? != 0,
+                    atomicity: None,
+                    alignment,
+                })
+            },
+            FUNC_CODE_INST_CALL => {
+                let _attributes = ops.next()?;
+                let cc_info = ops.next()?;
+                if cc_info & (1 << 17) != 0 {
+                    let _fast_math_flags = ops.next()?;
+                }
+                let explicit_type = if cc_info & (1 << 15) != 0 {
+                    Some(self.types.get(ops.next()?)?)
+                } else {
+                    None
+                };
+                let (callee, callee_type) = self.value_and_type(&mut ops)?;
+                let func_type = match (explicit_type, callee_type.as_ref()) {
+                    (Some(ty), _) => ty,
+                    (None, Type::PointerType { pointee_type, .. }) => pointee_type.clone(),
+                    (None, _) => return Err(Error::InvalidBitcode),
+                };
+                let (result_type, param_types) = match func_type.as_ref() {
+                    Type::FuncType {
+                        result_type,
+                        param_types,
+                        ..
+                    } => (result_type.clone(), param_types.clone()),
+                    _ => return Err(Error::InvalidBitcode),
+                };
+                let mut arguments = vec![];
+                for ty in &param_types {
+                    if let Type::MetadataType | Type::LabelType = ty.as_ref() {
+                        return Err(unsupported(format!("call argument of type {}", ty)));
+                    }
+                    arguments.push((self.value(&mut ops, ty)?, vec![]));
+                }
+                // any remaining operands are variadic arguments
+                while ops.remaining() > 0 {
+                    arguments.push((self.value_and_type(&mut ops)?.0, vec![]));
+                }
+                let dest = match result_type.as_ref() {
+                    Type::VoidType => None,
+                    _ => Some(self.define_local(result_type)),
+                };
+                inst!(Call {
+                    function: Either::Right(callee),
+                    function_ty: func_type,
+                    arguments,
+                    return_attributes: vec![],
+                    dest,
+                    function_attributes: vec![],
+                    is_tail_call: cc_info & 1 != 0,
+                    calling_convention: CallingConvention::from_u32(
+                        ((cc_info >> 1) & 0x3ff) as u32
+                    ),
+                    operand_bundles: vec![],
+                })
+            },
+            #[cfg(feature = "llvm-10-or-greater")]
+            FUNC_CODE_INST_FREEZE => {
+                let (operand, ty) = self.value_and_type(&mut ops)?;
+                inst!(Freeze {
+                    operand,
+                    dest: self.define_local(ty),
+                })
+            },
+            FUNC_CODE_INST_RET => {
+                let return_operand = if ops.remaining() == 0 {
+                    None
+                } else {
+                    Some(self.value_and_type(&mut ops)?.0)
+                };
+                term!(Ret { return_operand })
+            },
+            FUNC_CODE_INST_BR => {
+                let true_dest = body.block_name(ops.next()?)?;
+                if ops.remaining() == 0 {
+                    term!(Br {
+                        dest: true_dest,
+                        loop_metadata: None,
+                    })
+                } else {
+                    let false_dest = body.block_name(ops.next()?)?;
+                    let bool_type = self.types.builder.bool();
+                    term!(CondBr {
+                        condition: self.value(&mut ops, &bool_type)?,
+                        true_dest,
+                        false_dest,
+                        branch_weights: None,
+                        loop_metadata: None,
+                    })
+                }
+            },
+            FUNC_CODE_INST_SWITCH => {
+                let operand_type = self.types.get(ops.next()?)?;
+                let operand = self.value(&mut ops, &operand_type)?;
+                let default_dest = body.block_name(ops.next()?)?;
+                let mut dests = vec![];
+                while ops.remaining() >= 2 {
+                    // case values are absolute value IDs
+                    let value = self.constant(ops.next()?)?;
+                    dests.push((value, body.block_name(ops.next()?)?));
+                }
+                term!(Switch {
+                    operand,
+                    dests,
+                    default_dest,
+                    branch_weights: None,
+                })
+            },
+            FUNC_CODE_INST_UNREACHABLE => term!(Unreachable {}),
+            code => return Err(unsupported(format!("instruction record code {}", code))),
+        };
+        let (instrs, term) = body
+            .blocks
+            .get_mut(body.current)
+            .ok_or(Error::InvalidBitcode)?;
+        match inst_or_term {
+            Either::Left(inst) => instrs.push(inst),
+            Either::Right(terminator) => {
+                *term = Some(terminator);
+                body.current += 1;
+            },
+        }
+        Ok(())
+    }
+
+    /// Get the type of the result of a `getelementptr` with the given source
+    /// element type and operands
+    fn gep_result_type(
+        &mut self,
+        source_type: TypeRef,
+        operands: &[(OperandRef, TypeRef)],
+    ) -> Result<TypeRef, Error> {
+        let mut ty = source_type;
+        for (index, _) in operands.iter().skip(2) {
+            // only struct indices need to be known, and those are constants
+            let index = match index.as_ref() {
+                Operand::ConstantOperand(constant) => match constant.as_ref() {
+                    Constant::Int { value, .. } => *value,
+                    _ => 0,
+                },
+                _ => 0,
+            };
+            ty = self.types.element_type(&ty, index)?;
+        }
+        let (_, address_type) = operands.first().ok_or(Error::InvalidBitcode)?;
+        let pointer_type = match address_type.as_ref() {
+            Type::VectorType { element_type, .. } => element_type,
+            _ => address_type,
+        };
+        let result_type = match pointer_type.as_ref() {
+            Type::PointerType { addr_space, .. } => {
+                self.types.builder.pointer_in_addr_space(ty, *addr_space)
+            },
+            Type::OpaquePointerType { .. } => pointer_type.clone(),
+            _ => return Err(Error::InvalidBitcode),
+        };
+        // if any operand is a vector, the result is a vector of pointers
+        match operands.iter().find_map(|(_, ty)| vector_shape(ty)) {
+            Some((num_elements, scalable)) => Ok(vector_of(
+                &mut self.types.builder,
+                result_type,
+                num_elements,
+                scalable,
+            )),
+            None => Ok(result_type),
+        }
+    }
+
+    fn section(&self, index: Option<&u64>) -> Result<Option<String>, Error> {
+        match index {
+            None | Some(0) => Ok(None),
+            Some(&index) => self
+                .section_names
+                .get(index as usize - 1)
+                .cloned()
+                .map(Some)
+                .ok_or(Error::InvalidBitcode),
+        }
+    }
+
+    fn comdat(&self, index: Option<&u64>) -> Result<Option<Comdat>, Error> {
+        match index {
+            None | Some(0) => Ok(None),
+            Some(&index) => self
+                .comdats
+                .get(index as usize - 1)
+                .cloned()
+                .map(Some)
+                .ok_or(Error::InvalidBitcode),
+        }
+    }
+
+    /// Create the `Module`, once the module block has been read
+    fn finish(mut self, name: String) -> Result<Module, Error> {
+        self.define_globals();
+        let mut functions = vec![];
+        let mut global_vars = vec![];
+        let mut global_aliases = vec![];
+        let mut bodies = std::mem::take(&mut self.bodies).into_iter();
+        let names = self.global_names();
+        let globals = std::mem::take(&mut self.globals);
+        for (global, global_name) in globals.into_iter().zip(names) {
+            let ops = &global.ops;
+            let get = |i: usize| ops.get(i).copied().unwrap_or(0);
+            match global.kind {
+                GlobalKind::Function if global.is_defined_function() => {
+                    let body = bodies.next().ok_or(Error::InvalidBitcode)?;
+                    let (return_type, is_var_arg) = match global.ty.as_ref() {
+                        Type::FuncType {
+                            result_type,
+                            is_var_arg,
+                            ..
+                        } => (result_type.clone(), *is_var_arg),
+                        _ => return Err(Error::InvalidBitcode),
+                    };
+                    functions.push(Function {
+                        name: String::from_utf8_lossy(&global.name).into_owned(),
+                        parameters: body.parameters,
+                        is_var_arg,
+                        return_type,
+                        basic_blocks: body.basic_blocks,
+                        function_attributes: vec![],
+                        function_attribute_group: None,
+                        return_attributes: vec![],
+                        linkage: decode_linkage(op(ops, 3)?)?,
+                        visibility: decode_visibility(get(7))?,
+                        dll_storage_class: decode_dll_storage_class(get(11))?,
+                        calling_convention: CallingConvention::from_u32(op(ops, 1)? as u32),
+                        section: self.section(ops.get(6))?,
+                        comdat: self.comdat(ops.get(12))?,
+                        addr_space: global.addr_space,
+                        alignment: decode_alignment(get(5))?,
+                        garbage_collector_name: match get(8) {
+                            0 => None,
+                            index => Some(
+                                self.gc_names
+                                    .get(index as usize - 1)
+                                    .cloned()
+                                    .ok_or(Error::InvalidBitcode)?,
+                            ),
+                        },
+                        personality_function: match get(14) {
+                            0 => None,
+                            id => Some(self.constant(id - 1)?),
+                        },
+                        #[cfg(feature = "llvm-9-or-greater")]
+                        debugloc: None,
+                        #[cfg(feature = "llvm-9-or-greater")]
+                        subprogram: None,
+                    });
+                },
+                GlobalKind::GlobalVariable => global_vars.push(GlobalVariable {
+                    name: global_name,
+                    linkage: decode_linkage(op(ops, 3)?)?,
+                    visibility: decode_visibility(get(6))?,
+                    is_constant: op(ops, 1)? & 1 != 0,
+                    ty: self
+                        .types
+                        .builder
+                        .pointer_for(global.ty.clone(), global.addr_space),
+                    addr_space: global.addr_space,
+                    dll_storage_class: decode_dll_storage_class(get(10))?,
+                    thread_local_mode: decode_thread_local_mode(get(7))?,
+                    unnamed_addr: decode_unnamed_addr(get(8))?,
+                    initializer: match op(ops, 2)? {
+                        0 => None,
+                        id => Some(self.constant(id - 1)?),
+                    },
+                    section: self.section(ops.get(5))?,
+                    comdat: self.comdat(ops.get(11))?,
+                    alignment: decode_alignment(op(ops, 4)?)?,
+                    #[cfg(feature = "llvm-9-or-greater")]
+                    debugloc: None,
+                }),
+                GlobalKind::GlobalAlias => global_aliases.push(GlobalAlias {
+                    name: global_name,
+                    aliasee: self.constant(op(ops, 2)?)?,
+                    linkage: decode_linkage(op(ops, 3)?)?,
+                    visibility: decode_visibility(get(4))?,
+                    ty: self
+                        .types
+                        .builder
+                        .pointer_for(global.ty.clone(), global.addr_space),
+                    addr_space: global.addr_space,
+                    dll_storage_class: decode_dll_storage_class(get(5))?,
+                    thread_local_mode: decode_thread_local_mode(get(6))?,
+                    unnamed_addr: decode_unnamed_addr(get(7))?,
+                }),
+                _ => {},
+            }
+        }
+        Ok(Module {
+            source_file_name: self.source_file_name.unwrap_or_else(|| name.clone()),
+            name,
+            data_layout: self.data_layout,
+            target_triple: Some(self.triple.unwrap_or_default()),
+            functions,
+            global_vars,
+            global_aliases,
+            function_attribute_groups: vec![],
+            inline_assembly: split_inline_asm(&self.inline_asm),
+            metadata_nodes: vec![],
+            upgraded_intrinsics: vec![],
+            producers: vec![],
+            llvm_version: None,
+            types: self.types.builder.build(),
+        })
+    }
+}
+
+/// The instructions of a function, as they are read from its block
+#[derive(Default)]
+struct FunctionBlock {
+    /// The instructions and terminator of each basic block
+    blocks: Vec<(Vec<Instruction>, Option<Terminator>)>,
+    /// The index of the basic block currently being read
+    current: usize,
+    /// Names from the function's value symbol table, by value ID
+    value_names: HashMap<u64, Vec<u8>>,
+    /// Names from the function's value symbol table, by basic block index
+    block_names: HashMap<u64, Vec<u8>>,
+}
+
+impl FunctionBlock {
+    /// Get the placeholder `Name` of the basic block with the given index
+    fn block_name(&self, index: u64) -> Result<Name, Error> {
+        if (index as usize) < self.blocks.len() {
+            Ok(Name::Number(block_placeholder(index as usize)))
+        } else {
+            Err(Error::InvalidBitcode)
+        }
+    }
+}
+
+/// Until a function's names are known, its values are named with their value
+/// IDs, and its basic blocks with numbers counting down from `usize::MAX`
+fn block_placeholder(index: usize) -> usize {
+    usize::MAX - index
+}
+
+/// The number of elements of a vector type, and whether it is scalable
+fn vector_shape(ty: &TypeRef) -> Option<(usize, bool)> {
+    match ty.as_ref() {
+        #[cfg(feature = "llvm-11-or-greater")]
+        Type::VectorType {
+            num_elements,
+            scalable,
+            ..
+        } => Some((*num_elements, *scalable)),
+        #[cfg(feature = "llvm-10-or-lower")]
+        Type::VectorType { num_elements, .. } => Some((*num_elements, false)),
+        _ => None,
+    }
+}
+
+/// A cursor over the operands of an instruction record
+struct Ops<'r> {
+    ops: &'r [u64],
+    pos: usize,
+    /// The value ID the instruction's result would get, which value IDs in
+    /// the record are relative to
+    next_value: u64,
+}
+
+impl<'r> Ops<'r> {
+    fn next(&mut self) -> Result<u64, Error> {
+        let value = op(self.ops, self.pos)?;
+        self.pos += 1;
+        Ok(value)
+    }
+
+    fn remaining(&self) -> usize {
+        self.ops.len().saturating_sub(self.pos)
+    }
+
+    /// Read a relative value ID. IDs of values defined later wrap around, as
+    /// 32-bit numbers.
+    fn value_id(&mut self) -> Result<u64, Error> {
+        let relative = self.next()?;
+        Ok(u64::from(
+            (self.next_value as u32).wrapping_sub(relative as u32),
+        ))
+    }
+
+    /// Read a relative value ID which is stored as a signed number (as in
+    /// `phi` records)
+    fn signed_value_id(&mut self) -> Result<u64, Error> {
+        let relative = decode_sign_rotated(self.next()?);
+        Ok(self.next_value.wrapping_sub(relative))
+    }
+}
@@ -0,0 +1,279 @@
+//! A flat enumeration of instruction kinds, for when the operands of an
+//! instruction don't matter.
+
+use crate::instruction::Instruction;
+use crate::terminator::Terminator;
+use std::fmt::{self, Display};
+
+/// The kind of an [`Instruction`](../instruction/enum.Instruction.html) or
+/// [`Terminator`](../terminator/enum.Terminator.html), without any of its
+/// operands. Useful as a key for statistics or dispatch tables.
+///
+/// Each `Opcode` displays as its LLVM mnemonic, e.g. `getelementptr`, or as
+/// e.g. `<opcode 67>` for the opcodes `llvm-ir` doesn't model. The
+/// unconditional and conditional branches are both `Br`, as in LLVM.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Opcode {
+    // Terminators
+    Ret,
+    Br,
+    Switch,
+    IndirectBr,
+    Invoke,
+    Resume,
+    Unreachable,
+    CleanupRet,
+    CatchRet,
+    CatchSwitch,
+    #[cfg(feature = "llvm-9-or-greater")]
+    CallBr,
+    /// A [`terminator::Other`](../terminator/struct.Other.html), with its
+    /// numeric LLVM opcode
+    OtherTerminator(u32),
+    // Instructions
+    Add,
+    Sub,
+    Mul,
+    UDiv,
+    SDiv,
+    URem,
+    SRem,
+    And,
+    Or,
+    Xor,
+    Shl,
+    LShr,
+    AShr,
+    FAdd,
+    FSub,
+    FMul,
+    FDiv,
+    FRem,
+    FNeg,
+    ExtractElement,
+    InsertElement,
+    ShuffleVector,
+    ExtractValue,
+    InsertValue,
+    Alloca,
+    Load,
+    Store,
+    Fence,
+    CmpXchg,
+    AtomicRMW,
+    GetElementPtr,
+    Trunc,
+    ZExt,
+    SExt,
+    FPTrunc,
+    FPExt,
+    FPToUI,
+    FPToSI,
+    UIToFP,
+    SIToFP,
+    PtrToInt,
+    IntToPtr,
+    BitCast,
+    AddrSpaceCast,
+    ICmp,
+    FCmp,
+    Phi,
+    Select,
+    #[cfg(feature = "llvm-10-or-greater")]
+    Freeze,
+    Call,
+    VAArg,
+    LandingPad,
+    CatchPad,
+    CleanupPad,
+    /// An [`instruction::Other`](../instruction/struct.Other.html), with its
+    /// numeric LLVM opcode
+    Other(u32),
+}
+
+impl Opcode {
+    /// Is this the opcode of a [`Terminator`](../terminator/enum.Terminator.html)?
+    pub fn is_terminator(self) -> bool {
+        match self {
+            Opcode::Ret
+            | Opcode::Br
+            | Opcode::Switch
+            | Opcode::IndirectBr
+            | Opcode::Invoke
+            | Opcode::Resume
+            | Opcode::Unreachable
+            | Opcode::CleanupRet
+            | Opcode::CatchRet
+            | Opcode::CatchSwitch
+            | Opcode::OtherTerminator(_) => true,
+            #[cfg(feature = "llvm-9-or-greater")]
+            Opcode::CallBr => true,
+            _ => false,
+        }
+    }
+}
+
+impl Display for Opcode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mnemonic = match self {
+            Opcode::Ret => "ret",
+            Opcode::Br => "br",
+            Opcode::Switch => "switch",
+            Opcode::IndirectBr => "indirectbr",
+            Opcode::Invoke => "invoke",
+            Opcode::Resume => "resume",
+            Opcode::Unreachable => "unreachable",
+            Opcode::CleanupRet => "cleanupret",
+            Opcode::CatchRet => "catchret",
+            Opcode::CatchSwitch => "catchswitch",
+            #[cfg(feature = "llvm-9-or-greater")]
+            Opcode::CallBr => "callbr",
+            Opcode::OtherTerminator(opcode) | Opcode::Other(opcode) => {
+                return write!(f, "<opcode {}>", opcode);
+            },
+            Opcode::Add => "add",
+            Opcode::Sub => "sub",
+            Opcode::Mul => "mul",
+            Opcode::UDiv => "udiv",
+            Opcode::SDiv => "sdiv",
+            Opcode::URem => "urem",
+            Opcode::SRem => "srem",
+            Opcode::And => "and",
+            Opcode::Or => "or",
+            Opcode::Xor => "xor",
+            Opcode::Shl => "shl",
+            Opcode::LShr => "lshr",
+            Opcode::AShr => "ashr",
+            Opcode::FAdd => "fadd",
+            Opcode::FSub => "fsub",
+            Opcode::FMul => "fmul",
+            Opcode::FDiv => "fdiv",
+            Opcode::FRem => "frem",
+            Opcode::FNeg => "fneg",
+            Opcode::ExtractElement => "extractelement",
+            Opcode::InsertElement => "insertelement",
+            Opcode::ShuffleVector => "shufflevector",
+            Opcode::ExtractValue => "extractvalue",
+            Opcode::InsertValue => "insertvalue",
+            Opcode::Alloca => "alloca",
+            Opcode::Load => "load",
+            Opcode::Store => "store",
+            Opcode::Fence => "fence",
+            Opcode::CmpXchg => "cmpxchg",
+            Opcode::AtomicRMW => "atomicrmw",
+            Opcode::GetElementPtr => "getelementptr",
+            Opcode::Trunc => "trunc",
+            Opcode::ZExt => "zext",
+            Opcode::SExt => "sext",
+            Opcode::FPTrunc => "fptrunc",
+            Opcode::FPExt => "fpext",
+            Opcode::FPToUI => "fptoui",
+            Opcode::FPToSI => "fptosi",
+            Opcode::UIToFP => "uitofp",
+            Opcode::SIToFP => "sitofp",
+            Opcode::PtrToInt => "ptrtoint",
+            Opcode::IntToPtr => "inttoptr",
+            Opcode::BitCast => "bitcast",
+            Opcode::AddrSpaceCast => "addrspacecast",
+            Opcode::ICmp => "icmp",
+            Opcode::FCmp => "fcmp",
+            Opcode::Phi => "phi",
+            Opcode::Select => "select",
+            #[cfg(feature = "llvm-10-or-greater")]
+            Opcode::Freeze => "freeze",
+            Opcode::Call => "call",
+            Opcode::VAArg => "va_arg",
+            Opcode::LandingPad => "landingpad",
+            Opcode::CatchPad => "catchpad",
+            Opcode::CleanupPad => "cleanuppad",
+        };
+        write!(f, "{}", mnemonic)
+    }
+}
+
+impl Instruction {
+    /// Get the `Opcode` of the `Instruction`
+    pub fn opcode(&self) -> Opcode {
+        match self {
+            Instruction::Add(_) => Opcode::Add,
+            Instruction::Sub(_) => Opcode::Sub,
+            Instruction::Mul(_) => Opcode::Mul,
+            Instruction::UDiv(_) => Opcode::UDiv,
+            Instruction::SDiv(_) => Opcode::SDiv,
+            Instruction::URem(_) => Opcode::URem,
+            Instruction::SRem(_) => Opcode::SRem,
+            Instruction::And(_) => Opcode::And,
+            Instruction::Or(_) => Opcode::Or,
+            Instruction::Xor(_) => Opcode::Xor,
+            Instruction::Shl(_) => Opcode::Shl,
+            Instruction::LShr(_) => Opcode::LShr,
+            Instruction::AShr(_) => Opcode::AShr,
+            Instruction::FAdd(_) => Opcode::FAdd,
+            Instruction::FSub(_) => Opcode::FSub,
+            Instruction::FMul(_) => Opcode::FMul,
+            Instruction::FDiv(_) => Opcode::FDiv,
+            Instruction::FRem(_) => Opcode::FRem,
+            Instruction::FNeg(_) => Opcode::FNeg,
+            Instruction::ExtractElement(_) => Opcode::ExtractElement,
+            Instruction::InsertElement(_) => Opcode::InsertElement,
+            Instruction::ShuffleVector(_) => Opcode::ShuffleVector,
+            Instruction::ExtractValue(_) => Opcode::ExtractValue,
+            Instruction::InsertValue(_) => Opcode::InsertValue,
+            Instruction::Alloca(_) => Opcode::Alloca,
+            Instruction::Load(_) => Opcode::Load,
+            Instruction::Store(_) => Opcode::Store,
+            Instruction::Fence(_) => Opcode::Fence,
+            Instruction::CmpXchg(_) => Opcode::CmpXchg,
+            Instruction::AtomicRMW(_) => Opcode::AtomicRMW,
+            Instruction::GetElementPtr(_) => Opcode::GetElementPtr,
+            Instruction::Trunc(_) => Opcode::Trunc,
+            Instruction::ZExt(_) => Opcode::ZExt,
+            Instruction::SExt(_) => Opcode::SExt,
+            Instruction::FPTrunc(_) => Opcode::FPTrunc,
+            Instruction::FPExt(_) => Opcode::FPExt,
+            Instruction::FPToUI(_) => Opcode::FPToUI,
+            Instruction::FPToSI(_) => Opcode::FPToSI,
+            Instruction::UIToFP(_) => Opcode::UIToFP,
+            Instruction::SIToFP(_) => Opcode::SIToFP,
+            Instruction::PtrToInt(_) => Opcode::PtrToInt,
+            Instruction::IntToPtr(_) => Opcode::IntToPtr,
+            Instruction::BitCast(_) => Opcode::BitCast,
+            Instruction::AddrSpaceCast(_) => Opcode::AddrSpaceCast,
+            Instruction::ICmp(_) => Opcode::ICmp,
+            Instruction::FCmp(_) => Opcode::FCmp,
+            Instruction::Phi(_) => Opcode::Phi,
+            Instruction::Select(_) => Opcode::Select,
+            #[cfg(feature = "llvm-10-or-greater")]
+            Instruction::Freeze(_) => Opcode::Freeze,
+            Instruction::Call(_) => Opcode::Call,
+            Instruction::VAArg(_) => Opcode::VAArg,
+            Instruction::LandingPad(_) => Opcode::LandingPad,
+            Instruction::CatchPad(_) => Opcode::CatchPad,
+            Instruction::CleanupPad(_) => Opcode::CleanupPad,
+            Instruction::Other(other) => Opcode::Other(other.opcode),
+        }
+    }
+}
+
+impl Terminator {
+    /// Get the `Opcode` of the `Terminator`. Both `Br` and `CondBr` have
+    /// opcode `Opcode::Br`.
+    pub fn opcode(&self) -> Opcode {
+        match self {
+            Terminator::Ret(_) => Opcode::Ret,
+            Terminator::Br(_) | Terminator::CondBr(_) => Opcode::Br,
+            Terminator::Switch(_) => Opcode::Switch,
+            Terminator::IndirectBr(_) => Opcode::IndirectBr,
+            Terminator::Invoke(_) => Opcode::Invoke,
+            Terminator::Resume(_) => Opcode::Resume,
+            Terminator::Unreachable(_) => Opcode::Unreachable,
+            Terminator::CleanupRet(_) => Opcode::CleanupRet,
+            Terminator::CatchRet(_) => Opcode::CatchRet,
+            Terminator::CatchSwitch(_) => Opcode::CatchSwitch,
+            #[cfg(feature = "llvm-9-or-greater")]
+            Terminator::CallBr(_) => Opcode::CallBr,
+            Terminator::Other(other) => Opcode::OtherTerminator(other.opcode),
+        }
+    }
+}
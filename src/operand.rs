@@ -1,17 +1,20 @@
+use crate::name::MapLocalNames;
 use crate::types::{TypeRef, Typed, Types};
-use crate::{ConstantRef, Name};
+use crate::{ConstantRef, Metadata, Name};
+use either::Either;
 use std::fmt::{self, Display};
+use std::ops::Deref;
+use std::sync::Arc;
 
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Operand {
     /// e.g., `i32 %foo`
-    LocalOperand {
-        name: Name,
-        ty: TypeRef,
-    },
+    LocalOperand { name: Name, ty: TypeRef },
     /// includes [`GlobalReference`](../constant/enum.Constant.html#variant.GlobalReference) for things like `@foo`
     ConstantOperand(ConstantRef),
-    MetadataOperand, // --TODO not yet implemented-- MetadataOperand(Box<Metadata>),
+    /// e.g., the `metadata i32 %x` in `call void @llvm.dbg.value(metadata i32 %x, ...)`
+    MetadataOperand(Box<Metadata>),
 }
 
 impl Typed for Operand {
@@ -19,7 +22,7 @@ impl Typed for Operand {
         match self {
             Operand::LocalOperand { ty, .. } => ty.clone(),
             Operand::ConstantOperand(c) => types.type_of(c),
-            Operand::MetadataOperand => types.metadata_type(),
+            Operand::MetadataOperand(_) => types.metadata_type(),
         }
     }
 }
@@ -43,6 +46,226 @@ impl Operand {
             _ => None,
         }
     }
+
+    /// Get the `Name` of the local value, if the operand is a local operand
+    /// (e.g., `i32 %foo`); otherwise, returns `None`.
+    pub fn as_local(&self) -> Option<&Name> {
+        match self {
+            Operand::LocalOperand { name, .. } => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Display the operand without its type, as LLVM does where the type is
+    /// implied by the context (e.g., `%foo` or `10` rather than `i32 %foo` or
+    /// `i32 10`)
+    pub(crate) fn untyped(&self) -> Untyped<'_> {
+        Untyped(self)
+    }
+}
+
+/// Displays an `Operand` without its type; see `Operand::untyped()`
+pub(crate) struct Untyped<'a>(&'a Operand);
+
+impl Display for Untyped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Operand::LocalOperand { name, .. } => write!(f, "{}", name),
+            Operand::ConstantOperand(cref) => cref.fmt_untyped(f),
+            Operand::MetadataOperand(_) => write!(f, "{}", self.0),
+        }
+    }
+}
+
+impl MapLocalNames for Operand {
+    fn map_local_names(&mut self, f: &mut dyn FnMut(&mut Name)) {
+        match self {
+            Operand::LocalOperand { name, .. } => f(name),
+            Operand::ConstantOperand(_) => {},
+            Operand::MetadataOperand(md) => {
+                if let Metadata::Value(op) = md.as_mut() {
+                    op.map_local_names(f);
+                }
+            },
+        }
+    }
+}
+
+/// An `OperandRef` is a reference to an [`Operand`](enum.Operand.html).
+/// Most importantly, it implements `AsRef<Operand>` and `Deref<Target = Operand>`.
+/// It also has a cheap `Clone` -- only the reference is cloned, not the
+/// underlying `Operand`.
+///
+/// When a `Module` is parsed, each distinct `Operand` in it is only created
+/// once, and all the `OperandRef`s to that `Operand` refer to the same copy.
+//
+// `Arc` is used rather than `Rc` so that `Module` can remain `Sync`, as for
+// `ConstantRef`.
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+pub struct OperandRef(Arc<Operand>);
+
+impl AsRef<Operand> for OperandRef {
+    fn as_ref(&self) -> &Operand {
+        self.0.as_ref()
+    }
+}
+
+impl Deref for OperandRef {
+    type Target = Operand;
+
+    fn deref(&self) -> &Operand {
+        self.0.deref()
+    }
+}
+
+impl PartialEq<Operand> for OperandRef {
+    fn eq(&self, other: &Operand) -> bool {
+        self.as_ref() == other
+    }
+}
+
+impl From<Operand> for OperandRef {
+    fn from(op: Operand) -> Self {
+        Self::new(op)
+    }
+}
+
+impl Typed for OperandRef {
+    fn get_type(&self, types: &Types) -> TypeRef {
+        self.as_ref().get_type(types)
+    }
+}
+
+impl Display for OperandRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", &self.0)
+    }
+}
+
+impl OperandRef {
+    /// Construct a new `OperandRef` by consuming the given owned `Operand`.
+    //
+    // Internal users should get `OperandRef`s from the `ModuleContext` cache
+    // instead if possible, so that if we already have that `Operand`
+    // somewhere, we can just give you a new `OperandRef` to that `Operand`.
+    pub fn new(op: Operand) -> Self {
+        Self(Arc::new(op))
+    }
+
+    /// Get a mutable reference to the `Operand`, first giving this
+    /// `OperandRef` its own copy of it if it is shared with other
+    /// `OperandRef`s
+    pub(crate) fn make_mut(&mut self) -> &mut Operand {
+        Arc::make_mut(&mut self.0)
+    }
+}
+
+// An `OperandRef` is serialized as the `Operand` it refers to
+#[cfg(feature = "serde")]
+impl serde::Serialize for OperandRef {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_ref().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for OperandRef {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <Operand as serde::Deserialize>::deserialize(deserializer).map(Self::new)
+    }
+}
+
+/// Only copies the `Operand` if it actually refers to a local name, so that
+/// shared constant operands stay shared
+impl MapLocalNames for OperandRef {
+    fn map_local_names(&mut self, f: &mut dyn FnMut(&mut Name)) {
+        if !matches!(self.as_ref(), Operand::ConstantOperand(_)) {
+            self.make_mut().map_local_names(f);
+        }
+    }
+}
+
+/// Collecting the `Operand`s of an instruction (or of one of its fields),
+/// in the order they appear in the instruction
+pub(crate) trait HasOperands {
+    fn push_operands<'a>(&'a self, out: &mut Vec<&'a Operand>);
+    fn push_operands_mut<'a>(&'a mut self, out: &mut Vec<&'a mut OperandRef>);
+}
+
+impl HasOperands for OperandRef {
+    fn push_operands<'a>(&'a self, out: &mut Vec<&'a Operand>) {
+        out.push(self)
+    }
+
+    fn push_operands_mut<'a>(&'a mut self, out: &mut Vec<&'a mut OperandRef>) {
+        out.push(self)
+    }
+}
+
+impl HasOperands for Name {
+    fn push_operands<'a>(&'a self, _out: &mut Vec<&'a Operand>) {}
+    fn push_operands_mut<'a>(&'a mut self, _out: &mut Vec<&'a mut OperandRef>) {}
+}
+
+impl HasOperands for ConstantRef {
+    fn push_operands<'a>(&'a self, _out: &mut Vec<&'a Operand>) {}
+    fn push_operands_mut<'a>(&'a mut self, _out: &mut Vec<&'a mut OperandRef>) {}
+}
+
+impl<T: HasOperands> HasOperands for Vec<T> {
+    fn push_operands<'a>(&'a self, out: &mut Vec<&'a Operand>) {
+        for t in self {
+            t.push_operands(out);
+        }
+    }
+
+    fn push_operands_mut<'a>(&'a mut self, out: &mut Vec<&'a mut OperandRef>) {
+        for t in self {
+            t.push_operands_mut(out);
+        }
+    }
+}
+
+impl<T: HasOperands> HasOperands for Option<T> {
+    fn push_operands<'a>(&'a self, out: &mut Vec<&'a Operand>) {
+        if let Some(t) = self {
+            t.push_operands(out);
+        }
+    }
+
+    fn push_operands_mut<'a>(&'a mut self, out: &mut Vec<&'a mut OperandRef>) {
+        if let Some(t) = self {
+            t.push_operands_mut(out);
+        }
+    }
+}
+
+impl<A: HasOperands, B: HasOperands> HasOperands for (A, B) {
+    fn push_operands<'a>(&'a self, out: &mut Vec<&'a Operand>) {
+        self.0.push_operands(out);
+        self.1.push_operands(out);
+    }
+
+    fn push_operands_mut<'a>(&'a mut self, out: &mut Vec<&'a mut OperandRef>) {
+        self.0.push_operands_mut(out);
+        self.1.push_operands_mut(out);
+    }
+}
+
+impl<A: HasOperands, B: HasOperands> HasOperands for Either<A, B> {
+    fn push_operands<'a>(&'a self, out: &mut Vec<&'a Operand>) {
+        match self {
+            Either::Left(a) => a.push_operands(out),
+            Either::Right(b) => b.push_operands(out),
+        }
+    }
+
+    fn push_operands_mut<'a>(&'a mut self, out: &mut Vec<&'a mut OperandRef>) {
+        match self {
+            Either::Left(a) => a.push_operands_mut(out),
+            Either::Right(b) => b.push_operands_mut(out),
+        }
+    }
 }
 
 impl Display for Operand {
@@ -50,7 +273,7 @@ impl Display for Operand {
         match self {
             Operand::LocalOperand { name, ty } => write!(f, "{} {}", ty, name),
             Operand::ConstantOperand(cref) => write!(f, "{}", &cref),
-            Operand::MetadataOperand => write!(f, "<metadata>"),
+            Operand::MetadataOperand(md) => write!(f, "metadata {}", md),
         }
     }
 }
@@ -60,6 +283,7 @@ impl Display for Operand {
 // ********* //
 
 use crate::constant::Constant;
+use crate::error::Error;
 use crate::function::FunctionContext;
 use crate::llvm_sys::*;
 use crate::module::ModuleContext;
@@ -70,14 +294,14 @@ impl Operand {
         operand: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &FunctionContext,
-    ) -> Self {
+    ) -> Result<Self, Error> {
         let constant = unsafe { LLVMIsAConstant(operand) };
-        if !constant.is_null() {
-            Operand::ConstantOperand(Constant::from_llvm_ref(constant, ctx))
+        Ok(if !constant.is_null() {
+            Operand::ConstantOperand(Constant::from_llvm_ref(constant, ctx)?)
         } else if unsafe {
             LLVMGetValueKind(operand) == LLVMValueKind::LLVMMetadataAsValueValueKind
         } {
-            Operand::MetadataOperand
+            Operand::MetadataOperand(Box::new(Metadata::from_llvm_ref(operand, ctx, func_ctx)?))
         } else {
             Operand::LocalOperand {
                 name: func_ctx.val_names
@@ -93,6 +317,29 @@ impl Operand {
                     .clone(),
                 ty: ctx.types.type_from_llvm_ref(unsafe { LLVMTypeOf(operand) }),
             }
+        })
+    }
+}
+
+impl OperandRef {
+    /// Like `Operand::from_llvm_ref()`, but gets the `OperandRef` from the
+    /// `ModuleContext` cache if that operand was already converted, so that
+    /// each distinct local or constant operand is only created once
+    pub(crate) fn from_llvm_ref(
+        operand: LLVMValueRef,
+        ctx: &mut ModuleContext,
+        func_ctx: &FunctionContext,
+    ) -> Result<Self, Error> {
+        // metadata operands aren't shared; they're rare, and their conversion
+        // depends on more than just the `LLVMValueRef`
+        if unsafe { LLVMGetValueKind(operand) == LLVMValueKind::LLVMMetadataAsValueValueKind } {
+            return Ok(Self::new(Operand::from_llvm_ref(operand, ctx, func_ctx)?));
+        }
+        if let Some(opref) = ctx.operands.get(&operand) {
+            return Ok(opref.clone());
         }
+        let opref = Self::new(Operand::from_llvm_ref(operand, ctx, func_ctx)?);
+        ctx.operands.insert(operand, opref.clone());
+        Ok(opref)
     }
 }
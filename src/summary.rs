@@ -0,0 +1,486 @@
+//! ThinLTO module summaries.
+//!
+//! When compiling for ThinLTO (or with `opt -module-summary`), LLVM adds a
+//! summary of each global value to the bitcode: for functions, their size,
+//! the globals they reference, and the functions they call. This allows
+//! whole-program tools to reason about a program cheaply, without looking at
+//! every function body.
+//! See [LLVM 14 docs on ThinLTO](https://releases.llvm.org/14.0.0/docs/ThinLTO.html).
+//!
+//! The LLVM C API doesn't give access to summaries, so this module reads
+//! them from the bitcode directly. Only per-module summaries are supported,
+//! not the combined summaries of ThinLTO index files.
+
+use crate::bitstream::{BitstreamReader, Entry};
+use crate::error::Error;
+use crate::module::Linkage;
+use std::path::Path;
+
+/// The summary of a bitcode module, as written by LLVM for ThinLTO
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ModuleSummaryIndex {
+    /// Version of LLVM's summary format
+    pub version: u64,
+    /// The summaries of the module's global values, in the order they appear
+    /// in the bitcode
+    pub summaries: Vec<GlobalValueSummary>,
+}
+
+/// The summary of one global value (function, global variable, or alias)
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct GlobalValueSummary {
+    /// The name of the global value
+    pub name: String,
+    pub linkage: Linkage,
+    /// Whether the global value can't be imported into other modules
+    pub not_eligible_to_import: bool,
+    /// Whether the global value is known to be live. LLVM only computes this
+    /// when linking, so it is usually `false` in per-module summaries.
+    pub live: bool,
+    /// Whether the global value is known to be defined in the same linkage
+    /// unit as its uses
+    pub dso_local: bool,
+    pub kind: SummaryKind,
+}
+
+/// The kind-specific part of a `GlobalValueSummary`
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum SummaryKind {
+    Function(FunctionSummary),
+    GlobalVariable(GlobalVarSummary),
+    Alias {
+        /// Name of the global value the alias refers to
+        aliasee: String,
+    },
+}
+
+/// The summary of a function
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct FunctionSummary {
+    /// Number of instructions in the function
+    pub instruction_count: u64,
+    /// Whether the function is known not to access memory
+    pub read_none: bool,
+    /// Whether the function is known not to write memory
+    pub read_only: bool,
+    /// Whether the function is known not to recurse
+    pub no_recurse: bool,
+    /// Whether the function's return value is known not to alias anything
+    pub return_does_not_alias: bool,
+    /// Whether the function is marked `noinline`
+    pub no_inline: bool,
+    /// Names of the global values referenced by the function, other than by
+    /// calling them
+    pub refs: Vec<String>,
+    /// The function's calls
+    pub calls: Vec<CallEdge>,
+}
+
+/// The summary of a global variable
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct GlobalVarSummary {
+    /// Whether the variable might only be read (not written)
+    pub maybe_read_only: bool,
+    /// Whether the variable might only be written (not read)
+    pub maybe_write_only: bool,
+    /// Names of the global values referenced by the variable's initializer
+    pub refs: Vec<String>,
+}
+
+/// A call edge in the summary's call graph
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct CallEdge {
+    /// Name of the function being called
+    pub callee: String,
+    /// How hot the call is, if profile data was available
+    pub hotness: Hotness,
+    /// Frequency of the call relative to the entry of the calling function,
+    /// if LLVM recorded it (as a fixed-point number, scaled by 2^8)
+    pub relative_block_frequency: Option<u64>,
+}
+
+/// How hot a call is, according to profile data
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum Hotness {
+    Unknown,
+    Cold,
+    None,
+    Hot,
+    Critical,
+}
+
+impl ModuleSummaryIndex {
+    /// Read the summary of the LLVM bitcode (.bc) file at the given path.
+    /// Returns `Ok(None)` if the bitcode doesn't have a summary.
+    pub fn from_bc_path(path: impl AsRef<Path>) -> Result<Option<Self>, Error> {
+        let bitcode = std::fs::read(path).map_err(|e| Error::ReadFailed(e.to_string()))?;
+        Self::from_bc_bytes(&bitcode)
+    }
+
+    /// Read the summary of the given LLVM bitcode. If the bitcode contains
+    /// several modules, this reads the summary of the first.
+    /// Returns `Ok(None)` if the bitcode doesn't have a summary.
+    pub fn from_bc_bytes(bitcode: &[u8]) -> Result<Option<Self>, Error> {
+        RawModule::read(bitcode)?.summary_index()
+    }
+
+    /// Get the summary of the global value with the given name, if there is
+    /// one.
+    pub fn get_summary(&self, name: &str) -> Option<&GlobalValueSummary> {
+        self.summaries.iter().find(|summary| summary.name == name)
+    }
+
+    /// Iterate over the summarized functions, with their names.
+    pub fn functions(&self) -> impl Iterator<Item = (&str, &FunctionSummary)> {
+        self.summaries
+            .iter()
+            .filter_map(|summary| match &summary.kind {
+                SummaryKind::Function(func) => Some((summary.name.as_str(), func)),
+                _ => None,
+            })
+    }
+
+    /// Iterate over all the call edges in the summary, with the names of the
+    /// calling functions.
+    pub fn call_edges(&self) -> impl Iterator<Item = (&str, &CallEdge)> {
+        self.functions()
+            .flat_map(|(name, func)| func.calls.iter().map(move |call| (name, call)))
+    }
+}
+
+/// Get the names of the functions declared or defined in the given bitcode,
+/// in the order of their records, which is the order in which LLVM creates
+/// them when reading the bitcode. These are the names as written, before LLVM
+/// renames any intrinsics it auto-upgrades.
+pub(crate) fn function_names(bitcode: &[u8]) -> Result<Vec<String>, Error> {
+    let raw = RawModule::read(bitcode)?;
+    raw.names_of(&raw.function_ids)
+}
+
+// Block IDs
+const MODULE_BLOCK_ID: u64 = 8;
+const VALUE_SYMTAB_BLOCK_ID: u64 = 14;
+const GLOBALVAL_SUMMARY_BLOCK_ID: u64 = 20;
+const STRTAB_BLOCK_ID: u64 = 23;
+
+// Record codes in the module block
+const MODULE_CODE_VERSION: u64 = 1;
+const MODULE_CODE_GLOBALVAR: u64 = 7;
+const MODULE_CODE_FUNCTION: u64 = 8;
+const MODULE_CODE_ALIAS_OLD: u64 = 9;
+const MODULE_CODE_ALIAS: u64 = 14;
+const MODULE_CODE_IFUNC: u64 = 15;
+
+// Record codes in the value symbol table block
+const VST_CODE_ENTRY: u64 = 1;
+const VST_CODE_FNENTRY: u64 = 3;
+
+// Record codes in the string table block
+const STRTAB_BLOB: u64 = 1;
+
+// Record codes in the summary block
+const FS_PERMODULE: u64 = 1;
+const FS_PERMODULE_PROFILE: u64 = 2;
+const FS_PERMODULE_GLOBALVAR_INIT_REFS: u64 = 3;
+const FS_ALIAS: u64 = 7;
+const FS_VERSION: u64 = 10;
+const FS_PERMODULE_RELBF: u64 = 19;
+const FS_PERMODULE_VTABLE_GLOBALVAR_INIT_REFS: u64 = 23;
+
+/// The parts of a bitcode module needed to interpret its summary, or to name
+/// its functions
+#[derive(Default)]
+struct RawModule<'a> {
+    /// Version of the module block. From version 2, names are stored in the
+    /// string table.
+    version: u64,
+    /// For each module-level value (global variable, function, alias, or
+    /// ifunc), in order of value ID: its (offset, size) in the string table
+    strtab_refs: Vec<Option<(usize, usize)>>,
+    /// The value IDs of the functions, in the order of their records
+    function_ids: Vec<u64>,
+    /// Names from the module-level value symbol table, by value ID (before
+    /// version 2)
+    symtab_names: Vec<(u64, Vec<u8>)>,
+    strtab: &'a [u8],
+    /// The summary records, if there is a summary
+    summary: Option<Vec<(u64, Vec<u64>)>>,
+}
+
+impl<'a> RawModule<'a> {
+    fn read(bitcode: &'a [u8]) -> Result<Self, Error> {
+        let mut reader = BitstreamReader::new(bitcode)?;
+        let mut raw = Self::default();
+        let mut seen_module = false;
+        while let Some(entry) = reader.next()? {
+            match entry {
+                Entry::SubBlock(block) if block.id == MODULE_BLOCK_ID && !seen_module => {
+                    reader.enter_block(&block);
+                    raw.read_module_block(&mut reader)?;
+                    seen_module = true;
+                },
+                Entry::SubBlock(block) if block.id == STRTAB_BLOCK_ID && raw.strtab.is_empty() => {
+                    reader.enter_block(&block);
+                    loop {
+                        match reader.next()?.ok_or(Error::InvalidBitcode)? {
+                            Entry::Record(record) if record.code == STRTAB_BLOB => {
+                                raw.strtab = record.blob.ok_or(Error::InvalidBitcode)?;
+                            },
+                            Entry::Record(_) => {},
+                            Entry::SubBlock(block) => reader.skip_block(&block),
+                            Entry::EndBlock => break,
+                        }
+                    }
+                },
+                Entry::SubBlock(block) => reader.skip_block(&block),
+                Entry::Record(_) | Entry::EndBlock => {},
+            }
+        }
+        if !seen_module {
+            return Err(Error::InvalidBitcode);
+        }
+        Ok(raw)
+    }
+
+    fn read_module_block(&mut self, reader: &mut BitstreamReader<'a>) -> Result<(), Error> {
+        loop {
+            match reader.next()?.ok_or(Error::InvalidBitcode)? {
+                Entry::EndBlock => return Ok(()),
+                Entry::Record(record) => match record.code {
+                    MODULE_CODE_VERSION => {
+                        self.version = *record.ops.first().ok_or(Error::InvalidBitcode)?;
+                    },
+                    MODULE_CODE_GLOBALVAR
+                    | MODULE_CODE_FUNCTION
+                    | MODULE_CODE_ALIAS_OLD
+                    | MODULE_CODE_ALIAS
+                    | MODULE_CODE_IFUNC => {
+                        let strtab_ref = if self.version >= 2 && record.ops.len() >= 2 {
+                            Some((record.ops[0] as usize, record.ops[1] as usize))
+                        } else {
+                            None
+                        };
+                        if record.code == MODULE_CODE_FUNCTION {
+                            self.function_ids.push(self.strtab_refs.len() as u64);
+                        }
+                        self.strtab_refs.push(strtab_ref);
+                    },
+                    _ => {},
+                },
+                Entry::SubBlock(block) if block.id == GLOBALVAL_SUMMARY_BLOCK_ID => {
+                    reader.enter_block(&block);
+                    let mut records = vec![];
+                    loop {
+                        match reader.next()?.ok_or(Error::InvalidBitcode)? {
+                            Entry::Record(record) => records.push((record.code, record.ops)),
+                            Entry::SubBlock(block) => reader.skip_block(&block),
+                            Entry::EndBlock => break,
+                        }
+                    }
+                    self.summary = Some(records);
+                },
+                Entry::SubBlock(block) if block.id == VALUE_SYMTAB_BLOCK_ID => {
+                    reader.enter_block(&block);
+                    loop {
+                        match reader.next()?.ok_or(Error::InvalidBitcode)? {
+                            Entry::Record(record) => {
+                                let name_start = match record.code {
+                                    VST_CODE_ENTRY => 1,
+                                    VST_CODE_FNENTRY => 2,
+                                    _ => continue,
+                                };
+                                if record.ops.len() > name_start {
+                                    self.symtab_names
+                                        .push((record.ops[0], record.string_from(name_start)));
+                                }
+                            },
+                            Entry::SubBlock(block) => reader.skip_block(&block),
+                            Entry::EndBlock => break,
+                        }
+                    }
+                },
+                Entry::SubBlock(block) => reader.skip_block(&block),
+            }
+        }
+    }
+
+    /// Get the name of the module-level value with the given value ID
+    fn name_of(&self, value_id: u64) -> Result<String, Error> {
+        let strtab_ref = self
+            .strtab_refs
+            .get(value_id as usize)
+            .ok_or(Error::InvalidBitcode)?;
+        let bytes = match strtab_ref {
+            Some((offset, size)) => self
+                .strtab
+                .get(*offset .. offset + size)
+                .ok_or(Error::InvalidBitcode)?,
+            None => self
+                .symtab_names
+                .iter()
+                .find(|(id, _)| *id == value_id)
+                .map(|(_, name)| name.as_slice())
+                .unwrap_or_default(),
+        };
+        Ok(String::from_utf8_lossy(bytes).into_owned())
+    }
+
+    fn names_of(&self, value_ids: &[u64]) -> Result<Vec<String>, Error> {
+        value_ids.iter().map(|&id| self.name_of(id)).collect()
+    }
+
+    fn summary_index(&self) -> Result<Option<ModuleSummaryIndex>, Error> {
+        let records = match &self.summary {
+            Some(records) => records,
+            None => return Ok(None),
+        };
+        let mut version = 0;
+        let mut summaries = vec![];
+        for (code, ops) in records {
+            let (code, ops) = (*code, ops.as_slice());
+            let kind = match code {
+                FS_VERSION => {
+                    version = *ops.first().ok_or(Error::InvalidBitcode)?;
+                    continue;
+                },
+                FS_PERMODULE | FS_PERMODULE_PROFILE | FS_PERMODULE_RELBF => {
+                    SummaryKind::Function(self.function_summary(code, ops, version)?)
+                },
+                FS_PERMODULE_GLOBALVAR_INIT_REFS | FS_PERMODULE_VTABLE_GLOBALVAR_INIT_REFS => {
+                    let (var_flags, refs) = if version >= 5 {
+                        let var_flags = *ops.get(2).ok_or(Error::InvalidBitcode)?;
+                        let refs = if code == FS_PERMODULE_VTABLE_GLOBALVAR_INIT_REFS {
+                            let num_refs = *ops.get(3).ok_or(Error::InvalidBitcode)? as usize;
+                            ops.get(4 .. 4 + num_refs).ok_or(Error::InvalidBitcode)?
+                        } else {
+                            &ops[3 ..]
+                        };
+                        (var_flags, refs)
+                    } else {
+                        (0, ops.get(2 ..).ok_or(Error::InvalidBitcode)?)
+                    };
+                    SummaryKind::GlobalVariable(GlobalVarSummary {
+                        maybe_read_only: var_flags & 0x1 != 0,
+                        maybe_write_only: var_flags & 0x2 != 0,
+                        refs: self.names_of(refs)?,
+                    })
+                },
+                FS_ALIAS => SummaryKind::Alias {
+                    aliasee: self.name_of(*ops.get(2).ok_or(Error::InvalidBitcode)?)?,
+                },
+                _ => continue,
+            };
+            let value_id = *ops.first().ok_or(Error::InvalidBitcode)?;
+            let flags = *ops.get(1).ok_or(Error::InvalidBitcode)?;
+            summaries.push(GlobalValueSummary {
+                name: self.name_of(value_id)?,
+                linkage: decode_linkage(flags & 0xF)?,
+                not_eligible_to_import: (flags >> 4) & 0x1 != 0 || version < 3,
+                live: (flags >> 4) & 0x2 != 0 || version < 3,
+                dso_local: (flags >> 4) & 0x4 != 0,
+                kind,
+            });
+        }
+        Ok(Some(ModuleSummaryIndex { version, summaries }))
+    }
+
+    fn function_summary(
+        &self,
+        code: u64,
+        ops: &[u64],
+        version: u64,
+    ) -> Result<FunctionSummary, Error> {
+        let get = |index: usize| ops.get(index).copied().ok_or(Error::InvalidBitcode);
+        let instruction_count = get(2)?;
+        // the layout of the rest of the record depends on the version
+        let (function_flags, num_refs, refs_start) = match version {
+            0 ..= 3 => (0, get(3)?, 4),
+            4 => (get(3)?, get(4)?, 5),
+            5 | 6 => (get(3)?, get(4)?, 6),
+            _ => (get(3)?, get(4)?, 7),
+        };
+        let calls_start = refs_start + num_refs as usize;
+        let refs = ops
+            .get(refs_start .. calls_start)
+            .ok_or(Error::InvalidBitcode)?;
+        // each call edge is the callee's value ID, followed by (depending on
+        // the version and record kind) extra fields
+        let old_profile_format = version == 1;
+        let has_profile = code == FS_PERMODULE_PROFILE;
+        let has_relbf = code == FS_PERMODULE_RELBF;
+        let edge_len = if old_profile_format {
+            if has_profile {
+                3
+            } else {
+                2
+            }
+        } else if has_profile || has_relbf {
+            2
+        } else {
+            1
+        };
+        let calls = ops
+            .get(calls_start ..)
+            .ok_or(Error::InvalidBitcode)?
+            .chunks(edge_len)
+            .map(|edge| {
+                if edge.len() != edge_len {
+                    return Err(Error::InvalidBitcode);
+                }
+                let (hotness, relative_block_frequency) = if old_profile_format {
+                    (Hotness::Unknown, None)
+                } else if has_profile {
+                    (decode_hotness(edge[1]), None)
+                } else if has_relbf {
+                    (Hotness::Unknown, Some(edge[1]))
+                } else {
+                    (Hotness::Unknown, None)
+                };
+                Ok(CallEdge {
+                    callee: self.name_of(edge[0])?,
+                    hotness,
+                    relative_block_frequency,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(FunctionSummary {
+            instruction_count,
+            read_none: function_flags & 0x1 != 0,
+            read_only: function_flags & 0x2 != 0,
+            no_recurse: function_flags & 0x4 != 0,
+            return_does_not_alias: function_flags & 0x8 != 0,
+            no_inline: function_flags & 0x10 != 0,
+            refs: self.names_of(refs)?,
+            calls,
+        })
+    }
+}
+
+/// Decode a linkage as it appears in summary flags (which use the numbering
+/// of LLVM's `GlobalValue::LinkageTypes`, not the bitcode's usual numbering)
+fn decode_linkage(linkage: u64) -> Result<Linkage, Error> {
+    Ok(match linkage {
+        0 => Linkage::External,
+        1 => Linkage::AvailableExternally,
+        2 => Linkage::LinkOnceAny,
+        3 => Linkage::LinkOnceODR,
+        4 => Linkage::WeakAny,
+        5 => Linkage::WeakODR,
+        6 => Linkage::Appending,
+        7 => Linkage::Internal,
+        8 => Linkage::Private,
+        9 => Linkage::ExternalWeak,
+        10 => Linkage::Common,
+        _ => return Err(Error::InvalidBitcode),
+    })
+}
+
+fn decode_hotness(hotness: u64) -> Hotness {
+    match hotness {
+        1 => Hotness::Cold,
+        2 => Hotness::None,
+        3 => Hotness::Hot,
+        4 => Hotness::Critical,
+        _ => Hotness::Unknown,
+    }
+}
@@ -1,7 +1,11 @@
+use crate::constant::BigIntValue;
 #[cfg(feature = "llvm-9-or-greater")]
-use crate::debugloc::{DebugLoc, HasDebugLoc};
+use crate::debugloc::{DebugLoc, HasDebugLoc, HasDebugLocMut};
 use crate::function::{CallingConvention, FunctionAttribute, ParameterAttribute};
-use crate::instruction::{HasResult, InlineAssembly};
+use crate::instruction::{HasResult, InlineAssembly, OperandBundle};
+use crate::metadata::LoopMetadata;
+use crate::name::MapLocalNames;
+use crate::operand::{HasOperands, OperandRef};
 use crate::types::{Typed, Types};
 use crate::{Constant, ConstantRef, Name, Operand, Type, TypeRef};
 use either::Either;
@@ -10,7 +14,8 @@ use std::fmt::{self, Display};
 
 /// Terminator instructions end a basic block.
 /// See [LLVM 14 docs on Terminator Instructions](https://releases.llvm.org/14.0.0/docs/LangRef.html#terminator-instructions)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Terminator {
     Ret(Ret),
     Br(Br),
@@ -25,6 +30,8 @@ pub enum Terminator {
     CatchSwitch(CatchSwitch),
     #[cfg(feature = "llvm-9-or-greater")]
     CallBr(CallBr),
+    /// A terminator with an opcode `llvm-ir` doesn't otherwise handle
+    Other(Other),
 }
 
 /// The [`Type`](../enum.Type.html) of a `Terminator` is its result type.
@@ -49,6 +56,7 @@ impl Typed for Terminator {
             Terminator::CatchSwitch(t) => types.type_of(t),
             #[cfg(feature = "llvm-9-or-greater")]
             Terminator::CallBr(t) => types.type_of(t),
+            Terminator::Other(t) => types.type_of(t),
         }
     }
 }
@@ -70,6 +78,90 @@ impl HasDebugLoc for Terminator {
             Terminator::CatchSwitch(t) => t.get_debug_loc(),
             #[cfg(feature = "llvm-9-or-greater")]
             Terminator::CallBr(t) => t.get_debug_loc(),
+            Terminator::Other(t) => t.get_debug_loc(),
+        }
+    }
+}
+
+#[cfg(feature = "llvm-9-or-greater")]
+impl HasDebugLocMut for Terminator {
+    fn get_debug_loc_mut(&mut self) -> &mut Option<DebugLoc> {
+        match self {
+            Terminator::Ret(t) => t.get_debug_loc_mut(),
+            Terminator::Br(t) => t.get_debug_loc_mut(),
+            Terminator::CondBr(t) => t.get_debug_loc_mut(),
+            Terminator::Switch(t) => t.get_debug_loc_mut(),
+            Terminator::IndirectBr(t) => t.get_debug_loc_mut(),
+            Terminator::Invoke(t) => t.get_debug_loc_mut(),
+            Terminator::Resume(t) => t.get_debug_loc_mut(),
+            Terminator::Unreachable(t) => t.get_debug_loc_mut(),
+            Terminator::CleanupRet(t) => t.get_debug_loc_mut(),
+            Terminator::CatchRet(t) => t.get_debug_loc_mut(),
+            Terminator::CatchSwitch(t) => t.get_debug_loc_mut(),
+            #[cfg(feature = "llvm-9-or-greater")]
+            Terminator::CallBr(t) => t.get_debug_loc_mut(),
+            Terminator::Other(t) => t.get_debug_loc_mut(),
+        }
+    }
+}
+
+impl MapLocalNames for Terminator {
+    fn map_local_names(&mut self, f: &mut dyn FnMut(&mut Name)) {
+        match self {
+            Terminator::Ret(t) => t.map_local_names(f),
+            Terminator::Br(t) => t.map_local_names(f),
+            Terminator::CondBr(t) => t.map_local_names(f),
+            Terminator::Switch(t) => t.map_local_names(f),
+            Terminator::IndirectBr(t) => t.map_local_names(f),
+            Terminator::Invoke(t) => t.map_local_names(f),
+            Terminator::Resume(t) => t.map_local_names(f),
+            Terminator::Unreachable(t) => t.map_local_names(f),
+            Terminator::CleanupRet(t) => t.map_local_names(f),
+            Terminator::CatchRet(t) => t.map_local_names(f),
+            Terminator::CatchSwitch(t) => t.map_local_names(f),
+            #[cfg(feature = "llvm-9-or-greater")]
+            Terminator::CallBr(t) => t.map_local_names(f),
+            Terminator::Other(t) => t.map_local_names(f),
+        }
+    }
+}
+
+impl HasOperands for Terminator {
+    fn push_operands<'a>(&'a self, out: &mut Vec<&'a Operand>) {
+        match self {
+            Terminator::Ret(t) => t.push_operands(out),
+            Terminator::Br(t) => t.push_operands(out),
+            Terminator::CondBr(t) => t.push_operands(out),
+            Terminator::Switch(t) => t.push_operands(out),
+            Terminator::IndirectBr(t) => t.push_operands(out),
+            Terminator::Invoke(t) => t.push_operands(out),
+            Terminator::Resume(t) => t.push_operands(out),
+            Terminator::Unreachable(t) => t.push_operands(out),
+            Terminator::CleanupRet(t) => t.push_operands(out),
+            Terminator::CatchRet(t) => t.push_operands(out),
+            Terminator::CatchSwitch(t) => t.push_operands(out),
+            #[cfg(feature = "llvm-9-or-greater")]
+            Terminator::CallBr(t) => t.push_operands(out),
+            Terminator::Other(t) => t.push_operands(out),
+        }
+    }
+
+    fn push_operands_mut<'a>(&'a mut self, out: &mut Vec<&'a mut OperandRef>) {
+        match self {
+            Terminator::Ret(t) => t.push_operands_mut(out),
+            Terminator::Br(t) => t.push_operands_mut(out),
+            Terminator::CondBr(t) => t.push_operands_mut(out),
+            Terminator::Switch(t) => t.push_operands_mut(out),
+            Terminator::IndirectBr(t) => t.push_operands_mut(out),
+            Terminator::Invoke(t) => t.push_operands_mut(out),
+            Terminator::Resume(t) => t.push_operands_mut(out),
+            Terminator::Unreachable(t) => t.push_operands_mut(out),
+            Terminator::CleanupRet(t) => t.push_operands_mut(out),
+            Terminator::CatchRet(t) => t.push_operands_mut(out),
+            Terminator::CatchSwitch(t) => t.push_operands_mut(out),
+            #[cfg(feature = "llvm-9-or-greater")]
+            Terminator::CallBr(t) => t.push_operands_mut(out),
+            Terminator::Other(t) => t.push_operands_mut(out),
         }
     }
 }
@@ -90,6 +182,132 @@ impl Display for Terminator {
             Terminator::CatchSwitch(t) => write!(f, "{}", t),
             #[cfg(feature = "llvm-9-or-greater")]
             Terminator::CallBr(t) => write!(f, "{}", t),
+            Terminator::Other(t) => write!(f, "{}", t),
+        }
+    }
+}
+
+impl Terminator {
+    /// Get the result (destination) of the `Terminator`, or `None` if the
+    /// `Terminator` doesn't have a result (has void type).
+    pub fn try_get_result(&self) -> Option<&Name> {
+        match self {
+            Terminator::Invoke(t) => Some(&t.result),
+            Terminator::CatchSwitch(t) => Some(&t.result),
+            #[cfg(feature = "llvm-9-or-greater")]
+            Terminator::CallBr(t) => Some(&t.result),
+            Terminator::Other(t) => t.dest.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Get the name defined by the `Terminator`, if any. This is the same as
+    /// `try_get_result()`.
+    pub fn defined_name(&self) -> Option<&Name> {
+        self.try_get_result()
+    }
+
+    /// Iterate over the `Operand`s used by the `Terminator`, in the order they
+    /// appear in the `Terminator`.
+    pub fn operands(&self) -> impl Iterator<Item = &Operand> {
+        let mut operands = vec![];
+        self.push_operands(&mut operands);
+        operands.into_iter()
+    }
+
+    /// Like `operands()`, but allows modifying the `Operand`s in place.
+    ///
+    /// An `Operand` which is shared with other `OperandRef`s is copied before
+    /// it is handed out, so modifying it only affects this `Terminator`.
+    pub fn operands_mut(&mut self) -> impl Iterator<Item = &mut Operand> {
+        self.operand_refs_mut().map(OperandRef::make_mut)
+    }
+
+    /// Like `operands_mut()`, but gives the `OperandRef`s themselves, so that
+    /// they can be replaced without copying the `Operand`s they refer to.
+    pub(crate) fn operand_refs_mut(&mut self) -> impl Iterator<Item = &mut OperandRef> {
+        let mut operands = vec![];
+        self.push_operands_mut(&mut operands);
+        operands.into_iter()
+    }
+
+    /// Iterate over the names of the blocks which this `Terminator` may
+    /// transfer control to, in the order they appear in the `Terminator`.
+    /// There may be duplicates (e.g., several `Switch` cases with the same
+    /// destination).
+    ///
+    /// Unwinding to the caller (from a `Resume`, or a `CleanupRet` or
+    /// `CatchSwitch` without an unwind destination) isn't a successor; see
+    /// `is_function_exit()`. For a `CallBr`, these are its `return_label`
+    /// followed by its `other_labels`. For an [`Other`](struct.Other.html)
+    /// terminator, these are its `successors`.
+    pub fn successors(&self) -> impl Iterator<Item = &Name> {
+        let successors: Vec<&Name> = match self {
+            Terminator::Ret(_) | Terminator::Resume(_) | Terminator::Unreachable(_) => vec![],
+            Terminator::Br(br) => vec![&br.dest],
+            Terminator::CondBr(condbr) => vec![&condbr.true_dest, &condbr.false_dest],
+            Terminator::Switch(switch) => switch
+                .dests
+                .iter()
+                .map(|(_, dest)| dest)
+                .chain(std::iter::once(&switch.default_dest))
+                .collect(),
+            Terminator::IndirectBr(ibr) => ibr.possible_dests.iter().collect(),
+            Terminator::Invoke(invoke) => vec![&invoke.return_label, &invoke.exception_label],
+            Terminator::CleanupRet(cleanupret) => cleanupret.unwind_dest.iter().collect(),
+            Terminator::CatchRet(catchret) => vec![&catchret.successor],
+            Terminator::CatchSwitch(catchswitch) => catchswitch
+                .catch_handlers
+                .iter()
+                .chain(catchswitch.default_unwind_dest.iter())
+                .collect(),
+            #[cfg(feature = "llvm-9-or-greater")]
+            Terminator::CallBr(callbr) => std::iter::once(&callbr.return_label)
+                .chain(callbr.other_labels.iter())
+                .collect(),
+            Terminator::Other(other) => other.successors.iter().collect(),
+        };
+        successors.into_iter()
+    }
+
+    /// Does this `Terminator` (possibly) leave the function, by returning
+    /// from it or by unwinding to its caller?
+    ///
+    /// This is `true` for `Ret` and `Resume`, and for `CleanupRet` and
+    /// `CatchSwitch` when they have no unwind destination in the function.
+    /// It's `false` for `Unreachable`, which never leaves the function, and
+    /// for `Invoke`, whose exceptional successor is in the function.
+    pub fn is_function_exit(&self) -> bool {
+        match self {
+            Terminator::Ret(_) | Terminator::Resume(_) => true,
+            Terminator::CleanupRet(cleanupret) => cleanupret.unwind_dest.is_none(),
+            Terminator::CatchSwitch(catchswitch) => catchswitch.default_unwind_dest.is_none(),
+            _ => false,
+        }
+    }
+
+    /// Get the branch weights recorded in the `Terminator`'s `!prof`
+    /// metadata, for `CondBr` and `Switch`. Returns `None` for other
+    /// terminators, or if there is no such metadata.
+    ///
+    /// See the `branch_weights` fields of [`CondBr`](struct.CondBr.html) and
+    /// [`Switch`](struct.Switch.html) for the order of the weights.
+    pub fn branch_weights(&self) -> Option<Vec<u64>> {
+        match self {
+            Terminator::CondBr(condbr) => condbr.branch_weights.clone(),
+            Terminator::Switch(switch) => switch.branch_weights.clone(),
+            _ => None,
+        }
+    }
+
+    /// Get the `Terminator`'s decoded `!llvm.loop` metadata, for `Br` and
+    /// `CondBr`. Returns `None` for other terminators, or if there is no such
+    /// metadata.
+    pub fn loop_metadata(&self) -> Option<LoopMetadata> {
+        match self {
+            Terminator::Br(br) => br.loop_metadata.clone(),
+            Terminator::CondBr(condbr) => condbr.loop_metadata.clone(),
+            _ => None,
         }
     }
 }
@@ -117,7 +335,7 @@ impl Terminator {
 */
 
 macro_rules! impl_term {
-    ($term:ty, $id:ident) => {
+    ($term:ty, $id:ident, $as:ident) => {
         impl From<$term> for Terminator {
             fn from(term: $term) -> Terminator {
                 Terminator::$id(term)
@@ -134,6 +352,26 @@ macro_rules! impl_term {
             }
         }
 
+        impl<'a> TryFrom<&'a Terminator> for &'a $term {
+            type Error = &'static str;
+            fn try_from(term: &'a Terminator) -> Result<Self, Self::Error> {
+                match term {
+                    Terminator::$id(term) => Ok(term),
+                    _ => Err("Terminator is not of requested type"),
+                }
+            }
+        }
+
+        impl Terminator {
+            #[doc = concat!("If this is a `", stringify!($id), "`, get a reference to it")]
+            pub fn $as(&self) -> Option<&$term> {
+                match self {
+                    Terminator::$id(term) => Some(term),
+                    _ => None,
+                }
+            }
+        }
+
         #[cfg(feature = "llvm-9-or-greater")]
         impl HasDebugLoc for $term {
             fn get_debug_loc(&self) -> &Option<DebugLoc> {
@@ -141,6 +379,13 @@ macro_rules! impl_term {
             }
         }
 
+        #[cfg(feature = "llvm-9-or-greater")]
+        impl HasDebugLocMut for $term {
+            fn get_debug_loc_mut(&mut self) -> &mut Option<DebugLoc> {
+                &mut self.debugloc
+            }
+        }
+
         /* --TODO not yet implemented: metadata
         impl HasMetadata for $term {
             fn get_metadata(&self) -> &InstructionMetadata {
@@ -151,6 +396,29 @@ macro_rules! impl_term {
     };
 }
 
+macro_rules! impl_field_visitors {
+    ($term:ty $(, $field:ident)*) => {
+        impl MapLocalNames for $term {
+            #[allow(unused_variables)]
+            fn map_local_names(&mut self, f: &mut dyn FnMut(&mut Name)) {
+                $(self.$field.map_local_names(f);)*
+            }
+        }
+
+        impl HasOperands for $term {
+            #[allow(unused_variables)]
+            fn push_operands<'a>(&'a self, out: &mut Vec<&'a Operand>) {
+                $(self.$field.push_operands(out);)*
+            }
+
+            #[allow(unused_variables)]
+            fn push_operands_mut<'a>(&'a mut self, out: &mut Vec<&'a mut OperandRef>) {
+                $(self.$field.push_operands_mut(out);)*
+            }
+        }
+    };
+}
+
 macro_rules! impl_hasresult {
     ($term:ty) => {
         impl HasResult for $term {
@@ -172,16 +440,18 @@ macro_rules! void_typed {
 }
 
 /// See [LLVM 14 docs on the 'ret' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#ret-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Ret {
     /// The value being returned, or `None` if returning void.
-    pub return_operand: Option<Operand>,
+    pub return_operand: Option<OperandRef>,
     #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_term!(Ret, Ret);
+impl_term!(Ret, Ret, as_ret);
+impl_field_visitors!(Ret, return_operand);
 void_typed!(Ret); // technically the instruction has void type, even though the function may not
 
 impl Display for Ret {
@@ -205,16 +475,21 @@ impl Display for Ret {
 /// See [LLVM 14 docs on the 'br' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#br-instruction).
 /// The LLVM 'br' instruction has both conditional and unconditional variants, which we separate -- this is
 /// the unconditional variant, while the conditional variant is [`CondBr`](struct.CondBr.html).
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Br {
     /// The [`Name`](../enum.Name.html) of the [`BasicBlock`](../struct.BasicBlock.html) destination.
     pub dest: Name,
+    /// The branch's `!llvm.loop` metadata, if it is a loop's back edge and
+    /// has any
+    pub loop_metadata: Option<LoopMetadata>,
     #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_term!(Br, Br);
+impl_term!(Br, Br, as_br);
+impl_field_visitors!(Br, dest);
 void_typed!(Br);
 
 impl Display for Br {
@@ -231,20 +506,28 @@ impl Display for Br {
 /// See [LLVM 14 docs on the 'br' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#br-instruction).
 /// The LLVM 'br' instruction has both conditional and unconditional variants, which we separate -- this is
 /// the conditional variant, while the unconditional variant is [`Br`](struct.Br.html).
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CondBr {
     /// The branch condition.
-    pub condition: Operand,
+    pub condition: OperandRef,
     /// The [`Name`](../enum.Name.html) of the [`BasicBlock`](../struct.BasicBlock.html) destination if the `condition` is true.
     pub true_dest: Name,
     /// The [`Name`](../enum.Name.html) of the [`BasicBlock`](../struct.BasicBlock.html) destination if the `condition` is false.
     pub false_dest: Name,
+    /// Branch weights from the branch's `!prof` metadata, if any: the weights
+    /// of `true_dest` and `false_dest`, in that order.
+    pub branch_weights: Option<Vec<u64>>,
+    /// The branch's `!llvm.loop` metadata, if it is a loop's back edge and
+    /// has any
+    pub loop_metadata: Option<LoopMetadata>,
     #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_term!(CondBr, CondBr);
+impl_term!(CondBr, CondBr, as_condbr);
+impl_field_visitors!(CondBr, condition, true_dest, false_dest);
 void_typed!(CondBr);
 
 impl Display for CondBr {
@@ -263,19 +546,41 @@ impl Display for CondBr {
 }
 
 /// See [LLVM 14 docs on the 'switch' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#switch-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Switch {
-    pub operand: Operand,
+    pub operand: OperandRef,
+    /// Each case value is a `Constant::Int`, or a `Constant::BigInt` if the
+    /// operand is wider than 64 bits
     pub dests: Vec<(ConstantRef, Name)>,
     pub default_dest: Name,
+    /// Branch weights from the switch's `!prof` metadata, if any: the weight
+    /// of `default_dest`, followed by the weight of each of the `dests` in
+    /// order.
+    pub branch_weights: Option<Vec<u64>>,
     #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_term!(Switch, Switch);
+impl_term!(Switch, Switch, as_switch);
+impl_field_visitors!(Switch, operand, dests, default_dest);
 void_typed!(Switch);
 
+impl Switch {
+    /// Get the block which the `Switch` transfers control to when its operand
+    /// has the given value: the destination of the matching case, or else
+    /// the `default_dest`.
+    ///
+    /// `value` must have the same bitwidth as the `Switch`'s operand.
+    pub fn case_for_value(&self, value: &BigIntValue) -> &Name {
+        self.dests
+            .iter()
+            .find(|(case, _)| case.int_value().as_ref() == Some(value))
+            .map_or(&self.default_dest, |(_, dest)| dest)
+    }
+}
+
 impl Display for Switch {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -296,10 +601,11 @@ impl Display for Switch {
 }
 
 /// See [LLVM 14 docs on the 'indirectbr' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#indirectbr-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IndirectBr {
     /// Address to jump to (must be derived from a [`Constant::BlockAddress`](../enum.Constant.html))
-    pub operand: Operand,
+    pub operand: OperandRef,
     /// The "full set of possible destinations" which the `IndirectBr` could jump to.
     /// These are [`Name`](../enum.Name.html)s of
     /// [`BasicBlock`](../struct.BasicBlock.html)s in the current function;
@@ -310,22 +616,16 @@ pub struct IndirectBr {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_term!(IndirectBr, IndirectBr);
+impl_term!(IndirectBr, IndirectBr, as_indirectbr);
+impl_field_visitors!(IndirectBr, operand, possible_dests);
 void_typed!(IndirectBr);
 
 impl Display for IndirectBr {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "indirectbr {}, [ label {}",
-            &self.operand,
-            &self
-                .possible_dests
-                .get(0)
-                .expect("IndirectBr with no possible dests"),
-        )?;
-        for dest in &self.possible_dests[1 ..] {
-            write!(f, ", label {}", dest)?;
+        write!(f, "indirectbr {}, [", &self.operand)?;
+        for (i, dest) in self.possible_dests.iter().enumerate() {
+            let sep = if i == 0 { " " } else { ", " };
+            write!(f, "{}label {}", sep, dest)?;
         }
         write!(f, " ]")?;
         #[cfg(feature = "llvm-9-or-greater")]
@@ -337,30 +637,44 @@ impl Display for IndirectBr {
 }
 
 /// See [LLVM 14 docs on the 'invoke' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#invoke-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Invoke {
-    pub function: Either<InlineAssembly, Operand>,
-    pub arguments: Vec<(Operand, Vec<ParameterAttribute>)>,
+    pub function: Either<InlineAssembly, OperandRef>,
+    /// The type of the called function: a `FuncType`. With opaque pointers,
+    /// this can't be inferred from the type of `function`.
+    pub function_ty: TypeRef,
+    pub arguments: Vec<(OperandRef, Vec<ParameterAttribute>)>,
     pub return_attributes: Vec<ParameterAttribute>,
     pub result: Name, // The name of the variable that will get the result of the call (if the callee returns with 'ret')
     pub return_label: Name, // Should be the name of a basic block. If the callee returns normally (i.e., with 'ret'), control flow resumes here.
     pub exception_label: Name, // Should be the name of a basic block. If the callee returns with 'resume' or another exception-handling mechanism, control flow resumes here.
     pub function_attributes: Vec<FunctionAttribute>, // llvm-hs has the equivalent of Vec<Either<GroupID, FunctionAttribute>>, but I'm not sure how the GroupID option comes up
     pub calling_convention: CallingConvention,
+    pub operand_bundles: Vec<OperandBundle>,
     #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_term!(Invoke, Invoke);
+impl_term!(Invoke, Invoke, as_invoke);
+impl_field_visitors!(
+    Invoke,
+    function,
+    arguments,
+    result,
+    return_label,
+    exception_label,
+    operand_bundles
+);
 impl_hasresult!(Invoke);
 
 impl Typed for Invoke {
-    fn get_type(&self, types: &Types) -> TypeRef {
-        match types.type_of(&self.function).as_ref() {
+    fn get_type(&self, _types: &Types) -> TypeRef {
+        match self.function_ty.as_ref() {
             Type::FuncType { result_type, .. } => result_type.clone(),
             ty => panic!(
-                "Expected the function argument of an Invoke to have type FuncType; got {:?}",
+                "Expected the function_ty of an Invoke to be a FuncType, got {:?}",
                 ty
             ),
         }
@@ -400,16 +714,98 @@ impl Display for Invoke {
     }
 }
 
+impl Invoke {
+    /// Get the attributes of the argument with the given index at this call
+    /// site (e.g., `zeroext` or `byval(%struct.T)`), or an empty slice if
+    /// there is no such argument.
+    ///
+    /// These are the call site's own attributes, which may differ from those
+    /// of the called function's parameters.
+    pub fn argument_attributes(&self, index: usize) -> &[ParameterAttribute] {
+        self.arguments
+            .get(index)
+            .map_or(&[], |(_, attrs)| attrs.as_slice())
+    }
+
+    /// Iterate over the arguments of this call site, each with its
+    /// attributes
+    pub fn args_and_attrs(&self) -> impl Iterator<Item = (&Operand, &[ParameterAttribute])> {
+        self.arguments
+            .iter()
+            .map(|(arg, attrs)| (arg.as_ref(), attrs.as_slice()))
+    }
+
+    /// Iterate over the arguments of this call site, each with the type of
+    /// the corresponding parameter in `function_ty`, or `None` for arguments
+    /// passed to the variadic part of a varargs function
+    pub fn args_and_param_types(&self) -> impl Iterator<Item = (&Operand, Option<&TypeRef>)> {
+        let param_types = self.param_types();
+        self.arguments
+            .iter()
+            .enumerate()
+            .map(move |(i, (arg, _))| (arg.as_ref(), param_types.get(i)))
+    }
+
+    /// Get the arguments passed to the variadic part of a varargs function,
+    /// i.e., those after the fixed parameters of `function_ty`. This is empty
+    /// for calls to functions which aren't varargs.
+    pub fn variadic_arguments(&self) -> &[(OperandRef, Vec<ParameterAttribute>)] {
+        let num_fixed = self.param_types().len();
+        self.arguments.get(num_fixed ..).unwrap_or(&[])
+    }
+
+    fn param_types(&self) -> &[TypeRef] {
+        match self.function_ty.as_ref() {
+            Type::FuncType { param_types, .. } => param_types,
+            ty => panic!(
+                "Expected the function_ty of an Invoke to be a FuncType, got {:?}",
+                ty
+            ),
+        }
+    }
+
+    /// Get the type of the argument with the given index, if it is passed
+    /// `byval` at this call site, in which case the callee gets its own copy
+    /// of the pointed-to value
+    #[cfg(feature = "llvm-12-or-greater")]
+    pub fn byval_type(&self, index: usize) -> Option<&TypeRef> {
+        self.argument_attributes(index)
+            .iter()
+            .find_map(|attr| match attr {
+                ParameterAttribute::ByVal(ty) => Some(ty),
+                _ => None,
+            })
+    }
+
+    /// Get the index and the pointed-to type of the `sret` argument at this
+    /// call site, if any: the argument through which the callee returns a
+    /// struct
+    #[cfg(feature = "llvm-12-or-greater")]
+    pub fn sret_argument(&self) -> Option<(usize, &TypeRef)> {
+        self.arguments
+            .iter()
+            .enumerate()
+            .find_map(|(i, (_, attrs))| {
+                attrs.iter().find_map(|attr| match attr {
+                    ParameterAttribute::SRet(ty) => Some((i, ty)),
+                    _ => None,
+                })
+            })
+    }
+}
+
 /// See [LLVM 14 docs on the 'resume' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#resume-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Resume {
-    pub operand: Operand,
+    pub operand: OperandRef,
     #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_term!(Resume, Resume);
+impl_term!(Resume, Resume, as_resume);
+impl_field_visitors!(Resume, operand);
 void_typed!(Resume);
 
 impl Display for Resume {
@@ -424,14 +820,16 @@ impl Display for Resume {
 }
 
 /// See [LLVM 14 docs on the 'unreachable' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#unreachable-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Unreachable {
     #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_term!(Unreachable, Unreachable);
+impl_term!(Unreachable, Unreachable, as_unreachable);
+impl_field_visitors!(Unreachable);
 void_typed!(Unreachable);
 
 impl Display for Unreachable {
@@ -446,9 +844,10 @@ impl Display for Unreachable {
 }
 
 /// See [LLVM 14 docs on the 'cleanupret' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#cleanupret-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CleanupRet {
-    pub cleanup_pad: Operand,
+    pub cleanup_pad: OperandRef,
     /// `None` here indicates 'unwind to caller'
     pub unwind_dest: Option<Name>,
     #[cfg(feature = "llvm-9-or-greater")]
@@ -456,7 +855,8 @@ pub struct CleanupRet {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_term!(CleanupRet, CleanupRet);
+impl_term!(CleanupRet, CleanupRet, as_cleanupret);
+impl_field_visitors!(CleanupRet, cleanup_pad, unwind_dest);
 void_typed!(CleanupRet);
 
 impl Display for CleanupRet {
@@ -479,16 +879,18 @@ impl Display for CleanupRet {
 }
 
 /// See [LLVM 14 docs on the 'catchret' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#catchret-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CatchRet {
-    pub catch_pad: Operand,
+    pub catch_pad: OperandRef,
     pub successor: Name,
     #[cfg(feature = "llvm-9-or-greater")]
     pub debugloc: Option<DebugLoc>,
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_term!(CatchRet, CatchRet);
+impl_term!(CatchRet, CatchRet, as_catchret);
+impl_field_visitors!(CatchRet, catch_pad, successor);
 void_typed!(CatchRet);
 
 impl Display for CatchRet {
@@ -507,9 +909,10 @@ impl Display for CatchRet {
 }
 
 /// See [LLVM 14 docs on the 'catchswitch' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#catchswitch-instruction)
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CatchSwitch {
-    pub parent_pad: Operand,
+    pub parent_pad: OperandRef,
     /// Cannot be empty
     pub catch_handlers: Vec<Name>,
     /// `None` here indicates 'unwind to caller'
@@ -520,13 +923,21 @@ pub struct CatchSwitch {
     // --TODO not yet implemented-- pub metadata: InstructionMetadata,
 }
 
-impl_term!(CatchSwitch, CatchSwitch);
+impl_term!(CatchSwitch, CatchSwitch, as_catchswitch);
+impl_field_visitors!(
+    CatchSwitch,
+    parent_pad,
+    catch_handlers,
+    default_unwind_dest,
+    result
+);
 impl_hasresult!(CatchSwitch);
 
 impl Typed for CatchSwitch {
-    fn get_type(&self, _types: &Types) -> TypeRef {
-        unimplemented!("Typed for CatchSwitch")
-        // It's clear that there is a result of this instruction, but the documentation doesn't appear to clearly describe what its type is
+    fn get_type(&self, types: &Types) -> TypeRef {
+        // the result is a token which the `CatchPad`s in the handlers use to
+        // refer to the `CatchSwitch`
+        types.token_type()
     }
 }
 
@@ -539,7 +950,7 @@ impl Display for CatchSwitch {
             &self.parent_pad,
             &self
                 .catch_handlers
-                .get(0)
+                .first()
                 .expect("CatchSwitch with no handlers"),
         )?;
         for handler in &self.catch_handlers[1 ..] {
@@ -563,15 +974,18 @@ impl Display for CatchSwitch {
 
 /// See [LLVM 14 docs on the 'callbr' instruction](https://releases.llvm.org/14.0.0/docs/LangRef.html#callbr-instruction)
 #[cfg(feature = "llvm-9-or-greater")]
-#[derive(PartialEq, Clone, Debug)]
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CallBr {
-    pub function: Either<InlineAssembly, Operand>,
-    pub arguments: Vec<(Operand, Vec<ParameterAttribute>)>,
+    pub function: Either<InlineAssembly, OperandRef>,
+    /// The type of the called function: a `FuncType`. With opaque pointers,
+    /// this can't be inferred from the type of `function`.
+    pub function_ty: TypeRef,
+    pub arguments: Vec<(OperandRef, Vec<ParameterAttribute>)>,
     pub return_attributes: Vec<ParameterAttribute>,
     pub result: Name, // The name of the variable that will get the result of the call (if the callee returns with 'ret')
     pub return_label: Name, // Should be the name of a basic block. If the callee returns normally (i.e., with 'ret'), control flow resumes here.
-    /// `other_labels` should be `Vec<Name>`, but it appears there is no way to get this information with the LLVM C API (as opposed to the C++ API)
-    pub other_labels: (), //Vec<Name>, // Should be names of basic blocks. The callee may use an inline-asm 'goto' to resume control flow at one of these places.
+    pub other_labels: Vec<Name>, // Should be names of basic blocks. The callee may use an inline-asm 'goto' to resume control flow at one of these places.
     pub function_attributes: Vec<FunctionAttribute>,
     pub calling_convention: CallingConvention,
     #[cfg(feature = "llvm-9-or-greater")]
@@ -580,17 +994,26 @@ pub struct CallBr {
 }
 
 #[cfg(feature = "llvm-9-or-greater")]
-impl_term!(CallBr, CallBr);
+impl_term!(CallBr, CallBr, as_callbr);
+#[cfg(feature = "llvm-9-or-greater")]
+impl_field_visitors!(
+    CallBr,
+    function,
+    arguments,
+    result,
+    return_label,
+    other_labels
+);
 #[cfg(feature = "llvm-9-or-greater")]
 impl_hasresult!(CallBr);
 
 #[cfg(feature = "llvm-9-or-greater")]
 impl Typed for CallBr {
-    fn get_type(&self, types: &Types) -> TypeRef {
-        match types.type_of(&self.function).as_ref() {
+    fn get_type(&self, _types: &Types) -> TypeRef {
+        match self.function_ty.as_ref() {
             Type::FuncType { result_type, .. } => result_type.clone(),
             ty => panic!(
-                "Expected the function argument of a CallBr to have type FuncType; got {:?}",
+                "Expected the function_ty of a CallBr to be a FuncType, got {:?}",
                 ty
             ),
         }
@@ -619,7 +1042,77 @@ impl Display for CallBr {
                 write!(f, "{}, ", arg)?;
             }
         }
-        write!(f, ") to label {}", &self.return_label)?;
+        write!(f, ") to label {} [", &self.return_label)?;
+        for (i, label) in self.other_labels.iter().enumerate() {
+            if i == self.other_labels.len() - 1 {
+                write!(f, "label {}", label)?;
+            } else {
+                write!(f, "label {}, ", label)?;
+            }
+        }
+        write!(f, "]")?;
+        #[cfg(feature = "llvm-9-or-greater")]
+        if self.debugloc.is_some() {
+            write!(f, " (with debugloc)")?;
+        }
+        Ok(())
+    }
+}
+
+/// A terminator with an opcode `llvm-ir` doesn't otherwise handle, e.g., one
+/// added by a newer version of LLVM than `llvm-ir` knows about. Only its
+/// operands, successors, and result are recorded.
+///
+/// Parsing produces these unless
+/// [`ParseOptions::strict_opcodes`](../module/struct.ParseOptions.html#structfield.strict_opcodes)
+/// is set, in which case such terminators are an error instead.
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Other {
+    /// The terminator's opcode, as the numeric value of LLVM's `LLVMOpcode`
+    pub opcode: u32,
+    /// The operands, other than the successor blocks
+    pub operands: Vec<OperandRef>,
+    /// The names of the blocks the terminator may transfer control to
+    pub successors: Vec<Name>,
+    /// `None` if the terminator has void type
+    pub dest: Option<Name>,
+    /// The terminator's result type
+    pub ty: TypeRef,
+    #[cfg(feature = "llvm-9-or-greater")]
+    pub debugloc: Option<DebugLoc>,
+    // --TODO not yet implemented-- pub metadata: InstructionMetadata,
+}
+
+impl_term!(Other, Other, as_other);
+impl_field_visitors!(Other, operands, successors, dest);
+
+impl Typed for Other {
+    fn get_type(&self, _types: &Types) -> TypeRef {
+        self.ty.clone()
+    }
+}
+
+impl Display for Other {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(dest) = &self.dest {
+            write!(f, "{} = ", dest)?;
+        }
+        write!(f, "<opcode {}>", self.opcode)?;
+        for (i, op) in self.operands.iter().enumerate() {
+            write!(f, "{}{}", if i == 0 { " " } else { ", " }, op)?;
+        }
+        if !self.successors.is_empty() {
+            write!(f, " to [")?;
+            for (i, successor) in self.successors.iter().enumerate() {
+                if i == self.successors.len() - 1 {
+                    write!(f, "label {}", successor)?;
+                } else {
+                    write!(f, "label {}, ", successor)?;
+                }
+            }
+            write!(f, "]")?;
+        }
         #[cfg(feature = "llvm-9-or-greater")]
         if self.debugloc.is_some() {
             write!(f, " (with debugloc)")?;
@@ -632,11 +1125,13 @@ impl Display for CallBr {
 // from_llvm //
 // ********* //
 
+use crate::error::Error;
 use crate::from_llvm::*;
 use crate::function::FunctionContext;
 use crate::llvm_sys::*;
 use crate::module::ModuleContext;
 use llvm_sys::LLVMOpcode;
+use llvm_sys::LLVMTypeKind::LLVMVoidTypeKind;
 
 impl Terminator {
     #[rustfmt::skip] // so we can keep all of the match arms consistent
@@ -644,52 +1139,55 @@ impl Terminator {
         term: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
+    ) -> Result<Self, Error> {
         debug!("Processing terminator {:?}", unsafe {
             print_to_string(term)
         });
-        match unsafe { LLVMGetInstructionOpcode(term) } {
+        Ok(match unsafe { LLVMGetInstructionOpcode(term) } {
             LLVMOpcode::LLVMRet => {
-                Terminator::Ret(Ret::from_llvm_ref(term, ctx, func_ctx))
+                Terminator::Ret(Ret::from_llvm_ref(term, ctx, func_ctx)?)
             },
             LLVMOpcode::LLVMBr => match unsafe { LLVMGetNumOperands(term) } {
-                1 => Terminator::Br(Br::from_llvm_ref(term, func_ctx)),
-                3 => Terminator::CondBr(CondBr::from_llvm_ref(term, ctx, func_ctx)),
+                1 => Terminator::Br(Br::from_llvm_ref(term, ctx, func_ctx)?),
+                3 => Terminator::CondBr(CondBr::from_llvm_ref(term, ctx, func_ctx)?),
                 n => panic!("LLVMBr with {} operands, expected 1 or 3", n),
             },
             LLVMOpcode::LLVMSwitch => {
-                Terminator::Switch(Switch::from_llvm_ref(term, ctx, func_ctx))
+                Terminator::Switch(Switch::from_llvm_ref(term, ctx, func_ctx)?)
             },
             LLVMOpcode::LLVMIndirectBr => {
-                Terminator::IndirectBr(IndirectBr::from_llvm_ref(term, ctx, func_ctx))
+                Terminator::IndirectBr(IndirectBr::from_llvm_ref(term, ctx, func_ctx)?)
             },
             LLVMOpcode::LLVMInvoke => {
-                Terminator::Invoke(Invoke::from_llvm_ref(term, ctx, func_ctx))
+                Terminator::Invoke(Invoke::from_llvm_ref(term, ctx, func_ctx)?)
             },
             LLVMOpcode::LLVMResume => {
-                Terminator::Resume(Resume::from_llvm_ref(term, ctx, func_ctx))
+                Terminator::Resume(Resume::from_llvm_ref(term, ctx, func_ctx)?)
             },
             LLVMOpcode::LLVMUnreachable => {
-                Terminator::Unreachable(Unreachable::from_llvm_ref(term))
+                Terminator::Unreachable(Unreachable::from_llvm_ref(term, ctx))
             },
             LLVMOpcode::LLVMCleanupRet => {
-                Terminator::CleanupRet(CleanupRet::from_llvm_ref(term, ctx, func_ctx))
+                Terminator::CleanupRet(CleanupRet::from_llvm_ref(term, ctx, func_ctx)?)
             },
             LLVMOpcode::LLVMCatchRet => {
-                Terminator::CatchRet(CatchRet::from_llvm_ref(term, ctx, func_ctx))
+                Terminator::CatchRet(CatchRet::from_llvm_ref(term, ctx, func_ctx)?)
             },
             LLVMOpcode::LLVMCatchSwitch => {
-                Terminator::CatchSwitch(CatchSwitch::from_llvm_ref(term, ctx, func_ctx))
+                Terminator::CatchSwitch(CatchSwitch::from_llvm_ref(term, ctx, func_ctx)?)
             },
             #[cfg(feature="llvm-9-or-greater")]
             LLVMOpcode::LLVMCallBr => {
-                Terminator::CallBr(CallBr::from_llvm_ref(term, ctx, func_ctx))
+                Terminator::CallBr(CallBr::from_llvm_ref(term, ctx, func_ctx)?)
             },
-            opcode => panic!(
-                "Terminator::from_llvm_ref called with a non-terminator instruction (opcode {:?})",
-                opcode
-            ),
-        }
+            opcode if ctx.options.strict_opcodes => {
+                return Err(Error::unsupported(format!("Terminator has unexpected opcode {:?}", opcode)));
+            },
+            opcode => {
+                debug!("Terminator has unexpected opcode {:?}", opcode);
+                Terminator::Other(Other::from_llvm_ref(term, ctx, func_ctx)?)
+            },
+        })
     }
 }
 
@@ -698,38 +1196,83 @@ impl Ret {
         term: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
-        Self {
+    ) -> Result<Self, Error> {
+        Ok(Self {
             return_operand: match unsafe { LLVMGetNumOperands(term) } {
                 0 => None,
-                1 => Some(Operand::from_llvm_ref(
+                1 => Some(OperandRef::from_llvm_ref(
                     unsafe { LLVMGetOperand(term, 0) },
                     ctx,
                     func_ctx,
-                )),
+                )?),
                 n => panic!("Ret instruction with {} operands", n),
             },
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(term),
+            debugloc: DebugLoc::from_llvm_with_col(term, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(term),
-        }
+        })
     }
 }
 
 impl Br {
-    pub(crate) fn from_llvm_ref(term: LLVMValueRef, func_ctx: &mut FunctionContext) -> Self {
+    pub(crate) fn from_llvm_ref(
+        term: LLVMValueRef,
+        ctx: &mut ModuleContext,
+        func_ctx: &mut FunctionContext,
+    ) -> Result<Self, Error> {
         assert_eq!(unsafe { LLVMGetNumOperands(term) }, 1);
-        Self {
+        Ok(Self {
             dest: func_ctx
                 .bb_names
                 .get(unsafe { &op_to_bb(LLVMGetOperand(term, 0)) })
                 .expect("Failed to find destination bb in map")
                 .clone(),
+            loop_metadata: LoopMetadata::from_llvm_inst(term, ctx)?,
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(term),
+            debugloc: DebugLoc::from_llvm_with_col(term, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(term),
-        }
+        })
+    }
+}
+
+/// Decode the `!prof !{!"branch_weights", i32 <weight>, ...}` metadata
+/// attached to the given terminator, if any
+fn branch_weights_from_llvm(term: LLVMValueRef) -> Option<Vec<u64>> {
+    let prof = unsafe {
+        let context = LLVMGetTypeContext(LLVMTypeOf(term));
+        let kind = "prof";
+        LLVMGetMetadata(
+            term,
+            LLVMGetMDKindIDInContext(context, kind.as_ptr() as *const _, kind.len() as u32),
+        )
+    };
+    if prof.is_null() {
+        return None;
+    }
+    let num_operands = unsafe { LLVMGetMDNodeNumOperands(prof) } as usize;
+    let mut operands = vec![std::ptr::null_mut(); num_operands];
+    unsafe { LLVMGetMDNodeOperands(prof, operands.as_mut_ptr()) };
+    let (tag, weights) = operands.split_first()?;
+    let mut len = 0;
+    let tag = unsafe { LLVMGetMDString(*tag, &mut len) };
+    if tag.is_null() {
+        return None;
+    }
+    let tag = unsafe { std::slice::from_raw_parts(tag as *const u8, len as usize) };
+    if tag != b"branch_weights" {
+        return None;
     }
+    // LLVMGetMDNodeOperands() unwraps the constant weights
+    weights
+        .iter()
+        .map(|&weight| {
+            if unsafe { LLVMIsAConstantInt(weight) }.is_null() {
+                None
+            } else {
+                Some(unsafe { LLVMConstIntGetZExtValue(weight) } as u64)
+            }
+        })
+        .collect()
 }
 
 impl CondBr {
@@ -737,10 +1280,14 @@ impl CondBr {
         term: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
+    ) -> Result<Self, Error> {
         assert_eq!(unsafe { LLVMGetNumOperands(term) }, 3);
-        Self {
-            condition: Operand::from_llvm_ref(unsafe { LLVMGetOperand(term, 0) }, ctx, func_ctx),
+        Ok(Self {
+            condition: OperandRef::from_llvm_ref(
+                unsafe { LLVMGetOperand(term, 0) },
+                ctx,
+                func_ctx,
+            )?,
             true_dest: func_ctx
                 .bb_names
                 .get(unsafe { &op_to_bb(LLVMGetOperand(term, 2)) })
@@ -751,10 +1298,12 @@ impl CondBr {
                 .get(unsafe { &op_to_bb(LLVMGetOperand(term, 1)) })
                 .expect("Failed to find false-destination in bb map")
                 .clone(),
+            branch_weights: branch_weights_from_llvm(term),
+            loop_metadata: LoopMetadata::from_llvm_inst(term, ctx)?,
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(term),
+            debugloc: DebugLoc::from_llvm_with_col(term, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(term),
-        }
+        })
     }
 }
 
@@ -763,9 +1312,9 @@ impl Switch {
         term: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
-        Self {
-            operand: Operand::from_llvm_ref(unsafe { LLVMGetOperand(term, 0) }, ctx, func_ctx),
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            operand: OperandRef::from_llvm_ref(unsafe { LLVMGetOperand(term, 0) }, ctx, func_ctx)?,
             dests: {
                 let num_dests = unsafe { LLVMGetNumSuccessors(term) };
                 let dest_bbs = (1 ..= num_dests) // LLVMGetSuccessor(0) apparently gives the default dest
@@ -780,17 +1329,20 @@ impl Switch {
                     Constant::from_llvm_ref(unsafe { LLVMGetOperand(term, 2 * i) }, ctx)
                     // 2*i because empirically, operand 1 is the default dest, and operands 3/5/7/etc are the successor blocks
                 });
-                Iterator::zip(dest_vals, dest_bbs).collect()
+                Iterator::zip(dest_vals, dest_bbs)
+                    .map(|(val, bb)| Ok((val?, bb)))
+                    .collect::<Result<_, Error>>()?
             },
             default_dest: func_ctx
                 .bb_names
                 .get(unsafe { &LLVMGetSwitchDefaultDest(term) })
                 .expect("Failed to find switch default destination in map")
                 .clone(),
+            branch_weights: branch_weights_from_llvm(term),
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(term),
+            debugloc: DebugLoc::from_llvm_with_col(term, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(term),
-        }
+        })
     }
 }
 
@@ -799,9 +1351,9 @@ impl IndirectBr {
         term: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
-        Self {
-            operand: Operand::from_llvm_ref(unsafe { LLVMGetOperand(term, 0) }, ctx, func_ctx),
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            operand: OperandRef::from_llvm_ref(unsafe { LLVMGetOperand(term, 0) }, ctx, func_ctx)?,
             possible_dests: {
                 let num_dests = unsafe { LLVMGetNumSuccessors(term) };
                 (0 .. num_dests)
@@ -815,9 +1367,9 @@ impl IndirectBr {
                     .collect()
             },
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(term),
+            debugloc: DebugLoc::from_llvm_with_col(term, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(term),
-        }
+        })
     }
 }
 
@@ -826,14 +1378,15 @@ impl Invoke {
         term: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
+    ) -> Result<Self, Error> {
         use crate::instruction::CallInfo;
-        let callinfo = CallInfo::from_llvm_ref(term, ctx, func_ctx);
-        Self {
+        let callinfo = CallInfo::from_llvm_ref(term, ctx, func_ctx)?;
+        Ok(Self {
             function: callinfo.function,
+            function_ty: callinfo.function_ty,
             arguments: callinfo.arguments,
             return_attributes: callinfo.return_attributes,
-            result: Name::name_or_num(unsafe { get_value_name(term) }, &mut func_ctx.ctr),
+            result: Name::name_or_num(unsafe { get_value_name_bytes(term) }, &mut func_ctx.ctr),
             return_label: func_ctx
                 .bb_names
                 .get(unsafe { &LLVMGetNormalDest(term) })
@@ -846,10 +1399,11 @@ impl Invoke {
                 .clone(),
             function_attributes: callinfo.function_attributes,
             calling_convention: callinfo.calling_convention,
+            operand_bundles: OperandBundle::all_from_llvm_ref(term, 3, ctx, func_ctx)?, // the normal dest, unwind dest, and called function are the last three operands
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(term),
+            debugloc: DebugLoc::from_llvm_with_col(term, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(term),
-        }
+        })
     }
 }
 
@@ -858,23 +1412,25 @@ impl Resume {
         term: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
+    ) -> Result<Self, Error> {
         assert_eq!(unsafe { LLVMGetNumOperands(term) }, 1);
-        Self {
-            operand: Operand::from_llvm_ref(unsafe { LLVMGetOperand(term, 0) }, ctx, func_ctx),
+        Ok(Self {
+            operand: OperandRef::from_llvm_ref(unsafe { LLVMGetOperand(term, 0) }, ctx, func_ctx)?,
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(term),
+            debugloc: DebugLoc::from_llvm_with_col(term, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(term),
-        }
+        })
     }
 }
 
 impl Unreachable {
-    pub(crate) fn from_llvm_ref(term: LLVMValueRef) -> Self {
+    // `ctx` is only needed for the `debugloc`
+    #[cfg_attr(not(feature = "llvm-9-or-greater"), allow(unused_variables))]
+    pub(crate) fn from_llvm_ref(term: LLVMValueRef, ctx: &ModuleContext) -> Self {
         assert_eq!(unsafe { LLVMGetNumOperands(term) }, 0);
         Self {
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(term),
+            debugloc: DebugLoc::from_llvm_with_col(term, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(term),
         }
     }
@@ -885,10 +1441,15 @@ impl CleanupRet {
         term: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
-        assert_eq!(unsafe { LLVMGetNumOperands(term) }, 1);
-        Self {
-            cleanup_pad: Operand::from_llvm_ref(unsafe { LLVMGetOperand(term, 0) }, ctx, func_ctx),
+    ) -> Result<Self, Error> {
+        // the second operand, if present, is the unwind destination
+        assert!(unsafe { LLVMGetNumOperands(term) } <= 2);
+        Ok(Self {
+            cleanup_pad: OperandRef::from_llvm_ref(
+                unsafe { LLVMGetOperand(term, 0) },
+                ctx,
+                func_ctx,
+            )?,
             unwind_dest: {
                 let dest = unsafe { LLVMGetUnwindDest(term) };
                 if dest.is_null() {
@@ -910,9 +1471,9 @@ impl CleanupRet {
                 }
             },
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(term),
+            debugloc: DebugLoc::from_llvm_with_col(term, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(term),
-        }
+        })
     }
 }
 
@@ -921,18 +1482,22 @@ impl CatchRet {
         term: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
-        Self {
-            catch_pad: Operand::from_llvm_ref(unsafe { LLVMGetOperand(term, 0) }, ctx, func_ctx),
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            catch_pad: OperandRef::from_llvm_ref(
+                unsafe { LLVMGetOperand(term, 0) },
+                ctx,
+                func_ctx,
+            )?,
             successor: func_ctx
                 .bb_names
                 .get(unsafe { &LLVMGetSuccessor(term, 0) })
                 .expect("Failed to find CatchRet successor in map")
                 .clone(),
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(term),
+            debugloc: DebugLoc::from_llvm_with_col(term, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(term),
-        }
+        })
     }
 }
 
@@ -941,9 +1506,13 @@ impl CatchSwitch {
         term: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
-        Self {
-            parent_pad: Operand::from_llvm_ref(unsafe { LLVMGetOperand(term, 0) }, ctx, func_ctx),
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            parent_pad: OperandRef::from_llvm_ref(
+                unsafe { LLVMGetOperand(term, 0) },
+                ctx,
+                func_ctx,
+            )?,
             catch_handlers: {
                 let num_handlers = unsafe { LLVMGetNumHandlers(term) };
                 let mut handlers: Vec<LLVMBasicBlockRef> =
@@ -974,11 +1543,11 @@ impl CatchSwitch {
                     )
                 }
             },
-            result: Name::name_or_num(unsafe { get_value_name(term) }, &mut func_ctx.ctr),
+            result: Name::name_or_num(unsafe { get_value_name_bytes(term) }, &mut func_ctx.ctr),
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(term),
+            debugloc: DebugLoc::from_llvm_with_col(term, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(term),
-        }
+        })
     }
 }
 
@@ -988,25 +1557,82 @@ impl CallBr {
         term: LLVMValueRef,
         ctx: &mut ModuleContext,
         func_ctx: &mut FunctionContext,
-    ) -> Self {
+    ) -> Result<Self, Error> {
         use crate::instruction::CallInfo;
-        let callinfo = CallInfo::from_llvm_ref(term, ctx, func_ctx);
-        Self {
+        let callinfo = CallInfo::from_llvm_ref(term, ctx, func_ctx)?;
+        Ok(Self {
             function: callinfo.function,
+            function_ty: callinfo.function_ty,
             arguments: callinfo.arguments,
             return_attributes: callinfo.return_attributes,
-            result: Name::name_or_num(unsafe { get_value_name(term) }, &mut func_ctx.ctr),
+            result: Name::name_or_num(unsafe { get_value_name_bytes(term) }, &mut func_ctx.ctr),
             return_label: func_ctx
                 .bb_names
                 .get(unsafe { &LLVMGetNormalDest(term) })
                 .expect("Failed to find invoke return destination in map")
                 .clone(),
-            other_labels: (),
+            other_labels: {
+                // successor 0 is the normal destination, `return_label`
+                let num_successors = unsafe { LLVMGetNumSuccessors(term) };
+                (1 .. num_successors)
+                    .map(|i| {
+                        func_ctx
+                            .bb_names
+                            .get(unsafe { &LLVMGetSuccessor(term, i) })
+                            .expect("Failed to find callbr other label in map")
+                            .clone()
+                    })
+                    .collect()
+            },
             function_attributes: callinfo.function_attributes,
             calling_convention: callinfo.calling_convention,
             #[cfg(feature = "llvm-9-or-greater")]
-            debugloc: DebugLoc::from_llvm_with_col(term),
+            debugloc: DebugLoc::from_llvm_with_col(term, ctx),
             // metadata: InstructionMetadata::from_llvm_inst(term),
-        }
+        })
+    }
+}
+
+impl Other {
+    pub(crate) fn from_llvm_ref(
+        term: LLVMValueRef,
+        ctx: &mut ModuleContext,
+        func_ctx: &mut FunctionContext,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            opcode: unsafe { LLVMGetInstructionOpcode(term) } as u32,
+            operands: {
+                let num_operands = unsafe { LLVMGetNumOperands(term) } as u32;
+                (0 .. num_operands)
+                    .map(|i| unsafe { LLVMGetOperand(term, i) })
+                    .filter(|&op| unsafe { LLVMValueIsBasicBlock(op) } == 0)
+                    .map(|op| OperandRef::from_llvm_ref(op, ctx, func_ctx))
+                    .collect::<Result<_, _>>()?
+            },
+            successors: {
+                let num_successors = unsafe { LLVMGetNumSuccessors(term) };
+                (0 .. num_successors)
+                    .map(|i| {
+                        func_ctx
+                            .bb_names
+                            .get(unsafe { &LLVMGetSuccessor(term, i) })
+                            .expect("Failed to find terminator successor in map")
+                            .clone()
+                    })
+                    .collect()
+            },
+            dest: if unsafe { LLVMGetTypeKind(LLVMTypeOf(term)) == LLVMVoidTypeKind } {
+                None
+            } else {
+                Some(Name::name_or_num(
+                    unsafe { get_value_name_bytes(term) },
+                    &mut func_ctx.ctr,
+                ))
+            },
+            ty: ctx.types.type_from_llvm_ref(unsafe { LLVMTypeOf(term) }),
+            #[cfg(feature = "llvm-9-or-greater")]
+            debugloc: DebugLoc::from_llvm_with_col(term, ctx),
+            // metadata: InstructionMetadata::from_llvm_inst(term),
+        })
     }
 }
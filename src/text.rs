@@ -0,0 +1,346 @@
+//! Writing `Module`s and `Function`s as text, in a syntax close to LLVM's
+//! `.ll` format.
+//!
+//! Besides a plain dump of a `Module` in its own order, this supports a
+//! canonical mode whose output depends only on the contents of the `Module`,
+//! not on its order or on how it was produced, so that it can be used for
+//! golden tests (e.g., with FileCheck or `insta`) of analysis or transform
+//! output. See [`TextOptions::canonical()`](struct.TextOptions.html#method.canonical).
+//!
+//! Instructions and terminators are written with their `Display` impls, so
+//! the text has the same limitations: e.g., attributes, calling conventions,
+//! and metadata aren't included. So, the text is not meant to be parsed back
+//! by LLVM.
+
+use crate::function::Function;
+use crate::module::{
+    DLLStorageClass,
+    GlobalAlias,
+    GlobalVariable,
+    Linkage,
+    Module,
+    ThreadLocalMode,
+    UnnamedAddr,
+    Visibility,
+};
+use crate::name::Name;
+use crate::types::{NamedStructDef, Type};
+use std::fmt::{self, Display, Write};
+
+/// Options for writing a `Module` or `Function` as text.
+/// See [`Module::to_text_with_options()`](../module/struct.Module.html#method.to_text_with_options).
+///
+/// The default options write everything as is, in the `Module`'s order.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct TextOptions {
+    /// Write global variables, aliases, and functions sorted by name, rather
+    /// than in the order they appear in the `Module`. (Named struct types are
+    /// always sorted by name.)
+    pub sort_globals: bool,
+    /// Renumber the unnamed locals of each function as in
+    /// [`Function::rename_locals()`](../function/struct.Function.html#method.rename_locals)
+    /// before writing it
+    pub renumber_locals: bool,
+    /// Remove debug information from each function as in
+    /// [`Function::strip_debug_info()`](../function/struct.Function.html#method.strip_debug_info)
+    /// before writing it, including calls to debug intrinsics
+    pub strip_debug_info: bool,
+    /// Write the `; ModuleID = '...'` comment with the `Module`'s name, which
+    /// is usually the path it was read from
+    pub module_id: bool,
+}
+
+impl TextOptions {
+    /// Options for a stable, deterministic output: globals and functions
+    /// sorted by name, unnamed locals renumbered, no debug information, and
+    /// no `ModuleID` line. Two `Module`s which differ only in those respects
+    /// are written identically.
+    pub fn canonical() -> Self {
+        Self {
+            sort_globals: true,
+            renumber_locals: true,
+            strip_debug_info: true,
+            module_id: false,
+        }
+    }
+}
+
+/// Write the given `Module` as text, with the given options
+pub(crate) fn write_module(module: &Module, options: &TextOptions) -> String {
+    let mut text = String::new();
+    if options.module_id {
+        writeln!(text, "; ModuleID = '{}'", module.name).unwrap();
+    }
+    writeln!(
+        text,
+        "source_filename = {}",
+        Quoted(module.source_file_name.as_bytes())
+    )
+    .unwrap();
+    if !module.data_layout.layout_str.is_empty() {
+        writeln!(
+            text,
+            "target datalayout = {}",
+            Quoted(module.data_layout.layout_str.as_bytes())
+        )
+        .unwrap();
+    }
+    if let Some(triple) = &module.target_triple {
+        writeln!(text, "target triple = {}", Quoted(triple.as_bytes())).unwrap();
+    }
+
+    if !module.inline_assembly.is_empty() {
+        text.push('\n');
+        for asm in &module.inline_assembly {
+            writeln!(text, "module asm {}", Quoted(asm.as_bytes())).unwrap();
+        }
+    }
+
+    let mut structs = module.types.all_named_structs().peekable();
+    if structs.peek().is_some() {
+        text.push('\n');
+        for (name, def) in structs {
+            match def {
+                NamedStructDef::Opaque => writeln!(text, "%{} = type opaque", name).unwrap(),
+                NamedStructDef::Defined(ty) => writeln!(text, "%{} = type {}", name, ty).unwrap(),
+            }
+        }
+    }
+
+    let mut global_vars: Vec<&GlobalVariable> = module.global_vars.iter().collect();
+    let mut global_aliases: Vec<&GlobalAlias> = module.global_aliases.iter().collect();
+    let mut functions: Vec<&Function> = module.functions.iter().collect();
+    if options.sort_globals {
+        global_vars.sort_by(|a, b| a.name.cmp(&b.name));
+        global_aliases.sort_by(|a, b| a.name.cmp(&b.name));
+        functions.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+    if !global_vars.is_empty() {
+        text.push('\n');
+        for var in global_vars {
+            write_global_var(&mut text, var);
+        }
+    }
+    if !global_aliases.is_empty() {
+        text.push('\n');
+        for alias in global_aliases {
+            write_global_alias(&mut text, alias);
+        }
+    }
+    for func in functions {
+        text.push('\n');
+        text.push_str(&write_function(func, options));
+    }
+    text
+}
+
+/// Write the given `Function` as text, with the given options. Only
+/// `renumber_locals` and `strip_debug_info` apply to a single `Function`.
+pub(crate) fn write_function(func: &Function, options: &TextOptions) -> String {
+    if options.renumber_locals || options.strip_debug_info {
+        let mut func = func.clone();
+        if options.strip_debug_info {
+            func.strip_debug_info();
+        }
+        if options.renumber_locals {
+            func.rename_locals(false);
+        }
+        return write_function(&func, &TextOptions::default());
+    }
+
+    let mut text = String::new();
+    write!(text, "define").unwrap();
+    write_linkage_and_visibility(
+        &mut text,
+        func.linkage,
+        func.visibility,
+        func.dll_storage_class,
+    );
+    write!(text, " {} ", func.return_type).unwrap();
+    write_global_name(&mut text, &Name::from(func.name.as_str()));
+    text.push('(');
+    for (i, param) in func.parameters.iter().enumerate() {
+        if i > 0 {
+            text.push_str(", ");
+        }
+        write!(text, "{} {}", param.ty, param.name).unwrap();
+    }
+    if func.is_var_arg {
+        if !func.parameters.is_empty() {
+            text.push_str(", ");
+        }
+        text.push_str("...");
+    }
+    text.push(')');
+    if func.addr_space != 0 {
+        write!(text, " addrspace({})", func.addr_space).unwrap();
+    }
+    if let Some(section) = &func.section {
+        write!(text, " section {}", Quoted(section.as_bytes())).unwrap();
+    }
+    if let Some(alignment) = func.alignment {
+        write!(text, " align {}", alignment).unwrap();
+    }
+    if let Some(gc) = &func.garbage_collector_name {
+        write!(text, " gc {}", Quoted(gc.as_bytes())).unwrap();
+    }
+    text.push_str(" {\n");
+    for (i, bb) in func.basic_blocks.iter().enumerate() {
+        if i > 0 {
+            text.push('\n');
+        }
+        // the label is the block's name without the `%` sigil
+        let label = bb.name.to_string();
+        writeln!(text, "{}:", &label[1 ..]).unwrap();
+        for inst in &bb.instrs {
+            writeln!(text, "  {}", inst).unwrap();
+        }
+        writeln!(text, "  {}", bb.term).unwrap();
+    }
+    text.push_str("}\n");
+    text
+}
+
+fn write_global_var(text: &mut String, var: &GlobalVariable) {
+    write_global_name(text, &var.name);
+    text.push_str(" =");
+    if var.initializer.is_none() && var.linkage == Linkage::External {
+        text.push_str(" external");
+    }
+    write_linkage_and_visibility(text, var.linkage, var.visibility, var.dll_storage_class);
+    write_thread_local_mode(text, var.thread_local_mode);
+    write_unnamed_addr(text, var.unnamed_addr);
+    if var.addr_space != 0 {
+        write!(text, " addrspace({})", var.addr_space).unwrap();
+    }
+    text.push_str(if var.is_constant {
+        " constant"
+    } else {
+        " global"
+    });
+    match &var.initializer {
+        Some(init) => write!(text, " {}", init).unwrap(),
+        None => match var.ty.as_ref() {
+            Type::PointerType { pointee_type, .. } => write!(text, " {}", pointee_type).unwrap(),
+            ty => write!(text, " {}", ty).unwrap(),
+        },
+    }
+    if let Some(section) = &var.section {
+        write!(text, ", section {}", Quoted(section.as_bytes())).unwrap();
+    }
+    if let Some(comdat) = &var.comdat {
+        write!(text, ", comdat(${})", comdat.name).unwrap();
+    }
+    if let Some(alignment) = var.alignment {
+        write!(text, ", align {}", alignment).unwrap();
+    }
+    text.push('\n');
+}
+
+fn write_global_alias(text: &mut String, alias: &GlobalAlias) {
+    write_global_name(text, &alias.name);
+    text.push_str(" =");
+    write_linkage_and_visibility(
+        text,
+        alias.linkage,
+        alias.visibility,
+        alias.dll_storage_class,
+    );
+    write_thread_local_mode(text, alias.thread_local_mode);
+    write_unnamed_addr(text, alias.unnamed_addr);
+    match alias.ty.as_ref() {
+        Type::PointerType { pointee_type, .. } => {
+            writeln!(text, " alias {}, {}", pointee_type, alias.aliasee).unwrap()
+        },
+        _ => writeln!(text, " alias {}", alias.aliasee).unwrap(),
+    }
+}
+
+fn write_global_name(text: &mut String, name: &Name) {
+    struct GlobalName<'a>(&'a Name);
+
+    impl Display for GlobalName<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            self.0.fmt_with_sigil(f, '@')
+        }
+    }
+
+    write!(text, "{}", GlobalName(name)).unwrap();
+}
+
+fn write_linkage_and_visibility(
+    text: &mut String,
+    linkage: Linkage,
+    visibility: Visibility,
+    dll_storage_class: DLLStorageClass,
+) {
+    let linkage = match linkage {
+        Linkage::External => None,
+        Linkage::Private => Some("private"),
+        Linkage::Internal => Some("internal"),
+        Linkage::ExternalWeak => Some("extern_weak"),
+        Linkage::AvailableExternally => Some("available_externally"),
+        Linkage::LinkOnceAny => Some("linkonce"),
+        Linkage::LinkOnceODR => Some("linkonce_odr"),
+        Linkage::LinkOnceODRAutoHide => Some("linkonce_odr_autohide"),
+        Linkage::WeakAny => Some("weak"),
+        Linkage::WeakODR => Some("weak_odr"),
+        Linkage::Common => Some("common"),
+        Linkage::Appending => Some("appending"),
+        Linkage::DLLImport => Some("dllimport"),
+        Linkage::DLLExport => Some("dllexport"),
+        Linkage::Ghost => Some("ghost"),
+        Linkage::LinkerPrivate => Some("linker_private"),
+        Linkage::LinkerPrivateWeak => Some("linker_private_weak"),
+    };
+    if let Some(linkage) = linkage {
+        write!(text, " {}", linkage).unwrap();
+    }
+    match visibility {
+        Visibility::Default => {},
+        Visibility::Hidden => text.push_str(" hidden"),
+        Visibility::Protected => text.push_str(" protected"),
+    }
+    match dll_storage_class {
+        DLLStorageClass::Default => {},
+        DLLStorageClass::Import => text.push_str(" dllimport"),
+        DLLStorageClass::Export => text.push_str(" dllexport"),
+    }
+}
+
+fn write_thread_local_mode(text: &mut String, mode: ThreadLocalMode) {
+    match mode {
+        ThreadLocalMode::NotThreadLocal => {},
+        ThreadLocalMode::GeneralDynamic => text.push_str(" thread_local"),
+        ThreadLocalMode::LocalDynamic => text.push_str(" thread_local(localdynamic)"),
+        ThreadLocalMode::InitialExec => text.push_str(" thread_local(initialexec)"),
+        ThreadLocalMode::LocalExec => text.push_str(" thread_local(localexec)"),
+    }
+}
+
+fn write_unnamed_addr(text: &mut String, unnamed_addr: Option<UnnamedAddr>) {
+    match unnamed_addr {
+        None => {},
+        Some(UnnamedAddr::Local) => text.push_str(" local_unnamed_addr"),
+        Some(UnnamedAddr::Global) => text.push_str(" unnamed_addr"),
+    }
+}
+
+/// Displays the given bytes as a quoted string, with special bytes escaped as
+/// `\XX`, as in LLVM
+struct Quoted<'a>(&'a [u8]);
+
+impl Display for Quoted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\"")?;
+        for &b in self.0 {
+            let printable = b.is_ascii_graphic() || b == b' ';
+            if printable && b != b'\\' && b != b'"' {
+                write!(f, "{}", b as char)?;
+            } else {
+                write!(f, "\\{:02X}", b)?;
+            }
+        }
+        write!(f, "\"")
+    }
+}
@@ -0,0 +1,468 @@
+//! Simple transformations of `Function`s, in place.
+
+use crate::constant::Constant;
+#[cfg(feature = "llvm-9-or-greater")]
+use crate::debugloc::HasDebugLoc;
+use crate::function::{CallingConvention, Function};
+use crate::instruction::{Call, GetElementPtr, Instruction};
+use crate::metadata::Metadata;
+use crate::name::Name;
+use crate::operand::{Operand, OperandRef};
+use crate::types::{Type, Types};
+use crate::ConstantRef;
+use either::Either;
+use std::collections::{HashMap, HashSet};
+
+/// Simplify the instructions of the given `Function` in place, putting it
+/// into a more normalized form. `types` must be the `Types` of the `Module`
+/// containing the `Function`.
+///
+/// This performs the following rewrites, until none of them apply:
+///
+///   - integer binary operations whose operands are both constants are folded
+///     to a constant, unless the result would be undefined or poison (e.g.,
+///     division by zero, or a shift by at least the bit width);
+///   - `bitcast`s and `addrspacecast`s to the type of their operand are
+///     removed;
+///   - `getelementptr`s whose indices are all zero, and which have the type
+///     of their address, are removed;
+///   - a `getelementptr` whose address is the result of another
+///     `getelementptr`, and whose first index is zero, is collapsed into a
+///     single `getelementptr` from the other one's address.
+///
+/// When an instruction is removed, all of its uses are replaced with the
+/// simplified value.
+///
+/// Returns `true` if the `Function` was changed.
+pub fn simplify(func: &mut Function, types: &Types) -> bool {
+    let mut changed = false;
+    loop {
+        let geps: HashMap<Name, GetElementPtr> = func
+            .basic_blocks
+            .iter()
+            .flat_map(|bb| &bb.instrs)
+            .filter_map(|inst| match inst {
+                Instruction::GetElementPtr(gep) => Some((gep.dest.clone(), gep.clone())),
+                _ => None,
+            })
+            .collect();
+        let mut replacements = HashMap::new();
+        let mut rewritten = false;
+        for bb in &mut func.basic_blocks {
+            bb.instrs.retain_mut(|inst| {
+                for op in inst.operand_refs_mut() {
+                    replace_uses(op, &replacements);
+                }
+                if let Instruction::GetElementPtr(gep) = inst {
+                    if collapse_geps(gep, &geps, types) {
+                        rewritten = true;
+                    }
+                }
+                match simplified_value(inst, types) {
+                    Some(value) => {
+                        let dest = inst.try_get_result().expect("should have a result");
+                        replacements.insert(dest.clone(), value);
+                        false
+                    },
+                    None => true,
+                }
+            });
+        }
+        if replacements.is_empty() && !rewritten {
+            return changed;
+        }
+        changed = true;
+        for bb in &mut func.basic_blocks {
+            for inst in &mut bb.instrs {
+                for op in inst.operand_refs_mut() {
+                    replace_uses(op, &replacements);
+                }
+            }
+            for op in bb.term.operand_refs_mut() {
+                replace_uses(op, &replacements);
+            }
+        }
+    }
+}
+
+/// If the given `Operand` is (or, for a metadata operand, wraps) a local
+/// which has been replaced, replace it
+pub(crate) fn replace_uses(op: &mut OperandRef, replacements: &HashMap<Name, OperandRef>) {
+    if replacements.is_empty() {
+        return;
+    }
+    match op.as_ref() {
+        Operand::LocalOperand { name, .. } => {
+            if let Some(replacement) = final_replacement(name, replacements) {
+                *op = replacement.clone();
+            }
+        },
+        Operand::MetadataOperand(md) => {
+            if let Metadata::Value(Operand::LocalOperand { name, .. }) = md.as_ref() {
+                if let Some(replacement) = final_replacement(name, replacements) {
+                    let replacement = replacement.as_ref().clone();
+                    if let Operand::MetadataOperand(md) = op.make_mut() {
+                        **md = Metadata::Value(replacement);
+                    }
+                }
+            }
+        },
+        Operand::ConstantOperand(_) => {},
+    }
+}
+
+/// The value to replace uses of the local `name` with, if it has been
+/// replaced
+fn final_replacement<'r>(
+    name: &Name,
+    replacements: &'r HashMap<Name, OperandRef>,
+) -> Option<&'r OperandRef> {
+    let mut replacement = replacements.get(name)?;
+    // a replacement may itself have been replaced. This terminates even for
+    // (unreachable) self-referential code, since there are only so many
+    // replacements.
+    for _ in 1 .. replacements.len() {
+        match replacement.as_ref() {
+            Operand::LocalOperand { name, .. } => match replacements.get(name) {
+                Some(next) => replacement = next,
+                None => break,
+            },
+            _ => break,
+        }
+    }
+    Some(replacement)
+}
+
+/// If the given `Instruction` can be removed, the value to replace its uses
+/// with
+fn simplified_value(inst: &Instruction, types: &Types) -> Option<OperandRef> {
+    match inst {
+        Instruction::BitCast(bc) if types.type_of(&bc.operand) == bc.to_type => {
+            Some(bc.operand.clone())
+        },
+        Instruction::AddrSpaceCast(asc) if types.type_of(&asc.operand) == asc.to_type => {
+            Some(asc.operand.clone())
+        },
+        Instruction::GetElementPtr(gep)
+            if gep.indices.iter().all(|index| is_zero(index))
+                && types.type_of(gep) == types.type_of(&gep.address) =>
+        {
+            Some(gep.address.clone())
+        },
+        _ => {
+            let (bits, a, b) = int_operands(inst)?;
+            let value = fold_int_binop(inst, bits, a, b)?;
+            Some(OperandRef::new(Operand::ConstantOperand(ConstantRef::new(
+                Constant::Int { bits, value },
+            ))))
+        },
+    }
+}
+
+/// If `gep`'s address is the result of another `getelementptr` and `gep`'s
+/// first index is zero, rewrite `gep` to index directly from the other's
+/// address. Returns `true` if `gep` was rewritten.
+fn collapse_geps(
+    gep: &mut GetElementPtr,
+    geps: &HashMap<Name, GetElementPtr>,
+    types: &Types,
+) -> bool {
+    let inner = match gep.address.as_ref() {
+        Operand::LocalOperand { name, ty } if matches!(ty.as_ref(), Type::PointerType { .. }) => {
+            match geps.get(name) {
+                Some(inner) => inner,
+                None => return false,
+            }
+        },
+        _ => return false,
+    };
+    // `inner` must be a scalar (not vector) GEP which actually indexes
+    if inner.indices.is_empty()
+        || !matches!(
+            types.type_of(&inner.address).as_ref(),
+            Type::PointerType { .. }
+        )
+    {
+        return false;
+    }
+    match gep.indices.first() {
+        Some(first) if is_zero(first) => {},
+        _ => return false,
+    }
+    // in (unreachable) self-referential code, collapsing could go on forever
+    let mut address: &Operand = &inner.address;
+    for steps in 0 .. {
+        match address {
+            Operand::LocalOperand { name, .. } => match geps.get(name) {
+                _ if name == &gep.dest || steps > geps.len() => return false,
+                Some(gep) => address = &gep.address,
+                None => break,
+            },
+            _ => break,
+        }
+    }
+    gep.address = inner.address.clone();
+    gep.source_element_type = inner.source_element_type.clone();
+    gep.indices = inner
+        .indices
+        .iter()
+        .chain(gep.indices.iter().skip(1))
+        .cloned()
+        .collect();
+    gep.in_bounds &= inner.in_bounds;
+    true
+}
+
+fn is_zero(op: &Operand) -> bool {
+    matches!(op.as_constant(), Some(Constant::Int { value: 0, .. }))
+}
+
+/// If the `Instruction` is an integer binary operation whose operands are both
+/// constants, the bit width and the values of the operands
+fn int_operands(inst: &Instruction) -> Option<(u32, u64, u64)> {
+    let (op0, op1) = match inst {
+        Instruction::Add(i) => (&i.operand0, &i.operand1),
+        Instruction::Sub(i) => (&i.operand0, &i.operand1),
+        Instruction::Mul(i) => (&i.operand0, &i.operand1),
+        Instruction::UDiv(i) => (&i.operand0, &i.operand1),
+        Instruction::SDiv(i) => (&i.operand0, &i.operand1),
+        Instruction::URem(i) => (&i.operand0, &i.operand1),
+        Instruction::SRem(i) => (&i.operand0, &i.operand1),
+        Instruction::And(i) => (&i.operand0, &i.operand1),
+        Instruction::Or(i) => (&i.operand0, &i.operand1),
+        Instruction::Xor(i) => (&i.operand0, &i.operand1),
+        Instruction::Shl(i) => (&i.operand0, &i.operand1),
+        Instruction::LShr(i) => (&i.operand0, &i.operand1),
+        Instruction::AShr(i) => (&i.operand0, &i.operand1),
+        _ => return None,
+    };
+    match (op0.as_constant()?, op1.as_constant()?) {
+        (
+            Constant::Int { bits, value: a },
+            Constant::Int {
+                bits: b_bits,
+                value: b,
+            },
+        ) if bits == b_bits => Some((*bits, *a, *b)),
+        _ => None,
+    }
+}
+
+/// Fold the integer binary operation `inst` on the `bits`-bit values `a` and
+/// `b`, or return `None` if the result would be undefined or poison
+fn fold_int_binop(inst: &Instruction, bits: u32, a: u64, b: u64) -> Option<u64> {
+    let mask = if bits >= 64 {
+        u64::MAX
+    } else {
+        (1 << bits) - 1
+    };
+    let signed = |x: u64| -> i64 {
+        let shift = 64 - bits.min(64);
+        ((x << shift) as i64) >> shift
+    };
+    let (sa, sb) = (signed(a), signed(b));
+    let min_signed = signed(1 << (bits.min(64) - 1));
+    let value = match inst {
+        Instruction::Add(_) => a.wrapping_add(b),
+        Instruction::Sub(_) => a.wrapping_sub(b),
+        Instruction::Mul(_) => a.wrapping_mul(b),
+        Instruction::UDiv(_) => a.checked_div(b)?,
+        Instruction::URem(_) => a.checked_rem(b)?,
+        Instruction::SDiv(_) | Instruction::SRem(_)
+            if sb == 0 || (sa == min_signed && sb == -1) =>
+        {
+            return None
+        },
+        Instruction::SDiv(_) => (sa / sb) as u64,
+        Instruction::SRem(_) => (sa % sb) as u64,
+        Instruction::And(_) => a & b,
+        Instruction::Or(_) => a | b,
+        Instruction::Xor(_) => a ^ b,
+        Instruction::Shl(_) | Instruction::LShr(_) | Instruction::AShr(_)
+            if b >= u64::from(bits) =>
+        {
+            return None
+        },
+        Instruction::Shl(_) => a << b,
+        Instruction::LShr(_) => a >> b,
+        Instruction::AShr(_) => (sa >> b) as u64,
+        _ => return None,
+    };
+    Some(value & mask)
+}
+
+/// Remove the instructions of the given `Function` whose results are unused
+/// and which have no side effects, until none remain. Removing an instruction
+/// may make the instructions computing its operands dead in turn.
+///
+/// Instructions which may have side effects (see
+/// [`Instruction::may_have_side_effects()`](../instruction/enum.Instruction.html#method.may_have_side_effects)),
+/// such as stores and calls to debug intrinsics, are never removed. Uses of a
+/// removed value as metadata (e.g., in a `llvm.dbg.value`) are replaced with
+/// `undef`.
+///
+/// Returns `true` if the `Function` was changed.
+pub fn dce(func: &mut Function) -> bool {
+    let mut changed = false;
+    loop {
+        let mut uses: HashMap<Name, usize> = HashMap::new();
+        for bb in &func.basic_blocks {
+            let operands = bb.instrs.iter().flat_map(Instruction::operands);
+            for op in operands.chain(bb.term.operands()) {
+                if let Operand::LocalOperand { name, .. } = op {
+                    *uses.entry(name.clone()).or_default() += 1;
+                }
+            }
+        }
+        let mut removed = HashSet::new();
+        for bb in &mut func.basic_blocks {
+            bb.instrs.retain(|inst| {
+                let unused = match inst.try_get_result() {
+                    Some(dest) => !uses.contains_key(dest),
+                    None => true,
+                };
+                if unused && !inst.may_have_side_effects() {
+                    if let Some(dest) = inst.try_get_result() {
+                        removed.insert(dest.clone());
+                    }
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+        if removed.is_empty() {
+            return changed;
+        }
+        changed = true;
+        for bb in &mut func.basic_blocks {
+            for op in bb.instrs.iter_mut().flat_map(Instruction::operand_refs_mut) {
+                if let Operand::MetadataOperand(md) = op.as_ref() {
+                    if let Metadata::Value(Operand::LocalOperand { name, ty }) = md.as_ref() {
+                        if removed.contains(name) {
+                            let undef = ConstantRef::new(Constant::Undef(ty.clone()));
+                            *op = OperandRef::new(Operand::MetadataOperand(Box::new(
+                                Metadata::Value(Operand::ConstantOperand(undef)),
+                            )));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Where [`instrument()`](fn.instrument.html) inserts each call, relative to
+/// the instruction it instruments
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum InsertPosition {
+    Before,
+    After,
+}
+
+/// Insert a call to `callee` before or after every instruction of the given
+/// `Function` for which `matches` returns `true`. `types` must be the `Types`
+/// of the `Module` containing the `Function`, and `callee` must be a pointer
+/// to a function, such as a `GlobalReference` to a function added with
+/// [`Module::add_function()`](../module/struct.Module.html#method.add_function).
+///
+/// The arguments of each call are given by `arguments`, which is called with
+/// the instrumented instruction. The calls have no attributes, and get the
+/// `DebugLoc` of the instrumented instruction (on LLVM 9+). Instructions
+/// which are part of the `Phi`s (and exception-handling pad) at the start of a
+/// block can't have calls inserted between them, so calls for those are
+/// inserted after the last of them, regardless of `position`. Calls inserted
+/// by this function are never themselves instrumented.
+///
+/// If `callee` returns a value, each call's result gets an unnamed local, and
+/// the unnamed locals of the `Function` are renumbered as in
+/// [`Function::rename_locals()`](../function/struct.Function.html#method.rename_locals),
+/// so that they stay numbered sequentially.
+///
+/// Returns the number of calls inserted.
+pub fn instrument(
+    func: &mut Function,
+    types: &Types,
+    mut matches: impl FnMut(&Instruction) -> bool,
+    callee: &Operand,
+    position: InsertPosition,
+    mut arguments: impl FnMut(&Instruction) -> Vec<Operand>,
+) -> usize {
+    // with opaque pointers, the function type is only known for references to
+    // functions, not from the type of the callee
+    let function_ty = match callee.as_constant() {
+        Some(Constant::GlobalReference { ty, .. }) => ty.clone(),
+        _ => types.type_of(callee).pointee_type().unwrap_or_else(|| {
+            panic!(
+                "instrument: expected callee to be a function or pointer-to-function, got {:?}",
+                callee
+            )
+        }),
+    };
+    let returns_value = match function_ty.as_ref() {
+        Type::FuncType { result_type, .. } => *result_type != types.void(),
+        ty => panic!("instrument: expected callee to be a function, got {:?}", ty),
+    };
+    let callee = OperandRef::new(callee.clone());
+    let mut inserted = 0;
+    let mut make_call = |inst: &Instruction| {
+        inserted += 1;
+        Instruction::Call(Call {
+            function: Either::Right(callee.clone()),
+            function_ty: function_ty.clone(),
+            arguments: arguments(inst)
+                .into_iter()
+                .map(|arg| (arg.into(), vec![]))
+                .collect(),
+            return_attributes: vec![],
+            // a placeholder which can't clash with existing names, until the
+            // locals are renumbered
+            dest: if returns_value {
+                Some(Name::Number(usize::MAX - inserted))
+            } else {
+                None
+            },
+            function_attributes: vec![],
+            is_tail_call: false,
+            calling_convention: CallingConvention::C,
+            operand_bundles: vec![],
+            #[cfg(feature = "llvm-9-or-greater")]
+            debugloc: inst.get_debug_loc().clone(),
+        })
+    };
+    for bb in &mut func.basic_blocks {
+        let num_phis = bb
+            .instrs
+            .iter()
+            .take_while(|inst| matches!(inst, Instruction::Phi(_)))
+            .count();
+        let prefix_len = match bb.instrs.get(num_phis) {
+            Some(Instruction::LandingPad(_))
+            | Some(Instruction::CatchPad(_))
+            | Some(Instruction::CleanupPad(_)) => num_phis + 1,
+            _ => num_phis,
+        };
+        let mut deferred = vec![];
+        for (i, inst) in std::mem::take(&mut bb.instrs).into_iter().enumerate() {
+            if !matches(&inst) {
+                bb.instrs.push(inst);
+            } else if i < prefix_len {
+                deferred.push(make_call(&inst));
+                bb.instrs.push(inst);
+            } else {
+                let call = make_call(&inst);
+                match position {
+                    InsertPosition::Before => bb.instrs.extend([call, inst]),
+                    InsertPosition::After => bb.instrs.extend([inst, call]),
+                }
+            }
+            if i + 1 == prefix_len {
+                bb.instrs.append(&mut deferred);
+            }
+        }
+    }
+    if returns_value && inserted > 0 {
+        func.rename_locals(false);
+    }
+    inserted
+}
@@ -1,14 +1,18 @@
-use crate::module::AddrSpace;
+use crate::location::{InstrLoc, InstrPos};
+use crate::module::{AddrSpace, Alignment, DataLayout, Module};
 use either::Either;
 use std::borrow::Borrow;
-use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::{self, Display};
-use std::hash::Hash;
+use std::hash::{Hash, Hasher};
+use std::mem::size_of;
 use std::ops::Deref;
-use std::sync::Arc;
+use std::sync::{Arc, PoisonError, RwLock};
 
 /// See [LLVM 14 docs on Type System](https://releases.llvm.org/14.0.0/docs/LangRef.html#type-system)
 #[derive(PartialEq, Eq, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(non_camel_case_types)]
 pub enum Type {
     /// See [LLVM 14 docs on Void Type](https://releases.llvm.org/14.0.0/docs/LangRef.html#void-type)
@@ -20,6 +24,11 @@ pub enum Type {
         pointee_type: TypeRef,
         addr_space: AddrSpace,
     },
+    /// An opaque pointer (`ptr`), which has no pointee type. LLVM 14 only
+    /// produces these when opaque pointers are enabled (e.g., with
+    /// `-opaque-pointers`); later LLVM versions produce them by default.
+    /// See [LLVM 14 docs on Pointer Type](https://releases.llvm.org/14.0.0/docs/LangRef.html#pointer-type)
+    OpaquePointerType { addr_space: AddrSpace },
     /// See [LLVM 14 docs on Floating-Point Types](https://releases.llvm.org/14.0.0/docs/LangRef.html#floating-point-types)
     FPType(FPType),
     /// See [LLVM 14 docs on Function Type](https://releases.llvm.org/14.0.0/docs/LangRef.html#function-type)
@@ -72,12 +81,56 @@ pub enum Type {
     TokenType,
 }
 
+impl Type {
+    /// Get the address space of this type, if it is a pointer type
+    pub fn addr_space(&self) -> Option<AddrSpace> {
+        match self {
+            Type::PointerType { addr_space, .. } => Some(*addr_space),
+            Type::OpaquePointerType { addr_space } => Some(*addr_space),
+            _ => None,
+        }
+    }
+
+    /// Get the pointee type of this type, if it is a (typed) pointer type.
+    /// Opaque pointer types have no pointee type.
+    pub fn pointee_type(&self) -> Option<TypeRef> {
+        match self {
+            Type::PointerType { pointee_type, .. } => Some(pointee_type.clone()),
+            _ => None,
+        }
+    }
+
+    /// The heap memory owned directly by this `Type` (not counting the `Type`s
+    /// it refers to), in bytes
+    fn own_heap_size(&self) -> usize {
+        match self {
+            Type::FuncType { param_types, .. } => param_types.capacity() * size_of::<TypeRef>(),
+            Type::StructType { element_types, .. } => {
+                element_types.capacity() * size_of::<TypeRef>()
+            },
+            Type::NamedStructType { name } => name.capacity(),
+            _ => 0,
+        }
+    }
+}
+
 impl Display for Type {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Type::VoidType => write!(f, "void"),
             Type::IntegerType { bits } => write!(f, "i{}", bits),
-            Type::PointerType { pointee_type, .. } => write!(f, "{}*", pointee_type),
+            Type::PointerType {
+                pointee_type,
+                addr_space: 0,
+            } => write!(f, "{}*", pointee_type),
+            Type::PointerType {
+                pointee_type,
+                addr_space,
+            } => write!(f, "{} addrspace({})*", pointee_type, addr_space),
+            Type::OpaquePointerType { addr_space: 0 } => write!(f, "ptr"),
+            Type::OpaquePointerType { addr_space } => {
+                write!(f, "ptr addrspace({})", addr_space)
+            },
             Type::FPType(fpt) => write!(f, "{}", fpt),
             Type::FuncType {
                 result_type,
@@ -151,6 +204,7 @@ impl Display for Type {
 
 /// See [LLVM 14 docs on Floating-Point Types](https://releases.llvm.org/14.0.0/docs/LangRef.html#floating-point-types)
 #[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(non_camel_case_types)]
 pub enum FPType {
     Half,
@@ -223,6 +277,23 @@ impl TypeRef {
     }
 }
 
+// A `TypeRef` is serialized as the `Type` it refers to. This can't recurse
+// infinitely, because recursive types can only be formed through named
+// structs, and a `NamedStructType` refers to its definition by name.
+#[cfg(feature = "serde")]
+impl serde::Serialize for TypeRef {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_ref().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for TypeRef {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <Type as serde::Deserialize>::deserialize(deserializer).map(Self::new)
+    }
+}
+
 /// The `Typed` trait is used for anything that has a [`Type`](enum.Type.html).
 pub trait Typed {
     fn get_type(&self, types: &Types) -> TypeRef;
@@ -269,6 +340,10 @@ pub(crate) struct TypesBuilder {
     int_types: TypeCache<u32>,
     /// Map of (pointee type, address space) to the corresponding `Type::PointerType`
     pointer_types: TypeCache<(TypeRef, AddrSpace)>,
+    /// Map of address space to the corresponding `Type::OpaquePointerType`
+    opaque_pointer_types: TypeCache<AddrSpace>,
+    /// Whether any opaque pointer type has been created
+    opaque_pointers: bool,
     /// Map of `FPType` to the corresponding `Type::FPType`
     fp_types: TypeCache<FPType>,
     /// Map of `(result_type, param_types, is_var_arg)` to the corresponding `Type::FunctionType`
@@ -282,7 +357,7 @@ pub(crate) struct TypesBuilder {
     /// Map of struct name to the corresponding `Type::NamedStructType`
     named_struct_types: TypeCache<String>,
     /// Map of struct name to the corresponding `NamedStructDef`
-    named_struct_defs: HashMap<String, NamedStructDef>,
+    named_struct_defs: BTreeMap<String, NamedStructDef>,
     /// `TypeRef` to `Type::X86_MMXType`
     x86_mmx_type: TypeRef,
     /// `TypeRef` to `Type::X86_AMXType`
@@ -305,13 +380,15 @@ impl TypesBuilder {
             void_type: TypeRef::new(Type::VoidType),
             int_types: TypeCache::new(),
             pointer_types: TypeCache::new(),
+            opaque_pointer_types: TypeCache::new(),
+            opaque_pointers: false,
             fp_types: TypeCache::new(),
             func_types: TypeCache::new(),
             vec_types: TypeCache::new(),
             arr_types: TypeCache::new(),
             struct_types: TypeCache::new(),
             named_struct_types: TypeCache::new(),
-            named_struct_defs: HashMap::new(),
+            named_struct_defs: BTreeMap::new(),
             x86_mmx_type: TypeRef::new(Type::X86_MMXType),
             #[cfg(feature = "llvm-12-or-greater")]
             x86_amx_type: TypeRef::new(Type::X86_AMXType),
@@ -322,20 +399,34 @@ impl TypesBuilder {
         }
     }
 
+    /// Get a `TypesBuilder` which gives the same `TypeRef`s as this one, but
+    /// has no named struct definitions, for building the `Types` of another
+    /// `Module` in the same `Context`.
+    pub fn clone_without_defs(&self) -> Self {
+        let mut builder = self.clone();
+        builder.named_struct_defs.clear();
+        // `LLVMTypeRef`s are only meaningful within the LLVM context of the
+        // module they came from
+        builder.llvm_type_map.clear();
+        builder
+    }
+
     /// Consumes the `TypesBuilder`, producing a `Types`.
     /// This should be done when no new types are expected to be added;
     /// and it allows type lookups without &mut self.
     pub fn build(self) -> Types {
         Types {
             void_type: self.void_type,
-            int_types: self.int_types,
-            pointer_types: self.pointer_types,
-            fp_types: self.fp_types,
-            func_types: self.func_types,
-            vec_types: self.vec_types,
-            arr_types: self.arr_types,
-            struct_types: self.struct_types,
-            named_struct_types: self.named_struct_types,
+            int_types: SharedTypeCache::from(self.int_types),
+            pointer_types: SharedTypeCache::from(self.pointer_types),
+            opaque_pointer_types: SharedTypeCache::from(self.opaque_pointer_types),
+            opaque_pointers: self.opaque_pointers,
+            fp_types: SharedTypeCache::from(self.fp_types),
+            func_types: SharedTypeCache::from(self.func_types),
+            vec_types: SharedTypeCache::from(self.vec_types),
+            arr_types: SharedTypeCache::from(self.arr_types),
+            struct_types: SharedTypeCache::from(self.struct_types),
+            named_struct_types: SharedTypeCache::from(self.named_struct_types),
             named_struct_defs: self.named_struct_defs,
             x86_mmx_type: self.x86_mmx_type,
             #[cfg(feature = "llvm-12-or-greater")]
@@ -404,6 +495,29 @@ impl TypesBuilder {
             })
     }
 
+    /// Get an opaque pointer type (`ptr`) in the default address space (`0`)
+    pub fn opaque_pointer(&mut self) -> TypeRef {
+        self.opaque_pointer_in_addr_space(0)
+    }
+
+    /// Get an opaque pointer type in the specified address space
+    pub fn opaque_pointer_in_addr_space(&mut self, addr_space: AddrSpace) -> TypeRef {
+        self.opaque_pointers = true;
+        self.opaque_pointer_types
+            .lookup_or_insert(addr_space, || Type::OpaquePointerType { addr_space })
+    }
+
+    /// Get the type of a pointer to a value of type `pointee_type` in the
+    /// specified address space: an opaque pointer type if any opaque pointer
+    /// type has been created, or a `PointerType` to `pointee_type` otherwise
+    pub fn pointer_for(&mut self, pointee_type: TypeRef, addr_space: AddrSpace) -> TypeRef {
+        if self.opaque_pointers {
+            self.opaque_pointer_in_addr_space(addr_space)
+        } else {
+            self.pointer_in_addr_space(pointee_type, addr_space)
+        }
+    }
+
     /// Get a floating-point type
     pub fn fp(&mut self, fpt: FPType) -> TypeRef {
         self.fp_types.lookup_or_insert(fpt, || Type::FPType(fpt))
@@ -547,6 +661,7 @@ impl TypesBuilder {
 }
 
 #[derive(Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum NamedStructDef {
     /// An opaque struct type; see [LLVM 14 docs on Opaque Structure Types](https://releases.llvm.org/14.0.0/docs/LangRef.html#t-opaque).
     Opaque,
@@ -556,40 +671,48 @@ pub enum NamedStructDef {
 
 /// Holds a reference to all of the `Type`s used in the `Module`, and facilitates
 /// lookups so you can get a `TypeRef` to the `Type` you want.
+///
+/// `Types` can be shared between threads. Any thread may look up types
+/// (including types not used in the `Module`, which are then added) through a
+/// shared reference. The types are split across several independently-locked
+/// shards, so threads only wait on each other when adding new types to the
+/// same shard; looking up existing types never excludes other lookups.
+///
+/// The `Type`s themselves are immutable, and reading them takes no locks.
+/// Named structs, which may be recursive, refer to each other by name (see
+/// [`named_struct_def()`](#method.named_struct_def)) rather than directly, so
+/// walking even a recursive type only involves shared references.
 //
-// Unlike `TypesBuilder`, this is intended to be immutable, and performs type
-// lookups without &mut self.
+// Unlike `TypesBuilder`, this performs type lookups without &mut self.
 // It should be created from `TypesBuilder::build()`, and once it is built,
 // it should contain all types ever used in the `Module`.
-//
-// That said, if you happen to want a type which wasn't encountered when parsing
-// the `Module` (e.g., a pointer to some type in the `Module`, even if the
-// `Module` doesn't itself create pointers to that type), it will still
-// construct that `Type` and give you a `TypeRef`; you'll just be the sole owner
-// of that `Type` object.
 #[derive(Clone)]
 pub struct Types {
     /// `TypeRef` to `Type::VoidType`
     void_type: TypeRef,
     /// Map of integer size to `Type::IntegerType` of that size
-    int_types: TypeCache<u32>,
+    int_types: SharedTypeCache<u32>,
     /// Map of (pointee type, address space) to the corresponding `Type::PointerType`
-    pointer_types: TypeCache<(TypeRef, AddrSpace)>,
+    pointer_types: SharedTypeCache<(TypeRef, AddrSpace)>,
+    /// Map of address space to the corresponding `Type::OpaquePointerType`
+    opaque_pointer_types: SharedTypeCache<AddrSpace>,
+    /// Whether the `Module` uses opaque pointers rather than typed pointers
+    opaque_pointers: bool,
     /// Map of `FPType` to the corresponding `Type::FPType`
-    fp_types: TypeCache<FPType>,
+    fp_types: SharedTypeCache<FPType>,
     /// Map of `(result_type, param_types, is_var_arg)` to the corresponding `Type::FunctionType`
-    func_types: TypeCache<(TypeRef, Vec<TypeRef>, bool)>,
+    func_types: SharedTypeCache<(TypeRef, Vec<TypeRef>, bool)>,
     /// Map of (element type, #elements, scalable) to the corresponding `Type::VectorType`.
     /// For LLVM 10 and lower, `scalable` is always `false`.
-    vec_types: TypeCache<(TypeRef, usize, bool)>,
+    vec_types: SharedTypeCache<(TypeRef, usize, bool)>,
     /// Map of (element type, #elements) to the corresponding `Type::ArrayType`
-    arr_types: TypeCache<(TypeRef, usize)>,
+    arr_types: SharedTypeCache<(TypeRef, usize)>,
     /// Map of `(element_types, is_packed)` to the corresponding `Type::StructType`
-    struct_types: TypeCache<(Vec<TypeRef>, bool)>,
+    struct_types: SharedTypeCache<(Vec<TypeRef>, bool)>,
     /// Map of struct name to the corresponding `Type::NamedStructType`
-    named_struct_types: TypeCache<String>,
+    named_struct_types: SharedTypeCache<String>,
     /// Map of struct name to the corresponding `NamedStructDef`
-    named_struct_defs: HashMap<String, NamedStructDef>,
+    named_struct_defs: BTreeMap<String, NamedStructDef>,
     /// `TypeRef` to `Type::X86_MMXType`
     x86_mmx_type: TypeRef,
     /// `TypeRef` to `Type::X86_AMXType`
@@ -604,6 +727,29 @@ pub struct Types {
 }
 
 impl Types {
+    /// Estimate the heap memory used by this `Types` and the `Type`s in it, in
+    /// bytes.
+    /// See [`Module::memory_footprint()`](../module/struct.Module.html#method.memory_footprint).
+    pub(crate) fn heap_size(&self) -> usize {
+        let named_struct_defs = self.named_struct_defs.len()
+            * size_of::<(String, NamedStructDef)>()
+            + self
+                .named_struct_defs
+                .keys()
+                .map(String::capacity)
+                .sum::<usize>();
+        self.int_types.heap_size()
+            + self.pointer_types.heap_size()
+            + self.opaque_pointer_types.heap_size()
+            + self.fp_types.heap_size()
+            + self.func_types.heap_size()
+            + self.vec_types.heap_size()
+            + self.arr_types.heap_size()
+            + self.struct_types.heap_size()
+            + self.named_struct_types.heap_size()
+            + named_struct_defs
+    }
+
     /// Get the type of anything that is `Typed`
     pub fn type_of<T: Typed + ?Sized>(&self, t: &T) -> TypeRef {
         t.get_type(self)
@@ -617,8 +763,7 @@ impl Types {
     /// Get the integer type of the specified size (in bits)
     pub fn int(&self, bits: u32) -> TypeRef {
         self.int_types
-            .lookup(&bits)
-            .unwrap_or_else(|| TypeRef::new(Type::IntegerType { bits }))
+            .lookup_or_insert(bits, || Type::IntegerType { bits })
     }
 
     /// Get the boolean type (`i1`)
@@ -654,20 +799,46 @@ impl Types {
     /// Get a pointer type in the specified address space
     pub fn pointer_in_addr_space(&self, pointee_type: TypeRef, addr_space: AddrSpace) -> TypeRef {
         self.pointer_types
-            .lookup(&(pointee_type.clone(), addr_space))
-            .unwrap_or_else(|| {
-                TypeRef::new(Type::PointerType {
-                    pointee_type,
-                    addr_space,
-                })
+            .lookup_or_insert((pointee_type.clone(), addr_space), || Type::PointerType {
+                pointee_type,
+                addr_space,
             })
     }
 
+    /// Get an opaque pointer type (`ptr`) in the default address space (`0`)
+    pub fn opaque_pointer(&self) -> TypeRef {
+        self.opaque_pointer_in_addr_space(0)
+    }
+
+    /// Get an opaque pointer type in the specified address space
+    pub fn opaque_pointer_in_addr_space(&self, addr_space: AddrSpace) -> TypeRef {
+        self.opaque_pointer_types
+            .lookup_or_insert(addr_space, || Type::OpaquePointerType { addr_space })
+    }
+
+    /// Does the `Module` use opaque pointers (`ptr`) rather than typed
+    /// pointers? In LLVM 14, either all of a module's pointers are opaque or
+    /// none of them are.
+    pub fn uses_opaque_pointers(&self) -> bool {
+        self.opaque_pointers
+    }
+
+    /// Get the type of a pointer to a value of type `pointee_type` in the
+    /// specified address space: an opaque pointer type if the `Module` uses
+    /// opaque pointers (see
+    /// [`uses_opaque_pointers()`](#method.uses_opaque_pointers)), or a
+    /// `PointerType` to `pointee_type` otherwise.
+    pub fn pointer_for(&self, pointee_type: TypeRef, addr_space: AddrSpace) -> TypeRef {
+        if self.opaque_pointers {
+            self.opaque_pointer_in_addr_space(addr_space)
+        } else {
+            self.pointer_in_addr_space(pointee_type, addr_space)
+        }
+    }
+
     /// Get a floating-point type
     pub fn fp(&self, fpt: FPType) -> TypeRef {
-        self.fp_types
-            .lookup(&fpt)
-            .unwrap_or_else(|| TypeRef::new(Type::FPType(fpt)))
+        self.fp_types.lookup_or_insert(fpt, || Type::FPType(fpt))
     }
 
     /// Get the single-precision floating-point type
@@ -687,63 +858,54 @@ impl Types {
         param_types: Vec<TypeRef>,
         is_var_arg: bool,
     ) -> TypeRef {
-        self.func_types
-            .lookup(&(result_type.clone(), param_types.clone(), is_var_arg))
-            .unwrap_or_else(|| {
-                TypeRef::new(Type::FuncType {
-                    result_type,
-                    param_types,
-                    is_var_arg,
-                })
-            })
+        self.func_types.lookup_or_insert(
+            (result_type.clone(), param_types.clone(), is_var_arg),
+            || Type::FuncType {
+                result_type,
+                param_types,
+                is_var_arg,
+            },
+        )
     }
 
     /// Get a vector type
     #[cfg(feature = "llvm-11-or-greater")]
     pub fn vector_of(&self, element_type: TypeRef, num_elements: usize, scalable: bool) -> TypeRef {
         self.vec_types
-            .lookup(&(element_type.clone(), num_elements, scalable))
-            .unwrap_or_else(|| {
-                TypeRef::new(Type::VectorType {
+            .lookup_or_insert((element_type.clone(), num_elements, scalable), || {
+                Type::VectorType {
                     element_type,
                     num_elements,
                     scalable,
-                })
+                }
             })
     }
     #[cfg(feature = "llvm-10-or-lower")]
     pub fn vector_of(&self, element_type: TypeRef, num_elements: usize) -> TypeRef {
         self.vec_types
-            .lookup(&(element_type.clone(), num_elements, false))
-            .unwrap_or_else(|| {
-                TypeRef::new(Type::VectorType {
+            .lookup_or_insert((element_type.clone(), num_elements, false), || {
+                Type::VectorType {
                     element_type,
                     num_elements,
-                })
+                }
             })
     }
 
     /// Get an array type
     pub fn array_of(&self, element_type: TypeRef, num_elements: usize) -> TypeRef {
         self.arr_types
-            .lookup(&(element_type.clone(), num_elements))
-            .unwrap_or_else(|| {
-                TypeRef::new(Type::ArrayType {
-                    element_type,
-                    num_elements,
-                })
+            .lookup_or_insert((element_type.clone(), num_elements), || Type::ArrayType {
+                element_type,
+                num_elements,
             })
     }
 
     /// Get a struct type
     pub fn struct_of(&self, element_types: Vec<TypeRef>, is_packed: bool) -> TypeRef {
         self.struct_types
-            .lookup(&(element_types.clone(), is_packed))
-            .unwrap_or_else(|| {
-                TypeRef::new(Type::StructType {
-                    element_types,
-                    is_packed,
-                })
+            .lookup_or_insert((element_types.clone(), is_packed), || Type::StructType {
+                element_types,
+                is_packed,
             })
     }
 
@@ -753,9 +915,10 @@ impl Types {
     /// To get the actual _definition_ of a named struct (the `NamedStructDef`),
     /// use `named_struct_def()`.
     pub fn named_struct(&self, name: &str) -> TypeRef {
-        self.named_struct_types
-            .lookup(name)
-            .unwrap_or_else(|| TypeRef::new(Type::NamedStructType { name: name.into() }))
+        self.named_struct_types.lookup(name).unwrap_or_else(|| {
+            self.named_struct_types
+                .lookup_or_insert(name.into(), || Type::NamedStructType { name: name.into() })
+        })
     }
 
     /// Get the `NamedStructDef` for the struct with the given `name`, or
@@ -767,11 +930,16 @@ impl Types {
         self.named_struct_defs.get(name)
     }
 
-    /// Get the names of all the named structs
+    /// Get the names of all the named structs, in name order
     pub fn all_struct_names(&self) -> impl Iterator<Item = &String> {
         self.named_struct_defs.keys()
     }
 
+    /// Iterate over all the named structs and their definitions, in name order
+    pub fn all_named_structs(&self) -> impl Iterator<Item = (&String, &NamedStructDef)> {
+        self.named_struct_defs.iter()
+    }
+
     /// Add the given `NamedStructDef` as the definition of the struct with the given `name`.
     ///
     /// Panics if that name already had a definition.
@@ -829,6 +997,7 @@ impl Types {
             Type::PointerType { pointee_type, addr_space } => {
                 self.pointer_in_addr_space(pointee_type.clone(), *addr_space)
             },
+            Type::OpaquePointerType { addr_space } => self.opaque_pointer_in_addr_space(*addr_space),
             Type::FPType(fpt) => self.fp(*fpt),
             Type::FuncType { result_type, param_types, is_var_arg } => {
                 self.func_type(result_type.clone(), param_types.clone(), *is_var_arg)
@@ -867,6 +1036,346 @@ impl Types {
     }
 }
 
+impl Types {
+    /// Get the size and alignment of the given type under the given
+    /// `DataLayout`, as LLVM computes them.
+    ///
+    /// For a scalable vector, the sizes are for `vscale` = 1, and the
+    /// result's `scalable` is `true`; the actual sizes are these times
+    /// `vscale`, which is only known at runtime.
+    ///
+    /// Returns `None` for types which have no size: opaque structs, and types
+    /// which aren't first-class (e.g., `void`, functions, and labels).
+    pub fn size_and_alignment(&self, ty: &Type, layout: &DataLayout) -> Option<SizeAndAlignment> {
+        let alignments = &layout.alignments;
+        let bytes = |bits: u32| u64::from(bits / 8).max(1);
+        let aligned = |size_in_bits: u64, alignment: &Alignment| {
+            SizeAndAlignment::new(
+                size_in_bits,
+                bytes(alignment.abi),
+                bytes(alignment.pref),
+                false,
+            )
+        };
+        match ty {
+            Type::IntegerType { bits } => {
+                Some(aligned(u64::from(*bits), alignments.int_alignment(*bits)))
+            },
+            Type::FPType(fpt) => {
+                let bits = match fpt {
+                    FPType::Half => 16,
+                    #[cfg(feature = "llvm-11-or-greater")]
+                    FPType::BFloat => 16,
+                    FPType::Single => 32,
+                    FPType::Double => 64,
+                    FPType::X86_FP80 => 80,
+                    FPType::FP128 | FPType::PPC_FP128 => 128,
+                };
+                Some(aligned(bits, alignments.fp_alignment(*fpt)))
+            },
+            Type::PointerType { addr_space, .. } | Type::OpaquePointerType { addr_space } => {
+                let pointer_layout = alignments.ptr_alignment(*addr_space);
+                Some(aligned(
+                    u64::from(pointer_layout.size),
+                    &pointer_layout.alignment,
+                ))
+            },
+            Type::X86_MMXType => Some(vector_size_and_alignment(64, false, layout)),
+            Type::VectorType {
+                element_type,
+                num_elements,
+                #[cfg(feature = "llvm-11-or-greater")]
+                scalable,
+            } => {
+                #[cfg(feature = "llvm-11-or-greater")]
+                let scalable = *scalable;
+                #[cfg(feature = "llvm-10-or-lower")]
+                let scalable = false;
+                let element = self.size_and_alignment(element_type, layout)?;
+                let bits = element.size_in_bits * *num_elements as u64;
+                Some(vector_size_and_alignment(bits, scalable, layout))
+            },
+            Type::ArrayType {
+                element_type,
+                num_elements,
+            } => {
+                let element = self.size_and_alignment(element_type, layout)?;
+                if element.scalable {
+                    return None;
+                }
+                let size = element.alloc_size * *num_elements as u64;
+                Some(SizeAndAlignment::new(
+                    size * 8,
+                    element.abi_alignment,
+                    element.preferred_alignment,
+                    false,
+                ))
+            },
+            Type::StructType {
+                element_types,
+                is_packed,
+            } => {
+                let struct_layout =
+                    self.literal_struct_layout(element_types, *is_packed, layout)?;
+                // as in LLVM, the alignment of a struct is at least that of
+                // aggregates, but this doesn't change its size (only its
+                // alloc size); and a packed struct is still only ABI-aligned
+                // to 1, though it prefers the alignment of aggregates
+                let agg_alignment = alignments.agg_alignment();
+                let abi = if *is_packed {
+                    1
+                } else {
+                    bytes(agg_alignment.abi).max(struct_layout.alignment)
+                };
+                let preferred = bytes(agg_alignment.pref).max(struct_layout.alignment);
+                Some(SizeAndAlignment::new(
+                    struct_layout.size * 8,
+                    abi,
+                    preferred,
+                    false,
+                ))
+            },
+            Type::NamedStructType { name } => match self.named_struct_def(name)? {
+                NamedStructDef::Opaque => None,
+                NamedStructDef::Defined(def) => self.size_and_alignment(def, layout),
+            },
+            _ => None,
+        }
+    }
+
+    /// for internal use: the allocation size and ABI alignment of the given
+    /// type, in bytes, under the given `DataLayout`; that is, how much space
+    /// an `alloca` of the type takes up, including any tail padding.
+    ///
+    /// Returns `None` for unsized types (opaque structs, scalable vectors,
+    /// functions, etc).
+    pub(crate) fn alloc_size_and_align(
+        &self,
+        ty: &Type,
+        layout: &DataLayout,
+    ) -> Option<(u64, u64)> {
+        match self.size_and_alignment(ty, layout)? {
+            SizeAndAlignment { scalable: true, .. } => None,
+            size => Some((size.alloc_size, size.abi_alignment)),
+        }
+    }
+}
+
+/// The size and alignment of a vector type with the given total size: the
+/// data layout's alignment for vectors of exactly that size if there is one,
+/// or else the size rounded up to a power of two (in bytes)
+fn vector_size_and_alignment(bits: u64, scalable: bool, layout: &DataLayout) -> SizeAndAlignment {
+    let (abi_alignment, preferred_alignment) =
+        match layout.alignments.explicit_vec_alignment(bits as u32) {
+            Some(alignment) => (
+                u64::from(alignment.abi / 8).max(1),
+                u64::from(alignment.pref / 8).max(1),
+            ),
+            None => {
+                let natural = bits.div_ceil(8).next_power_of_two();
+                (natural, natural)
+            },
+        };
+    SizeAndAlignment::new(bits, abi_alignment, preferred_alignment, scalable)
+}
+
+impl Types {
+    /// Get the memory layout of the named struct with the given `name`, as
+    /// LLVM would lay it out under the given `DataLayout`.
+    ///
+    /// Returns `None` if there is no struct by that name, if it is opaque, or
+    /// if any of its fields are unsized.
+    pub fn struct_layout(&self, name: &str, layout: &DataLayout) -> Option<StructLayout> {
+        match self.named_struct_def(name)? {
+            NamedStructDef::Opaque => None,
+            NamedStructDef::Defined(def) => match def.as_ref() {
+                Type::StructType {
+                    element_types,
+                    is_packed,
+                } => self.literal_struct_layout(element_types, *is_packed, layout),
+                _ => None,
+            },
+        }
+    }
+
+    /// Lay out a struct with the given fields: each field is placed at the
+    /// next offset satisfying its ABI alignment (or immediately after the
+    /// previous field, if the struct is packed), and the struct is padded at
+    /// the end to a multiple of its most aligned field's alignment
+    fn literal_struct_layout(
+        &self,
+        element_types: &[TypeRef],
+        is_packed: bool,
+        layout: &DataLayout,
+    ) -> Option<StructLayout> {
+        let mut size = 0;
+        let mut alignment = 1;
+        let mut field_offsets = Vec::with_capacity(element_types.len());
+        let mut fields_size = 0;
+        for element_type in element_types {
+            let (element_size, element_align) = self.alloc_size_and_align(element_type, layout)?;
+            let element_align = if is_packed { 1 } else { element_align };
+            let offset = round_up(size, element_align);
+            field_offsets.push(offset);
+            size = offset + element_size;
+            fields_size += element_size;
+            alignment = alignment.max(element_align);
+        }
+        let size = round_up(size, alignment);
+        Some(StructLayout {
+            size,
+            alignment,
+            field_offsets,
+            padding: size - fields_size,
+        })
+    }
+}
+
+/// The memory layout of a struct type, as computed by
+/// [`Types::struct_layout()`](struct.Types.html#method.struct_layout).
+/// All sizes and offsets are in bytes.
+#[derive(PartialEq, Eq, Clone, Debug, Hash)]
+pub struct StructLayout {
+    /// Size of the struct, including any padding at the end; this is the
+    /// distance between consecutive structs in an array
+    pub size: u64,
+    /// ABI alignment of the struct, which is that of its most aligned field.
+    /// The struct type itself may be more aligned, if the `DataLayout` aligns
+    /// all aggregates more; see
+    /// [`Types::size_and_alignment()`](struct.Types.html#method.size_and_alignment).
+    pub alignment: u64,
+    /// Offset of each field from the start of the struct
+    pub field_offsets: Vec<u64>,
+    /// Total number of padding bytes, both between fields and at the end
+    pub padding: u64,
+}
+
+/// The size and alignment of a type under a `DataLayout`, as computed by
+/// [`Types::size_and_alignment()`](struct.Types.html#method.size_and_alignment).
+/// All sizes and alignments are in bytes, except for `size_in_bits`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub struct SizeAndAlignment {
+    /// The number of bits needed to hold a value of the type, e.g., 1 for
+    /// `i1` and 80 for `x86_fp80`
+    pub size_in_bits: u64,
+    /// The maximum number of bytes which a store of the type may overwrite
+    pub store_size: u64,
+    /// The number of bytes an `alloca` of the type takes up: the store size,
+    /// padded to a multiple of the ABI alignment. This is the distance between
+    /// consecutive elements of an array of the type.
+    pub alloc_size: u64,
+    /// The minimum alignment required by the ABI
+    pub abi_alignment: u64,
+    /// The alignment LLVM prefers where it is free to choose, e.g., for a
+    /// global variable without an explicit alignment
+    pub preferred_alignment: u64,
+    /// If `true`, the type is a scalable vector, and the sizes are multiples
+    /// of `vscale`
+    pub scalable: bool,
+}
+
+impl SizeAndAlignment {
+    fn new(
+        size_in_bits: u64,
+        abi_alignment: u64,
+        preferred_alignment: u64,
+        scalable: bool,
+    ) -> Self {
+        let store_size = size_in_bits.div_ceil(8);
+        Self {
+            size_in_bits,
+            store_size,
+            alloc_size: round_up(store_size, abi_alignment),
+            abi_alignment,
+            preferred_alignment: preferred_alignment.max(abi_alignment),
+            scalable,
+        }
+    }
+}
+
+/// Round `n` up to a multiple of `align`
+fn round_up(n: u64, align: u64) -> u64 {
+    n.div_ceil(align) * align
+}
+
+/// The result types of all of the instructions and terminators in a `Module`,
+/// computed once up front so that each lookup is O(1).
+///
+/// Computing the type of some instructions requires walking other types (for
+/// instance, the type of a `GetElementPtr` is found by walking its indices
+/// through the pointee type), which can be costly if done repeatedly, e.g., in
+/// an analysis which queries the same instructions many times.
+///
+/// A `ResultTypes` is a snapshot: it isn't updated if the `Module` is modified
+/// afterwards.
+#[derive(Clone, Debug)]
+pub struct ResultTypes {
+    /// Indexed by function, then block, then position within the block, with
+    /// each block's terminator last
+    types: Vec<Vec<Vec<TypeRef>>>,
+}
+
+impl ResultTypes {
+    /// Compute the result types of all of the instructions and terminators in
+    /// the given `Module`
+    pub fn new(module: &Module) -> Self {
+        let types = module
+            .functions
+            .iter()
+            .map(|func| {
+                func.basic_blocks
+                    .iter()
+                    .map(|bb| {
+                        bb.instrs
+                            .iter()
+                            .map(|inst| module.type_of(inst))
+                            .chain(std::iter::once(module.type_of(&bb.term)))
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect();
+        Self { types }
+    }
+
+    /// Get the result type of the instruction or terminator identified by the
+    /// given `InstrLoc`, or `None` if there is no such instruction.
+    ///
+    /// This is the same as `module.type_of()` on the instruction, but doesn't
+    /// recompute the type.
+    pub fn get(&self, loc: InstrLoc) -> Option<&TypeRef> {
+        let block = self.types.get(loc.block.func)?.get(loc.block.block)?;
+        match loc.pos {
+            InstrPos::Instr(index) => block[.. block.len() - 1].get(index),
+            InstrPos::Terminator => block.last(),
+        }
+    }
+}
+
+// Only the named struct definitions need to be serialized; every other type
+// can be reconstructed on demand from the `TypeRef`s that refer to it.
+// (They are kept in name order, so the serialized form is deterministic.)
+#[cfg(feature = "serde")]
+impl serde::Serialize for Types {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.named_struct_defs.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Types {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let named_struct_defs: BTreeMap<String, NamedStructDef> =
+            serde::Deserialize::deserialize(deserializer)?;
+        let mut builder = TypesBuilder::new();
+        for (name, def) in named_struct_defs {
+            builder.named_struct(name.clone());
+            builder.add_named_struct_def(name, def);
+        }
+        Ok(builder.build())
+    }
+}
+
 #[derive(Clone, Debug)]
 struct TypeCache<K: Eq + Hash + Clone> {
     map: HashMap<K, TypeRef>,
@@ -882,10 +1391,10 @@ impl<K: Eq + Hash + Clone> TypeCache<K> {
 
     /// Get a `TypeRef` to the `Type` with the given key,
     /// or `None` if the `Type` is not present.
-    fn lookup<Q: ?Sized>(&self, key: &Q) -> Option<TypeRef>
+    fn lookup<Q>(&self, key: &Q) -> Option<TypeRef>
     where
         K: Borrow<Q>,
-        Q: Hash + Eq,
+        Q: ?Sized + Hash + Eq,
     {
         self.map.get(key).cloned()
     }
@@ -906,6 +1415,119 @@ impl<K: Eq + Hash + Clone> TypeCache<K> {
     }
 }
 
+/// Number of shards in a `SharedTypeCache`
+const NUM_SHARDS: usize = 16;
+
+/// Index of the shard of a `SharedTypeCache` responsible for the given key
+fn shard_index<Q: ?Sized + Hash>(key: &Q) -> usize {
+    // `DefaultHasher::new()` always hashes the same way, unlike `RandomState`,
+    // so a key is always assigned to the same shard
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish() as usize % NUM_SHARDS
+}
+
+/// Like `TypeCache`, but new `Type`s can be added through a shared reference,
+/// from multiple threads at once.
+///
+/// The map is split into shards by key hash, each behind its own `RwLock`, so
+/// lookups only take a (shared) read lock, and adding a `Type` only blocks
+/// threads using the same shard.
+struct SharedTypeCache<K: Eq + Hash + Clone> {
+    shards: Vec<RwLock<HashMap<K, TypeRef>>>,
+}
+
+impl<K: Eq + Hash + Clone> SharedTypeCache<K> {
+    /// Get the shard responsible for the given key
+    fn shard<Q: ?Sized + Hash>(&self, key: &Q) -> &RwLock<HashMap<K, TypeRef>> {
+        &self.shards[shard_index(key)]
+    }
+
+    /// Get a `TypeRef` to the `Type` with the given key,
+    /// or `None` if the `Type` is not present.
+    fn lookup<Q>(&self, key: &Q) -> Option<TypeRef>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        // A panic while a shard was locked can't leave it inconsistent (we
+        // only ever insert complete entries), so ignore lock poisoning
+        let shard = self
+            .shard(key)
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        shard.get(key).cloned()
+    }
+
+    /// Get a `TypeRef` to the `Type` with the given key.
+    /// The `if_missing` function or closure will be called to create that `Type`
+    /// if it hasn't been created yet. No lock is held while it runs, so it may
+    /// itself look up types.
+    fn lookup_or_insert(&self, key: K, if_missing: impl FnOnce() -> Type) -> TypeRef {
+        if let Some(typeref) = self.lookup(&key) {
+            return typeref;
+        }
+        let new_typeref = TypeRef::new(if_missing());
+        let mut shard = self
+            .shard(&key)
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        // another thread may have added the `Type` since we checked above, in
+        // which case that one is kept, so that every thread gets the same
+        // `TypeRef`
+        shard.entry(key).or_insert(new_typeref).clone()
+    }
+}
+
+impl<K: Eq + Hash + Clone> SharedTypeCache<K> {
+    /// Estimate the heap memory used by this cache and the `Type`s in it, in
+    /// bytes
+    fn heap_size(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| {
+                let shard = shard.read().unwrap_or_else(PoisonError::into_inner);
+                let table = shard.capacity() * size_of::<(K, TypeRef)>();
+                // each `Type`'s `Arc` allocation, plus any heap memory owned
+                // by the `Type`, which is mirrored in its key (e.g., a
+                // `StructType`'s element types)
+                let types: usize = shard
+                    .values()
+                    .map(|ty| 2 * size_of::<usize>() + size_of::<Type>() + 2 * ty.own_heap_size())
+                    .sum();
+                table + types
+            })
+            .sum()
+    }
+}
+
+impl<K: Eq + Hash + Clone> From<TypeCache<K>> for SharedTypeCache<K> {
+    fn from(cache: TypeCache<K>) -> Self {
+        let mut shards: Vec<HashMap<K, TypeRef>> =
+            (0 .. NUM_SHARDS).map(|_| HashMap::new()).collect();
+        for (key, typeref) in cache.map {
+            shards[shard_index(&key)].insert(key, typeref);
+        }
+        Self {
+            shards: shards.into_iter().map(RwLock::new).collect(),
+        }
+    }
+}
+
+impl<K: Eq + Hash + Clone> Clone for SharedTypeCache<K> {
+    fn clone(&self) -> Self {
+        Self {
+            shards: self
+                .shards
+                .iter()
+                .map(|shard| {
+                    RwLock::new(shard.read().unwrap_or_else(PoisonError::into_inner).clone())
+                })
+                .collect(),
+        }
+    }
+}
+
 // ********* //
 // from_llvm //
 // ********* //
@@ -913,7 +1535,7 @@ impl<K: Eq + Hash + Clone> TypeCache<K> {
 use crate::from_llvm::*;
 use crate::llvm_sys::*;
 use llvm_sys::LLVMTypeKind;
-use std::collections::hash_map::Entry;
+use std::collections::btree_map::Entry;
 
 impl TypesBuilder {
     pub(crate) fn type_from_llvm_ref(&mut self, ty: LLVMTypeRef) -> TypeRef {
@@ -931,8 +1553,14 @@ impl TypesBuilder {
             LLVMTypeKind::LLVMVoidTypeKind => self.void(),
             LLVMTypeKind::LLVMIntegerTypeKind => self.int(unsafe { LLVMGetIntTypeWidth(ty) }),
             LLVMTypeKind::LLVMPointerTypeKind => {
+                let addr_space = unsafe { LLVMGetPointerAddressSpace(ty) };
+                // only opaque pointers have no contained (pointee) type
+                #[cfg(feature = "llvm-14-or-greater")]
+                if unsafe { LLVMGetNumContainedTypes(ty) } == 0 {
+                    return self.opaque_pointer_in_addr_space(addr_space);
+                }
                 let pointee_type = self.type_from_llvm_ref(unsafe { LLVMGetElementType(ty) });
-                self.pointer_in_addr_space(pointee_type, unsafe { LLVMGetPointerAddressSpace(ty) })
+                self.pointer_in_addr_space(pointee_type, addr_space)
             },
             LLVMTypeKind::LLVMArrayTypeKind => {
                 let element_type = self.type_from_llvm_ref(unsafe { LLVMGetElementType(ty) });
@@ -968,7 +1596,7 @@ impl TypesBuilder {
 
                 match name {
                     Some(s) if !s.is_empty() => {
-                        if self.named_struct_types.contains_key(&s) {
+                        if self.named_struct_defs.contains_key(&s) {
                             // already defined: return the NamedStructType and don't change the definition
                             self.named_struct(s)
                         } else if unsafe { LLVMIsOpaqueStruct(ty) } != 0 {
@@ -977,15 +1605,18 @@ impl TypesBuilder {
                             // return the NamedStructType
                             self.named_struct(s)
                         } else {
-                            // add the NamedStructType first, so that the call to struct_type_from_llvm_ref will terminate
+                            // add the NamedStructType first, with a placeholder
+                            // definition, so that the call to struct_type_from_llvm_ref
+                            // will terminate. (The NamedStructType may already exist,
+                            // if it was created by another Module in the same Context.)
                             let named_struct_typeref = self.named_struct(s.clone());
+                            self.named_struct_defs
+                                .insert(s.clone(), NamedStructDef::Opaque);
                             // now compute the actual struct type. Any self-references will point to the NamedStructType we just created
                             let actual_struct_type = self.struct_type_from_llvm_ref(ty);
-                            // add this definition for the named struct
-                            self.add_named_struct_def(
-                                s,
-                                NamedStructDef::Defined(actual_struct_type),
-                            );
+                            // replace the placeholder with the actual definition
+                            self.named_struct_defs
+                                .insert(s, NamedStructDef::Defined(actual_struct_type));
                             // And now we return the NamedStructType
                             named_struct_typeref
                         }
@@ -0,0 +1,142 @@
+//! A uniform view of the things an `Operand` (or a local `Name`) can refer
+//! to, similar to LLVM's `Value` class hierarchy.
+
+use crate::basicblock::BasicBlock;
+use crate::constant::Constant;
+use crate::function::{Function, Parameter};
+use crate::instruction::Instruction;
+use crate::module::{GlobalAlias, GlobalVariable, Module};
+use crate::name::Name;
+use crate::operand::Operand;
+use crate::terminator::Terminator;
+
+/// What a value in a `Module` is: where it is defined, for values with a
+/// name, or the value itself, for constants.
+///
+/// Use [`Module::resolve_operand()`](../module/struct.Module.html#method.resolve_operand)
+/// to get the `ValueKind` of an `Operand`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum ValueKind<'m> {
+    /// The result of an `Instruction`
+    Instruction(&'m Instruction),
+    /// The result of a `Terminator` (for instance, an `Invoke`)
+    Terminator(&'m Terminator),
+    /// A parameter of the function
+    Argument(&'m Parameter),
+    /// A constant, other than a reference to a global value defined in the
+    /// `Module`
+    Constant(&'m Constant),
+    /// A global value defined in the `Module`
+    Global(GlobalValue<'m>),
+    /// A basic block of the function
+    Block(&'m BasicBlock),
+}
+
+/// A global value defined in a `Module`.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash)]
+pub enum GlobalValue<'m> {
+    Function(&'m Function),
+    Variable(&'m GlobalVariable),
+    Alias(&'m GlobalAlias),
+}
+
+impl<'m> ValueKind<'m> {
+    /// Get the `Instruction`, if this is the result of one
+    pub fn as_instruction(self) -> Option<&'m Instruction> {
+        match self {
+            ValueKind::Instruction(inst) => Some(inst),
+            _ => None,
+        }
+    }
+
+    /// Get the `Constant`, if this is a constant which isn't a reference to a
+    /// global value defined in the `Module`
+    pub fn as_constant(self) -> Option<&'m Constant> {
+        match self {
+            ValueKind::Constant(constant) => Some(constant),
+            _ => None,
+        }
+    }
+
+    /// Is this a constant, including a reference to a global value?
+    pub fn is_constant(self) -> bool {
+        matches!(self, ValueKind::Constant(_) | ValueKind::Global(_))
+    }
+}
+
+impl Function {
+    /// Resolve a local `Name` in this `Function`: to the parameter, the
+    /// `Instruction` or `Terminator` whose result it is, or the basic block
+    /// with that name. Returns `None` if nothing in the `Function` has the
+    /// name.
+    ///
+    /// This searches the whole `Function`. To resolve many names, an
+    /// [`SsaGraph`](../analysis/struct.SsaGraph.html) is more efficient.
+    pub fn resolve_local(&self, name: &Name) -> Option<ValueKind<'_>> {
+        if let Some(param) = self.parameters.iter().find(|p| &p.name == name) {
+            return Some(ValueKind::Argument(param));
+        }
+        for bb in &self.basic_blocks {
+            if &bb.name == name {
+                return Some(ValueKind::Block(bb));
+            }
+            if let Some(inst) = bb
+                .instrs
+                .iter()
+                .find(|inst| inst.try_get_result() == Some(name))
+            {
+                return Some(ValueKind::Instruction(inst));
+            }
+            if bb.term.try_get_result() == Some(name) {
+                return Some(ValueKind::Terminator(&bb.term));
+            }
+        }
+        None
+    }
+}
+
+impl Module {
+    /// Get the function, global variable, or alias defined in this `Module`
+    /// with the given `Name` (if any).
+    pub fn get_global_by_name(&self, name: &Name) -> Option<GlobalValue<'_>> {
+        let func = name
+            .as_bytes()
+            .and_then(|bytes| self.functions.iter().find(|f| f.name.as_bytes() == bytes));
+        if let Some(func) = func {
+            Some(GlobalValue::Function(func))
+        } else if let Some(var) = self.get_global_var_by_name(name) {
+            Some(GlobalValue::Variable(var))
+        } else {
+            self.global_aliases
+                .iter()
+                .find(|alias| &alias.name == name)
+                .map(GlobalValue::Alias)
+        }
+    }
+
+    /// Resolve an `Operand` used in the given `Function` of this `Module` to
+    /// what it refers to.
+    ///
+    /// A `GlobalReference` to a global value defined in the `Module` resolves
+    /// to `ValueKind::Global`; references to global values which are only
+    /// declared resolve to `ValueKind::Constant`, as do all other constants.
+    /// Returns `None` for metadata operands, and for local operands which
+    /// aren't defined in `func`.
+    pub fn resolve_operand<'m>(
+        &'m self,
+        func: &'m Function,
+        operand: &'m Operand,
+    ) -> Option<ValueKind<'m>> {
+        match operand {
+            Operand::LocalOperand { name, .. } => func.resolve_local(name),
+            Operand::ConstantOperand(constant) => match constant.as_ref() {
+                Constant::GlobalReference { name, .. } => match self.get_global_by_name(name) {
+                    Some(global) => Some(ValueKind::Global(global)),
+                    None => Some(ValueKind::Constant(constant)),
+                },
+                constant => Some(ValueKind::Constant(constant)),
+            },
+            Operand::MetadataOperand(_) => None,
+        }
+    }
+}
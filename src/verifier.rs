@@ -0,0 +1,284 @@
+//! Rust-side structural validation of the IR data structures.
+//! See [`Module::verify()`](../module/struct.Module.html#method.verify).
+
+use crate::function::Function;
+use crate::instruction::Instruction;
+use crate::module::Module;
+use crate::name::Name;
+use crate::operand::Operand;
+use crate::terminator::Terminator;
+use crate::types::{Type, Types};
+use std::collections::{HashMap, HashSet};
+
+impl Module {
+    /// Check the `Module` for structural problems: for instance, branches to
+    /// blocks which don't exist, uses of local values which are never
+    /// defined, or instruction operands with mismatched types.
+    ///
+    /// This checks the Rust data structures, so it is useful for IR which
+    /// has been constructed or modified in Rust. It is much less thorough
+    /// than LLVM's own verifier; to run that on bitcode, use
+    /// [`Module::from_bc_path_verified()`](struct.Module.html#method.from_bc_path_verified).
+    ///
+    /// Returns a description of each problem found, if any.
+    pub fn verify(&self) -> Result<(), Vec<String>> {
+        let mut errors = vec![];
+        for func in &self.functions {
+            FunctionVerifier::new(func, &self.types, &mut errors).verify();
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+struct FunctionVerifier<'a> {
+    func: &'a Function,
+    types: &'a Types,
+    errors: &'a mut Vec<String>,
+    /// Local values defined in the function
+    defined: HashSet<&'a Name>,
+    /// Map from block name to the names of its predecessors
+    preds: HashMap<&'a Name, Vec<&'a Name>>,
+}
+
+impl<'a> FunctionVerifier<'a> {
+    fn new(func: &'a Function, types: &'a Types, errors: &'a mut Vec<String>) -> Self {
+        Self {
+            func,
+            types,
+            errors,
+            defined: HashSet::new(),
+            preds: HashMap::new(),
+        }
+    }
+
+    fn error(&mut self, block: Option<&Name>, msg: String) {
+        match block {
+            None => self
+                .errors
+                .push(format!("function {:?}: {}", self.func.name, msg)),
+            Some(block) => self.errors.push(format!(
+                "function {:?}, block {}: {}",
+                self.func.name, block, msg
+            )),
+        }
+    }
+
+    fn verify(mut self) {
+        let func = self.func;
+        if func.basic_blocks.is_empty() {
+            self.error(None, "function has no basic blocks".into());
+            return;
+        }
+
+        // collect block names and definitions, checking for duplicates
+        for bb in &func.basic_blocks {
+            if self.preds.insert(&bb.name, vec![]).is_some() {
+                self.error(
+                    None,
+                    format!("block name {} is used more than once", bb.name),
+                );
+            }
+        }
+        let results = func
+            .parameters
+            .iter()
+            .map(|p| &p.name)
+            .chain(
+                func.basic_blocks
+                    .iter()
+                    .flat_map(|bb| bb.instrs.iter().filter_map(|inst| inst.try_get_result())),
+            )
+            .chain(
+                func.basic_blocks
+                    .iter()
+                    .filter_map(|bb| bb.term.try_get_result()),
+            );
+        for name in results {
+            if !self.defined.insert(name) {
+                self.error(
+                    None,
+                    format!("local value {} is defined more than once", name),
+                );
+            }
+        }
+
+        // check branch targets, and compute predecessors
+        for bb in &func.basic_blocks {
+            for dest in bb.term.successors() {
+                match self.preds.get_mut(dest) {
+                    Some(preds) => {
+                        if !preds.contains(&&bb.name) {
+                            preds.push(&bb.name);
+                        }
+                    },
+                    None => self.error(
+                        Some(&bb.name),
+                        format!("terminator branches to nonexistent block {}", dest),
+                    ),
+                }
+            }
+        }
+
+        for bb in &func.basic_blocks {
+            for inst in &bb.instrs {
+                self.verify_instruction(&bb.name, inst);
+            }
+            self.verify_terminator(&bb.name, &bb.term);
+        }
+    }
+
+    /// Check that the given operand refers to a value which exists
+    fn check_operand(&mut self, block: &Name, operand: &Operand) {
+        if let Operand::LocalOperand { name, .. } = operand {
+            if !self.defined.contains(name) {
+                self.error(
+                    Some(block),
+                    format!("use of undefined local value {}", name),
+                );
+            }
+        }
+    }
+
+    /// Check that the two operands exist and have the same type
+    fn check_same_type(&mut self, block: &Name, what: &str, op0: &Operand, op1: &Operand) {
+        self.check_operand(block, op0);
+        self.check_operand(block, op1);
+        let ty0 = self.types.type_of(op0);
+        let ty1 = self.types.type_of(op1);
+        if ty0 != ty1 {
+            self.error(
+                Some(block),
+                format!(
+                    "{} has operands of different types: {} and {}",
+                    what, ty0, ty1
+                ),
+            );
+        }
+    }
+
+    fn verify_instruction(&mut self, block: &Name, inst: &Instruction) {
+        macro_rules! binop {
+            ($i:expr) => {
+                self.check_same_type(block, &$i.dest.to_string(), &$i.operand0, &$i.operand1)
+            };
+        }
+        match inst {
+            Instruction::Add(i) => binop!(i),
+            Instruction::Sub(i) => binop!(i),
+            Instruction::Mul(i) => binop!(i),
+            Instruction::UDiv(i) => binop!(i),
+            Instruction::SDiv(i) => binop!(i),
+            Instruction::URem(i) => binop!(i),
+            Instruction::SRem(i) => binop!(i),
+            Instruction::And(i) => binop!(i),
+            Instruction::Or(i) => binop!(i),
+            Instruction::Xor(i) => binop!(i),
+            Instruction::Shl(i) => binop!(i),
+            Instruction::LShr(i) => binop!(i),
+            Instruction::AShr(i) => binop!(i),
+            Instruction::FAdd(i) => binop!(i),
+            Instruction::FSub(i) => binop!(i),
+            Instruction::FMul(i) => binop!(i),
+            Instruction::FDiv(i) => binop!(i),
+            Instruction::FRem(i) => binop!(i),
+            Instruction::ICmp(i) => binop!(i),
+            Instruction::FCmp(i) => binop!(i),
+            Instruction::Select(i) => {
+                self.check_operand(block, &i.condition);
+                self.check_same_type(block, &i.dest.to_string(), &i.true_value, &i.false_value);
+            },
+            Instruction::Load(i) => self.check_operand(block, &i.address),
+            Instruction::Store(i) => {
+                self.check_operand(block, &i.address);
+                self.check_operand(block, &i.value);
+                let addr_ty = self.types.type_of(&i.address);
+                let value_ty = self.types.type_of(&i.value);
+                match addr_ty.as_ref() {
+                    Type::PointerType { pointee_type, .. } => {
+                        if pointee_type != &value_ty {
+                            self.error(
+                                Some(block),
+                                format!(
+                                    "store of a {} to an address of type {}",
+                                    value_ty, addr_ty
+                                ),
+                            );
+                        }
+                    },
+                    // opaque pointers can point to a value of any type
+                    Type::OpaquePointerType { .. } => {},
+                    _ => self.error(
+                        Some(block),
+                        format!("store to an address of non-pointer type {}", addr_ty),
+                    ),
+                }
+            },
+            Instruction::Phi(phi) => {
+                for (value, pred) in &phi.incoming_values {
+                    self.check_operand(block, value);
+                    let value_ty = self.types.type_of(value);
+                    if value_ty != phi.to_type {
+                        self.error(
+                            Some(block),
+                            format!(
+                                "{} has type {} but an incoming value of type {}",
+                                phi.dest, phi.to_type, value_ty
+                            ),
+                        );
+                    }
+                    if !self.preds[block].contains(&pred) {
+                        self.error(
+                            Some(block),
+                            format!(
+                                "{} has an incoming value from {}, which is not a predecessor",
+                                phi.dest, pred
+                            ),
+                        );
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn verify_terminator(&mut self, block: &Name, term: &Terminator) {
+        match term {
+            Terminator::Ret(ret) => {
+                let returned_ty = match &ret.return_operand {
+                    None => self.types.void(),
+                    Some(op) => {
+                        self.check_operand(block, op);
+                        self.types.type_of(op)
+                    },
+                };
+                if returned_ty != self.func.return_type {
+                    self.error(
+                        Some(block),
+                        format!(
+                            "ret of type {} in a function returning {}",
+                            returned_ty, self.func.return_type
+                        ),
+                    );
+                }
+            },
+            Terminator::CondBr(condbr) => {
+                self.check_operand(block, &condbr.condition);
+                let ty = self.types.type_of(&condbr.condition);
+                if ty != self.types.bool() {
+                    self.error(
+                        Some(block),
+                        format!("br condition has type {}, expected i1", ty),
+                    );
+                }
+            },
+            Terminator::Switch(switch) => self.check_operand(block, &switch.operand),
+            Terminator::IndirectBr(ibr) => self.check_operand(block, &ibr.operand),
+            Terminator::Resume(resume) => self.check_operand(block, &resume.operand),
+            _ => {},
+        }
+    }
+}
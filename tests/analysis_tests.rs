@@ -0,0 +1,753 @@
+use llvm_ir::analysis::{
+    backward_slice,
+    AliasResult,
+    BasicAliasAnalysis,
+    BlockAddressInconsistency,
+    BlockAddresses,
+    ControlFlowGraph,
+    CoroutineInfo,
+    Definition,
+    DominatorTree,
+    EdgeAssignments,
+    LoadDependences,
+    LoopInfo,
+    MemoryDependences,
+    Reachability,
+    SsaGraph,
+};
+use llvm_ir::instruction;
+use llvm_ir::intrinsic::Intrinsic;
+use llvm_ir::location::{InstrLoc, InstrPos, InstrRef, ModuleLoc};
+use llvm_ir::terminator::{Br, CondBr, Ret};
+use llvm_ir::{
+    BasicBlock,
+    Constant,
+    ConstantRef,
+    Function,
+    Instruction,
+    Module,
+    Name,
+    Operand,
+    Terminator,
+};
+use std::convert::TryInto;
+use std::path::{Path, PathBuf};
+
+fn init_logging() {
+    // capture log messages with test harness
+    let _ = env_logger::builder().is_test(true).try_init();
+}
+
+fn br(dest: &str) -> Terminator {
+    Terminator::Br(Br {
+        dest: Name::from(dest),
+        loop_metadata: None,
+        #[cfg(feature = "llvm-9-or-greater")]
+        debugloc: None,
+    })
+}
+
+fn condbr(true_dest: &str, false_dest: &str) -> Terminator {
+    Terminator::CondBr(CondBr {
+        condition: Operand::ConstantOperand(ConstantRef::new(Constant::Int { bits: 1, value: 0 }))
+            .into(),
+        true_dest: Name::from(true_dest),
+        false_dest: Name::from(false_dest),
+        branch_weights: None,
+        loop_metadata: None,
+        #[cfg(feature = "llvm-9-or-greater")]
+        debugloc: None,
+    })
+}
+
+fn ret() -> Terminator {
+    Terminator::Ret(Ret {
+        return_operand: None,
+        #[cfg(feature = "llvm-9-or-greater")]
+        debugloc: None,
+    })
+}
+
+// Test against bitcode compiled with the same version of LLVM
+fn llvm_bc_dir() -> PathBuf {
+    Path::new("tests/basic_bc").join(format!("llvm{}", llvm_ir::llvm_version()))
+}
+
+/// Build a `Function` with no instructions, just blocks with the given names
+/// and terminators
+fn func_with_blocks(blocks: Vec<(&str, Terminator)>) -> Function {
+    let mut func = Function::new("test_func");
+    for (name, term) in blocks {
+        let mut bb = BasicBlock::new(Name::from(name));
+        bb.term = term;
+        func.basic_blocks.push(bb);
+    }
+    func
+}
+
+/// Two nested loops, plus an unreachable block:
+///
+/// ```text
+/// entry -> outer_pre -> outer <-------------+
+///                        |                  |
+///                        v                  |
+///                      inner_pre -> inner <-+--+
+///                                    |  |   |  |
+///                                    |  +---+--+ (inner -> inner)
+///                                    v      |
+///                                  outer_latch
+///                                    |
+///                                    v
+///                                   exit
+/// ```
+fn nested_loops() -> Function {
+    func_with_blocks(vec![
+        ("entry", br("outer_pre")),
+        ("outer_pre", br("outer")),
+        ("outer", condbr("inner_pre", "exit")),
+        ("inner_pre", br("inner")),
+        ("inner", condbr("inner", "outer_latch")),
+        ("outer_latch", br("outer")),
+        ("exit", ret()),
+        ("dead", br("inner")),
+    ])
+}
+
+#[test]
+fn cfg_and_dominators() {
+    init_logging();
+    let func = nested_loops();
+    let cfg = ControlFlowGraph::new(&func);
+    assert_eq!(cfg.entry(), &Name::from("entry"));
+    assert_eq!(
+        cfg.succs(&Name::from("outer")).collect::<Vec<_>>(),
+        vec![&Name::from("inner_pre"), &Name::from("exit")],
+    );
+    assert_eq!(
+        cfg.preds(&Name::from("inner")).collect::<Vec<_>>(),
+        vec![
+            &Name::from("inner_pre"),
+            &Name::from("inner"),
+            &Name::from("dead")
+        ],
+    );
+    assert!(cfg.is_reachable(&Name::from("exit")));
+    assert!(!cfg.is_reachable(&Name::from("dead")));
+    assert_eq!(cfg.reverse_postorder().count(), 7);
+    assert_eq!(cfg.reverse_postorder().next(), Some(&Name::from("entry")));
+
+    let domtree = DominatorTree::new(&cfg);
+    assert_eq!(domtree.idom(&Name::from("entry")), None);
+    assert_eq!(
+        domtree.idom(&Name::from("outer")),
+        Some(&Name::from("outer_pre"))
+    );
+    assert_eq!(
+        domtree.idom(&Name::from("exit")),
+        Some(&Name::from("outer"))
+    );
+    assert_eq!(
+        domtree.idom(&Name::from("outer_latch")),
+        Some(&Name::from("inner"))
+    );
+    assert_eq!(domtree.idom(&Name::from("dead")), None);
+    assert!(domtree.dominates(&Name::from("outer"), &Name::from("inner")));
+    assert!(domtree.dominates(&Name::from("inner"), &Name::from("inner")));
+    assert!(!domtree.strictly_dominates(&Name::from("inner"), &Name::from("inner")));
+    assert!(!domtree.dominates(&Name::from("inner"), &Name::from("exit")));
+    assert!(!domtree.dominates(&Name::from("entry"), &Name::from("dead")));
+    assert_eq!(domtree.depth(&Name::from("entry")), Some(0));
+    assert_eq!(domtree.depth(&Name::from("inner")), Some(4));
+    assert_eq!(domtree.depth(&Name::from("dead")), None);
+    assert_eq!(
+        domtree.children(&Name::from("outer")).collect::<Vec<_>>(),
+        vec![&Name::from("inner_pre"), &Name::from("exit")],
+    );
+}
+
+#[test]
+fn nested_loop_info() {
+    init_logging();
+    let func = nested_loops();
+    let cfg = ControlFlowGraph::new(&func);
+    let domtree = DominatorTree::new(&cfg);
+    let loop_info = LoopInfo::new(&cfg, &domtree);
+
+    assert_eq!(loop_info.loops().len(), 2);
+    assert_eq!(
+        loop_info.back_edges(),
+        &[
+            (&Name::from("outer_latch"), &Name::from("outer")),
+            (&Name::from("inner"), &Name::from("inner")),
+        ]
+    );
+
+    let outer = &loop_info.loops()[0];
+    assert_eq!(outer.header(), &Name::from("outer"));
+    assert_eq!(outer.depth(), 1);
+    assert_eq!(
+        outer.blocks().collect::<Vec<_>>(),
+        vec![
+            &Name::from("outer"),
+            &Name::from("inner_pre"),
+            &Name::from("inner"),
+            &Name::from("outer_latch"),
+        ]
+    );
+    assert_eq!(outer.latches(), &[&Name::from("outer_latch")]);
+    assert_eq!(outer.exiting_blocks(), &[&Name::from("outer")]);
+    assert_eq!(outer.exit_blocks(), &[&Name::from("exit")]);
+    assert_eq!(outer.preheader(), Some(&Name::from("outer_pre")));
+    assert!(loop_info.parent(outer).is_none());
+    assert_eq!(loop_info.top_level_loops().count(), 1);
+
+    let inner = &loop_info.loops()[1];
+    assert_eq!(inner.header(), &Name::from("inner"));
+    assert_eq!(inner.depth(), 2);
+    assert_eq!(
+        inner.blocks().collect::<Vec<_>>(),
+        vec![&Name::from("inner")]
+    );
+    assert_eq!(inner.latches(), &[&Name::from("inner")]);
+    assert_eq!(inner.exit_blocks(), &[&Name::from("outer_latch")]);
+    assert_eq!(inner.preheader(), Some(&Name::from("inner_pre")));
+    assert_eq!(
+        loop_info.parent(inner).map(|l| l.header()),
+        Some(&Name::from("outer"))
+    );
+    assert_eq!(
+        loop_info
+            .sub_loops(outer)
+            .map(|l| l.header())
+            .collect::<Vec<_>>(),
+        vec![&Name::from("inner")]
+    );
+
+    assert!(outer.contains(&Name::from("inner")));
+    assert!(!inner.contains(&Name::from("outer")));
+    assert!(!outer.contains(&Name::from("dead")));
+    assert_eq!(loop_info.loop_depth(&Name::from("entry")), 0);
+    assert_eq!(loop_info.loop_depth(&Name::from("outer_latch")), 1);
+    assert_eq!(loop_info.loop_depth(&Name::from("inner")), 2);
+    assert_eq!(loop_info.loop_depth(&Name::from("dead")), 0);
+    assert!(loop_info.is_loop_header(&Name::from("outer")));
+    assert!(loop_info.is_loop_header(&Name::from("inner")));
+    assert!(!loop_info.is_loop_header(&Name::from("outer_latch")));
+}
+
+#[test]
+fn irreducible_cycle_is_not_a_loop() {
+    init_logging();
+    // `a` and `b` form a cycle, but it can be entered at either block, so
+    // neither dominates the other
+    let func = func_with_blocks(vec![
+        ("entry", condbr("a", "b")),
+        ("a", condbr("b", "exit")),
+        ("b", br("a")),
+        ("exit", ret()),
+    ]);
+    let cfg = ControlFlowGraph::new(&func);
+    let domtree = DominatorTree::new(&cfg);
+    let loop_info = LoopInfo::new(&cfg, &domtree);
+    assert!(loop_info.loops().is_empty());
+    assert!(loop_info.back_edges().is_empty());
+}
+
+// the exact structure of the loop depends on the LLVM version which
+// compiled loop.bc
+#[cfg(feature = "llvm-14")]
+#[test]
+fn loopbc_loop_info() {
+    init_logging();
+    let path = llvm_bc_dir().join("loop.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("loop").unwrap();
+    let cfg = ControlFlowGraph::new(func);
+    let domtree = DominatorTree::new(&cfg);
+    let loop_info = LoopInfo::new(&cfg, &domtree);
+    assert_eq!(loop_info.loops().len(), 1);
+    let l = &loop_info.loops()[0];
+    assert_eq!(l.header(), &Name::from(18));
+    assert_eq!(l.latches(), &[&Name::from(18)]);
+    assert_eq!(l.blocks().collect::<Vec<_>>(), vec![&Name::from(18)]);
+    assert_eq!(l.exit_blocks(), &[&Name::from(36)]);
+    assert_eq!(l.preheader(), Some(&Name::from(16)));
+    assert_eq!(loop_info.loop_depth(&Name::from(46)), 0);
+}
+
+#[test]
+fn ssa_graph() {
+    init_logging();
+    let path = llvm_bc_dir().join("loop.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("loop").unwrap();
+    let ssa = SsaGraph::new(func);
+
+    // parameters are numbered first
+    assert_eq!(ssa.value_number(&Name::from(0)), Some(0));
+    assert_eq!(ssa.value_number(&Name::from(1)), Some(1));
+    match ssa.definition(&Name::from(1)) {
+        Some(Definition::Parameter { index, param }) => {
+            assert_eq!(*index, 1);
+            assert_eq!(param, &&func.parameters[1]);
+        },
+        def => panic!("Expected %1 to be a parameter; got {:?}", def),
+    }
+    assert_eq!(ssa.definition(&Name::from(1)).unwrap().block(), None);
+
+    // every instruction result resolves back to that instruction
+    let mut num_results = func.parameters.len();
+    for bb in &func.basic_blocks {
+        for (i, inst) in bb.instrs.iter().enumerate() {
+            if let Some(dest) = inst.try_get_result() {
+                num_results += 1;
+                let def = ssa
+                    .definition(dest)
+                    .expect("Result should have a definition");
+                assert_eq!(def.name(), dest);
+                assert_eq!(def.block(), Some(&bb.name));
+                match def {
+                    Definition::Instruction {
+                        index,
+                        inst: def_inst,
+                        ..
+                    } => {
+                        assert_eq!(*index, i);
+                        assert_eq!(def_inst, &inst);
+                    },
+                    def => panic!("Expected an instruction definition; got {:?}", def),
+                }
+                assert_eq!(ssa.defining_instruction(dest), Some(inst));
+                let number = ssa.value_number(dest).unwrap();
+                assert_eq!(ssa.value(number), def);
+            }
+        }
+    }
+    assert_eq!(ssa.num_values(), num_results);
+    assert_eq!(ssa.values().count(), num_results);
+
+    // the operand of the bitcast is the result of the alloca
+    let alloca: &instruction::Alloca = &func.basic_blocks[0].instrs[0]
+        .clone()
+        .try_into()
+        .expect("Should be an alloca");
+    let bitcast: &instruction::BitCast = &func.basic_blocks[0].instrs[1]
+        .clone()
+        .try_into()
+        .expect("Should be a bitcast");
+    match ssa.resolve(&bitcast.operand) {
+        Some(Definition::Instruction { inst, .. }) => {
+            assert_eq!(inst, &&Instruction::Alloca(alloca.clone()))
+        },
+        def => panic!(
+            "Expected the bitcast operand to be the alloca; got {:?}",
+            def
+        ),
+    }
+    let constant = Operand::ConstantOperand(ConstantRef::new(Constant::Int { bits: 32, value: 3 }));
+    assert!(ssa.resolve(&constant).is_none());
+    assert!(ssa.definition(&Name::from("nonexistent")).is_none());
+
+    // the bitcast is the first use of the alloca
+    let first_use = &ssa.uses(&alloca.dest)[0];
+    assert_eq!(first_use.block, &func.basic_blocks[0].name);
+    assert_eq!(first_use.pos, InstrPos::Instr(1));
+    assert_eq!(
+        first_use.user,
+        InstrRef::Instr(&func.basic_blocks[0].instrs[1])
+    );
+    assert_eq!(first_use.operand_index, 0);
+    assert!(ssa.uses(&Name::from("nonexistent")).is_empty());
+
+    // each use refers to the value, and uses are in the order they appear
+    let mut num_uses = 0;
+    for def in ssa.values() {
+        let uses = ssa.uses(def.name());
+        num_uses += uses.len();
+        for u in uses {
+            let operand = match u.user {
+                InstrRef::Instr(inst) => inst.operands().nth(u.operand_index),
+                InstrRef::Terminator(term) => term.operands().nth(u.operand_index),
+            };
+            assert!(
+                matches!(operand, Some(Operand::LocalOperand { name, .. }) if name == def.name())
+            );
+        }
+        let positions: Vec<(usize, InstrPos, usize)> = uses
+            .iter()
+            .map(|u| {
+                let block = func
+                    .basic_blocks
+                    .iter()
+                    .position(|bb| &bb.name == u.block)
+                    .unwrap();
+                (block, u.pos, u.operand_index)
+            })
+            .collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+    }
+    let num_local_operands = func
+        .basic_blocks
+        .iter()
+        .flat_map(|bb| {
+            bb.instrs
+                .iter()
+                .flat_map(Instruction::operands)
+                .chain(bb.term.operands())
+        })
+        .filter(|op| matches!(op, Operand::LocalOperand { .. }))
+        .count();
+    assert_eq!(num_uses, num_local_operands);
+}
+
+// the block numbering depends on the LLVM version which compiled loop.bc
+#[cfg(feature = "llvm-14")]
+#[test]
+fn edge_assignments() {
+    init_logging();
+    let path = llvm_bc_dir().join("loop.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("loop").unwrap();
+    let edges = EdgeAssignments::new(func);
+    let int = |value| Operand::ConstantOperand(ConstantRef::new(Constant::Int { bits: 64, value }));
+    let local = |n: usize| Name::from(n);
+
+    // entering the loop
+    let copies = edges.assignments(&local(16), &local(18));
+    assert_eq!(copies.len(), 2);
+    assert_eq!((copies[0].0, copies[0].1), (&local(19), &int(1)));
+    assert_eq!((copies[1].0, copies[1].1), (&local(20), &int(0)));
+
+    // the back edge
+    let copies = edges.assignments(&local(18), &local(18));
+    assert_eq!(copies.len(), 2);
+    assert_eq!(copies[0].0, &local(19));
+    assert!(matches!(copies[0].1, Operand::LocalOperand { name, .. } if name == &local(33)));
+    assert_eq!(copies[1].0, &local(20));
+    assert!(matches!(copies[1].1, Operand::LocalOperand { name, .. } if name == &local(34)));
+
+    // edges into blocks without phis, and non-edges, have no copies
+    assert!(edges.assignments(&local(11), &local(16)).is_empty());
+    assert!(edges.assignments(&local(16), &local(11)).is_empty());
+
+    let num_copies: usize = edges.edges().map(|(_, copies)| copies.len()).sum();
+    let num_incoming: usize = func
+        .basic_blocks
+        .iter()
+        .flat_map(|bb| &bb.instrs)
+        .filter_map(|inst| match inst {
+            Instruction::Phi(phi) => Some(phi.incoming_values.len()),
+            _ => None,
+        })
+        .sum();
+    assert_eq!(num_copies, num_incoming);
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn basic_alias_analysis() {
+    init_logging();
+    let path = llvm_bc_dir().join("alias.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("pointers").unwrap();
+    let aa = BasicAliasAnalysis::new(&module, func);
+
+    // the address loaded from by the load with the given result name
+    let addr = |load: &str| -> &Operand {
+        let load: &instruction::Load = func.basic_blocks[0]
+            .instrs
+            .iter()
+            .find_map(|inst| match inst {
+                Instruction::Load(l) if l.dest == Name::from(load) => Some(l),
+                _ => None,
+            })
+            .expect("Failed to find load");
+        &load.address
+    };
+
+    // distinct fields and elements of the same alloca
+    assert_eq!(aa.alias(addr("l0"), addr("l1")), AliasResult::NoAlias);
+    assert_eq!(aa.alias(addr("l1"), addr("l2")), AliasResult::NoAlias);
+    // the same element, reached through different GEPs
+    assert_eq!(aa.alias(addr("l1"), addr("l3")), AliasResult::MustAlias);
+    // a bitcast of the base, and the first field of the base
+    assert_eq!(aa.alias(addr("l0"), addr("l4")), AliasResult::MustAlias);
+    assert_eq!(aa.alias(addr("l1"), addr("l4")), AliasResult::MayAlias);
+    // distinct allocas
+    assert_eq!(aa.alias(addr("l0"), addr("l5")), AliasResult::NoAlias);
+    // parameters and loaded pointers may point anywhere
+    assert_eq!(aa.alias(addr("l5"), addr("l6")), AliasResult::MayAlias);
+    assert_eq!(aa.alias(addr("l6"), addr("l7")), AliasResult::MayAlias);
+    assert_eq!(aa.alias(addr("l6"), addr("l6")), AliasResult::MustAlias);
+    // constant GEPs into a global, and distinct globals
+    assert_eq!(aa.alias(addr("l8"), addr("l9")), AliasResult::NoAlias);
+    assert_eq!(aa.alias(addr("l8"), addr("l1")), AliasResult::NoAlias);
+    assert_eq!(aa.alias(addr("l9"), addr("l10")), AliasResult::NoAlias);
+    // a global alias may alias its aliasee
+    assert_eq!(aa.alias(addr("l10"), addr("l11")), AliasResult::MayAlias);
+    // an out-of-bounds index may land anywhere in the object
+    assert_eq!(aa.alias(addr("l1"), addr("l12")), AliasResult::MayAlias);
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn memory_dependences() {
+    init_logging();
+    let path = llvm_bc_dir().join("memdeps.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("memdeps").unwrap();
+    let deps = MemoryDependences::new(&module, func);
+    assert_eq!(deps.loads().count(), 6);
+
+    // @memdeps is the second function; its blocks are entry, then, join,
+    // loop, exit
+    let at = |block: usize, index: usize| ModuleLoc::new(1, block).instr(index);
+    let writers = |locs: &[InstrLoc], live_on_entry: bool| LoadDependences {
+        writers: locs.to_vec(),
+        live_on_entry,
+    };
+
+    // %l0: only the preceding store to the same alloca
+    assert_eq!(
+        deps.dependences(at(0, 4)),
+        Some(&writers(&[at(0, 2)], false))
+    );
+    // %l1: a parameter may alias either alloca, and isn't stored to
+    assert_eq!(
+        deps.dependences(at(0, 5)),
+        Some(&writers(&[at(0, 2), at(0, 3)], true))
+    );
+    // %l2: a store on each path
+    assert_eq!(
+        deps.dependences(at(2, 0)),
+        Some(&writers(&[at(0, 2), at(1, 0)], false))
+    );
+    // %l3: the atomicrmw doesn't overwrite %b, and the readnone and readonly
+    // calls are ignored
+    assert_eq!(
+        deps.dependences(at(2, 1)),
+        Some(&writers(&[at(0, 3), at(1, 1)], false))
+    );
+    // %l4: the call may write to %b
+    assert_eq!(
+        deps.dependences(at(2, 3)),
+        Some(&writers(&[at(0, 3), at(1, 1), at(2, 2)], false))
+    );
+    // %l5: the store to %param in the loop body may alias %a
+    assert_eq!(
+        deps.dependences(at(3, 0)),
+        Some(&writers(&[at(0, 2), at(1, 0), at(2, 2), at(3, 1)], false))
+    );
+    // not a load
+    assert_eq!(deps.dependences(at(0, 2)), None);
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn backward_slices() {
+    init_logging();
+    let path = llvm_bc_dir().join("memdeps.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("memdeps").unwrap();
+    let deps = MemoryDependences::new(&module, func);
+
+    // @memdeps is the second function; its blocks are entry, then, join,
+    // loop, exit
+    let at = |block: usize, index: usize| ModuleLoc::new(1, block).instr(index);
+    let ret = ModuleLoc::new(1, 4).terminator();
+
+    // the returned %l5 is loaded from the alloca %a
+    assert_eq!(backward_slice(func, ret, 0, None), vec![at(0, 0), at(3, 0)]);
+    // following %l5 to the stores (and call) which may have written %a, and to
+    // their operands: %b is passed to the call, and %l5 is stored to %param
+    // in the loop
+    assert_eq!(
+        backward_slice(func, ret, 0, Some(&deps)),
+        vec![
+            at(0, 0),
+            at(0, 1),
+            at(0, 2),
+            at(1, 0),
+            at(2, 2),
+            at(3, 0),
+            at(3, 1),
+        ]
+    );
+    // a store's operands are its address and then its value. %param isn't
+    // defined by an instruction, so its slice is empty.
+    assert!(backward_slice(func, at(3, 1), 0, Some(&deps)).is_empty());
+    assert_eq!(
+        backward_slice(func, at(3, 1), 1, None),
+        vec![at(0, 0), at(3, 0)]
+    );
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn size_metrics() {
+    init_logging();
+    let path = llvm_bc_dir().join("size_metrics.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("metrics").unwrap();
+    let cfg = ControlFlowGraph::new(func);
+    let domtree = DominatorTree::new(&cfg);
+    let loop_info = LoopInfo::new(&cfg, &domtree);
+    let metrics = func.size_metrics(&module, &loop_info);
+    assert_eq!(metrics.instruction_count, 13);
+    assert_eq!(metrics.call_sites, 2);
+    assert_eq!(metrics.max_loop_depth, 2);
+    // i8 at 0, i32 at 4, [3 x i64] at 16, { i8, i32 } at 40, x86_fp80 at 48
+    assert_eq!(metrics.stack_bytes, 64);
+    assert!(metrics.has_dynamic_allocas);
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn coroutine_info() {
+    init_logging();
+    let path = llvm_bc_dir().join("coroutines.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+
+    let func = module.get_func_by_name("counter").unwrap();
+    assert!(func.is_presplit_coroutine());
+    let coro = CoroutineInfo::new(func).expect("Should be a coroutine");
+    assert!(coro.is_presplit());
+    assert_eq!(coro.id().intrinsic, Intrinsic::CoroId);
+    assert_eq!(coro.id().block, &Name::from("entry"));
+    assert_eq!(coro.handle(), Some(&Name::from("hdl")));
+    let begin = coro.begin().expect("Should have a coro.begin");
+    assert_eq!((begin.block, begin.index), (&Name::from("begin"), 1));
+
+    let suspends = coro.suspend_points();
+    assert_eq!(suspends.len(), 2);
+    assert_eq!(suspends[0].call.block, &Name::from("loop"));
+    assert_eq!(suspends[0].call.intrinsic, Intrinsic::CoroSuspend);
+    let save = suspends[0].save.expect("Should have a coro.save");
+    assert_eq!(save.call.dest, Some(Name::from("save")));
+    assert!(!suspends[0].is_final);
+    assert_eq!(suspends[1].call.block, &Name::from("final"));
+    assert_eq!(suspends[1].save, None);
+    assert!(suspends[1].is_final);
+
+    assert_eq!(coro.ends().len(), 1);
+    assert_eq!(coro.ends()[0].block, &Name::from("suspended"));
+    assert_eq!(coro.frees().len(), 1);
+    // the frame is only allocated on the heap
+    assert_eq!(coro.frame_alloca(), None);
+
+    let func = module.get_func_by_name("stack_frame").unwrap();
+    let coro = CoroutineInfo::new(func).expect("Should be a coroutine");
+    assert!(coro.suspend_points().is_empty());
+    let alloca = coro.frame_alloca().expect("Should have a frame alloca");
+    assert_eq!(alloca.dest, Name::from("frame"));
+
+    let func = module.get_func_by_name("not_a_coroutine").unwrap();
+    assert!(!func.is_presplit_coroutine());
+    assert!(CoroutineInfo::new(func).is_none());
+
+    assert_eq!(
+        Intrinsic::from_name("llvm.coro.size.i64"),
+        Some(Intrinsic::CoroSize)
+    );
+    assert_eq!(
+        Intrinsic::from_name("llvm.coro.id.retcon.once"),
+        Some(Intrinsic::CoroIdRetconOnce)
+    );
+    assert!(Intrinsic::CoroEnd.is_coroutine());
+    assert!(!Intrinsic::VaStart.is_coroutine());
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn block_addresses() {
+    init_logging();
+    let path = llvm_bc_dir().join("indirectbr.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let labels = module.global_vars[0].initializer.as_ref().unwrap();
+    assert_eq!(
+        &format!("{}", labels),
+        "[ blockaddress(@dispatch, %one), blockaddress(@dispatch, %two) ]"
+    );
+    let addresses = BlockAddresses::new(&module);
+    assert_eq!(
+        addresses.address_taken_blocks("dispatch"),
+        &[&Name::from("one"), &Name::from("two")]
+    );
+    assert!(addresses.is_address_taken("dispatch", &Name::from("two")));
+    assert!(!addresses.is_address_taken("dispatch", &Name::from("entry")));
+    assert!(addresses.address_taken_blocks("nowhere").is_empty());
+    assert_eq!(addresses.functions().collect::<Vec<_>>(), vec!["dispatch"]);
+    assert!(addresses.inconsistencies().is_empty());
+
+    let path = llvm_bc_dir().join("blockaddress.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let addresses = BlockAddresses::new(&module);
+    // one address is taken by a global initializer, the other by an instruction
+    assert_eq!(
+        addresses.address_taken_blocks("mismatched"),
+        &[&Name::from("taken"), &Name::from(0)]
+    );
+    assert_eq!(
+        addresses.inconsistencies(),
+        &[
+            BlockAddressInconsistency::UntakenDestination {
+                function: "mismatched",
+                block: &Name::from("entry"),
+                dest: &Name::from("untaken"),
+            },
+            BlockAddressInconsistency::MissingDestination {
+                function: "mismatched",
+                block: &Name::from("entry"),
+                dest: &Name::from(0),
+            },
+        ]
+    );
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn reachability() {
+    init_logging();
+    let path = llvm_bc_dir().join("reachability.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let reachability = Reachability::new(&module);
+    let func_names = |funcs: Vec<&Function>| -> Vec<String> {
+        funcs.into_iter().map(|func| func.name.clone()).collect()
+    };
+    assert_eq!(
+        func_names(reachability.reachable_functions().collect()),
+        vec!["main", "helper", "aliased", "init"]
+    );
+    assert_eq!(
+        func_names(reachability.dead_functions().collect()),
+        vec!["dead", "dead_callee"]
+    );
+    let dead_vars: Vec<&Name> = reachability
+        .dead_global_vars()
+        .map(|var| &var.name)
+        .collect();
+    assert_eq!(
+        dead_vars,
+        vec![&Name::from("dead_table"), &Name::from("dead_counter")]
+    );
+    let dead_aliases: Vec<&Name> = reachability
+        .dead_global_aliases()
+        .map(|alias| &alias.name)
+        .collect();
+    assert_eq!(dead_aliases, vec![&Name::from("dead_alias")]);
+    // kept alive only by `llvm.used`
+    assert!(reachability.is_reachable(&Name::from("kept")));
+    // declared functions are reachable if something reachable calls them
+    assert!(reachability.is_reachable(&Name::from("external")));
+
+    // with only `main` as a root, the ctor, the alias, and `llvm.used` are dead
+    let reachability = Reachability::with_roots(&module, vec![Name::from("main")]);
+    assert_eq!(
+        func_names(reachability.reachable_functions().collect()),
+        vec!["main", "helper"]
+    );
+    assert!(reachability.is_reachable(&Name::from("table")));
+    assert!(!reachability.is_reachable(&Name::from("kept")));
+    assert!(!reachability.is_reachable(&Name::from("exported")));
+    assert_eq!(reachability.dead_global_aliases().count(), 2);
+}
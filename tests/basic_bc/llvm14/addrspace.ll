@@ -0,0 +1,12 @@
+; Pointers in non-default address spaces
+
+@shared = addrspace(3) global [4 x i32] zeroinitializer
+
+define i32 @f(i32 addrspace(1)* %p) {
+  %q = getelementptr i32, i32 addrspace(1)* %p, i64 1
+  %a = load i32, i32 addrspace(1)* %q
+  %b = load i32, i32 addrspace(3)* getelementptr ([4 x i32], [4 x i32] addrspace(3)* @shared, i64 0, i64 2)
+  %c = icmp eq i32 addrspace(1)* %p, null
+  %sum = add i32 %a, %b
+  ret i32 %sum
+}
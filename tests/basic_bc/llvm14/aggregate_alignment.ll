@@ -0,0 +1,11 @@
+; Named structs under a data layout with a non-default aggregate alignment
+
+target datalayout = "e-a:64:64"
+
+%S = type { i8 }
+%T = type { i8, %S }
+%P = type <{ i8, i16 }>
+
+@s = external global %S
+@t = external global %T
+@p = external global %P
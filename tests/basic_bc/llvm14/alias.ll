@@ -0,0 +1,34 @@
+; Pointers for testing alias analysis
+
+%struct.S = type { i32, [4 x i32] }
+
+@g = global %struct.S zeroinitializer
+@h = global i32 0
+@ha = alias i32, i32* @h
+
+define void @pointers(i32* %param, i32** %pp) {
+  %a = alloca %struct.S
+  %b = alloca i32
+  %a_0 = getelementptr %struct.S, %struct.S* %a, i64 0, i32 0
+  %a_1_2 = getelementptr %struct.S, %struct.S* %a, i64 0, i32 1, i64 2
+  %a_1_3 = getelementptr %struct.S, %struct.S* %a, i64 0, i32 1, i64 3
+  %a_1 = getelementptr %struct.S, %struct.S* %a, i64 0, i32 1
+  %a_1_x = getelementptr [4 x i32], [4 x i32]* %a_1, i64 0, i64 2
+  %a_cast = bitcast %struct.S* %a to i32*
+  %a_1_oob = getelementptr %struct.S, %struct.S* %a, i64 0, i32 1, i64 7
+  %loaded = load i32*, i32** %pp
+  %l0 = load i32, i32* %a_0
+  %l1 = load i32, i32* %a_1_2
+  %l2 = load i32, i32* %a_1_3
+  %l3 = load i32, i32* %a_1_x
+  %l4 = load i32, i32* %a_cast
+  %l5 = load i32, i32* %b
+  %l6 = load i32, i32* %param
+  %l7 = load i32, i32* %loaded
+  %l8 = load i32, i32* getelementptr (%struct.S, %struct.S* @g, i64 0, i32 1, i64 2)
+  %l9 = load i32, i32* getelementptr (%struct.S, %struct.S* @g, i64 0, i32 0)
+  %l10 = load i32, i32* @h
+  %l11 = load i32, i32* @ha
+  %l12 = load i32, i32* %a_1_oob
+  ret void
+}
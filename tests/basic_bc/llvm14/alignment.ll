@@ -0,0 +1,16 @@
+; Explicit and atomic alignments
+
+target datalayout = "e-m:e-i64:64-f80:128-n8:16:32:64-S128"
+
+@g = global i64 0, align 16
+
+define void @f(i64* %p) {
+entry:
+  %a = alloca i64, align 32
+  %x = load i64, i64* %p, align 4
+  %y = load atomic i64, i64* %p seq_cst, align 8
+  %z = load atomic i64, i64* %p acquire, align 4
+  store atomic i64 %x, i64* %p release, align 8
+  store i64 %y, i64* %a, align 1
+  ret void
+}
@@ -0,0 +1,4 @@
+define i32 @first(i32 %x) {
+  %y = add i32 %x, 1
+  ret i32 %y
+}
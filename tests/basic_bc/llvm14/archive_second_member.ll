@@ -0,0 +1,6 @@
+declare i32 @first(i32)
+
+define i32 @second(i32 %x) {
+  %y = call i32 @first(i32 %x)
+  ret i32 %y
+}
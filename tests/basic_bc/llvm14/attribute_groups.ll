@@ -0,0 +1,20 @@
+; Attribute groups are numbered in order of first appearance: function
+; attributes of each function (defined or declared), then call sites
+
+define void @a() nounwind {
+  call void @c() cold
+  ret void
+}
+
+declare void @c() noinline
+
+define void @b() nounwind {
+  call void @c() nounwind
+  call void @c() cold
+  ret void
+}
+
+define void @d() {
+  call void @c() minsize
+  ret void
+}
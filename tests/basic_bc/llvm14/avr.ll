@@ -0,0 +1,25 @@
+target datalayout = "e-P1-p:16:8-i8:8-i16:8-i32:8-i64:8-f32:8-f64:8-n8-a:8"
+target triple = "avr"
+
+@counter = addrspace(1) global i16 0
+@table = addrspace(1) constant [2 x void (i8) addrspace(1)*] [void (i8) addrspace(1)* @isr, void (i8) addrspace(1)* @plain]
+
+define avr_intrcc void @isr(i8 %x) addrspace(1) {
+  ret void
+}
+
+define void @plain(i8 %x) addrspace(1) {
+  ret void
+}
+
+define cc 99 void @numbered() addrspace(1) {
+  ret void
+}
+
+define void @caller(void (i8) addrspace(1)* %fp) addrspace(1) {
+  call avr_intrcc addrspace(1) void @isr(i8 1)
+  call cc 99 addrspace(1) void @numbered()
+  call addrspace(1) void %fp(i8 2)
+  %v = load i16, i16 addrspace(1)* @counter
+  ret void
+}
@@ -0,0 +1,21 @@
+; blockaddress constants which disagree with the indirectbr destinations
+
+@targets = internal constant [1 x i8*] [i8* blockaddress(@mismatched, %0)]
+
+define i32 @mismatched(i8* %target) {
+entry:
+  indirectbr i8* %target, [label %taken, label %untaken]
+
+taken:
+  ret i32 1
+
+untaken:
+  ret i32 2
+
+0:
+  ret i32 3
+}
+
+define i8* @in_instruction() {
+  ret i8* blockaddress(@mismatched, %taken)
+}
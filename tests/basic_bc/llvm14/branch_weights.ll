@@ -0,0 +1,27 @@
+; Branches with !prof branch weights
+
+define i32 @weighted(i32 %x, i1 %c) {
+entry:
+  br i1 %c, label %sw, label %plain, !prof !0
+
+sw:
+  switch i32 %x, label %default [
+    i32 0, label %zero
+    i32 1, label %one
+  ], !prof !1
+
+plain:
+  br i1 %c, label %zero, label %one
+
+zero:
+  ret i32 0
+
+one:
+  ret i32 1
+
+default:
+  ret i32 2
+}
+
+!0 = !{!"branch_weights", i32 2000, i32 1}
+!1 = !{!"branch_weights", i32 5, i32 10, i32 20}
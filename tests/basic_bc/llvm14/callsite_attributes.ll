@@ -0,0 +1,27 @@
+; Attributes on the arguments and return values of call sites
+
+%struct.T = type { i32, i64 }
+
+declare void @takes_byval(%struct.T*, i32)
+declare void @returns_sret(%struct.T*, i32)
+declare i32 @__gxx_personality_v0(...)
+declare i32 @printf(i8*, ...)
+
+define zeroext i8 @narrow(i8 %x) {
+  ret i8 %x
+}
+
+define void @caller(%struct.T* %p) personality i32 (...)* @__gxx_personality_v0 {
+  call void @takes_byval(%struct.T* byval(%struct.T) align 8 %p, i32 signext 3)
+  %r = call zeroext i8 @narrow(i8 noundef 7)
+  %n = call i32 (i8*, ...) @printf(i8* null, i32 1, i64 2)
+  invoke void @returns_sret(%struct.T* noalias sret(%struct.T) %p, i32 5)
+          to label %cont unwind label %lpad
+
+cont:
+  ret void
+
+lpad:
+  %lp = landingpad { i8*, i32 } cleanup
+  resume { i8*, i32 } %lp
+}
@@ -0,0 +1,16 @@
+; ModuleID = 'checked_arithmetic.ll'
+source_filename = "checked_arithmetic.ll"
+
+define i32 @checked(i32 %a, i32 %b, i64 %x, <4 x i16> %v) {
+  %sum = call { i32, i1 } @llvm.sadd.with.overflow.i32(i32 %a, i32 %b)
+  %sum.value = extractvalue { i32, i1 } %sum, 0
+  %diffs = call { <4 x i16>, <4 x i1> } @llvm.usub.with.overflow.v4i16(<4 x i16> %v, <4 x i16> %v)
+  %ctpop = call i32 @llvm.ctpop.i32(i32 %a)
+  %product = call { i64, i1 } @llvm.umul.with.overflow.i64(i64 %x, i64 3)
+  ret i32 %sum.value
+}
+
+declare { i32, i1 } @llvm.sadd.with.overflow.i32(i32, i32)
+declare { <4 x i16>, <4 x i1> } @llvm.usub.with.overflow.v4i16(<4 x i16>, <4 x i16>)
+declare { i64, i1 } @llvm.umul.with.overflow.i64(i64, i64)
+declare i32 @llvm.ctpop.i32(i32)
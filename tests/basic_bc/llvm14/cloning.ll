@@ -0,0 +1,26 @@
+; ModuleID = 'cloning.ll'
+source_filename = "cloning.ll"
+
+@handlers = global [1 x i32 (i32)*] [i32 (i32)* @fact]
+
+define i32 @fact(i32 %n) {
+entry:
+  %done = icmp sle i32 %n, 1
+  br i1 %done, label %base, label %rec
+
+base:
+  ret i32 1
+
+rec:
+  %m = sub i32 %n, 1
+  %r = call i32 @fact(i32 %m)
+  %p = mul i32 %n, %r
+  ret i32 %p
+}
+
+define i32 @main() {
+  %a = call i32 @fact(i32 5)
+  %b = call i32 @fact(i32 6)
+  %s = add i32 %a, %b
+  ret i32 %s
+}
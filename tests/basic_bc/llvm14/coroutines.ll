@@ -0,0 +1,75 @@
+; ModuleID = 'coroutines.cpp'
+source_filename = "coroutines.cpp"
+target datalayout = "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-f80:128-n8:16:32:64-S128"
+target triple = "x86_64-pc-linux-gnu"
+
+declare token @llvm.coro.id(i32, i8*, i8*, i8*)
+declare i1 @llvm.coro.alloc(token)
+declare i64 @llvm.coro.size.i64()
+declare i8* @llvm.coro.begin(token, i8*)
+declare token @llvm.coro.save(i8*)
+declare i8 @llvm.coro.suspend(token, i1)
+declare i8* @llvm.coro.free(token, i8*)
+declare i1 @llvm.coro.end(i8*, i1)
+declare void @print(i32)
+declare noalias i8* @malloc(i64)
+declare void @free(i8*)
+
+; a coroutine whose frame is allocated on the heap, unless the allocation is
+; elided
+define i8* @counter(i32 %n) "coroutine.presplit"="0" {
+entry:
+  %id = call token @llvm.coro.id(i32 0, i8* null, i8* null, i8* null)
+  %need.alloc = call i1 @llvm.coro.alloc(token %id)
+  br i1 %need.alloc, label %dyn.alloc, label %begin
+
+dyn.alloc:
+  %size = call i64 @llvm.coro.size.i64()
+  %alloc = call i8* @malloc(i64 %size)
+  br label %begin
+
+begin:
+  %mem = phi i8* [ null, %entry ], [ %alloc, %dyn.alloc ]
+  %hdl = call noalias i8* @llvm.coro.begin(token %id, i8* %mem)
+  br label %loop
+
+loop:
+  %n.val = phi i32 [ %n, %begin ], [ %inc, %loop.resume ]
+  call void @print(i32 %n.val)
+  %save = call token @llvm.coro.save(i8* %hdl)
+  %suspend = call i8 @llvm.coro.suspend(token %save, i1 false)
+  switch i8 %suspend, label %suspended [ i8 0, label %loop.resume
+                                         i8 1, label %cleanup ]
+
+loop.resume:
+  %inc = add i32 %n.val, 1
+  br label %loop
+
+final:
+  %final.suspend = call i8 @llvm.coro.suspend(token none, i1 true)
+  switch i8 %final.suspend, label %suspended [ i8 1, label %cleanup ]
+
+cleanup:
+  %free.mem = call i8* @llvm.coro.free(token %id, i8* %hdl)
+  call void @free(i8* %free.mem)
+  br label %suspended
+
+suspended:
+  %unused = call i1 @llvm.coro.end(i8* %hdl, i1 false)
+  ret i8* %hdl
+}
+
+; a coroutine whose frame is on the stack
+define void @stack_frame() "coroutine.presplit"="1" {
+entry:
+  %frame = alloca [64 x i8], align 8
+  %id = call token @llvm.coro.id(i32 0, i8* null, i8* null, i8* null)
+  %frame.ptr = bitcast [64 x i8]* %frame to i8*
+  %hdl = call noalias i8* @llvm.coro.begin(token %id, i8* %frame.ptr)
+  %unused = call i1 @llvm.coro.end(i8* %hdl, i1 false)
+  ret void
+}
+
+define void @not_a_coroutine() {
+  ret void
+}
@@ -0,0 +1,26 @@
+; global constructors and destructors, with and without associated data
+$init_a = comdat any
+
+@counter = global i32 0
+@data = global i32 0, comdat($init_a)
+@llvm.global_ctors = appending global [3 x { i32, void ()*, i8* }] [{ i32, void ()*, i8* } { i32 65535, void ()* @init_a, i8* bitcast (i32* @data to i8*) }, { i32, void ()*, i8* } { i32 101, void ()* @init_b, i8* null }, { i32, void ()*, i8* } { i32 200, void ()* bitcast (i32 ()* @init_c to void ()*), i8* null }]
+@llvm.global_dtors = appending global [1 x { i32, void ()*, i8* }] [{ i32, void ()*, i8* } { i32 65535, void ()* @fini, i8* null }]
+
+define void @init_a() comdat {
+  store i32 1, i32* @counter
+  ret void
+}
+
+define void @init_b() {
+  store i32 2, i32* @counter
+  ret void
+}
+
+define i32 @init_c() {
+  ret i32 3
+}
+
+define void @fini() {
+  store i32 0, i32* @counter
+  ret void
+}
@@ -0,0 +1,35 @@
+; ModuleID = 'dce.ll'
+source_filename = "dce.ll"
+
+define i32 @dce(i32* %p, i32 %x) !dbg !4 {
+  %1 = add i32 %x, 1
+  %2 = mul i32 %1, 2
+  %3 = load i32, i32* %p
+  %4 = load volatile i32, i32* %p
+  store i32 %x, i32* %p
+  %5 = call i32 @pure(i32 %x) #0
+  %6 = call i32 @pure(i32 %x)
+  %7 = call i32 @llvm.ctpop.i32(i32 %x)
+  %8 = sub i32 %x, 3
+  call void @llvm.dbg.value(metadata i32 %8, metadata !6, metadata !DIExpression()), !dbg !8
+  %9 = udiv i32 %x, 7
+  ret i32 %9
+}
+
+declare i32 @pure(i32)
+declare i32 @llvm.ctpop.i32(i32)
+declare void @llvm.dbg.value(metadata, metadata, metadata)
+
+attributes #0 = { nounwind readnone willreturn }
+
+!llvm.dbg.cu = !{!0}
+!llvm.module.flags = !{!3}
+
+!0 = distinct !DICompileUnit(language: DW_LANG_C99, file: !1, emissionKind: FullDebug)
+!1 = !DIFile(filename: "dce.c", directory: "/")
+!3 = !{i32 2, !"Debug Info Version", i32 3}
+!4 = distinct !DISubprogram(name: "dce", scope: !1, file: !1, line: 1, type: !5, unit: !0, spFlags: DISPFlagDefinition)
+!5 = !DISubroutineType(types: !{})
+!6 = !DILocalVariable(name: "y", scope: !4, file: !1, line: 2, type: !7)
+!7 = !DIBasicType(name: "int", size: 32, encoding: DW_ATE_signed)
+!8 = !DILocation(line: 2, scope: !4)
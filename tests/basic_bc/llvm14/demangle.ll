@@ -0,0 +1,18 @@
+; Functions with mangled Rust and C++ names
+
+define void @_ZN4core3ptr13drop_in_place17h0123456789abcdefE() {
+  ret void
+}
+
+define i32 @_Z3addii(i32 %a, i32 %b) {
+  %sum = add i32 %a, %b
+  ret i32 %sum
+}
+
+define void @_ZN2ns3fooEv() {
+  ret void
+}
+
+define i32 @main() {
+  ret i32 0
+}
@@ -0,0 +1,29 @@
+; The "new" side of the `module_diff` test; compare diff_old.ll
+source_filename = "diff.c"
+
+@unchanged = global i32 0
+@changed_init = global i32 3
+@added_global = global i32 4
+
+define i32 @same(i32 %0) {
+  %2 = add i32 %0, 1
+  ret i32 %2
+}
+
+define i32 @changed(i32 %x) {
+entry:
+  %c = icmp sgt i32 %x, 0
+  br i1 %c, label %pos, label %exit
+
+pos:
+  %a = shl i32 %x, 1
+  br label %exit
+
+exit:
+  %r = phi i32 [ %a, %pos ], [ 0, %entry ]
+  ret i32 %r
+}
+
+define void @added() {
+  ret void
+}
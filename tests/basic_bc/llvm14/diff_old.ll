@@ -0,0 +1,32 @@
+; The "old" side of the `module_diff` test; compare diff_new.ll
+source_filename = "diff.c"
+
+@unchanged = global i32 0
+@changed_init = global i32 1
+@removed_global = global i32 2
+
+define i32 @same(i32 %0) {
+  %2 = add i32 %0, 1
+  ret i32 %2
+}
+
+define i32 @changed(i32 %x) {
+entry:
+  %c = icmp sgt i32 %x, 0
+  br i1 %c, label %pos, label %neg
+
+pos:
+  %a = mul i32 %x, 2
+  br label %exit
+
+neg:
+  br label %exit
+
+exit:
+  %r = phi i32 [ %a, %pos ], [ 0, %neg ]
+  ret i32 %r
+}
+
+define void @removed() {
+  ret void
+}
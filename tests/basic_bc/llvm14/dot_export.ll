@@ -0,0 +1,30 @@
+; functions whose names only differ in characters which aren't safe in
+; filenames, or in case, and an `asm goto` with an indirect destination
+
+define i32 @"a?b"() {
+  ret i32 0
+}
+
+define i32 @"a!b"() {
+  ret i32 1
+}
+
+define i32 @"A?b"() {
+  ret i32 2
+}
+
+define i32 @"a_b.1"() {
+  ret i32 3
+}
+
+define i32 @asm_goto(i32 %x) {
+entry:
+  callbr void asm "", "r,X"(i32 %x, i8* blockaddress(@asm_goto, %fail))
+          to label %normal [label %fail]
+
+normal:
+  ret i32 1
+
+fail:
+  ret i32 0
+}
@@ -0,0 +1,19 @@
+; ModuleID = 'fixed_point.ll'
+source_filename = "fixed_point.ll"
+
+define i32 @dsp(i8 %a, i8 %b, i32 %x, i32 %y, i16 %p, i16 %q, <4 x i32> %v) {
+  %sum = call i8 @llvm.uadd.sat.i8(i8 %a, i8 %b)
+  %shifted = call <4 x i32> @llvm.sshl.sat.v4i32(<4 x i32> %v, <4 x i32> %v)
+  %prod = call i32 @llvm.smul.fix.i32(i32 %x, i32 %y, i32 16)
+  %prod.sat = call i32 @llvm.smul.fix.sat.i32(i32 %x, i32 %y, i32 31)
+  %quot = call i16 @llvm.udiv.fix.i16(i16 %p, i16 %q, i32 8)
+  %sum.wrapped = call { i8, i1 } @llvm.uadd.with.overflow.i8(i8 %a, i8 %b)
+  ret i32 %prod.sat
+}
+
+declare i8 @llvm.uadd.sat.i8(i8, i8)
+declare <4 x i32> @llvm.sshl.sat.v4i32(<4 x i32>, <4 x i32>)
+declare i32 @llvm.smul.fix.i32(i32, i32, i32 immarg)
+declare i32 @llvm.smul.fix.sat.i32(i32, i32, i32 immarg)
+declare i16 @llvm.udiv.fix.i16(i16, i16, i32 immarg)
+declare { i8, i1 } @llvm.uadd.with.overflow.i8(i8, i8)
@@ -0,0 +1,5 @@
+; A constant `fneg` expression, which can't be folded because its operand
+; depends on the address of a global
+
+@x = global i32 0
+@y = global float fneg (float bitcast (i32 ptrtoint (i32* @x to i32) to float))
@@ -0,0 +1,40 @@
+; Windows-style (funclet-based) exception handling
+
+target triple = "x86_64-pc-windows-msvc"
+
+declare void @may_throw()
+
+declare i32 @__CxxFrameHandler3(...)
+
+define void @f() personality i32 (...)* @__CxxFrameHandler3 {
+entry:
+  invoke void @may_throw()
+          to label %done unwind label %dispatch
+
+dispatch:
+  %cs = catchswitch within none [label %handler1, label %handler2] unwind label %cleanup
+
+handler1:
+  %cp1 = catchpad within %cs [i8* null, i32 64, i8* null]
+  catchret from %cp1 to label %done
+
+handler2:
+  %cp2 = catchpad within %cs []
+  invoke void @may_throw() [ "funclet"(token %cp2) ]
+          to label %handler2.ok unwind label %inner
+
+handler2.ok:
+  catchret from %cp2 to label %done
+
+inner:
+  %inner.pad = cleanuppad within %cp2 []
+  cleanupret from %inner.pad unwind label %cleanup
+
+cleanup:
+  %cleanup.pad = cleanuppad within none []
+  call void @may_throw() [ "funclet"(token %cleanup.pad) ]
+  cleanupret from %cleanup.pad unwind to caller
+
+done:
+  ret void
+}
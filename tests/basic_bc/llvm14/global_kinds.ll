@@ -0,0 +1,21 @@
+; References to each kind of global value
+
+@var = global i32 0
+@alias = alias i32, i32* @var
+@ifunc = ifunc void (), void ()* ()* @resolver
+
+define void ()* @resolver() {
+  ret void ()* @impl
+}
+
+define void @impl() {
+  ret void
+}
+
+define void @uses() {
+  store i32 1, i32* @var
+  store i32 2, i32* @alias
+  call void @ifunc()
+  call void @impl()
+  ret void
+}
@@ -0,0 +1,21 @@
+; computed goto, as produced by e.g. `goto *labels[i];` in C
+
+@labels = internal constant [2 x i8*] [i8* blockaddress(@dispatch, %one), i8* blockaddress(@dispatch, %two)]
+
+define i32 @dispatch(i64 %i) {
+entry:
+  %slot = getelementptr inbounds [2 x i8*], [2 x i8*]* @labels, i64 0, i64 %i
+  %target = load i8*, i8** %slot
+  indirectbr i8* %target, [label %one, label %two]
+
+one:
+  ret i32 1
+
+two:
+  ret i32 2
+}
+
+define void @nowhere(i8* %target) {
+entry:
+  indirectbr i8* %target, []
+}
@@ -0,0 +1,43 @@
+; ModuleID = 'loop_metadata.c'
+source_filename = "loop_metadata.c"
+target datalayout = "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-f80:128-n8:16:32:64-S128"
+target triple = "x86_64-pc-linux-gnu"
+
+define void @hinted(i32* %p, i32 %n) {
+entry:
+  br label %loop
+
+loop:
+  %i = phi i32 [ 0, %entry ], [ %next, %latch ]
+  %addr = getelementptr inbounds i32, i32* %p, i32 %i
+  store i32 %i, i32* %addr, align 4, !llvm.access.group !5
+  %next = add nsw i32 %i, 1
+  %done = icmp eq i32 %next, %n
+  br i1 %done, label %exit, label %latch
+
+latch:
+  br label %loop, !llvm.loop !0
+
+exit:
+  br label %inner
+
+inner:
+  %j = phi i32 [ 0, %exit ], [ %nextj, %inner ]
+  %nextj = add nsw i32 %j, 1
+  %donej = icmp eq i32 %nextj, %n
+  br i1 %donej, label %end, label %inner, !llvm.loop !6
+
+end:
+  ret void
+}
+
+!0 = distinct !{!0, !1, !2, !3, !4, !8}
+!1 = !{!"llvm.loop.mustprogress"}
+!2 = !{!"llvm.loop.unroll.count", i32 4}
+!3 = !{!"llvm.loop.vectorize.enable", i1 true}
+!4 = !{!"llvm.loop.parallel_accesses", !5}
+!5 = distinct !{}
+!6 = distinct !{!6, !7, !9}
+!7 = !{!"llvm.loop.unroll.disable"}
+!8 = !{!"my.custom.hint", i32 3}
+!9 = !{!"llvm.loop.vectorize.width", !"not a number"}
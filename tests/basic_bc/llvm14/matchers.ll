@@ -0,0 +1,21 @@
+; ModuleID = 'matchers.ll'
+source_filename = "matchers.ll"
+
+%struct.S = type { i32, [4 x i32] }
+
+@g = global %struct.S zeroinitializer
+@h = global i32 0
+@p = global i32* getelementptr inbounds (%struct.S, %struct.S* @g, i64 0, i32 1, i64 2)
+@q = global i64 add (i64 ptrtoint (i32* @h to i64), i64 1)
+@r = global i1 icmp ult (i64 ptrtoint (i32* @h to i64), i64 ptrtoint (%struct.S* @g to i64))
+
+define i32 @f(i32 %x, i32 %y) {
+  %a = add i32 %x, 0
+  %b = mul i32 %a, %y
+  %c = icmp slt i32 %b, 10
+  %d = select i1 %c, i32 %a, i32 %y
+  %e = getelementptr inbounds %struct.S, %struct.S* @g, i64 0, i32 1, i64 2
+  %f = load i32, i32* %e
+  %g = zext i1 %c to i32
+  ret i32 %d
+}
@@ -0,0 +1,41 @@
+; Loads and the stores, atomics, and calls which may write the memory they read
+
+declare void @opaque(i32*)
+
+define i32 @pure(i32 %x) readnone {
+  %y = add i32 %x, 1
+  ret i32 %y
+}
+
+define i32 @memdeps(i32* %param, i1 %cond) {
+entry:
+  %a = alloca i32
+  %b = alloca i32
+  store i32 1, i32* %a
+  store i32 2, i32* %b
+  %l0 = load i32, i32* %a
+  %l1 = load i32, i32* %param
+  br i1 %cond, label %then, label %join
+
+then:
+  store i32 3, i32* %a
+  %old = atomicrmw add i32* %b, i32 1 seq_cst
+  %p = call i32 @pure(i32 %old)
+  call void @opaque(i32* %b) readonly
+  br label %join
+
+join:
+  %l2 = load i32, i32* %a
+  %l3 = load i32, i32* %b
+  call void @opaque(i32* %b)
+  %l4 = load i32, i32* %b
+  br label %loop
+
+loop:
+  %l5 = load i32, i32* %a
+  store i32 %l5, i32* %param
+  br i1 %cond, label %loop, label %exit
+
+exit:
+  ret i32 %l5
+}
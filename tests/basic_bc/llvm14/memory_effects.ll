@@ -0,0 +1,23 @@
+; functions with various memory attributes
+define i32 @pure(i32 %x) readnone {
+  ret i32 %x
+}
+
+define i32 @reader(i32* %p) readonly argmemonly {
+  %v = load i32, i32* %p
+  ret i32 %v
+}
+
+define void @writer(i32* %p) writeonly argmemonly {
+  store i32 0, i32* %p
+  ret void
+}
+
+define void @hidden() inaccessiblememonly {
+  ret void
+}
+
+define void @unknown(i32* %p) {
+  store i32 1, i32* %p
+  ret void
+}
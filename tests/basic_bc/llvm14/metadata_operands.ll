@@ -0,0 +1,25 @@
+; ModuleID = 'metadata_operands.ll'
+source_filename = "metadata_operands.ll"
+
+define i64 @read_sp() {
+  %1 = call i64 @llvm.read_register.i64(metadata !0)
+  ret i64 %1
+}
+
+define double @constrained(double %a, double %b) #0 {
+  %1 = call double @llvm.experimental.constrained.fadd.f64(double %a, double %b, metadata !"round.dynamic", metadata !"fpexcept.strict") #0
+  ret double %1
+}
+
+define i64 @self_referential() {
+  %1 = call i64 @llvm.read_register.i64(metadata !1)
+  ret i64 %1
+}
+
+declare i64 @llvm.read_register.i64(metadata)
+declare double @llvm.experimental.constrained.fadd.f64(double, double, metadata, metadata)
+
+attributes #0 = { strictfp }
+
+!0 = !{!"sp"}
+!1 = distinct !{!1, i32 7, null}
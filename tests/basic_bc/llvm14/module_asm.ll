@@ -0,0 +1,8 @@
+; ModuleID = 'module_asm.ll'
+source_filename = "module_asm.ll"
+
+module asm ".globl asm_func"
+module asm "asm_func:"
+module asm "  ret"
+
+declare void @asm_func()
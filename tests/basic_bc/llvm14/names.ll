@@ -0,0 +1,14 @@
+; Values with names which need quoting
+
+@"\FF\FEglobal" = global i32 0
+@0 = global i32 1
+
+define i32 @names(i32 %"a b") {
+"entry block":
+  %"x\01y" = add i32 %"a b", 1
+  %"q\22" = load i32, i32* @"\FF\FEglobal"
+  %"1st" = load i32, i32* @0
+  %r = add i32 %"x\01y", %"q\22"
+  %s = add i32 %r, %"1st"
+  ret i32 %s
+}
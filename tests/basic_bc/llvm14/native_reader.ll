@@ -0,0 +1,117 @@
+; A module without attributes or metadata, for comparing the native bitcode
+; reader against LLVM's
+source_filename = "native_reader.c"
+target datalayout = "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-f80:128-n8:16:32:64-S128"
+target triple = "x86_64-pc-linux-gnu"
+
+%struct.point = type { i32, i32 }
+%struct.list = type { i64, %struct.list* }
+%struct.packed = type <{ i8, i32 }>
+
+@counter = dso_local global i32 0, align 4
+@origin = internal constant %struct.point { i32 3, i32 -4 }, align 4
+@greeting = private unnamed_addr constant [6 x i8] c"hello\00", align 1
+@table = dso_local global [4 x i16] [i16 1, i16 2, i16 3, i16 65535], align 2
+@values = dso_local global <2 x float> <float 1.5, float -2.0>, align 8
+@head = dso_local global %struct.list zeroinitializer, align 8
+@tail_ptr = dso_local global %struct.list** getelementptr inbounds (%struct.list, %struct.list* @head, i32 0, i32 1), align 8
+@origin_y = dso_local global i32* getelementptr (%struct.point, %struct.point* @origin, i64 0, i32 1), align 8
+@addr = dso_local global i64 ptrtoint (i32* @counter to i64), align 8
+@big = dso_local global i128 -170141183460469231731687303715884105728, align 16
+@pi = dso_local constant double 3.141590e+00, align 8
+@tls = thread_local global i8 7, section ".tdata.custom"
+@packed = weak global %struct.packed <{ i8 1, i32 2 }>
+@ext = external global i32
+@mystery = dso_local global i1 icmp eq (i32* @counter, i32* @ext)
+@counter_alias = alias i32, i32* @counter
+
+declare i32 @printf(i8*, ...)
+
+declare void @llvm.memset.p0i8.i64(i8*, i8, i64, i1)
+
+define dso_local i32 @sum(i32* %arr, i32 %n) {
+entry:
+  %cmp = icmp sgt i32 %n, 0
+  br i1 %cmp, label %loop, label %exit
+
+loop:
+  %i = phi i32 [ 0, %entry ], [ %next, %loop ]
+  %acc = phi i32 [ 0, %entry ], [ %acc.next, %loop ]
+  %idx = sext i32 %i to i64
+  %p = getelementptr inbounds i32, i32* %arr, i64 %idx
+  %v = load i32, i32* %p, align 4
+  %acc.next = add nsw i32 %acc, %v
+  %next = add nuw nsw i32 %i, 1
+  %done = icmp eq i32 %next, %n
+  br i1 %done, label %exit, label %loop
+
+exit:
+  %result = phi i32 [ 0, %entry ], [ %acc.next, %loop ]
+  ret i32 %result
+}
+
+define i32 @classify(i32 %x) {
+  switch i32 %x, label %2 [
+    i32 0, label %1
+    i32 7, label %3
+  ]
+
+1:
+  br label %4
+
+2:
+  br label %4
+
+3:
+  unreachable
+
+4:
+  %5 = phi i32 [ 10, %1 ], [ 20, %2 ]
+  %6 = call i32 (i8*, ...) @printf(i8* getelementptr inbounds ([6 x i8], [6 x i8]* @greeting, i64 0, i64 0), i32 %5)
+  ret i32 %5
+}
+
+define internal double @floats(double %a, float %b) {
+  %ext = fpext float %b to double
+  %sum = fadd double %a, %ext
+  %neg = fneg double %sum
+  %lt = fcmp olt double %neg, 0.000000e+00
+  %sel = select i1 %lt, double %sum, double %neg
+  %pi = load double, double* @pi
+  %prod = fmul double %sel, %pi
+  ret double %prod
+}
+
+define %struct.point @points(%struct.point %p, <4 x i32> %v) {
+  %pt = alloca %struct.point, align 4
+  %buf = alloca i8, i32 16, align 16
+  call void @llvm.memset.p0i8.i64(i8* %buf, i8 0, i64 16, i1 false)
+  %x = extractvalue %struct.point %p, 0
+  %y.ptr = getelementptr inbounds %struct.point, %struct.point* %pt, i32 0, i32 1
+  store volatile i32 %x, i32* %y.ptr, align 4
+  %q = insertvalue %struct.point %p, i32 5, 1
+  %w = add <4 x i32> %v, <i32 1, i32 2, i32 3, i32 4>
+  %eq = icmp eq <4 x i32> %w, zeroinitializer
+  %f = freeze i32 %x
+  %t = trunc i32 %f to i8
+  %u = udiv i32 %f, 3
+  %z = zext i8 %t to i32
+  %c = bitcast %struct.point* %pt to i8*
+  %pi = ptrtoint i8* %c to i64
+  %s = shl i64 %pi, 2
+  ret %struct.point %q
+}
+
+define void @recurse(%struct.list* %l) {
+  %1 = getelementptr %struct.list, %struct.list* %l, i64 0, i32 1
+  %2 = load %struct.list*, %struct.list** %1
+  %3 = icmp eq %struct.list* %2, null
+  br i1 %3, label %done, label %more
+
+more:
+  tail call void @recurse(%struct.list* %2)
+  br label %done
+
+done:
+  ret void
+}
@@ -0,0 +1,25 @@
+; ModuleID = 'opaque_pointers.ll'
+source_filename = "opaque_pointers.ll"
+
+%pair = type { i32, i64 }
+
+@g = global %pair zeroinitializer
+
+define i64 @second(ptr %p, i32 %i) {
+  %arr = alloca [4 x %pair]
+  %elem = getelementptr inbounds [4 x %pair], ptr %arr, i64 0, i32 %i, i32 1
+  %field = getelementptr inbounds %pair, ptr %p, i64 0, i32 1
+  %old = atomicrmw add ptr %field, i64 1 seq_cst
+  store i64 %old, ptr %elem
+  %global = getelementptr %pair, ptr @g, i64 1
+  store ptr %global, ptr %p
+  ret i64 %old
+}
+
+define i64 @caller(ptr %p) {
+  %field = load ptr, ptr %p
+  %value = load i64, ptr %field
+  %result = call i64 @second(ptr getelementptr (%pair, ptr @g, i64 0, i32 1), i32 0)
+  %sum = add i64 %value, %result
+  ret i64 %sum
+}
@@ -0,0 +1,23 @@
+; Calls and invokes with operand bundles
+
+declare void @f(i32)
+
+declare i32 @__gxx_personality_v0(...)
+
+define void @g(i32 %x) personality i32 (...)* @__gxx_personality_v0 {
+entry:
+  call void @f(i32 %x) [ "deopt"(i32 %x, i32 7), "gc-transition"() ]
+  call void @f(i32 1) [ "my, \22tag\22"(<2 x i32> <i32 1, i32 2>, [2 x i8] c"(]") ]
+  call void @f(i32 2)
+  call void @f(i32 3) [ "gc-transition"() ]
+  invoke void @f(i32 %x) [ "deopt"(i32 3) ]
+          to label %ok unwind label %bad
+
+ok:
+  ret void
+
+bad:
+  %lp = landingpad { i8*, i32 }
+          cleanup
+  resume { i8*, i32 } %lp
+}
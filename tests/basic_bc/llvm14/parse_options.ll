@@ -0,0 +1,20 @@
+; Functions and constant arrays for testing `ParseOptions`
+
+@small = global [2 x i32] [i32 1, i32 2]
+@large = global [6 x i32] [i32 1, i32 2, i32 3, i32 4, i32 5, i32 6]
+@large_ptrs = global [5 x i32*] [i32* null, i32* null, i32* null, i32* null, i32* getelementptr ([2 x i32], [2 x i32]* @small, i32 0, i32 1)]
+
+define i32 @keep(i32 %x) {
+entry:
+  %y = add i32 %x, 1
+  ret i32 %y
+}
+
+define i32 @skip_me(i32 %x) {
+entry:
+  %y = mul i32 %x, 2
+  br label %exit
+
+exit:
+  ret i32 %y
+}
@@ -0,0 +1,28 @@
+; ModuleID = 'passes.c'
+source_filename = "passes.c"
+target datalayout = "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-f80:128-n8:16:32:64-S128"
+target triple = "x86_64-pc-linux-gnu"
+
+define i32 @max(i32 %a, i32 %b) {
+entry:
+  %result = alloca i32, align 4
+  %cmp = icmp sgt i32 %a, %b
+  br i1 %cmp, label %then, label %else
+
+then:
+  store i32 %a, i32* %result, align 4
+  br label %end
+
+else:
+  store i32 %b, i32* %result, align 4
+  br label %end
+
+end:
+  %r = load i32, i32* %result, align 4
+  ret i32 %r
+}
+
+define i32 @inc(i32 %0) {
+  %2 = add i32 %0, 1
+  ret i32 %2
+}
@@ -0,0 +1,44 @@
+; exported and internal globals, some of which are only referenced from dead code
+@exported = global i32* @table
+@table = internal global i32 0
+@dead_table = internal global i32* @dead_counter
+@dead_counter = internal global i32 0
+@kept = internal global i32 0
+@llvm.used = appending global [1 x i8*] [i8* bitcast (i32* @kept to i8*)], section "llvm.metadata"
+@llvm.global_ctors = appending global [1 x { i32, void ()*, i8* }] [{ i32, void ()*, i8* } { i32 65535, void ()* @init, i8* null }]
+
+@alias = alias i32 (), i32 ()* @aliased
+@dead_alias = internal alias i32 (), i32 ()* @dead
+
+define i32 @main() {
+  %1 = call i32 @helper()
+  %2 = call i32 @external(i32 %1)
+  ret i32 %2
+}
+
+define internal i32 @helper() {
+  %1 = load i32, i32* @table
+  ret i32 %1
+}
+
+define internal i32 @aliased() {
+  ret i32 0
+}
+
+define internal void @init() {
+  store i32 1, i32* @table
+  ret void
+}
+
+define internal i32 @dead() {
+  %1 = call i32 @dead_callee()
+  ret i32 %1
+}
+
+define internal i32 @dead_callee() {
+  %1 = load i32*, i32** @dead_table
+  %2 = load i32, i32* %1
+  ret i32 %2
+}
+
+declare i32 @external(i32)
@@ -0,0 +1,13 @@
+; aliases of functions and globals, through casts and other aliases
+@g = global i32 0
+@g_alias = alias i32, i32* @g
+@g_cast = alias i8, i8* bitcast (i32* @g to i8*)
+@g_alias_alias = alias i8, i8* bitcast (i32* @g_alias to i8*)
+@g_gep = alias i32, getelementptr (i32, i32* @g, i64 1)
+@f_alias = weak alias void (), void ()* @f
+@f_cast = alias i32 (i32), bitcast (void ()* @f_alias to i32 (i32)*)
+
+define void @f() {
+  call i32 @f_cast(i32 0)
+  ret void
+}
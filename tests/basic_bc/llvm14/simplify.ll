@@ -0,0 +1,29 @@
+; ModuleID = 'simplify.ll'
+source_filename = "simplify.ll"
+
+%struct.S = type { i32, [4 x i32] }
+
+define i32 @fold(i32 %x) {
+  %1 = add i32 2, 3
+  %2 = mul i32 %1, -4
+  %3 = lshr i32 %2, 28
+  %4 = sdiv i32 %3, 0
+  %5 = shl i8 1, 8
+  %6 = add i32 %x, %3
+  ret i32 %6
+}
+
+define i32* @casts(i32* %p) {
+  %1 = bitcast i32* %p to i32*
+  %2 = getelementptr inbounds i32, i32* %1, i64 0
+  %3 = bitcast i32* %2 to i8*
+  %4 = bitcast i8* %3 to i32*
+  ret i32* %4
+}
+
+define i32* @geps(%struct.S* %s) {
+  %1 = getelementptr inbounds %struct.S, %struct.S* %s, i64 1
+  %2 = getelementptr inbounds %struct.S, %struct.S* %1, i64 0, i32 1
+  %3 = getelementptr [4 x i32], [4 x i32]* %2, i64 0, i64 2
+  ret i32* %3
+}
@@ -0,0 +1,31 @@
+; A function with allocas, calls, and nested loops, for testing size metrics
+
+target datalayout = "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-f80:128-n8:16:32:64-S128"
+target triple = "x86_64-unknown-linux-gnu"
+
+declare void @g()
+
+define void @metrics(i64 %n, i1 %c) {
+entry:
+  %a = alloca i8
+  %b = alloca i32
+  %c.arr = alloca [3 x i64], align 16
+  %d = alloca { i8, i32 }
+  %e = alloca x86_fp80
+  %dyn = alloca i32, i64 %n
+  call void @g()
+  br label %outer
+
+outer:
+  br label %inner
+
+inner:
+  call void @g()
+  br i1 %c, label %inner, label %outer.latch
+
+outer.latch:
+  br i1 %c, label %outer, label %exit
+
+exit:
+  ret void
+}
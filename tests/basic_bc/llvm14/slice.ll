@@ -0,0 +1,47 @@
+; a module to extract slices from
+
+%struct.Used = type { i32, %struct.Nested* }
+%struct.Nested = type { i64 }
+%struct.Unused = type { i8 }
+%struct.InGlobal = type { i32 (i32)* }
+
+@table = internal global %struct.InGlobal { i32 (i32)* @in_table }
+@counter = global i32 0
+@counter_ptr = global i32* @counter
+@unused_var = global %struct.Unused zeroinitializer
+
+@twice_alias = alias i32 (i32), i32 (i32)* @twice
+
+define i32 @root(%struct.Used* %u) {
+  %field = getelementptr %struct.Used, %struct.Used* %u, i32 0, i32 0
+  %x = load i32, i32* %field
+  %y = call i32 @callee(i32 %x)
+  %z = call i32 @twice_alias(i32 %y)
+  %slot = getelementptr %struct.InGlobal, %struct.InGlobal* @table, i32 0, i32 0
+  %f = load i32 (i32)*, i32 (i32)** %slot
+  %w = call i32 %f(i32 %z)
+  %p = load i32*, i32** @counter_ptr
+  store i32 %w, i32* %p
+  ret i32 %w
+}
+
+define i32 @callee(i32 %x) {
+  %c = load i32, i32* @counter
+  %r = add i32 %x, %c
+  ret i32 %r
+}
+
+define i32 @twice(i32 %x) {
+  %r = call i32 @callee(i32 %x)
+  %s = mul i32 %r, 2
+  ret i32 %s
+}
+
+define i32 @in_table(i32 %x) {
+  ret i32 %x
+}
+
+define void @unrelated() {
+  store %struct.Unused zeroinitializer, %struct.Unused* @unused_var
+  ret void
+}
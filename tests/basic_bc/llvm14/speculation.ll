@@ -0,0 +1,26 @@
+; ModuleID = 'speculation.ll'
+source_filename = "speculation.ll"
+
+define void @speculation(i32* %p, i32 %x, i32 %y) {
+  %1 = add i32 %x, %y
+  %2 = udiv i32 %x, 7
+  %3 = udiv i32 %x, %y
+  %4 = sdiv i32 %x, -1
+  %5 = srem i32 %x, 3
+  %6 = load i32, i32* %p
+  %7 = load volatile i32, i32* %p
+  %8 = load atomic i32, i32* %p seq_cst, align 4
+  store i32 %x, i32* %p
+  %9 = alloca i32
+  %10 = call i32 @pure(i32 %x) #0
+  %11 = call i32 @llvm.ctpop.i32(i32 %x)
+  %12 = call i32 @pure(i32 %x)
+  %13 = atomicrmw add i32* %p, i32 1 seq_cst
+  fence seq_cst
+  ret void
+}
+
+declare i32 @pure(i32)
+declare i32 @llvm.ctpop.i32(i32)
+
+attributes #0 = { nounwind readnone willreturn }
@@ -0,0 +1,25 @@
+; ModuleID = 'stackmaps.c'
+source_filename = "stackmaps.c"
+target datalayout = "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-f80:128-n8:16:32:64-S128"
+target triple = "x86_64-pc-linux-gnu"
+
+declare void @llvm.experimental.stackmap(i64, i32, ...)
+declare i64 @llvm.experimental.patchpoint.i64(i64, i32, i8*, i32, ...)
+declare void @llvm.experimental.patchpoint.void(i64, i32, i8*, i32, ...)
+declare i32 @__gxx_personality_v0(...)
+
+define i64 @patched(i64 %a, i64 %b, i8* %target) personality i32 (...)* @__gxx_personality_v0 {
+entry:
+  call void (i64, i32, ...) @llvm.experimental.stackmap(i64 1, i32 8, i64 %a, i64 %b)
+  call void (i64, i32, ...) @llvm.experimental.stackmap(i64 2, i32 0)
+  %r = call i64 (i64, i32, i8*, i32, ...) @llvm.experimental.patchpoint.i64(i64 3, i32 15, i8* %target, i32 2, i64 %a, i64 %b, i64 %a)
+  invoke void (i64, i32, i8*, i32, ...) @llvm.experimental.patchpoint.void(i64 4, i32 15, i8* null, i32 0, i64 %a)
+          to label %done unwind label %lpad
+
+done:
+  ret i64 %r
+
+lpad:
+  %lp = landingpad { i8*, i32 } cleanup
+  ret i64 0
+}
@@ -0,0 +1,30 @@
+; ModuleID = 'statepoints.ll'
+source_filename = "statepoints.ll"
+target datalayout = "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-f80:128-n8:16:32:64-S128"
+target triple = "x86_64-pc-linux-gnu"
+
+declare token @llvm.experimental.gc.statepoint.p0f_i32i32f(i64, i32, i32 (i32)*, i32, i32, ...)
+declare i8 addrspace(1)* @llvm.experimental.gc.relocate.p1i8(token, i32, i32)
+declare i32 @llvm.experimental.gc.result.i32(token)
+declare i32 @callee(i32)
+declare i32 @__gxx_personality_v0(...)
+
+define i8 addrspace(1)* @relocated(i8 addrspace(1)* %obj, i32 %x) gc "statepoint-example" personality i32 (...)* @__gxx_personality_v0 {
+entry:
+  %derived = getelementptr i8, i8 addrspace(1)* %obj, i64 8
+  %sp = call token (i64, i32, i32 (i32)*, i32, i32, ...) @llvm.experimental.gc.statepoint.p0f_i32i32f(i64 7, i32 0, i32 (i32)* @callee, i32 1, i32 0, i32 %x, i32 0, i32 0) [ "gc-live"(i8 addrspace(1)* %obj, i8 addrspace(1)* %derived), "deopt"(i32 %x) ]
+  %res = call i32 @llvm.experimental.gc.result.i32(token %sp)
+  %obj.rel = call i8 addrspace(1)* @llvm.experimental.gc.relocate.p1i8(token %sp, i32 0, i32 0)
+  %derived.rel = call i8 addrspace(1)* @llvm.experimental.gc.relocate.p1i8(token %sp, i32 0, i32 1)
+  %sp2 = invoke token (i64, i32, i32 (i32)*, i32, i32, ...) @llvm.experimental.gc.statepoint.p0f_i32i32f(i64 8, i32 0, i32 (i32)* @callee, i32 1, i32 0, i32 %res, i32 0, i32 0) [ "gc-live"(i8 addrspace(1)* %obj.rel) ]
+          to label %normal unwind label %lpad
+
+normal:
+  %obj.rel2 = call i8 addrspace(1)* @llvm.experimental.gc.relocate.p1i8(token %sp2, i32 0, i32 0)
+  ret i8 addrspace(1)* %obj.rel2
+
+lpad:
+  %lp = landingpad token cleanup
+  %obj.exc = call i8 addrspace(1)* @llvm.experimental.gc.relocate.p1i8(token %lp, i32 0, i32 0)
+  ret i8 addrspace(1)* %obj.exc
+}
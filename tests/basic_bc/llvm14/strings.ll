@@ -0,0 +1,13 @@
+; ModuleID = 'strings.ll'
+source_filename = "strings.ll"
+
+@.str = private unnamed_addr constant [6 x i8] c"hello\00", align 1
+@.str.1 = private unnamed_addr constant [1 x i8] zeroinitializer, align 1
+@raw = constant [3 x i8] c"a\00b", align 1
+@tag = constant [4 x i8] c"\E2\9C\93\00", align 1
+@buffer = global [6 x i8] c"hello\00", align 1
+@numbers = constant [2 x i32] [i32 1, i32 2], align 4
+
+define i8* @greeting() {
+  ret i8* getelementptr inbounds ([6 x i8], [6 x i8]* @.str, i64 0, i64 0)
+}
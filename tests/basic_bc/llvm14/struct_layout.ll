@@ -0,0 +1,14 @@
+; Named structs with various layouts
+
+target datalayout = "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-f80:128-n8:16:32:64-S128"
+target triple = "x86_64-unknown-linux-gnu"
+
+%A = type { i8, i32, i8 }
+%P = type <{ i8, i32 }>
+%N = type { %A, i64, [3 x i16] }
+%O = type opaque
+
+@a = external global %A
+@p = external global %P
+@n = external global %N
+@o = external global %O
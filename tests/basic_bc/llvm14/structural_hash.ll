@@ -0,0 +1,92 @@
+; functions which are structurally identical, or nearly so
+
+@counter = global i32 0
+
+define i32 @named(i32 %x, i32 %y) {
+entry:
+  %cmp = icmp slt i32 %x, %y
+  br i1 %cmp, label %then, label %else
+
+then:
+  %sum = add i32 %x, 1
+  store i32 %sum, i32* @counter
+  br label %else
+
+else:
+  %result = phi i32 [ %sum, %then ], [ %y, %entry ]
+  ret i32 %result
+}
+
+define internal i32 @unnamed(i32 %0, i32 %1) {
+  %3 = icmp slt i32 %0, %1
+  br i1 %3, label %4, label %6
+
+4:
+  %5 = add i32 %0, 1
+  store i32 %5, i32* @counter
+  br label %6
+
+6:
+  %7 = phi i32 [ %5, %4 ], [ %1, %2 ]
+  ret i32 %7
+}
+
+define i32 @other_constant(i32 %x, i32 %y) {
+entry:
+  %cmp = icmp slt i32 %x, %y
+  br i1 %cmp, label %then, label %else
+
+then:
+  %sum = add i32 %x, 2
+  store i32 %sum, i32* @counter
+  br label %else
+
+else:
+  %result = phi i32 [ %sum, %then ], [ %y, %entry ]
+  ret i32 %result
+}
+
+define i32 @other_predicate(i32 %x, i32 %y) {
+entry:
+  %cmp = icmp sgt i32 %x, %y
+  br i1 %cmp, label %then, label %else
+
+then:
+  %sum = add i32 %x, 1
+  store i32 %sum, i32* @counter
+  br label %else
+
+else:
+  %result = phi i32 [ %sum, %then ], [ %y, %entry ]
+  ret i32 %result
+}
+
+define i32 @swapped_branch(i32 %x, i32 %y) {
+entry:
+  %cmp = icmp slt i32 %x, %y
+  br i1 %cmp, label %else, label %then
+
+then:
+  %sum = add i32 %x, 1
+  store i32 %sum, i32* @counter
+  br label %else
+
+else:
+  %result = phi i32 [ %sum, %then ], [ %y, %entry ]
+  ret i32 %result
+}
+
+define i32 @swapped_operands(i32 %x, i32 %y) {
+entry:
+  %cmp = icmp slt i32 %y, %x
+  br i1 %cmp, label %then, label %else
+
+then:
+  %sum = add i32 %x, 1
+  store i32 %sum, i32* @counter
+  br label %else
+
+else:
+  %result = phi i32 [ %sum, %then ], [ %y, %entry ]
+  ret i32 %result
+}
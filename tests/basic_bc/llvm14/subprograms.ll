@@ -0,0 +1,41 @@
+; functions from two source files of one C++ compile unit, and one without
+; debug info
+
+define i32 @_Z3addii(i32 %a, i32 %b) !dbg !5 {
+  %sum = add i32 %a, %b, !dbg !10
+  ret i32 %sum, !dbg !10
+}
+
+define linkonce_odr i32 @_Z6squarei(i32 %x) !dbg !11 {
+  %sq = mul i32 %x, %x, !dbg !13
+  ret i32 %sq, !dbg !13
+}
+
+define i32 @main() !dbg !14 {
+  %r = call i32 @_Z3addii(i32 1, i32 2), !dbg !15
+  ret i32 %r, !dbg !15
+}
+
+define void @no_debug_info() {
+  ret void
+}
+
+!llvm.dbg.cu = !{!0}
+!llvm.module.flags = !{!3, !4}
+
+!0 = distinct !DICompileUnit(language: DW_LANG_C_plus_plus_14, file: !1, producer: "clang version 14.0.6", isOptimized: false, runtimeVersion: 0, emissionKind: FullDebug)
+!1 = !DIFile(filename: "main.cpp", directory: "/src")
+!2 = !DIFile(filename: "util.h", directory: "/src")
+!3 = !{i32 7, !"Dwarf Version", i32 4}
+!4 = !{i32 2, !"Debug Info Version", i32 3}
+!5 = distinct !DISubprogram(name: "add", linkageName: "_Z3addii", scope: !1, file: !1, line: 3, type: !6, scopeLine: 3, spFlags: DISPFlagDefinition, unit: !0)
+!6 = !DISubroutineType(types: !7)
+!7 = !{!8, !8, !8}
+!8 = !DIBasicType(name: "int", size: 32, encoding: DW_ATE_signed)
+!10 = !DILocation(line: 4, column: 10, scope: !5)
+!11 = distinct !DISubprogram(name: "square", linkageName: "_Z6squarei", scope: !2, file: !2, line: 7, type: !12, scopeLine: 7, spFlags: DISPFlagDefinition, unit: !0)
+!12 = !DISubroutineType(types: !{!8, !8})
+!13 = !DILocation(line: 8, column: 12, scope: !11)
+!14 = distinct !DISubprogram(name: "main", scope: !1, file: !1, line: 9, type: !16, scopeLine: 9, spFlags: DISPFlagDefinition, unit: !0)
+!15 = !DILocation(line: 10, column: 3, scope: !14)
+!16 = !DISubroutineType(types: !{!8})
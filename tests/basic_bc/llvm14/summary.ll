@@ -0,0 +1,25 @@
+; a module with a ThinLTO summary: build with `opt -module-summary`
+@counter = global i32 0
+@table = constant [1 x void ()*] [void ()* @leaf]
+
+@caller_alias = alias void (), void ()* @caller
+
+define internal void @leaf() noinline {
+  store i32 1, i32* @counter
+  ret void
+}
+
+define void @caller() !prof !0 {
+  call void @leaf()
+  call void @external()
+  %v = load i32, i32* @counter
+  ret void
+}
+
+define i32 @pure(i32 %x) readnone norecurse {
+  ret i32 %x
+}
+
+declare void @external()
+
+!0 = !{!"function_entry_count", i64 100}
@@ -0,0 +1,34 @@
+; Function attributes which were added in LLVM 12 or later, plus
+; target-specific string attributes
+
+define void @f.mustprogress() mustprogress {
+  ret void
+}
+
+define void @f.hot() hot {
+  ret void
+}
+
+define void @f.noprofile() noprofile {
+  ret void
+}
+
+define void @f.vscale_range() vscale_range(2,4) {
+  ret void
+}
+
+define void @f.vscale_range_unbounded() vscale_range(1,0) {
+  ret void
+}
+
+define void @f.nosanitize_coverage() nosanitize_coverage {
+  ret void
+}
+
+define void @f.swiftasync(i8* swiftasync %p) {
+  ret void
+}
+
+define void @f.target() "target-cpu"="x86-64" "target-features"="+sse2,+cx8" {
+  ret void
+}
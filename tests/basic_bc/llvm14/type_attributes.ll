@@ -0,0 +1,14 @@
+; Parameter attributes which carry a type or an integer value
+
+%struct.T = type { i32, i64 }
+
+define void @f.byref(%struct.T* byref(%struct.T) align 8 %p) {
+  ret void
+}
+
+define i32* @f.elementtype([4 x i32]* %p) {
+  %q = call i32* @llvm.preserve.array.access.index.p0i32.p0a4i32([4 x i32]* elementtype([4 x i32]) %p, i32 0, i32 1)
+  ret i32* %q
+}
+
+declare i32* @llvm.preserve.array.access.index.p0i32.p0a4i32([4 x i32]*, i32, i32)
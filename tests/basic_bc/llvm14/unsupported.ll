@@ -0,0 +1,12 @@
+; A constant shufflevector expression, which llvm-ir cannot parse for LLVM 11+,
+; so parsing this module should fail with an error rather than a panic.
+@g = global i32 0
+
+define <vscale x 2 x i32> @f(i32 %x) {
+entry:
+  %y = add i32 %x, 1
+  br label %next
+
+next:
+  ret <vscale x 2 x i32> shufflevector (<vscale x 2 x i32> insertelement (<vscale x 2 x i32> undef, i32 ptrtoint (i32* @g to i32), i32 0), <vscale x 2 x i32> undef, <vscale x 2 x i32> zeroinitializer)
+}
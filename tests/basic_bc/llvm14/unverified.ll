@@ -0,0 +1,8 @@
+; This module is intentionally malformed: the instruction defining %1 does not
+; dominate its use in %0. Assemble it with `llvm-as -disable-verify`.
+define i32 @bad(i32 %x) {
+entry:
+  %0 = add i32 %1, 1
+  %1 = add i32 %x, 2
+  ret i32 %0
+}
@@ -0,0 +1,12 @@
+; globals kept alive by `llvm.used` and `llvm.compiler.used`
+
+@kept = internal global i32 0
+@kept_too = internal addrspace(1) global i8 0
+@compiler_only = internal global [4 x i8] zeroinitializer
+
+@llvm.used = appending global [3 x i8*] [i8* bitcast (i32* @kept to i8*), i8* addrspacecast (i8 addrspace(1)* @kept_too to i8*), i8* bitcast (void ()* @handler to i8*)], section "llvm.metadata"
+@llvm.compiler.used = appending global [1 x i8*] [i8* getelementptr inbounds ([4 x i8], [4 x i8]* @compiler_only, i32 0, i32 0)], section "llvm.metadata"
+
+define internal void @handler() {
+  ret void
+}
@@ -0,0 +1,17 @@
+; variadic function using va_arg and the va_* intrinsics
+define i32 @first_vararg(i32 %n, ...) {
+  %ap = alloca i8*
+  %ap_ptr = bitcast i8** %ap to i8*
+  call void @llvm.va_start(i8* %ap_ptr)
+  %copy = alloca i8*
+  %copy_ptr = bitcast i8** %copy to i8*
+  call void @llvm.va_copy(i8* %copy_ptr, i8* %ap_ptr)
+  %x = va_arg i8** %ap, i32
+  call void @llvm.va_end(i8* %copy_ptr)
+  call void @llvm.va_end(i8* %ap_ptr)
+  ret i32 %x
+}
+
+declare void @llvm.va_start(i8*)
+declare void @llvm.va_copy(i8*, i8*)
+declare void @llvm.va_end(i8*)
@@ -0,0 +1,23 @@
+; ModuleID = 'values.ll'
+source_filename = "values.ll"
+
+@counter = global i32 0
+@alias = alias i32, i32* @counter
+
+define i32 @values(i32 %x) personality i32 (...)* @__gxx_personality_v0 {
+entry:
+  %sum = add i32 %x, 1
+  %old = load i32, i32* @alias
+  %r = invoke i32 @ext(i32 %sum) to label %cont unwind label %lpad
+
+cont:
+  store i32 %r, i32* @counter
+  ret i32 %old
+
+lpad:
+  %lp = landingpad { i8*, i32 } cleanup
+  resume { i8*, i32 } %lp
+}
+
+declare i32 @ext(i32)
+declare i32 @__gxx_personality_v0(...)
@@ -0,0 +1,15 @@
+; ModuleID = 'vector_reduce.ll'
+source_filename = "vector_reduce.ll"
+
+define float @reductions(<4 x i32> %v, <8 x float> %f) {
+  %sum = call i32 @llvm.vector.reduce.add.v4i32(<4 x i32> %v)
+  %min = call i32 @llvm.vector.reduce.umin.v4i32(<4 x i32> %v)
+  %total = call reassoc float @llvm.vector.reduce.fadd.v8f32(float 1.000000e+00, <8 x float> %f)
+  %max = call nnan float @llvm.vector.reduce.fmax.v8f32(<8 x float> %f)
+  ret float %total
+}
+
+declare i32 @llvm.vector.reduce.add.v4i32(<4 x i32>)
+declare i32 @llvm.vector.reduce.umin.v4i32(<4 x i32>)
+declare float @llvm.vector.reduce.fadd.v8f32(float, <8 x float>)
+declare float @llvm.vector.reduce.fmax.v8f32(<8 x float>)
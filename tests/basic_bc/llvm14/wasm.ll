@@ -0,0 +1,21 @@
+target datalayout = "e-m:e-p:32:32-p10:8:8-p20:8:8-i64:64-n32:64-S128-ni:1:10:20"
+target triple = "wasm32-unknown-unknown"
+
+@g = global i32 0
+@ext = addrspace(1) global i32 0
+
+define i32 @add(i32 %a, i32 %b) {
+  %s = add i32 %a, %b
+  ret i32 %s
+}
+
+define swiftcc void @callee() {
+  ret void
+}
+
+define i32 @caller(i32 addrspace(1)* %p) {
+  call swiftcc void @callee()
+  %v = load i32, i32 addrspace(1)* %p
+  %r = call i32 @add(i32 %v, i32 1)
+  ret i32 %r
+}
@@ -0,0 +1,22 @@
+; Switches on integers wider than 64 bits
+
+define i32 @wide_switch(i128 %x) {
+entry:
+  switch i128 %x, label %default [
+    i128 3, label %small
+    i128 18446744073709551616, label %big
+    i128 -1, label %allones
+  ]
+
+small:
+  ret i32 1
+
+big:
+  ret i32 2
+
+allones:
+  ret i32 3
+
+default:
+  ret i32 0
+}
@@ -1,22 +1,67 @@
+#![allow(
+    clippy::bool_assert_comparison,
+    clippy::get_first,
+    clippy::match_like_matches_macro,
+    clippy::non_minimal_cfg,
+    clippy::option_as_ref_deref
+)]
+
 use either::Either;
 use itertools::Itertools;
-use llvm_ir::function::{FunctionAttribute, ParameterAttribute};
-use llvm_ir::instruction;
-use llvm_ir::module::{Alignment, Endianness, Mangling, PointerLayout};
+use llvm_ir::analysis::{ControlFlowGraph, SsaGraph};
+use llvm_ir::constant::{BigIntValue, Float, GlobalKind};
+use llvm_ir::function::{
+    CallingConvention,
+    FunctionAttribute,
+    MemoryEffects,
+    MemoryLocation,
+    ParameterAttribute,
+};
+use llvm_ir::instruction::{self, HasResult};
+use llvm_ir::intrinsic::Intrinsic;
+use llvm_ir::location::InstrRef;
+use llvm_ir::matchers::*;
+use llvm_ir::metadata::{LoopHint, MetadataNode};
+use llvm_ir::module::{
+    Alignment,
+    Endianness,
+    Linkage,
+    Mangling,
+    ParseOptions,
+    PointerLayout,
+    Producer,
+    UpgradedIntrinsic,
+};
+use llvm_ir::summary::{Hotness, ModuleSummaryIndex, SummaryKind};
 use llvm_ir::terminator;
-use llvm_ir::types::{FPType, NamedStructDef};
+use llvm_ir::transform;
+use llvm_ir::types::{FPType, NamedStructDef, ResultTypes, SizeAndAlignment, StructLayout, Types};
+use llvm_ir::value::GlobalValue;
+use llvm_ir::Error;
+use llvm_ir::Function;
 #[cfg(feature = "llvm-9-or-greater")]
 use llvm_ir::HasDebugLoc;
+use llvm_ir::InstrLoc;
 use llvm_ir::Instruction;
 use llvm_ir::IntPredicate;
+use llvm_ir::Metadata;
 use llvm_ir::Module;
+use llvm_ir::ModuleLoc;
 use llvm_ir::Name;
+use llvm_ir::Opcode;
 use llvm_ir::Operand;
+use llvm_ir::OperandRef;
 use llvm_ir::Terminator;
 use llvm_ir::Type;
-use llvm_ir::{Constant, ConstantRef};
+use llvm_ir::TypeRef;
+use llvm_ir::ValueKind;
+use llvm_ir::{Constant, ConstantRef, Context};
+use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
+use std::num::NonZeroU32;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
 
 fn init_logging() {
     // capture log messages with test harness
@@ -88,8 +133,8 @@ fn hellobc() {
         .try_into()
         .unwrap_or_else(|_| panic!("Terminator should be a Ret but is {:?}", &bb.term));
     assert_eq!(
-        ret.return_operand,
-        Some(Operand::ConstantOperand(ConstantRef::new(Constant::Int {
+        ret.return_operand.as_deref(),
+        Some(&Operand::ConstantOperand(ConstantRef::new(Constant::Int {
             bits: 32,
             value: 0
         })))
@@ -104,6 +149,36 @@ fn hellobc() {
     }
 }
 
+#[cfg(feature = "llvm-14")]
+#[test]
+fn producers() {
+    init_logging();
+    let path = llvm_bc_dir().join("hello.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    assert_eq!(module.llvm_version_parsed_with(), Some("14"));
+    assert_eq!(
+        module.producers,
+        vec![Producer {
+            ident: "Homebrew clang version 14.0.6".into(),
+            name: "Homebrew clang".into(),
+            version: Some((14, 0, 6)),
+        }]
+    );
+    assert!(module.producers[0].is_clang());
+    assert!(!module.producers[0].is_rustc());
+
+    let rustc = Producer::parse("rustc version 1.70.0-nightly (90c541806 2023-05-31)");
+    assert_eq!(rustc.name, "rustc");
+    assert_eq!(rustc.version, Some((1, 70, 0)));
+    assert!(rustc.is_rustc());
+    let ubuntu = Producer::parse("Ubuntu clang version 14.0.0-1ubuntu1");
+    assert_eq!(ubuntu.version, Some((14, 0, 0)));
+    assert!(ubuntu.is_clang());
+    let unknown = Producer::parse("my frontend");
+    assert_eq!(unknown.name, "my frontend");
+    assert_eq!(unknown.version, None);
+}
+
 // this test relates to the version of the file compiled with debuginfo
 #[cfg(feature = "llvm-9-or-greater")]
 #[test]
@@ -150,6 +225,111 @@ fn hellobcg() {
     assert_eq!(&ret.to_string(), "ret i32 0 (with debugloc)");
 }
 
+#[cfg(feature = "llvm-9-or-greater")]
+#[test]
+fn source_map() {
+    init_logging();
+    let path = llvm_bc_dir().join("hello.bc-g");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let source_map = module.source_map();
+    assert_eq!(source_map.filenames().collect::<Vec<_>>(), vec!["hello.c"]);
+    assert_eq!(source_map.lines("hello.c").collect::<Vec<_>>(), vec![3, 4]);
+    assert_eq!(source_map.lines("other.c").count(), 0);
+    // main() itself is on line 3, but has no instructions there
+    assert_eq!(source_map.instrs_at("hello.c", 3), &[]);
+    assert_eq!(source_map.functions_at("hello.c", 3), &[0]);
+    let ret = ModuleLoc::new(0, 0).terminator();
+    assert_eq!(source_map.instrs_at("hello.c", 4), &[ret]);
+    assert_eq!(source_map.functions_at("hello.c", 4), &[0]);
+    assert_eq!(source_map.instrs_at("hello.c", 5), &[]);
+    let debugloc = source_map
+        .debug_loc_of(ret)
+        .expect("Expected the ret to have a debugloc");
+    assert_eq!(debugloc.line, 4);
+    assert_eq!(debugloc.col, Some(3));
+    assert_eq!(source_map.lines_of_function(0), vec![("hello.c", 4)]);
+
+    // every instruction with a debugloc is indexed under its line
+    let path = llvm_bc_dir().join("linkedlist.bc-g");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let source_map = module.source_map();
+    let mut num_with_debugloc = 0;
+    for loc in module.instr_locs() {
+        let debugloc = match module.get_instr(loc).unwrap() {
+            InstrRef::Instr(inst) => inst.get_debug_loc(),
+            InstrRef::Terminator(term) => term.get_debug_loc(),
+        };
+        assert_eq!(source_map.debug_loc_of(loc), debugloc.as_ref());
+        if let Some(debugloc) = debugloc {
+            num_with_debugloc += 1;
+            assert!(source_map
+                .instrs_at(&debugloc.filename, debugloc.line)
+                .contains(&loc));
+            assert!(source_map
+                .functions_at(&debugloc.filename, debugloc.line)
+                .contains(&loc.block.func));
+        }
+    }
+    assert!(num_with_debugloc > 0);
+    let total: usize = source_map
+        .lines("linkedlist.c")
+        .map(|line| source_map.instrs_at("linkedlist.c", line).len())
+        .sum();
+    assert_eq!(total, num_with_debugloc);
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn subprograms() {
+    init_logging();
+    let path = llvm_bc_dir().join("subprograms.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let add = module.get_func_by_name("_Z3addii").unwrap();
+    let subprogram = add.subprogram().expect("Should have a subprogram");
+    assert_eq!(subprogram.name, "add");
+    assert_eq!(subprogram.linkage_name.as_deref(), Some("_Z3addii"));
+    assert_eq!(subprogram.line, 3);
+    let unit = subprogram
+        .compile_unit
+        .as_ref()
+        .expect("Should have a compile unit");
+    assert_eq!(unit.filename, "main.cpp");
+    assert_eq!(unit.directory.as_deref(), Some("/src"));
+    assert_eq!(unit.producer, "clang version 14.0.6");
+
+    // defined in a header, but part of the same compile unit
+    let square = module.get_func_by_name("_Z6squarei").unwrap();
+    let subprogram = square.subprogram().expect("Should have a subprogram");
+    assert_eq!(subprogram.name, "square");
+    assert_eq!(subprogram.line, 7);
+    assert_eq!(subprogram.compile_unit.as_ref(), Some(unit));
+    assert_eq!(square.get_debug_loc().as_ref().unwrap().filename, "util.h");
+
+    let main = module.get_func_by_name("main").unwrap();
+    assert_eq!(main.subprogram().unwrap().linkage_name, None);
+    let no_debug_info = module.get_func_by_name("no_debug_info").unwrap();
+    assert_eq!(no_debug_info.subprogram(), None);
+
+    let by_file = module.functions_by_source_file();
+    let names = |file: &str| {
+        by_file[file]
+            .iter()
+            .map(|func| func.name.as_str())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        by_file.keys().copied().collect::<Vec<_>>(),
+        vec!["main.cpp", "util.h"]
+    );
+    assert_eq!(names("main.cpp"), vec!["_Z3addii", "main"]);
+    assert_eq!(names("util.h"), vec!["_Z6squarei"]);
+
+    let mut stripped = module.clone();
+    stripped.strip_debug_info();
+    assert!(stripped.functions.iter().all(|f| f.subprogram().is_none()));
+    assert!(stripped.functions_by_source_file().is_empty());
+}
+
 #[test]
 #[allow(clippy::cognitive_complexity)]
 fn loopbc() {
@@ -302,7 +482,7 @@ fn loopbc() {
         alloca.num_elements,
         Operand::ConstantOperand(ConstantRef::new(Constant::Int { bits: 32, value: 1 })) // One element, which is an array of 10 elements. Not 10 elements, each of which are i32.
     );
-    assert_eq!(alloca.alignment, 16);
+    assert_eq!(alloca.alignment, NonZeroU32::new(16));
     assert_eq!(
         module.type_of(alloca),
         module.types.pointer_to(allocated_type.clone())
@@ -338,8 +518,13 @@ fn loopbc() {
         .clone()
         .try_into()
         .expect("Should be a call");
-    if let Either::Right(Operand::ConstantOperand(cref)) = &lifetimestart.function {
-        if let Constant::GlobalReference { ref name, ref ty } = cref.as_ref() {
+    if let Either::Right(Operand::ConstantOperand(cref)) =
+        lifetimestart.function.as_ref().map_right(|op| op.as_ref())
+    {
+        if let Constant::GlobalReference {
+            ref name, ref ty, ..
+        } = cref.as_ref()
+        {
             assert_eq!(
                 module.type_of(&lifetimestart.function),
                 module.types.pointer_to(ty.clone())
@@ -363,6 +548,7 @@ fn loopbc() {
             } else {
                 panic!("lifetimestart.function has unexpected type {:?}", ty);
             }
+            assert_eq!(&lifetimestart.function_ty, ty);
         } else {
             panic!(
                 "lifetimestart.function not a GlobalReference as expected; it is actually another kind of Constant: {:?}",
@@ -408,8 +594,13 @@ fn loopbc() {
         .clone()
         .try_into()
         .expect("Should be a call");
-    if let Either::Right(Operand::ConstantOperand(cref)) = &memset.function {
-        if let Constant::GlobalReference { ref name, ref ty } = cref.as_ref() {
+    if let Either::Right(Operand::ConstantOperand(cref)) =
+        memset.function.as_ref().map_right(|op| op.as_ref())
+    {
+        if let Constant::GlobalReference {
+            ref name, ref ty, ..
+        } = cref.as_ref()
+        {
             assert_eq!(*name, Name::from("llvm.memset.p0i8.i64"));
             if let Type::FuncType {
                 result_type,
@@ -493,7 +684,7 @@ fn loopbc() {
         );
         assert_eq!(add.dest, Name::Number(5));
         assert_eq!(module.type_of(add), module.types.i32());
-        assert_eq!(&add.to_string(), "%5 = add i32 %1, i32 -1");
+        assert_eq!(&add.to_string(), "%5 = add i32 %1, -1");
     }
     #[cfg(feature = "llvm-13")]
     {
@@ -514,7 +705,7 @@ fn loopbc() {
         );
         assert_eq!(add.dest, Name::Number(7));
         assert_eq!(module.type_of(add), module.types.i32());
-        assert_eq!(&add.to_string(), "%7 = add i32 %0, i32 3");
+        assert_eq!(&add.to_string(), "%7 = add i32 %0, 3");
     }
     #[cfg(feature = "llvm-14-or-greater")]
     {
@@ -535,7 +726,7 @@ fn loopbc() {
         );
         assert_eq!(add.dest, Name::Number(8));
         assert_eq!(module.type_of(add), module.types.i32());
-        assert_eq!(&add.to_string(), "%8 = add i32 %0, i32 3");
+        assert_eq!(&add.to_string(), "%8 = add i32 %0, 3");
     }
     #[cfg(feature = "llvm-12-or-lower")]
     {
@@ -559,7 +750,7 @@ fn loopbc() {
             }))
         );
         assert_eq!(module.type_of(icmp), module.types.bool());
-        assert_eq!(&icmp.to_string(), "%6 = icmp ult i32 %5, i32 10");
+        assert_eq!(&icmp.to_string(), "%6 = icmp ult i32 %5, 10");
     }
     #[cfg(feature = "llvm-13")]
     {
@@ -583,7 +774,7 @@ fn loopbc() {
             }))
         );
         assert_eq!(module.type_of(icmp), module.types.bool());
-        assert_eq!(&icmp.to_string(), "%5 = icmp slt i32 %1, i32 11");
+        assert_eq!(&icmp.to_string(), "%5 = icmp slt i32 %1, 11");
     }
     #[cfg(feature = "llvm-14-or-greater")]
     {
@@ -607,7 +798,7 @@ fn loopbc() {
             }))
         );
         assert_eq!(module.type_of(icmp), module.types.bool());
-        assert_eq!(&icmp.to_string(), "%6 = icmp ult i32 %5, i32 10");
+        assert_eq!(&icmp.to_string(), "%6 = icmp ult i32 %5, 10");
     }
 
     let condbr: &terminator::CondBr = &bbs[0].term.clone().try_into().expect("Should be a condbr");
@@ -786,14 +977,17 @@ fn loopbc() {
         phi.incoming_values,
         vec![
             (
-                Operand::ConstantOperand(ConstantRef::new(Constant::Int { bits: 64, value: 0 })),
+                OperandRef::new(Operand::ConstantOperand(ConstantRef::new(Constant::Int {
+                    bits: 64,
+                    value: 0
+                }))),
                 Name::Number(7)
             ),
             (
-                Operand::LocalOperand {
+                OperandRef::new(Operand::LocalOperand {
                     name: Name::Number(20),
                     ty: module.types.i64()
-                },
+                }),
                 Name::Number(19)
             ),
         ]
@@ -803,14 +997,17 @@ fn loopbc() {
         phi.incoming_values,
         vec![
             (
-                Operand::LocalOperand {
+                OperandRef::new(Operand::LocalOperand {
                     name: Name::Number(19),
                     ty: module.types.i64()
-                },
+                }),
                 Name::Number(12)
             ),
             (
-                Operand::ConstantOperand(ConstantRef::new(Constant::Int { bits: 64, value: 1 })),
+                OperandRef::new(Operand::ConstantOperand(ConstantRef::new(Constant::Int {
+                    bits: 64,
+                    value: 1
+                }))),
                 Name::Number(7)
             ),
         ]
@@ -820,14 +1017,17 @@ fn loopbc() {
         phi.incoming_values,
         vec![
             (
-                Operand::LocalOperand {
+                OperandRef::new(Operand::LocalOperand {
                     name: Name::Number(22),
                     ty: module.types.i64()
-                },
+                }),
                 Name::Number(14)
             ),
             (
-                Operand::ConstantOperand(ConstantRef::new(Constant::Int { bits: 64, value: 1 })),
+                OperandRef::new(Operand::ConstantOperand(ConstantRef::new(Constant::Int {
+                    bits: 64,
+                    value: 1
+                }))),
                 Name::Number(7)
             ),
         ]
@@ -837,14 +1037,17 @@ fn loopbc() {
         phi.incoming_values,
         vec![
             (
-                Operand::ConstantOperand(ConstantRef::new(Constant::Int { bits: 64, value: 1 })),
+                OperandRef::new(Operand::ConstantOperand(ConstantRef::new(Constant::Int {
+                    bits: 64,
+                    value: 1
+                }))),
                 Name::Number(17)
             ),
             (
-                Operand::LocalOperand {
+                OperandRef::new(Operand::LocalOperand {
                     name: Name::Number(34),
                     ty: module.types.i64()
-                },
+                }),
                 Name::Number(19)
             ),
         ]
@@ -854,43 +1057,31 @@ fn loopbc() {
         phi.incoming_values,
         vec![
             (
-                Operand::ConstantOperand(ConstantRef::new(Constant::Int { bits: 64, value: 1 })),
+                OperandRef::new(Operand::ConstantOperand(ConstantRef::new(Constant::Int {
+                    bits: 64,
+                    value: 1
+                }))),
                 Name::Number(16)
             ),
             (
-                Operand::LocalOperand {
+                OperandRef::new(Operand::LocalOperand {
                     name: Name::Number(33),
                     ty: module.types.i64()
-                },
+                }),
                 Name::Number(18)
             ),
         ]
     );
     #[cfg(feature = "llvm-9-or-lower")]
-    assert_eq!(
-        &phi.to_string(),
-        "%11 = phi i64 [ i64 0, %7 ], [ i64 %20, %19 ]"
-    );
+    assert_eq!(&phi.to_string(), "%11 = phi i64 [ 0, %7 ], [ %20, %19 ]");
     #[cfg(feature = "llvm-10")]
-    assert_eq!(
-        &phi.to_string(),
-        "%13 = phi i64 [ i64 %19, %12 ], [ i64 1, %7 ]"
-    );
+    assert_eq!(&phi.to_string(), "%13 = phi i64 [ %19, %12 ], [ 1, %7 ]");
     #[cfg(feature = "llvm-11")]
-    assert_eq!(
-        &phi.to_string(),
-        "%15 = phi i64 [ i64 %22, %14 ], [ i64 1, %7 ]"
-    );
+    assert_eq!(&phi.to_string(), "%15 = phi i64 [ %22, %14 ], [ 1, %7 ]");
     #[cfg(any(feature = "llvm-12", feature = "llvm-13"))]
-    assert_eq!(
-        &phi.to_string(),
-        "%20 = phi i64 [ i64 1, %17 ], [ i64 %34, %19 ]"
-    );
+    assert_eq!(&phi.to_string(), "%20 = phi i64 [ 1, %17 ], [ %34, %19 ]");
     #[cfg(feature = "llvm-14-or-greater")]
-    assert_eq!(
-        &phi.to_string(),
-        "%19 = phi i64 [ i64 1, %16 ], [ i64 %33, %18 ]"
-    );
+    assert_eq!(&phi.to_string(), "%19 = phi i64 [ 1, %16 ], [ %33, %18 ]");
 
     #[cfg(feature = "llvm-11-or-lower")]
     let gep: &instruction::GetElementPtr = &bbs[2].instrs[1]
@@ -1020,7 +1211,7 @@ fn loopbc() {
         }
     );
     assert_eq!(store.volatile, true);
-    assert_eq!(store.alignment, 4);
+    assert_eq!(store.alignment, NonZeroU32::new(4));
     assert_eq!(module.type_of(store), module.types.void());
     assert_eq!(store_inst.is_atomic(), false);
     #[cfg(feature = "llvm-9-or-lower")]
@@ -1089,8 +1280,9 @@ fn loopbc() {
     assert_eq!(load.dest, Name::Number(26));
     #[cfg(feature = "llvm-14-or-greater")]
     assert_eq!(load.dest, Name::Number(25));
+    assert_eq!(load.loaded_type, module.types.i32());
     assert_eq!(load.volatile, true);
-    assert_eq!(load.alignment, 4);
+    assert_eq!(load.alignment, NonZeroU32::new(4));
     assert_eq!(module.type_of(load), module.types.i32());
     assert_eq!(load_inst.is_atomic(), false);
     #[cfg(feature = "llvm-10-or-lower")]
@@ -1241,8 +1433,214 @@ fn switchbc() {
     assert_eq!(phi.incoming_values.len(), 10);
     assert_eq!(
         &phi.to_string(),
-        "%13 = phi i32 [ i32 -1, %10 ], [ i32 -3, %9 ], [ i32 0, %8 ], [ i32 77, %7 ], [ i32 -33, %6 ], [ i32 1, %5 ], [ i32 -5, %4 ], [ i32 -7, %3 ], [ i32 5, %2 ], [ i32 3, %1 ]",
+        "%13 = phi i32 [ -1, %10 ], [ -3, %9 ], [ 0, %8 ], [ 77, %7 ], [ -33, %6 ], [ 1, %5 ], [ -5, %4 ], [ -7, %3 ], [ 5, %2 ], [ 3, %1 ]",
+    );
+
+    // the string constant should be kept in its compact raw form
+    let global = module
+        .get_global_var_by_name(&Name::from("str"))
+        .expect("Failed to find global @str");
+    let initializer = global
+        .initializer
+        .as_ref()
+        .expect("Expected @str to have an initializer");
+    match initializer.as_ref() {
+        Constant::RawDataArray { element_type, data } => {
+            assert_eq!(element_type, &module.types.i8());
+            assert_eq!(data.as_slice(), b"reached default\0");
+        },
+        c => panic!("Expected @str to be a RawDataArray; got {:?}", c),
+    }
+    assert_eq!(initializer.raw_data_num_elements(), Some(16));
+    assert_eq!(
+        initializer.raw_data_element(1),
+        Some(Constant::Int {
+            bits: 8,
+            value: u64::from(b'e')
+        })
+    );
+    assert_eq!(initializer.raw_data_element(16), None);
+    assert_eq!(initializer.raw_data_elements().count(), 16);
+    assert_eq!(
+        module.type_of(initializer),
+        module.types.array_of(module.types.i8(), 16)
+    );
+    assert_eq!(&initializer.to_string(), "c\"reached default\\00\"");
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn wide_switch() {
+    init_logging();
+    let path = llvm_bc_dir().join("wide_switch.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("wide_switch").unwrap();
+    let switch: &terminator::Switch = &func.basic_blocks[0]
+        .term
+        .clone()
+        .try_into()
+        .expect("Should be a switch");
+    assert_eq!(switch.dests.len(), 3);
+    assert_eq!(
+        switch.dests[0].0,
+        ConstantRef::new(Constant::BigInt(BigIntValue::from_u64(128, 3)))
+    );
+    assert_eq!(
+        switch.dests[1].0,
+        ConstantRef::new(Constant::BigInt(BigIntValue::from_words(128, vec![0, 1])))
+    );
+    assert_eq!(
+        switch.dests[2].0,
+        ConstantRef::new(Constant::BigInt(BigIntValue::from_words(
+            128,
+            vec![u64::MAX, u64::MAX]
+        )))
+    );
+    assert_eq!(module.type_of(&switch.dests[1].0), module.types.int(128));
+    assert_eq!(switch.dests[1].0.to_string(), "i128 18446744073709551616");
+    assert_eq!(switch.dests[2].0.to_string(), "i128 -1");
+
+    assert_eq!(
+        switch.case_for_value(&BigIntValue::from_u64(128, 3)),
+        &Name::from("small")
+    );
+    assert_eq!(
+        switch.case_for_value(&BigIntValue::from_words(128, vec![0, 1])),
+        &Name::from("big")
+    );
+    assert_eq!(
+        switch.case_for_value(&BigIntValue::from_words(128, vec![u64::MAX; 2])),
+        &Name::from("allones")
+    );
+    assert_eq!(
+        switch.case_for_value(&BigIntValue::from_u64(128, 4)),
+        &Name::from("default")
+    );
+    assert_eq!(BigIntValue::from_words(128, vec![0, 1]).to_u64(), None);
+    assert_eq!(BigIntValue::from_u64(128, 3).to_u64(), Some(3));
+
+    let all_ones = BigIntValue::from_words(128, vec![u64::MAX; 2]);
+    assert_eq!(all_ones.to_string(), "-1");
+    assert_eq!(
+        all_ones.to_unsigned_string(),
+        "340282366920938463463374607431768211455"
+    );
+    let all_ones = BigIntValue::from_words(100, vec![u64::MAX; 2]);
+    assert_eq!(all_ones.to_string(), "-1");
+    assert_eq!(
+        all_ones.to_unsigned_string(),
+        "1267650600228229401496703205375"
+    );
+    let min = BigIntValue::from_words(128, vec![0, 1 << 63]);
+    assert_eq!(min.to_string(), "-170141183460469231731687303715884105728");
+    assert_eq!(
+        min.to_unsigned_string(),
+        "170141183460469231731687303715884105728"
+    );
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn branch_weights() {
+    init_logging();
+    let path = llvm_bc_dir().join("branch_weights.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("weighted").unwrap();
+
+    let entry = func.get_bb_by_name(&Name::from("entry")).unwrap();
+    assert_eq!(entry.term.branch_weights(), Some(vec![2000, 1]));
+    let sw = func.get_bb_by_name(&Name::from("sw")).unwrap();
+    assert_eq!(sw.term.branch_weights(), Some(vec![5, 10, 20]));
+    let switch: &terminator::Switch = &sw.term.clone().try_into().expect("Should be a switch");
+    assert_eq!(switch.branch_weights, Some(vec![5, 10, 20]));
+    let plain = func.get_bb_by_name(&Name::from("plain")).unwrap();
+    assert_eq!(plain.term.branch_weights(), None);
+    let zero = func.get_bb_by_name(&Name::from("zero")).unwrap();
+    assert_eq!(zero.term.branch_weights(), None);
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn loop_metadata() {
+    init_logging();
+    let path = llvm_bc_dir().join("loop_metadata.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("hinted").unwrap();
+
+    let latch = func.get_bb_by_name(&Name::from("latch")).unwrap();
+    let md = latch
+        .term
+        .loop_metadata()
+        .expect("Should have loop metadata");
+    assert_eq!(md.hints.len(), 5);
+    assert_eq!(md.hints[0], LoopHint::MustProgress);
+    assert_eq!(md.hints[1], LoopHint::UnrollCount(4));
+    assert_eq!(md.hints[2], LoopHint::VectorizeEnable(true));
+    match &md.hints[3] {
+        LoopHint::ParallelAccesses(groups) => {
+            assert_eq!(groups.len(), 1);
+            assert_eq!(
+                module.metadata_nodes[groups[0]].1,
+                MetadataNode::Tuple(vec![])
+            );
+        },
+        hint => panic!("Expected ParallelAccesses; got {:?}", hint),
+    }
+    // unrecognized hints are left as they are
+    match &md.hints[4] {
+        LoopHint::Other(Metadata::Node(id)) => assert_eq!(
+            module.metadata_nodes[*id].1.operands()[0],
+            Some(Metadata::String("my.custom.hint".into()))
+        ),
+        hint => panic!("Expected Other; got {:?}", hint),
+    }
+    // the loop ID refers to itself, but that isn't a hint
+    assert_eq!(
+        module.metadata_nodes[md.id].1.operands()[0],
+        Some(Metadata::Node(md.id))
+    );
+
+    let inner = func.get_bb_by_name(&Name::from("inner")).unwrap();
+    let md = inner
+        .term
+        .loop_metadata()
+        .expect("Should have loop metadata");
+    assert_eq!(md.hints.len(), 2);
+    assert_eq!(md.hints[0], LoopHint::UnrollDisable);
+    // a recognized hint with malformed operands is also left as it is
+    assert!(matches!(md.hints[1], LoopHint::Other(Metadata::Node(_))));
+
+    let entry = func.get_bb_by_name(&Name::from("entry")).unwrap();
+    assert_eq!(entry.term.loop_metadata(), None);
+    let end = func.get_bb_by_name(&Name::from("end")).unwrap();
+    assert_eq!(end.term.loop_metadata(), None);
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn block_order() {
+    init_logging();
+    let path = llvm_bc_dir().join("loop_metadata.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("hinted").unwrap();
+
+    // the entry block comes first, and the rest follow in layout order
+    assert_eq!(func.entry_block().unwrap().name, Name::from("entry"));
+    let names: Vec<_> = func.basic_blocks.iter().map(|bb| bb.name.clone()).collect();
+    assert_eq!(
+        names,
+        ["entry", "loop", "latch", "exit", "inner", "end"]
+            .iter()
+            .map(|&name| Name::from(name))
+            .collect::<Vec<_>>()
     );
+    for (i, name) in names.iter().enumerate() {
+        assert_eq!(func.block_position(name), Some(i));
+    }
+    assert_eq!(func.block_position(&Name::from("nonexistent")), None);
+
+    let decl = Function::new("decl");
+    assert!(decl.entry_block().is_none());
 }
 
 #[test]
@@ -1259,7 +1657,7 @@ fn variablesbc() {
         var.initializer,
         Some(ConstantRef::new(Constant::Int { bits: 32, value: 5 }))
     );
-    assert_eq!(var.alignment, 4);
+    assert_eq!(var.alignment, NonZeroU32::new(4));
     #[cfg(feature = "llvm-9-or-greater")]
     assert!(var.get_debug_loc().is_none()); // this file was compiled without debuginfo
 
@@ -1296,7 +1694,9 @@ fn variablesbc() {
         global_load.address,
         Operand::ConstantOperand(ConstantRef::new(Constant::GlobalReference {
             name: Name::from("global"),
-            ty: module.types.i32()
+            ty: module.types.i32(),
+            kind: GlobalKind::GlobalVariable,
+            addr_space: 0,
         }))
     );
     assert_eq!(module.type_of(global_load), module.types.i32());
@@ -1310,7 +1710,9 @@ fn variablesbc() {
         global_store.address,
         Operand::ConstantOperand(ConstantRef::new(Constant::GlobalReference {
             name: Name::from("global"),
-            ty: module.types.i32()
+            ty: module.types.i32(),
+            kind: GlobalKind::GlobalVariable,
+            addr_space: 0,
         }))
     );
     assert_eq!(module.type_of(global_store), module.types.void());
@@ -1517,8 +1919,13 @@ fn rustbc() {
         ],
         false,
     );
-    if let Either::Right(Operand::ConstantOperand(cref)) = &call.function {
-        if let Constant::GlobalReference { ref name, ref ty } = cref.as_ref() {
+    if let Either::Right(Operand::ConstantOperand(cref)) =
+        call.function.as_ref().map_right(|op| op.as_ref())
+    {
+        if let Constant::GlobalReference {
+            ref name, ref ty, ..
+        } = cref.as_ref()
+        {
             assert_eq!(name, &Name::from("_ZN68_$LT$alloc..vec..Vec$LT$T$GT$$u20$as$u20$core..ops..deref..Deref$GT$5deref17h378128d7d9378466E"));
             match ty.as_ref() {
                 Type::FuncType {
@@ -1795,7 +2202,7 @@ fn simple_linked_list_g() {
     );
     assert_eq!(
         &func.basic_blocks[0].instrs[7].to_string(),
-        "call @llvm.dbg.declare(<metadata>, <metadata>, <metadata>) (with debugloc)",
+        "call @llvm.dbg.declare(metadata i32* %2, metadata !0, metadata !10) (with debugloc)",
     );
 
     // the tenth instruction should have a different debugloc
@@ -2237,92 +2644,3563 @@ fn param_and_func_attributes() {
     assert_eq!(f.function_attributes[0], FunctionAttribute::StrictFP);
 }
 
-#[cfg(feature = "llvm-11-or-greater")]
+#[cfg(feature = "llvm-14")]
 #[test]
-fn float_types() {
+fn target_attributes() {
     init_logging();
-    let path = llvm_bc_dir().join("float_types.bc");
+    let path = llvm_bc_dir().join("target_attributes.bc");
     let module = Module::from_bc_path(&path).expect("Failed to parse module");
 
-    let f = module.get_func_by_name("takes_half").unwrap();
-    assert_eq!(
-        module.type_of(f.parameters.get(0).unwrap()),
-        module.types.fp(FPType::Half)
-    );
-    let f = module.get_func_by_name("takes_bfloat").unwrap();
-    assert_eq!(
-        module.type_of(f.parameters.get(0).unwrap()),
-        module.types.fp(FPType::BFloat)
-    );
-    let f = module.get_func_by_name("takes_float").unwrap();
-    assert_eq!(
-        module.type_of(f.parameters.get(0).unwrap()),
-        module.types.fp(FPType::Single)
-    );
-    let f = module.get_func_by_name("takes_double").unwrap();
-    assert_eq!(
-        module.type_of(f.parameters.get(0).unwrap()),
-        module.types.fp(FPType::Double)
-    );
-    let f = module.get_func_by_name("takes_fp128").unwrap();
-    assert_eq!(
-        module.type_of(f.parameters.get(0).unwrap()),
-        module.types.fp(FPType::FP128)
-    );
-    let f = module.get_func_by_name("takes_x86_fp80").unwrap();
+    let f = module.get_func_by_name("f.mustprogress").unwrap();
+    assert_eq!(f.function_attributes, vec![FunctionAttribute::MustProgress]);
+    let f = module.get_func_by_name("f.hot").unwrap();
+    assert_eq!(f.function_attributes, vec![FunctionAttribute::Hot]);
+    let f = module.get_func_by_name("f.noprofile").unwrap();
+    assert_eq!(f.function_attributes, vec![FunctionAttribute::NoProfile]);
+    let f = module.get_func_by_name("f.vscale_range").unwrap();
     assert_eq!(
-        module.type_of(f.parameters.get(0).unwrap()),
-        module.types.fp(FPType::X86_FP80)
+        f.function_attributes,
+        vec![FunctionAttribute::VScaleRange {
+            min: 2,
+            max: Some(4)
+        }]
     );
-    let f = module.get_func_by_name("takes_ppc_fp128").unwrap();
+    let f = module.get_func_by_name("f.vscale_range_unbounded").unwrap();
     assert_eq!(
-        module.type_of(f.parameters.get(0).unwrap()),
-        module.types.fp(FPType::PPC_FP128)
+        f.function_attributes,
+        vec![FunctionAttribute::VScaleRange { min: 1, max: None }]
     );
 
-    let f = module.get_func_by_name("returns_half").unwrap();
-    assert_eq!(
-        f.return_type,
-        module.types.pointer_to(module.types.fp(FPType::Half))
-    );
-    let f = module.get_func_by_name("returns_bfloat").unwrap();
+    // attributes we don't have a variant for are identified by kind ID
+    let f = module.get_func_by_name("f.nosanitize_coverage").unwrap();
+    assert_eq!(f.function_attributes.len(), 1);
+    match f.function_attributes[0] {
+        FunctionAttribute::EnumAttribute { kind_id, value } => {
+            assert_ne!(kind_id, 0);
+            assert_eq!(value, 0);
+        },
+        ref attr => panic!("Expected an EnumAttribute; got {:?}", attr),
+    }
+    let f = module.get_func_by_name("f.swiftasync").unwrap();
+    assert_eq!(f.parameters[0].attributes.len(), 1);
+    match f.parameters[0].attributes[0] {
+        ParameterAttribute::EnumAttribute { kind_id, value } => {
+            assert_ne!(kind_id, 0);
+            assert_eq!(value, 0);
+        },
+        ref attr => panic!("Expected an EnumAttribute; got {:?}", attr),
+    }
+
+    let f = module.get_func_by_name("f.target").unwrap();
+    assert_eq!(f.get_string_attribute("target-cpu"), Some("x86-64"));
     assert_eq!(
-        f.return_type,
-        module.types.pointer_to(module.types.fp(FPType::BFloat))
+        f.get_string_attribute("target-features"),
+        Some("+sse2,+cx8")
     );
-    let f = module.get_func_by_name("returns_float").unwrap();
+    assert_eq!(f.get_string_attribute("tune-cpu"), None);
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn type_attributes() {
+    init_logging();
+    let path = llvm_bc_dir().join("type_attributes.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let struct_ty = module.types.named_struct("struct.T");
+    let array_ty = module.types.array_of(module.types.i32(), 4);
+
+    let f = module.get_func_by_name("f.byref").unwrap();
+    let attrs = &f.parameters[0].attributes;
+    assert_eq!(attrs.len(), 2);
+    assert!(attrs.contains(&ParameterAttribute::ByRef(struct_ty.clone())));
+    assert!(attrs.contains(&ParameterAttribute::Alignment(8)));
+    let byref = attrs
+        .iter()
+        .find(|attr| matches!(attr, ParameterAttribute::ByRef(_)))
+        .unwrap();
+    assert_eq!(byref.type_value(), Some(&struct_ty));
+    assert_eq!(byref.int_value(), None);
+    let align = attrs
+        .iter()
+        .find(|attr| matches!(attr, ParameterAttribute::Alignment(_)))
+        .unwrap();
+    assert_eq!(align.int_value(), Some(8));
+    assert_eq!(align.type_value(), None);
+
+    let f = module.get_func_by_name("f.elementtype").unwrap();
+    let call: &instruction::Call = &f.basic_blocks[0].instrs[0]
+        .clone()
+        .try_into()
+        .expect("Should be a call");
     assert_eq!(
-        f.return_type,
-        module.types.pointer_to(module.types.fp(FPType::Single))
+        call.arguments[0].1,
+        vec![ParameterAttribute::ElementType(array_ty.clone())]
     );
-    let f = module.get_func_by_name("returns_double").unwrap();
+    assert_eq!(call.arguments[0].1[0].type_value(), Some(&array_ty));
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn callsite_attributes() {
+    init_logging();
+    let path = llvm_bc_dir().join("callsite_attributes.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let struct_ty = module.types.named_struct("struct.T");
+    let func = module.get_func_by_name("caller").unwrap();
+    let bb = &func.basic_blocks[0];
+
+    let call = bb.instrs[0].as_call().expect("Expected a call");
     assert_eq!(
-        f.return_type,
-        module.types.pointer_to(module.types.fp(FPType::Double))
+        call.argument_attributes(0),
+        [
+            ParameterAttribute::ByVal(struct_ty.clone()),
+            ParameterAttribute::Alignment(8)
+        ]
     );
-    let f = module.get_func_by_name("returns_fp128").unwrap();
+    assert_eq!(call.argument_attributes(1), [ParameterAttribute::SignExt]);
+    assert!(call.argument_attributes(2).is_empty());
+    assert_eq!(call.byval_type(0), Some(&struct_ty));
+    assert_eq!(call.byval_type(1), None);
+    assert_eq!(call.sret_argument(), None);
+    assert!(call.return_attributes.is_empty());
+
+    let call = bb.instrs[1].as_call().expect("Expected a call");
+    assert_eq!(call.return_attributes, [ParameterAttribute::ZeroExt]);
+    assert_eq!(call.argument_attributes(0), [ParameterAttribute::NoUndef]);
+
+    let invoke = bb.term.as_invoke().expect("Expected an invoke");
+    assert_eq!(invoke.sret_argument(), Some((0, &struct_ty)));
+    assert!(invoke
+        .argument_attributes(0)
+        .contains(&ParameterAttribute::NoAlias));
+    assert!(invoke.argument_attributes(1).is_empty());
+    assert_eq!(invoke.byval_type(0), None);
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn call_arguments() {
+    init_logging();
+    let path = llvm_bc_dir().join("callsite_attributes.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let i8_ty = module.types.int(8);
+    let i32_ty = module.types.i32();
+
+    let narrow = module.get_func_by_name("narrow").unwrap();
+    let params: Vec<_> = narrow.params_and_types().collect();
+    assert_eq!(params, [(&Name::from("x"), &i8_ty)]);
+
+    let func = module.get_func_by_name("caller").unwrap();
+    let bb = &func.basic_blocks[0];
+
+    let call = bb.instrs[0].as_call().expect("Expected a call");
+    let args: Vec<_> = call.args_and_attrs().collect();
+    assert_eq!(args.len(), 2);
+    assert_eq!(args[0].0, call.arguments[0].0.as_ref());
+    assert_eq!(args[0].1.len(), 2);
+    assert_eq!(args[1].1, [ParameterAttribute::SignExt]);
+    assert!(call.variadic_arguments().is_empty());
+
+    let printf = bb.instrs[2].as_call().expect("Expected a call");
+    let types: Vec<_> = printf.args_and_param_types().map(|(_, ty)| ty).collect();
     assert_eq!(
-        f.return_type,
-        module.types.pointer_to(module.types.fp(FPType::FP128))
+        types,
+        [Some(&module.types.pointer_to(i8_ty.clone())), None, None]
     );
-    let f = module.get_func_by_name("returns_x86_fp80").unwrap();
+    let variadic = printf.variadic_arguments();
+    assert_eq!(variadic.len(), 2);
+    assert_eq!(module.type_of(&variadic[0].0), i32_ty);
+    assert_eq!(module.type_of(&variadic[1].0), module.types.i64());
+
+    let invoke = bb.term.as_invoke().expect("Expected an invoke");
+    assert_eq!(invoke.args_and_param_types().count(), 2);
+    assert!(invoke.args_and_param_types().all(|(_, ty)| ty.is_some()));
+    assert!(invoke.variadic_arguments().is_empty());
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn fneg_constant_expr() {
+    init_logging();
+    let path = llvm_bc_dir().join("fneg_constexpr.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let var = module.get_global_var_by_name(&Name::from("y")).unwrap();
+    let init = var.initializer.as_ref().expect("Expected an initializer");
+    match init.as_ref() {
+        Constant::FNeg(fneg) => {
+            assert!(matches!(fneg.operand.as_ref(), Constant::BitCast(_)));
+        },
+        c => panic!("Expected an FNeg, got {:?}", c),
+    }
+    assert_eq!(module.type_of(init), module.types.single());
     assert_eq!(
-        f.return_type,
-        module.types.pointer_to(module.types.fp(FPType::X86_FP80))
+        init.to_string(),
+        "fneg (bitcast (ptrtoint (i32* @x to i32) to float))"
     );
-    let f = module.get_func_by_name("returns_ppc_fp128").unwrap();
+
+    #[cfg(feature = "native-reader")]
+    {
+        let native = Module::from_bc_path_native(&path).expect("Failed to parse module natively");
+        assert_eq!(native.global_vars, module.global_vars);
+    }
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn attribute_groups() {
+    init_logging();
+    let path = llvm_bc_dir().join("attribute_groups.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+
+    // numbered as in `llvm-dis` output
+    let groups: Vec<_> = module
+        .function_attribute_groups
+        .iter()
+        .map(|group| (group.group_id, group.attrs.clone()))
+        .collect();
     assert_eq!(
-        f.return_type,
-        module.types.pointer_to(module.types.fp(FPType::PPC_FP128))
+        groups,
+        vec![
+            (0, vec![FunctionAttribute::NoUnwind]),
+            (1, vec![FunctionAttribute::NoInline]),
+            (2, vec![FunctionAttribute::Cold]),
+            (3, vec![FunctionAttribute::MinimizeSize]),
+        ]
     );
+
+    let a = module.get_func_by_name("a").unwrap();
+    assert_eq!(a.function_attribute_group, Some(0));
+    let b = module.get_func_by_name("b").unwrap();
+    assert_eq!(b.function_attribute_group, Some(0));
+    let d = module.get_func_by_name("d").unwrap();
+    assert_eq!(d.function_attribute_group, None);
+    assert!(d.function_attributes.is_empty());
 }
 
+#[cfg(feature = "llvm-14")]
 #[test]
-fn datalayouts() {
+fn operand_bundles() {
     init_logging();
-    let path = llvm_bc_dir().join("hello.bc");
+    let path = llvm_bc_dir().join("operand_bundles.bc");
     let module = Module::from_bc_path(&path).expect("Failed to parse module");
-    let data_layout = &module.data_layout;
+    let func = module.get_func_by_name("g").unwrap();
+    let x = Operand::LocalOperand {
+        name: Name::from("x"),
+        ty: module.types.i32(),
+    };
+    let i32_const =
+        |v: u64| Operand::ConstantOperand(ConstantRef::new(Constant::Int { bits: 32, value: v }));
+
+    let bb = &func.basic_blocks[0];
+    let call: &instruction::Call = &bb.instrs[0].clone().try_into().expect("Should be a call");
+    assert_eq!(call.arguments.len(), 1);
+    assert_eq!(
+        call.operand_bundles,
+        vec![
+            instruction::OperandBundle {
+                tag: "deopt".into(),
+                args: vec![x.clone().into(), i32_const(7).into()],
+            },
+            instruction::OperandBundle {
+                tag: "gc-transition".into(),
+                args: vec![],
+            },
+        ]
+    );
+
+    // tags and arguments which need escaping or contain brackets
+    let call: &instruction::Call = &bb.instrs[1].clone().try_into().expect("Should be a call");
+    assert_eq!(call.operand_bundles.len(), 1);
+    assert_eq!(call.operand_bundles[0].tag, "my, \"tag\"");
+    assert_eq!(call.operand_bundles[0].args.len(), 2);
+
+    let call: &instruction::Call = &bb.instrs[2].clone().try_into().expect("Should be a call");
+    assert_eq!(call.operand_bundles, vec![]);
+
+    // a call whose only bundle has no operands
+    let call: &instruction::Call = &bb.instrs[3].clone().try_into().expect("Should be a call");
+    assert_eq!(
+        call.operand_bundles,
+        vec![instruction::OperandBundle {
+            tag: "gc-transition".into(),
+            args: vec![],
+        }]
+    );
+
+    let invoke: &terminator::Invoke = &bb.term.clone().try_into().expect("Should be an invoke");
+    assert_eq!(invoke.arguments, vec![(OperandRef::new(x), vec![])]);
+    assert_eq!(
+        invoke.operand_bundles,
+        vec![instruction::OperandBundle {
+            tag: "deopt".into(),
+            args: vec![i32_const(3).into()],
+        }]
+    );
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn funclet_eh() {
+    init_logging();
+    let path = llvm_bc_dir().join("funclets.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("f").unwrap();
+    let token = |name: &str| Operand::LocalOperand {
+        name: Name::from(name),
+        ty: module.types.token_type(),
+    };
+    let none = Operand::ConstantOperand(ConstantRef::new(Constant::TokenNone));
+
+    let dispatch = func.get_bb_by_name(&Name::from("dispatch")).unwrap();
+    let catchswitch: &terminator::CatchSwitch = &dispatch
+        .term
+        .clone()
+        .try_into()
+        .expect("Should be a catchswitch");
+    assert_eq!(catchswitch.parent_pad, none);
+    assert_eq!(
+        catchswitch.catch_handlers,
+        vec![Name::from("handler1"), Name::from("handler2")]
+    );
+    assert_eq!(catchswitch.default_unwind_dest, Some(Name::from("cleanup")));
+    assert_eq!(catchswitch.result, Name::from("cs"));
+    assert_eq!(module.type_of(&dispatch.term), module.types.token_type());
+
+    let handler1 = func.get_bb_by_name(&Name::from("handler1")).unwrap();
+    let catchpad: &instruction::CatchPad = &handler1.instrs[0]
+        .clone()
+        .try_into()
+        .expect("Should be a catchpad");
+    assert_eq!(catchpad.catch_switch, token("cs"));
+    assert_eq!(catchpad.args.len(), 3);
+    let catchret: &terminator::CatchRet = &handler1
+        .term
+        .clone()
+        .try_into()
+        .expect("Should be a catchret");
+    assert_eq!(catchret.catch_pad, token("cp1"));
+    assert_eq!(catchret.successor, Name::from("done"));
+
+    let handler2 = func.get_bb_by_name(&Name::from("handler2")).unwrap();
+    let invoke: &terminator::Invoke = &handler2
+        .term
+        .clone()
+        .try_into()
+        .expect("Should be an invoke");
+    assert_eq!(
+        invoke.operand_bundles,
+        vec![instruction::OperandBundle {
+            tag: "funclet".into(),
+            args: vec![token("cp2").into()],
+        }]
+    );
+
+    let inner = func.get_bb_by_name(&Name::from("inner")).unwrap();
+    let cleanuppad: &instruction::CleanupPad = &inner.instrs[0]
+        .clone()
+        .try_into()
+        .expect("Should be a cleanuppad");
+    assert_eq!(cleanuppad.parent_pad, token("cp2"));
+    assert!(cleanuppad.args.is_empty());
+    let cleanupret: &terminator::CleanupRet = &inner
+        .term
+        .clone()
+        .try_into()
+        .expect("Should be a cleanupret");
+    assert_eq!(cleanupret.cleanup_pad, token("inner.pad"));
+    assert_eq!(cleanupret.unwind_dest, Some(Name::from("cleanup")));
+
+    let cleanup = func.get_bb_by_name(&Name::from("cleanup")).unwrap();
+    let cleanuppad: &instruction::CleanupPad = &cleanup.instrs[0]
+        .clone()
+        .try_into()
+        .expect("Should be a cleanuppad");
+    assert_eq!(cleanuppad.parent_pad, none);
+    let cleanupret: &terminator::CleanupRet = &cleanup
+        .term
+        .clone()
+        .try_into()
+        .expect("Should be a cleanupret");
+    assert_eq!(cleanupret.unwind_dest, None);
+}
+
+#[test]
+#[cfg(feature = "llvm-14")]
+fn metadata_operands() {
+    init_logging();
+    let path = llvm_bc_dir().join("metadata_operands.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let node = |md: &Metadata| match md {
+        Metadata::Node(id) => &module.metadata_nodes[*id],
+        _ => panic!("Expected a metadata node; got {:?}", md),
+    };
+
+    let func = module.get_func_by_name("read_sp").unwrap();
+    let call: &instruction::Call = &func.basic_blocks[0].instrs[0]
+        .clone()
+        .try_into()
+        .expect("Should be a call");
+    let md = match call.arguments[0].0.as_ref() {
+        Operand::MetadataOperand(md) => md,
+        op => panic!("Expected a metadata operand; got {:?}", op),
+    };
+    assert_eq!(
+        module.type_of(&call.arguments[0].0),
+        module.types.metadata_type()
+    );
+    let (id, tuple) = node(md);
+    assert_eq!(
+        tuple,
+        &MetadataNode::Tuple(vec![Some(Metadata::String("sp".to_owned()))])
+    );
+    assert_eq!(
+        &call.arguments[0].0.to_string(),
+        &format!("metadata !{}", id)
+    );
+
+    let func = module.get_func_by_name("constrained").unwrap();
+    let call: &instruction::Call = &func.basic_blocks[0].instrs[0]
+        .clone()
+        .try_into()
+        .expect("Should be a call");
+    assert_eq!(
+        call.arguments[2].0,
+        Operand::MetadataOperand(Box::new(Metadata::String("round.dynamic".to_owned())))
+    );
+    assert_eq!(
+        &call.arguments[3].0.to_string(),
+        "metadata !\"fpexcept.strict\""
+    );
+
+    // a node which refers to itself
+    let func = module.get_func_by_name("self_referential").unwrap();
+    let call: &instruction::Call = &func.basic_blocks[0].instrs[0]
+        .clone()
+        .try_into()
+        .expect("Should be a call");
+    let md = match call.arguments[0].0.as_ref() {
+        Operand::MetadataOperand(md) => md,
+        op => panic!("Expected a metadata operand; got {:?}", op),
+    };
+    let (id, tuple) = node(md);
+    assert_eq!(
+        tuple,
+        &MetadataNode::Tuple(vec![
+            Some(Metadata::Node(*id)),
+            Some(Metadata::Value(Operand::ConstantOperand(ConstantRef::new(
+                Constant::Int { bits: 32, value: 7 }
+            )))),
+            None,
+        ])
+    );
+}
+
+#[test]
+#[cfg(feature = "llvm-14")]
+fn instr_locs() {
+    init_logging();
+    let path = llvm_bc_dir().join("linkedlist.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+
+    let total: usize = module
+        .functions
+        .iter()
+        .flat_map(|f| &f.basic_blocks)
+        .map(|bb| bb.instrs.len() + 1)
+        .sum();
+    let locs: Vec<InstrLoc> = module.instr_locs().collect();
+    assert_eq!(locs.len(), total);
+    assert!(locs.windows(2).all(|w| w[0] < w[1]));
+    assert!(locs.iter().all(|&loc| module.get_instr(loc).is_some()));
+
+    // locations can be used as keys for analysis results
+    let by_loc: HashMap<InstrLoc, String> = locs
+        .iter()
+        .map(|&loc| (loc, format!("{:?}", module.get_instr(loc).unwrap())))
+        .collect();
+    assert_eq!(by_loc.len(), total);
+
+    let func = module
+        .get_func_by_name("indirectly_recursive_type")
+        .unwrap();
+    let entry = module
+        .locate_block("indirectly_recursive_type", &func.basic_blocks[0].name)
+        .expect("Failed to locate block");
+    assert_eq!(entry, ModuleLoc::new(1, 0));
+    assert_eq!(module.get_block(entry), Some(&func.basic_blocks[0]));
+    assert_eq!(
+        module
+            .get_instr(entry.instr(0))
+            .and_then(InstrRef::as_instr),
+        Some(&func.basic_blocks[0].instrs[0])
+    );
+    assert_eq!(
+        module
+            .get_instr(entry.terminator())
+            .and_then(InstrRef::as_terminator),
+        Some(&func.basic_blocks[0].term)
+    );
+    let past_end = entry.instr(func.basic_blocks[0].instrs.len());
+    assert_eq!(module.get_instr(past_end), None);
+    assert_eq!(module.get_block(ModuleLoc::new(3, 0)), None);
+    assert_eq!(
+        module.locate_block("no_such_function", &Name::from(0)),
+        None
+    );
+}
+
+#[test]
+#[cfg(feature = "llvm-14")]
+fn all_instructions() {
+    init_logging();
+    let path = llvm_bc_dir().join("linkedlist.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+
+    let instrs: Vec<_> = module.all_instructions().collect();
+    let num_instrs: usize = module
+        .functions
+        .iter()
+        .flat_map(|f| &f.basic_blocks)
+        .map(|bb| bb.instrs.len())
+        .sum();
+    assert_eq!(instrs.len(), num_instrs);
+    // in order, and each with its containing function and block
+    let (f, bb, inst) = instrs[0];
+    assert_eq!(f, &module.functions[0]);
+    assert_eq!(bb, &module.functions[0].basic_blocks[0]);
+    assert_eq!(inst, &module.functions[0].basic_blocks[0].instrs[0]);
+    assert!(instrs
+        .iter()
+        .all(|(f, bb, inst)| f.basic_blocks.contains(bb) && bb.instrs.contains(inst)));
+
+    let terms: Vec<_> = module.all_terminators().collect();
+    let num_blocks: usize = module.functions.iter().map(|f| f.basic_blocks.len()).sum();
+    assert_eq!(terms.len(), num_blocks);
+    assert!(terms.iter().all(|(_, bb, term)| &bb.term == *term));
+    let returning_funcs: Vec<&str> = module
+        .all_terminators()
+        .filter(|(_, _, term)| matches!(term, Terminator::Ret(_)))
+        .map(|(f, _, _)| f.name.as_str())
+        .collect();
+    assert_eq!(returning_funcs.len(), module.functions.len());
+}
+
+#[test]
+#[cfg(feature = "llvm-14")]
+fn canonicalize() {
+    init_logging();
+    let mut module =
+        Module::from_bc_path(llvm_bc_dir().join("linkedlist.bc")).expect("Failed to parse module");
+    let mut module_g = Module::from_bc_path(llvm_bc_dir().join("linkedlist.bc-g"))
+        .expect("Failed to parse module");
+    module_g.strip_debug_info();
+    for func in &module_g.functions {
+        assert!(func.get_debug_loc().is_none());
+        for bb in &func.basic_blocks {
+            assert!(bb.instrs.iter().all(|i| i.get_debug_loc().is_none()));
+            assert!(bb.term.get_debug_loc().is_none());
+        }
+    }
+    // with the debug info and the `llvm.dbg.declare` calls gone, only the
+    // numbering of the locals differs
+    module.rename_locals(false);
+    module_g.rename_locals(false);
+    for (func, func_g) in module.functions.iter().zip(&module_g.functions) {
+        assert_eq!(func.parameters, func_g.parameters);
+        assert_eq!(func.basic_blocks, func_g.basic_blocks);
+    }
+
+    let module =
+        Module::from_bc_path(llvm_bc_dir().join("funclets.bc")).expect("Failed to parse module");
+    let mut func = module.get_func_by_name("f").unwrap().clone();
+    func.rename_locals(true);
+    let block_names: Vec<&Name> = func.basic_blocks.iter().map(|bb| &bb.name).collect();
+    assert!(block_names
+        .iter()
+        .all(|name| matches!(name, Name::Number(_))));
+    let dispatch = &func.basic_blocks[1];
+    let catchswitch: &terminator::CatchSwitch = &dispatch
+        .term
+        .clone()
+        .try_into()
+        .expect("Should be a catchswitch");
+    for handler in &catchswitch.catch_handlers {
+        assert!(block_names.contains(&handler));
+    }
+    let handler = func.get_bb_by_name(&catchswitch.catch_handlers[0]).unwrap();
+    let catchpad: &instruction::CatchPad = &handler.instrs[0]
+        .clone()
+        .try_into()
+        .expect("Should be a catchpad");
+    assert_eq!(
+        catchpad.catch_switch,
+        Operand::LocalOperand {
+            name: catchswitch.result.clone(),
+            ty: module.types.token_type(),
+        }
+    );
+
+    // renaming again has no further effect
+    let mut renamed_again = func.clone();
+    renamed_again.rename_locals(true);
+    assert_eq!(renamed_again, func);
+}
+
+#[test]
+#[cfg(feature = "llvm-14")]
+fn structural_hash() {
+    init_logging();
+    let path = llvm_bc_dir().join("structural_hash.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let hash = |name: &str| module.get_func_by_name(name).unwrap().structural_hash();
+    // names, linkage, and the numbering of locals don't matter
+    assert_eq!(hash("named"), hash("unnamed"));
+    // opcodes and predicates, constants, operand order, and topology do
+    assert_ne!(hash("named"), hash("other_constant"));
+    assert_ne!(hash("named"), hash("other_predicate"));
+    assert_ne!(hash("named"), hash("swapped_operands"));
+    assert_ne!(hash("named"), hash("swapped_branch"));
+
+    let mut func = module.get_func_by_name("named").unwrap().clone();
+    func.name = "renamed".into();
+    func.rename_locals(true);
+    assert_eq!(func.structural_hash(), hash("named"));
+
+    // neither does debug info
+    let module =
+        Module::from_bc_path(llvm_bc_dir().join("linkedlist.bc")).expect("Failed to parse module");
+    let module_g = Module::from_bc_path(llvm_bc_dir().join("linkedlist.bc-g"))
+        .expect("Failed to parse module");
+    for (func, func_g) in module.functions.iter().zip(&module_g.functions) {
+        assert_eq!(func.structural_hash(), func_g.structural_hash());
+    }
+}
+
+#[test]
+#[cfg(feature = "llvm-14")]
+fn isomorphism() {
+    init_logging();
+    let path = llvm_bc_dir().join("structural_hash.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let named = module.get_func_by_name("named").unwrap();
+    let unnamed = module.get_func_by_name("unnamed").unwrap();
+    let mapping = named
+        .is_isomorphic_to(unnamed)
+        .expect("Should be isomorphic");
+    assert_eq!(mapping.len(), 8);
+    assert_eq!(mapping[&Name::from("x")], Name::from(0));
+    assert_eq!(mapping[&Name::from("entry")], Name::from(2));
+    assert_eq!(mapping[&Name::from("then")], Name::from(4));
+    assert_eq!(mapping[&Name::from("result")], Name::from(7));
+    let reverse = unnamed
+        .is_isomorphic_to(named)
+        .expect("Should be isomorphic");
+    assert_eq!(reverse[&Name::from(6)], Name::from("else"));
+    assert!(named.is_isomorphic_to(named).is_some());
+    for other in &[
+        "other_constant",
+        "other_predicate",
+        "swapped_operands",
+        "swapped_branch",
+    ] {
+        let other = module.get_func_by_name(other).unwrap();
+        assert!(named.is_isomorphic_to(other).is_none());
+    }
+
+    // a function and a debug-info version of it
+    let module =
+        Module::from_bc_path(llvm_bc_dir().join("linkedlist.bc")).expect("Failed to parse module");
+    let module_g = Module::from_bc_path(llvm_bc_dir().join("linkedlist.bc-g"))
+        .expect("Failed to parse module");
+    for (func, func_g) in module.functions.iter().zip(&module_g.functions) {
+        assert!(func.is_isomorphic_to(func_g).is_some());
+    }
+}
+
+#[test]
+#[cfg(feature = "llvm-14")]
+fn module_inline_asm() {
+    init_logging();
+    let path = llvm_bc_dir().join("module_asm.bc");
+    let mut module = Module::from_bc_path(&path).expect("Failed to parse module");
+    assert_eq!(
+        module.inline_assembly,
+        vec![".globl asm_func", "asm_func:", "  ret"]
+    );
+
+    module.append_inline_asm(".data\nasm_data:");
+    assert_eq!(module.inline_assembly.len(), 5);
+    assert_eq!(module.inline_assembly[3], ".data");
+    assert_eq!(module.inline_assembly[4], "asm_data:");
+
+    assert!(module.remove_inline_asm("  ret"));
+    assert!(!module.remove_inline_asm("  ret"));
+    assert_eq!(
+        module.inline_asm_string(),
+        ".globl asm_func\nasm_func:\n.data\nasm_data:\n"
+    );
+
+    // modules without inline assembly have no blocks
+    let module =
+        Module::from_bc_path(llvm_bc_dir().join("hello.bc")).expect("Failed to parse module");
+    assert!(module.inline_assembly.is_empty());
+    assert_eq!(module.inline_asm_string(), "");
+}
+
+#[test]
+#[cfg(feature = "llvm-14")]
+fn result_types() {
+    init_logging();
+    for file in &["linkedlist.bc", "funclets.bc", "operand_bundles.bc"] {
+        let module =
+            Module::from_bc_path(llvm_bc_dir().join(file)).expect("Failed to parse module");
+        let result_types = ResultTypes::new(&module);
+        for loc in module.instr_locs() {
+            let expected = match module.get_instr(loc).unwrap() {
+                InstrRef::Instr(inst) => module.type_of(inst),
+                InstrRef::Terminator(term) => module.type_of(term),
+            };
+            assert_eq!(result_types.get(loc), Some(&expected));
+        }
+    }
+
+    let module =
+        Module::from_bc_path(llvm_bc_dir().join("linkedlist.bc")).expect("Failed to parse module");
+    let result_types = ResultTypes::new(&module);
+    let entry = ModuleLoc::new(0, 0);
+    let num_instrs = module.functions[0].basic_blocks[0].instrs.len();
+    assert_eq!(result_types.get(entry.instr(num_instrs)), None);
+    assert_eq!(
+        result_types.get(entry.terminator()),
+        Some(&module.types.void())
+    );
+    assert_eq!(result_types.get(ModuleLoc::new(3, 0).terminator()), None);
+}
+
+#[test]
+#[cfg(feature = "llvm-14")]
+fn types_shared_between_threads() {
+    init_logging();
+    let path = llvm_bc_dir().join("linkedlist.bc");
+    let module = Arc::new(Module::from_bc_path(&path).expect("Failed to parse module"));
+    let threads: Vec<_> = (0 .. 4)
+        .map(|_| {
+            let module = Arc::clone(&module);
+            thread::spawn(move || {
+                let types = &module.types;
+                // walking the mutually recursive structs only reads shared
+                // data, even while other threads are adding types
+                let mut name = "struct.NodeA".to_owned();
+                for _ in 0 .. 10 {
+                    let next = match types.named_struct_def(&name) {
+                        Some(NamedStructDef::Defined(def)) => match def.as_ref() {
+                            Type::StructType { element_types, .. } => {
+                                element_types[1].pointee_type()
+                            },
+                            ty => panic!("Expected a struct, got {:?}", ty),
+                        },
+                        def => panic!("Expected a defined struct, got {:?}", def),
+                    };
+                    name = match next.as_deref() {
+                        Some(Type::NamedStructType { name }) => name.clone(),
+                        ty => panic!("Expected a named struct, got {:?}", ty),
+                    };
+                }
+                assert_eq!(name, "struct.NodeA");
+                // an existing type, and one which isn't used in the module
+                (
+                    types.i32(),
+                    types.pointer_to(types.array_of(types.i32(), 1234)),
+                )
+            })
+        })
+        .collect();
+    let results: Vec<(TypeRef, TypeRef)> = threads
+        .into_iter()
+        .map(|thread| thread.join().expect("Thread panicked"))
+        .collect();
+
+    // every thread gets the same interned `Type` objects, and so does the
+    // module itself
+    let i32_ty = &module.functions[0].return_type;
+    let new_ty = module
+        .types
+        .pointer_to(module.types.array_of(module.types.i32(), 1234));
+    for (i32_result, new_result) in &results {
+        assert!(std::ptr::eq(i32_result.as_ref(), i32_ty.as_ref()));
+        assert!(std::ptr::eq(new_result.as_ref(), new_ty.as_ref()));
+    }
+}
+
+#[test]
+fn float_constants_compare_by_bits() {
+    let nan = Constant::Float(Float::Double(f64::NAN));
+    assert_eq!(nan, nan.clone());
+    assert_ne!(
+        Constant::Float(Float::Double(0.0)),
+        Constant::Float(Float::Double(-0.0))
+    );
+    assert_ne!(Float::Single(1.0), Float::Double(1.0));
+
+    let set: HashSet<Constant> = vec![
+        nan.clone(),
+        nan,
+        Constant::Float(Float::Single(1.0)),
+        Constant::Float(Float::Single(1.0)),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(set.len(), 2);
+}
+
+#[test]
+#[cfg(feature = "llvm-14")]
+fn ir_as_hash_keys() {
+    init_logging();
+    let path = llvm_bc_dir().join("linkedlist.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("simple_linked_list").unwrap();
+
+    // each instruction (or terminator) defining a value is unique, so each is
+    // distinct as a key
+    let instrs: HashSet<&Instruction> = func
+        .basic_blocks
+        .iter()
+        .flat_map(|bb| &bb.instrs)
+        .filter(|inst| inst.try_get_result().is_some())
+        .collect();
+    let num_results = func
+        .basic_blocks
+        .iter()
+        .flat_map(|bb| &bb.instrs)
+        .filter_map(|inst| inst.try_get_result())
+        .count();
+    assert_eq!(instrs.len(), num_results);
+
+    let mut counts: HashMap<&Terminator, usize> = HashMap::new();
+    for bb in &func.basic_blocks {
+        *counts.entry(&bb.term).or_default() += 1;
+    }
+    assert_eq!(counts.values().sum::<usize>(), func.basic_blocks.len());
+
+    let operand = Operand::LocalOperand {
+        name: Name::from(0),
+        ty: module.types.i32(),
+    };
+    let operands: HashSet<Operand> = vec![operand.clone(), operand].into_iter().collect();
+    assert_eq!(operands.len(), 1);
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn matchers() {
+    init_logging();
+    let path = llvm_bc_dir().join("matchers.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("f").unwrap();
+    let instrs = &func.basic_blocks[0].instrs;
+
+    // %a = add i32 %x, 0
+    assert!(m_add(m_value(), m_constant_int(0)).matches_instr(&instrs[0]));
+    assert!(m_add(m_local("x"), m_constant()).matches_instr(&instrs[0]));
+    assert!(!m_add(m_value(), m_constant_int(1)).matches_instr(&instrs[0]));
+    assert!(!m_sub(m_value(), m_value()).matches_instr(&instrs[0]));
+
+    // %c = icmp slt i32 %b, 10
+    let pat = m_icmp(
+        IntPredicate::SLT,
+        m_mul(m_value(), m_value()),
+        m_constant_int(10),
+    );
+    assert!(
+        !pat.matches_instr(&instrs[2]),
+        "should not look through %b without an SsaGraph"
+    );
+    let ssa = SsaGraph::new(func);
+    assert!(pat.matches_instr_in(&instrs[2], &ssa));
+    assert!(!m_icmp(IntPredicate::EQ, m_value(), m_value()).matches_instr(&instrs[2]));
+
+    // %d = select i1 %c, i32 %a, i32 %y
+    let pat = m_select(
+        m_icmp(IntPredicate::SLT, m_value(), m_value()),
+        m_add(m_local("x"), m_constant_int(0)),
+        m_local("y"),
+    );
+    assert!(pat.matches_instr_in(&instrs[3], &ssa));
+
+    // %e = getelementptr inbounds %struct.S, %struct.S* @g, i64 0, i32 1, i64 2
+    assert!(m_gep(m_constant()).matches_instr(&instrs[4]));
+    let gep_indices = || -> Vec<Box<dyn Matcher>> {
+        vec![
+            Box::new(m_constant_int(0)),
+            Box::new(m_constant_int(1)),
+            Box::new(m_constant_int(2)),
+        ]
+    };
+    assert!(m_gep(m_value())
+        .with_indices(gep_indices())
+        .matches_instr(&instrs[4]));
+    assert!(!m_gep(m_value())
+        .with_indices(vec![Box::new(m_constant_int(0))])
+        .matches_instr(&instrs[4]));
+
+    // %g = zext i1 %c to i32
+    assert!(m_zext(m_local("c")).matches_instr(&instrs[6]));
+    assert!(
+        m_zext(m_icmp(IntPredicate::SLT, m_value(), m_value())).matches_instr_in(&instrs[6], &ssa)
+    );
+    assert!(!m_sext(m_value()).matches_instr(&instrs[6]));
+
+    // the same matchers work on constant expressions
+    let initializer = |name: &str| {
+        module
+            .get_global_var_by_name(&Name::from(name))
+            .unwrap()
+            .initializer
+            .clone()
+            .unwrap()
+    };
+    let p = initializer("p");
+    assert!(m_gep(m_constant())
+        .with_indices(gep_indices())
+        .matches_constant(&p));
+    let q = initializer("q");
+    assert!(m_add(m_ptrtoint(m_value()), m_constant_int(1)).matches_constant(&q));
+    assert!(!m_add(m_ptrtoint(m_value()), m_constant_int(0)).matches_constant(&q));
+    let r = initializer("r");
+    assert!(m_icmp(
+        IntPredicate::ULT,
+        m_ptrtoint(m_value()),
+        m_ptrtoint(m_value())
+    )
+    .matches_constant(&r));
+    assert!(m_combine_or(
+        m_add(m_value(), m_value()),
+        m_icmp(IntPredicate::ULT, m_value(), m_value())
+    )
+    .matches_constant(&r));
+    assert!(!m_combine_and(m_constant(), m_add(m_value(), m_value())).matches_constant(&r));
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn instruction_operands() {
+    init_logging();
+    let path = llvm_bc_dir().join("matchers.bc");
+    let mut module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("f").unwrap();
+    let bb = &func.basic_blocks[0];
+    let local_names = |inst: &Instruction| -> Vec<String> {
+        inst.operands()
+            .map(|op| match op {
+                Operand::LocalOperand { name, .. } => name.to_string(),
+                Operand::ConstantOperand(c) => c.to_string(),
+                Operand::MetadataOperand(md) => md.to_string(),
+            })
+            .collect()
+    };
+
+    // %a = add i32 %x, 0
+    assert_eq!(local_names(&bb.instrs[0]), vec!["%x", "i32 0"]);
+    assert_eq!(bb.instrs[0].defined_name(), Some(&Name::from("a")));
+    // %d = select i1 %c, i32 %a, i32 %y
+    assert_eq!(local_names(&bb.instrs[3]), vec!["%c", "%a", "%y"]);
+    // %e = getelementptr inbounds %struct.S, %struct.S* @g, i64 0, i32 1, i64 2
+    assert_eq!(bb.instrs[4].operands().count(), 4);
+    // ret i32 %d
+    let ret_operands: Vec<&Operand> = bb.term.operands().collect();
+    assert_eq!(ret_operands.len(), 1);
+    assert!(
+        matches!(ret_operands[0], Operand::LocalOperand { name, .. } if *name == Name::from("d"))
+    );
+    assert_eq!(bb.term.defined_name(), None);
+
+    // replace every use of %a with %x
+    let func = &mut module.functions[0];
+    for inst in &mut func.basic_blocks[0].instrs {
+        for op in inst.operands_mut() {
+            if let Operand::LocalOperand { name, .. } = op {
+                if *name == Name::from("a") {
+                    *name = Name::from("x");
+                }
+            }
+        }
+    }
+    let bb = &func.basic_blocks[0];
+    assert_eq!(local_names(&bb.instrs[1]), vec!["%x", "%y"]);
+    assert_eq!(local_names(&bb.instrs[3]), vec!["%c", "%x", "%y"]);
+    assert_eq!(bb.instrs[0].defined_name(), Some(&Name::from("a")));
+
+    // the callee, arguments, and operand bundle arguments of calls and
+    // invokes are all operands
+    let path = llvm_bc_dir().join("operand_bundles.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("g").unwrap();
+    let bb = &func.basic_blocks[0];
+    assert_eq!(bb.instrs[0].operands().count(), 4);
+    assert_eq!(bb.instrs[2].operands().count(), 2);
+    assert_eq!(bb.term.operands().count(), 3);
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn global_reference_kinds() {
+    init_logging();
+    let path = llvm_bc_dir().join("global_kinds.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("uses").unwrap();
+    let kinds: Vec<(&Name, GlobalKind)> = func.basic_blocks[0]
+        .instrs
+        .iter()
+        .flat_map(|inst| inst.operands())
+        .filter_map(|op| match op.as_constant() {
+            Some(Constant::GlobalReference { name, kind, .. }) => Some((name, *kind)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            (&Name::from("var"), GlobalKind::GlobalVariable),
+            (&Name::from("alias"), GlobalKind::GlobalAlias),
+            (&Name::from("ifunc"), GlobalKind::GlobalIFunc),
+            (&Name::from("impl"), GlobalKind::Function),
+        ]
+    );
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn address_spaces() {
+    init_logging();
+    let path = llvm_bc_dir().join("addrspace.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("f").unwrap();
+    let bb = &func.basic_blocks[0];
+    let i32_in = |addr_space| {
+        module
+            .types
+            .pointer_in_addr_space(module.types.i32(), addr_space)
+    };
+
+    // a getelementptr instruction stays in the address space of its base
+    let gep: &instruction::GetElementPtr =
+        &bb.instrs[0].clone().try_into().expect("Should be a GEP");
+    assert_eq!(module.type_of(gep), i32_in(1));
+    assert_eq!(module.type_of(gep).addr_space(), Some(1));
+    assert_eq!(&module.type_of(gep).to_string(), "i32 addrspace(1)*");
+
+    // as does a constant getelementptr on a global
+    let load: &instruction::Load = &bb.instrs[2].clone().try_into().expect("Should be a load");
+    assert_eq!(module.type_of(&load.address), i32_in(3));
+    match load.address.as_constant() {
+        Some(Constant::GetElementPtr(gep)) => match gep.address.as_ref() {
+            Constant::GlobalReference { addr_space, .. } => assert_eq!(*addr_space, 3),
+            c => panic!("Expected a GlobalReference; got {:?}", c),
+        },
+        c => panic!("Expected a constant GEP; got {:?}", c),
+    }
+
+    // null pointers keep their address space
+    let icmp: &instruction::ICmp = &bb.instrs[3].clone().try_into().expect("Should be an icmp");
+    assert_eq!(module.type_of(&icmp.operand1), i32_in(1));
+    assert_eq!(&icmp.operand1.to_string(), "i32 addrspace(1)* null");
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn avr_module() {
+    init_logging();
+    let path = llvm_bc_dir().join("avr.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    assert_eq!(module.data_layout.program_address_space, 1);
+
+    // functions are in program memory, with their calling conventions
+    let isr = module.get_func_by_name("isr").unwrap();
+    assert_eq!(isr.addr_space, 1);
+    assert_eq!(isr.calling_convention, CallingConvention::AVR_Intr);
+    let numbered = module.get_func_by_name("numbered").unwrap();
+    assert_eq!(numbered.calling_convention, CallingConvention::Numbered(99));
+
+    // globals in program memory, and pointers to them
+    let counter = module
+        .global_vars
+        .iter()
+        .find(|var| var.name == Name::from("counter"))
+        .unwrap();
+    assert_eq!(counter.addr_space, 1);
+    assert_eq!(counter.ty.addr_space(), Some(1));
+    let table = module
+        .global_vars
+        .iter()
+        .find(|var| var.name == Name::from("table"))
+        .unwrap();
+    let fn_ptr = module.types.pointer_in_addr_space(
+        module
+            .types
+            .func_type(module.types.void(), vec![module.types.i8()], false),
+        1,
+    );
+    assert_eq!(
+        module.type_of(table.initializer.as_ref().unwrap()),
+        module.types.array_of(fn_ptr.clone(), 2)
+    );
+
+    // call sites
+    let caller = module.get_func_by_name("caller").unwrap();
+    assert_eq!(caller.parameters[0].ty, fn_ptr);
+    let instrs = &caller.basic_blocks[0].instrs;
+    let call: &instruction::Call = (&instrs[0]).try_into().expect("Should be a call");
+    assert_eq!(call.calling_convention, CallingConvention::AVR_Intr);
+    assert_eq!(
+        module.type_of(call.function.as_ref().right().unwrap()),
+        fn_ptr
+    );
+    let call: &instruction::Call = (&instrs[1]).try_into().expect("Should be a call");
+    assert_eq!(call.calling_convention, CallingConvention::Numbered(99));
+    let call: &instruction::Call = (&instrs[2]).try_into().expect("Should be a call");
+    assert_eq!(call.calling_convention, CallingConvention::C);
+    let load: &instruction::Load = (&instrs[3]).try_into().expect("Should be a load");
+    assert_eq!(
+        module.type_of(&load.address),
+        module.types.pointer_in_addr_space(module.types.i16(), 1)
+    );
+    assert_eq!(
+        &load.to_string(),
+        "%v = load i16 addrspace(1)* @counter, align 1"
+    );
+
+    // the functions in `avr-calling-conventions.ll.bc` from LLVM's tests
+    let path = Path::new("tests/llvm_bc/avr-calling-conventions.ll.bc");
+    let module = Module::from_bc_path(path).expect("Failed to parse module");
+    let conventions: Vec<_> = module
+        .functions
+        .iter()
+        .map(|f| (f.name.as_str(), f.calling_convention))
+        .collect();
+    assert_eq!(
+        conventions,
+        vec![
+            ("foo", CallingConvention::AVR_Intr),
+            ("bar", CallingConvention::AVR_Signal),
+            ("baz", CallingConvention::C),
+        ]
+    );
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn wasm_module() {
+    init_logging();
+    let path = llvm_bc_dir().join("wasm.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    assert_eq!(
+        module.target_triple.as_deref(),
+        Some("wasm32-unknown-unknown")
+    );
+    assert_eq!(module.data_layout.program_address_space, 0);
+    assert_eq!(module.data_layout.alignments.ptr_alignment(0).size, 32);
+    assert!(module.functions.iter().all(|f| f.addr_space == 0));
+
+    let ext = module
+        .global_vars
+        .iter()
+        .find(|var| var.name == Name::from("ext"))
+        .unwrap();
+    assert_eq!(ext.addr_space, 1);
+
+    let callee = module.get_func_by_name("callee").unwrap();
+    assert_eq!(callee.calling_convention, CallingConvention::Swift);
+    let caller = module.get_func_by_name("caller").unwrap();
+    assert_eq!(
+        caller.parameters[0].ty,
+        module.types.pointer_in_addr_space(module.types.i32(), 1)
+    );
+    let instrs = &caller.basic_blocks[0].instrs;
+    let call: &instruction::Call = (&instrs[0]).try_into().expect("Should be a call");
+    assert_eq!(call.calling_convention, CallingConvention::Swift);
+    let call: &instruction::Call = (&instrs[2]).try_into().expect("Should be a call");
+    assert_eq!(call.calling_convention, CallingConvention::C);
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn global_ctors_and_dtors() {
+    init_logging();
+    let path = llvm_bc_dir().join("ctors.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    assert_eq!(
+        module.global_ctors(),
+        vec![
+            (65535, Name::from("init_a"), Some(Name::from("data"))),
+            (101, Name::from("init_b"), None),
+            (200, Name::from("init_c"), None),
+        ]
+    );
+    assert_eq!(
+        module.global_dtors(),
+        vec![(65535, Name::from("fini"), None)]
+    );
+
+    let module =
+        Module::from_bc_path(llvm_bc_dir().join("hello.bc")).expect("Failed to parse module");
+    assert!(module.global_ctors().is_empty());
+    assert!(module.global_dtors().is_empty());
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn used_globals() {
+    init_logging();
+    let path = llvm_bc_dir().join("used.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    assert_eq!(
+        module.used_globals(),
+        vec![
+            Name::from("kept"),
+            Name::from("kept_too"),
+            Name::from("handler"),
+        ]
+    );
+    assert_eq!(
+        module.compiler_used_globals(),
+        vec![Name::from("compiler_only")]
+    );
+
+    let module =
+        Module::from_bc_path(llvm_bc_dir().join("hello.bc")).expect("Failed to parse module");
+    assert!(module.used_globals().is_empty());
+    assert!(module.compiler_used_globals().is_empty());
+}
+
+#[cfg(all(feature = "llvm-14", feature = "native-reader"))]
+#[test]
+fn native_reader() {
+    init_logging();
+    let path = llvm_bc_dir().join("native_reader.bc");
+    let expected = Module::from_bc_path(&path).expect("Failed to parse module");
+    let module = Module::from_bc_path_native(&path).expect("Failed to parse module natively");
+    assert_eq!(module.name, expected.name);
+    assert_eq!(module.source_file_name, "native_reader.c");
+    assert_eq!(module.target_triple, expected.target_triple);
+    assert_eq!(module.data_layout, expected.data_layout);
+    // metadata isn't read, and LLVM isn't involved
+    assert!(module.producers.is_empty());
+    assert_eq!(module.llvm_version_parsed_with(), None);
+    assert_eq!(module.global_vars, expected.global_vars);
+    assert_eq!(module.global_aliases, expected.global_aliases);
+    assert_eq!(module.functions.len(), expected.functions.len());
+    for (func, expected) in module.functions.iter().zip(&expected.functions) {
+        assert_eq!(func, expected);
+    }
+    for name in &["points", "recurse"] {
+        assert!(module.get_func_by_name(name).is_some());
+    }
+
+    let bitcode = std::fs::read(&path).unwrap();
+    let from_bytes = Module::from_bc_bytes_native(&bitcode).expect("Failed to parse bytes");
+    assert_eq!(from_bytes.name, "");
+    assert_eq!(from_bytes.functions, module.functions);
+
+    // exception handling is outside the subset the native reader supports
+    match Module::from_bc_path_native(llvm_bc_dir().join("funclets.bc")) {
+        Err(Error::Unsupported { function, .. }) => assert!(function.is_some()),
+        Err(err) => panic!("Expected Error::Unsupported, got {:?}", err),
+        Ok(_) => panic!("Expected Error::Unsupported, but parsing succeeded"),
+    }
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn text_output() {
+    init_logging();
+    let path = llvm_bc_dir().join("native_reader.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+
+    let text = module.to_text();
+    assert!(text.starts_with(&format!(
+        "; ModuleID = '{}'\nsource_filename = \"native_reader.c\"\n",
+        path.display()
+    )));
+    assert!(text.contains("\n%struct.list = type { i64, %struct.list* }\n"));
+    assert!(text.contains("\n@ext = external global i32\n"));
+    assert!(text.contains("\n@tls = thread_local global i8 7, section \".tdata.custom\"\n"));
+    assert!(text.contains("\n@greeting = private unnamed_addr constant c\"hello\\00\", align 1\n"));
+    assert!(text.contains("\n@counter_alias = alias i32, i32* @counter\n"));
+    let classify = module.get_func_by_name("classify").unwrap();
+    assert_eq!(
+        classify.to_text(),
+        "define i32 @classify(i32 %x) {\n\
+         0:\n  switch i32 %x, label %2 [ i32 0, label %1; i32 7, label %3; ]\n\n\
+         1:\n  br label %4\n\n\
+         2:\n  br label %4\n\n\
+         3:\n  unreachable\n\n\
+         4:\n  %5 = phi i32 [ 10, %1 ], [ 20, %2 ]\n  \
+         %6 = call @printf(getelementptr inbounds ([6 x i8]* @greeting, i64 0, i64 0), i32 %5)\n  \
+         ret i32 %5\n\
+         }\n"
+    );
+    assert!(text.contains(&classify.to_text()));
+
+    // the canonical text doesn't depend on the module's name or order
+    let canonical = module.to_canonical_text();
+    assert!(canonical.starts_with("source_filename = \"native_reader.c\"\n"));
+    let mut shuffled = module.clone();
+    shuffled.name = "shuffled".into();
+    shuffled.functions.reverse();
+    shuffled.global_vars.reverse();
+    assert_ne!(shuffled.to_text(), text);
+    assert_eq!(shuffled.to_canonical_text(), canonical);
+    let func_names: Vec<&str> = canonical
+        .lines()
+        .filter_map(|line| line.strip_prefix("define "))
+        .map(|line| &line[line.find('@').unwrap() + 1 .. line.find('(').unwrap()])
+        .collect();
+    assert_eq!(
+        func_names,
+        vec!["classify", "floats", "points", "recurse", "sum"]
+    );
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn module_editing() {
+    use llvm_ir::error::EditError;
+
+    init_logging();
+    let path = llvm_bc_dir().join("native_reader.bc");
+    let mut module = Module::from_bc_path(&path).expect("Failed to parse module");
+
+    assert_eq!(
+        module.global_referrers(&Name::from("counter")),
+        vec![Name::from("addr"), Name::from("counter_alias")]
+    );
+    assert_eq!(
+        module.global_referrers(&Name::from("greeting")),
+        vec![Name::from("classify")]
+    );
+    assert_eq!(
+        module.global_referrers(&Name::from("recurse")),
+        vec![Name::from("recurse")]
+    );
+
+    // names are shared between functions, global variables, and aliases
+    let mut check = module.get_func_by_name("sum").unwrap().clone();
+    assert_eq!(
+        module.add_function(check.clone()),
+        Err(EditError::NameTaken(Name::from("sum")))
+    );
+    let mut var = module
+        .get_global_var_by_name(&Name::from("ext"))
+        .unwrap()
+        .clone();
+    var.name = Name::from("counter_alias");
+    assert_eq!(
+        module.add_global_var(var.clone()),
+        Err(EditError::NameTaken(Name::from("counter_alias")))
+    );
+    var.name = Name::from("ext2");
+    assert_eq!(module.add_global_var(var), Ok(()));
+    assert!(module.get_global_var_by_name(&Name::from("ext2")).is_some());
+
+    // added functions get an attribute group, shared if it already exists
+    let num_groups = module.function_attribute_groups.len();
+    check.name = "check".into();
+    check.function_attributes = vec![FunctionAttribute::NoUnwind];
+    check.function_attribute_group = None;
+    assert_eq!(module.add_function(check.clone()), Ok(()));
+    let group = module
+        .get_func_by_name("check")
+        .unwrap()
+        .function_attribute_group;
+    assert_eq!(group, Some(num_groups));
+    assert_eq!(
+        module.function_attribute_groups[num_groups].attrs,
+        vec![FunctionAttribute::NoUnwind]
+    );
+    check.name = "check2".into();
+    assert_eq!(module.add_function(check), Ok(()));
+    assert_eq!(
+        module
+            .get_func_by_name("check2")
+            .unwrap()
+            .function_attribute_group,
+        group
+    );
+    assert_eq!(module.function_attribute_groups.len(), num_groups + 1);
+
+    // removing something which is still referred to fails unless forced
+    assert_eq!(
+        module
+            .remove_global_var(&Name::from("counter"), false)
+            .err(),
+        Some(EditError::StillReferenced {
+            name: Name::from("counter"),
+            referrers: vec![Name::from("addr"), Name::from("counter_alias")],
+        })
+    );
+    let alias = module
+        .remove_global_alias(&Name::from("counter_alias"), false)
+        .expect("alias should be removable");
+    assert_eq!(alias.name, Name::from("counter_alias"));
+    assert!(module.global_aliases.is_empty());
+    assert!(module
+        .remove_global_var(&Name::from("counter"), false)
+        .is_err());
+    let counter = module
+        .remove_global_var(&Name::from("counter"), true)
+        .expect("forced removal should succeed");
+    assert_eq!(counter.name, Name::from("counter"));
+    assert!(module
+        .get_global_var_by_name(&Name::from("counter"))
+        .is_none());
+
+    // a function which refers only to itself can be removed
+    let recurse = module
+        .remove_function("recurse", false)
+        .expect("recurse should be removable");
+    assert_eq!(recurse.name, "recurse");
+    assert!(module.get_func_by_name("recurse").is_none());
+    assert_eq!(
+        module.remove_function("recurse", false).err(),
+        Some(EditError::NotFound(Name::from("recurse")))
+    );
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn function_cloning() {
+    use llvm_ir::error::EditError;
+
+    init_logging();
+    let path = llvm_bc_dir().join("cloning.bc");
+    let mut module = Module::from_bc_path(&path).expect("Failed to parse module");
+
+    // the recursive call in the copy calls the copy
+    let fact_spec = module
+        .clone_function("fact", "fact_spec")
+        .expect("clone should succeed");
+    assert_eq!(fact_spec.name, "fact_spec");
+    assert_eq!(
+        module.global_referrers(&Name::from("fact_spec")),
+        vec![Name::from("fact_spec")]
+    );
+    assert_eq!(
+        module.global_referrers(&Name::from("fact")),
+        vec![
+            Name::from("fact"),
+            Name::from("main"),
+            Name::from("handlers")
+        ]
+    );
+    let fact = module.get_func_by_name("fact").unwrap();
+    let fact_spec = module.get_func_by_name("fact_spec").unwrap();
+    assert_eq!(fact_spec.parameters, fact.parameters);
+    assert_eq!(fact_spec.basic_blocks.len(), fact.basic_blocks.len());
+
+    assert_eq!(
+        module.clone_function("fact", "main").err(),
+        Some(EditError::NameTaken(Name::from("main")))
+    );
+    assert_eq!(
+        module.clone_function("nonexistent", "copy").err(),
+        Some(EditError::NotFound(Name::from("nonexistent")))
+    );
+
+    // taking the address of a function isn't a call
+    assert_eq!(module.redirect_calls("fact", "fact_spec"), 3);
+    assert_eq!(
+        module.global_referrers(&Name::from("fact")),
+        vec![Name::from("handlers")]
+    );
+    assert_eq!(module.redirect_calls("fact", "fact_spec"), 0);
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn extract_slice() {
+    init_logging();
+    let path = llvm_bc_dir().join("slice.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func_names = |module: &Module| {
+        module
+            .functions
+            .iter()
+            .map(|func| func.name.clone())
+            .collect::<Vec<_>>()
+    };
+    let var_names = |module: &Module| {
+        module
+            .global_vars
+            .iter()
+            .map(|var| var.name.clone())
+            .collect::<Vec<_>>()
+    };
+    let struct_names =
+        |module: &Module| module.types.all_struct_names().cloned().collect::<Vec<_>>();
+
+    let slice = module.extract_slice(&["root"]);
+    // `callee` and `in_table` are only declared, but `twice` keeps its body,
+    // since `twice_alias` aliases it
+    assert_eq!(func_names(&slice), vec!["root", "twice"]);
+    assert_eq!(
+        slice.get_func_by_name("root"),
+        module.get_func_by_name("root")
+    );
+    assert_eq!(
+        slice.get_func_by_name("twice"),
+        module.get_func_by_name("twice")
+    );
+    // `counter` is only referred to through `counter_ptr`
+    assert_eq!(
+        var_names(&slice),
+        vec![
+            Name::from("table"),
+            Name::from("counter"),
+            Name::from("counter_ptr")
+        ]
+    );
+    assert_eq!(slice.global_aliases.len(), 1);
+    assert_eq!(slice.global_aliases[0].name, Name::from("twice_alias"));
+    assert_eq!(
+        struct_names(&slice),
+        vec!["struct.InGlobal", "struct.Nested", "struct.Used"]
+    );
+    assert_eq!(slice.target_triple, module.target_triple);
+
+    let slice = module.extract_slice(&["unrelated", "callee"]);
+    assert_eq!(func_names(&slice), vec!["callee", "unrelated"]);
+    assert_eq!(
+        var_names(&slice),
+        vec![Name::from("counter"), Name::from("unused_var")]
+    );
+    assert!(slice.global_aliases.is_empty());
+    assert_eq!(struct_names(&slice), vec!["struct.Unused"]);
+
+    // the original `Module` is unchanged
+    assert_eq!(module.functions.len(), 5);
+    assert_eq!(struct_names(&module).len(), 4);
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn resolve_alias() {
+    init_logging();
+    let path = llvm_bc_dir().join("resolve_alias.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let resolve = |name: &str| match module.resolve_alias(&Name::from(name)) {
+        Some(Either::Left(func)) => Some(func.name.clone()),
+        Some(Either::Right(var)) => Some(format!("{}", var.name)),
+        None => None,
+    };
+    assert_eq!(resolve("g_alias").as_deref(), Some("%g"));
+    assert_eq!(resolve("g_cast").as_deref(), Some("%g"));
+    assert_eq!(resolve("g_alias_alias").as_deref(), Some("%g"));
+    assert_eq!(resolve("f_alias").as_deref(), Some("f"));
+    assert_eq!(resolve("f_cast").as_deref(), Some("f"));
+    // definitions resolve to themselves
+    assert_eq!(resolve("f").as_deref(), Some("f"));
+    assert_eq!(resolve("g").as_deref(), Some("%g"));
+    // an alias of an offset into `g` doesn't resolve to `g`
+    assert_eq!(resolve("g_gep"), None);
+    assert_eq!(resolve("nonexistent"), None);
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn string_literals() {
+    init_logging();
+    let path = llvm_bc_dir().join("strings.bc");
+    let mut module = Module::from_bc_path(&path).expect("Failed to parse module");
+
+    // non-constant globals and non-i8 arrays are skipped
+    let strings = |module: &Module| {
+        module
+            .string_literals()
+            .map(|(name, string, null_terminated)| (name.clone(), string, null_terminated))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        strings(&module),
+        vec![
+            (Name::from(".str"), "hello".to_owned(), true),
+            (Name::from(".str.1"), String::new(), true),
+            (Name::from("raw"), "a\0b".to_owned(), false),
+            (Name::from("tag"), "\u{2713}".to_owned(), true),
+        ]
+    );
+
+    // arrays of individual elements are decoded too
+    let mut var = module
+        .get_global_var_by_name(&Name::from("raw"))
+        .unwrap()
+        .clone();
+    let i8_ty = module.types.i8();
+    let byte = |value| ConstantRef::new(Constant::Int { bits: 8, value });
+    var.name = Name::from("expanded");
+    var.initializer = Some(ConstantRef::new(Constant::Array {
+        element_type: i8_ty,
+        elements: vec![byte(b'h' as u64), byte(b'i' as u64), byte(0)],
+    }));
+    assert_eq!(module.add_global_var(var), Ok(()));
+    assert_eq!(
+        strings(&module).last(),
+        Some(&(Name::from("expanded"), "hi".to_owned(), true))
+    );
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn instrument() {
+    use llvm_ir::constant::GlobalKind;
+    use transform::InsertPosition;
+
+    init_logging();
+    let path = llvm_bc_dir().join("native_reader.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let callee = |name: &str, ty: TypeRef| {
+        Operand::ConstantOperand(ConstantRef::new(Constant::GlobalReference {
+            name: Name::from(name),
+            ty,
+            kind: GlobalKind::Function,
+            addr_space: 0,
+        }))
+    };
+
+    // a void callee, before each load and phi
+    let mut sum = module.get_func_by_name("sum").unwrap().clone();
+    let trace_ty = module
+        .types
+        .func_type(module.types.void(), vec![module.types.i32()], false);
+    let trace = callee("__trace", trace_ty);
+    let mut id = 0;
+    let inserted = transform::instrument(
+        &mut sum,
+        &module.types,
+        |inst| matches!(inst, Instruction::Load(_) | Instruction::Phi(_)),
+        &trace,
+        InsertPosition::Before,
+        |_| {
+            id += 1;
+            vec![Operand::ConstantOperand(ConstantRef::new(Constant::Int {
+                bits: 32,
+                value: id,
+            }))]
+        },
+    );
+    assert_eq!(inserted, 4);
+    let instrs: Vec<String> = sum
+        .get_bb_by_name(&Name::from("loop"))
+        .unwrap()
+        .instrs
+        .iter()
+        .map(|inst| inst.to_string())
+        .collect();
+    assert_eq!(
+        instrs,
+        vec![
+            "%i = phi i32 [ 0, %entry ], [ %next, %loop ]",
+            "%acc = phi i32 [ 0, %entry ], [ %acc.next, %loop ]",
+            "call @__trace(i32 1)",
+            "call @__trace(i32 2)",
+            "%idx = sext i32 %i to i64",
+            "%p = getelementptr inbounds i32* %arr, i64 %idx",
+            "call @__trace(i32 3)",
+            "%v = load i32* %p, align 4",
+            "%acc.next = add i32 %acc, %v",
+            "%next = add i32 %i, 1",
+            "%done = icmp eq i32 %next, %n",
+        ]
+    );
+    let exit = sum.get_bb_by_name(&Name::from("exit")).unwrap();
+    assert_eq!(exit.instrs.len(), 2);
+    assert_eq!(exit.instrs[1].to_string(), "call @__trace(i32 4)");
+
+    // a callee returning a value, after each call: the unnamed locals are
+    // renumbered around the new results
+    let mut classify = module.get_func_by_name("classify").unwrap().clone();
+    let count_ty = module.types.func_type(module.types.i32(), vec![], false);
+    let count = callee("__count", count_ty);
+    let inserted = transform::instrument(
+        &mut classify,
+        &module.types,
+        |inst| matches!(inst, Instruction::Call(_) | Instruction::Phi(_)),
+        &count,
+        InsertPosition::After,
+        |_| vec![],
+    );
+    assert_eq!(inserted, 2);
+    let bb = classify.get_bb_by_name(&Name::from(4)).unwrap();
+    let instrs: Vec<String> = bb.instrs.iter().map(|inst| inst.to_string()).collect();
+    assert_eq!(
+        instrs,
+        vec![
+            "%5 = phi i32 [ 10, %1 ], [ 20, %2 ]",
+            "%6 = call @__count()",
+            "%7 = call @printf(getelementptr inbounds ([6 x i8]* @greeting, i64 0, i64 0), i32 %5)",
+            "%8 = call @__count()",
+        ]
+    );
+    assert_eq!(bb.term.to_string(), "ret i32 %5");
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn split_and_merge_blocks() {
+    init_logging();
+    let path = llvm_bc_dir().join("native_reader.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+
+    let original = module.get_func_by_name("sum").unwrap();
+    let mut sum = original.clone();
+    let split = sum.split_block(&Name::from("loop"), 2);
+    assert_eq!(split, Name::from("loop.split"));
+    let block_names: Vec<String> = sum
+        .basic_blocks
+        .iter()
+        .map(|bb| bb.name.to_string())
+        .collect();
+    assert_eq!(block_names, vec!["%entry", "%loop", "%loop.split", "%exit"]);
+    let bb = sum.get_bb_by_name(&Name::from("loop")).unwrap();
+    assert_eq!(bb.instrs.len(), 2);
+    assert_eq!(bb.term.to_string(), "br label %loop.split");
+    assert_eq!(
+        bb.instrs[0].to_string(),
+        "%i = phi i32 [ 0, %entry ], [ %next, %loop.split ]"
+    );
+    let bb = sum.get_bb_by_name(&Name::from("loop.split")).unwrap();
+    assert_eq!(bb.instrs.len(), 6);
+    assert_eq!(bb.term.to_string(), "br i1 %done, label %exit, label %loop");
+    let bb = sum.get_bb_by_name(&Name::from("exit")).unwrap();
+    assert_eq!(
+        bb.instrs[0].to_string(),
+        "%result = phi i32 [ 0, %entry ], [ %acc.next, %loop.split ]"
+    );
+
+    // splitting at the end of a block leaves the new block empty
+    let split = sum.split_block(&Name::from("loop"), 2);
+    assert_eq!(split, Name::from("loop.split1"));
+    let bb = sum.get_bb_by_name(&split).unwrap();
+    assert!(bb.instrs.is_empty());
+    assert_eq!(bb.term.to_string(), "br label %loop.split");
+
+    // merging undoes the splits
+    assert_eq!(sum.merge_single_successor_blocks(), 2);
+    assert_eq!(&sum, original);
+    assert_eq!(sum.merge_single_successor_blocks(), 0);
+
+    // an unnamed block gets an unnamed new block, and the locals are
+    // renumbered
+    let original = module.get_func_by_name("classify").unwrap();
+    let mut classify = original.clone();
+    let split = classify.split_block(&Name::from(4), 1);
+    assert_eq!(split, Name::from(6));
+    let bb = classify.get_bb_by_name(&Name::from(4)).unwrap();
+    assert_eq!(
+        bb.instrs[0].to_string(),
+        "%5 = phi i32 [ 10, %1 ], [ 20, %2 ]"
+    );
+    assert_eq!(bb.term.to_string(), "br label %6");
+    let bb = classify.get_bb_by_name(&Name::from(6)).unwrap();
+    assert_eq!(bb.instrs[0].try_get_result(), Some(&Name::from(7)));
+    assert_eq!(bb.term.to_string(), "ret i32 %5");
+    assert_eq!(classify.merge_single_successor_blocks(), 1);
+    assert_eq!(&classify, original);
+
+    // the merged block's phis are replaced with their incoming values
+    let mut classify = original.clone();
+    classify.basic_blocks.remove(2); // block %2, one of the predecessors of %4
+    classify.basic_blocks[0].term = Terminator::Br(terminator::Br {
+        dest: Name::from(1),
+        loop_metadata: None,
+        debugloc: None,
+    });
+    assert_eq!(classify.merge_single_successor_blocks(), 2);
+    assert_eq!(classify.basic_blocks.len(), 2);
+    let bb = &classify.basic_blocks[0];
+    assert_eq!(bb.instrs.len(), 1);
+    assert_eq!(
+        bb.instrs[0].to_string(),
+        "%1 = call @printf(getelementptr inbounds ([6 x i8]* @greeting, i64 0, i64 0), i32 10)"
+    );
+    assert_eq!(bb.term.to_string(), "ret i32 10");
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn run_passes() {
+    init_logging();
+    let path = llvm_bc_dir().join("passes.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("max").unwrap();
+    assert!(matches!(
+        &func.basic_blocks[0].instrs[0],
+        Instruction::Alloca(_)
+    ));
+
+    let module = Module::from_bc_path_with_passes(&path, &["mem2reg", "instnamer"])
+        .expect("Failed to parse module");
+    let func = module.get_func_by_name("max").unwrap();
+    let instrs: Vec<String> = func
+        .basic_blocks
+        .iter()
+        .flat_map(|bb| &bb.instrs)
+        .map(|inst| inst.to_string())
+        .collect();
+    assert_eq!(
+        instrs,
+        vec![
+            "%cmp = icmp sgt i32 %a, %b",
+            "%result.0 = phi i32 [ %a, %then ], [ %b, %else ]",
+        ]
+    );
+    let func = module.get_func_by_name("inc").unwrap();
+    assert_eq!(func.parameters[0].name, Name::from("arg"));
+    assert_eq!(func.basic_blocks[0].name, Name::from("bb"));
+    assert_eq!(
+        func.basic_blocks[0].instrs[0].to_string(),
+        "%i = add i32 %arg, 1"
+    );
+
+    // nested pipelines can be given too
+    let module = Module::from_bc_path_with_passes(&path, &["function(mem2reg,simplifycfg)"])
+        .expect("Failed to parse module");
+    let func = module.get_func_by_name("max").unwrap();
+    assert_eq!(func.basic_blocks.len(), 1);
+
+    match Module::from_bc_path_with_passes(&path, &["no-such-pass"]) {
+        Err(Error::PassesFailed(msg)) => assert!(msg.contains("no-such-pass"), "{}", msg),
+        Err(e) => panic!("Expected PassesFailed, got {:?}", e),
+        Ok(_) => panic!("Expected PassesFailed"),
+    }
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn name_unnamed_locals() {
+    init_logging();
+    let path = llvm_bc_dir().join("passes.bc");
+    let mut module = Module::from_bc_path(&path).expect("Failed to parse module");
+    module.name_unnamed_locals();
+    let max = module.get_func_by_name("max").unwrap();
+    assert_eq!(max.basic_blocks[1].name, Name::from("then"));
+    let inc = module.get_func_by_name("inc").unwrap();
+    assert_eq!(inc.parameters[0].name, Name::from("arg0"));
+    assert_eq!(inc.basic_blocks[0].name, Name::from("bb1"));
+    let add: instruction::Add = inc.basic_blocks[0].instrs[0]
+        .clone()
+        .try_into()
+        .expect("Should be an add");
+    assert_eq!(add.dest, Name::from("v2"));
+    assert_eq!(
+        add.operand0,
+        Operand::LocalOperand {
+            name: Name::from("arg0"),
+            ty: module.types.i32(),
+        }
+    );
+
+    // with debug info, variables' names are used where available
+    let path = llvm_bc_dir().join("linkedlist.bc-g");
+    let options = ParseOptions {
+        name_unnamed_locals: true,
+        ..ParseOptions::default()
+    };
+    let module =
+        Module::from_bc_path_with_options(&path, &options).expect("Failed to parse module");
+    let func = module.get_func_by_name("simple_linked_list").unwrap();
+    assert_eq!(func.parameters[0].name, Name::from("arg0"));
+    let dests: Vec<Name> = func.basic_blocks[0]
+        .instrs
+        .iter()
+        .filter_map(|inst| inst.try_get_result().cloned())
+        .take(8)
+        .collect();
+    let expected: Vec<Name> = [
+        "x", "list", "list_1", "list_2", "list_3", "list_4", "v8", "v9",
+    ]
+    .iter()
+    .map(|&name| Name::from(name))
+    .collect();
+    assert_eq!(dests, expected);
+    for func in &module.functions {
+        for bb in &func.basic_blocks {
+            assert!(matches!(bb.name, Name::Name(_)));
+            for inst in &bb.instrs {
+                assert!(!matches!(inst.try_get_result(), Some(Name::Number(_))));
+            }
+        }
+    }
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn memory_effects() {
+    init_logging();
+    let path = llvm_bc_dir().join("memory_effects.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let effects = |name: &str| module.get_func_by_name(name).unwrap().memory_effects();
+    assert_eq!(effects("pure"), MemoryEffects::None);
+    assert_eq!(
+        effects("reader"),
+        MemoryEffects::ReadOnly(MemoryLocation::ArgMem)
+    );
+    assert_eq!(
+        effects("writer"),
+        MemoryEffects::WriteOnly(MemoryLocation::ArgMem)
+    );
+    assert_eq!(
+        effects("hidden"),
+        MemoryEffects::ReadWrite(MemoryLocation::InaccessibleMem)
+    );
+    assert_eq!(
+        effects("unknown"),
+        MemoryEffects::ReadWrite(MemoryLocation::Any)
+    );
+    assert!(!effects("reader").may_write());
+    assert!(effects("writer").may_write());
+    assert!(!effects("writer").may_read());
+    assert_eq!(effects("pure").location(), None);
+
+    assert_eq!(
+        MemoryEffects::of_intrinsic("llvm.memcpy.p0i8.p0i8.i64"),
+        Some(MemoryEffects::ReadWrite(MemoryLocation::ArgMem))
+    );
+    assert_eq!(
+        MemoryEffects::of_intrinsic("llvm.sadd.with.overflow.i32"),
+        Some(MemoryEffects::None)
+    );
+    assert_eq!(
+        MemoryEffects::of_intrinsic("llvm.dbg.value"),
+        Some(MemoryEffects::None)
+    );
+    assert_eq!(MemoryEffects::of_intrinsic("llvm.memcpyfoo"), None);
+    assert_eq!(MemoryEffects::of_intrinsic("memcpy"), None);
+
+    // combining call-site and callee attributes
+    assert_eq!(
+        effects("reader").intersect(MemoryEffects::WriteOnly(MemoryLocation::Any)),
+        MemoryEffects::None
+    );
+    assert_eq!(
+        effects("hidden").intersect(MemoryEffects::ReadOnly(
+            MemoryLocation::InaccessibleOrArgMem
+        )),
+        MemoryEffects::ReadOnly(MemoryLocation::InaccessibleMem)
+    );
+    assert_eq!(
+        effects("hidden").intersect(effects("writer")),
+        MemoryEffects::None
+    );
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn vaarg() {
+    init_logging();
+    let path = llvm_bc_dir().join("vaarg.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("first_vararg").unwrap();
+    assert!(func.is_var_arg);
+    let bb = &func.basic_blocks[0];
+    let intrinsics: Vec<Intrinsic> = bb
+        .instrs
+        .iter()
+        .filter_map(|inst| match inst {
+            Instruction::Call(call) => call.intrinsic(),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        intrinsics,
+        vec![
+            Intrinsic::VaStart,
+            Intrinsic::VaCopy,
+            Intrinsic::VaEnd,
+            Intrinsic::VaEnd,
+        ]
+    );
+    assert!(intrinsics.iter().all(|i| i.is_vararg()));
+    assert_eq!(Intrinsic::VaStart.base_name(), "llvm.va_start");
+    assert_eq!(
+        Intrinsic::from_name("llvm.va_start"),
+        Some(Intrinsic::VaStart)
+    );
+    assert_eq!(Intrinsic::from_name("llvm.va_startx"), None);
+
+    let vaarg: &instruction::VAArg = &bb.instrs[6]
+        .clone()
+        .try_into()
+        .unwrap_or_else(|_| panic!("Expected a VAArg, got {:?}", &bb.instrs[6]));
+    assert_eq!(vaarg.dest, Name::from("x"));
+    assert_eq!(vaarg.cur_type, module.types.i32());
+    assert_eq!(
+        vaarg.arg_list,
+        Operand::LocalOperand {
+            name: Name::from("ap"),
+            ty: module
+                .types
+                .pointer_to(module.types.pointer_to(module.types.i8())),
+        }
+    );
+    assert_eq!(module.type_of(vaarg), module.types.i32());
+    assert_eq!(&vaarg.to_string(), "%x = va_arg i8** %ap, i32");
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn stackmaps() {
+    init_logging();
+    let path = llvm_bc_dir().join("stackmaps.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("patched").unwrap();
+    let a = Operand::LocalOperand {
+        name: Name::from("a"),
+        ty: module.types.i64(),
+    };
+    let b = Operand::LocalOperand {
+        name: Name::from("b"),
+        ty: module.types.i64(),
+    };
+    let bb = &func.basic_blocks[0];
+    let calls: Vec<&instruction::Call> = bb
+        .instrs
+        .iter()
+        .map(|inst| inst.as_call().expect("Should be a call"))
+        .collect();
+
+    assert_eq!(calls[0].intrinsic(), Some(Intrinsic::StackMap));
+    let stackmap = calls[0].as_stackmap().expect("Should be a stackmap");
+    assert_eq!(stackmap.id, 1);
+    assert_eq!(stackmap.num_shadow_bytes, 8);
+    assert_eq!(stackmap.live_operands, vec![&a, &b]);
+    let stackmap = calls[1].as_stackmap().expect("Should be a stackmap");
+    assert_eq!(stackmap.id, 2);
+    assert!(stackmap.live_operands.is_empty());
+    assert!(calls[0].as_patchpoint().is_none());
+
+    assert_eq!(calls[2].intrinsic(), Some(Intrinsic::PatchPoint));
+    let patchpoint = calls[2].as_patchpoint().expect("Should be a patchpoint");
+    assert_eq!(patchpoint.id, 3);
+    assert_eq!(patchpoint.num_bytes, 15);
+    assert_eq!(
+        patchpoint.target,
+        &Operand::LocalOperand {
+            name: Name::from("target"),
+            ty: module.types.pointer_to(module.types.i8()),
+        }
+    );
+    assert_eq!(patchpoint.call_args, vec![&a, &b]);
+    assert_eq!(patchpoint.live_operands, vec![&a]);
+    assert!(calls[2].as_stackmap().is_none());
+
+    let invoke = bb.term.as_invoke().expect("Should be an invoke");
+    assert_eq!(invoke.intrinsic(), Some(Intrinsic::PatchPoint));
+    let patchpoint = invoke.as_patchpoint().expect("Should be a patchpoint");
+    assert_eq!(patchpoint.id, 4);
+    assert!(patchpoint.call_args.is_empty());
+    assert_eq!(patchpoint.live_operands, vec![&a]);
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn statepoints() {
+    init_logging();
+    let path = llvm_bc_dir().join("statepoints.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("relocated").unwrap();
+    let local = |name: &str, ty: TypeRef| Operand::LocalOperand {
+        name: Name::from(name),
+        ty,
+    };
+    let gc_ptr = module.types.pointer_in_addr_space(module.types.i8(), 1);
+    let obj = local("obj", gc_ptr.clone());
+    let derived = local("derived", gc_ptr.clone());
+    let x = local("x", module.types.i32());
+
+    let entry = &func.basic_blocks[0];
+    let call = |i: usize| entry.instrs[i].as_call().expect("Should be a call");
+    assert_eq!(call(1).intrinsic(), Some(Intrinsic::GcStatepoint));
+    let statepoint = call(1).as_statepoint().expect("Should be a statepoint");
+    assert_eq!(statepoint.id, 7);
+    assert_eq!(statepoint.num_patch_bytes, 0);
+    assert_eq!(statepoint.call_args, vec![&x]);
+    assert_eq!(statepoint.flags, 0);
+    assert_eq!(statepoint.gc_live, vec![&obj, &derived]);
+    assert_eq!(statepoint.deopt_args, vec![&x]);
+    assert!(statepoint.gc_transition_args.is_empty());
+
+    let result = call(2).as_gc_result().expect("Should be a gc.result");
+    assert_eq!(result.find_statepoint(func), Some(statepoint.clone()));
+
+    let relocate = call(4).as_gc_relocate().expect("Should be a gc.relocate");
+    assert_eq!((relocate.base_index, relocate.derived_index), (0, 1));
+    let found = relocate
+        .find_statepoint(func)
+        .expect("Should find the statepoint");
+    assert_eq!(found, statepoint);
+    assert_eq!(relocate.base(&found), Some(&obj));
+    assert_eq!(relocate.derived(&found), Some(&derived));
+    assert!(call(4).as_statepoint().is_none());
+
+    // relocations after an invoked statepoint, on both paths
+    let invoke = entry.term.as_invoke().expect("Should be an invoke");
+    let invoked = invoke.as_statepoint().expect("Should be a statepoint");
+    assert_eq!(invoked.id, 8);
+    for block in &["normal", "lpad"] {
+        let bb = func.get_bb_by_name(&Name::from(*block)).unwrap();
+        let relocate = bb
+            .instrs
+            .iter()
+            .find_map(|inst| inst.as_call()?.as_gc_relocate())
+            .expect("Should have a gc.relocate");
+        let found = relocate.find_statepoint(func);
+        assert_eq!(found.as_ref(), Some(&invoked));
+        assert_eq!(
+            relocate.base(&found.unwrap()),
+            Some(&local("obj.rel", gc_ptr.clone()))
+        );
+    }
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn checked_arithmetic() {
+    use llvm_ir::intrinsic::Signedness;
+
+    init_logging();
+    let path = llvm_bc_dir().join("checked_arithmetic.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("checked").unwrap();
+    let local = |name: &str, ty: TypeRef| Operand::LocalOperand {
+        name: Name::from(name),
+        ty,
+    };
+    let bool_ty = module.types.bool();
+    let instrs = &func.basic_blocks[0].instrs;
+    let call = |i: usize| instrs[i].as_call().expect("Should be a call");
+
+    assert_eq!(call(0).intrinsic(), Some(Intrinsic::SAddWithOverflow));
+    assert!(Intrinsic::SAddWithOverflow.is_arithmetic_with_overflow());
+    let sum = call(0)
+        .as_checked_arithmetic()
+        .expect("Should be checked arithmetic");
+    assert_eq!(sum.op, Opcode::Add);
+    assert_eq!(sum.signedness, Signedness::Signed);
+    let (a, b) = (
+        local("a", module.types.i32()),
+        local("b", module.types.i32()),
+    );
+    assert_eq!(sum.operands, (&a, &b));
+    assert_eq!(sum.dest, Some(&Name::from("sum")));
+    assert_eq!(
+        module.type_of(&sum),
+        module
+            .types
+            .struct_of(vec![module.types.i32(), bool_ty.clone()], false)
+    );
+    assert_eq!(module.type_of(&sum), module.type_of(call(0)));
+
+    // vector operands have a vector of overflow bits
+    let diffs = call(2)
+        .as_checked_arithmetic()
+        .expect("Should be checked arithmetic");
+    assert_eq!(
+        (diffs.op, diffs.signedness),
+        (Opcode::Sub, Signedness::Unsigned)
+    );
+    let vec_ty = module.types.vector_of(module.types.i16(), 4, false);
+    let bools_ty = module.types.vector_of(bool_ty, 4, false);
+    assert_eq!(
+        module.type_of(&diffs),
+        module.types.struct_of(vec![vec_ty, bools_ty], false)
+    );
+
+    assert!(call(3).as_checked_arithmetic().is_none());
+    assert!(!Intrinsic::VaStart.is_arithmetic_with_overflow());
+
+    let product = call(4)
+        .as_checked_arithmetic()
+        .expect("Should be checked arithmetic");
+    assert_eq!(
+        (product.op, product.signedness),
+        (Opcode::Mul, Signedness::Unsigned)
+    );
+    assert_eq!(product.operands.0, &local("x", module.types.i64()));
+    assert_eq!(
+        product.operands.1.as_constant(),
+        Some(&Constant::Int { bits: 64, value: 3 })
+    );
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn saturating_and_fixed_point_arithmetic() {
+    use llvm_ir::intrinsic::Signedness;
+
+    init_logging();
+    let path = llvm_bc_dir().join("fixed_point.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("dsp").unwrap();
+    let local = |name: &str, ty: TypeRef| Operand::LocalOperand {
+        name: Name::from(name),
+        ty,
+    };
+    let instrs = &func.basic_blocks[0].instrs;
+    let call = |i: usize| instrs[i].as_call().expect("Should be a call");
+
+    assert_eq!(call(0).intrinsic(), Some(Intrinsic::UAddSat));
+    assert!(Intrinsic::UAddSat.is_saturating_arithmetic());
+    let sum = call(0)
+        .as_saturating_arithmetic()
+        .expect("Should be saturating arithmetic");
+    assert_eq!(
+        (sum.op, sum.signedness),
+        (Opcode::Add, Signedness::Unsigned)
+    );
+    let (a, b) = (local("a", module.types.i8()), local("b", module.types.i8()));
+    assert_eq!(sum.operands, (&a, &b));
+    assert!(call(0).as_fixed_point_arithmetic().is_none());
+    assert!(call(0).as_checked_arithmetic().is_none());
+
+    let shifted = call(1)
+        .as_saturating_arithmetic()
+        .expect("Should be saturating arithmetic");
+    assert_eq!(
+        (shifted.op, shifted.signedness),
+        (Opcode::Shl, Signedness::Signed)
+    );
+
+    // `llvm.smul.fix.sat` isn't mistaken for `llvm.smul.fix`
+    assert_eq!(call(2).intrinsic(), Some(Intrinsic::SMulFix));
+    assert_eq!(call(3).intrinsic(), Some(Intrinsic::SMulFixSat));
+    assert!(Intrinsic::SMulFixSat.is_fixed_point_arithmetic());
+    assert!(!Intrinsic::SMulFixSat.is_saturating_arithmetic());
+    let (x, y) = (
+        local("x", module.types.i32()),
+        local("y", module.types.i32()),
+    );
+    let prod = call(2)
+        .as_fixed_point_arithmetic()
+        .expect("Should be fixed-point arithmetic");
+    assert_eq!(
+        (prod.op, prod.signedness, prod.saturating),
+        (Opcode::Mul, Signedness::Signed, false)
+    );
+    assert_eq!(prod.operands, (&x, &y));
+    assert_eq!(prod.scale, 16);
+    let prod_sat = call(3)
+        .as_fixed_point_arithmetic()
+        .expect("Should be fixed-point arithmetic");
+    assert!(prod_sat.saturating);
+    assert_eq!(prod_sat.scale, 31);
+    assert!(call(3).as_saturating_arithmetic().is_none());
+
+    let quot = call(4)
+        .as_fixed_point_arithmetic()
+        .expect("Should be fixed-point arithmetic");
+    assert_eq!(
+        (quot.op, quot.signedness, quot.saturating),
+        (Opcode::UDiv, Signedness::Unsigned, false)
+    );
+    assert_eq!(quot.scale, 8);
+
+    assert!(call(5).as_saturating_arithmetic().is_none());
+    assert!(call(5).as_fixed_point_arithmetic().is_none());
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn vector_reductions() {
+    use llvm_ir::intrinsic::ReductionKind;
+
+    init_logging();
+    let path = llvm_bc_dir().join("vector_reduce.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("reductions").unwrap();
+    let v = Operand::LocalOperand {
+        name: Name::from("v"),
+        ty: module.types.vector_of(module.types.i32(), 4, false),
+    };
+    let f = Operand::LocalOperand {
+        name: Name::from("f"),
+        ty: module
+            .types
+            .vector_of(module.types.fp(FPType::Single), 8, false),
+    };
+    let instrs = &func.basic_blocks[0].instrs;
+    let call = |i: usize| instrs[i].as_call().expect("Should be a call");
+    let reduction = |i: usize| {
+        call(i)
+            .as_vector_reduction()
+            .expect("Should be a vector reduction")
+    };
+
+    assert_eq!(call(0).intrinsic(), Some(Intrinsic::VectorReduceAdd));
+    assert!(Intrinsic::VectorReduceAdd.is_vector_reduction());
+    let sum = reduction(0);
+    assert_eq!(sum.kind, ReductionKind::Add);
+    assert_eq!(sum.kind.opcode(), Some(Opcode::Add));
+    assert_eq!(sum.vector, &v);
+    assert_eq!(sum.accumulator, None);
+
+    let min = reduction(1);
+    assert_eq!(min.kind, ReductionKind::UMin);
+    assert_eq!(min.kind.opcode(), None);
+    assert!(!min.kind.is_floating_point());
+
+    let total = reduction(2);
+    assert_eq!(total.kind, ReductionKind::FAdd);
+    assert!(total.kind.is_floating_point());
+    assert_eq!(total.vector, &f);
+    assert_eq!(
+        total.accumulator.and_then(Operand::as_constant),
+        Some(&Constant::Float(Float::Single(1.0)))
+    );
+
+    let max = reduction(3);
+    assert_eq!(max.kind, ReductionKind::FMax);
+    assert_eq!(max.vector, &f);
+    assert_eq!(max.accumulator, None);
+
+    // the names from before LLVM 12
+    assert_eq!(
+        Intrinsic::from_name("llvm.experimental.vector.reduce.umin.v4i32"),
+        Some(Intrinsic::VectorReduceUMin)
+    );
+    assert_eq!(
+        Intrinsic::from_name("llvm.experimental.vector.reduce.v2.fmul.f32.v4f32"),
+        Some(Intrinsic::VectorReduceFMul)
+    );
+    assert_eq!(
+        Intrinsic::from_name("llvm.experimental.vector.reduce.frobnicate"),
+        None
+    );
+}
+
+#[test]
+fn shared_context() {
+    init_logging();
+    let path = llvm_bc_dir().join("linkedlist.bc");
+    let context = Context::new();
+    let module_a = Module::from_bc_path_in(&context, &path).expect("Failed to parse module");
+    let module_b = Module::from_bc_path_in(&context, &path).expect("Failed to parse module");
+    let hello = Module::from_bc_path_in(&context, llvm_bc_dir().join("hello.bc"))
+        .expect("Failed to parse module");
+    let unshared = Module::from_bc_path(&path).expect("Failed to parse module");
+
+    // `Module`s in the same `Context` share `TypeRef`s
+    let struct_name = "struct.SimpleLinkedList";
+    let struct_a = module_a.types.named_struct(struct_name);
+    let struct_b = module_b.types.named_struct(struct_name);
+    assert!(std::ptr::eq(struct_a.as_ref(), struct_b.as_ref()));
+    assert!(std::ptr::eq(
+        module_a.types.i32().as_ref(),
+        hello.types.i32().as_ref()
+    ));
+    let func_a = module_a.get_func_by_name("simple_linked_list").unwrap();
+    let func_b = module_b.get_func_by_name("simple_linked_list").unwrap();
+    assert!(std::ptr::eq(
+        func_a.parameters[0].ty.as_ref(),
+        func_b.parameters[0].ty.as_ref()
+    ));
+
+    // ... but `Module`s not parsed in that `Context` don't
+    let struct_unshared = unshared.types.named_struct(struct_name);
+    assert_eq!(struct_a, struct_unshared);
+    assert!(!std::ptr::eq(struct_a.as_ref(), struct_unshared.as_ref()));
+
+    // each `Module` has only its own named struct definitions
+    assert!(module_b.types.named_struct_def(struct_name).is_some());
+    assert!(hello.types.named_struct_def(struct_name).is_none());
+    assert_eq!(hello.types.all_struct_names().count(), 0);
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn thinlto_summary() {
+    init_logging();
+    let path = llvm_bc_dir().join("summary.bc");
+    let index = ModuleSummaryIndex::from_bc_path(path)
+        .expect("Failed to read summary")
+        .expect("Expected the module to have a summary");
+    assert_eq!(index.version, 9);
+    let names: Vec<&str> = index.summaries.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec!["leaf", "caller", "pure", "counter", "table", "caller_alias"]
+    );
+
+    let leaf = index.get_summary("leaf").unwrap();
+    assert_eq!(leaf.linkage, Linkage::Internal);
+    assert!(leaf.dso_local);
+    let leaf = match &leaf.kind {
+        SummaryKind::Function(func) => func,
+        kind => panic!("Expected a function summary, got {:?}", kind),
+    };
+    assert_eq!(leaf.instruction_count, 2);
+    assert!(leaf.no_inline);
+    assert_eq!(leaf.refs, vec!["counter"]);
+    assert!(leaf.calls.is_empty());
+
+    let caller = index.get_summary("caller").unwrap();
+    assert_eq!(caller.linkage, Linkage::External);
+    assert!(!caller.dso_local);
+    let calls: Vec<(&str, &str, Hotness)> = index
+        .call_edges()
+        .map(|(caller, edge)| (caller, edge.callee.as_str(), edge.hotness))
+        .collect();
+    assert_eq!(
+        calls,
+        vec![
+            ("caller", "leaf", Hotness::None),
+            ("caller", "external", Hotness::None),
+        ]
+    );
+
+    let (_, pure) = index.functions().find(|(name, _)| *name == "pure").unwrap();
+    assert!(pure.read_none);
+    assert!(pure.no_recurse);
+    assert!(!pure.no_inline);
+
+    match &index.get_summary("table").unwrap().kind {
+        SummaryKind::GlobalVariable(var) => assert_eq!(var.refs, vec!["leaf"]),
+        kind => panic!("Expected a global variable summary, got {:?}", kind),
+    }
+    assert_eq!(
+        index.get_summary("caller_alias").unwrap().kind,
+        SummaryKind::Alias {
+            aliasee: "caller".into()
+        }
+    );
+    assert!(index.get_summary("external").is_none());
+
+    // a summary written by an older LLVM, which names values in the
+    // `VALUE_SYMTAB` rather than the string table
+    let path = Path::new("tests/llvm_bc/thinlto-summary-local-5.0.ll.bc");
+    let index = ModuleSummaryIndex::from_bc_path(path)
+        .expect("Failed to read summary")
+        .expect("Expected the module to have a summary");
+    assert_eq!(index.version, 3);
+    assert!(matches!(
+        index.get_summary("foo").unwrap().kind,
+        SummaryKind::Function(_)
+    ));
+    assert!(matches!(
+        index.get_summary("bar").unwrap().kind,
+        SummaryKind::GlobalVariable(_)
+    ));
+    assert_eq!(
+        index.get_summary("baz").unwrap().kind,
+        SummaryKind::Alias {
+            aliasee: "bar".into()
+        }
+    );
+
+    // a module without a summary
+    let path = llvm_bc_dir().join("hello.bc");
+    assert_eq!(ModuleSummaryIndex::from_bc_path(&path), Ok(None));
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn module_diff() {
+    init_logging();
+    let old =
+        Module::from_bc_path(llvm_bc_dir().join("diff_old.bc")).expect("Failed to parse module");
+    let new =
+        Module::from_bc_path(llvm_bc_dir().join("diff_new.bc")).expect("Failed to parse module");
+    assert!(old.diff(&old).is_empty());
+
+    let diff = old.diff(&new);
+    assert!(!diff.is_empty());
+    assert_eq!(diff.added_functions, vec!["added"]);
+    assert_eq!(diff.removed_functions, vec!["removed"]);
+    assert_eq!(diff.changed_functions.len(), 1);
+    let changed = &diff.changed_functions[0];
+    assert_eq!(changed.name, "changed");
+    assert!(changed.added_blocks.is_empty());
+    assert_eq!(changed.removed_blocks, vec![Name::from("neg")]);
+    assert_eq!(
+        changed.changed_blocks,
+        vec![Name::from("entry"), Name::from("pos"), Name::from("exit")]
+    );
+    assert_eq!(diff.added_globals, vec![Name::from("added_global")]);
+    assert_eq!(diff.removed_globals, vec![Name::from("removed_global")]);
+    assert_eq!(diff.changed_globals, vec![Name::from("changed_init")]);
+
+    // debug information is ignored
+    let plain =
+        Module::from_bc_path(llvm_bc_dir().join("hello.bc")).expect("Failed to parse module");
+    let debug =
+        Module::from_bc_path(llvm_bc_dir().join("hello.bc-g")).expect("Failed to parse module");
+    assert!(plain.diff(&debug).is_empty(), "{:?}", plain.diff(&debug));
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn memory_footprint() {
+    init_logging();
+    let hello =
+        Module::from_bc_path(llvm_bc_dir().join("hello.bc")).expect("Failed to parse module");
+    let footprint = hello.memory_footprint();
+    assert!(footprint.instructions > 0);
+    assert!(footprint.constants > 0);
+    assert!(footprint.types > 0);
+    assert!(footprint.names > 0);
+    assert_eq!(footprint.debug_locs, 0);
+    assert_eq!(
+        footprint.total(),
+        footprint.instructions
+            + footprint.constants
+            + footprint.types
+            + footprint.names
+            + footprint.debug_locs
+            + footprint.metadata
+    );
+
+    // the version with debug info has the same code, plus debug locations
+    let hello_g =
+        Module::from_bc_path(llvm_bc_dir().join("hello.bc-g")).expect("Failed to parse module");
+    let footprint_g = hello_g.memory_footprint();
+    assert!(footprint_g.debug_locs > 0);
+    assert!(footprint_g.total() > footprint.total());
+
+    // a larger module uses more memory
+    let loop_module =
+        Module::from_bc_path(llvm_bc_dir().join("loop.bc")).expect("Failed to parse module");
+    assert!(loop_module.memory_footprint().instructions > footprint.instructions);
+}
+
+#[test]
+fn shared_operands() {
+    init_logging();
+    let module =
+        Module::from_bc_path(llvm_bc_dir().join("loop.bc")).expect("Failed to parse module");
+    let func = module.get_func_by_name("loop").unwrap();
+    let mut operands: HashMap<&Operand, &Operand> = HashMap::new();
+    let mut num_uses = 0;
+    for bb in &func.basic_blocks {
+        let ops = bb.instrs.iter().flat_map(Instruction::operands);
+        for op in ops.chain(bb.term.operands()) {
+            if let Operand::MetadataOperand(_) = op {
+                continue;
+            }
+            num_uses += 1;
+            // each use of the same operand refers to the same `Operand`
+            let first = *operands.entry(op).or_insert(op);
+            assert!(std::ptr::eq(first, op), "{} is not shared", op);
+        }
+    }
+    assert!(operands.len() < num_uses);
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn opcodes() {
+    init_logging();
+    let module =
+        Module::from_bc_path(llvm_bc_dir().join("loop.bc")).expect("Failed to parse module");
+    let func = module.get_func_by_name("loop").unwrap();
+    let entry = &func.basic_blocks[0];
+    let opcodes: Vec<Opcode> = entry.instrs.iter().map(Instruction::opcode).collect();
+    assert_eq!(
+        opcodes,
+        vec![
+            Opcode::Alloca,
+            Opcode::BitCast,
+            Opcode::Call,
+            Opcode::Call,
+            Opcode::Add,
+            Opcode::ICmp,
+        ]
+    );
+    assert_eq!(entry.term.opcode(), Opcode::Br);
+    assert_eq!(Opcode::GetElementPtr.to_string(), "getelementptr");
+    assert_eq!(Opcode::VAArg.to_string(), "va_arg");
+
+    // each opcode's mnemonic appears in the instruction's text
+    for bb in &func.basic_blocks {
+        for inst in &bb.instrs {
+            assert!(!inst.opcode().is_terminator());
+            assert!(inst.to_string().contains(&inst.opcode().to_string()));
+        }
+        assert!(bb.term.opcode().is_terminator());
+        assert!(bb.term.to_string().contains(&bb.term.opcode().to_string()));
+    }
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn other_opcodes() {
+    init_logging();
+    let module =
+        Module::from_bc_path(llvm_bc_dir().join("loop.bc")).expect("Failed to parse module");
+    // LLVM 14 has no opcodes which `llvm-ir` doesn't model, so we construct
+    // `Other`s directly
+    let operand = OperandRef::new(Operand::LocalOperand {
+        name: Name::from(0),
+        ty: module.types.i32(),
+    });
+    let inst = Instruction::Other(instruction::Other {
+        opcode: 67,
+        operands: vec![operand.clone()],
+        dest: Some(Name::from("x")),
+        ty: module.types.i32(),
+        debugloc: None,
+    });
+    assert_eq!(inst.opcode(), Opcode::Other(67));
+    assert!(!inst.opcode().is_terminator());
+    assert_eq!(&inst.to_string(), "%x = <opcode 67> i32 %0");
+    assert_eq!(inst.try_get_result(), Some(&Name::from("x")));
+    assert_eq!(module.type_of(&inst), module.types.i32());
+    assert!(inst.may_have_side_effects());
+
+    let term = Terminator::Other(terminator::Other {
+        opcode: 68,
+        operands: vec![operand],
+        successors: vec![Name::from("a"), Name::from("b")],
+        dest: None,
+        ty: module.types.void(),
+        debugloc: None,
+    });
+    assert_eq!(term.opcode(), Opcode::OtherTerminator(68));
+    assert!(term.opcode().is_terminator());
+    assert_eq!(
+        &term.to_string(),
+        "<opcode 68> i32 %0 to [label %a, label %b]"
+    );
+    assert_eq!(term.try_get_result(), None);
+    assert_eq!(
+        term.successors().collect::<Vec<_>>(),
+        vec![&Name::from("a"), &Name::from("b")]
+    );
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn value_kinds() {
+    init_logging();
+    let path = llvm_bc_dir().join("values.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("values").unwrap();
+    let entry = &func.basic_blocks[0];
+    let cont = &func.basic_blocks[1];
+    let resolve = |name: &str| func.resolve_local(&Name::from(name));
+
+    assert_eq!(resolve("x"), Some(ValueKind::Argument(&func.parameters[0])));
+    assert_eq!(
+        resolve("sum"),
+        Some(ValueKind::Instruction(&entry.instrs[0]))
+    );
+    assert_eq!(resolve("r"), Some(ValueKind::Terminator(&entry.term)));
+    assert_eq!(resolve("cont"), Some(ValueKind::Block(cont)));
+    assert_eq!(resolve("nonexistent"), None);
+
+    // the operands of `%old = load i32, i32* @alias`
+    let load = entry.instrs[1].as_load().unwrap();
+    assert!(load.address.as_local().is_none());
+    assert_eq!(
+        module.resolve_operand(func, &load.address),
+        Some(ValueKind::Global(GlobalValue::Alias(
+            &module.global_aliases[0]
+        )))
+    );
+    // the operands of `store i32 %r, i32* @counter`
+    let store = cont.instrs[0].as_store().unwrap();
+    assert_eq!(store.value.as_local(), Some(&Name::from("r")));
+    assert_eq!(
+        module.resolve_operand(func, &store.address),
+        Some(ValueKind::Global(GlobalValue::Variable(
+            &module.global_vars[0]
+        )))
+    );
+    // `@ext` is only declared, so it stays a constant
+    let invoke = entry.term.as_invoke().unwrap();
+    let callee = invoke.function.as_ref().right().unwrap();
+    let resolved = module.resolve_operand(func, callee).unwrap();
+    assert!(matches!(
+        resolved,
+        ValueKind::Constant(Constant::GlobalReference { .. })
+    ));
+    assert!(resolved.is_constant());
+    assert_eq!(
+        module.resolve_operand(func, &invoke.arguments[0].0),
+        Some(ValueKind::Instruction(&entry.instrs[0]))
+    );
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn parse_options() {
+    init_logging();
+    let path = llvm_bc_dir().join("parse_options.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+
+    // the default options are the same as `from_bc_path()`
+    let defaults = Module::from_bc_path_with_options(&path, &ParseOptions::default())
+        .expect("Failed to parse module");
+    assert!(module.diff(&defaults).is_empty());
+    assert_eq!(defaults.functions, module.functions);
+
+    let options = ParseOptions {
+        skip_function_bodies: Some(Box::new(|name| name.starts_with("skip"))),
+        max_constant_array_elements: Some(4),
+        ..ParseOptions::default()
+    };
+    let skipped =
+        Module::from_bc_path_with_options(&path, &options).expect("Failed to parse module");
+    assert_eq!(skipped.functions.len(), 2);
+    let keep = skipped.get_func_by_name("keep").unwrap();
+    assert_eq!(keep, module.get_func_by_name("keep").unwrap());
+    let skip_me = skipped.get_func_by_name("skip_me").unwrap();
+    assert!(skip_me.basic_blocks.is_empty());
+    assert_eq!(
+        skip_me.parameters,
+        module.get_func_by_name("skip_me").unwrap().parameters
+    );
+
+    let initializer = |module: &Module, name: &str| {
+        module
+            .get_global_var_by_name(&Name::from(name))
+            .unwrap()
+            .initializer
+            .clone()
+            .unwrap()
+    };
+    assert_eq!(
+        initializer(&skipped, "small"),
+        initializer(&module, "small")
+    );
+    assert!(matches!(
+        initializer(&module, "large").as_ref(),
+        Constant::RawDataArray { .. }
+    ));
+    for (large, len) in &[("large", 6), ("large_ptrs", 5)] {
+        match initializer(&skipped, large).as_ref() {
+            Constant::Undef(ty) => match ty.as_ref() {
+                Type::ArrayType { num_elements, .. } => assert_eq!(num_elements, len),
+                ty => panic!("Expected an array type; got {:?}", ty),
+            },
+            c => panic!("Expected {} to be Undef; got {:?}", large, c),
+        }
+    }
+
+    // lossy parsing of a module without anything unsupported is the same as
+    // regular parsing
+    let options = ParseOptions {
+        lossy: true,
+        ..ParseOptions::default()
+    };
+    let lossy = Module::from_bc_path_with_options(&path, &options).expect("Failed to parse module");
+    assert_eq!(lossy.functions, module.functions);
+
+    let options = ParseOptions {
+        skip_debug_info: true,
+        ..ParseOptions::default()
+    };
+    let hello_path = llvm_bc_dir().join("hello.bc-g");
+    let hello = Module::from_bc_path(&hello_path).expect("Failed to parse module");
+    let no_debug =
+        Module::from_bc_path_with_options(&hello_path, &options).expect("Failed to parse module");
+    assert!(hello.functions[0].debugloc.is_some());
+    assert!(no_debug.functions[0].debugloc.is_none());
+    let debuglocs = |module: &Module| {
+        module
+            .functions
+            .iter()
+            .flat_map(|f| &f.basic_blocks)
+            .filter(|bb| bb.term.get_debug_loc().is_some())
+            .count()
+    };
+    assert!(debuglocs(&hello) > 0);
+    assert_eq!(debuglocs(&no_debug), 0);
+    // parsing without options afterwards still gives debuglocs
+    let hello_again = Module::from_bc_path(&hello_path).expect("Failed to parse module");
+    assert_eq!(debuglocs(&hello_again), debuglocs(&hello));
+
+    // debug intrinsics and debug-info metadata are skipped too
+    let variables_path = llvm_bc_dir().join("variables.bc-g");
+    let mut variables = Module::from_bc_path(&variables_path).expect("Failed to parse module");
+    let no_debug = Module::from_bc_path_with_options(&variables_path, &options)
+        .expect("Failed to parse module");
+    assert!(!variables.metadata_nodes.is_empty());
+    assert!(no_debug.metadata_nodes.is_empty());
+    let kinds: HashSet<&str> = variables
+        .metadata_nodes
+        .iter()
+        .filter_map(|(_, node)| match node {
+            MetadataNode::Specialized { kind, .. } => Some(kind.as_str()),
+            MetadataNode::Tuple(_) => None,
+        })
+        .collect();
+    for kind in &[
+        "DICompileUnit",
+        "DISubprogram",
+        "DILocalVariable",
+        "DIExpression",
+    ] {
+        assert!(kinds.contains(kind), "missing {} in {:?}", kind, kinds);
+    }
+    assert!(no_debug.memory_footprint().total() < variables.memory_footprint().total());
+    variables.strip_debug_info();
+    assert_eq!(no_debug.functions, variables.functions);
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn alignment() {
+    init_logging();
+    let path = llvm_bc_dir().join("alignment.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let var = module.get_global_var_by_name(&Name::from("g")).unwrap();
+    assert_eq!(var.alignment, NonZeroU32::new(16));
+    let func = module.get_func_by_name("f").unwrap();
+    let instrs = &func.basic_blocks[0].instrs;
+
+    let alloca: &instruction::Alloca = match &instrs[0] {
+        Instruction::Alloca(alloca) => alloca,
+        inst => panic!("Expected an alloca; got {}", inst),
+    };
+    assert_eq!(alloca.alignment, NonZeroU32::new(32));
+    assert_eq!(alloca.effective_alignment(&module), NonZeroU32::new(32));
+    let mut unspecified = alloca.clone();
+    unspecified.alignment = None;
+    assert_eq!(unspecified.effective_alignment(&module), NonZeroU32::new(8));
+    assert!(!unspecified.to_string().contains("align"));
+
+    let loads: Vec<&instruction::Load> = instrs
+        .iter()
+        .filter_map(|inst| match inst {
+            Instruction::Load(load) => Some(load),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(loads.len(), 3);
+    assert_eq!(loads[0].effective_alignment(&module), NonZeroU32::new(4));
+    // non-atomic, so there are no requirements
+    assert!(loads[0].has_valid_atomic_alignment(&module));
+    assert!(loads[1].has_valid_atomic_alignment(&module));
+    // an atomic `i64` load needs an alignment of at least 8
+    assert!(!loads[2].has_valid_atomic_alignment(&module));
+    let mut unspecified = loads[1].clone();
+    unspecified.alignment = None;
+    assert_eq!(unspecified.effective_alignment(&module), NonZeroU32::new(8));
+    assert!(!unspecified.has_valid_atomic_alignment(&module));
+
+    let stores: Vec<&instruction::Store> = instrs
+        .iter()
+        .filter_map(|inst| match inst {
+            Instruction::Store(store) => Some(store),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(stores.len(), 2);
+    assert!(stores[0].has_valid_atomic_alignment(&module));
+    assert_eq!(stores[1].effective_alignment(&module), NonZeroU32::new(1));
+    assert!(stores[1].has_valid_atomic_alignment(&module));
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn instruction_ref_conversions() {
+    init_logging();
+    let path = llvm_bc_dir().join("alignment.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let bb = &module.get_func_by_name("f").unwrap().basic_blocks[0];
+
+    // converting references doesn't need a clone
+    let alloca: &instruction::Alloca = (&bb.instrs[0]).try_into().expect("Should be an alloca");
+    assert_eq!(alloca.dest, Name::from("a"));
+    let not_a_load: Result<&instruction::Load, _> = (&bb.instrs[0]).try_into();
+    assert!(not_a_load.is_err());
+    let ret: &terminator::Ret = (&bb.term).try_into().expect("Should be a ret");
+    assert_eq!(ret.return_operand, None);
+
+    assert_eq!(bb.instrs[0].as_alloca(), Some(alloca));
+    assert!(bb.instrs[0].as_load().is_none());
+    assert_eq!(
+        bb.instrs[1].as_load().map(|load| &load.dest),
+        Some(&Name::from("x"))
+    );
+    assert_eq!(
+        bb.instrs.iter().filter_map(Instruction::as_store).count(),
+        2
+    );
+    assert_eq!(bb.term.as_ret(), Some(ret));
+    assert!(bb.term.as_br().is_none());
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn has_result() {
+    init_logging();
+    let path = llvm_bc_dir().join("alignment.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let bb = &module.get_func_by_name("f").unwrap().basic_blocks[0];
+
+    fn dest_and_type<T: HasResult>(inst: &T, types: &Types) -> (Name, TypeRef) {
+        (inst.dest().clone(), inst.result_type(types))
+    }
+    let alloca = bb.instrs[0].as_alloca().unwrap();
+    assert_eq!(
+        dest_and_type(alloca, &module.types),
+        (Name::from("a"), module.types.pointer_to(module.types.i64()))
+    );
+    let load = bb.instrs[1].as_load().unwrap();
+    assert_eq!(
+        dest_and_type(load, &module.types),
+        (Name::from("x"), module.types.i64())
+    );
+
+    assert_eq!(bb.instrs[1].try_dest(), Some(&Name::from("x")));
+    assert_eq!(
+        bb.instrs[1].try_result(&module.types),
+        Some((&Name::from("x"), module.types.i64()))
+    );
+    let store = &bb.instrs[4];
+    assert!(store.as_store().is_some());
+    assert_eq!(store.try_dest(), None);
+    assert_eq!(store.try_result(&module.types), None);
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn terminator_successors() {
+    init_logging();
+    let path = llvm_bc_dir().join("funclets.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("f").unwrap();
+    let successors = |block: &str| -> Vec<String> {
+        func.get_bb_by_name(&Name::from(block))
+            .unwrap()
+            .term
+            .successors()
+            .map(|name| name.to_string())
+            .collect()
+    };
+    assert_eq!(successors("entry"), vec!["%done", "%dispatch"]);
+    assert_eq!(
+        successors("dispatch"),
+        vec!["%handler1", "%handler2", "%cleanup"]
+    );
+    assert_eq!(successors("handler1"), vec!["%done"]);
+    assert_eq!(successors("inner"), vec!["%cleanup"]);
+    assert!(successors("cleanup").is_empty());
+    assert!(successors("done").is_empty());
+
+    let exits: Vec<String> = func
+        .basic_blocks
+        .iter()
+        .filter(|bb| bb.term.is_function_exit())
+        .map(|bb| bb.name.to_string())
+        .collect();
+    assert_eq!(exits, vec!["%cleanup", "%done"]);
+
+    let path = llvm_bc_dir().join("switch.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("has_a_switch").unwrap();
+    let term = &func.basic_blocks[0].term;
+    assert_eq!(term.successors().count(), 10);
+    assert!(!term.is_function_exit());
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn indirectbr() {
+    init_logging();
+    let path = llvm_bc_dir().join("indirectbr.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+
+    let func = module.get_func_by_name("dispatch").unwrap();
+    let ibr: &terminator::IndirectBr = (&func.basic_blocks[0].term)
+        .try_into()
+        .expect("Should be an indirectbr");
+    assert_eq!(
+        ibr.operand,
+        Operand::LocalOperand {
+            name: Name::from("target"),
+            ty: module.types.pointer_to(module.types.i8()),
+        }
+    );
+    assert_eq!(
+        ibr.possible_dests,
+        vec![Name::from("one"), Name::from("two")]
+    );
+    assert_eq!(
+        func.basic_blocks[0].term.successors().collect::<Vec<_>>(),
+        vec![&Name::from("one"), &Name::from("two")]
+    );
+    assert_eq!(
+        &ibr.to_string(),
+        "indirectbr i8* %target, [ label %one, label %two ]"
+    );
+    let cfg = ControlFlowGraph::new(func);
+    assert!(cfg.is_reachable(&Name::from("one")));
+    assert!(cfg.is_reachable(&Name::from("two")));
+    assert_eq!(
+        cfg.preds(&Name::from("two")).collect::<Vec<_>>(),
+        vec![&Name::from("entry")]
+    );
+
+    // an `IndirectBr` with no possible destinations
+    let func = module.get_func_by_name("nowhere").unwrap();
+    let ibr = func.basic_blocks[0]
+        .term
+        .as_indirectbr()
+        .expect("Should be an indirectbr");
+    assert!(ibr.possible_dests.is_empty());
+    assert_eq!(&ibr.to_string(), "indirectbr i8* %target, [ ]");
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn upgraded_intrinsics() {
+    init_logging();
+    let path = Path::new("tests/llvm_bc/auto_upgrade_intrinsics.bc");
+    let module = Module::from_bc_path(path).expect("Failed to parse module");
+    assert!(module.upgraded_intrinsics.is_empty());
+    let options = ParseOptions {
+        find_upgraded_intrinsics: true,
+        ..ParseOptions::default()
+    };
+    let module = Module::from_bc_path_with_options(path, &options).expect("Failed to parse module");
+    let upgraded = |old_name: &str, new_name: &str| UpgradedIntrinsic {
+        old_name: old_name.to_owned(),
+        new_name: Some(new_name.to_owned()),
+    };
+    assert_eq!(
+        module.upgraded_intrinsics,
+        vec![
+            upgraded("llvm.ctlz.i8", "llvm.ctlz.i8"),
+            upgraded("llvm.ctlz.i16", "llvm.ctlz.i16"),
+            upgraded("llvm.ctlz.i32", "llvm.ctlz.i32"),
+            upgraded("llvm.ctlz.i42", "llvm.ctlz.i42"),
+            upgraded("llvm.objectsize.i32", "llvm.objectsize.i32.p0i8"),
+            upgraded("llvm.cttz.i8", "llvm.cttz.i8"),
+            upgraded("llvm.cttz.i16", "llvm.cttz.i16"),
+            upgraded("llvm.cttz.i32", "llvm.cttz.i32"),
+            upgraded("llvm.cttz.i42", "llvm.cttz.i42"),
+            upgraded("llvm.masked.load.v2f64", "llvm.masked.load.v2f64.p0v2f64"),
+            upgraded("llvm.masked.store.v2f64", "llvm.masked.store.v2f64.p0v2f64"),
+        ]
+    );
+    let func = module
+        .get_func_by_name("test.objectsize")
+        .expect("Failed to find function");
+    assert_eq!(func.declared_intrinsics(), vec!["llvm.objectsize.i32.p0i8"]);
+    let func = module
+        .get_func_by_name("test.ctlz")
+        .expect("Failed to find function");
+    assert_eq!(
+        func.declared_intrinsics(),
+        vec![
+            "llvm.ctlz.i16",
+            "llvm.ctlz.i32",
+            "llvm.ctlz.i42",
+            "llvm.ctlz.i8"
+        ]
+    );
+
+    let path = llvm_bc_dir().join("hello.bc");
+    let module =
+        Module::from_bc_path_with_options(&path, &options).expect("Failed to parse module");
+    assert!(module.upgraded_intrinsics.is_empty());
+    let func = module
+        .get_func_by_name("main")
+        .expect("Failed to find function");
+    assert!(func.declared_intrinsics().is_empty());
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn simplify() {
+    init_logging();
+    let path = llvm_bc_dir().join("simplify.bc");
+    let mut module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let Module {
+        functions, types, ..
+    } = &mut module;
+    for func in functions.iter_mut() {
+        assert!(transform::simplify(func, types));
+        assert!(!transform::simplify(func, types));
+    }
+
+    let instrs = |name: &str| -> Vec<String> {
+        let func = module.get_func_by_name(name).unwrap();
+        func.basic_blocks[0]
+            .instrs
+            .iter()
+            .map(|inst| inst.to_string())
+            .collect()
+    };
+    // division by zero and oversized shifts aren't folded
+    assert_eq!(
+        instrs("fold"),
+        vec![
+            "%4 = sdiv i32 15, 0",
+            "%5 = shl i8 1, 8",
+            "%6 = add i32 %x, 15",
+        ]
+    );
+    assert_eq!(
+        instrs("casts"),
+        vec!["%3 = bitcast i32* %p to i8*", "%4 = bitcast i8* %3 to i32*"]
+    );
+    // %1 and %2 are now unused, but `simplify()` doesn't remove them
+    assert_eq!(
+        instrs("geps"),
+        vec![
+            "%1 = getelementptr inbounds %struct.S* %s, i64 1",
+            "%2 = getelementptr inbounds %struct.S* %s, i64 1, i32 1",
+            "%3 = getelementptr %struct.S* %s, i64 1, i32 1, i64 2",
+        ]
+    );
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn dce() {
+    init_logging();
+    let path = llvm_bc_dir().join("dce.bc");
+    let mut module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = &mut module.functions[0];
+    assert!(transform::dce(func));
+    assert!(!transform::dce(func));
+    let instrs: Vec<String> = func.basic_blocks[0]
+        .instrs
+        .iter()
+        .map(|inst| inst.to_string())
+        .collect();
+    // %1 is only dead once %2 has been removed; %8 is only used as metadata
+    assert_eq!(
+        instrs,
+        vec![
+            "%4 = load volatile i32* %p, align 4",
+            "store i32 %x, i32* %p, align 4",
+            "%6 = call @pure(i32 %x)",
+            "call @llvm.dbg.value(metadata i32 undef, metadata !0, metadata !7) (with debugloc)",
+            "%9 = udiv i32 %x, 7",
+        ]
+    );
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn map_instructions() {
+    use llvm_ir::function::InstrMapResult;
+
+    init_logging();
+    let path = llvm_bc_dir().join("dce.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let mut func = module.functions[0].clone();
+    let renames = func.map_instructions(|inst| match inst {
+        // %2 and %3 are unused
+        Instruction::Mul(_) => InstrMapResult::Delete,
+        Instruction::Load(load) if !load.volatile => InstrMapResult::Delete,
+        // replacing an instruction with one of the same name renames nothing
+        Instruction::Add(_) => InstrMapResult::Replace(vec![inst.clone()]),
+        Instruction::Sub(sub) => {
+            let mut sub = sub.clone();
+            sub.dest = Name::from("diff");
+            InstrMapResult::Replace(vec![sub.into()])
+        },
+        // the last replacement takes over the uses
+        Instruction::UDiv(udiv) => {
+            let mut first = udiv.clone();
+            first.dest = Name::from("quot.unused");
+            let mut second = udiv.clone();
+            second.dest = Name::from("quot");
+            InstrMapResult::Replace(vec![first.into(), second.into()])
+        },
+        _ => InstrMapResult::Keep,
+    });
+    assert_eq!(
+        renames,
+        HashMap::from([
+            (Name::from(8), Name::from("diff")),
+            (Name::from(9), Name::from("quot")),
+        ])
+    );
+    let bb = &func.basic_blocks[0];
+    let instrs: Vec<String> = bb.instrs.iter().map(|inst| inst.to_string()).collect();
+    assert_eq!(instrs.len(), 10);
+    assert_eq!(instrs[0], "%1 = add i32 %x, 1");
+    assert_eq!(instrs[1], "%4 = load volatile i32* %p, align 4");
+    assert_eq!(
+        instrs[6 ..],
+        [
+            "%diff = sub i32 %x, 3",
+            "call @llvm.dbg.value(metadata i32 %diff, metadata !0, metadata !7) (with debugloc)",
+            "%quot.unused = udiv i32 %x, 7",
+            "%quot = udiv i32 %x, 7",
+        ]
+    );
+    assert_eq!(bb.term.to_string(), "ret i32 %quot");
+
+    // keeping everything changes nothing
+    let mut copy = func.clone();
+    assert!(copy.map_instructions(|_| InstrMapResult::Keep).is_empty());
+    assert_eq!(copy, func);
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn side_effects_and_speculation() {
+    init_logging();
+    let path = llvm_bc_dir().join("speculation.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("speculation").unwrap();
+    let instrs = &func.basic_blocks[0].instrs;
+    let side_effects: Vec<bool> = instrs
+        .iter()
+        .map(Instruction::may_have_side_effects)
+        .collect();
+    let speculatable: Vec<bool> = instrs
+        .iter()
+        .map(Instruction::is_safe_to_speculate)
+        .collect();
+    // add, udiv by 7, udiv by %y, sdiv by -1, srem by 3, load, volatile load,
+    // atomic load, store, alloca, pure call, ctpop, unknown call, atomicrmw,
+    // fence
+    assert_eq!(
+        side_effects,
+        vec![
+            false, false, false, false, false, false, true, true, true, false, false, false, true,
+            true, true,
+        ]
+    );
+    assert_eq!(
+        speculatable,
+        vec![
+            true, true, false, false, true, false, false, false, false, false, false, true, false,
+            false, false,
+        ]
+    );
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn struct_layouts() {
+    init_logging();
+    let path = llvm_bc_dir().join("struct_layout.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    // named structs are always in name order
+    let names: Vec<&String> = module
+        .types
+        .all_named_structs()
+        .map(|(name, _)| name)
+        .collect();
+    assert_eq!(names, vec!["A", "N", "O", "P"]);
+
+    let layout = |name: &str| module.types.struct_layout(name, &module.data_layout);
+    assert_eq!(
+        layout("A"),
+        Some(StructLayout {
+            size: 12,
+            alignment: 4,
+            field_offsets: vec![0, 4, 8],
+            padding: 6,
+        })
+    );
+    assert_eq!(
+        layout("P"),
+        Some(StructLayout {
+            size: 5,
+            alignment: 1,
+            field_offsets: vec![0, 1],
+            padding: 0,
+        })
+    );
+    assert_eq!(
+        layout("N"),
+        Some(StructLayout {
+            size: 32,
+            alignment: 8,
+            field_offsets: vec![0, 16, 24],
+            padding: 6,
+        })
+    );
+    assert_eq!(layout("O"), None);
+    assert_eq!(layout("nonexistent"), None);
+
+    // the data layout's aggregate alignment doesn't pad a struct, but does
+    // align a struct field
+    let path = llvm_bc_dir().join("aggregate_alignment.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let layout = |name: &str| module.types.struct_layout(name, &module.data_layout);
+    assert_eq!(
+        layout("S"),
+        Some(StructLayout {
+            size: 1,
+            alignment: 1,
+            field_offsets: vec![0],
+            padding: 0,
+        })
+    );
+    assert_eq!(
+        layout("T"),
+        Some(StructLayout {
+            size: 16,
+            alignment: 8,
+            field_offsets: vec![0, 8],
+            padding: 7,
+        })
+    );
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn sizes_and_alignments() {
+    init_logging();
+    let path = llvm_bc_dir().join("struct_layout.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let types = &module.types;
+    let size = |ty: TypeRef| types.size_and_alignment(&ty, &module.data_layout);
+    let expected = |size_in_bits, store_size, alloc_size, abi_alignment, preferred_alignment| {
+        Some(SizeAndAlignment {
+            size_in_bits,
+            store_size,
+            alloc_size,
+            abi_alignment,
+            preferred_alignment,
+            scalable: false,
+        })
+    };
+
+    assert_eq!(size(types.bool()), expected(1, 1, 1, 1, 1));
+    assert_eq!(size(types.i64()), expected(64, 8, 8, 8, 8));
+    assert_eq!(
+        size(types.fp(FPType::X86_FP80)),
+        expected(80, 10, 16, 16, 16)
+    );
+    assert_eq!(size(types.pointer_to(types.i8())), expected(64, 8, 8, 8, 8));
+
+    assert_eq!(
+        size(types.vector_of(types.i32(), 4, false)),
+        expected(128, 16, 16, 16, 16)
+    );
+    // vectors without an alignment in the data layout are naturally aligned
+    assert_eq!(
+        size(types.vector_of(types.i32(), 3, false)),
+        expected(96, 12, 16, 16, 16)
+    );
+    assert_eq!(
+        size(types.vector_of(types.bool(), 4, false)),
+        expected(4, 1, 1, 1, 1)
+    );
+    assert_eq!(
+        size(types.vector_of(types.i32(), 4, true)),
+        Some(SizeAndAlignment {
+            scalable: true,
+            ..expected(128, 16, 16, 16, 16).unwrap()
+        })
+    );
+
+    assert_eq!(
+        size(types.array_of(types.i16(), 3)),
+        expected(48, 6, 6, 2, 2)
+    );
+    assert_eq!(
+        size(types.array_of(types.fp(FPType::X86_FP80), 2)),
+        expected(256, 32, 32, 16, 16)
+    );
+
+    // structs prefer the alignment of aggregates, even when packed
+    assert_eq!(size(types.named_struct("A")), expected(96, 12, 12, 4, 8));
+    assert_eq!(size(types.named_struct("P")), expected(40, 5, 5, 1, 8));
+    assert_eq!(size(types.named_struct("N")), expected(256, 32, 32, 8, 8));
+    assert_eq!(size(types.named_struct("O")), None);
+    assert_eq!(size(types.void()), None);
+    assert_eq!(
+        size(types.array_of(types.vector_of(types.i32(), 4, true), 2)),
+        None
+    );
+
+    // the data layout's aggregate alignment raises the alignment and alloc
+    // size of a struct, but not its size, and not the ABI alignment of a
+    // packed struct
+    let path = llvm_bc_dir().join("aggregate_alignment.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let types = &module.types;
+    let size = |ty: TypeRef| types.size_and_alignment(&ty, &module.data_layout);
+    assert_eq!(size(types.named_struct("S")), expected(8, 1, 8, 8, 8));
+    assert_eq!(size(types.named_struct("T")), expected(128, 16, 16, 8, 8));
+    assert_eq!(size(types.named_struct("P")), expected(24, 3, 3, 1, 8));
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn quoted_names() {
+    init_logging();
+    let path = llvm_bc_dir().join("names.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("names").unwrap();
+    assert_eq!(func.parameters[0].name, Name::from("a b"));
+    assert_eq!(&func.parameters[0].name.to_string(), "%\"a b\"");
+    let bb = &func.basic_blocks[0];
+    assert_eq!(bb.name, Name::from("entry block"));
+    assert_eq!(&bb.name.to_string(), "%\"entry block\"");
+
+    // a name with a control character, which is escaped when displayed
+    assert_eq!(bb.instrs[0].defined_name(), Some(&Name::from("x\u{1}y")));
+    assert_eq!(
+        &bb.instrs[0].defined_name().unwrap().to_string(),
+        "%\"x\\01y\""
+    );
+    // a name with a quote
+    assert_eq!(bb.instrs[1].defined_name(), Some(&Name::from("q\"")));
+    assert_eq!(
+        &bb.instrs[1].defined_name().unwrap().to_string(),
+        "%\"q\\22\""
+    );
+    // a name which starts with a digit
+    assert_eq!(
+        &bb.instrs[2].defined_name().unwrap().to_string(),
+        "%\"1st\""
+    );
+
+    // a global whose name isn't valid UTF-8
+    let global_name = Name::from(b"\xFF\xFEglobal".to_vec());
+    assert_eq!(
+        global_name,
+        Name::Bytes(Box::new(b"\xFF\xFEglobal".to_vec()))
+    );
+    assert_eq!(global_name.as_bytes(), Some(&b"\xFF\xFEglobal"[..]));
+    assert_eq!(module.global_vars[0].name, global_name);
+    let load: &instruction::Load = &bb.instrs[1].clone().try_into().expect("Should be a load");
+    assert_eq!(&load.address.to_string(), "i32* @\"\\FF\\FEglobal\"");
+    // and an unnamed global
+    let load: &instruction::Load = &bb.instrs[2].clone().try_into().expect("Should be a load");
+    assert_eq!(&load.address.to_string(), "i32* @0");
+
+    // valid UTF-8 names are always `Name::Name`s
+    assert_eq!(Name::from(b"plain".to_vec()), Name::from("plain"));
+    assert_eq!(&Name::from("plain").to_string(), "%plain");
+    assert_eq!(Name::from(3).as_bytes(), None);
+}
+
+#[cfg(all(
+    feature = "llvm-14",
+    any(feature = "rustc-demangle", feature = "cpp_demangle")
+))]
+#[test]
+fn demangled_names() {
+    init_logging();
+    let path = llvm_bc_dir().join("demangle.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let demangled = |name: &str| module.get_func_by_name(name).unwrap().demangled_name();
+    assert_eq!(demangled("main"), None);
+
+    #[cfg(feature = "rustc-demangle")]
+    {
+        let name = "_ZN4core3ptr13drop_in_place17h0123456789abcdefE";
+        assert_eq!(demangled(name).as_deref(), Some("core::ptr::drop_in_place"));
+        let func = module
+            .get_func_by_demangled_name("core::ptr::drop_in_place")
+            .expect("Failed to find function by demangled name");
+        assert_eq!(func.name, name);
+    }
+
+    #[cfg(feature = "cpp_demangle")]
+    {
+        assert_eq!(demangled("_Z3addii").as_deref(), Some("add(int, int)"));
+        assert_eq!(demangled("_ZN2ns3fooEv").as_deref(), Some("ns::foo()"));
+        let func = module
+            .get_func_by_demangled_name("add(int, int)")
+            .expect("Failed to find function by demangled name");
+        assert_eq!(func.name, "_Z3addii");
+    }
+
+    assert!(module.get_func_by_demangled_name("nonexistent").is_none());
+}
+
+#[cfg(feature = "llvm-11-or-greater")]
+#[test]
+fn float_types() {
+    init_logging();
+    let path = llvm_bc_dir().join("float_types.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+
+    let f = module.get_func_by_name("takes_half").unwrap();
+    assert_eq!(
+        module.type_of(f.parameters.get(0).unwrap()),
+        module.types.fp(FPType::Half)
+    );
+    let f = module.get_func_by_name("takes_bfloat").unwrap();
+    assert_eq!(
+        module.type_of(f.parameters.get(0).unwrap()),
+        module.types.fp(FPType::BFloat)
+    );
+    let f = module.get_func_by_name("takes_float").unwrap();
+    assert_eq!(
+        module.type_of(f.parameters.get(0).unwrap()),
+        module.types.fp(FPType::Single)
+    );
+    let f = module.get_func_by_name("takes_double").unwrap();
+    assert_eq!(
+        module.type_of(f.parameters.get(0).unwrap()),
+        module.types.fp(FPType::Double)
+    );
+    let f = module.get_func_by_name("takes_fp128").unwrap();
+    assert_eq!(
+        module.type_of(f.parameters.get(0).unwrap()),
+        module.types.fp(FPType::FP128)
+    );
+    let f = module.get_func_by_name("takes_x86_fp80").unwrap();
+    assert_eq!(
+        module.type_of(f.parameters.get(0).unwrap()),
+        module.types.fp(FPType::X86_FP80)
+    );
+    let f = module.get_func_by_name("takes_ppc_fp128").unwrap();
+    assert_eq!(
+        module.type_of(f.parameters.get(0).unwrap()),
+        module.types.fp(FPType::PPC_FP128)
+    );
+
+    let f = module.get_func_by_name("returns_half").unwrap();
+    assert_eq!(
+        f.return_type,
+        module.types.pointer_to(module.types.fp(FPType::Half))
+    );
+    let f = module.get_func_by_name("returns_bfloat").unwrap();
+    assert_eq!(
+        f.return_type,
+        module.types.pointer_to(module.types.fp(FPType::BFloat))
+    );
+    let f = module.get_func_by_name("returns_float").unwrap();
+    assert_eq!(
+        f.return_type,
+        module.types.pointer_to(module.types.fp(FPType::Single))
+    );
+    let f = module.get_func_by_name("returns_double").unwrap();
+    assert_eq!(
+        f.return_type,
+        module.types.pointer_to(module.types.fp(FPType::Double))
+    );
+    let f = module.get_func_by_name("returns_fp128").unwrap();
+    assert_eq!(
+        f.return_type,
+        module.types.pointer_to(module.types.fp(FPType::FP128))
+    );
+    let f = module.get_func_by_name("returns_x86_fp80").unwrap();
+    assert_eq!(
+        f.return_type,
+        module.types.pointer_to(module.types.fp(FPType::X86_FP80))
+    );
+    let f = module.get_func_by_name("returns_ppc_fp128").unwrap();
+    assert_eq!(
+        f.return_type,
+        module.types.pointer_to(module.types.fp(FPType::PPC_FP128))
+    );
+}
+
+#[test]
+fn datalayouts() {
+    init_logging();
+    let path = llvm_bc_dir().join("hello.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let data_layout = &module.data_layout;
 
     #[cfg(feature = "llvm-10-or-greater")]
     {
@@ -2520,3 +6398,286 @@ fn fences() {
     assert_eq!(syncscope.atomicity.synch_scope, SynchronizationScope::SingleThread);
 }
 */
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_roundtrip() {
+    init_logging();
+    let path = llvm_bc_dir().join("linkedlist.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let json = serde_json::to_string(&module).expect("Failed to serialize module");
+    let roundtripped: Module = serde_json::from_str(&json).expect("Failed to deserialize module");
+
+    assert_eq!(roundtripped.name, module.name);
+    assert_eq!(roundtripped.source_file_name, module.source_file_name);
+    assert_eq!(roundtripped.data_layout, module.data_layout);
+    assert_eq!(roundtripped.target_triple, module.target_triple);
+    assert_eq!(roundtripped.functions, module.functions);
+    assert_eq!(roundtripped.global_vars, module.global_vars);
+    assert_eq!(roundtripped.global_aliases, module.global_aliases);
+    assert_eq!(
+        roundtripped.types.all_struct_names().collect::<Vec<_>>(),
+        module.types.all_struct_names().collect::<Vec<_>>(),
+    );
+    let struct_name = "struct.SimpleLinkedList";
+    assert_eq!(
+        format!("{:?}", roundtripped.types.named_struct_def(struct_name)),
+        format!("{:?}", module.types.named_struct_def(struct_name)),
+    );
+    // the deserialized `Types` should still hand out the same `TypeRef`s
+    assert_eq!(
+        roundtripped.types.named_struct(struct_name),
+        module.types.named_struct(struct_name)
+    );
+    assert_eq!(roundtripped.types.i32(), module.types.i32());
+}
+
+#[test]
+fn dot_export() {
+    init_logging();
+    let path = llvm_bc_dir().join("switch.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("has_a_switch").unwrap();
+    let dot = func.to_dot();
+    assert!(dot.starts_with("digraph \"has_a_switch\" {\n"));
+    assert!(dot.ends_with("}\n"));
+    assert!(dot.contains("    \"%1\" -> \"%12\" [label=\"i32 0\"];\n"));
+    assert!(dot.contains("    \"%1\" -> \"%3\" [label=\"i32 13\"];\n"));
+    assert!(dot.contains("    \"%1\" -> \"%10\" [label=\"default\"];\n"));
+    assert!(dot.contains("    \"%2\" -> \"%12\";\n"));
+    assert!(dot.contains("  %14 = add i32 %13, %0\\l  ret i32 %14\\l\"];\n"));
+    assert_eq!(dot.matches(" -> ").count(), 19);
+
+    let path = llvm_bc_dir().join("loop.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = module.get_func_by_name("loop").unwrap();
+    let dot = func.to_dot();
+    assert!(dot.contains("[label=\"true\"]"));
+    assert!(dot.contains("[label=\"false\"]"));
+
+    let dir = std::env::temp_dir().join("llvm-ir-dot-export-test");
+    module
+        .cfgs_to_dot_dir(&dir)
+        .expect("Failed to write dot files");
+    for func in &module.functions {
+        let contents = std::fs::read_to_string(dir.join(format!("{}.dot", func.name)))
+            .expect("Failed to read dot file");
+        assert_eq!(contents, func.to_dot());
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn dot_export_callbr_and_clashing_names() {
+    init_logging();
+    let path = llvm_bc_dir().join("dot_export.bc");
+    let module = Module::from_bc_path(&path).expect("Failed to parse module");
+
+    // all the successors of the `callbr` are edges, including its other labels
+    let func = module.get_func_by_name("asm_goto").unwrap();
+    let callbr: &terminator::CallBr = &func.basic_blocks[0]
+        .term
+        .clone()
+        .try_into()
+        .expect("Expected a callbr");
+    assert_eq!(callbr.return_label, Name::from("normal"));
+    assert_eq!(callbr.other_labels, vec![Name::from("fail")]);
+    assert_eq!(
+        func.basic_blocks[0].term.successors().collect::<Vec<_>>(),
+        vec![&Name::from("normal"), &Name::from("fail")]
+    );
+    let dot = func.to_dot();
+    assert!(dot.contains("    \"%entry\" -> \"%normal\";\n"));
+    assert!(dot.contains("    \"%entry\" -> \"%fail\";\n"));
+
+    // functions whose filenames would clash get distinct files
+    let dir = std::env::temp_dir().join("llvm-ir-dot-export-clash-test");
+    module
+        .cfgs_to_dot_dir(&dir)
+        .expect("Failed to write dot files");
+    for (filename, func_name) in &[
+        ("a_b.dot", "a?b"),
+        ("a_b.1.dot", "a!b"),
+        ("A_b.2.dot", "A?b"),
+        ("a_b.1.1.dot", "a_b.1"),
+        ("asm_goto.dot", "asm_goto"),
+    ] {
+        let contents =
+            std::fs::read_to_string(dir.join(filename)).expect("Failed to read dot file");
+        assert_eq!(
+            contents,
+            module.get_func_by_name(func_name).unwrap().to_dot()
+        );
+    }
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 5);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn verify() {
+    init_logging();
+    for filename in &[
+        "hello.bc",
+        "loop.bc",
+        "switch.bc",
+        "variables.bc",
+        "linkedlist.bc",
+    ] {
+        let path = llvm_bc_dir().join(filename);
+        let module = Module::from_bc_path_verified(&path).expect("Failed to parse module");
+        assert_eq!(module.verify(), Ok(()), "{}", filename);
+    }
+
+    // break the module in a few ways, and check that `verify()` notices
+    let path = llvm_bc_dir().join("switch.bc");
+    let mut module = Module::from_bc_path(&path).expect("Failed to parse module");
+    let func = &mut module.functions[0];
+    func.basic_blocks[1].term = Terminator::Br(terminator::Br {
+        dest: Name::from("nonexistent"),
+        loop_metadata: None,
+        #[cfg(feature = "llvm-9-or-greater")]
+        debugloc: None,
+    });
+    let last_bb = func.basic_blocks.last_mut().unwrap();
+    last_bb.term = Terminator::Ret(terminator::Ret {
+        return_operand: None,
+        #[cfg(feature = "llvm-9-or-greater")]
+        debugloc: None,
+    });
+    let errors = module
+        .verify()
+        .expect_err("Expected the module to fail verification");
+    assert_eq!(
+        errors,
+        vec![
+            "function \"has_a_switch\", block %2: terminator branches to nonexistent block %nonexistent".to_owned(),
+            "function \"has_a_switch\", block %12: %13 has an incoming value from %2, which is not a predecessor".to_owned(),
+            "function \"has_a_switch\", block %12: ret of type void in a function returning i32".to_owned(),
+        ]
+    );
+}
+
+// unverified.bc is malformed in a way that LLVM's verifier catches
+#[cfg(feature = "llvm-14")]
+#[test]
+fn verify_malformed_bitcode() {
+    init_logging();
+    let path = llvm_bc_dir().join("unverified.bc");
+    let err = match Module::from_bc_path_verified(&path) {
+        Ok(_) => panic!("Expected unverified.bc to fail verification"),
+        Err(Error::VerificationFailed(err)) => err,
+        Err(err) => panic!("Expected a verification error; got {:?}", err),
+    };
+    assert!(
+        err.contains("Instruction does not dominate all uses!"),
+        "unexpected error {:?}",
+        err
+    );
+}
+
+#[test]
+fn missing_file_error() {
+    init_logging();
+    let path = llvm_bc_dir().join("nonexistent.bc");
+    match Module::from_bc_path(&path) {
+        Err(Error::ReadFailed(_)) => {},
+        Err(err) => panic!("Expected a ReadFailed error; got {:?}", err),
+        Ok(_) => panic!("Expected an error reading a nonexistent file"),
+    }
+}
+
+#[cfg(feature = "llvm-14")]
+#[test]
+fn archives() {
+    init_logging();
+    // archive.a contains archive_first.bc, a text file, and
+    // archive_second_member.bc (whose name is stored in the long-name table)
+    let path = llvm_bc_dir().join("archive.a");
+    let modules = Module::from_archive_path(&path).expect("Failed to parse archive");
+    assert_eq!(modules.len(), 2);
+    assert_eq!(
+        modules[0].name,
+        format!("{}(archive_first.bc)", path.display())
+    );
+    assert!(modules[0].get_func_by_name("first").is_some());
+    assert_eq!(
+        modules[1].name,
+        format!("{}(archive_second_member.bc)", path.display())
+    );
+    assert!(modules[1].get_func_by_name("second").is_some());
+
+    match Module::from_bc_path(&path) {
+        Err(Error::ReadFailed(_)) => {},
+        Err(err) => panic!("Expected a ReadFailed error; got {:?}", err),
+        Ok(_) => panic!("Expected an error parsing an archive as bitcode"),
+    }
+}
+
+#[cfg(all(feature = "llvm-14", feature = "embedded-bitcode"))]
+#[test]
+fn embedded_bitcode() {
+    init_logging();
+    // embedded.o is an ELF object file whose `.llvmbc` section contains
+    // archive_first.bc followed by archive_second_member.bc, as if two
+    // objects built with `-fembed-bitcode` had been linked with `ld -r`
+    let path = llvm_bc_dir().join("embedded.o");
+    let modules = Module::from_object_with_embedded_bc(&path).expect("Failed to parse object");
+    assert_eq!(modules.len(), 2);
+    assert!(modules[0].get_func_by_name("first").is_some());
+    assert!(modules[1].get_func_by_name("second").is_some());
+
+    // bitcode itself isn't an object file
+    let path = llvm_bc_dir().join("archive_first.bc");
+    match Module::from_object_with_embedded_bc(&path) {
+        Err(Error::ReadFailed(_)) => {},
+        Err(err) => panic!("Expected a ReadFailed error; got {:?}", err),
+        Ok(_) => panic!("Expected an error parsing bitcode as an object file"),
+    }
+}
+
+// unsupported.bc contains IR which llvm-ir can't convert
+#[cfg(feature = "llvm-14")]
+#[test]
+fn unsupported_ir_error() {
+    init_logging();
+    let path = llvm_bc_dir().join("unsupported.bc");
+    match Module::from_bc_path(&path) {
+        Err(Error::Unsupported {
+            message,
+            function,
+            block,
+            instruction,
+        }) => {
+            assert!(
+                message.contains("ShuffleVector"),
+                "unexpected message {:?}",
+                message
+            );
+            assert_eq!(function.as_deref(), Some("f"));
+            assert_eq!(block, Some(Name::from("next")));
+            assert!(
+                instruction
+                    .as_deref()
+                    .unwrap_or("")
+                    .contains("ret <vscale x 2 x i32> shufflevector"),
+                "unexpected instruction {:?}",
+                instruction
+            );
+        },
+        Err(err) => panic!("Expected an Unsupported error; got {:?}", err),
+        Ok(_) => panic!("Expected unsupported.bc to fail to parse"),
+    }
+    // with `lossy`, the function is kept, without its body
+    let options = ParseOptions {
+        lossy: true,
+        ..ParseOptions::default()
+    };
+    let module =
+        Module::from_bc_path_with_options(&path, &options).expect("Failed to parse module");
+    let func = module
+        .get_func_by_name("f")
+        .expect("Failed to find function");
+    assert!(func.basic_blocks.is_empty());
+    assert_eq!(module.global_vars.len(), 1);
+}
@@ -3,6 +3,7 @@
 //! These tests simply ensure that we can parse all of the `.bc` files in LLVM 12's `test/Bitcode` directory without crashing.
 //! We only include the `.bc` files which are new or have changed since LLVM 11 (older ones are covered in other llvm_*_tests.rs).
 //! Human-readable `.ll` versions of these files can be found in the LLVM repo at `test/Bitcode` at the git tag `llvmorg-12.0.0`.
+#![allow(clippy::needless_borrows_for_generic_args)]
 
 use llvm_ir::Module;
 use std::path::Path;
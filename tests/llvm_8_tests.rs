@@ -1,5 +1,9 @@
 //! These tests simply ensure that we can parse all of the `.bc` files in LLVM 8's `test/Bitcode` directory without crashing.
 //! Human-readable `.ll` versions of these files can be found in the LLVM repo at `test/Bitcode` on the git branch `release_80`.
+#![allow(
+    clippy::bool_assert_comparison,
+    clippy::needless_borrows_for_generic_args
+)]
 
 use llvm_ir::Module;
 use std::path::Path;
@@ -228,7 +232,9 @@ fn DILocation_implicit_code_extra_checks() {
         .clone()
         .try_into()
         .unwrap_or_else(|_| panic!("Expected an invoke, got {:?}", &entry.term));
-    if let Either::Right(Operand::ConstantOperand(cref)) = &invoke.function {
+    if let Either::Right(Operand::ConstantOperand(cref)) =
+        invoke.function.as_ref().map_right(|op| op.as_ref())
+    {
         if let Constant::GlobalReference { name, .. } = cref.as_ref() {
             assert_eq!(name, &Name::from("_ZN1A3fooEi"));
         } else {
@@ -244,7 +250,7 @@ fn DILocation_implicit_code_extra_checks() {
         );
     }
     assert_eq!(invoke.arguments.len(), 2);
-    if let Operand::LocalOperand { name, ty } = &invoke.arguments[0].0 {
+    if let Operand::LocalOperand { name, ty } = invoke.arguments[0].0.as_ref() {
         assert_eq!(name, &Name::from("a"));
         if let Type::PointerType { pointee_type, .. } = ty.as_ref() {
             if let Type::NamedStructType { name } = pointee_type.as_ref() {
@@ -3,6 +3,7 @@
 //! These tests simply ensure that we can parse all of the `.bc` files in LLVM 9's `test/Bitcode` directory without crashing.
 //! We only include the `.bc` files which are new or have changed since LLVM 8 (older ones are covered in llvm_8_tests.rs).
 //! Human-readable `.ll` versions of these files can be found in the LLVM repo at `test/Bitcode` on the git branch `release/9.x`.
+#![allow(clippy::needless_borrows_for_generic_args)]
 
 use llvm_ir::Module;
 use std::path::Path;
@@ -58,8 +59,9 @@ fn callbr_parsing() {
         .unwrap_or_else(|_| panic!("Expected a callbr, got {:?}", &bb.term));
     assert!(callbr.function.is_left());
     assert_eq!(callbr.return_label, Name::from("normal"));
+    assert_eq!(callbr.other_labels, vec![Name::from("fail")]);
     assert_eq!(
         &format!("{}", callbr),
-        "%0 = callbr <inline assembly>(i32 %x, blockaddr) to label %normal",
+        "%0 = callbr <inline assembly>(i32 %x, blockaddress(@test_asm_goto, %fail)) to label %normal [label %fail]",
     )
 }
@@ -0,0 +1,91 @@
+#![cfg(feature = "llvm-14-or-greater")]
+
+//! Tests of parsing bitcode which uses opaque pointers.
+//! These are in their own test binary because putting LLVM into opaque-pointer
+//! mode affects everything parsed afterwards in the same process.
+
+use llvm_ir::module::ParseOptions;
+use llvm_ir::types::Type;
+use llvm_ir::{Constant, Module, Name};
+use std::path::Path;
+
+fn init_logging() {
+    // capture log messages with test harness
+    let _ = env_logger::builder().is_test(true).try_init();
+}
+
+#[test]
+fn opaque_pointers() {
+    init_logging();
+    let path = Path::new("tests/basic_bc/llvm14/opaque_pointers.bc");
+    let options = ParseOptions {
+        opaque_pointers: true,
+        ..ParseOptions::default()
+    };
+    let module = Module::from_bc_path_with_options(path, &options).expect("Failed to parse module");
+    assert!(module.types.uses_opaque_pointers());
+    let ptr = module.types.opaque_pointer();
+    assert_eq!(&format!("{}", ptr), "ptr");
+    assert_eq!(ptr.pointee_type(), None);
+
+    let global = module
+        .get_global_var_by_name(&Name::from("g"))
+        .expect("Failed to find global");
+    assert_eq!(global.ty, ptr);
+
+    let func = module.get_func_by_name("second").unwrap();
+    assert_eq!(func.parameters[0].ty, ptr);
+    let pair = module.types.named_struct("pair");
+    let bb = &func.basic_blocks[0];
+
+    let alloca = bb.instrs[0].as_alloca().expect("Expected an alloca");
+    assert_eq!(module.type_of(alloca), ptr);
+
+    let elem = bb.instrs[1].as_getelementptr().expect("Expected a GEP");
+    assert_eq!(
+        elem.source_element_type,
+        module.types.array_of(pair.clone(), 4)
+    );
+    assert_eq!(module.type_of(elem), ptr);
+
+    let field = bb.instrs[2].as_getelementptr().expect("Expected a GEP");
+    assert_eq!(field.source_element_type, pair);
+    assert_eq!(module.type_of(field), ptr);
+
+    let rmw = bb.instrs[3].as_atomicrmw().expect("Expected an atomicrmw");
+    assert_eq!(module.type_of(&rmw.address), ptr);
+    assert_eq!(module.type_of(rmw), module.types.i64());
+
+    let global_gep = bb.instrs[5].as_getelementptr().expect("Expected a GEP");
+    assert_eq!(global_gep.source_element_type, pair);
+    assert_eq!(module.type_of(&global_gep.address), ptr);
+    assert!(matches!(
+        module.type_of(global_gep).as_ref(),
+        Type::OpaquePointerType { addr_space: 0 }
+    ));
+
+    let caller = module.get_func_by_name("caller").unwrap();
+    let bb = &caller.basic_blocks[0];
+    let load = bb.instrs[0].as_load().expect("Expected a load");
+    assert_eq!(load.loaded_type, ptr);
+    let load = bb.instrs[1].as_load().expect("Expected a load");
+    assert_eq!(load.loaded_type, module.types.i64());
+    assert_eq!(module.type_of(load), module.types.i64());
+
+    let call = bb.instrs[2].as_call().expect("Expected a call");
+    assert_eq!(
+        call.function_ty,
+        module.types.func_type(
+            module.types.i64(),
+            vec![ptr.clone(), module.types.i32()],
+            false
+        )
+    );
+    assert_eq!(module.type_of(call), module.types.i64());
+    let const_gep = match call.arguments[0].0.as_constant() {
+        Some(Constant::GetElementPtr(gep)) => gep,
+        arg => panic!("Expected a constant GEP, got {:?}", arg),
+    };
+    assert_eq!(const_gep.source_element_type, pair);
+    assert_eq!(module.type_of(const_gep), ptr);
+}